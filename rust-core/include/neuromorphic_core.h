@@ -0,0 +1,96 @@
+/*
+ * neuromorphic_core.h
+ *
+ * C interface to the neuromorphic_core spiking network simulator.
+ *
+ * Declarations match src/ffi.rs, as checked by tests/ffi_header.rs. Build
+ * the library with
+ *   cargo build --release --lib --features ffi
+ * and link against target/release/libneuromorphic_core.so, or the static
+ * target/release/libneuromorphic_core.a.
+ *
+ * Functions returning pointers return NULL on failure, and nc_last_error()
+ * then describes the failure. Spikes emitted by nc_step() are buffered
+ * until collected with nc_get_spikes().
+ */
+#ifndef NEUROMORPHIC_CORE_H
+#define NEUROMORPHIC_CORE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque simulation handle. */
+typedef struct NcSimulation NcSimulation;
+
+/* Parameters of an all-to-all network created by nc_create(). */
+typedef struct NcConfig {
+    double dt_ms;                  /* Simulation time step (ms) */
+    double tau_m_ms;               /* Membrane time constant (ms) */
+    double v_rest;                 /* Resting membrane potential */
+    double v_thresh;               /* Firing threshold */
+    double v_reset;                /* Reset potential after a spike */
+    double a_plus;                 /* STDP potentiation rate */
+    double a_minus;                /* STDP depression rate */
+    double tau_plus_ms;            /* STDP potentiation time constant (ms) */
+    double tau_minus_ms;           /* STDP depression time constant (ms) */
+    double w_min;                  /* Minimum synaptic weight */
+    double w_max;                  /* Maximum synaptic weight */
+    double initial_weight;         /* Initial weight of every synapse */
+    uint8_t synaptic_transmission; /* Nonzero to transmit spikes */
+} NcConfig;
+
+/* Description of the last failure on this thread, or NULL. */
+const char *nc_last_error(void);
+
+/* Fill config with default parameters. */
+void nc_config_default(NcConfig *config);
+
+/* Create num_neurons all-to-all connected neurons; config may be NULL. */
+NcSimulation *nc_create(size_t num_neurons, const NcConfig *config);
+
+/* Create a simulation from a NUL-terminated TOML experiment configuration. */
+NcSimulation *nc_create_from_toml(const char *toml);
+
+/* Free a simulation; NULL is ignored. */
+void nc_destroy(NcSimulation *sim);
+
+/* Advance one step with len input currents (may be NULL if len is 0);
+ * returns the number of neurons that fired. */
+size_t nc_step(NcSimulation *sim, const double *currents, size_t len);
+
+/* Make neuron fire during the next step; returns 0 if it does not exist. */
+int32_t nc_inject_spike(NcSimulation *sim, size_t neuron);
+
+/* Number of spikes waiting to be collected. */
+size_t nc_pending_spikes(const NcSimulation *sim);
+
+/* Move up to capacity of the oldest uncollected spikes into the arrays;
+ * returns how many were written. */
+size_t nc_get_spikes(NcSimulation *sim, size_t *neurons, double *times_ms, size_t capacity);
+
+/* Current simulation time (ms). */
+double nc_time(const NcSimulation *sim);
+
+/* Number of neurons. */
+size_t nc_num_neurons(const NcSimulation *sim);
+
+/* Number of synapses. */
+size_t nc_num_synapses(const NcSimulation *sim);
+
+/* Copy up to capacity synapses into the arrays (any may be NULL);
+ * returns how many were written. */
+size_t nc_get_synapses(const NcSimulation *sim, size_t *pre, size_t *post, double *weights,
+                       size_t capacity);
+
+/* Membrane potential of neuron, or NaN if it does not exist. */
+double nc_membrane_potential(const NcSimulation *sim, size_t neuron);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* NEUROMORPHIC_CORE_H */
@@ -0,0 +1,9 @@
+#!/usr/bin/env bash
+# Check that the library, including the `wasm` exports, compiles for
+# wasm32-unknown-unknown. Run from anywhere; needs the wasm32 target
+# (`rustup target add wasm32-unknown-unknown`).
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+cargo check --lib --target wasm32-unknown-unknown
+cargo check --lib --target wasm32-unknown-unknown --features wasm
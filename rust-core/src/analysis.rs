@@ -0,0 +1,666 @@
+//! analysis.rs
+//!
+//! Spike train statistics.
+//!
+//! Standard descriptive statistics of recorded activity: mean firing
+//! rates, inter-spike-interval (ISI) distributions, the coefficient of
+//! variation of the ISIs (about 1 for Poisson firing, near 0 for regular
+//! firing), the Fano factor of spike counts, population rate time series,
+//! stimulus-aligned histograms, cross-correlograms, synchrony and
+//! oscillation measures, distances between spike trains, and
+//! spike-triggered averages of the input.
+//! Functions take the flat `Vec<Spike>` returned by a run, or a single
+//! neuron's sorted spike times as produced by `spike_trains`.
+
+use crate::decoding;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use crate::util::mean_std;
+use std::ops::Range;
+
+/// Per-neuron summary produced by `summarize`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NeuronStats {
+    /// Number of spikes
+    pub spike_count: usize,
+    /// Mean firing rate (Hz)
+    pub rate_hz: f64,
+    /// Coefficient of variation of the ISIs, if the neuron fired at least
+    /// three times
+    pub cv_isi: Option<f64>,
+    /// Fano factor of the spike counts, if it is defined
+    pub fano_factor: Option<f64>,
+}
+
+/// Histogram of spike time differences between two trains.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Correlogram {
+    /// Largest lag magnitude covered
+    pub max_lag: Milliseconds,
+    /// Bin width
+    pub bin: Milliseconds,
+    /// Number of spike pairs per lag bin, from `-max_lag` upwards
+    pub counts: Vec<usize>,
+}
+
+/// Peri-stimulus time histogram: spike counts aligned to stimulus onsets.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Psth {
+    /// Analyzed neurons
+    pub neurons: Range<usize>,
+    /// Stimulus onset of each trial
+    pub onsets: Vec<Milliseconds>,
+    /// Time before each onset included in the window
+    pub pre: Milliseconds,
+    /// Bin width
+    pub bin: Milliseconds,
+    /// `counts[trial][neuron][bin]`, with neurons relative to
+    /// `neurons.start`
+    pub counts: Vec<Vec<Vec<usize>>>,
+}
+
+/// Sorted spike times of each of `num_neurons` neurons.
+///
+/// Spikes of neurons outside `0..num_neurons` are ignored.
+pub fn spike_trains(spikes: &[Spike], num_neurons: usize) -> Vec<Vec<Milliseconds>> {
+    let mut trains = vec![Vec::new(); num_neurons];
+    for s in spikes {
+        if let Some(train) = trains.get_mut(s.neuron_id) {
+            train.push(s.time);
+        }
+    }
+    for train in trains.iter_mut() {
+        train.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    trains
+}
+
+/// Mean firing rate (Hz) of each neuron over `[0, duration)`.
+pub fn mean_firing_rates(spikes: &[Spike], num_neurons: usize, duration: Milliseconds) -> Vec<f64> {
+    decoding::firing_rates(spikes, 0..num_neurons, Milliseconds::ZERO, duration)
+}
+
+/// Inter-spike intervals (ms) of a sorted spike train.
+pub fn isis(train: &[Milliseconds]) -> Vec<f64> {
+    train.windows(2).map(|w| (w[1] - w[0]).0).collect()
+}
+
+/// Histogram of the ISIs of `train` with bins of width `bin` covering
+/// `[0, max_isi)`; longer intervals are not counted.
+pub fn isi_histogram(
+    train: &[Milliseconds],
+    bin: Milliseconds,
+    max_isi: Milliseconds,
+) -> Vec<usize> {
+    let num_bins = if bin.0 > 0.0 {
+        (max_isi / bin).ceil().max(0.0) as usize
+    } else {
+        0
+    };
+    let mut counts = vec![0; num_bins];
+    for isi in isis(train) {
+        let k = (isi / bin.0).floor();
+        if k >= 0.0 && (k as usize) < num_bins {
+            counts[k as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// Coefficient of variation (standard deviation over mean) of the ISIs.
+///
+/// Returns `None` for trains with fewer than two intervals.
+pub fn cv_isi(train: &[Milliseconds]) -> Option<f64> {
+    let intervals = isis(train);
+    if intervals.len() < 2 {
+        return None;
+    }
+    let (mean, std) = mean_std(&intervals);
+    (mean > 0.0).then(|| std / mean)
+}
+
+/// Fano factor (variance over mean) of the spike counts of `train` in
+/// consecutive windows of length `window` covering `[start, end)`.
+///
+/// Returns `None` if fewer than two windows fit or the train is silent.
+pub fn fano_factor(
+    train: &[Milliseconds],
+    start: Milliseconds,
+    end: Milliseconds,
+    window: Milliseconds,
+) -> Option<f64> {
+    if window.0 <= 0.0 {
+        return None;
+    }
+    let num_windows = ((end - start) / window).floor().max(0.0) as usize;
+    if num_windows < 2 {
+        return None;
+    }
+    let mut counts = vec![0.0; num_windows];
+    for &t in train {
+        let k = ((t - start) / window).floor();
+        if k >= 0.0 && (k as usize) < num_windows {
+            counts[k as usize] += 1.0;
+        }
+    }
+    let (mean, std) = mean_std(&counts);
+    (mean > 0.0).then(|| std * std / mean)
+}
+
+/// Rate, ISI variability and Fano factor of every neuron over
+/// `[0, duration)`, with spike counts taken in windows of `fano_window`.
+pub fn summarize(
+    spikes: &[Spike],
+    num_neurons: usize,
+    duration: Milliseconds,
+    fano_window: Milliseconds,
+) -> Vec<NeuronStats> {
+    let rates = mean_firing_rates(spikes, num_neurons, duration);
+    spike_trains(spikes, num_neurons)
+        .into_iter()
+        .zip(rates)
+        .map(|(train, rate_hz)| NeuronStats {
+            spike_count: train.len(),
+            rate_hz,
+            cv_isi: cv_isi(&train),
+            fano_factor: fano_factor(&train, Milliseconds::ZERO, duration, fano_window),
+        })
+        .collect()
+}
+
+/// Population firing rate over time, as `(bin_centers, rates_hz)`.
+///
+/// `spikes` should contain the spikes of one population of `size` neurons,
+/// e.g. from `Simulation::spikes_of_group`, or all spikes with the network
+/// size. Spikes in `[start, end)` are counted in bins of width `bin`, and
+/// each count is converted to a mean rate per neuron. With `sigma`, the
+/// rate is smoothed with a Gaussian kernel of that standard deviation,
+/// renormalized at the edges of the window.
+pub fn population_rate(
+    spikes: &[Spike],
+    size: usize,
+    start: Milliseconds,
+    end: Milliseconds,
+    bin: Milliseconds,
+    sigma: Option<Milliseconds>,
+) -> (Vec<Milliseconds>, Vec<f64>) {
+    if bin.0 <= 0.0 || end <= start {
+        return (Vec::new(), Vec::new());
+    }
+    let num_bins = ((end - start) / bin).ceil() as usize;
+    let mut counts = vec![0.0; num_bins];
+    for s in spikes {
+        if s.time >= start && s.time < end {
+            let k = (((s.time - start) / bin) as usize).min(num_bins - 1);
+            counts[k] += 1.0;
+        }
+    }
+
+    let norm = size as f64 * bin.as_secs();
+    let mut rates: Vec<f64> = counts
+        .into_iter()
+        .map(|c| if norm > 0.0 { c / norm } else { 0.0 })
+        .collect();
+    if let Some(sigma) = sigma.filter(|s| s.0 > 0.0) {
+        rates = gaussian_smooth(&rates, sigma / bin);
+    }
+
+    let times = (0..num_bins)
+        .map(|k| start + bin * (k as f64 + 0.5))
+        .collect();
+    (times, rates)
+}
+
+/// Convolve `values` with a Gaussian of standard deviation `sigma` samples,
+/// truncated at four standard deviations and renormalized at the edges.
+fn gaussian_smooth(values: &[f64], sigma: f64) -> Vec<f64> {
+    let radius = (4.0 * sigma).ceil() as isize;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|d| (-(d as f64).powi(2) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    (0..values.len() as isize)
+        .map(|i| {
+            let (mut sum, mut weight) = (0.0, 0.0);
+            for (d, &w) in (-radius..=radius).zip(&kernel) {
+                if let Some(&v) = usize::try_from(i + d).ok().and_then(|j| values.get(j)) {
+                    sum += w * v;
+                    weight += w;
+                }
+            }
+            sum / weight
+        })
+        .collect()
+}
+
+impl Psth {
+    /// Bin the spikes of `neurons` in the window `[onset - pre, onset + post)`
+    /// around each stimulus onset.
+    ///
+    /// Windows of different trials may overlap; a spike is then counted in
+    /// each of them.
+    pub fn compute(
+        spikes: &[Spike],
+        neurons: Range<usize>,
+        onsets: &[Milliseconds],
+        pre: Milliseconds,
+        post: Milliseconds,
+        bin: Milliseconds,
+    ) -> Self {
+        let num_bins = if bin.0 > 0.0 {
+            ((pre + post) / bin).ceil().max(0.0) as usize
+        } else {
+            0
+        };
+        let mut counts = vec![vec![vec![0; num_bins]; neurons.len()]; onsets.len()];
+        for s in spikes.iter().filter(|s| neurons.contains(&s.neuron_id)) {
+            for (trial, &onset) in onsets.iter().enumerate() {
+                let k = ((s.time - (onset - pre)) / bin).floor();
+                if k >= 0.0 && (k as usize) < num_bins {
+                    counts[trial][s.neuron_id - neurons.start][k as usize] += 1;
+                }
+            }
+        }
+        Self {
+            neurons,
+            onsets: onsets.to_vec(),
+            pre,
+            bin,
+            counts,
+        }
+    }
+
+    /// Number of bins per trial.
+    pub fn num_bins(&self) -> usize {
+        self.counts
+            .first()
+            .and_then(|t| t.first())
+            .map_or(0, Vec::len)
+    }
+
+    /// Start of each bin relative to stimulus onset.
+    pub fn bin_times(&self) -> Vec<Milliseconds> {
+        (0..self.num_bins())
+            .map(|k| self.bin * k as f64 - self.pre)
+            .collect()
+    }
+
+    /// Trial-averaged firing rate (Hz) of neuron `k` (relative to
+    /// `neurons.start`) in each bin.
+    pub fn mean_rate(&self, k: usize) -> Vec<f64> {
+        let norm = self.onsets.len() as f64 * self.bin.as_secs();
+        (0..self.num_bins())
+            .map(|b| {
+                let total: usize = self.counts.iter().map(|trial| trial[k][b]).sum();
+                if norm > 0.0 {
+                    total as f64 / norm
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// Trial-averaged firing rate (Hz) of the whole population in each bin.
+    pub fn population_mean_rate(&self) -> Vec<f64> {
+        let n = self.neurons.len();
+        let mut rates = vec![0.0; self.num_bins()];
+        for k in 0..n {
+            for (r, v) in rates.iter_mut().zip(self.mean_rate(k)) {
+                *r += v / n as f64;
+            }
+        }
+        rates
+    }
+
+    /// Response latency of neuron `k`: start of the first bin after onset
+    /// whose trial-averaged rate exceeds the mean pre-stimulus rate by
+    /// `threshold_hz`.
+    pub fn response_latency(&self, k: usize, threshold_hz: f64) -> Option<Milliseconds> {
+        let rates = self.mean_rate(k);
+        let times = self.bin_times();
+        let baseline: Vec<f64> = rates
+            .iter()
+            .zip(&times)
+            .filter(|(_, t)| t.0 < -1e-9)
+            .map(|(&r, _)| r)
+            .collect();
+        let (base, _) = mean_std(&baseline);
+        rates
+            .iter()
+            .zip(&times)
+            .find(|(&r, t)| t.0 >= -1e-9 && r > base + threshold_hz)
+            .map(|(_, &t)| t)
+    }
+}
+
+/// Victor–Purpura distance between two spike trains.
+///
+/// The minimal cost of transforming one train into the other, where
+/// inserting or deleting a spike costs `1` and moving a spike by `Δt`
+/// costs `cost_per_ms * |Δt|`. A cost of zero compares spike counts only;
+/// large costs approach a coincidence count.
+pub fn victor_purpura(a: &[Milliseconds], b: &[Milliseconds], cost_per_ms: f64) -> f64 {
+    let mut prev: Vec<f64> = (0..=b.len()).map(|j| j as f64).collect();
+    for (i, &ta) in a.iter().enumerate() {
+        let mut row = vec![(i + 1) as f64; b.len() + 1];
+        for (j, &tb) in b.iter().enumerate() {
+            let shift = prev[j] + cost_per_ms * (ta - tb).abs().0;
+            row[j + 1] = (prev[j + 1] + 1.0).min(row[j] + 1.0).min(shift);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// van Rossum distance between two spike trains.
+///
+/// Each train is convolved with a causal exponential kernel of time
+/// constant `tau` and the distance is `sqrt(∫ (f - g)² dt / tau)`, computed
+/// in closed form. Identical trains have distance zero and a single extra
+/// spike contributes `sqrt(1/2)`.
+pub fn van_rossum(a: &[Milliseconds], b: &[Milliseconds], tau: Milliseconds) -> f64 {
+    let overlap = |x: &[Milliseconds], y: &[Milliseconds]| -> f64 {
+        x.iter()
+            .flat_map(|&s| y.iter().map(move |&t| (-((s - t).abs() / tau)).exp()))
+            .sum()
+    };
+    let squared = 0.5 * (overlap(a, a) + overlap(b, b) - 2.0 * overlap(a, b));
+    squared.max(0.0).sqrt()
+}
+
+/// Symmetric matrix of `distance` between every pair of `trains`.
+///
+/// For example
+/// `distance_matrix(&trains, |a, b| van_rossum(a, b, Milliseconds(10.0)))`.
+pub fn distance_matrix<D>(trains: &[Vec<Milliseconds>], distance: D) -> Vec<Vec<f64>>
+where
+    D: Fn(&[Milliseconds], &[Milliseconds]) -> f64,
+{
+    let n = trains.len();
+    let mut matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let d = distance(&trains[i], &trains[j]);
+            matrix[i][j] = d;
+            matrix[j][i] = d;
+        }
+    }
+    matrix
+}
+
+impl Correlogram {
+    /// Start of each lag bin.
+    pub fn lags(&self) -> Vec<Milliseconds> {
+        (0..self.counts.len())
+            .map(|k| self.bin * k as f64 - self.max_lag)
+            .collect()
+    }
+
+    /// Balance of pairs at positive versus negative lags, in `[-1, 1]`.
+    ///
+    /// Positive values mean the second train tends to fire after the first,
+    /// the signature of a potentiated first-to-second connection under STDP.
+    pub fn asymmetry(&self) -> f64 {
+        let (mut before, mut after) = (0, 0);
+        for (lag, &c) in self.lags().iter().zip(&self.counts) {
+            let center = lag.0 + self.bin.0 / 2.0;
+            if center < 0.0 {
+                before += c;
+            } else if center > 0.0 {
+                after += c;
+            }
+        }
+        let total = before + after;
+        if total == 0 {
+            0.0
+        } else {
+            (after as f64 - before as f64) / total as f64
+        }
+    }
+}
+
+/// Cross-correlogram of sorted trains `a` and `b`: counts of the lags
+/// `t_b - t_a` in `[-max_lag, max_lag)`, in bins of width `bin`.
+pub fn cross_correlogram(
+    a: &[Milliseconds],
+    b: &[Milliseconds],
+    max_lag: Milliseconds,
+    bin: Milliseconds,
+) -> Correlogram {
+    let num_bins = if bin.0 > 0.0 {
+        (max_lag * 2.0 / bin).ceil().max(0.0) as usize
+    } else {
+        0
+    };
+    let mut counts = vec![0; num_bins];
+    let mut first = 0;
+    for &ta in a {
+        // Both trains are sorted, so the window start only moves forward
+        while first < b.len() && b[first] - ta < -max_lag {
+            first += 1;
+        }
+        for &tb in &b[first..] {
+            let lag = tb - ta;
+            if lag >= max_lag {
+                break;
+            }
+            let k = ((lag + max_lag) / bin).floor() as usize;
+            if k < num_bins {
+                counts[k] += 1;
+            }
+        }
+    }
+    Correlogram {
+        max_lag,
+        bin,
+        counts,
+    }
+}
+
+/// Cross-correlograms of every pair `(i, j)` with `i` before `j` in
+/// `neurons`, e.g. the members of a group.
+pub fn pairwise_correlograms(
+    spikes: &[Spike],
+    neurons: &[usize],
+    max_lag: Milliseconds,
+    bin: Milliseconds,
+) -> Vec<(usize, usize, Correlogram)> {
+    let size = neurons.iter().max().map_or(0, |&m| m + 1);
+    let trains = spike_trains(spikes, size);
+    let mut result = Vec::new();
+    for (k, &i) in neurons.iter().enumerate() {
+        for &j in &neurons[k + 1..] {
+            let c = cross_correlogram(&trains[i], &trains[j], max_lag, bin);
+            result.push((i, j, c));
+        }
+    }
+    result
+}
+
+/// Spike counts of each neuron in `neurons` in bins of width `bin` over
+/// `[start, end)`. Rows are empty if `bin` is not positive and finite or
+/// the window is empty.
+pub fn binned_counts(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+    bin: Milliseconds,
+) -> Vec<Vec<f64>> {
+    if bin.0 <= 0.0 || !bin.0.is_finite() || end <= start {
+        return vec![Vec::new(); neurons.len()];
+    }
+    let num_bins = ((end - start) / bin).ceil() as usize;
+    let mut counts = vec![vec![0.0; num_bins]; neurons.len()];
+    for s in spikes {
+        if neurons.contains(&s.neuron_id) && s.time >= start && s.time < end {
+            let k = (((s.time - start) / bin) as usize).min(num_bins - 1);
+            counts[s.neuron_id - neurons.start][k] += 1.0;
+        }
+    }
+    counts
+}
+
+/// Golomb–Hansel synchrony index χ of a set of equally long signals, e.g.
+/// membrane potential traces or binned spike counts.
+///
+/// χ² is the variance of the population-averaged signal divided by the
+/// mean variance of the individual signals: `1` for perfectly synchronous
+/// activity, about `1/√N` for `N` independent neurons. Returns `None` if
+/// the signals differ in length or none varies.
+pub fn synchrony_index(signals: &[Vec<f64>]) -> Option<f64> {
+    let len = signals.first()?.len();
+    if signals.iter().any(|s| s.len() != len) {
+        return None;
+    }
+    let mean_signal: Vec<f64> = (0..len)
+        .map(|t| signals.iter().map(|s| s[t]).sum::<f64>() / signals.len() as f64)
+        .collect();
+    let (_, pop_std) = mean_std(&mean_signal);
+    let mean_var = signals
+        .iter()
+        .map(|s| mean_std(s).1.powi(2))
+        .sum::<f64>()
+        / signals.len() as f64;
+    (mean_var > 0.0).then(|| (pop_std * pop_std / mean_var).sqrt())
+}
+
+/// Synchrony index of the binned spike counts of `neurons` over
+/// `[start, end)`.
+pub fn spike_synchrony(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+    bin: Milliseconds,
+) -> Option<f64> {
+    synchrony_index(&binned_counts(spikes, neurons, start, end, bin))
+}
+
+/// Mean Pearson correlation coefficient between the binned spike counts of
+/// all pairs of `neurons` over `[start, end)`.
+///
+/// Pairs involving a neuron with constant counts (e.g. silent) are
+/// skipped; returns `None` if no pair remains.
+pub fn mean_pairwise_correlation(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+    bin: Milliseconds,
+) -> Option<f64> {
+    let counts = binned_counts(spikes, neurons, start, end, bin);
+    let standardized: Vec<Vec<f64>> = counts
+        .iter()
+        .filter_map(|c| {
+            let (mean, std) = mean_std(c);
+            (std > 0.0).then(|| c.iter().map(|x| (x - mean) / std).collect())
+        })
+        .collect();
+    let (mut sum, mut pairs) = (0.0, 0);
+    for (i, a) in standardized.iter().enumerate() {
+        for b in &standardized[i + 1..] {
+            sum += a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>() / a.len() as f64;
+            pairs += 1;
+        }
+    }
+    (pairs > 0).then(|| sum / pairs as f64)
+}
+
+/// Power spectrum of a uniformly sampled signal, as
+/// `(frequencies_hz, power)` from zero up to the Nyquist frequency.
+///
+/// The mean is removed first, so power at zero frequency is zero. Useful
+/// on the output of `population_rate` to detect network oscillations.
+pub fn power_spectrum(signal: &[f64], sample_interval: Milliseconds) -> (Vec<f64>, Vec<f64>) {
+    let n = signal.len();
+    if n == 0 || sample_interval.0 <= 0.0 {
+        return (Vec::new(), Vec::new());
+    }
+    let (mean, _) = mean_std(signal);
+    let fs = 1.0 / sample_interval.as_secs();
+    (0..=n / 2)
+        .map(|k| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (t, &x) in signal.iter().enumerate() {
+                let angle = -std::f64::consts::TAU * (k * t) as f64 / n as f64;
+                re += (x - mean) * angle.cos();
+                im += (x - mean) * angle.sin();
+            }
+            (k as f64 * fs / n as f64, (re * re + im * im) / n as f64)
+        })
+        .unzip()
+}
+
+/// Frequency (Hz) with the most power in a spectrum from `power_spectrum`,
+/// ignoring zero frequency.
+pub fn dominant_frequency(frequencies: &[f64], power: &[f64]) -> Option<f64> {
+    frequencies
+        .iter()
+        .zip(power)
+        .skip(1)
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(&f, _)| f)
+}
+
+/// Spike-triggered average of `signals` over the `window` before each
+/// spike of `train`.
+///
+/// `signals` holds one row per input channel sampled every
+/// `sample_interval` from time zero. The result has one row per channel
+/// with `window / sample_interval + 1` entries in time order, the last
+/// being the sample at the spike. Spikes whose window is not fully covered
+/// by the signals are skipped; `None` if no spike remains.
+pub fn spike_triggered_average(
+    train: &[Milliseconds],
+    signals: &[Vec<f64>],
+    sample_interval: Milliseconds,
+    window: Milliseconds,
+) -> Option<Vec<Vec<f64>>> {
+    if sample_interval.0 <= 0.0 || !sample_interval.0.is_finite() {
+        return None;
+    }
+    let lags = (window / sample_interval).round().max(0.0) as usize;
+    let len = signals.iter().map(Vec::len).min().unwrap_or(0);
+    let mut sums = vec![vec![0.0; lags + 1]; signals.len()];
+    let mut count = 0;
+    for t in train {
+        let end = (*t / sample_interval + 1e-9).floor();
+        if end < lags as f64 || end >= len as f64 {
+            continue;
+        }
+        let start = end as usize - lags;
+        for (sum, signal) in sums.iter_mut().zip(signals) {
+            for (s, x) in sum.iter_mut().zip(&signal[start..=start + lags]) {
+                *s += x;
+            }
+        }
+        count += 1;
+    }
+    (count > 0).then(|| {
+        sums.into_iter()
+            .map(|row| row.into_iter().map(|s| s / count as f64).collect())
+            .collect()
+    })
+}
+
+/// Spike-triggered average of the input spike trains of `inputs` before
+/// each spike of `train`: the mean input spike count per bin of width
+/// `bin` over the preceding `window`, one row per input neuron.
+///
+/// Inputs are binned from time zero to the last output spike. The last
+/// bin of each row is the one containing the output spike.
+pub fn spike_triggered_input(
+    input_spikes: &[Spike],
+    inputs: Range<usize>,
+    train: &[Milliseconds],
+    window: Milliseconds,
+    bin: Milliseconds,
+) -> Option<Vec<Vec<f64>>> {
+    let end = train.iter().copied().fold(Milliseconds::ZERO, Milliseconds::max) + bin;
+    let counts = binned_counts(input_spikes, inputs, Milliseconds::ZERO, end, bin);
+    spike_triggered_average(train, &counts, bin, window)
+}
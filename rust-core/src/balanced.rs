@@ -0,0 +1,113 @@
+//! balanced.rs
+//!
+//! Brunel-style balanced excitatory/inhibitory random network.
+//!
+//! The sparsely connected E/I network of Brunel (2000) is the standard
+//! benchmark for spiking network dynamics. Depending on the relative
+//! inhibitory strength `g` and the external drive, it settles into
+//! synchronous-regular, asynchronous-irregular, or oscillatory regimes.
+//! Every neuron receives a fixed number of excitatory and inhibitory inputs,
+//! each with the same transmission delay.
+
+use crate::connectivity::fixed_in_degree;
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Parameters of a balanced network.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalancedParams {
+    /// Number of excitatory neurons
+    pub num_excitatory: usize,
+    /// Number of inhibitory neurons
+    pub num_inhibitory: usize,
+    /// Connection probability; each neuron receives `epsilon * N_E`
+    /// excitatory and `epsilon * N_I` inhibitory inputs
+    pub epsilon: f64,
+    /// Excitatory weight (membrane potential jump)
+    pub j: f64,
+    /// Relative inhibitory strength; inhibitory weights are `-g * j`
+    pub g: f64,
+    /// Transmission delay of every synapse
+    pub delay: Milliseconds,
+    /// Seed for wiring
+    pub seed: u64,
+}
+
+impl Default for BalancedParams {
+    fn default() -> Self {
+        Self {
+            num_excitatory: 800,
+            num_inhibitory: 200,
+            epsilon: 0.1,
+            j: 0.1,
+            g: 5.0,
+            delay: Milliseconds(1.5),
+            seed: 0,
+        }
+    }
+}
+
+/// A balanced network simulation together with its layout.
+pub struct BalancedNetwork {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Excitatory neuron indices
+    pub excitatory: Range<usize>,
+    /// Inhibitory neuron indices
+    pub inhibitory: Range<usize>,
+}
+
+/// Build a Brunel-style balanced random network.
+///
+/// Populations `excitatory` and `inhibitory` are registered on the
+/// network. All synapses are static and synaptic transmission is enabled on
+/// `config`. External drive is supplied through the input current function
+/// passed to `run`.
+pub fn build_balanced(
+    params: &BalancedParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+    stdp_params: STDPParams,
+) -> BalancedNetwork {
+    let mut rng = Rng::new(params.seed);
+    let mut builder = NetworkBuilder::new();
+    let excitatory =
+        builder.add_population("excitatory", params.num_excitatory, neuron_params.clone());
+    let inhibitory = builder.add_population("inhibitory", params.num_inhibitory, neuron_params);
+    let all = excitatory.start..inhibitory.end;
+
+    let c_e = (params.epsilon * params.num_excitatory as f64).round() as usize;
+    let c_i = (params.epsilon * params.num_inhibitory as f64).round() as usize;
+    let j_e = params.j;
+    let j_i = -params.g * params.j;
+
+    let mut connections =
+        fixed_in_degree(excitatory.clone(), all.clone(), c_e, &mut rng, |_, _, _| j_e);
+    connections.extend(fixed_in_degree(
+        inhibitory.clone(),
+        all,
+        c_i,
+        &mut rng,
+        |_, _, _| j_i,
+    ));
+
+    builder.add_synapses(connections.into_iter().map(|(pre, post, w)| {
+        let mut synapse = Synapse::with_delay(pre, post, w, params.delay);
+        synapse.plasticity = Plasticity::Static;
+        synapse
+    }));
+
+    config.synaptic_transmission = true;
+    BalancedNetwork {
+        simulation: Simulation::from_network(builder.build(), config, stdp_params),
+        excitatory,
+        inhibitory,
+    }
+}
@@ -0,0 +1,501 @@
+//! snn.rs
+//!
+//! Command-line interface to the simulator.
+//!
+//! Collaborators who do not write Rust can still run experiments: `snn`
+//! runs TOML experiment configurations (see `config`), sweeps one of their
+//! parameters over repeated trials, summarizes spike files, and converts
+//! spike files between CSV and JSON.
+
+use neuromorphic_core::analysis::summarize;
+use neuromorphic_core::bursts::{burst_stats, detect_all_bursts, MaxIntervalParams};
+use neuromorphic_core::compression::{self, Compression};
+use neuromorphic_core::config::{ExperimentConfig, Stimulus};
+use neuromorphic_core::experiment::{run_metrics, Experiment};
+#[cfg(feature = "grpc")]
+use neuromorphic_core::grpc::{ControlServer, SERVICE};
+use neuromorphic_core::manifest::RunManifest;
+use neuromorphic_core::numpy::write_spikes_npy;
+use neuromorphic_core::output::{OutputConfig, OutputFormat};
+use neuromorphic_core::parquet::write_spikes_parquet;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::spike_codec::{read_spikes_binary, write_spikes_binary};
+use neuromorphic_core::spike_io::{
+    read_spikes_csv, read_spikes_json, write_spikes_csv, write_spikes_json,
+};
+use neuromorphic_core::spike_server::SpikeServer;
+use neuromorphic_core::stopping::StopReason;
+use neuromorphic_core::units::Milliseconds;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+/// Time resolution of `.spk` files written by `convert`.
+const SPK_RESOLUTION: Milliseconds = Milliseconds(0.001);
+
+const USAGE: &str = "\
+Usage: snn <command> [arguments]
+
+Commands:
+  run <config.toml> [--out DIR] [--formats LIST] [--run-dirs PREFIX]
+                    [--compress gzip|zstd] [--serve ADDR] [--manifest FILE]
+      Run the experiment and write its results to DIR (default: current
+      directory), or to a new numbered subdirectory PREFIX-NNNN of DIR
+      with --run-dirs. LIST is a comma-separated list of csv (spikes.csv
+      and weights.csv), json (results.json), npz, parquet and spk, and
+      defaults to csv,json. CSV, JSON and .spk files are compressed with
+      --compress (adding .gz or .zst). With --serve, wait for a client on
+      ADDR (e.g. 127.0.0.1:7878), stream spikes to it and inject the
+      spikes it sends; weights are not sampled then. With --manifest,
+      also write a JSON reproducibility manifest (configuration, seed,
+      crate version, network hash and output checksums) to FILE.
+  sweep <config.toml> --param NAME --values V1,V2,... [--trials N] [--out FILE]
+      Run N seeded trials (default 5) per value of parameter NAME and
+      print mean and standard deviation of the run metrics; with --out,
+      also write them as CSV. NAME is one of simulation.dt_ms,
+      simulation.t_max_ms, neuron.<key>, stdp.<key>, stimulus.rate_hz or
+      stimulus.amplitude, with keys as in the configuration file.
+      neuron.* keys apply to every population.
+  analyze <spikes.csv|spikes.json|spikes.spk> [--neurons N] [--duration MS] [--window MS]
+      Print firing statistics and bursts of a spike file. N and MS
+      default to the largest neuron id and spike time in the file; the
+      Fano factor window defaults to 100 ms.
+  convert <input> <output>
+      Convert a spike file between CSV, JSON and the compact binary .spk
+      format, chosen by extension. The output may also be a .parquet or
+      NumPy .npy file, and is compressed if its name ends in .gz or .zst.
+      Compressed inputs must be decompressed first, e.g. with gunzip.
+  control <ADDR>
+      Serve the gRPC control API of proto/control.proto on ADDR, e.g.
+      0.0.0.0:50051, until interrupted. Needs the grpc feature.
+
+With the tracing feature, set NEUROMORPHIC_LOG to a level (error, warn,
+info, debug or trace) to log run telemetry to standard error.
+";
+
+fn main() -> ExitCode {
+    #[cfg(feature = "tracing")]
+    if let Some(level) = std::env::var("NEUROMORPHIC_LOG")
+        .ok()
+        .and_then(|name| neuromorphic_core::trace::Level::from_name(&name))
+    {
+        neuromorphic_core::trace::set_global_subscriber(
+            neuromorphic_core::trace::FmtSubscriber::new(level),
+        );
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        eprint!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let result = match command.as_str() {
+        "run" => Args::parse(&args[1..], 1).and_then(|a| run(&a)),
+        "sweep" => Args::parse(&args[1..], 1).and_then(|a| sweep(&a)),
+        "analyze" => Args::parse(&args[1..], 1).and_then(|a| analyze(&a)),
+        "convert" => Args::parse(&args[1..], 2).and_then(|a| convert(&a)),
+        "control" => Args::parse(&args[1..], 1).and_then(|a| control(&a)),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        other => Err(CliError::Usage(format!("unknown command `{other}`"))),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(CliError::Io(e)) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Failure of a command.
+enum CliError {
+    /// Malformed command line
+    Usage(String),
+    /// Failure while reading, running or writing
+    Io(io::Error),
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+/// Positional arguments and `--name value` options of a command.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    /// Split `args`, requiring exactly `positional` positional arguments.
+    fn parse(args: &[String], positional: usize) -> Result<Self, CliError> {
+        let mut parsed = Self {
+            positional: Vec::new(),
+            options: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("`--{name}` needs a value")))?;
+                    parsed.options.push((name.to_string(), value.clone()));
+                }
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        if parsed.positional.len() != positional {
+            return Err(CliError::Usage(format!(
+                "expected {positional} argument(s), got {}",
+                parsed.positional.len()
+            )));
+        }
+        Ok(parsed)
+    }
+
+    /// Value of option `name`, failing on options outside `known`.
+    fn option(&self, name: &str, known: &[&str]) -> Result<Option<&str>, CliError> {
+        if let Some((unknown, _)) = self
+            .options
+            .iter()
+            .find(|(n, _)| !known.contains(&n.as_str()))
+        {
+            return Err(CliError::Usage(format!("unknown option `--{unknown}`")));
+        }
+        Ok(self
+            .options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str()))
+    }
+
+    /// Option `name` parsed as a number.
+    fn number<T: std::str::FromStr>(
+        &self,
+        name: &str,
+        known: &[&str],
+    ) -> Result<Option<T>, CliError> {
+        self.option(name, known)?
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| CliError::Usage(format!("invalid value `{v}` for `--{name}`")))
+            })
+            .transpose()
+    }
+}
+
+/// `snn run`: run a configuration and write its results.
+fn run(args: &Args) -> Result<(), CliError> {
+    let known = ["out", "serve", "compress", "formats", "run-dirs", "manifest"];
+    let config_text = std::fs::read_to_string(&args.positional[0])?;
+    let config = ExperimentConfig::from_toml(&config_text)?;
+    let mut output = OutputConfig::new(args.option("out", &known)?.unwrap_or("."));
+    if let Some(name) = args.option("compress", &known)? {
+        output.compression = Compression::from_name(name).ok_or_else(|| {
+            CliError::Usage(format!("invalid value `{name}` for `--compress`"))
+        })?;
+    }
+    if let Some(names) = args.option("formats", &known)? {
+        output.formats = names
+            .split(',')
+            .map(|name| {
+                OutputFormat::from_name(name.trim()).ok_or_else(|| {
+                    CliError::Usage(format!("invalid value `{name}` for `--formats`"))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    match args.option("run-dirs", &known)? {
+        Some(prefix) => output.prefix = prefix.to_string(),
+        None => output.per_run = false,
+    }
+
+    let mut sim = config.build();
+    let manifest = RunManifest::new(&sim, Some(config.seed)).with_config(&config_text);
+    let (spikes, weights) = match args.option("serve", &known)? {
+        Some(address) => {
+            let server = SpikeServer::bind(address)?;
+            eprintln!("waiting for a client on {}", server.local_addr());
+            server.wait_for_clients(1, Duration::MAX);
+            (server.run(&mut sim, config.input_current()), Vec::new())
+        }
+        None => {
+            let (spikes, weights, _) = sim.run_parallel(config.input_current(), &[]);
+            (spikes, weights)
+        }
+    };
+    let mut run = output.create_run()?;
+    run.write_results(&sim, &spikes, &weights, Some(StopReason::Completed))?;
+    if let Some(path) = args.option("manifest", &known)? {
+        let mut manifest = manifest;
+        for file in run.files() {
+            manifest.add_output(file)?;
+        }
+        manifest.write(path)?;
+    }
+
+    for (name, value) in run_metrics(&sim, &spikes) {
+        println!("{name:<14}{value:.4}");
+    }
+    println!("wrote results to {}", run.dir().display());
+    Ok(())
+}
+
+/// `snn sweep`: repeated trials for each value of one parameter.
+fn sweep(args: &Args) -> Result<(), CliError> {
+    let known = ["param", "values", "trials", "out"];
+    let base = ExperimentConfig::load(&args.positional[0])?;
+    let param = args
+        .option("param", &known)?
+        .ok_or_else(|| CliError::Usage("sweep needs `--param`".to_string()))?;
+    let values = args
+        .option("values", &known)?
+        .ok_or_else(|| CliError::Usage("sweep needs `--values`".to_string()))?
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse::<f64>()
+                .map_err(|_| CliError::Usage(format!("invalid sweep value `{v}`")))
+        })
+        .collect::<Result<Vec<f64>, CliError>>()?;
+    let trials = args.number("trials", &known)?.unwrap_or(5);
+    // Fail on unknown parameters before running anything
+    set_param(&mut base.clone(), param, 0.0)?;
+
+    let mut rows = Vec::new();
+    for &value in &values {
+        let mut config = base.clone();
+        set_param(&mut config, param, value)?;
+        let mut experiment = Experiment::new(&format!("{param} = {value}"), trials);
+        experiment.base_seed = config.seed;
+        experiment.parallel = true;
+        let report = experiment.run(|seed| {
+            let mut config = config.clone();
+            config.seed = seed;
+            let mut sim = config.build();
+            let (spikes, _) = sim.run(config.input_current());
+            run_metrics(&sim, &spikes)
+        });
+        println!("{report}");
+        rows.extend(report.summary.into_iter().map(|m| (value, m)));
+    }
+
+    if let Some(path) = args.option("out", &known)? {
+        let mut writer = compression::create(path)?;
+        writeln!(writer, "{param},metric,mean,std,min,max")?;
+        for (value, m) in rows {
+            writeln!(
+                writer,
+                "{value},{},{},{},{},{}",
+                m.name, m.mean, m.std, m.min, m.max
+            )?;
+        }
+        writer.finish()?;
+    }
+    Ok(())
+}
+
+/// Set sweep parameter `name` of `config` to `value`.
+fn set_param(config: &mut ExperimentConfig, name: &str, value: f64) -> Result<(), CliError> {
+    let unknown = || CliError::Usage(format!("unknown sweep parameter `{name}`"));
+    let (table, key) = name.split_once('.').ok_or_else(unknown)?;
+    match table {
+        "simulation" => match key {
+            "dt_ms" => config.simulation.dt = Milliseconds(value),
+            "t_max_ms" => config.simulation.t_max = Milliseconds(value),
+            _ => return Err(unknown()),
+        },
+        "neuron" => {
+            let params = std::iter::once(&mut config.neuron)
+                .chain(config.populations.iter_mut().map(|p| &mut p.params));
+            for p in params {
+                match key {
+                    "tau_m_ms" => p.tau_m = Milliseconds(value),
+                    "v_rest" => p.v_rest = value,
+                    "v_thresh" => p.v_thresh = value,
+                    "v_reset" => p.v_reset = value,
+                    _ => return Err(unknown()),
+                }
+            }
+        }
+        "stdp" => {
+            let params = std::iter::once(&mut config.stdp).chain(
+                config
+                    .projections
+                    .iter_mut()
+                    .filter_map(|p| p.plasticity.as_mut()),
+            );
+            for p in params {
+                match key {
+                    "a_plus" => p.a_plus = value,
+                    "a_minus" => p.a_minus = value,
+                    "tau_plus_ms" => p.tau_plus = Milliseconds(value),
+                    "tau_minus_ms" => p.tau_minus = Milliseconds(value),
+                    "w_min" => p.w_min = value,
+                    "w_max" => p.w_max = value,
+                    _ => return Err(unknown()),
+                }
+            }
+        }
+        "stimulus" => {
+            if key != "rate_hz" && key != "amplitude" {
+                return Err(unknown());
+            }
+            for stimulus in &mut config.stimuli {
+                match (key, stimulus) {
+                    ("rate_hz", Stimulus::Poisson { rate_hz, .. }) => *rate_hz = value,
+                    ("amplitude", Stimulus::Current { amplitude, .. }) => *amplitude = value,
+                    _ => {}
+                }
+            }
+        }
+        _ => return Err(unknown()),
+    }
+    Ok(())
+}
+
+/// `snn analyze`: firing statistics of a spike file.
+fn analyze(args: &Args) -> Result<(), CliError> {
+    let known = ["neurons", "duration", "window"];
+    let spikes = read_spikes(Path::new(&args.positional[0]))?;
+    let neurons = match args.number("neurons", &known)? {
+        Some(n) => n,
+        None => spikes.iter().map(|s| s.neuron_id + 1).max().unwrap_or(0),
+    };
+    let duration = match args.number("duration", &known)? {
+        Some(d) => Milliseconds(d),
+        None => spikes
+            .iter()
+            .map(|s| s.time)
+            .fold(Milliseconds::ZERO, Milliseconds::max),
+    };
+    let window = Milliseconds(args.number("window", &known)?.unwrap_or(100.0));
+    if spikes.iter().any(|s| s.neuron_id >= neurons) {
+        return Err(CliError::Usage(format!(
+            "spike file contains neuron ids outside 0..{neurons}"
+        )));
+    }
+
+    let stats = summarize(&spikes, neurons, duration, window);
+    let mean_of = |values: Vec<f64>| {
+        if values.is_empty() {
+            f64::NAN
+        } else {
+            values.iter().sum::<f64>() / values.len() as f64
+        }
+    };
+    let bursts = detect_all_bursts(&spikes, neurons, &MaxIntervalParams::default());
+    let burst = burst_stats(&bursts, spikes.len(), neurons, duration);
+    println!("{:<22}{}", "spikes", spikes.len());
+    println!("{:<22}{}", "neurons", neurons);
+    println!("{:<22}{}", "duration_ms", duration.0);
+    println!(
+        "{:<22}{:.4}",
+        "mean_rate_hz",
+        mean_of(stats.iter().map(|s| s.rate_hz).collect())
+    );
+    println!(
+        "{:<22}{:.4}",
+        "mean_cv_isi",
+        mean_of(stats.iter().filter_map(|s| s.cv_isi).collect())
+    );
+    println!(
+        "{:<22}{:.4}",
+        "mean_fano_factor",
+        mean_of(stats.iter().filter_map(|s| s.fano_factor).collect())
+    );
+    println!(
+        "{:<22}{:.4}",
+        "silent_fraction",
+        stats.iter().filter(|s| s.spike_count == 0).count() as f64 / neurons.max(1) as f64
+    );
+    println!("{:<22}{}", "bursts", burst.num_bursts);
+    println!("{:<22}{:.4}", "burst_rate_hz", burst.burst_rate_hz);
+    println!(
+        "{:<22}{:.4}",
+        "fraction_in_bursts", burst.fraction_in_bursts
+    );
+    Ok(())
+}
+
+/// `snn convert`: convert a spike file between CSV, JSON and binary, or
+/// export it to Parquet or NumPy.
+fn convert(args: &Args) -> Result<(), CliError> {
+    args.option("", &[])?;
+    let spikes = read_spikes(Path::new(&args.positional[0]))?;
+    let output = Path::new(&args.positional[1]);
+    let export = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    if export.as_deref() == Some("parquet") {
+        write_spikes_parquet(&spikes, output)?;
+    } else if export.as_deref() == Some("npy") {
+        write_spikes_npy(&spikes, output)?;
+    } else {
+        match extension(output)?.as_str() {
+            "csv" => write_spikes_csv(&spikes, output)?,
+            "spk" => write_spikes_binary(&spikes, SPK_RESOLUTION, output)?,
+            _ => write_spikes_json(&spikes, output)?,
+        }
+    }
+    println!("converted {} spikes", spikes.len());
+    Ok(())
+}
+
+/// `snn control`: serve the gRPC control API.
+#[cfg(feature = "grpc")]
+fn control(args: &Args) -> Result<(), CliError> {
+    args.option("", &[])?;
+    let server = ControlServer::bind(&args.positional[0])?;
+    eprintln!("serving {} on {}", SERVICE, server.local_addr()?);
+    Ok(server.serve()?)
+}
+
+#[cfg(not(feature = "grpc"))]
+fn control(_: &Args) -> Result<(), CliError> {
+    Err(CliError::Usage(
+        "`control` needs snn built with the grpc feature".to_string(),
+    ))
+}
+
+/// Read a CSV, JSON or binary spike file, chosen by extension.
+fn read_spikes(path: &Path) -> Result<Vec<Spike>, CliError> {
+    Ok(match extension(path)?.as_str() {
+        "csv" => read_spikes_csv(path)?,
+        "spk" => read_spikes_binary(path)?,
+        _ => read_spikes_json(path)?,
+    })
+}
+
+/// Lowercase extension of a spike file, which must be `csv`, `json` or
+/// `spk`, before any `.gz` or `.zst`.
+fn extension(path: &Path) -> Result<String, CliError> {
+    let uncompressed = match Compression::from_path(path) {
+        Compression::None => path,
+        _ => Path::new(path.file_stem().unwrap_or_default()),
+    };
+    let ext = uncompressed
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if ext == "csv" || ext == "json" || ext == "spk" {
+        Ok(ext)
+    } else {
+        Err(CliError::Usage(format!(
+            "`{}` is not a .csv, .json or .spk file",
+            path.display()
+        )))
+    }
+}
@@ -0,0 +1,185 @@
+//! bursts.rs
+//!
+//! Burst detection in spike trains.
+//!
+//! Bursts — short runs of closely spaced spikes followed by silence — carry
+//! information distinct from single spikes and are the defining behaviour
+//! of bursting neuron models. This module implements the max-interval
+//! method: a burst starts at an ISI no longer than `max_begin_isi`,
+//! continues while ISIs stay within `max_end_isi`, is merged with a
+//! following burst closer than `min_interburst_interval`, and is kept only
+//! if it lasts at least `min_duration` and contains at least `min_spikes`
+//! spikes.
+
+use crate::analysis::spike_trains;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+
+/// Parameters of the max-interval burst detector.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxIntervalParams {
+    /// Largest ISI that can start a burst
+    pub max_begin_isi: Milliseconds,
+    /// Largest ISI within a burst
+    pub max_end_isi: Milliseconds,
+    /// Bursts closer than this are merged
+    pub min_interburst_interval: Milliseconds,
+    /// Shortest accepted burst
+    pub min_duration: Milliseconds,
+    /// Fewest spikes in an accepted burst
+    pub min_spikes: usize,
+}
+
+impl Default for MaxIntervalParams {
+    fn default() -> Self {
+        Self {
+            max_begin_isi: Milliseconds(10.0),
+            max_end_isi: Milliseconds(20.0),
+            min_interburst_interval: Milliseconds(50.0),
+            min_duration: Milliseconds(5.0),
+            min_spikes: 3,
+        }
+    }
+}
+
+/// A detected burst.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Burst {
+    /// Neuron that burst
+    pub neuron_id: usize,
+    /// Time of the first spike
+    pub start: Milliseconds,
+    /// Time of the last spike
+    pub end: Milliseconds,
+    /// Number of spikes in the burst
+    pub num_spikes: usize,
+}
+
+impl Burst {
+    /// Time from first to last spike.
+    pub fn duration(&self) -> Milliseconds {
+        self.end - self.start
+    }
+
+    /// Mean firing frequency (Hz) within the burst.
+    pub fn intra_burst_frequency(&self) -> f64 {
+        let secs = self.duration().as_secs();
+        if secs > 0.0 {
+            (self.num_spikes - 1) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Summary of the bursts of one or more neurons.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BurstStats {
+    /// Number of bursts
+    pub num_bursts: usize,
+    /// Bursts per second per neuron
+    pub burst_rate_hz: f64,
+    /// Mean burst duration
+    pub mean_duration: Milliseconds,
+    /// Mean intra-burst frequency (Hz)
+    pub mean_intra_burst_frequency: f64,
+    /// Fraction of all spikes that fall within bursts
+    pub fraction_in_bursts: f64,
+}
+
+/// Detect the bursts of a single neuron from its sorted spike times.
+pub fn detect_bursts(
+    neuron_id: usize,
+    train: &[Milliseconds],
+    params: &MaxIntervalParams,
+) -> Vec<Burst> {
+    // Candidate bursts as index ranges into `train`
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i + 1 < train.len() {
+        if train[i + 1] - train[i] > params.max_begin_isi {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        i += 1;
+        while i + 1 < train.len() && train[i + 1] - train[i] <= params.max_end_isi {
+            i += 1;
+        }
+        candidates.push((first, i));
+        i += 1;
+    }
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (first, last) in candidates {
+        match merged.last_mut() {
+            Some(prev) if train[first] - train[prev.1] < params.min_interburst_interval => {
+                prev.1 = last;
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(first, last)| Burst {
+            neuron_id,
+            start: train[first],
+            end: train[last],
+            num_spikes: last - first + 1,
+        })
+        .filter(|b| b.duration() >= params.min_duration && b.num_spikes >= params.min_spikes)
+        .collect()
+}
+
+/// Detect the bursts of every neuron in `0..num_neurons`, ordered by
+/// neuron, then time.
+pub fn detect_all_bursts(
+    spikes: &[Spike],
+    num_neurons: usize,
+    params: &MaxIntervalParams,
+) -> Vec<Burst> {
+    spike_trains(spikes, num_neurons)
+        .iter()
+        .enumerate()
+        .flat_map(|(i, train)| detect_bursts(i, train, params))
+        .collect()
+}
+
+/// Summarize `bursts` detected in `total_spikes` spikes of `num_neurons`
+/// neurons recorded for `duration`.
+pub fn burst_stats(
+    bursts: &[Burst],
+    total_spikes: usize,
+    num_neurons: usize,
+    duration: Milliseconds,
+) -> BurstStats {
+    let n = bursts.len();
+    let exposure = duration.as_secs() * num_neurons as f64;
+    let in_bursts: usize = bursts.iter().map(|b| b.num_spikes).sum();
+    let mean = |f: &dyn Fn(&Burst) -> f64| {
+        if n == 0 {
+            0.0
+        } else {
+            bursts.iter().map(f).sum::<f64>() / n as f64
+        }
+    };
+    BurstStats {
+        num_bursts: n,
+        burst_rate_hz: if exposure > 0.0 {
+            n as f64 / exposure
+        } else {
+            0.0
+        },
+        mean_duration: Milliseconds(mean(&|b| b.duration().0)),
+        mean_intra_burst_frequency: mean(&|b| b.intra_burst_frequency()),
+        fraction_in_bursts: if total_spikes > 0 {
+            in_bursts as f64 / total_spikes as f64
+        } else {
+            0.0
+        },
+    }
+}
@@ -0,0 +1,39 @@
+//! cancellation.rs
+//!
+//! Cooperative cancellation of running simulations.
+//!
+//! A `CancellationToken` is a cheap, cloneable handle around a shared flag.
+//! One clone is attached to a `Simulation`; another can be kept by a GUI or
+//! server thread and triggered at any time. The simulation loop checks the
+//! flag once per step and stops cleanly, returning partial results.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared handle used to request that a running simulation stop.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new, non-cancelled token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation. All clones of this token observe the request.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// Clear a previous cancellation request so the token can be reused.
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,1294 @@
+//! compression.rs
+//!
+//! Gzip and Zstandard compression of output files.
+//!
+//! Spike logs of long runs are mostly the same few characters, such as the
+//! digits and separators of `812,10234.5`, repeated millions of times, and
+//! shrink to a small fraction of their size when compressed. A
+//! `CompressedWriter` compresses whatever is written to it as it goes, in
+//! the gzip format (RFC 1952), which every tool reads, or in Zstandard
+//! (RFC 8878), which decompresses faster. Both are read directly by
+//! `pandas.read_csv`.
+//!
+//! The path-based writers of the crate, such as `write_spikes_csv`,
+//! `write_spikes_binary`, `VoltageProbe::write_csv`, `SpikeRecorder` and
+//! the plots and model exports, open their file with `create` or `write`,
+//! which pick the compression from the file extension: `spikes.csv.gz` is
+//! written with gzip, `spikes.spk.zst` with Zstandard, anything else
+//! uncompressed. Only the container formats with their own layout, HDF5,
+//! NumPy and Parquet, are written uncompressed whatever their name.
+//!
+//! Input is compressed in blocks of `BLOCK_SIZE` bytes. Both encoders find
+//! repeated strings, also in the previous block, with the same LZ77 match
+//! finder. Gzip blocks code literals and matches with Huffman codes built
+//! for the block. Zstandard blocks Huffman-code the literals and code the
+//! matches with FSE tables, fitted to the block when that pays off. Files
+//! come out about the size of those of `gzip -6` and `zstd -3`. The crate
+//! does not read compressed files back: its readers, which open their file
+//! with `open`, reject a `.gz` or `.zst` path with an error asking for it to
+//! be decompressed first.
+
+use crate::util::{crc32_update, invalid_input};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Number of input bytes compressed at a time.
+pub const BLOCK_SIZE: usize = 128 * 1024;
+/// Longest distance of a deflate match.
+const DEFLATE_WINDOW: usize = 32 * 1024;
+/// Longest deflate match.
+const DEFLATE_MAX_MATCH: usize = 258;
+/// Zstandard window, 2^17 bytes: matches may reach through the previous
+/// block.
+const ZSTD_WINDOW_LOG: u32 = 17;
+
+/// Compression format of an output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Compression {
+    /// Uncompressed
+    #[default]
+    None,
+    /// Gzip, extension `.gz`
+    Gzip,
+    /// Zstandard, extension `.zst`
+    Zstd,
+}
+
+impl Compression {
+    /// Compression implied by the extension of `path`: `.gz` for gzip,
+    /// `.zst` for Zstandard, none otherwise.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// Compression called `name`: `none`, `gzip` or `zstd`, case
+    /// insensitively.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "gzip" | "gz" => Some(Self::Gzip),
+            "zstd" | "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// File extension of the format, without the dot.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+            Self::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Create the file at `path`, compressed as implied by its extension (see
+/// `Compression::from_path`).
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<CompressedWriter<BufWriter<File>>> {
+    let compression = Compression::from_path(&path);
+    CompressedWriter::new(BufWriter::new(File::create(path)?), compression)
+}
+
+/// Write `contents` to the file at `path`, like `std::fs::write`, but
+/// compressed as implied by its extension.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let mut writer = create(path)?;
+    writer.write_all(contents.as_ref())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Open the file at `path` for reading.
+///
+/// Fails with `InvalidInput` if its extension marks it as compressed,
+/// which the crate cannot decompress.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref();
+    if Compression::from_path(path) != Compression::None {
+        return Err(invalid_input(format!(
+            "{} is compressed; decompress it first",
+            path.display()
+        )));
+    }
+    File::open(path)
+}
+
+/// Writer compressing everything written to it into another writer.
+///
+/// The stream must be completed with `finish`, which reports any error.
+/// Dropping the writer finishes it too, but ignores errors. `flush`
+/// compresses the input so far and flushes it, at a small cost in size,
+/// so that a reader sees all of it.
+#[derive(Debug)]
+pub struct CompressedWriter<W: Write> {
+    inner: Option<W>,
+    format: Format,
+    /// Input not yet compressed, after up to a window of earlier input
+    buffer: Vec<u8>,
+    /// Start of the input not yet compressed in `buffer`
+    pending: usize,
+}
+
+/// Encoder state of a format.
+#[derive(Debug)]
+enum Format {
+    None,
+    Gzip {
+        bits: BitWriter,
+        crc: u32,
+        /// Input size modulo 2^32
+        size: u32,
+    },
+    Zstd {
+        hash: Xxh64,
+    },
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Start a stream in `compression` format, writing its header to
+    /// `inner`.
+    pub fn new(mut inner: W, compression: Compression) -> io::Result<Self> {
+        let format = match compression {
+            Compression::None => Format::None,
+            Compression::Gzip => {
+                // No file name or time, unknown operating system
+                inner.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255])?;
+                Format::Gzip {
+                    bits: BitWriter::default(),
+                    crc: 0,
+                    size: 0,
+                }
+            }
+            Compression::Zstd => {
+                inner.write_all(&0xFD2F_B528u32.to_le_bytes())?;
+                // Content checksum, no content size; the window descriptor
+                // holds the window log minus 10 in its high five bits
+                inner.write_all(&[0x04, ((ZSTD_WINDOW_LOG - 10) << 3) as u8])?;
+                Format::Zstd {
+                    hash: Xxh64::default(),
+                }
+            }
+        };
+        Ok(Self {
+            inner: Some(inner),
+            format,
+            buffer: Vec::new(),
+            pending: 0,
+        })
+    }
+
+    /// Complete the stream and return the underlying writer, flushed.
+    pub fn finish(mut self) -> io::Result<W> {
+        let result = self.finish_stream();
+        let inner = self
+            .inner
+            .take()
+            .expect("Compressed stream already finished");
+        result.map(|_| inner)
+    }
+
+    /// Compress the remaining input as the last block and write the
+    /// trailer.
+    fn finish_stream(&mut self) -> io::Result<()> {
+        self.compress_block(true)?;
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("Compressed stream already finished");
+        match &mut self.format {
+            Format::None => {}
+            Format::Gzip { bits, crc, size } => {
+                bits.align();
+                inner.write_all(&bits.take())?;
+                inner.write_all(&crc.to_le_bytes())?;
+                inner.write_all(&size.to_le_bytes())?;
+            }
+            Format::Zstd { hash } => {
+                inner.write_all(&(hash.digest() as u32).to_le_bytes())?;
+            }
+        }
+        inner.flush()
+    }
+
+    /// Compress the pending input as one block, keeping the end of the
+    /// input as history for the next one.
+    fn compress_block(&mut self, last: bool) -> io::Result<()> {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("Compressed stream already finished");
+        let block = &self.buffer[self.pending..];
+        let window = match &mut self.format {
+            Format::None => return Ok(()),
+            Format::Gzip { bits, crc, size } => {
+                *crc = crc32_update(*crc, block.iter());
+                *size = size.wrapping_add(block.len() as u32);
+                deflate_block(bits, &self.buffer, self.pending, last);
+                inner.write_all(&bits.take())?;
+                DEFLATE_WINDOW
+            }
+            Format::Zstd { hash } => {
+                hash.update(block);
+                inner.write_all(&zstd_block(&self.buffer, self.pending, last))?;
+                1 << ZSTD_WINDOW_LOG
+            }
+        };
+        self.buffer
+            .drain(..self.buffer.len().saturating_sub(window));
+        self.pending = self.buffer.len();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Format::None = self.format {
+            let inner = self
+                .inner
+                .as_mut()
+                .expect("Compressed stream already finished");
+            return inner.write(data);
+        }
+        let n = data
+            .len()
+            .min(BLOCK_SIZE - (self.buffer.len() - self.pending));
+        self.buffer.extend_from_slice(&data[..n]);
+        if self.buffer.len() - self.pending == BLOCK_SIZE {
+            self.compress_block(false)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.len() > self.pending {
+            self.compress_block(false)?;
+        }
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("Compressed stream already finished");
+        if let Format::Gzip { bits, .. } = &mut self.format {
+            if bits.count > 0 {
+                // Empty stored block, which ends on a byte boundary
+                bits.bits(0, 3);
+                bits.align();
+                bits.bytes.extend_from_slice(&[0, 0, 0xff, 0xff]);
+                inner.write_all(&bits.take())?;
+            }
+        }
+        inner.flush()
+    }
+}
+
+impl<W: Write> Drop for CompressedWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish_stream();
+        }
+    }
+}
+
+/// Bits packed into bytes least significant bit first, as in deflate and
+/// Zstandard.
+#[derive(Debug, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits not yet in `bytes`, in the low `count` bits
+    acc: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Append the low `n` bits of `value`, at most 32.
+    fn bits(&mut self, value: u64, n: u32) {
+        self.acc |= value << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Append a deflate Huffman code, which is packed starting from its
+    /// most significant bit.
+    fn huffman(&mut self, code: u16, length: u8) {
+        let reversed = code.reverse_bits() >> (16 - u32::from(length));
+        self.bits(u64::from(reversed), u32::from(length));
+    }
+
+    /// Pad with zeros to a byte boundary.
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.bits(0, 8 - self.count);
+        }
+    }
+
+    /// Complete bytes so far, leaving any partial byte.
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+}
+
+/// Repeat of `length` bytes at `position` from `distance` bytes back.
+#[derive(Debug, Clone, Copy)]
+struct Match {
+    position: usize,
+    length: usize,
+    distance: usize,
+}
+
+/// Hash chains of the positions of three-byte strings.
+struct MatchFinder {
+    /// Last position of each hash
+    head: Vec<u32>,
+    /// Previous position with the same hash, by position
+    prev: Vec<u32>,
+}
+
+impl MatchFinder {
+    const HASH_BITS: u32 = 15;
+    /// Candidates compared before settling for the longest so far
+    const MAX_CHAIN: usize = 32;
+    const MIN_MATCH: usize = 3;
+    const NONE: u32 = u32::MAX;
+
+    fn new(len: usize) -> Self {
+        Self {
+            head: vec![Self::NONE; 1 << Self::HASH_BITS],
+            prev: vec![Self::NONE; len],
+        }
+    }
+
+    fn hash(data: &[u8], i: usize) -> usize {
+        let bytes = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], 0]);
+        (bytes.wrapping_mul(0x9E37_79B1) >> (32 - Self::HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + Self::MIN_MATCH <= data.len() {
+            let h = Self::hash(data, i);
+            self.prev[i] = self.head[h];
+            self.head[h] = i as u32;
+        }
+    }
+
+    /// Longest earlier match for the bytes at `i`, as `(length, distance)`.
+    fn longest(
+        &self,
+        data: &[u8],
+        i: usize,
+        window: usize,
+        max_length: usize,
+    ) -> Option<(usize, usize)> {
+        if i + Self::MIN_MATCH > data.len() {
+            return None;
+        }
+        let max_length = max_length.min(data.len() - i);
+        let mut best = (0, 0);
+        let mut candidate = self.head[Self::hash(data, i)];
+        for _ in 0..Self::MAX_CHAIN {
+            if candidate == Self::NONE || i - candidate as usize > window {
+                break;
+            }
+            let c = candidate as usize;
+            if data[c + best.0] == data[i + best.0] {
+                let length = common_prefix(&data[c..c + max_length], &data[i..i + max_length]);
+                if length > best.0 {
+                    best = (length, i - c);
+                    if length == max_length {
+                        break;
+                    }
+                }
+            }
+            candidate = self.prev[c];
+        }
+        (best.0 >= Self::MIN_MATCH).then_some(best)
+    }
+}
+
+/// Length of the common prefix of two equally long slices, compared eight
+/// bytes at a time.
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    let mut length = 0;
+    for (x, y) in a.chunks_exact(8).zip(b.chunks_exact(8)) {
+        let x = u64::from_le_bytes(x.try_into().unwrap());
+        let y = u64::from_le_bytes(y.try_into().unwrap());
+        if x != y {
+            return length + ((x ^ y).trailing_zeros() / 8) as usize;
+        }
+        length += 8;
+    }
+    length
+        + a[length..]
+            .iter()
+            .zip(&b[length..])
+            .take_while(|(x, y)| x == y)
+            .count()
+}
+
+/// Matches for the bytes of `data` from `start` on, where the bytes before
+/// `start` are earlier input that matches may refer to.
+///
+/// Matches are taken greedily, except that a match is dropped for a longer
+/// one starting at the next byte.
+fn find_matches(data: &[u8], start: usize, window: usize, max_length: usize) -> Vec<Match> {
+    /// Matches at least this long are taken without looking further
+    const GOOD_LENGTH: usize = 32;
+
+    let mut finder = MatchFinder::new(data.len());
+    for i in start.saturating_sub(window)..start {
+        finder.insert(data, i);
+    }
+    let mut matches = Vec::new();
+    let mut next = None;
+    let mut i = start;
+    while i < data.len() {
+        let found = next
+            .take()
+            .or_else(|| finder.longest(data, i, window, max_length));
+        finder.insert(data, i);
+        let Some((length, distance)) = found else {
+            i += 1;
+            continue;
+        };
+        if length < GOOD_LENGTH {
+            let later = finder.longest(data, i + 1, window, max_length);
+            if later.is_some_and(|(l, _)| l > length) {
+                next = later;
+                i += 1;
+                continue;
+            }
+        }
+        matches.push(Match {
+            position: i,
+            length,
+            distance,
+        });
+        for j in i + 1..i + length {
+            finder.insert(data, j);
+        }
+        i += length;
+    }
+    matches
+}
+
+/// Code lengths of a Huffman code for symbols with frequencies `freqs`,
+/// none longer than `limit`. Unused symbols get length 0.
+fn huffman_lengths(freqs: &[u32], limit: usize) -> Vec<u8> {
+    let mut weights: Vec<u64> = freqs.iter().map(|&f| u64::from(f)).collect();
+    loop {
+        let depths = tree_depths(&weights);
+        if depths.iter().all(|&d| d <= limit) {
+            return depths.into_iter().map(|d| d as u8).collect();
+        }
+        // Flatten the distribution until the tree is shallow enough
+        for w in weights.iter_mut().filter(|w| **w > 0) {
+            *w = w.div_ceil(2);
+        }
+    }
+}
+
+/// Depth of each symbol in a Huffman tree for `weights`.
+fn tree_depths(weights: &[u64]) -> Vec<usize> {
+    let mut depths = vec![0; weights.len()];
+    let leaves: Vec<usize> = (0..weights.len()).filter(|&s| weights[s] > 0).collect();
+    if leaves.len() == 1 {
+        depths[leaves[0]] = 1;
+    }
+    if leaves.len() < 2 {
+        return depths;
+    }
+    let mut parent = vec![0; 2 * leaves.len() - 1];
+    let mut heap: BinaryHeap<_> = leaves
+        .iter()
+        .enumerate()
+        .map(|(node, &s)| Reverse((weights[s], node)))
+        .collect();
+    let mut next = leaves.len();
+    while let (Some(Reverse((w1, a))), Some(Reverse((w2, b)))) = (heap.pop(), heap.pop()) {
+        parent[a] = next;
+        parent[b] = next;
+        heap.push(Reverse((w1 + w2, next)));
+        next += 1;
+    }
+    // Parents are created after their children, so walk down from the root
+    let mut node_depth = vec![0; parent.len()];
+    for node in (0..parent.len() - 1).rev() {
+        node_depth[node] = node_depth[parent[node]] + 1;
+    }
+    for (node, &s) in leaves.iter().enumerate() {
+        depths[s] = node_depth[node];
+    }
+    depths
+}
+
+/// Make sure at least two symbols have a nonzero frequency, so that the
+/// code is complete, which some decoders require.
+fn ensure_two_symbols(freqs: &mut [u32]) {
+    let mut s = 0;
+    while freqs.iter().filter(|&&f| f > 0).count() < 2 {
+        freqs[s] = freqs[s].max(1);
+        s += 1;
+    }
+}
+
+/// Deflate's canonical codes for code `lengths`: shorter codes first, and
+/// codes of the same length in symbol order.
+fn deflate_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut count = [0u16; 16];
+    for &l in lengths.iter().filter(|&&l| l > 0) {
+        count[l as usize] += 1;
+    }
+    let mut next = [0u16; 16];
+    let mut code = 0;
+    for bits in 1..16 {
+        code = (code + count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&l| {
+            let code = next[l as usize];
+            next[l as usize] += 1;
+            code
+        })
+        .collect()
+}
+
+/// Deflate length codes 257.. as base length and extra bits.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Deflate distance codes as base distance and extra bits.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which the lengths of the code length code are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Index of the last entry of a base table not above `value`.
+fn code_of<T: Copy + Into<u64>>(bases: &[T], value: usize) -> usize {
+    bases.partition_point(|&b| b.into() <= value as u64) - 1
+}
+
+/// Write the bytes of `data` from `start` on as one deflate block with
+/// dynamic Huffman codes.
+fn deflate_block(bits: &mut BitWriter, data: &[u8], start: usize, last: bool) {
+    let matches = find_matches(data, start, DEFLATE_WINDOW, DEFLATE_MAX_MATCH);
+
+    let mut literal_freqs = [0u32; 286];
+    let mut distance_freqs = [0u32; 30];
+    let mut position = start;
+    for m in &matches {
+        for &b in &data[position..m.position] {
+            literal_freqs[b as usize] += 1;
+        }
+        literal_freqs[257 + code_of(&LENGTH_BASE, m.length)] += 1;
+        distance_freqs[code_of(&DISTANCE_BASE, m.distance)] += 1;
+        position = m.position + m.length;
+    }
+    for &b in &data[position..] {
+        literal_freqs[b as usize] += 1;
+    }
+    literal_freqs[256] = 1;
+    ensure_two_symbols(&mut literal_freqs);
+    ensure_two_symbols(&mut distance_freqs);
+    let literal_lengths = huffman_lengths(&literal_freqs, 15);
+    let distance_lengths = huffman_lengths(&distance_freqs, 15);
+    let literal_codes = deflate_codes(&literal_lengths);
+    let distance_codes = deflate_codes(&distance_lengths);
+
+    // Both sets of code lengths, run-length coded with the code length
+    // alphabet: 0-15 a length, 16 the previous length 3-6 times, 17 and 18
+    // a run of 3-10 and 11-138 zeros
+    let num_literals = 1 + literal_lengths.iter().rposition(|&l| l > 0).unwrap_or(0);
+    let num_distances = 1 + distance_lengths.iter().rposition(|&l| l > 0).unwrap_or(0);
+    let lengths: Vec<u8> = literal_lengths[..num_literals]
+        .iter()
+        .chain(&distance_lengths[..num_distances])
+        .copied()
+        .collect();
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == length).count();
+        if length == 0 && run >= 3 {
+            let n = run.min(138);
+            runs.push(if n >= 11 {
+                (18, (n - 11) as u8)
+            } else {
+                (17, (n - 3) as u8)
+            });
+            i += n;
+        } else if length > 0 && run >= 4 {
+            runs.push((length, 0));
+            let mut repeats = run - 1;
+            while repeats >= 3 {
+                let n = repeats.min(6);
+                runs.push((16, (n - 3) as u8));
+                repeats -= n;
+            }
+            i += run - repeats;
+        } else {
+            runs.push((length, 0));
+            i += 1;
+        }
+    }
+    let mut code_length_freqs = [0u32; 19];
+    for &(symbol, _) in &runs {
+        code_length_freqs[symbol as usize] += 1;
+    }
+    ensure_two_symbols(&mut code_length_freqs);
+    let code_length_lengths = huffman_lengths(&code_length_freqs, 7);
+    let code_length_codes = deflate_codes(&code_length_lengths);
+    let num_code_lengths = CODE_LENGTH_ORDER
+        .iter()
+        .rposition(|&s| code_length_lengths[s] > 0)
+        .map_or(4, |k| (k + 1).max(4));
+
+    bits.bits(u64::from(last), 1);
+    bits.bits(2, 2);
+    bits.bits(num_literals as u64 - 257, 5);
+    bits.bits(num_distances as u64 - 1, 5);
+    bits.bits(num_code_lengths as u64 - 4, 4);
+    for &s in &CODE_LENGTH_ORDER[..num_code_lengths] {
+        bits.bits(u64::from(code_length_lengths[s]), 3);
+    }
+    for &(symbol, extra) in &runs {
+        let s = symbol as usize;
+        bits.huffman(code_length_codes[s], code_length_lengths[s]);
+        match symbol {
+            16 => bits.bits(u64::from(extra), 2),
+            17 => bits.bits(u64::from(extra), 3),
+            18 => bits.bits(u64::from(extra), 7),
+            _ => {}
+        }
+    }
+
+    let mut position = start;
+    let literals = |bits: &mut BitWriter, bytes: &[u8]| {
+        for &b in bytes {
+            bits.huffman(literal_codes[b as usize], literal_lengths[b as usize]);
+        }
+    };
+    for m in &matches {
+        literals(bits, &data[position..m.position]);
+        let code = code_of(&LENGTH_BASE, m.length);
+        bits.huffman(literal_codes[257 + code], literal_lengths[257 + code]);
+        bits.bits(
+            (m.length - usize::from(LENGTH_BASE[code])) as u64,
+            u32::from(LENGTH_EXTRA[code]),
+        );
+        let code = code_of(&DISTANCE_BASE, m.distance);
+        bits.huffman(distance_codes[code], distance_lengths[code]);
+        bits.bits(
+            (m.distance - usize::from(DISTANCE_BASE[code])) as u64,
+            u32::from(DISTANCE_EXTRA[code]),
+        );
+        position = m.position + m.length;
+    }
+    literals(bits, &data[position..]);
+    bits.huffman(literal_codes[256], literal_lengths[256]);
+}
+
+/// Zstandard literal length codes as baseline and extra bits.
+const LITERAL_LENGTH_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LITERAL_LENGTH_EXTRA: [u32; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+/// Zstandard match length codes as baseline and extra bits.
+const MATCH_LENGTH_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const MATCH_LENGTH_EXTRA: [u32; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+/// Predefined FSE distributions of the Zstandard sequence codes, where -1
+/// is a probability below 1.
+const LITERAL_LENGTH_DISTRIBUTION: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const MATCH_LENGTH_DISTRIBUTION: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OFFSET_DISTRIBUTION: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Write the bytes of `data` from `start` on as one Zstandard block,
+/// compressed unless that would make it larger.
+fn zstd_block(data: &[u8], start: usize, last: bool) -> Vec<u8> {
+    let block = &data[start..];
+    let matches = find_matches(data, start, 1 << ZSTD_WINDOW_LOG, BLOCK_SIZE);
+    let mut literals = Vec::new();
+    let mut sequences = Vec::with_capacity(matches.len());
+    let mut position = start;
+    for m in &matches {
+        literals.extend_from_slice(&data[position..m.position]);
+        sequences.push((m.position - position, m.distance, m.length));
+        position = m.position + m.length;
+    }
+    literals.extend_from_slice(&data[position..]);
+
+    let mut body = zstd_literals(&literals);
+    zstd_sequences(&sequences, &mut body);
+    // Block header: last-block flag, type (raw 0, compressed 2) and size
+    let (kind, content) = if body.len() < block.len() {
+        (2, body.as_slice())
+    } else {
+        (0, block)
+    };
+    let header = u32::from(last) | kind << 1 | (content.len() as u32) << 3;
+    let mut out = header.to_le_bytes()[..3].to_vec();
+    out.extend_from_slice(content);
+    out
+}
+
+/// Zstandard literals section for `literals`.
+fn zstd_literals(literals: &[u8]) -> Vec<u8> {
+    let n = literals.len();
+    // Header of a raw (type 0) or run-length (type 1) section
+    let header = |kind: u32| -> Vec<u8> {
+        let size = n as u32;
+        match n {
+            0..=31 => vec![(kind | size << 3) as u8],
+            32..=4095 => (kind | 1 << 2 | size << 4).to_le_bytes()[..2].to_vec(),
+            _ => (kind | 3 << 2 | size << 4).to_le_bytes()[..3].to_vec(),
+        }
+    };
+    let raw = || [header(0), literals.to_vec()].concat();
+
+    let mut freqs = [0u32; 256];
+    for &b in literals {
+        freqs[b as usize] += 1;
+    }
+    let used = freqs.iter().filter(|&&f| f > 0).count();
+    if used == 1 {
+        return [header(1), vec![literals[0]]].concat();
+    }
+    // Weights are only stored directly for up to 128 symbols before the
+    // last one
+    let max_symbol = freqs.iter().rposition(|&f| f > 0).unwrap_or(0);
+    if used == 0 || max_symbol > 128 || n < 64 {
+        return raw();
+    }
+
+    let lengths = huffman_lengths(&freqs[..=max_symbol], 11);
+    let max_bits = lengths.iter().copied().max().unwrap_or(0);
+    let weights: Vec<u8> = lengths
+        .iter()
+        .map(|&l| if l == 0 { 0 } else { max_bits + 1 - l })
+        .collect();
+    // Codes are assigned from the longest, in symbol order within a length
+    let mut count = [0u32; 12];
+    for &l in &lengths {
+        count[l as usize] += 1;
+    }
+    let mut next = [0u32; 12];
+    for bits in (1..max_bits as usize).rev() {
+        next[bits] = (next[bits + 1] + count[bits + 1]) >> 1;
+    }
+    let codes: Vec<u32> = lengths
+        .iter()
+        .map(|&l| {
+            let code = next[l as usize];
+            next[l as usize] += 1;
+            code
+        })
+        .collect();
+
+    // Tree description: the weights of all but the last symbol, which is
+    // implied, four bits each
+    let mut tree = vec![127 + max_symbol as u8];
+    for pair in weights[..max_symbol].chunks(2) {
+        tree.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+    // Streams are read backwards, so the literals are written in reverse
+    let stream = |part: &[u8]| {
+        let mut bits = BitWriter::default();
+        for &b in part.iter().rev() {
+            bits.bits(u64::from(codes[b as usize]), u32::from(lengths[b as usize]));
+        }
+        bits.bits(1, 1);
+        bits.align();
+        bits.bytes
+    };
+    let single = n <= 1023;
+    let mut compressed = tree;
+    if single {
+        compressed.extend(stream(literals));
+    } else {
+        let segment = n.div_ceil(4);
+        let streams: Vec<Vec<u8>> = literals.chunks(segment).map(stream).collect();
+        for s in &streams[..3] {
+            compressed.extend_from_slice(&(s.len() as u16).to_le_bytes());
+        }
+        streams.iter().for_each(|s| compressed.extend_from_slice(s));
+    }
+    let size = compressed.len();
+    if size >= n {
+        return raw();
+    }
+    // Compressed (type 2) section header: size format, then the literal
+    // and compressed sizes with 10, 14 or 18 bits each
+    let (format, size_bits, header_len) = match n.max(size) {
+        _ if single => (0, 10, 3),
+        0..=1023 => (1, 10, 3),
+        1024..=16383 => (2, 14, 4),
+        _ => (3, 18, 5),
+    };
+    let header = 2 | format << 2 | (n as u64) << 4 | (size as u64) << (4 + size_bits);
+    [header.to_le_bytes()[..header_len].to_vec(), compressed].concat()
+}
+
+/// Append the Zstandard sequences section for `(literal length, offset,
+/// match length)` sequences to `out`.
+fn zstd_sequences(sequences: &[(usize, usize, usize)], out: &mut Vec<u8>) {
+    let n = sequences.len();
+    match n {
+        0..=127 => out.push(n as u8),
+        128..=0x7EFF => out.extend_from_slice(&[(n >> 8) as u8 + 128, n as u8]),
+        _ => {
+            out.push(0xFF);
+            out.extend_from_slice(&((n - 0x7F00) as u16).to_le_bytes());
+        }
+    }
+    if n == 0 {
+        return;
+    }
+    // Code and extra bits of each field
+    let codes: Vec<[(u8, u64, u32); 3]> = sequences
+        .iter()
+        .map(|&(literal_length, offset, match_length)| {
+            let ll = code_of(&LITERAL_LENGTH_BASE, literal_length);
+            let ml = code_of(&MATCH_LENGTH_BASE, match_length);
+            // Offset values 1-3 repeat earlier offsets
+            let value = offset as u64 + 3;
+            let of = 63 - value.leading_zeros();
+            [
+                (
+                    ll as u8,
+                    (literal_length as u32 - LITERAL_LENGTH_BASE[ll]).into(),
+                    LITERAL_LENGTH_EXTRA[ll],
+                ),
+                (
+                    ml as u8,
+                    (match_length as u32 - MATCH_LENGTH_BASE[ml]).into(),
+                    MATCH_LENGTH_EXTRA[ml],
+                ),
+                (of as u8, value - (1 << of), of),
+            ]
+        })
+        .collect();
+
+    // Tables in the order literal length, offset, match length, each
+    // predefined, a single repeated code or described before the bitstream
+    let mut descriptions = Vec::new();
+    let (literal_mode, literal_table) = sequence_table(
+        codes.iter().map(|c| c[0].0),
+        &LITERAL_LENGTH_DISTRIBUTION,
+        6,
+        9,
+        &mut descriptions,
+    );
+    let (offset_mode, offset_table) = sequence_table(
+        codes.iter().map(|c| c[2].0),
+        &OFFSET_DISTRIBUTION,
+        5,
+        8,
+        &mut descriptions,
+    );
+    let (match_mode, match_table) = sequence_table(
+        codes.iter().map(|c| c[1].0),
+        &MATCH_LENGTH_DISTRIBUTION,
+        6,
+        9,
+        &mut descriptions,
+    );
+    out.push(literal_mode << 6 | offset_mode << 4 | match_mode << 2);
+    out.extend(descriptions);
+
+    // The decoder reads the initial states, then for each sequence the
+    // offset, match and literal length extra bits, followed by the
+    // literal length, match and offset state updates. The stream is read
+    // backwards, so everything is written in the opposite order, and the
+    // states are chosen from the last sequence back.
+    let mut bits = BitWriter::default();
+    let [ll, ml, of] = codes[n - 1];
+    let mut states = [
+        literal_table.first_state(ll.0),
+        match_table.first_state(ml.0),
+        offset_table.first_state(of.0),
+    ];
+    for (k, [ll, ml, of]) in codes.iter().enumerate().rev() {
+        if k + 1 < n {
+            let (offset_state, value, count) = offset_table.previous(of.0, states[2]);
+            bits.bits(value, count);
+            let (match_state, value, count) = match_table.previous(ml.0, states[1]);
+            bits.bits(value, count);
+            let (literal_state, value, count) = literal_table.previous(ll.0, states[0]);
+            bits.bits(value, count);
+            states = [literal_state, match_state, offset_state];
+        }
+        bits.bits(ll.1, ll.2);
+        bits.bits(ml.1, ml.2);
+        bits.bits(of.1, of.2);
+    }
+    bits.bits(states[1] as u64, match_table.log);
+    bits.bits(states[2] as u64, offset_table.log);
+    bits.bits(states[0] as u64, literal_table.log);
+    bits.bits(1, 1);
+    bits.align();
+    out.extend(bits.bytes);
+}
+
+/// Compression mode of a sequence field for the codes `symbols`, with
+/// its table. Any table description is appended to `description`.
+///
+/// A single code is coded in RLE mode (1), otherwise a table fitted to the
+/// block (mode 2) is used if it and its description take fewer bits than
+/// the `predefined` table (mode 0) of `predefined_log`.
+fn sequence_table(
+    symbols: impl Iterator<Item = u8>,
+    predefined: &[i16],
+    predefined_log: u32,
+    max_log: u32,
+    description: &mut Vec<u8>,
+) -> (u8, FseTable) {
+    let mut counts = vec![0u32; predefined.len()];
+    for s in symbols {
+        counts[s as usize] += 1;
+    }
+    let used = counts.iter().filter(|&&c| c > 0).count();
+    if used == 1 {
+        let symbol = counts.iter().position(|&c| c > 0).unwrap_or(0);
+        let mut distribution = vec![0; symbol + 1];
+        distribution[symbol] = 1;
+        description.push(symbol as u8);
+        return (1, FseTable::new(&distribution, 0));
+    }
+
+    // Cost in bits of coding the counts with a distribution
+    let cost = |distribution: &[i16], log: u32| -> f64 {
+        counts
+            .iter()
+            .zip(distribution)
+            .filter(|(&c, _)| c > 0)
+            .map(|(&c, &p)| f64::from(c) * (f64::from(log) - f64::from(p.max(1)).log2()))
+            .sum()
+    };
+    let total: u32 = counts.iter().sum();
+    let log = (u32::BITS - total.leading_zeros())
+        .max(u32::BITS - (used as u32).leading_zeros() + 1)
+        .clamp(5, max_log);
+    let distribution = normalize(&counts, log);
+    let header = fse_description(&distribution, log);
+    let custom = cost(&distribution, log) + 8.0 * header.len() as f64;
+    if custom < cost(predefined, predefined_log) {
+        description.extend(header);
+        (2, FseTable::new(&distribution, log))
+    } else {
+        (0, FseTable::new(predefined, predefined_log))
+    }
+}
+
+/// Scale `counts` to a distribution summing to `1 << log`, keeping every
+/// used symbol at least 1.
+fn normalize(counts: &[u32], log: u32) -> Vec<i16> {
+    let size = 1i64 << log;
+    let total: u64 = counts.iter().map(|&c| u64::from(c)).sum();
+    let mut distribution: Vec<i16> = counts
+        .iter()
+        .map(|&c| match c {
+            0 => 0,
+            _ => ((u64::from(c) * size as u64 + total / 2) / total).max(1) as i16,
+        })
+        .collect();
+    let mut sum: i64 = distribution.iter().map(|&p| i64::from(p)).sum();
+    // Rounding errors go to the most probable symbol
+    while sum != size {
+        let (largest, _) = distribution
+            .iter()
+            .enumerate()
+            .max_by_key(|&(s, &p)| (p, Reverse(s)))
+            .expect("Distribution of at least two symbols");
+        let step = if sum > size { -1 } else { 1 };
+        distribution[largest] += step as i16;
+        sum += step;
+    }
+    distribution
+}
+
+/// FSE table description of `distribution`: the accuracy log, then each
+/// probability plus one in as few bits as the remaining total allows, with
+/// runs of zero probabilities coded in two-bit repeat flags.
+fn fse_description(distribution: &[i16], log: u32) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    bits.bits(u64::from(log - 5), 4);
+    let mut remaining = (1i32 << log) + 1;
+    let mut threshold = 1i32 << log;
+    let mut count_bits = log + 1;
+    let mut previous_zero = false;
+    let mut symbol = 0;
+    while remaining > 1 {
+        if previous_zero {
+            let mut start = symbol;
+            while distribution[symbol] == 0 {
+                symbol += 1;
+            }
+            while symbol >= start + 24 {
+                start += 24;
+                bits.bits(0xFFFF, 16);
+            }
+            while symbol >= start + 3 {
+                start += 3;
+                bits.bits(3, 2);
+            }
+            bits.bits((symbol - start) as u64, 2);
+        }
+        let p = i32::from(distribution[symbol]);
+        symbol += 1;
+        let max = 2 * threshold - 1 - remaining;
+        remaining -= p.abs();
+        let mut value = p + 1;
+        if value >= threshold {
+            value += max;
+        }
+        // Values below `max` need one bit less
+        bits.bits(value as u64, count_bits - u32::from(value < max));
+        previous_zero = value == 1;
+        while remaining < threshold {
+            count_bits -= 1;
+            threshold >>= 1;
+        }
+    }
+    bits.align();
+    bits.bytes
+}
+
+/// FSE decoding table, as built by a Zstandard decoder, with the reverse
+/// mapping needed to encode.
+struct FseTable {
+    log: u32,
+    /// Symbol, number of bits read and baseline of the next state, by state
+    states: Vec<(u8, u32, usize)>,
+    /// State decoding a symbol from which the decoder moves to a state, by
+    /// `symbol << log | next state`
+    previous: Vec<u16>,
+}
+
+impl FseTable {
+    /// Table for a `distribution` summing to `1 << log`, where -1 is a
+    /// probability below 1.
+    fn new(distribution: &[i16], log: u32) -> Self {
+        let size = 1usize << log;
+        let mut symbols = vec![0u8; size];
+        // Symbols with a probability below 1 take one cell each at the end
+        let mut high = size - 1;
+        for (s, &p) in distribution.iter().enumerate() {
+            if p == -1 {
+                symbols[high] = s as u8;
+                high -= 1;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut position = 0;
+        for (s, &p) in distribution.iter().enumerate() {
+            for _ in 0..p.max(0) {
+                symbols[position] = s as u8;
+                loop {
+                    position = (position + step) & (size - 1);
+                    if position <= high {
+                        break;
+                    }
+                }
+            }
+        }
+        let mut next: Vec<usize> = distribution.iter().map(|&p| p.max(1) as usize).collect();
+        let states: Vec<(u8, u32, usize)> = symbols
+            .iter()
+            .map(|&s| {
+                let x = next[s as usize];
+                next[s as usize] += 1;
+                let bits = log - (usize::BITS - 1 - x.leading_zeros());
+                (s, bits, (x << bits) - size)
+            })
+            .collect();
+        // The next states of the cells of a symbol cover the table once
+        let mut previous = vec![0u16; distribution.len() << log];
+        for (state, &(s, bits, baseline)) in states.iter().enumerate() {
+            let first = (s as usize) << log | baseline;
+            previous[first..first + (1 << bits)].fill(state as u16);
+        }
+        Self {
+            log,
+            states,
+            previous,
+        }
+    }
+
+    /// Any state decoding `symbol`.
+    fn first_state(&self, symbol: u8) -> usize {
+        self.states
+            .iter()
+            .position(|&(s, _, _)| s == symbol)
+            .expect("Symbol outside the FSE table")
+    }
+
+    /// The state decoding `symbol` from which the decoder moves to `next`,
+    /// with the bits it reads to do so and their number.
+    fn previous(&self, symbol: u8, next: usize) -> (usize, u64, u32) {
+        let state = self.previous[(symbol as usize) << self.log | next] as usize;
+        let (_, bits, baseline) = self.states[state];
+        (state, (next - baseline) as u64, bits)
+    }
+}
+
+/// Streaming XXH64 hash with seed 0, the Zstandard content checksum.
+#[derive(Debug)]
+struct Xxh64 {
+    lanes: [u64; 4],
+    /// Input not yet hashed, less than one 32-byte stripe
+    buffer: Vec<u8>,
+    total: u64,
+}
+
+const PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME64_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME64_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME64_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+impl Default for Xxh64 {
+    fn default() -> Self {
+        Self {
+            lanes: [
+                PRIME64_1.wrapping_add(PRIME64_2),
+                PRIME64_2,
+                0,
+                0u64.wrapping_sub(PRIME64_1),
+            ],
+            buffer: Vec::with_capacity(32),
+            total: 0,
+        }
+    }
+}
+
+impl Xxh64 {
+    fn round(acc: u64, input: u64) -> u64 {
+        acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME64_1)
+    }
+
+    fn read_u64(bytes: &[u8]) -> u64 {
+        u64::from_le_bytes(bytes[..8].try_into().unwrap())
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total += data.len() as u64;
+        if !self.buffer.is_empty() {
+            let n = data.len().min(32 - self.buffer.len());
+            self.buffer.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.buffer.len() < 32 {
+                return;
+            }
+            let stripe = std::mem::take(&mut self.buffer);
+            self.stripe(&stripe);
+        }
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        self.buffer.extend_from_slice(stripes.remainder());
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (lane, bytes) in self.lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+            *lane = Self::round(*lane, Self::read_u64(bytes));
+        }
+    }
+
+    fn digest(&self) -> u64 {
+        let mut h = if self.total >= 32 {
+            let [v1, v2, v3, v4] = self.lanes;
+            let mut h = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for v in self.lanes {
+                h = (h ^ Self::round(0, v))
+                    .wrapping_mul(PRIME64_1)
+                    .wrapping_add(PRIME64_4);
+            }
+            h
+        } else {
+            PRIME64_5
+        };
+        h = h.wrapping_add(self.total);
+        let mut rest = self.buffer.as_slice();
+        while rest.len() >= 8 {
+            h ^= Self::round(0, Self::read_u64(rest));
+            h = h
+                .rotate_left(27)
+                .wrapping_mul(PRIME64_1)
+                .wrapping_add(PRIME64_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let word = u32::from_le_bytes(rest[..4].try_into().unwrap());
+            h ^= u64::from(word).wrapping_mul(PRIME64_1);
+            h = h
+                .rotate_left(23)
+                .wrapping_mul(PRIME64_2)
+                .wrapping_add(PRIME64_3);
+            rest = &rest[4..];
+        }
+        for &b in rest {
+            h ^= u64::from(b).wrapping_mul(PRIME64_5);
+            h = h.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+        h ^= h >> 33;
+        h = h.wrapping_mul(PRIME64_2);
+        h ^= h >> 29;
+        h = h.wrapping_mul(PRIME64_3);
+        h ^ h >> 32
+    }
+}
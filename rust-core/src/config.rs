@@ -0,0 +1,772 @@
+//! config.rs
+//!
+//! Experiment configuration files.
+//!
+//! An experiment written in Rust has to be recompiled for every parameter
+//! change and is awkward to share. An `ExperimentConfig` holds everything
+//! needed to set up a run — neuron and STDP parameters, simulation
+//! settings, populations, projections and stimuli — and is loaded from a
+//! TOML file such as
+//!
+//! ```toml
+//! seed = 42
+//!
+//! [simulation]
+//! dt_ms = 0.1
+//! t_max_ms = 1000.0
+//! synaptic_transmission = true
+//!
+//! [neuron]
+//! tau_m_ms = 20.0
+//! v_rest = 0.0
+//! v_thresh = 1.0
+//! v_reset = 0.0
+//!
+//! [stdp]
+//! a_plus = 0.01
+//! a_minus = 0.012
+//! tau_plus_ms = 20.0
+//! tau_minus_ms = 20.0
+//! w_min = 0.0
+//! w_max = 1.0
+//!
+//! [[population]]
+//! name = "input"
+//! size = 100
+//!
+//! [[population]]
+//! name = "output"
+//! size = 10
+//! tau_m_ms = 10.0          # overrides [neuron]
+//!
+//! [[projection]]
+//! pre = "input"
+//! post = "output"
+//! connector = "all_to_all" # or "one_to_one", or "list" with `pairs`
+//! weight_min = 0.1         # uniform weights; or a single `weight`
+//! weight_max = 0.5
+//! delay_ms = 1.0           # or `delay_min_ms` and `delay_max_ms`
+//! plastic = true           # learn with the [stdp] parameters
+//!
+//! [[stimulus]]
+//! kind = "poisson"         # or "current" with `amplitude`
+//! population = "input"
+//! rate_hz = 20.0
+//! start_ms = 0.0
+//! duration_ms = 500.0
+//! ```
+//!
+//! `[simulation]` keys default to `SimulationConfig::default()`; `seed`
+//! defaults to zero and seeds both the network builder and the Poisson
+//! stimuli. Only the TOML subset shown is understood: tables, arrays of
+//! tables, and keys holding numbers, booleans, strings or arrays. Unknown
+//! keys and tables are errors, so a typo cannot silently fall back to a
+//! default.
+
+use crate::encoding::PoissonEncoder;
+use crate::network::{Network, NetworkBuilder};
+use crate::neuron::NeuronParams;
+use crate::population::{Connector, DelayDistribution, Population, Projection};
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::stdp::STDPParams;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use crate::weights::WeightInit;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// External drive applied to a population.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stimulus {
+    /// Independent Poisson spike trains injected into every neuron.
+    Poisson {
+        /// Target population
+        population: String,
+        /// Firing rate of each train (Hz)
+        rate_hz: f64,
+        /// Start of the trains
+        start: Milliseconds,
+        /// Length of the trains
+        duration: Milliseconds,
+    },
+    /// Constant input current to every neuron.
+    Current {
+        /// Target population
+        population: String,
+        /// Input current
+        amplitude: f64,
+        /// Time the current switches on
+        start: Milliseconds,
+        /// Time the current stays on
+        duration: Milliseconds,
+    },
+}
+
+impl Stimulus {
+    /// Name of the driven population.
+    pub fn population(&self) -> &str {
+        match self {
+            Stimulus::Poisson { population, .. } | Stimulus::Current { population, .. } => {
+                population
+            }
+        }
+    }
+}
+
+/// Complete description of an experiment.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExperimentConfig {
+    /// Seed of the network builder and the Poisson stimuli
+    pub seed: u64,
+    /// Simulation settings
+    pub simulation: SimulationConfig,
+    /// Default neuron parameters of the populations
+    pub neuron: NeuronParams,
+    /// STDP parameters of plastic projections
+    pub stdp: STDPParams,
+    /// Populations in neuron id order
+    pub populations: Vec<Population>,
+    /// Projections between the populations
+    pub projections: Vec<Projection>,
+    /// External drive
+    pub stimuli: Vec<Stimulus>,
+}
+
+impl ExperimentConfig {
+    /// Parse a TOML configuration.
+    ///
+    /// Returns an `InvalidData` error for syntax errors, missing, unknown
+    /// or mistyped keys, and references to undefined populations.
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let mut doc = Document::parse(text)?;
+
+        let seed = doc.root.usize("seed")?.unwrap_or(0) as u64;
+        doc.root.finish()?;
+
+        let mut simulation = SimulationConfig::default();
+        if let Some(mut s) = doc.table("simulation")? {
+            if let Some(dt) = s.number("dt_ms")? {
+                simulation.dt = Milliseconds(dt);
+            }
+            if let Some(t_max) = s.number("t_max_ms")? {
+                simulation.t_max = Milliseconds(t_max);
+            }
+            if let Some(partitions) = s.usize("num_partitions")? {
+                simulation.num_partitions = partitions;
+            }
+            if let Some(factor) = s.number("realtime_factor")? {
+                simulation.realtime_factor = Some(factor);
+            }
+            if let Some(warmup) = s.number("warmup_ms")? {
+                simulation.warmup = Milliseconds(warmup);
+            }
+            if let Some(b) = s.bool("plasticity_during_warmup")? {
+                simulation.plasticity_during_warmup = b;
+            }
+            if let Some(b) = s.bool("synaptic_transmission")? {
+                simulation.synaptic_transmission = b;
+            }
+            s.finish()?;
+        }
+        if simulation.dt.0.is_nan() || simulation.dt.0 <= 0.0 {
+            return Err(invalid("`dt_ms` must be positive".to_string()));
+        }
+
+        let mut n = doc
+            .table("neuron")?
+            .ok_or_else(|| invalid("Missing table `[neuron]`".to_string()))?;
+        let neuron = NeuronParams {
+            tau_m: Milliseconds(n.required_number("tau_m_ms")?),
+            v_rest: n.required_number("v_rest")?,
+            v_thresh: n.required_number("v_thresh")?,
+            v_reset: n.required_number("v_reset")?,
+        };
+        n.finish()?;
+
+        let mut s = doc
+            .table("stdp")?
+            .ok_or_else(|| invalid("Missing table `[stdp]`".to_string()))?;
+        let stdp = STDPParams {
+            a_plus: s.required_number("a_plus")?,
+            a_minus: s.required_number("a_minus")?,
+            tau_plus: Milliseconds(s.required_number("tau_plus_ms")?),
+            tau_minus: Milliseconds(s.required_number("tau_minus_ms")?),
+            w_min: s.required_number("w_min")?,
+            w_max: s.required_number("w_max")?,
+        };
+        s.finish()?;
+
+        let mut populations = Vec::new();
+        for mut p in doc.array("population") {
+            let name = p.required_string("name")?;
+            if populations.iter().any(|q: &Population| q.name == name) {
+                return Err(p.error(format!("Duplicate population `{name}`")));
+            }
+            let size = p.required_usize("size")?;
+            let params = NeuronParams {
+                tau_m: p.number("tau_m_ms")?.map_or(neuron.tau_m, Milliseconds),
+                v_rest: p.number("v_rest")?.unwrap_or(neuron.v_rest),
+                v_thresh: p.number("v_thresh")?.unwrap_or(neuron.v_thresh),
+                v_reset: p.number("v_reset")?.unwrap_or(neuron.v_reset),
+            };
+            p.finish()?;
+            populations.push(Population::new(&name, size, params));
+        }
+        let size_of = |section: &Section, name: &str| {
+            populations
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| p.size)
+                .ok_or_else(|| section.error(format!("Unknown population `{name}`")))
+        };
+
+        let mut projections = Vec::new();
+        for mut p in doc.array("projection") {
+            let pre = p.required_string("pre")?;
+            let post = p.required_string("post")?;
+            let (pre_size, post_size) = (size_of(&p, &pre)?, size_of(&p, &post)?);
+            let connector = match p.string("connector")?.as_deref() {
+                None | Some("all_to_all") => Connector::AllToAll,
+                Some("one_to_one") if pre_size != post_size => {
+                    return Err(p.error(format!(
+                        "`one_to_one` needs populations of equal size, \
+                         got {pre_size} and {post_size}"
+                    )))
+                }
+                Some("one_to_one") => Connector::OneToOne,
+                Some("list") => {
+                    let pairs = p.pairs("pairs")?;
+                    if let Some(&(i, j)) = pairs
+                        .iter()
+                        .find(|&&(i, j)| i >= pre_size || j >= post_size)
+                    {
+                        return Err(p.error(format!("Connection ({i}, {j}) out of range")));
+                    }
+                    Connector::List(pairs)
+                }
+                Some(other) => return Err(p.error(format!("Unknown connector `{other}`"))),
+            };
+            let weight = match (
+                p.number("weight")?,
+                p.number("weight_min")?,
+                p.number("weight_max")?,
+            ) {
+                (Some(w), None, None) => WeightInit::Constant(w),
+                (None, Some(low), Some(high)) => WeightInit::Uniform { low, high },
+                _ => {
+                    return Err(p.error(
+                        "Give either `weight` or both `weight_min` and `weight_max`".to_string(),
+                    ))
+                }
+            };
+            let delay = match (
+                p.number("delay_ms")?,
+                p.number("delay_min_ms")?,
+                p.number("delay_max_ms")?,
+            ) {
+                (None, None, None) => DelayDistribution::Constant(Milliseconds::ZERO),
+                (Some(d), None, None) => DelayDistribution::Constant(Milliseconds(d)),
+                (None, Some(min), Some(max)) => DelayDistribution::Uniform {
+                    min: Milliseconds(min),
+                    max: Milliseconds(max),
+                },
+                _ => {
+                    return Err(p.error(
+                        "Give either `delay_ms` or both `delay_min_ms` and `delay_max_ms`"
+                            .to_string(),
+                    ))
+                }
+            };
+            let mut projection = Projection::new(&pre, &post, connector, weight).with_delay(delay);
+            if p.bool("plastic")?.unwrap_or(false) {
+                projection = projection.with_plasticity(stdp.clone());
+            }
+            p.finish()?;
+            projections.push(projection);
+        }
+
+        let mut stimuli = Vec::new();
+        for mut s in doc.array("stimulus") {
+            let kind = s.required_string("kind")?;
+            let population = s.required_string("population")?;
+            size_of(&s, &population)?;
+            let start = Milliseconds(s.number("start_ms")?.unwrap_or(0.0));
+            let duration = s
+                .number("duration_ms")?
+                .map_or(simulation.t_max - start, Milliseconds);
+            let stimulus = match kind.as_str() {
+                "poisson" => Stimulus::Poisson {
+                    population,
+                    rate_hz: s.required_number("rate_hz")?,
+                    start,
+                    duration,
+                },
+                "current" => Stimulus::Current {
+                    population,
+                    amplitude: s.required_number("amplitude")?,
+                    start,
+                    duration,
+                },
+                other => return Err(s.error(format!("Unknown stimulus kind `{other}`"))),
+            };
+            s.finish()?;
+            stimuli.push(stimulus);
+        }
+        doc.finish()?;
+
+        Ok(Self {
+            seed,
+            simulation,
+            neuron,
+            stdp,
+            populations,
+            projections,
+            stimuli,
+        })
+    }
+
+    /// Read and parse a TOML configuration file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Build the network described by the populations and projections.
+    ///
+    /// # Panics
+    /// Panics if a projection cannot be wired, e.g. because it names an
+    /// undefined population, which `from_toml` rules out.
+    pub fn network(&self) -> Network {
+        let mut builder = NetworkBuilder::new();
+        builder.seed(self.seed);
+        for population in &self.populations {
+            builder.add(population);
+        }
+        for projection in &self.projections {
+            builder
+                .project(projection)
+                .expect("Projections are validated by from_toml");
+        }
+        builder.build()
+    }
+
+    /// Build the simulation with the Poisson stimuli already injected.
+    ///
+    /// Current stimuli are applied through `input_current`.
+    pub fn build(&self) -> Simulation {
+        let mut sim =
+            Simulation::from_network(self.network(), self.simulation.clone(), self.stdp.clone());
+        // Offset the seed so stimuli do not replay the builder's draws
+        let mut rng = Rng::new(self.seed.wrapping_add(1));
+        for stimulus in &self.stimuli {
+            if let Stimulus::Poisson {
+                population,
+                rate_hz,
+                start,
+                duration,
+            } = stimulus
+            {
+                let range = self.range(population);
+                let encoder = PoissonEncoder {
+                    max_rate_hz: *rate_hz,
+                    duration: *duration,
+                    start: *start,
+                    first_neuron: range.start,
+                };
+                sim.inject_spikes(&encoder.encode(&vec![1.0; range.len()], &mut rng));
+            }
+        }
+        sim
+    }
+
+    /// Input current function applying the current stimuli, for
+    /// `Simulation::run` on a simulation from `build`.
+    pub fn input_current(&self) -> impl Fn(usize, Milliseconds) -> f64 + Sync {
+        let currents: Vec<(Range<usize>, f64, Milliseconds, Milliseconds)> = self
+            .stimuli
+            .iter()
+            .filter_map(|stimulus| match stimulus {
+                Stimulus::Current {
+                    population,
+                    amplitude,
+                    start,
+                    duration,
+                } => Some((
+                    self.range(population),
+                    *amplitude,
+                    *start,
+                    *start + *duration,
+                )),
+                Stimulus::Poisson { .. } => None,
+            })
+            .collect();
+        move |neuron, time| {
+            currents
+                .iter()
+                .filter(|(range, _, start, end)| {
+                    range.contains(&neuron) && time >= *start && time < *end
+                })
+                .map(|c| c.1)
+                .sum()
+        }
+    }
+
+    /// Neuron id range of population `name`, in population order.
+    fn range(&self, name: &str) -> Range<usize> {
+        let mut start = 0;
+        for p in &self.populations {
+            if p.name == name {
+                return start..start + p.size;
+            }
+            start += p.size;
+        }
+        panic!("Unknown population: {name}")
+    }
+}
+
+/// Value of a TOML key.
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+    String(String),
+    Array(Vec<Value>),
+}
+
+/// Keys of one table, consumed as they are read.
+#[derive(Debug)]
+struct Section {
+    /// Table name as written in the header, empty for the root
+    name: String,
+    /// Line of the table header
+    line: usize,
+    /// Remaining `(key, value, line)` entries
+    entries: Vec<(String, Value, usize)>,
+}
+
+impl Section {
+    fn new(name: &str, line: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            line,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Error located at the table header.
+    fn error(&self, message: String) -> io::Error {
+        if self.name.is_empty() {
+            invalid(message)
+        } else {
+            invalid(format!("Line {} (`{}`): {message}", self.line, self.name))
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<(Value, usize)> {
+        let k = self.entries.iter().position(|e| e.0 == key)?;
+        let (_, value, line) = self.entries.remove(k);
+        Some((value, line))
+    }
+
+    fn number(&mut self, key: &str) -> io::Result<Option<f64>> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Number(x), _)) => Ok(Some(x)),
+            Some((_, line)) => Err(invalid(format!("Line {line}: `{key}` must be a number"))),
+        }
+    }
+
+    fn usize(&mut self, key: &str) -> io::Result<Option<usize>> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Number(x), line)) => to_usize(x).map(Some).ok_or_else(|| {
+                invalid(format!(
+                    "Line {line}: `{key}` must be a non-negative integer"
+                ))
+            }),
+            Some((_, line)) => Err(invalid(format!("Line {line}: `{key}` must be a number"))),
+        }
+    }
+
+    fn bool(&mut self, key: &str) -> io::Result<Option<bool>> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Bool(b), _)) => Ok(Some(b)),
+            Some((_, line)) => Err(invalid(format!(
+                "Line {line}: `{key}` must be true or false"
+            ))),
+        }
+    }
+
+    fn string(&mut self, key: &str) -> io::Result<Option<String>> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::String(s), _)) => Ok(Some(s)),
+            Some((_, line)) => Err(invalid(format!("Line {line}: `{key}` must be a string"))),
+        }
+    }
+
+    /// Array of `[i, j]` index pairs.
+    fn pairs(&mut self, key: &str) -> io::Result<Vec<(usize, usize)>> {
+        let (value, line) = self
+            .take(key)
+            .ok_or_else(|| self.error(format!("Missing `{key}`")))?;
+        let error = || {
+            invalid(format!(
+                "Line {line}: `{key}` must be an array of [i, j] pairs"
+            ))
+        };
+        let Value::Array(items) = value else {
+            return Err(error());
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Value::Array(pair) => match pair.as_slice() {
+                    [Value::Number(i), Value::Number(j)] => Ok((
+                        to_usize(*i).ok_or_else(error)?,
+                        to_usize(*j).ok_or_else(error)?,
+                    )),
+                    _ => Err(error()),
+                },
+                _ => Err(error()),
+            })
+            .collect()
+    }
+
+    fn required_number(&mut self, key: &str) -> io::Result<f64> {
+        self.number(key)?
+            .ok_or_else(|| self.error(format!("Missing `{key}`")))
+    }
+
+    fn required_usize(&mut self, key: &str) -> io::Result<usize> {
+        self.usize(key)?
+            .ok_or_else(|| self.error(format!("Missing `{key}`")))
+    }
+
+    fn required_string(&mut self, key: &str) -> io::Result<String> {
+        self.string(key)?
+            .ok_or_else(|| self.error(format!("Missing `{key}`")))
+    }
+
+    /// Fail if any key was not read.
+    fn finish(&self) -> io::Result<()> {
+        match self.entries.first() {
+            Some((key, _, line)) => Err(invalid(format!("Line {line}: unknown key `{key}`"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parsed TOML document.
+#[derive(Debug)]
+struct Document {
+    root: Section,
+    /// Tables in file order; `true` marks `[[array]]` entries
+    tables: Vec<(bool, Section)>,
+}
+
+impl Document {
+    fn parse(text: &str) -> io::Result<Self> {
+        let mut doc = Self {
+            root: Section::new("", 0),
+            tables: Vec::new(),
+        };
+        for (k, raw) in text.lines().enumerate() {
+            let line = k + 1;
+            let content = strip_comment(raw).trim();
+            if content.is_empty() {
+                continue;
+            }
+            let error = |message: &str| invalid(format!("Line {line}: {message}"));
+            if let Some(header) = content.strip_prefix("[[") {
+                let name = header
+                    .strip_suffix("]]")
+                    .ok_or_else(|| error("unterminated table header"))?
+                    .trim();
+                doc.tables.push((true, Section::new(name, line)));
+            } else if let Some(header) = content.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("unterminated table header"))?
+                    .trim();
+                if doc.tables.iter().any(|(_, t)| t.name == name) {
+                    return Err(error(&format!("table `{name}` defined twice")));
+                }
+                doc.tables.push((false, Section::new(name, line)));
+            } else {
+                let (key, value) = content
+                    .split_once('=')
+                    .ok_or_else(|| error("expected `key = value`"))?;
+                let key = key.trim();
+                if key.is_empty()
+                    || !key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    return Err(error(&format!("invalid key `{key}`")));
+                }
+                let mut parser = ValueParser {
+                    chars: value.trim().chars().collect(),
+                    pos: 0,
+                };
+                let value = parser.value().map_err(|m| error(&m))?;
+                if parser.pos != parser.chars.len() {
+                    return Err(error("unexpected characters after value"));
+                }
+                let section = match doc.tables.last_mut() {
+                    Some((_, t)) => t,
+                    None => &mut doc.root,
+                };
+                if section.entries.iter().any(|e| e.0 == key) {
+                    return Err(error(&format!("key `{key}` defined twice")));
+                }
+                section.entries.push((key.to_string(), value, line));
+            }
+        }
+        Ok(doc)
+    }
+
+    /// Remove and return table `[name]`.
+    fn table(&mut self, name: &str) -> io::Result<Option<Section>> {
+        match self.tables.iter().position(|(_, t)| t.name == name) {
+            None => Ok(None),
+            Some(k) if self.tables[k].0 => Err(self.tables[k]
+                .1
+                .error(format!("`{name}` must be a table, not an array of tables"))),
+            Some(k) => Ok(Some(self.tables.remove(k).1)),
+        }
+    }
+
+    /// Remove and return all `[[name]]` tables in file order.
+    fn array(&mut self, name: &str) -> Vec<Section> {
+        let (taken, kept) = std::mem::take(&mut self.tables)
+            .into_iter()
+            .partition(|(array, t)| *array && t.name == name);
+        self.tables = kept;
+        taken.into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// Fail if any table was not read.
+    fn finish(self) -> io::Result<()> {
+        match self.tables.first() {
+            Some((_, t)) => Err(invalid(format!(
+                "Line {}: unknown table `{}`",
+                t.line, t.name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Cursor over the characters of one value.
+struct ValueParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl ValueParser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            None => Err("missing value".to_string()),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|&c| c != ',' && c != ']' && !c.is_whitespace())
+                {
+                    self.pos += 1;
+                }
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "inf" | "+inf" => Ok(Value::Number(f64::INFINITY)),
+                    "-inf" => Ok(Value::Number(f64::NEG_INFINITY)),
+                    _ => word
+                        .replace('_', "")
+                        .parse()
+                        .map(Value::Number)
+                        .map_err(|_| format!("invalid value `{word}`")),
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or("unterminated string")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escaped = *self.chars.get(self.pos).ok_or("unterminated string")?;
+                    self.pos += 1;
+                    out.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        '"' => '"',
+                        '\\' => '\\',
+                        other => return Err(format!("unsupported escape `\\{other}`")),
+                    });
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.get(self.pos) == Some(&']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err("expected `,` or `]` in array".to_string()),
+            }
+        }
+    }
+}
+
+/// `line` without a trailing `#` comment outside of strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (k, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..k],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Convert a non-negative integral number to `usize`.
+fn to_usize(x: f64) -> Option<usize> {
+    (x >= 0.0 && x.fract() == 0.0 && x <= usize::MAX as f64).then_some(x as usize)
+}
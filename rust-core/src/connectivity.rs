@@ -0,0 +1,324 @@
+//! connectivity.rs
+//!
+//! Connectivity generators.
+//!
+//! Realistic cortical models are sparse and random or locally structured
+//! rather than all-to-all. The generators in this module produce
+//! `(pre, post, weight)` triples between neuron index ranges, ready to be
+//! passed to `NetworkBuilder::connect_all`. Randomness comes from an
+//! explicitly seeded `Rng`, and each synapse's initial weight is drawn from
+//! a caller-supplied hook `weight(pre, post, rng)`.
+//!
+//! Random generators never produce self-connections when the ranges
+//! overlap.
+
+use crate::rng::Rng;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// A generated connection: `(pre, post, weight)`.
+pub type Connection = (usize, usize, f64);
+
+/// Erdős–Rényi wiring: each ordered pair is connected independently with
+/// probability `p`.
+pub fn erdos_renyi<W>(
+    pre: Range<usize>,
+    post: Range<usize>,
+    p: f64,
+    rng: &mut Rng,
+    mut weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let mut connections = Vec::new();
+    for i in pre {
+        for j in post.clone() {
+            if i != j && rng.bernoulli(p) {
+                let w = weight(i, j, rng);
+                connections.push((i, j, w));
+            }
+        }
+    }
+    connections
+}
+
+/// Fixed in-degree wiring: every neuron in `post` receives exactly `k`
+/// connections from distinct neurons in `pre`.
+///
+/// If fewer than `k` candidates exist, all of them are connected.
+pub fn fixed_in_degree<W>(
+    pre: Range<usize>,
+    post: Range<usize>,
+    k: usize,
+    rng: &mut Rng,
+    mut weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let mut connections = Vec::with_capacity(post.len() * k);
+    for j in post {
+        let mut candidates: Vec<usize> = pre.clone().filter(|&i| i != j).collect();
+        for i in sample_distinct(&mut candidates, k, rng) {
+            let w = weight(i, j, rng);
+            connections.push((i, j, w));
+        }
+    }
+    connections
+}
+
+/// Fixed out-degree wiring: every neuron in `pre` sends exactly `k`
+/// connections to distinct neurons in `post`.
+///
+/// If fewer than `k` candidates exist, all of them are connected.
+pub fn fixed_out_degree<W>(
+    pre: Range<usize>,
+    post: Range<usize>,
+    k: usize,
+    rng: &mut Rng,
+    mut weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let mut connections = Vec::with_capacity(pre.len() * k);
+    for i in pre {
+        let mut candidates: Vec<usize> = post.clone().filter(|&j| j != i).collect();
+        for j in sample_distinct(&mut candidates, k, rng) {
+            let w = weight(i, j, rng);
+            connections.push((i, j, w));
+        }
+    }
+    connections
+}
+
+/// Watts–Strogatz small-world wiring over `neurons`.
+///
+/// Starts from a ring lattice where every neuron is linked to its `k / 2`
+/// nearest neighbours on each side, then rewires each link to a uniformly
+/// chosen new target with probability `beta`, avoiding self-links and
+/// duplicates. Each undirected link yields a synapse in both directions.
+pub fn watts_strogatz<W>(
+    neurons: Range<usize>,
+    k: usize,
+    beta: f64,
+    rng: &mut Rng,
+    weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let n = neurons.len();
+    let half = (k / 2).min(n.saturating_sub(1) / 2);
+    let mut links: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for i in 0..n {
+        for d in 1..=half {
+            links.insert(ordered(i, (i + d) % n));
+        }
+    }
+    let mut degree = vec![2 * half; n];
+
+    for i in 0..n {
+        for d in 1..=half {
+            let j = (i + d) % n;
+            if !rng.bernoulli(beta) || !links.contains(&ordered(i, j)) {
+                continue;
+            }
+            // Saturated nodes keep their lattice link
+            if degree[i] + 1 >= n {
+                continue;
+            }
+            let target = loop {
+                let t = rng.index(n);
+                if t != i && !links.contains(&ordered(i, t)) {
+                    break t;
+                }
+            };
+            links.remove(&ordered(i, j));
+            links.insert(ordered(i, target));
+            degree[j] -= 1;
+            degree[target] += 1;
+        }
+    }
+
+    symmetric_connections(links, neurons.start, rng, weight)
+}
+
+/// Barabási–Albert scale-free wiring over `neurons`.
+///
+/// Begins with a fully linked core of `m + 1` neurons; every further
+/// neuron attaches to `m` distinct existing neurons chosen with probability
+/// proportional to their degree. Each undirected link yields a synapse in
+/// both directions.
+pub fn barabasi_albert<W>(
+    neurons: Range<usize>,
+    m: usize,
+    rng: &mut Rng,
+    weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let n = neurons.len();
+    let core = (m + 1).min(n);
+    let mut links: BTreeSet<(usize, usize)> = BTreeSet::new();
+    // Each node appears once per incident link, so uniform draws from this
+    // list are degree-proportional.
+    let mut endpoints: Vec<usize> = Vec::new();
+    for i in 0..core {
+        for j in (i + 1)..core {
+            links.insert((i, j));
+            endpoints.extend([i, j]);
+        }
+    }
+
+    for new in core..n {
+        let mut targets = BTreeSet::new();
+        while targets.len() < m.min(new) {
+            let t = if endpoints.is_empty() {
+                rng.index(new)
+            } else {
+                endpoints[rng.index(endpoints.len())]
+            };
+            targets.insert(t);
+        }
+        for t in targets {
+            links.insert(ordered(new, t));
+            endpoints.extend([new, t]);
+        }
+    }
+
+    symmetric_connections(links, neurons.start, rng, weight)
+}
+
+/// Weight hook returning the same weight for every synapse.
+pub fn constant_weight(w: f64) -> impl FnMut(usize, usize, &mut Rng) -> f64 {
+    move |_, _, _| w
+}
+
+/// Normalize an undirected link so the smaller index comes first.
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Expand undirected links between local indices into synapses in both
+/// directions, offset by `start`.
+fn symmetric_connections<W>(
+    links: BTreeSet<(usize, usize)>,
+    start: usize,
+    rng: &mut Rng,
+    mut weight: W,
+) -> Vec<Connection>
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let mut connections = Vec::with_capacity(links.len() * 2);
+    for (a, b) in links {
+        let (i, j) = (start + a, start + b);
+        let w = weight(i, j, rng);
+        connections.push((i, j, w));
+        let w = weight(j, i, rng);
+        connections.push((j, i, w));
+    }
+    connections
+}
+
+/// Choose up to `k` distinct items by partial Fisher–Yates shuffle.
+///
+/// Returns them in sorted order so the generated connection list does not
+/// depend on shuffle internals beyond the chosen set.
+fn sample_distinct(candidates: &mut [usize], k: usize, rng: &mut Rng) -> Vec<usize> {
+    let k = k.min(candidates.len());
+    for m in 0..k {
+        let r = m + rng.index(candidates.len() - m);
+        candidates.swap(m, r);
+    }
+    let mut chosen = candidates[..k].to_vec();
+    chosen.sort_unstable();
+    chosen
+}
+
+/// Width and height of a 2D neuron sheet laid out row-major.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SheetShape {
+    /// Number of columns
+    pub width: usize,
+    /// Number of rows
+    pub height: usize,
+}
+
+impl SheetShape {
+    /// Create a sheet shape.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    /// Number of neurons in the sheet.
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Whether the sheet has no neurons.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Output sheet produced by sliding a `kernel` window with `stride`
+    /// over this sheet without padding.
+    pub fn convolved(&self, kernel: SheetShape, stride: usize) -> SheetShape {
+        let stride = stride.max(1);
+        let out = |n: usize, k: usize| if n < k { 0 } else { (n - k) / stride + 1 };
+        SheetShape::new(out(self.width, kernel.width), out(self.height, kernel.height))
+    }
+}
+
+/// Convolution-like local receptive field wiring.
+///
+/// Connects a 2D input sheet starting at neuron `input_start` to one or
+/// more 2D output feature maps starting at `output_start`. Each output
+/// neuron receives a `kernel_shape` window of the input, offset by
+/// `stride`, with initial weights taken from its map's shared kernel
+/// (row-major, one kernel per map). Maps are laid out one after another,
+/// each with shape `input_shape.convolved(kernel_shape, stride)`.
+///
+/// Weights start out shared but are updated independently by STDP.
+///
+/// # Panics
+/// Panics if a kernel does not have `kernel_shape.len()` entries.
+pub fn receptive_fields(
+    input_start: usize,
+    input_shape: SheetShape,
+    output_start: usize,
+    kernel_shape: SheetShape,
+    stride: usize,
+    kernels: &[Vec<f64>],
+) -> Vec<Connection> {
+    let stride = stride.max(1);
+    let out_shape = input_shape.convolved(kernel_shape, stride);
+    let mut connections = Vec::with_capacity(kernels.len() * out_shape.len() * kernel_shape.len());
+
+    for (map, kernel) in kernels.iter().enumerate() {
+        assert_eq!(
+            kernel.len(),
+            kernel_shape.len(),
+            "Kernel {map} has the wrong number of weights"
+        );
+        let map_start = output_start + map * out_shape.len();
+        for oy in 0..out_shape.height {
+            for ox in 0..out_shape.width {
+                let post = map_start + oy * out_shape.width + ox;
+                for ky in 0..kernel_shape.height {
+                    for kx in 0..kernel_shape.width {
+                        let x = ox * stride + kx;
+                        let y = oy * stride + ky;
+                        let pre = input_start + y * input_shape.width + x;
+                        connections.push((pre, post, kernel[ky * kernel_shape.width + kx]));
+                    }
+                }
+            }
+        }
+    }
+    connections
+}
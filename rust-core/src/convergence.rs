@@ -0,0 +1,228 @@
+//! convergence.rs
+//!
+//! Convergence detection for STDP training.
+//!
+//! `StopCondition::WeightConverged` ends a run once the weights of the
+//! whole network settle, but in a layered model different projections
+//! usually converge at different times. A `ConvergenceMonitor` tracks the
+//! mean absolute weight change of each named projection over a sliding
+//! window and records when it first drops below a threshold. On
+//! convergence a projection can simply be reported, have its plasticity
+//! frozen, or contribute to stopping the run once every projection has
+//! converged.
+//!
+//! Tracked synapses are followed across `Simulation::remove_synapse` and
+//! `remove_neuron`, so a freeze always lands on the synapses that were
+//! tracked rather than on whatever shifted into their indices.
+
+use crate::monitor::{reached, shift_selected, Monitor, StepView};
+use crate::stopping::StopCondition;
+use crate::units::Milliseconds;
+use crate::util::lock;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// What happens when a projection converges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConvergenceAction {
+    /// Only record the convergence time.
+    Report,
+    /// Make the projection's synapses static. Requires the monitor to be
+    /// attached with `Simulation::add_convergence_monitor`.
+    Freeze,
+}
+
+/// Convergence state of one projection.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectionConvergence {
+    /// Projection name
+    pub name: String,
+    /// Mean |Δw| over the last completed window, if any
+    pub last_change: Option<f64>,
+    /// End of the first window whose mean |Δw| fell below the threshold
+    pub converged_at: Option<Milliseconds>,
+}
+
+/// Tracked projection with its weights at the start of the window.
+#[derive(Debug)]
+struct Tracked {
+    status: ProjectionConvergence,
+    /// Current index of each tracked synapse; `None` once removed
+    synapses: Vec<Option<usize>>,
+    snapshot: Vec<f64>,
+}
+
+/// Window settings and per-projection state shared by all clones.
+#[derive(Debug)]
+struct ConvergenceState {
+    window: Milliseconds,
+    epsilon: f64,
+    action: ConvergenceAction,
+    window_start: Option<Milliseconds>,
+    projections: Vec<Tracked>,
+    /// Synapses awaiting a freeze by the simulation
+    to_freeze: Vec<usize>,
+}
+
+/// Shared handle detecting per-projection weight convergence.
+#[derive(Debug, Clone)]
+pub struct ConvergenceMonitor {
+    state: Arc<Mutex<ConvergenceState>>,
+}
+
+impl ConvergenceMonitor {
+    /// Detect convergence as a mean |Δw| below `epsilon` over consecutive
+    /// windows of length `window`.
+    pub fn new(window: Milliseconds, epsilon: f64, action: ConvergenceAction) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ConvergenceState {
+                window,
+                epsilon,
+                action,
+                window_start: None,
+                projections: Vec::new(),
+                to_freeze: Vec::new(),
+            })),
+        }
+    }
+
+    /// Track the synapses with the given indices as projection `name`.
+    ///
+    /// `Simulation::projection_synapses` finds the synapses between two
+    /// populations.
+    pub fn track(self, name: &str, synapses: Vec<usize>) -> Self {
+        self.lock().projections.push(Tracked {
+            status: ProjectionConvergence {
+                name: name.to_string(),
+                last_change: None,
+                converged_at: None,
+            },
+            synapses: synapses.into_iter().map(Some).collect(),
+            snapshot: Vec::new(),
+        });
+        self
+    }
+
+    /// Current state of every tracked projection, in tracking order.
+    pub fn status(&self) -> Vec<ProjectionConvergence> {
+        self.lock()
+            .projections
+            .iter()
+            .map(|p| p.status.clone())
+            .collect()
+    }
+
+    /// Convergence time of projection `name`, if it has converged.
+    pub fn converged_at(&self, name: &str) -> Option<Milliseconds> {
+        self.lock()
+            .projections
+            .iter()
+            .find(|p| p.status.name == name)
+            .and_then(|p| p.status.converged_at)
+    }
+
+    /// Whether every tracked projection has converged.
+    pub fn all_converged(&self) -> bool {
+        let state = self.lock();
+        !state.projections.is_empty()
+            && state
+                .projections
+                .iter()
+                .all(|p| p.status.converged_at.is_some())
+    }
+
+    /// Stop condition satisfied once every projection has converged. The
+    /// run then ends with `StopReason::Custom`.
+    pub fn stop_condition(&self) -> StopCondition {
+        let monitor = self.clone();
+        StopCondition::Custom(Box::new(move |_, _| monitor.all_converged()))
+    }
+
+    /// Take the synapses that should be frozen now.
+    pub(crate) fn take_frozen(&self) -> Vec<usize> {
+        std::mem::take(&mut self.lock().to_freeze)
+    }
+
+    /// Lock the shared state.
+    fn lock(&self) -> MutexGuard<'_, ConvergenceState> {
+        lock(&self.state)
+    }
+}
+
+impl Monitor for ConvergenceMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = self.lock();
+        let time = step.end_time();
+        let weights = |synapses: &[Option<usize>]| -> Vec<f64> {
+            synapses
+                .iter()
+                .map(|k| k.and_then(|k| step.synapses.get(k)).map_or(f64::NAN, |s| s.weight))
+                .collect()
+        };
+        let Some(start) = state.window_start else {
+            state.window_start = Some(time);
+            for p in state.projections.iter_mut() {
+                p.snapshot = weights(&p.synapses);
+            }
+            return;
+        };
+        if !reached(time, start + state.window) {
+            return;
+        }
+
+        let ConvergenceState {
+            epsilon,
+            action,
+            projections,
+            to_freeze,
+            ..
+        } = &mut *state;
+        for p in projections.iter_mut() {
+            let current = weights(&p.synapses);
+            // Synapses removed since the window started read as NaN
+            let changes: Vec<f64> = current
+                .iter()
+                .zip(&p.snapshot)
+                .map(|(w, w0)| (w - w0).abs())
+                .filter(|d| d.is_finite())
+                .collect();
+            let change = if changes.is_empty() {
+                0.0
+            } else {
+                changes.iter().sum::<f64>() / changes.len() as f64
+            };
+            p.status.last_change = Some(change);
+            if p.status.converged_at.is_none() && change < *epsilon {
+                p.status.converged_at = Some(time);
+                if *action == ConvergenceAction::Freeze {
+                    to_freeze.extend(p.synapses.iter().flatten());
+                }
+            }
+            p.snapshot = current;
+        }
+        state.window_start = Some(start + state.window);
+    }
+    fn synapse_removed(&mut self, index: usize) {
+        let mut state = self.lock();
+        for p in state.projections.iter_mut() {
+            shift_selected(&mut p.synapses, index);
+        }
+        state.to_freeze.retain(|&k| k != index);
+        for k in state.to_freeze.iter_mut() {
+            if *k > index {
+                *k -= 1;
+            }
+        }
+    }
+
+    fn max_synapse(&self) -> Option<usize> {
+        let state = self.lock();
+        state
+            .projections
+            .iter()
+            .flat_map(|p| p.synapses.iter().flatten())
+            .copied()
+            .max()
+    }
+}
@@ -0,0 +1,481 @@
+//! conversion.rs
+//!
+//! ANN-to-SNN conversion of trained ReLU networks.
+//!
+//! Training a spiking network with STDP is only interesting next to a
+//! baseline, and the classic one is a conventional network trained with
+//! backpropagation and converted to spikes. A ReLU unit's activation maps
+//! onto the firing rate of a non-leaky integrate-and-fire neuron driven by
+//! the same weighted input, so `convert` turns the dense layers of a
+//! `ReluNetwork` into a rate-coded `Simulation`:
+//!
+//! - every unit becomes a neuron with threshold 1 and a membrane time
+//!   constant long enough to make the leak negligible, reset by
+//!   subtraction (`ResetMode::Subtract`) so that no charge is lost and the
+//!   spike count tracks the integrated input;
+//! - the input layer is driven by a constant current equal to the input
+//!   value, so an input of 0.5 fires every other step;
+//! - biases become constant currents, and weights become static synapses
+//!   with a delay of one step.
+//!
+//! Rates saturate at one spike per step, so each layer is rescaled by
+//! threshold balancing: with `λ_l` the largest activation of layer `l`,
+//! weights are multiplied by `λ_{l-1} / λ_l` and biases divided by `λ_l`,
+//! which is equivalent to raising the thresholds of layer `l` to `λ_l`.
+//! `λ_l` is taken as a high percentile of the activations over calibration
+//! inputs (data-based normalization, Rueckauer et al. 2017), or without
+//! them from bounds propagated through the weights (model-based, Diehl et
+//! al. 2015). Inputs are expected in `[0, 1]`.
+//!
+//! Trained weights are read from JSON,
+//!
+//! ```text
+//! {"layers": [{"weights": [[...], ...], "biases": [...]}, ...]}
+//! ```
+//!
+//! or from an `.npz` archive with arrays `W0`, `b0`, `W1`, `b1`, ... as
+//! written by `numpy.savez("ann.npz", W0=..., b0=..., ...)`. Weight
+//! matrices are indexed `[output, input]`, like `torch.nn.Linear.weight`
+//! (transpose Keras kernels), and biases may be omitted. ReLU is applied
+//! after every layer including the last, whose activations become the
+//! output firing rates.
+
+use crate::json::{Json, JsonParser};
+use crate::network::NetworkBuilder;
+use crate::neuron::{NeuronParams, ResetMode};
+use crate::numpy::read_npz;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// Membrane time constant of converted neurons, long enough for the leak
+/// to be negligible over any practical run.
+pub const CONVERSION_TAU_M: Milliseconds = Milliseconds(1e9);
+
+/// A dense layer of a trained network.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DenseLayer {
+    /// Weights indexed `[output][input]`
+    pub weights: Vec<Vec<f64>>,
+    /// Bias of each output
+    pub biases: Vec<f64>,
+}
+
+impl DenseLayer {
+    /// Number of inputs.
+    pub fn inputs(&self) -> usize {
+        self.weights.first().map_or(0, Vec::len)
+    }
+
+    /// Number of outputs.
+    pub fn outputs(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// ReLU activations for `input`.
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.weights
+            .iter()
+            .zip(&self.biases)
+            .map(|(row, b)| {
+                let z: f64 = row.iter().zip(input).map(|(w, x)| w * x).sum::<f64>() + b;
+                z.max(0.0)
+            })
+            .collect()
+    }
+}
+
+/// A trained feedforward network of dense ReLU layers.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReluNetwork {
+    /// Layers, first layer first
+    pub layers: Vec<DenseLayer>,
+}
+
+impl ReluNetwork {
+    /// Network of `layers`.
+    ///
+    /// Returns an `InvalidData` error if a layer is empty or ragged, has a
+    /// bias count differing from its outputs, or does not take the outputs
+    /// of the previous layer as inputs.
+    pub fn new(layers: Vec<DenseLayer>) -> io::Result<Self> {
+        if layers.is_empty() {
+            return Err(invalid("Network has no layers".to_string()));
+        }
+        for (k, layer) in layers.iter().enumerate() {
+            if layer.outputs() == 0 || layer.inputs() == 0 {
+                return Err(invalid(format!("Layer {k} is empty")));
+            }
+            if layer.weights.iter().any(|row| row.len() != layer.inputs()) {
+                return Err(invalid(format!(
+                    "Weight rows of layer {k} differ in length"
+                )));
+            }
+            if layer.biases.len() != layer.outputs() {
+                return Err(invalid(format!(
+                    "Layer {k} has {} biases for {} outputs",
+                    layer.biases.len(),
+                    layer.outputs()
+                )));
+            }
+            if k > 0 && layer.inputs() != layers[k - 1].outputs() {
+                return Err(invalid(format!(
+                    "Layer {k} takes {} inputs but layer {} has {} outputs",
+                    layer.inputs(),
+                    k - 1,
+                    layers[k - 1].outputs()
+                )));
+            }
+        }
+        Ok(Self { layers })
+    }
+
+    /// Parse the JSON layout described in the module documentation.
+    pub fn from_json(text: &str) -> io::Result<Self> {
+        let doc = JsonParser::new(text).parse_document().map_err(invalid)?;
+        let Some(Json::Array(layers)) = doc.get("layers") else {
+            return Err(invalid("Missing `layers` array".to_string()));
+        };
+        let layers = layers
+            .iter()
+            .enumerate()
+            .map(|(k, layer)| {
+                let Some(Json::Array(rows)) = layer.get("weights") else {
+                    return Err(invalid(format!("Layer {k} lacks a `weights` array")));
+                };
+                let weights = rows
+                    .iter()
+                    .map(|row| numbers(row, &format!("weights of layer {k}")))
+                    .collect::<io::Result<Vec<_>>>()?;
+                let biases = match layer.get("biases") {
+                    Some(biases) => numbers(biases, &format!("biases of layer {k}"))?,
+                    None => vec![0.0; weights.len()],
+                };
+                Ok(DenseLayer { weights, biases })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Self::new(layers)
+    }
+
+    /// Read a JSON file, see `from_json`.
+    pub fn read_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Read an `.npz` archive with arrays `W0`, `b0`, `W1`, `b1`, ...
+    pub fn read_npz<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let arrays = read_npz(path)?;
+        let array = |name: &str| arrays.iter().find(|(n, _)| n == name).map(|(_, a)| a);
+        let mut layers = Vec::new();
+        while let Some(weights) = array(&format!("W{}", layers.len())) {
+            let k = layers.len();
+            let values = |a: &crate::numpy::NpyArray, name: &str| {
+                a.to_f64()
+                    .ok_or_else(|| invalid(format!("`{name}{k}` is not a numeric array")))
+            };
+            let [outputs, inputs] = *weights.shape() else {
+                return Err(invalid(format!("`W{k}` is not a matrix")));
+            };
+            let weights: Vec<Vec<f64>> = values(weights, "W")?
+                .chunks(inputs.max(1))
+                .map(<[f64]>::to_vec)
+                .collect();
+            let biases = match array(&format!("b{k}")) {
+                Some(b) if b.shape() == [outputs] => values(b, "b")?,
+                Some(_) => return Err(invalid(format!("`b{k}` does not match `W{k}`"))),
+                None => vec![0.0; outputs],
+            };
+            layers.push(DenseLayer { weights, biases });
+        }
+        Self::new(layers)
+    }
+
+    /// Number of inputs.
+    pub fn input_size(&self) -> usize {
+        self.layers[0].inputs()
+    }
+
+    /// Number of outputs.
+    pub fn output_size(&self) -> usize {
+        self.layers[self.layers.len() - 1].outputs()
+    }
+
+    /// Activations of every layer for `input`, first layer first.
+    pub fn activations(&self, input: &[f64]) -> Vec<Vec<f64>> {
+        let mut activations: Vec<Vec<f64>> = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let previous = activations.last().map_or(input, Vec::as_slice);
+            let next = layer.forward(previous);
+            activations.push(next);
+        }
+        activations
+    }
+
+    /// Output activations for `input`.
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.activations(input).pop().unwrap_or_default()
+    }
+
+    /// Index of the largest output for `input`.
+    pub fn predict(&self, input: &[f64]) -> usize {
+        argmax(&self.forward(input))
+    }
+}
+
+/// Settings of `convert`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionParams {
+    /// Simulation time step; one step is the unit of the rate code
+    pub dt: Milliseconds,
+    /// Percentile of calibration activations taken as a layer's maximum
+    pub percentile: f64,
+}
+
+impl Default for ConversionParams {
+    fn default() -> Self {
+        Self {
+            dt: Milliseconds(1.0),
+            percentile: 99.9,
+        }
+    }
+}
+
+/// A rate-coded spiking network converted from a `ReluNetwork`.
+pub struct ConvertedNetwork {
+    /// The converted network, with populations `layer0` (input) to
+    /// `layerN` (output)
+    pub simulation: Simulation,
+    /// Neuron index range of each layer, input layer first
+    pub layers: Vec<Range<usize>>,
+    /// Activation of each ANN layer that maps to one spike per step
+    pub scales: Vec<f64>,
+    /// Constant current of each neuron, carrying the biases
+    bias_currents: Vec<f64>,
+    /// Current raising the membrane potential by one threshold per step
+    unit_current: f64,
+}
+
+impl ConvertedNetwork {
+    /// Input current of every neuron while `input` is presented.
+    pub fn currents(&self, input: &[f64]) -> Vec<f64> {
+        let mut currents = self.bias_currents.clone();
+        for (i, x) in self.layers[0].clone().zip(input) {
+            currents[i] += x * self.unit_current;
+        }
+        currents
+    }
+
+    /// Present `input` from rest for `steps` steps and return the spike
+    /// count of every output neuron.
+    pub fn run(&mut self, input: &[f64], steps: usize) -> Vec<usize> {
+        let currents = self.currents(input);
+        let output = self.layers[self.layers.len() - 1].clone();
+        let mut counts = vec![0; output.len()];
+        self.simulation.reset();
+        for _ in 0..steps {
+            for spike in self.simulation.step(|i, _| currents[i]) {
+                if output.contains(&spike.neuron_id) {
+                    counts[spike.neuron_id - output.start] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Estimate of the ANN output activations from `run`: spike rates per
+    /// step times the output scale. Converges as `steps` grows, after a
+    /// latency of one step per layer.
+    pub fn output_rates(&mut self, input: &[f64], steps: usize) -> Vec<f64> {
+        let scale = self.scales[self.scales.len() - 1];
+        self.run(input, steps)
+            .into_iter()
+            .map(|count| count as f64 / steps.max(1) as f64 * scale)
+            .collect()
+    }
+
+    /// Index of the output neuron with the most spikes for `input`; ties
+    /// go to the lowest index.
+    pub fn predict(&mut self, input: &[f64], steps: usize) -> usize {
+        let counts = self.run(input, steps);
+        argmax(&counts.iter().map(|&c| c as f64).collect::<Vec<f64>>())
+    }
+
+    /// Fraction of `inputs` classified as their `labels`.
+    pub fn accuracy(&mut self, inputs: &[Vec<f64>], labels: &[usize], steps: usize) -> f64 {
+        if inputs.is_empty() {
+            return 0.0;
+        }
+        let correct = inputs
+            .iter()
+            .zip(labels)
+            .filter(|(input, &label)| self.predict(input, steps) == label)
+            .count();
+        correct as f64 / inputs.len() as f64
+    }
+}
+
+/// Convert `ann` into a rate-coded spiking network.
+///
+/// Layers are balanced on the activations of `calibration` inputs, or on
+/// weight-based bounds if it is empty.
+pub fn convert(
+    ann: &ReluNetwork,
+    calibration: &[Vec<f64>],
+    params: &ConversionParams,
+) -> ConvertedNetwork {
+    let scales = if calibration.is_empty() {
+        model_scales(ann)
+    } else {
+        data_scales(ann, calibration, params.percentile)
+    };
+
+    let neuron_params = NeuronParams {
+        tau_m: CONVERSION_TAU_M,
+        v_rest: 0.0,
+        v_thresh: 1.0,
+        v_reset: 0.0,
+    };
+    let mut builder = NetworkBuilder::new();
+    let mut layers =
+        vec![builder.add_population("layer0", ann.input_size(), neuron_params.clone())];
+    for (k, layer) in ann.layers.iter().enumerate() {
+        layers.push(builder.add_population(
+            &format!("layer{}", k + 1),
+            layer.outputs(),
+            neuron_params.clone(),
+        ));
+    }
+
+    let unit_current = CONVERSION_TAU_M / params.dt;
+    let mut bias_currents = vec![0.0; builder.num_neurons()];
+    let mut previous_scale = 1.0;
+    for (k, layer) in ann.layers.iter().enumerate() {
+        let (pre, post) = (layers[k].clone(), layers[k + 1].clone());
+        let scale = scales[k];
+        let synapses = layer.weights.iter().zip(post.clone()).flat_map(|(row, j)| {
+            row.iter()
+                .zip(pre.clone())
+                .filter(|(&w, _)| w != 0.0)
+                .map(move |(&w, i)| Synapse {
+                    delay: params.dt,
+                    plasticity: Plasticity::Static,
+                    ..Synapse::new(i, j, w * previous_scale / scale)
+                })
+        });
+        builder.add_synapses(synapses.collect::<Vec<_>>());
+        for (j, b) in post.zip(&layer.biases) {
+            bias_currents[j] = b / scale * unit_current;
+        }
+        previous_scale = scale;
+    }
+
+    let config = SimulationConfig {
+        dt: params.dt,
+        synaptic_transmission: true,
+        reset: ResetMode::Subtract,
+        ..SimulationConfig::default()
+    };
+    // Every synapse is static, so the learning rule never applies.
+    let stdp = STDPParams {
+        a_plus: 0.0,
+        a_minus: 0.0,
+        tau_plus: Milliseconds(20.0),
+        tau_minus: Milliseconds(20.0),
+        w_min: f64::NEG_INFINITY,
+        w_max: f64::INFINITY,
+    };
+    ConvertedNetwork {
+        simulation: Simulation::from_network(builder.build(), config, stdp),
+        layers,
+        scales,
+        bias_currents,
+        unit_current,
+    }
+}
+
+/// Layer maxima as a percentile of the activations over `inputs`.
+fn data_scales(ann: &ReluNetwork, inputs: &[Vec<f64>], percentile: f64) -> Vec<f64> {
+    let mut per_layer: Vec<Vec<f64>> = vec![Vec::new(); ann.layers.len()];
+    for input in inputs {
+        for (values, activations) in per_layer.iter_mut().zip(ann.activations(input)) {
+            values.extend(activations.into_iter().filter(|&a| a > 0.0));
+        }
+    }
+    per_layer
+        .into_iter()
+        .map(|mut values| {
+            if values.is_empty() {
+                return 1.0;
+            }
+            values.sort_by(f64::total_cmp);
+            let rank = (percentile / 100.0).clamp(0.0, 1.0) * (values.len() - 1) as f64;
+            values[rank.round() as usize]
+        })
+        .collect()
+}
+
+/// Layer maxima as the largest activation any input in `[0, 1]` can cause,
+/// bounded layer by layer.
+fn model_scales(ann: &ReluNetwork) -> Vec<f64> {
+    let mut bounds = vec![1.0; ann.input_size()];
+    ann.layers
+        .iter()
+        .map(|layer| {
+            bounds = layer
+                .weights
+                .iter()
+                .zip(&layer.biases)
+                .map(|(row, b)| {
+                    row.iter()
+                        .zip(&bounds)
+                        .map(|(w, x)| w.max(0.0) * x)
+                        .sum::<f64>()
+                        + b.max(0.0)
+                })
+                .collect();
+            let max = bounds.iter().copied().fold(0.0, f64::max);
+            if max > 0.0 {
+                max
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
+
+/// Index of the largest value; ties go to the lowest index.
+fn argmax(values: &[f64]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |best, (i, &v)| {
+            if v > best.1 {
+                (i, v)
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Numeric array `value`, described as `what` in errors.
+fn numbers(value: &Json, what: &str) -> io::Result<Vec<f64>> {
+    match value {
+        Json::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Json::Number(x) => Ok(*x),
+                _ => Err(invalid(format!("Non-numeric entry in {what}"))),
+            })
+            .collect(),
+        _ => Err(invalid(format!("Expected an array of {what}"))),
+    }
+}
@@ -0,0 +1,456 @@
+//! cpg.rs
+//!
+//! Central pattern generators built from half-center oscillators.
+//!
+//! Rhythmic movements such as walking and swimming are generated by spinal
+//! circuits that oscillate without rhythmic input. The half-center model
+//! (Brown, 1911) explains the rhythm with two pools of neurons, flexor and
+//! extensor, that inhibit each other: the active pool silences the other
+//! until spike-frequency adaptation weakens it enough for the other to
+//! escape, and the roles swap. The adaptation time constant largely sets
+//! the period; the tonic drive starts and stops the rhythm and shapes the
+//! bursts.
+//!
+//! Neurons of this crate have no adaptation of their own, so `Cpg` keeps an
+//! adaptation current per neuron that jumps at every spike and decays
+//! exponentially, and subtracts it from the drive it feeds the simulation
+//! on every `Cpg::step`. Several oscillators are coupled flexor to flexor
+//! and extensor to extensor; inhibitory couplings push oscillators into
+//! antiphase and excitatory couplings into phase, e.g. the alternating
+//! tripods of a hexapod gait built by `CpgParams::tripod_gait`.
+//!
+//! The phase of an oscillator runs from 0 at the onset of a flexor burst to
+//! 1 at the next one. `PhaseTracker` estimates it online while the CPG
+//! runs, for driving actuators in real time, and `burst_onsets` with
+//! `relative_phases` measure it offline from recorded spikes.
+
+use crate::bursts::{detect_bursts, MaxIntervalParams};
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::f64::consts::TAU;
+use std::ops::Range;
+
+/// Parameters of one half-center oscillator.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfCenterParams {
+    /// Neurons per half-center
+    pub size: usize,
+    /// Tonic input current
+    pub drive: f64,
+    /// Relative standard deviation of the drive across neurons
+    pub heterogeneity: f64,
+    /// Weight between neurons of the same half-center
+    pub excitation: f64,
+    /// Magnitude of the weight from each neuron onto the other half-center
+    pub inhibition: f64,
+    /// Delay of every synapse
+    pub delay: Milliseconds,
+    /// Adaptation current added by each spike
+    pub adaptation_increment: f64,
+    /// Decay time constant of the adaptation current
+    pub adaptation_tau: Milliseconds,
+}
+
+impl Default for HalfCenterParams {
+    fn default() -> Self {
+        Self {
+            size: 10,
+            drive: 1.6,
+            heterogeneity: 0.02,
+            excitation: 0.02,
+            inhibition: 0.1,
+            delay: Milliseconds(1.0),
+            adaptation_increment: 0.1,
+            adaptation_tau: Milliseconds(100.0),
+        }
+    }
+}
+
+/// A coupling from oscillator `from` onto oscillator `to`.
+///
+/// Every flexor neuron of `from` connects to every flexor neuron of `to`
+/// with `weight`, and likewise for extensors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coupling {
+    /// Source oscillator
+    pub from: usize,
+    /// Target oscillator
+    pub to: usize,
+    /// Weight of every coupling synapse; negative pushes into antiphase
+    pub weight: f64,
+}
+
+/// Layout of a central pattern generator.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpgParams {
+    /// Number of half-center oscillators
+    pub num_oscillators: usize,
+    /// Parameters shared by every oscillator
+    pub half_center: HalfCenterParams,
+    /// Couplings between oscillators
+    pub couplings: Vec<Coupling>,
+    /// Seed for the drive heterogeneity
+    pub seed: u64,
+}
+
+impl Default for CpgParams {
+    fn default() -> Self {
+        Self {
+            num_oscillators: 1,
+            half_center: HalfCenterParams::default(),
+            couplings: Vec::new(),
+            seed: 0,
+        }
+    }
+}
+
+impl CpgParams {
+    /// Six oscillators, one per leg of a hexapod, in the tripod gait.
+    ///
+    /// Legs are numbered left front, middle, hind, then right front,
+    /// middle, hind. Legs 0, 4 and 2 form one tripod and legs 3, 1 and 5
+    /// the other; legs of the same tripod excite and legs of different
+    /// tripods inhibit each other. The couplings are weak next to the
+    /// synapses within a half-center, enough to lock the tripods in phase
+    /// and into antiphase with each other within a few cycles while
+    /// slowing the rhythm by about a third; stronger inhibition holds the
+    /// flexors of one tripod back for longer and stretches the cycle.
+    pub fn tripod_gait() -> Self {
+        const TRIPOD: [usize; 6] = [0, 1, 0, 1, 0, 1];
+        let mut couplings = Vec::new();
+        for (from, tripod_from) in TRIPOD.iter().enumerate() {
+            for (to, tripod_to) in TRIPOD.iter().enumerate() {
+                if from == to {
+                    continue;
+                }
+                let weight = if tripod_from == tripod_to {
+                    0.004
+                } else {
+                    -0.002
+                };
+                couplings.push(Coupling { from, to, weight });
+            }
+        }
+        Self {
+            num_oscillators: 6,
+            couplings,
+            ..Self::default()
+        }
+    }
+}
+
+/// Build a central pattern generator.
+///
+/// Half-centers are registered as populations `osc{k}_flexor` and
+/// `osc{k}_extensor`, and every synapse is static. Synaptic transmission is
+/// enabled on `config`. Extensors start adapted so every oscillator begins
+/// with a flexor burst.
+///
+/// # Panics
+/// Panics if a coupling names an oscillator that does not exist.
+pub fn build_cpg(
+    params: &CpgParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+    stdp_params: STDPParams,
+) -> Cpg {
+    let hc = &params.half_center;
+    let mut builder = NetworkBuilder::new();
+    let mut flexors = Vec::with_capacity(params.num_oscillators);
+    let mut extensors = Vec::with_capacity(params.num_oscillators);
+    for k in 0..params.num_oscillators {
+        flexors.push(builder.add_population(
+            &format!("osc{k}_flexor"),
+            hc.size,
+            neuron_params.clone(),
+        ));
+        extensors.push(builder.add_population(
+            &format!("osc{k}_extensor"),
+            hc.size,
+            neuron_params.clone(),
+        ));
+    }
+
+    let mut synapses = Vec::new();
+    let mut connect = |pre: &Range<usize>, post: &Range<usize>, weight: f64| {
+        for i in pre.clone() {
+            for j in post.clone().filter(|&j| j != i) {
+                synapses.push(Synapse {
+                    plasticity: Plasticity::Static,
+                    ..Synapse::with_delay(i, j, weight, hc.delay)
+                });
+            }
+        }
+    };
+    for (flexor, extensor) in flexors.iter().zip(&extensors) {
+        connect(flexor, flexor, hc.excitation);
+        connect(extensor, extensor, hc.excitation);
+        connect(flexor, extensor, -hc.inhibition);
+        connect(extensor, flexor, -hc.inhibition);
+    }
+    for c in &params.couplings {
+        assert!(
+            c.from < params.num_oscillators && c.to < params.num_oscillators,
+            "Coupling {} -> {} outside 0..{}",
+            c.from,
+            c.to,
+            params.num_oscillators
+        );
+        connect(&flexors[c.from], &flexors[c.to], c.weight);
+        connect(&extensors[c.from], &extensors[c.to], c.weight);
+    }
+    builder.add_synapses(synapses);
+
+    let n = builder.num_neurons();
+    let mut rng = Rng::new(params.seed);
+    let bias = (0..n)
+        .map(|_| 1.0 + hc.heterogeneity * rng.normal())
+        .collect();
+    let mut oscillator = vec![0; n];
+    for (k, (flexor, extensor)) in flexors.iter().zip(&extensors).enumerate() {
+        for i in flexor.clone().chain(extensor.clone()) {
+            oscillator[i] = k;
+        }
+    }
+    let mut adaptation = vec![0.0; n];
+    for i in extensors.iter().flat_map(|r| r.clone()) {
+        adaptation[i] = hc.drive;
+    }
+    let trackers = flexors
+        .iter()
+        .map(|r| PhaseTracker::new(r.clone(), hc.adaptation_tau / 2.0))
+        .collect();
+
+    config.synaptic_transmission = true;
+    Cpg {
+        simulation: Simulation::from_network(builder.build(), config, stdp_params),
+        drive: vec![hc.drive; params.num_oscillators],
+        flexors,
+        extensors,
+        oscillator,
+        bias,
+        adaptation,
+        adaptation_increment: hc.adaptation_increment,
+        adaptation_tau: hc.adaptation_tau,
+        trackers,
+    }
+}
+
+/// A central pattern generator and its adaptation state.
+pub struct Cpg {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Flexor neurons of each oscillator
+    pub flexors: Vec<Range<usize>>,
+    /// Extensor neurons of each oscillator
+    pub extensors: Vec<Range<usize>>,
+    /// Tonic drive of each oscillator
+    drive: Vec<f64>,
+    /// Oscillator of each neuron
+    oscillator: Vec<usize>,
+    /// Relative drive of each neuron
+    bias: Vec<f64>,
+    /// Adaptation current of each neuron
+    adaptation: Vec<f64>,
+    adaptation_increment: f64,
+    adaptation_tau: Milliseconds,
+    /// Online phase estimate of each oscillator
+    trackers: Vec<PhaseTracker>,
+}
+
+impl Cpg {
+    /// Number of oscillators.
+    pub fn num_oscillators(&self) -> usize {
+        self.flexors.len()
+    }
+
+    /// Tonic drive of oscillator `k`.
+    pub fn drive(&self, k: usize) -> f64 {
+        self.drive[k]
+    }
+
+    /// Set the tonic drive of oscillator `k` from the next step on. A drive
+    /// below `v_thresh - v_rest` of its neurons silences the oscillator.
+    pub fn set_drive(&mut self, k: usize, drive: f64) {
+        self.drive[k] = drive;
+    }
+
+    /// Set the tonic drive of every oscillator.
+    pub fn set_drive_all(&mut self, drive: f64) {
+        self.drive.fill(drive);
+    }
+
+    /// Advance by one time step and return the spikes emitted.
+    pub fn step(&mut self) -> Vec<Spike> {
+        let current: Vec<f64> = (0..self.adaptation.len())
+            .map(|i| self.drive[self.oscillator[i]] * self.bias[i] - self.adaptation[i])
+            .collect();
+        let time = self.simulation.time();
+        let dt = self.simulation.config().dt;
+        let spikes = self.simulation.step(|i, _| current[i]);
+
+        let decay = (-(dt / self.adaptation_tau)).exp();
+        for a in &mut self.adaptation {
+            *a *= decay;
+        }
+        let fired: Vec<usize> = spikes.iter().map(|s| s.neuron_id).collect();
+        for &i in &fired {
+            self.adaptation[i] += self.adaptation_increment;
+        }
+        for tracker in &mut self.trackers {
+            tracker.observe(time, &fired);
+        }
+        spikes
+    }
+
+    /// Step for `duration` and return the spikes emitted.
+    pub fn run(&mut self, duration: Milliseconds) -> Vec<Spike> {
+        let end = self.simulation.time() + duration;
+        let mut spikes = Vec::new();
+        while self.simulation.time() < end {
+            spikes.extend(self.step());
+        }
+        spikes
+    }
+
+    /// Online phase tracker of oscillator `k`.
+    pub fn tracker(&self, k: usize) -> &PhaseTracker {
+        &self.trackers[k]
+    }
+
+    /// Current phase of every oscillator in `[0, 1)`, `None` until it has
+    /// completed a cycle.
+    pub fn phases(&self) -> Vec<Option<f64>> {
+        let time = self.simulation.time();
+        self.trackers.iter().map(|t| t.phase(time)).collect()
+    }
+}
+
+/// Online phase estimate of a rhythmically bursting population.
+///
+/// A burst starts with the first spike after more than `min_gap` of
+/// silence. The phase at time `t` is the time since the last onset divided
+/// by the last period, so it advances linearly and assumes the rhythm
+/// holds its frequency.
+#[derive(Debug, Clone)]
+pub struct PhaseTracker {
+    /// Neurons whose bursts define the phase
+    pub neurons: Range<usize>,
+    /// Shortest silence separating two bursts
+    pub min_gap: Milliseconds,
+    last_spike: Option<Milliseconds>,
+    last_onset: Option<Milliseconds>,
+    period: Option<Milliseconds>,
+}
+
+impl PhaseTracker {
+    /// Tracker of the bursts of `neurons`.
+    pub fn new(neurons: Range<usize>, min_gap: Milliseconds) -> Self {
+        Self {
+            neurons,
+            min_gap,
+            last_spike: None,
+            last_onset: None,
+            period: None,
+        }
+    }
+
+    /// Record the neurons that fired at `time`.
+    pub fn observe(&mut self, time: Milliseconds, fired: &[usize]) {
+        if !fired.iter().any(|i| self.neurons.contains(i)) {
+            return;
+        }
+        let onset = self
+            .last_spike
+            .is_none_or(|last| time - last > self.min_gap);
+        if onset {
+            if let Some(previous) = self.last_onset {
+                self.period = Some(time - previous);
+            }
+            self.last_onset = Some(time);
+        }
+        self.last_spike = Some(time);
+    }
+
+    /// Onset of the last burst.
+    pub fn last_onset(&self) -> Option<Milliseconds> {
+        self.last_onset
+    }
+
+    /// Time between the last two burst onsets.
+    pub fn period(&self) -> Option<Milliseconds> {
+        self.period
+    }
+
+    /// Phase in `[0, 1)` at `time`, `None` before two onsets.
+    pub fn phase(&self, time: Milliseconds) -> Option<f64> {
+        let period = self.period?;
+        let onset = self.last_onset?;
+        Some(((time - onset) / period).rem_euclid(1.0))
+    }
+}
+
+/// Onsets of the bursts of the population `neurons` in `spikes`.
+///
+/// The spikes of all neurons are merged into one train before burst
+/// detection, so `params` applies to the population rhythm.
+pub fn burst_onsets(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    params: &MaxIntervalParams,
+) -> Vec<Milliseconds> {
+    let mut train: Vec<Milliseconds> = spikes
+        .iter()
+        .filter(|s| neurons.contains(&s.neuron_id))
+        .map(|s| s.time)
+        .collect();
+    train.sort_by(|a, b| a.0.total_cmp(&b.0));
+    detect_bursts(neurons.start, &train, params)
+        .iter()
+        .map(|b| b.start)
+        .collect()
+}
+
+/// Phase in `[0, 1)` of `time` within the cycle of `onsets` containing it,
+/// or `None` outside the first and last onsets.
+pub fn phase_at(onsets: &[Milliseconds], time: Milliseconds) -> Option<f64> {
+    let next = onsets.iter().position(|&t| t > time)?;
+    if next == 0 {
+        return None;
+    }
+    let (start, end) = (onsets[next - 1], onsets[next]);
+    Some((time - start) / (end - start))
+}
+
+/// Phase of each burst onset of `other` within the cycles of `reference`.
+///
+/// Onsets outside the reference cycles are skipped. For oscillators in
+/// antiphase the phases cluster around 0.5.
+pub fn relative_phases(reference: &[Milliseconds], other: &[Milliseconds]) -> Vec<f64> {
+    other
+        .iter()
+        .filter_map(|&t| phase_at(reference, t))
+        .collect()
+}
+
+/// Circular mean of `phases` in `[0, 1)` and its vector strength in
+/// `[0, 1]`, where 1 means every phase is equal; `None` when empty.
+pub fn mean_phase(phases: &[f64]) -> Option<(f64, f64)> {
+    if phases.is_empty() {
+        return None;
+    }
+    let n = phases.len() as f64;
+    let x = phases.iter().map(|p| (TAU * p).cos()).sum::<f64>() / n;
+    let y = phases.iter().map(|p| (TAU * p).sin()).sum::<f64>() / n;
+    let mean = (y.atan2(x) / TAU).rem_euclid(1.0);
+    Some((mean, x.hypot(y)))
+}
@@ -0,0 +1,162 @@
+//! dataset.rs
+//!
+//! MNIST dataset loading and conversion to spike trains.
+//!
+//! The unsupervised STDP digit-recognition experiment (Diehl & Cook, 2015)
+//! is the canonical benchmark for spiking networks. This module reads the
+//! MNIST images and labels in their original IDX format, normalizes pixel
+//! intensities to `[0, 1]`, and encodes each image into input spikes using
+//! the rate or latency encoders.
+//!
+//! Enabled by the `mnist` feature.
+
+use crate::encoding::{LatencyEncoder, PoissonEncoder};
+use crate::pipeline::LabeledDataset;
+use crate::rng::Rng;
+use crate::spike::Spike;
+use crate::util::invalid;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// IDX magic number of an unsigned-byte 3D tensor (images).
+const IDX_IMAGES_MAGIC: u32 = 0x0000_0803;
+/// IDX magic number of an unsigned-byte 1D tensor (labels).
+const IDX_LABELS_MAGIC: u32 = 0x0000_0801;
+
+/// An MNIST split with normalized images.
+#[derive(Debug, Clone)]
+pub struct Mnist {
+    /// Images as row-major pixel intensities in `[0, 1]`
+    pub images: Vec<Vec<f64>>,
+    /// Digit label of each image
+    pub labels: Vec<u8>,
+    /// Image height in pixels
+    pub rows: usize,
+    /// Image width in pixels
+    pub cols: usize,
+}
+
+impl Mnist {
+    /// Load an image file and its matching label file, e.g.
+    /// `train-images-idx3-ubyte` and `train-labels-idx1-ubyte`.
+    pub fn load<P: AsRef<Path>, Q: AsRef<Path>>(images: P, labels: Q) -> io::Result<Self> {
+        let (raw, rows, cols) = read_idx_images(images)?;
+        let labels = read_idx_labels(labels)?;
+        if labels.len() != raw.len() {
+            return Err(invalid(format!(
+                "{} images but {} labels",
+                raw.len(),
+                labels.len()
+            )));
+        }
+        let images = raw
+            .into_iter()
+            .map(|img| img.into_iter().map(|p| p as f64 / 255.0).collect())
+            .collect();
+        Ok(Self {
+            images,
+            labels,
+            rows,
+            cols,
+        })
+    }
+
+    /// Number of images.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether the split contains no images.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Images and labels as a `LabeledDataset` for `Pipeline`.
+    pub fn to_dataset(&self) -> LabeledDataset {
+        LabeledDataset::new(
+            self.images.clone(),
+            self.labels.iter().map(|&l| l as usize).collect(),
+        )
+    }
+
+    /// Rate-encode image `index` into Poisson spike trains, one input
+    /// neuron per pixel.
+    pub fn rate_encode(&self, index: usize, encoder: &PoissonEncoder, rng: &mut Rng) -> Vec<Spike> {
+        encoder.encode(&self.images[index], rng)
+    }
+
+    /// Latency-encode image `index`: brighter pixels fire earlier.
+    pub fn latency_encode(&self, index: usize, encoder: &LatencyEncoder) -> Vec<Spike> {
+        encoder.encode(&self.images[index])
+    }
+}
+
+/// Read an IDX image file into raw pixel vectors plus `(rows, cols)`.
+///
+/// Fails with `InvalidData` if the file is shorter than its header claims.
+pub fn read_idx_images<P: AsRef<Path>>(path: P) -> io::Result<(Vec<Vec<u8>>, usize, usize)> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    expect_magic(&mut reader, IDX_IMAGES_MAGIC)?;
+    let count = read_u32(&mut reader)? as usize;
+    let rows = read_u32(&mut reader)? as usize;
+    let cols = read_u32(&mut reader)? as usize;
+    let pixels = rows.checked_mul(cols);
+    expect_payload(file_len, 16, pixels.and_then(|p| p.checked_mul(count)))?;
+
+    let mut images = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut pixels = vec![0u8; rows * cols];
+        reader.read_exact(&mut pixels)?;
+        images.push(pixels);
+    }
+    Ok((images, rows, cols))
+}
+
+/// Read an IDX label file.
+///
+/// Fails with `InvalidData` if the file is shorter than its header claims.
+pub fn read_idx_labels<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    expect_magic(&mut reader, IDX_LABELS_MAGIC)?;
+    let count = read_u32(&mut reader)? as usize;
+    expect_payload(file_len, 8, Some(count))?;
+    let mut labels = vec![0u8; count];
+    reader.read_exact(&mut labels)?;
+    Ok(labels)
+}
+
+/// Fail with `InvalidData` unless a file of `file_len` bytes holds
+/// `payload` bytes after its `header_len`-byte header; `None` means the
+/// header sizes overflowed.
+fn expect_payload(file_len: u64, header_len: u64, payload: Option<usize>) -> io::Result<()> {
+    let available = file_len.saturating_sub(header_len);
+    match payload {
+        Some(bytes) if bytes as u64 <= available => Ok(()),
+        _ => Err(invalid(format!(
+            "IDX header describes more data than the {available} bytes in the file"
+        ))),
+    }
+}
+
+/// Read a big-endian `u32`.
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// Fail with `InvalidData` unless the next word is `magic`.
+fn expect_magic<R: Read>(reader: &mut R, magic: u32) -> io::Result<()> {
+    let found = read_u32(reader)?;
+    if found != magic {
+        return Err(invalid(format!(
+            "Unexpected IDX magic number {found:#010x}, expected {magic:#010x}"
+        )));
+    }
+    Ok(())
+}
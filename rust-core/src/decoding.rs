@@ -0,0 +1,136 @@
+//! decoding.rs
+//!
+//! Readouts turning spike trains into feature vectors.
+//!
+//! Downstream classifiers and regressors work on numbers, not events. The
+//! decoders in this module summarize the activity of a range of neurons
+//! within a time window `[start, end)` as a plain `Vec<f64>` with one entry
+//! per neuron or population, ready to be fed to a readout.
+
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Number of spikes of each neuron in `neurons` during `[start, end)`.
+pub fn spike_counts(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Vec<f64> {
+    let mut counts = vec![0.0; neurons.len()];
+    for s in spikes {
+        if neurons.contains(&s.neuron_id) && s.time >= start && s.time < end {
+            counts[s.neuron_id - neurons.start] += 1.0;
+        }
+    }
+    counts
+}
+
+/// Firing rate (Hz) of each neuron in `neurons` during `[start, end)`.
+pub fn firing_rates(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Vec<f64> {
+    let secs = (end - start).as_secs();
+    let counts = spike_counts(spikes, neurons, start, end);
+    if secs <= 0.0 {
+        return vec![0.0; counts.len()];
+    }
+    counts.into_iter().map(|c| c / secs).collect()
+}
+
+/// Mean firing rate (Hz) of each population during `[start, end)`.
+///
+/// Empty populations have a rate of zero.
+pub fn population_rates(
+    spikes: &[Spike],
+    populations: &[Range<usize>],
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Vec<f64> {
+    populations
+        .iter()
+        .map(|p| {
+            let rates = firing_rates(spikes, p.clone(), start, end);
+            if rates.is_empty() {
+                0.0
+            } else {
+                rates.iter().sum::<f64>() / rates.len() as f64
+            }
+        })
+        .collect()
+}
+
+/// Latency (ms) of the first spike of each neuron in `neurons` relative to
+/// `start`, within `[start, end)`.
+///
+/// Neurons that stay silent are assigned the full window length
+/// `end - start`, so every entry is finite.
+pub fn first_spike_latencies(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Vec<f64> {
+    let window = (end - start).max(Milliseconds::ZERO);
+    let mut latencies = vec![window.0; neurons.len()];
+    for s in spikes {
+        if neurons.contains(&s.neuron_id) && s.time >= start && s.time < end {
+            let latency = &mut latencies[s.neuron_id - neurons.start];
+            *latency = latency.min((s.time - start).0);
+        }
+    }
+    latencies
+}
+
+/// Index (relative to `neurons.start`) of the neuron with the most spikes
+/// in `[start, end)`, ties going to the lowest index.
+///
+/// Returns `None` if no neuron in the range fired.
+pub fn most_active(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Option<usize> {
+    argmax(&spike_counts(spikes, neurons, start, end))
+        .filter(|&(_, c)| c > 0.0)
+        .map(|(k, _)| k)
+}
+
+/// Index (relative to `neurons.start`) of the neuron that fired first in
+/// `[start, end)`, ties going to the lowest index.
+///
+/// Returns `None` if no neuron in the range fired.
+pub fn first_to_fire(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+) -> Option<usize> {
+    spikes
+        .iter()
+        .filter(|s| neurons.contains(&s.neuron_id) && s.time >= start && s.time < end)
+        .min_by(|a, b| {
+            a.time
+                .0
+                .total_cmp(&b.time.0)
+                .then(a.neuron_id.cmp(&b.neuron_id))
+        })
+        .map(|s| s.neuron_id - neurons.start)
+}
+
+/// Position and value of the largest entry, ties going to the lowest index.
+fn argmax(values: &[f64]) -> Option<(usize, f64)> {
+    values
+        .iter()
+        .copied()
+        .enumerate()
+        .fold(None, |best, (k, v)| match best {
+            Some((_, b)) if b >= v => best,
+            _ => Some((k, v)),
+        })
+}
@@ -0,0 +1,691 @@
+//! encoding.rs
+//!
+//! Conversion of analog data into spike trains.
+//!
+//! Spiking networks consume events, not numbers, so external data such as
+//! pixel intensities has to be encoded into spikes first. Encoders in this
+//! module map a vector of analog values onto spike trains of a matching
+//! set of input neurons, using an explicitly seeded `Rng` wherever the code
+//! is stochastic.
+
+use crate::rng::Rng;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+
+/// Poisson rate encoder.
+///
+/// Each value drives an independent Poisson process with rate
+/// `value * max_rate_hz`, so larger values produce more spikes. Negative
+/// values are treated as zero.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoissonEncoder {
+    /// Firing rate for a value of `1.0` (Hz)
+    pub max_rate_hz: f64,
+    /// Length of the generated spike trains
+    pub duration: Milliseconds,
+    /// Time of the start of the trains
+    pub start: Milliseconds,
+    /// Neuron id receiving the first value
+    pub first_neuron: usize,
+}
+
+impl PoissonEncoder {
+    /// Create an encoder starting at time zero on neuron `0`.
+    pub fn new(max_rate_hz: f64, duration: Milliseconds) -> Self {
+        Self {
+            max_rate_hz,
+            duration,
+            start: Milliseconds::ZERO,
+            first_neuron: 0,
+        }
+    }
+
+    /// Encode `values` into spikes sorted by time, then neuron id.
+    ///
+    /// Value `k` drives neuron `first_neuron + k`.
+    pub fn encode(&self, values: &[f64], rng: &mut Rng) -> Vec<Spike> {
+        let mut spikes = Vec::new();
+        for (k, &value) in values.iter().enumerate() {
+            let rate_per_ms = (value.max(0.0) * self.max_rate_hz) / 1000.0;
+            if rate_per_ms <= 0.0 || !rate_per_ms.is_finite() {
+                continue;
+            }
+            let mut t = rng.exponential(rate_per_ms);
+            while t < self.duration.0 {
+                spikes.push(Spike::new(self.first_neuron + k, self.start + Milliseconds(t)));
+                t += rng.exponential(rate_per_ms);
+            }
+        }
+        sort_spikes(&mut spikes);
+        spikes
+    }
+}
+
+/// Latency (time-to-first-spike) encoder.
+///
+/// Each value in `[0, 1]` is mapped to a single spike whose latency within
+/// the coding window decreases linearly with the value: `1.0` fires at the
+/// start of the window and values just above `threshold` near its end.
+/// Values at or below `threshold` produce no spike; values above `1.0` are
+/// treated as `1.0`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyEncoder {
+    /// Length of the coding window
+    pub window: Milliseconds,
+    /// Values at or below this level stay silent
+    pub threshold: f64,
+    /// Time of the start of the window
+    pub start: Milliseconds,
+    /// Neuron id receiving the first value
+    pub first_neuron: usize,
+}
+
+impl LatencyEncoder {
+    /// Create an encoder starting at time zero on neuron `0`.
+    pub fn new(window: Milliseconds) -> Self {
+        Self {
+            window,
+            threshold: 0.0,
+            start: Milliseconds::ZERO,
+            first_neuron: 0,
+        }
+    }
+
+    /// Spike latency for `value` relative to the window start, if it fires.
+    pub fn latency(&self, value: f64) -> Option<Milliseconds> {
+        (value > self.threshold).then(|| self.window * (1.0 - value.min(1.0)))
+    }
+
+    /// Encode `values` into at most one spike per neuron, sorted by time,
+    /// then neuron id.
+    ///
+    /// Value `k` drives neuron `first_neuron + k`.
+    pub fn encode(&self, values: &[f64]) -> Vec<Spike> {
+        let mut spikes: Vec<Spike> = values
+            .iter()
+            .enumerate()
+            .filter_map(|(k, &v)| {
+                self.latency(v)
+                    .map(|t| Spike::new(self.first_neuron + k, self.start + t))
+            })
+            .collect();
+        sort_spikes(&mut spikes);
+        spikes
+    }
+}
+
+/// Rank-order encoder and decoder.
+///
+/// Following Thorpe's rank-order code, only the order in which neurons
+/// fire carries information: the largest value fires first, the next
+/// largest second, and so on, one spike per neuron `interval` apart. Values
+/// at or below `threshold` stay silent; ties fire in neuron order. Decoding
+/// recovers a value of `modulation^rank` for each neuron, the desensitizing
+/// weighting used by rank-order readouts.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankOrderEncoder {
+    /// Time between spikes of consecutive ranks
+    pub interval: Milliseconds,
+    /// Values at or below this level stay silent
+    pub threshold: f64,
+    /// Decoded value ratio between consecutive ranks, in `(0, 1]`
+    pub modulation: f64,
+    /// Time of the first spike
+    pub start: Milliseconds,
+    /// Neuron id receiving the first value
+    pub first_neuron: usize,
+}
+
+impl RankOrderEncoder {
+    /// Create an encoder starting at time zero on neuron `0`.
+    pub fn new(interval: Milliseconds) -> Self {
+        Self {
+            interval,
+            threshold: 0.0,
+            modulation: 0.9,
+            start: Milliseconds::ZERO,
+            first_neuron: 0,
+        }
+    }
+
+    /// Encode `values` into at most one spike per neuron, largest first.
+    ///
+    /// Value `k` drives neuron `first_neuron + k`.
+    pub fn encode(&self, values: &[f64]) -> Vec<Spike> {
+        let mut order: Vec<usize> = (0..values.len())
+            .filter(|&k| values[k] > self.threshold)
+            .collect();
+        order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+        order
+            .into_iter()
+            .enumerate()
+            .map(|(rank, k)| {
+                let t = self.start + self.interval * rank as f64;
+                Spike::new(self.first_neuron + k, t)
+            })
+            .collect()
+    }
+
+    /// Firing rank of each of `len` neurons in `spikes`, or `None` for
+    /// neurons that did not fire. Only the first spike of a neuron counts.
+    pub fn ranks(&self, spikes: &[Spike], len: usize) -> Vec<Option<usize>> {
+        let mut sorted = spikes.to_vec();
+        sort_spikes(&mut sorted);
+        let mut ranks = vec![None; len];
+        let mut next = 0;
+        for s in sorted {
+            let Some(k) = s.neuron_id.checked_sub(self.first_neuron) else {
+                continue;
+            };
+            if let Some(rank @ None) = ranks.get_mut(k) {
+                *rank = Some(next);
+                next += 1;
+            }
+        }
+        ranks
+    }
+
+    /// Decode `spikes` into `modulation^rank` per neuron, or `0` for
+    /// neurons that did not fire.
+    pub fn decode(&self, spikes: &[Spike], len: usize) -> Vec<f64> {
+        self.ranks(spikes, len)
+            .into_iter()
+            .map(|rank| rank.map_or(0.0, |r| self.modulation.powi(r as i32)))
+            .collect()
+    }
+}
+
+/// Phase-of-firing encoder and decoder.
+///
+/// Values are represented by the phase at which a neuron fires relative to
+/// a reference oscillation, as in hippocampal theta-phase coding. A value
+/// `v` in `[0, 1)` fires once per cycle at phase `2πv` of the reference,
+/// whose phase is zero at `start`, for `cycles` cycles. Values
+/// wrap around, so `0.0` and `1.0` share a phase; negative and non-finite
+/// values stay silent.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseEncoder {
+    /// Frequency of the reference oscillation (Hz)
+    pub frequency_hz: f64,
+    /// Number of oscillation cycles to encode
+    pub cycles: usize,
+    /// Time at which the reference oscillation has phase zero
+    pub start: Milliseconds,
+    /// Neuron id receiving the first value
+    pub first_neuron: usize,
+}
+
+impl PhaseEncoder {
+    /// Create a single-cycle encoder starting at time zero on neuron `0`.
+    pub fn new(frequency_hz: f64) -> Self {
+        Self {
+            frequency_hz,
+            cycles: 1,
+            start: Milliseconds::ZERO,
+            first_neuron: 0,
+        }
+    }
+
+    /// Period of the reference oscillation.
+    pub fn period(&self) -> Milliseconds {
+        Milliseconds(1000.0 / self.frequency_hz)
+    }
+
+    /// Phase of the reference oscillation at `t`, in `[0, 2π)`.
+    pub fn phase_at(&self, t: Milliseconds) -> f64 {
+        let cycles = (t - self.start) / self.period();
+        std::f64::consts::TAU * cycles.rem_euclid(1.0)
+    }
+
+    /// Encode `values` into one spike per neuron and cycle, sorted by time,
+    /// then neuron id.
+    ///
+    /// Value `k` drives neuron `first_neuron + k`.
+    pub fn encode(&self, values: &[f64]) -> Vec<Spike> {
+        let period = self.period();
+        let mut spikes = Vec::new();
+        if !period.0.is_finite() || period.0 <= 0.0 {
+            return spikes;
+        }
+        for (k, &v) in values.iter().enumerate() {
+            if v < 0.0 || !v.is_finite() {
+                continue;
+            }
+            let offset = period * v.rem_euclid(1.0);
+            for cycle in 0..self.cycles {
+                let t = self.start + period * cycle as f64 + offset;
+                spikes.push(Spike::new(self.first_neuron + k, t));
+            }
+        }
+        sort_spikes(&mut spikes);
+        spikes
+    }
+
+    /// Decode the values of `len` neurons from the circular mean firing
+    /// phase of their spikes, in `[0, 1)`.
+    ///
+    /// Neurons without spikes decode to `None`.
+    pub fn decode(&self, spikes: &[Spike], len: usize) -> Vec<Option<f64>> {
+        let mut sums = vec![(0.0, 0.0, 0usize); len];
+        for s in spikes {
+            let Some(k) = s.neuron_id.checked_sub(self.first_neuron) else {
+                continue;
+            };
+            if let Some((x, y, n)) = sums.get_mut(k) {
+                let phase = self.phase_at(s.time);
+                *x += phase.cos();
+                *y += phase.sin();
+                *n += 1;
+            }
+        }
+        sums.into_iter()
+            .map(|(x, y, n)| {
+                (n > 0).then(|| {
+                    let phase = f64::atan2(y, x).rem_euclid(std::f64::consts::TAU);
+                    (phase / std::f64::consts::TAU).rem_euclid(1.0)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Population encoder with overlapping Gaussian receptive fields.
+///
+/// A scalar in `[min, max]` is represented by `size` neurons whose
+/// preferred values are evenly spread over the range. Each neuron's
+/// activation is `exp(-(x - μ_k)² / 2σ²)`, which is then turned into spikes
+/// by latency coding (strongly activated neurons fire first) or by Poisson
+/// rate coding. With `circular`, distances wrap around the range, which
+/// suits angles and other periodic variables.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GaussianPopulationEncoder {
+    /// Number of neurons
+    pub size: usize,
+    /// Lower end of the encoded range
+    pub min: f64,
+    /// Upper end of the encoded range
+    pub max: f64,
+    /// Tuning curve width σ
+    pub sigma: f64,
+    /// Whether the range is periodic
+    pub circular: bool,
+    /// Neuron id of the first neuron in the population
+    pub first_neuron: usize,
+}
+
+impl GaussianPopulationEncoder {
+    /// Create an encoder for `[min, max]` whose tuning curves overlap
+    /// their neighbours at roughly half height.
+    pub fn new(size: usize, min: f64, max: f64) -> Self {
+        let spacing = (max - min) / size.saturating_sub(1).max(1) as f64;
+        Self {
+            size,
+            min,
+            max,
+            sigma: spacing / 1.5,
+            circular: false,
+            first_neuron: 0,
+        }
+    }
+
+    /// Preferred value of neuron `k`.
+    pub fn center(&self, k: usize) -> f64 {
+        let range = self.max - self.min;
+        if self.circular {
+            self.min + range * k as f64 / self.size.max(1) as f64
+        } else {
+            self.min + range * k as f64 / self.size.saturating_sub(1).max(1) as f64
+        }
+    }
+
+    /// Tuning curve activation of every neuron for `x`, in `[0, 1]`.
+    pub fn activations(&self, x: f64) -> Vec<f64> {
+        let range = self.max - self.min;
+        (0..self.size)
+            .map(|k| {
+                let mut d = (x - self.center(k)).abs();
+                if self.circular && range > 0.0 {
+                    d %= range;
+                    d = d.min(range - d);
+                }
+                (-(d * d) / (2.0 * self.sigma * self.sigma)).exp()
+            })
+            .collect()
+    }
+
+    /// Encode `x` with one latency-coded spike per sufficiently activated
+    /// neuron.
+    pub fn encode_latency(&self, x: f64, encoder: &LatencyEncoder) -> Vec<Spike> {
+        let encoder = LatencyEncoder {
+            first_neuron: self.first_neuron,
+            ..encoder.clone()
+        };
+        encoder.encode(&self.activations(x))
+    }
+
+    /// Encode `x` as Poisson spike trains with rates proportional to the
+    /// activations.
+    pub fn encode_rate(&self, x: f64, encoder: &PoissonEncoder, rng: &mut Rng) -> Vec<Spike> {
+        let encoder = PoissonEncoder {
+            first_neuron: self.first_neuron,
+            ..encoder.clone()
+        };
+        encoder.encode(&self.activations(x), rng)
+    }
+}
+
+/// Most spikes a `DeltaEncoder` emits for one sample.
+pub const MAX_DELTA_SPIKES: usize = 1 << 16;
+
+/// Delta-modulation encoder for sampled signals.
+///
+/// Like a DVS pixel, the encoder tracks a reference level and emits a
+/// spike on the ON channel each time the signal rises `threshold` above
+/// it, or on the OFF channel each time it falls `threshold` below it,
+/// moving the reference by `threshold` per spike. Slowly varying signals
+/// therefore produce few spikes and fast changes many. A single sample
+/// emits at most `MAX_DELTA_SPIKES` per channel; a larger jump emits that
+/// many and moves the reference straight to the sample. Samples that are
+/// NaN or infinite are skipped.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeltaEncoder {
+    /// Signal change that triggers a spike
+    pub threshold: f64,
+    /// Neuron id of the ON channel; the OFF channel is `on_neuron + 1`
+    pub on_neuron: usize,
+}
+
+impl DeltaEncoder {
+    /// Create an encoder with ON/OFF channels on neurons `0` and `1`.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            on_neuron: 0,
+        }
+    }
+
+    /// Neuron id of the OFF channel.
+    pub fn off_neuron(&self) -> usize {
+        self.on_neuron + 1
+    }
+
+    /// Encode a signal given as `(time, value)` samples in time order.
+    ///
+    /// The first finite sample sets the reference level.
+    ///
+    /// # Panics
+    /// Panics if `threshold` is not positive and finite.
+    pub fn encode(&self, samples: &[(Milliseconds, f64)]) -> Vec<Spike> {
+        let mut reference = None;
+        let mut spikes = Vec::new();
+        for &(t, value) in samples {
+            self.step(&mut reference, t, value, &mut spikes);
+        }
+        spikes
+    }
+
+    /// Start a streaming encoder with this configuration.
+    ///
+    /// # Panics
+    /// The stream panics like `encode`.
+    pub fn stream(&self) -> DeltaStream {
+        DeltaStream {
+            encoder: self.clone(),
+            reference: None,
+        }
+    }
+
+    /// Process one sample against `reference`, appending emitted spikes.
+    pub(crate) fn step(
+        &self,
+        reference: &mut Option<f64>,
+        t: Milliseconds,
+        value: f64,
+        spikes: &mut Vec<Spike>,
+    ) {
+        assert!(
+            self.threshold > 0.0 && self.threshold.is_finite(),
+            "Delta threshold must be positive and finite, got {}",
+            self.threshold
+        );
+        if !value.is_finite() {
+            return;
+        }
+        let Some(level) = reference.as_mut() else {
+            *reference = Some(value);
+            return;
+        };
+        let steps = ((value - *level).abs() / self.threshold).floor();
+        if steps < 1.0 {
+            return;
+        }
+        let neuron = if value > *level {
+            self.on_neuron
+        } else {
+            self.off_neuron()
+        };
+        if steps > MAX_DELTA_SPIKES as f64 {
+            *level = value;
+            spikes.extend((0..MAX_DELTA_SPIKES).map(|_| Spike::new(neuron, t)));
+            return;
+        }
+        *level += (value - *level).signum() * steps * self.threshold;
+        spikes.extend((0..steps as usize).map(|_| Spike::new(neuron, t)));
+    }
+}
+
+/// Cochlear filterbank encoder for audio.
+///
+/// Audio samples are split into frequency channels by a bank of band-pass
+/// filters with centre frequencies evenly spaced on the ERB scale and
+/// bandwidths of one equivalent rectangular bandwidth, a cheap stand-in for
+/// a gammatone filterbank. Each channel is half-wave rectified, like inner
+/// hair cells, and drives an integrate-and-fire unit that emits a spike on
+/// neuron `first_neuron + k` whenever the integrated output of channel `k`
+/// reaches `threshold`, so louder bands fire faster.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CochlearEncoder {
+    /// Audio sample rate (Hz)
+    pub sample_rate_hz: f64,
+    /// Centre frequency of each channel (Hz), lowest first
+    pub center_frequencies: Vec<f64>,
+    /// Integrated rectified amplitude (amplitude × ms) per spike
+    pub threshold: f64,
+    /// Time of the first sample
+    pub start: Milliseconds,
+    /// Neuron id of the lowest channel
+    pub first_neuron: usize,
+}
+
+impl CochlearEncoder {
+    /// Create an encoder with `channels` ERB-spaced channels between
+    /// `min_hz` and `max_hz`, starting at time zero on neuron `0`.
+    pub fn new(sample_rate_hz: f64, channels: usize, min_hz: f64, max_hz: f64) -> Self {
+        let (lo, hi) = (hz_to_erb_rate(min_hz), hz_to_erb_rate(max_hz));
+        let center_frequencies = (0..channels)
+            .map(|k| {
+                let frac = k as f64 / channels.saturating_sub(1).max(1) as f64;
+                erb_rate_to_hz(lo + (hi - lo) * frac)
+            })
+            .collect();
+        Self {
+            sample_rate_hz,
+            center_frequencies,
+            threshold: 1.0,
+            start: Milliseconds::ZERO,
+            first_neuron: 0,
+        }
+    }
+
+    /// Band-pass filtered output of every channel.
+    ///
+    /// Channels at or above the Nyquist frequency are silent.
+    pub fn filterbank(&self, samples: &[f64]) -> Vec<Vec<f64>> {
+        self.center_frequencies
+            .iter()
+            .map(|&f| {
+                if f <= 0.0 || f >= self.sample_rate_hz / 2.0 {
+                    return vec![0.0; samples.len()];
+                }
+                let bandwidth = 24.7 * (4.37 * f / 1000.0 + 1.0);
+                let mut first = Biquad::band_pass(f, f / bandwidth, self.sample_rate_hz);
+                let mut second = first.clone();
+                samples
+                    .iter()
+                    .map(|&x| second.process(first.process(x)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Encode `samples` into spikes sorted by time, then neuron id.
+    pub fn encode(&self, samples: &[f64]) -> Vec<Spike> {
+        let dt = 1000.0 / self.sample_rate_hz;
+        let mut spikes = Vec::new();
+        if self.threshold <= 0.0 || !dt.is_finite() {
+            return spikes;
+        }
+        for (k, channel) in self.filterbank(samples).into_iter().enumerate() {
+            let mut charge = 0.0;
+            for (n, y) in channel.into_iter().enumerate() {
+                charge += y.max(0.0) * dt;
+                if charge >= self.threshold {
+                    charge -= self.threshold;
+                    let t = self.start + Milliseconds(n as f64 * dt);
+                    spikes.push(Spike::new(self.first_neuron + k, t));
+                }
+            }
+        }
+        sort_spikes(&mut spikes);
+        spikes
+    }
+}
+
+/// Frequency (Hz) on the ERB-rate scale of Glasberg & Moore (1990).
+fn hz_to_erb_rate(hz: f64) -> f64 {
+    21.4 * (4.37 * hz / 1000.0 + 1.0).log10()
+}
+
+/// Inverse of `hz_to_erb_rate`.
+fn erb_rate_to_hz(erb: f64) -> f64 {
+    (10f64.powf(erb / 21.4) - 1.0) * 1000.0 / 4.37
+}
+
+/// Second-order IIR filter section (direct form I).
+#[derive(Debug, Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    /// Band-pass with unit peak gain at `center_hz` and quality `q`.
+    fn band_pass(center_hz: f64, q: f64, sample_rate_hz: f64) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * center_hz / sample_rate_hz;
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [alpha / a0, 0.0, -alpha / a0],
+            a: [-2.0 * w0.cos() / a0, (1.0 - alpha) / a0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// Filter one sample.
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// Push-based encoder for live sample streams.
+///
+/// Samples are pushed one at a time, in time order, as they arrive from a
+/// sensor; each push returns the spikes it caused, ready to be passed to
+/// `Simulation::inject_spikes` before the next `Simulation::step`.
+pub trait StreamingEncoder {
+    /// Feed the sample `value` taken at time `t` and return the emitted
+    /// spikes in time order.
+    fn push_sample(&mut self, t: Milliseconds, value: f64) -> Vec<Spike>;
+}
+
+/// Streaming form of `DeltaEncoder`, created by `DeltaEncoder::stream`.
+#[derive(Debug, Clone)]
+pub struct DeltaStream {
+    encoder: DeltaEncoder,
+    reference: Option<f64>,
+}
+
+impl StreamingEncoder for DeltaStream {
+    fn push_sample(&mut self, t: Milliseconds, value: f64) -> Vec<Spike> {
+        let mut spikes = Vec::new();
+        self.encoder.step(&mut self.reference, t, value, &mut spikes);
+        spikes
+    }
+}
+
+/// Streaming Poisson rate encoder for a single input neuron.
+///
+/// Between consecutive samples the neuron fires as a Poisson process with
+/// rate `value * max_rate_hz` of the newer sample, so spikes for the
+/// interval ending at `t` are emitted when the sample at `t` arrives. The
+/// first sample only sets the start of the stream.
+#[derive(Debug, Clone)]
+pub struct RateStream {
+    /// Firing rate for a value of `1.0` (Hz)
+    pub max_rate_hz: f64,
+    /// Neuron id receiving the spikes
+    pub neuron: usize,
+    rng: Rng,
+    last: Option<Milliseconds>,
+}
+
+impl RateStream {
+    /// Create a stream driving `neuron`, seeded with `seed`.
+    pub fn new(max_rate_hz: f64, neuron: usize, seed: u64) -> Self {
+        Self {
+            max_rate_hz,
+            neuron,
+            rng: Rng::new(seed),
+            last: None,
+        }
+    }
+}
+
+impl StreamingEncoder for RateStream {
+    fn push_sample(&mut self, t: Milliseconds, value: f64) -> Vec<Spike> {
+        let Some(last) = self.last.replace(t) else {
+            return Vec::new();
+        };
+        let encoder = PoissonEncoder {
+            start: last,
+            first_neuron: self.neuron,
+            ..PoissonEncoder::new(self.max_rate_hz, (t - last).max(Milliseconds::ZERO))
+        };
+        encoder.encode(&[value], &mut self.rng)
+    }
+}
+
+/// Sort spikes by time, breaking ties by neuron id.
+pub(crate) fn sort_spikes(spikes: &mut [Spike]) {
+    spikes.sort_by(|a, b| {
+        a.time
+            .0
+            .total_cmp(&b.time.0)
+            .then(a.neuron_id.cmp(&b.neuron_id))
+    });
+}
@@ -0,0 +1,150 @@
+//! energy.rs
+//!
+//! Operation counting and energy estimates.
+//!
+//! The appeal of event-driven processing is that work, and therefore
+//! energy, scales with activity rather than with network size. To check
+//! that claim for a particular experiment, an `EnergyMonitor` counts the
+//! operations a neuromorphic chip would perform — neuron updates, spikes,
+//! synaptic events and plasticity updates — and an `EnergyModel` turns the
+//! counts into an estimate by assigning each operation a cost in
+//! picojoules.
+
+use crate::monitor::{Monitor, StepView};
+use crate::synapse::Plasticity;
+use crate::util::lock;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Energy cost per operation, in picojoules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnergyModel {
+    /// Cost of updating one neuron for one step
+    pub pj_per_neuron_update: f64,
+    /// Cost of emitting one spike
+    pub pj_per_spike: f64,
+    /// Cost of delivering a spike across one synapse
+    pub pj_per_synaptic_event: f64,
+    /// Cost of one plasticity update of a synapse
+    pub pj_per_plasticity_update: f64,
+}
+
+impl Default for EnergyModel {
+    /// Per-operation figures of the order reported for Intel's Loihi
+    /// (Davies et al., 2018).
+    fn default() -> Self {
+        Self {
+            pj_per_neuron_update: 52.0,
+            pj_per_spike: 1.7,
+            pj_per_synaptic_event: 23.6,
+            pj_per_plasticity_update: 120.0,
+        }
+    }
+}
+
+/// Operation counts of a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationCounts {
+    /// Simulation steps observed
+    pub steps: u64,
+    /// Neuron state updates
+    pub neuron_updates: u64,
+    /// Spikes emitted
+    pub spikes: u64,
+    /// Spikes delivered across synapses
+    pub synaptic_events: u64,
+    /// Synapse updates by plastic synapses whose pre- or post-synaptic
+    /// neuron fired
+    pub plasticity_updates: u64,
+}
+
+impl OperationCounts {
+    /// Estimated energy in picojoules under `model`.
+    pub fn energy_pj(&self, model: &EnergyModel) -> f64 {
+        self.neuron_updates as f64 * model.pj_per_neuron_update
+            + self.spikes as f64 * model.pj_per_spike
+            + self.synaptic_events as f64 * model.pj_per_synaptic_event
+            + self.plasticity_updates as f64 * model.pj_per_plasticity_update
+    }
+
+    /// Estimated energy in joules under `model`.
+    pub fn energy_joules(&self, model: &EnergyModel) -> f64 {
+        self.energy_pj(model) * 1e-12
+    }
+
+    /// Estimated energy per emitted spike in picojoules, or `None` if no
+    /// spike was emitted.
+    pub fn energy_per_spike_pj(&self, model: &EnergyModel) -> Option<f64> {
+        (self.spikes > 0).then(|| self.energy_pj(model) / self.spikes as f64)
+    }
+
+    /// Synaptic events a clock-driven dense implementation would have
+    /// performed instead, one per synapse per step, divided by the events
+    /// actually needed. Values above one quantify the saving of event-driven
+    /// transmission.
+    pub fn sparsity_gain(&self, num_synapses: usize) -> Option<f64> {
+        (self.synaptic_events > 0)
+            .then(|| (self.steps * num_synapses as u64) as f64 / self.synaptic_events as f64)
+    }
+}
+
+/// Shared handle counting operations after every step.
+///
+/// Synaptic events are counted for every outgoing synapse of a firing
+/// neuron, whether or not `SimulationConfig::synaptic_transmission` is
+/// enabled. Like other monitors it skips the warmup phase.
+#[derive(Debug, Clone, Default)]
+pub struct EnergyMonitor {
+    counts: Arc<Mutex<OperationCounts>>,
+}
+
+impl EnergyMonitor {
+    /// Create a monitor with all counts at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Operations counted so far.
+    pub fn counts(&self) -> OperationCounts {
+        *self.lock()
+    }
+
+    /// Estimated energy of the operations so far, in picojoules.
+    pub fn energy_pj(&self, model: &EnergyModel) -> f64 {
+        self.counts().energy_pj(model)
+    }
+
+    /// Reset all counts to zero.
+    pub fn reset(&self) {
+        *self.lock() = OperationCounts::default();
+    }
+
+    /// Lock the shared counts.
+    fn lock(&self) -> MutexGuard<'_, OperationCounts> {
+        lock(&self.counts)
+    }
+}
+
+impl Monitor for EnergyMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut counts = self.lock();
+        counts.steps += 1;
+        counts.neuron_updates += step.neurons.len() as u64;
+        counts.spikes += step.fired.len() as u64;
+        if step.fired.is_empty() {
+            return;
+        }
+        // `fired` is sorted, so membership is a binary search
+        let fired = |i: usize| step.fired.binary_search(&i).is_ok();
+        for syn in step.synapses {
+            let pre = fired(syn.pre_neuron);
+            if pre {
+                counts.synaptic_events += 1;
+            }
+            if syn.plasticity != Plasticity::Static {
+                counts.plasticity_updates += pre as u64 + fired(syn.post_neuron) as u64;
+            }
+        }
+    }
+}
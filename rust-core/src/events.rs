@@ -0,0 +1,239 @@
+//! events.rs
+//!
+//! Event-camera (DVS) input.
+//!
+//! Dynamic vision sensors report per-pixel brightness changes as a stream
+//! of `(t, x, y, polarity)` events, which map naturally onto input spikes.
+//! This module reads such recordings and maps pixel events to input neuron
+//! spikes, enabling neuromorphic vision datasets like N-MNIST and
+//! DVS-Gesture. Supported layouts:
+//!
+//! - CSV with columns `t_us,x,y,polarity` (time in microseconds, polarity
+//!   `1` for ON and `0` for OFF); a header line is optional.
+//! - The N-MNIST binary layout: 5 bytes per event — `x`, `y`, then a
+//!   24-bit big-endian word whose top bit is the polarity and whose low 23
+//!   bits are the timestamp in microseconds.
+//! - AEDAT 3.1 files; polarity event packets are decoded and all other
+//!   packet types are skipped.
+
+use crate::encoding::sort_spikes;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use std::path::Path;
+
+/// A single DVS pixel event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DvsEvent {
+    /// Event time
+    pub time: Milliseconds,
+    /// Pixel column
+    pub x: u16,
+    /// Pixel row
+    pub y: u16,
+    /// `true` for a brightness increase (ON), `false` for a decrease (OFF)
+    pub polarity: bool,
+}
+
+/// Read events from a `t_us,x,y,polarity` CSV file.
+pub fn read_events_csv<P: AsRef<Path>>(path: P) -> io::Result<Vec<DvsEvent>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (n == 0 && line.starts_with(|c: char| c.is_alphabetic())) {
+            continue;
+        }
+        match parse_csv_event(line) {
+            Some(event) => events.push(event),
+            None => {
+                return Err(invalid(format!(
+                    "Malformed event on line {}: {line}",
+                    n + 1
+                )))
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Read events in the N-MNIST 40-bit binary layout.
+pub fn read_nmnist_bin<P: AsRef<Path>>(path: P) -> io::Result<Vec<DvsEvent>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    if bytes.len() % 5 != 0 {
+        return Err(invalid(
+            "N-MNIST file length is not a multiple of 5".to_string(),
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(5)
+        .map(|b| {
+            let word = u32::from_be_bytes([0, b[2], b[3], b[4]]);
+            DvsEvent {
+                time: Milliseconds((word & 0x7F_FFFF) as f64 / 1000.0),
+                x: b[0] as u16,
+                y: b[1] as u16,
+                polarity: word & 0x80_0000 != 0,
+            }
+        })
+        .collect())
+}
+
+/// Read polarity events from an AEDAT 3.1 file.
+///
+/// Fails with `InvalidData` if a packet header describes more data than
+/// the rest of the file holds.
+pub fn read_aedat31<P: AsRef<Path>>(path: P) -> io::Result<Vec<DvsEvent>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    // Skip the ASCII header, which ends with "#!END-HEADER".
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Err(invalid("AEDAT header not terminated".to_string()));
+        }
+        if line.starts_with(b"#!END-HEADER") {
+            break;
+        }
+        if !line.starts_with(b"#") {
+            return Err(invalid("Not an AEDAT 3.1 file".to_string()));
+        }
+    }
+
+    const POLARITY_EVENT: i16 = 1;
+    let mut events = Vec::new();
+    let mut header = [0u8; 28];
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let field =
+            |k: usize| i32::from_le_bytes([header[k], header[k + 1], header[k + 2], header[k + 3]]);
+        let event_type = i16::from_le_bytes([header[0], header[1]]);
+        let event_size = field(4).max(0) as usize;
+        let ts_overflow = field(12) as i64;
+        let event_capacity = field(16).max(0) as usize;
+
+        let remaining = file_len.saturating_sub(reader.stream_position()?);
+        let body_len = event_size
+            .checked_mul(event_capacity)
+            .filter(|&len| len as u64 <= remaining)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "AEDAT packet of {event_capacity} events of {event_size} bytes \
+                     exceeds the {remaining} bytes left in the file"
+                ))
+            })?;
+        let mut body = vec![0u8; body_len];
+        reader.read_exact(&mut body)?;
+        if event_type != POLARITY_EVENT || event_size < 8 {
+            continue;
+        }
+        for e in body.chunks_exact(event_size) {
+            let data = u32::from_le_bytes([e[0], e[1], e[2], e[3]]);
+            let ts = i32::from_le_bytes([e[4], e[5], e[6], e[7]]) as i64;
+            if data & 1 == 0 {
+                continue; // invalid event
+            }
+            let micros = (ts_overflow << 31) | ts;
+            events.push(DvsEvent {
+                time: Milliseconds(micros as f64 / 1000.0),
+                x: ((data >> 17) & 0x7FFF) as u16,
+                y: ((data >> 2) & 0x7FFF) as u16,
+                polarity: data & 2 != 0,
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// Mapping from sensor pixels to input neurons.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PixelMapping {
+    /// Sensor width in pixels
+    pub width: usize,
+    /// Sensor height in pixels
+    pub height: usize,
+    /// Use separate neurons for ON and OFF events
+    pub split_polarity: bool,
+    /// Neuron id of pixel `(0, 0)`
+    pub first_neuron: usize,
+}
+
+impl PixelMapping {
+    /// Map a `width` × `height` sensor with separate ON and OFF neurons,
+    /// starting at neuron `0`.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            split_polarity: true,
+            first_neuron: 0,
+        }
+    }
+
+    /// Number of input neurons required.
+    pub fn num_neurons(&self) -> usize {
+        let channels = if self.split_polarity { 2 } else { 1 };
+        channels * self.width * self.height
+    }
+
+    /// Input neuron for `event`, or `None` if it lies outside the sensor.
+    ///
+    /// Pixels are numbered row-major; with `split_polarity`, all ON
+    /// neurons come first, followed by all OFF neurons.
+    pub fn neuron(&self, event: &DvsEvent) -> Option<usize> {
+        let (x, y) = (event.x as usize, event.y as usize);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let channel = usize::from(self.split_polarity && !event.polarity);
+        Some(self.first_neuron + (channel * self.height + y) * self.width + x)
+    }
+
+    /// Convert events into input spikes sorted by time, shifting times so
+    /// the first event occurs at `start`. Out-of-range events are dropped.
+    pub fn to_spikes(&self, events: &[DvsEvent], start: Milliseconds) -> Vec<Spike> {
+        let t0 = events
+            .iter()
+            .map(|e| e.time)
+            .reduce(Milliseconds::min)
+            .unwrap_or(Milliseconds::ZERO);
+        let mut spikes: Vec<Spike> = events
+            .iter()
+            .filter_map(|e| self.neuron(e).map(|i| Spike::new(i, start + (e.time - t0))))
+            .collect();
+        sort_spikes(&mut spikes);
+        spikes
+    }
+}
+
+/// Parse one `t_us,x,y,polarity` line.
+fn parse_csv_event(line: &str) -> Option<DvsEvent> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [t, x, y, p] = fields.as_slice() else {
+        return None;
+    };
+    let polarity = match *p {
+        "1" | "true" => true,
+        "0" | "-1" | "false" => false,
+        _ => return None,
+    };
+    Some(DvsEvent {
+        time: Milliseconds(t.parse::<f64>().ok()? / 1000.0),
+        x: x.parse().ok()?,
+        y: y.parse().ok()?,
+        polarity,
+    })
+}
@@ -0,0 +1,236 @@
+//! experiment.rs
+//!
+//! Repeated trials with aggregated statistics.
+//!
+//! A single simulation run says little when weights, inputs, or
+//! connectivity are random: results have to be repeated over seeds and
+//! reported as mean ± standard deviation. An `Experiment` runs the same
+//! trial function for a series of seeds, optionally in parallel, collects
+//! the named metrics each trial returns, and summarizes them in one
+//! `ExperimentReport` that can be printed as a table or written to CSV.
+
+use crate::compression;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+use crate::util::mean_std;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Named metric values of one trial.
+pub type Metrics = BTreeMap<String, f64>;
+
+/// Settings for a series of trials.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Experiment {
+    /// Name shown in the report
+    pub name: String,
+    /// Number of trials
+    pub trials: usize,
+    /// Seed of the first trial; trial `k` uses `base_seed + k`
+    pub base_seed: u64,
+    /// Distribute trials across the available CPU cores (ignored on wasm32)
+    pub parallel: bool,
+}
+
+impl Experiment {
+    /// Describe `trials` sequential trials seeded from zero.
+    pub fn new(name: &str, trials: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            trials,
+            base_seed: 0,
+            parallel: false,
+        }
+    }
+
+    /// Seeds of the trials, in order.
+    pub fn seeds(&self) -> Vec<u64> {
+        (0..self.trials as u64)
+            .map(|k| self.base_seed + k)
+            .collect()
+    }
+
+    /// Run `trial` once per seed and aggregate the returned metrics.
+    ///
+    /// The trial function should build everything random from its seed,
+    /// e.g. with `NetworkBuilder::seed`, so that trials are independent and
+    /// reproducible.
+    pub fn run<F>(&self, trial: F) -> ExperimentReport
+    where
+        F: Fn(u64) -> Metrics + Sync,
+    {
+        let seeds = self.seeds();
+        let serial = !self.parallel || seeds.len() < 2 || cfg!(target_arch = "wasm32");
+        let results: Vec<Metrics> = if serial {
+            seeds.iter().map(|&s| trial(s)).collect()
+        } else {
+            let workers = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+                .min(seeds.len());
+            let chunk_size = seeds.len().div_ceil(workers);
+            let trial = &trial;
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = seeds
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || chunk.iter().map(|&s| trial(s)).collect::<Vec<_>>())
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().expect("Experiment worker thread panicked"))
+                    .collect()
+            })
+        };
+        ExperimentReport::new(&self.name, seeds, results)
+    }
+}
+
+/// Statistics of one metric across trials.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricSummary {
+    /// Metric name
+    pub name: String,
+    /// Number of trials that reported the metric
+    pub count: usize,
+    /// Mean across trials
+    pub mean: f64,
+    /// Sample standard deviation across trials (zero for one trial)
+    pub std: f64,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+}
+
+/// Per-trial metrics and their summary.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExperimentReport {
+    /// Experiment name
+    pub name: String,
+    /// Seed of each trial
+    pub seeds: Vec<u64>,
+    /// Metrics of each trial, in seed order
+    pub trials: Vec<Metrics>,
+    /// One summary per metric, sorted by name
+    pub summary: Vec<MetricSummary>,
+}
+
+impl ExperimentReport {
+    /// Summarize `trials` run with `seeds`.
+    pub fn new(name: &str, seeds: Vec<u64>, trials: Vec<Metrics>) -> Self {
+        let mut values: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+        for metrics in &trials {
+            for (name, &v) in metrics {
+                values.entry(name).or_default().push(v);
+            }
+        }
+        let summary = values
+            .into_iter()
+            .map(|(name, v)| {
+                let n = v.len();
+                let (mean, population_std) = mean_std(&v);
+                let std = if n > 1 {
+                    population_std * (n as f64 / (n - 1) as f64).sqrt()
+                } else {
+                    0.0
+                };
+                MetricSummary {
+                    name: name.to_string(),
+                    count: n,
+                    mean,
+                    std,
+                    min: v.iter().copied().fold(f64::INFINITY, f64::min),
+                    max: v.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                }
+            })
+            .collect();
+        Self {
+            name: name.to_string(),
+            seeds,
+            trials,
+            summary,
+        }
+    }
+
+    /// Summary of metric `name`, if any trial reported it.
+    pub fn metric(&self, name: &str) -> Option<&MetricSummary> {
+        self.summary.iter().find(|m| m.name == name)
+    }
+
+    /// Write one row per trial with a `seed` column followed by one column
+    /// per metric; metrics a trial did not report are left empty.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        write!(writer, "seed")?;
+        for m in &self.summary {
+            write!(writer, ",{}", m.name)?;
+        }
+        writeln!(writer)?;
+        for (seed, metrics) in self.seeds.iter().zip(&self.trials) {
+            write!(writer, "{seed}")?;
+            for m in &self.summary {
+                match metrics.get(&m.name) {
+                    Some(v) => write!(writer, ",{v}")?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExperimentReport {
+    /// Table of mean, standard deviation, min and max per metric.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({} trials)", self.name, self.trials.len())?;
+        let width = self
+            .summary
+            .iter()
+            .map(|m| m.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        writeln!(
+            f,
+            "{:<width$}  {:>12}  {:>12}  {:>12}  {:>12}",
+            "metric", "mean", "std", "min", "max"
+        )?;
+        for m in &self.summary {
+            writeln!(
+                f,
+                "{:<width$}  {:>12.4}  {:>12.4}  {:>12.4}  {:>12.4}",
+                m.name, m.mean, m.std, m.min, m.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Common metrics of a finished run: `total_spikes`, `mean_rate_hz`,
+/// `mean_weight` and `std_weight`.
+pub fn run_metrics(sim: &Simulation, spikes: &[Spike]) -> Metrics {
+    let n = sim.neurons().len();
+    let secs = sim.time().as_secs();
+    let rate = if n > 0 && secs > 0.0 {
+        spikes.len() as f64 / n as f64 / secs
+    } else {
+        0.0
+    };
+    let weights: Vec<f64> = sim.synapses().iter().map(|s| s.weight).collect();
+    let (mean, std) = mean_std(&weights);
+    Metrics::from([
+        ("total_spikes".to_string(), spikes.len() as f64),
+        ("mean_rate_hz".to_string(), rate),
+        ("mean_weight".to_string(), mean),
+        ("std_weight".to_string(), std),
+    ])
+}
@@ -0,0 +1,101 @@
+//! feedforward.rs
+//!
+//! Layered feedforward network construction.
+//!
+//! Many spiking network experiments use a stack of layers (e.g. 784-400-10
+//! for digit classification) where each layer projects only to the next.
+//! `build_feedforward` wires such a network with a chosen inter-layer
+//! connectivity and weight initialization, and returns the simulation
+//! together with the neuron index range of every layer for input injection
+//! and readout.
+
+use crate::connectivity::{erdos_renyi, fixed_in_degree, Connection};
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::stdp::STDPParams;
+use std::ops::Range;
+
+/// Connectivity between consecutive layers.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerConnectivity {
+    /// Every neuron connects to every neuron of the next layer.
+    Full,
+    /// Each pair is connected independently with this probability.
+    Probability(f64),
+    /// Every neuron receives this many inputs from the previous layer.
+    FixedInDegree(usize),
+}
+
+/// A feedforward simulation together with its layer layout.
+pub struct FeedforwardNetwork {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Neuron index range of each layer, input layer first
+    pub layers: Vec<Range<usize>>,
+}
+
+impl FeedforwardNetwork {
+    /// Index range of the input layer.
+    pub fn input(&self) -> Range<usize> {
+        self.layers.first().cloned().unwrap_or(0..0)
+    }
+
+    /// Index range of the output layer.
+    pub fn output(&self) -> Range<usize> {
+        self.layers.last().cloned().unwrap_or(0..0)
+    }
+}
+
+/// Build an N-layer feedforward network.
+///
+/// Layers are registered as populations named `layer0`, `layer1`, ... so
+/// they can also be looked up with `Simulation::population`.
+///
+/// # Arguments
+/// * `layer_sizes` - Number of neurons per layer, input layer first
+/// * `neuron_params` - Parameters shared by every neuron
+/// * `connectivity` - Wiring between consecutive layers
+/// * `config` - Simulation configuration
+/// * `stdp_params` - STDP parameters for all synapses
+/// * `seed` - Seed for random connectivity and weight initialization
+/// * `weight` - Weight hook receiving `(pre, post, rng)`
+pub fn build_feedforward<W>(
+    layer_sizes: &[usize],
+    neuron_params: NeuronParams,
+    connectivity: LayerConnectivity,
+    config: SimulationConfig,
+    stdp_params: STDPParams,
+    seed: u64,
+    mut weight: W,
+) -> FeedforwardNetwork
+where
+    W: FnMut(usize, usize, &mut Rng) -> f64,
+{
+    let mut rng = Rng::new(seed);
+    let mut builder = NetworkBuilder::new();
+    let layers: Vec<Range<usize>> = layer_sizes
+        .iter()
+        .enumerate()
+        .map(|(k, &n)| builder.add_population(&format!("layer{k}"), n, neuron_params.clone()))
+        .collect();
+
+    for pair in layers.windows(2) {
+        let (pre, post) = (pair[0].clone(), pair[1].clone());
+        let connections: Vec<Connection> = match connectivity {
+            LayerConnectivity::Full => erdos_renyi(pre, post, 1.0, &mut rng, &mut weight),
+            LayerConnectivity::Probability(p) => erdos_renyi(pre, post, p, &mut rng, &mut weight),
+            LayerConnectivity::FixedInDegree(k) => {
+                fixed_in_degree(pre, post, k, &mut rng, &mut weight)
+            }
+        };
+        builder.connect_all(connections);
+    }
+
+    FeedforwardNetwork {
+        simulation: Simulation::from_network(builder.build(), config, stdp_params),
+        layers,
+    }
+}
@@ -0,0 +1,380 @@
+//! ffi.rs
+//!
+//! C interface for embedding the simulator.
+//!
+//! Robotics stacks and hardware test benches are mostly C or C++. This
+//! module exports a small C API over an opaque `NcSimulation` handle:
+//! create a network from an `NcConfig` or a TOML experiment configuration,
+//! advance it one step at a time with per-neuron input currents, and copy
+//! out the emitted spikes and current weights. The declarations are in
+//! `include/neuromorphic_core.h`, which must be kept in sync with this file;
+//! `tests/ffi_header.rs` fails when the two disagree.
+//!
+//! `cargo build --release --lib --features ffi` builds both a shared and a
+//! static library.
+//!
+//! Functions never unwind into C. Failures return null or zero, and
+//! `nc_last_error` then describes the most recent failure on the calling
+//! thread.
+
+use crate::config::ExperimentConfig;
+use crate::neuron::NeuronParams;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::units::Milliseconds;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Parameters of an all-to-all network created by `nc_create`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NcConfig {
+    /// Simulation time step (ms)
+    pub dt_ms: f64,
+    /// Membrane time constant (ms)
+    pub tau_m_ms: f64,
+    /// Resting membrane potential
+    pub v_rest: f64,
+    /// Firing threshold
+    pub v_thresh: f64,
+    /// Reset potential after a spike
+    pub v_reset: f64,
+    /// STDP potentiation rate
+    pub a_plus: f64,
+    /// STDP depression rate
+    pub a_minus: f64,
+    /// STDP potentiation time constant (ms)
+    pub tau_plus_ms: f64,
+    /// STDP depression time constant (ms)
+    pub tau_minus_ms: f64,
+    /// Minimum synaptic weight
+    pub w_min: f64,
+    /// Maximum synaptic weight
+    pub w_max: f64,
+    /// Initial weight of every synapse
+    pub initial_weight: f64,
+    /// Nonzero to transmit spikes through synapses
+    pub synaptic_transmission: u8,
+}
+
+impl Default for NcConfig {
+    fn default() -> Self {
+        Self {
+            dt_ms: 0.1,
+            tau_m_ms: 20.0,
+            v_rest: 0.0,
+            v_thresh: 1.0,
+            v_reset: 0.0,
+            a_plus: 0.01,
+            a_minus: 0.012,
+            tau_plus_ms: 20.0,
+            tau_minus_ms: 20.0,
+            w_min: 0.0,
+            w_max: 1.0,
+            initial_weight: 0.5,
+            synaptic_transmission: 1,
+        }
+    }
+}
+
+/// Simulation behind a C handle.
+pub struct NcSimulation {
+    sim: Simulation,
+    /// Per-neuron current from configured stimuli
+    stimulus: Box<dyn Fn(usize, Milliseconds) -> f64 + Sync>,
+    /// Spikes not yet copied out by `nc_get_spikes`
+    pending: Vec<Spike>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember `message` as the calling thread's last error.
+fn set_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning a panic into `fallback` and a recorded error.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Simulation panicked".to_string());
+        set_error(message);
+        fallback
+    })
+}
+
+/// Description of the last failure on this thread, or null. The string is
+/// owned by the library and valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn nc_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+}
+
+/// Fill `config` with default parameters.
+///
+/// # Safety
+/// `config` must be null or point to writable memory for an `NcConfig`.
+#[no_mangle]
+pub unsafe extern "C" fn nc_config_default(config: *mut NcConfig) {
+    if !config.is_null() {
+        config.write(NcConfig::default());
+    }
+}
+
+/// Create a network of `num_neurons` all-to-all connected neurons.
+///
+/// Returns null on invalid parameters.
+///
+/// # Safety
+/// `config` must be null, for the defaults, or point to a valid `NcConfig`.
+#[no_mangle]
+pub unsafe extern "C" fn nc_create(
+    num_neurons: usize,
+    config: *const NcConfig,
+) -> *mut NcSimulation {
+    let c = if config.is_null() {
+        NcConfig::default()
+    } else {
+        *config
+    };
+    if c.dt_ms.is_nan() || c.dt_ms <= 0.0 {
+        set_error("dt_ms must be positive");
+        return std::ptr::null_mut();
+    }
+    guard(std::ptr::null_mut(), || {
+        let params = NeuronParams {
+            tau_m: Milliseconds(c.tau_m_ms),
+            v_rest: c.v_rest,
+            v_thresh: c.v_thresh,
+            v_reset: c.v_reset,
+        };
+        let sim_config = SimulationConfig {
+            dt: Milliseconds(c.dt_ms),
+            t_max: Milliseconds(f64::INFINITY),
+            synaptic_transmission: c.synaptic_transmission != 0,
+            ..SimulationConfig::default()
+        };
+        let stdp = STDPParams {
+            a_plus: c.a_plus,
+            a_minus: c.a_minus,
+            tau_plus: Milliseconds(c.tau_plus_ms),
+            tau_minus: Milliseconds(c.tau_minus_ms),
+            w_min: c.w_min,
+            w_max: c.w_max,
+        };
+        let sim = Simulation::new(num_neurons, params, sim_config, stdp, c.initial_weight);
+        into_handle(sim, Box::new(|_, _| 0.0))
+    })
+}
+
+/// Create a simulation from a NUL-terminated TOML experiment
+/// configuration (see `config`), with its stimuli applied.
+///
+/// Returns null and sets the last error if the configuration is invalid.
+///
+/// # Safety
+/// `toml` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn nc_create_from_toml(toml: *const c_char) -> *mut NcSimulation {
+    if toml.is_null() {
+        set_error("Configuration is null");
+        return std::ptr::null_mut();
+    }
+    let Ok(text) = CStr::from_ptr(toml).to_str() else {
+        set_error("Configuration is not valid UTF-8");
+        return std::ptr::null_mut();
+    };
+    match ExperimentConfig::from_toml(text) {
+        Ok(config) => guard(std::ptr::null_mut(), || {
+            into_handle(config.build(), Box::new(config.input_current()))
+        }),
+        Err(e) => {
+            set_error(e.to_string());
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Box a simulation into a C handle.
+fn into_handle(
+    sim: Simulation,
+    stimulus: Box<dyn Fn(usize, Milliseconds) -> f64 + Sync>,
+) -> *mut NcSimulation {
+    Box::into_raw(Box::new(NcSimulation {
+        sim,
+        stimulus,
+        pending: Vec::new(),
+    }))
+}
+
+/// Free a simulation. Null is ignored.
+///
+/// # Safety
+/// `sim` must be null or a handle from `nc_create*` not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nc_destroy(sim: *mut NcSimulation) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
+
+/// Advance one time step and return the number of neurons that fired.
+///
+/// `currents` holds `len` input currents added to neuron `0..len`; it may
+/// be null when `len` is zero. Emitted spikes are kept until collected with
+/// `nc_get_spikes`.
+///
+/// # Safety
+/// `sim` must be a live handle and `currents` must point to `len` doubles.
+#[no_mangle]
+pub unsafe extern "C" fn nc_step(
+    sim: *mut NcSimulation,
+    currents: *const f64,
+    len: usize,
+) -> usize {
+    let state = &mut *sim;
+    let currents: &[f64] = if currents.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(currents, len)
+    };
+    guard(0, || {
+        let stimulus = &state.stimulus;
+        let fired = state
+            .sim
+            .step(|i, t| stimulus(i, t) + currents.get(i).copied().unwrap_or(0.0));
+        let n = fired.len();
+        state.pending.extend(fired);
+        n
+    })
+}
+
+/// Make `neuron` fire during the next step. Returns 0 if the neuron does
+/// not exist, 1 otherwise.
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_inject_spike(sim: *mut NcSimulation, neuron: usize) -> i32 {
+    let sim = &mut (*sim).sim;
+    if neuron >= sim.neurons().len() {
+        set_error(format!("Neuron {neuron} does not exist"));
+        return 0;
+    }
+    let time = sim.time();
+    sim.inject_spikes(&[Spike::new(neuron, time)]);
+    1
+}
+
+/// Number of spikes waiting to be collected.
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_pending_spikes(sim: *const NcSimulation) -> usize {
+    (*sim).pending.len()
+}
+
+/// Move up to `capacity` of the oldest uncollected spikes into `neurons`
+/// and `times_ms`, and return how many were written.
+///
+/// # Safety
+/// `sim` must be a live handle; `neurons` and `times_ms` must each point to
+/// `capacity` writable elements.
+#[no_mangle]
+pub unsafe extern "C" fn nc_get_spikes(
+    sim: *mut NcSimulation,
+    neurons: *mut usize,
+    times_ms: *mut f64,
+    capacity: usize,
+) -> usize {
+    let state = &mut *sim;
+    if neurons.is_null() || times_ms.is_null() {
+        return 0;
+    }
+    let n = capacity.min(state.pending.len());
+    for (k, spike) in state.pending.drain(..n).enumerate() {
+        *neurons.add(k) = spike.neuron_id;
+        *times_ms.add(k) = spike.time.0;
+    }
+    n
+}
+
+/// Current simulation time (ms).
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_time(sim: *const NcSimulation) -> f64 {
+    (*sim).sim.time().0
+}
+
+/// Number of neurons.
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_num_neurons(sim: *const NcSimulation) -> usize {
+    (*sim).sim.neurons().len()
+}
+
+/// Number of synapses.
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_num_synapses(sim: *const NcSimulation) -> usize {
+    (*sim).sim.synapses().len()
+}
+
+/// Copy up to `capacity` synapses as `(pre, post, weight)` into the three
+/// arrays, in synapse order, and return how many were written. Any array
+/// may be null to skip it.
+///
+/// # Safety
+/// `sim` must be a live handle; non-null arrays must each point to
+/// `capacity` writable elements.
+#[no_mangle]
+pub unsafe extern "C" fn nc_get_synapses(
+    sim: *const NcSimulation,
+    pre: *mut usize,
+    post: *mut usize,
+    weights: *mut f64,
+    capacity: usize,
+) -> usize {
+    let synapses = (*sim).sim.synapses();
+    let n = capacity.min(synapses.len());
+    for (k, s) in synapses[..n].iter().enumerate() {
+        if !pre.is_null() {
+            *pre.add(k) = s.pre_neuron;
+        }
+        if !post.is_null() {
+            *post.add(k) = s.post_neuron;
+        }
+        if !weights.is_null() {
+            *weights.add(k) = s.weight;
+        }
+    }
+    n
+}
+
+/// Membrane potential of `neuron`, or NaN if it does not exist.
+///
+/// # Safety
+/// `sim` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nc_membrane_potential(sim: *const NcSimulation, neuron: usize) -> f64 {
+    (*sim)
+        .sim
+        .neurons()
+        .get(neuron)
+        .map_or(f64::NAN, |n| n.v_mem)
+}
@@ -0,0 +1,1106 @@
+//! grpc.rs
+//!
+//! gRPC control API for remote experiments.
+//!
+//! Long runs belong on a compute machine, while the job scheduler or web
+//! frontend that orchestrates them lives elsewhere. A `ControlServer`
+//! exposes one simulation through the `neuromorphic.Control` service of
+//! `proto/control.proto`, from which clients in any language can be
+//! generated with `protoc`:
+//!
+//! ```text
+//! Configure(ConfigureRequest) -> Status            build from TOML configuration
+//! Step(StepRequest) -> StepReply                   advance by a number of steps
+//! Run(RunRequest) -> StepReply                     advance to a time or to t_max
+//! GetResults(ResultsRequest) -> Results            spikes so far, as results JSON
+//! Checkpoint(CheckpointRequest) -> CheckpointState membrane potentials and weights
+//! ```
+//!
+//! Like the HDF5 and Parquet writers, the server is built on the standard
+//! library alone. It implements what unary gRPC calls need, cleartext
+//! HTTP/2 with prior knowledge (what clients use for insecure channels),
+//! HPACK header decoding and flow control, but no TLS, compression or
+//! streaming calls; put it behind a TLS-terminating proxy on untrusted
+//! networks. All connections share the simulation and calls are served one
+//! at a time. A connection may hold `MAX_CONCURRENT_STREAMS` open requests;
+//! the limit is advertised in the server's SETTINGS and further requests are
+//! reset with REFUSED_STREAM, which clients may retry. Frames that break the
+//! protocol close the connection.
+
+use crate::config::ExperimentConfig;
+use crate::results::RunResults;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+use crate::stopping::StopReason;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Fully qualified name of the service.
+pub const SERVICE: &str = "neuromorphic.Control";
+/// Largest request message accepted, the gRPC default.
+pub const MAX_MESSAGE_SIZE: usize = 4 << 20;
+/// Most requests a connection may have open at once. The limit is
+/// advertised in the server's settings, and streams beyond it are refused.
+pub const MAX_CONCURRENT_STREAMS: u32 = 100;
+
+const PREFACE: &[u8; 24] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+// HTTP/2 frame types
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+
+// HTTP/2 frame flags
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+const PRIORITY: u8 = 0x20;
+
+// HTTP/2 settings
+const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+
+/// Initial flow-control window and frame size limit of HTTP/2
+const DEFAULT_WINDOW: i64 = 65_535;
+const DEFAULT_MAX_FRAME: usize = 16_384;
+/// Largest header block accepted
+const MAX_HEADER_BLOCK: usize = 64 << 10;
+/// RST_STREAM error code for a stream refused before any processing
+const REFUSED_STREAM: u32 = 0x7;
+/// HPACK dynamic table size, the HTTP/2 default
+const HEADER_TABLE_SIZE: usize = 4096;
+
+// gRPC status codes
+const OK: u32 = 0;
+const INVALID_ARGUMENT: u32 = 3;
+const RESOURCE_EXHAUSTED: u32 = 8;
+const FAILED_PRECONDITION: u32 = 9;
+const UNIMPLEMENTED: u32 = 12;
+const INTERNAL: u32 = 13;
+
+/// gRPC server controlling one simulation.
+#[derive(Debug)]
+pub struct ControlServer {
+    listener: TcpListener,
+    session: Arc<Mutex<Option<Session>>>,
+}
+
+impl ControlServer {
+    /// Listen on `address`, e.g. `"0.0.0.0:50051"`. No simulation exists
+    /// until a client calls `Configure`.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            session: Arc::default(),
+        })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve clients, each connection on its own thread. Only returns if
+    /// accepting a connection fails.
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let session = Arc::clone(&self.session);
+            let connection = Connection::new(stream?)?;
+            std::thread::spawn(move || connection.serve(&session));
+        }
+        Ok(())
+    }
+}
+
+/// Simulation driven by the clients.
+struct Session {
+    sim: Simulation,
+    input: Box<dyn Fn(usize, Milliseconds) -> f64 + Send + Sync>,
+    spikes: Vec<Spike>,
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("time", &self.sim.time())
+            .field("spikes", &self.spikes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    /// Step until `done` holds, returning the emitted spikes.
+    fn advance(&mut self, mut done: impl FnMut(&Simulation, u64) -> bool) -> Vec<Spike> {
+        let first = self.spikes.len();
+        let mut steps = 0;
+        while !done(&self.sim, steps) {
+            let fired = self.sim.step(&*self.input);
+            self.spikes.extend(fired);
+            steps += 1;
+        }
+        self.spikes[first..].to_vec()
+    }
+
+    fn status(&self) -> Vec<u8> {
+        let mut reply = ProtoWriter::default();
+        reply.uint(1, self.sim.neurons().len() as u64);
+        reply.uint(2, self.sim.synapses().len() as u64);
+        reply.double(3, self.sim.time().0);
+        reply.double(4, self.sim.config().t_max.0);
+        reply.bytes
+    }
+
+    fn step_reply(&self, spikes: &[Spike]) -> Vec<u8> {
+        let mut reply = ProtoWriter::default();
+        reply.double(1, self.sim.time().0);
+        reply.packed_uints(2, spikes.iter().map(|s| s.neuron_id as u64));
+        reply.packed_doubles(3, spikes.iter().map(|s| s.time.0));
+        reply.uint(4, u64::from(self.finished()));
+        reply.bytes
+    }
+
+    fn finished(&self) -> bool {
+        self.sim.time() >= self.sim.config().t_max
+    }
+}
+
+/// Failed call, reported to the client as a gRPC status.
+#[derive(Debug)]
+struct Failure {
+    code: u32,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Serve a call of `method` with the encoded `request`, returning the
+/// encoded reply.
+fn call(
+    session: &Mutex<Option<Session>>,
+    method: &str,
+    request: &[u8],
+) -> Result<Vec<u8>, Failure> {
+    const METHODS: [&str; 5] = ["Configure", "Step", "Run", "GetResults", "Checkpoint"];
+    if !METHODS.contains(&method) {
+        return Err(Failure::new(
+            UNIMPLEMENTED,
+            format!("Unknown method {SERVICE}/{method}"),
+        ));
+    }
+    let request = ProtoMessage::parse(request)?;
+    let mut session = session
+        .lock()
+        .map_err(|_| Failure::new(INTERNAL, "A previous call panicked"))?;
+
+    if method == "Configure" {
+        let config = ExperimentConfig::from_toml(request.string(1)?)
+            .map_err(|e| Failure::new(INVALID_ARGUMENT, e.to_string()))?;
+        let configured = session.insert(Session {
+            sim: config.build(),
+            input: Box::new(config.input_current()),
+            spikes: Vec::new(),
+        });
+        return Ok(configured.status());
+    }
+    let session = session
+        .as_mut()
+        .ok_or_else(|| Failure::new(FAILED_PRECONDITION, "No simulation; call Configure first"))?;
+
+    match method {
+        "Step" => {
+            let steps = request.uint(1).max(1);
+            let n = session.sim.neurons().len();
+            let now = session.sim.time();
+            let inject = request
+                .uints(2)?
+                .into_iter()
+                .map(|neuron| match usize::try_from(neuron) {
+                    Ok(neuron) if neuron < n => Ok(Spike::new(neuron, now)),
+                    _ => Err(Failure::new(
+                        INVALID_ARGUMENT,
+                        format!("Neuron {neuron} is out of range for {n} neurons"),
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            session.sim.inject_spikes(&inject);
+            let spikes = session.advance(|_, done| done == steps);
+            Ok(session.step_reply(&spikes))
+        }
+        "Run" => {
+            let until = match request.double(1) {
+                t if t > 0.0 => Milliseconds(t),
+                _ => session.sim.config().t_max,
+            };
+            if !until.0.is_finite() {
+                return Err(Failure::new(
+                    INVALID_ARGUMENT,
+                    "The run is unbounded; give until_ms",
+                ));
+            }
+            let spikes = session.advance(|sim, _| sim.time() >= until);
+            Ok(session.step_reply(&spikes))
+        }
+        "GetResults" => {
+            let reason = session.finished().then_some(StopReason::Completed);
+            let results = RunResults::new(&session.sim, session.spikes.clone(), Vec::new(), reason);
+            let mut reply = ProtoWriter::default();
+            reply.string(1, &results.to_json());
+            Ok(reply.bytes)
+        }
+        _ => {
+            let (sim, synapses) = (&session.sim, session.sim.synapses());
+            let mut reply = ProtoWriter::default();
+            reply.double(1, sim.time().0);
+            reply.packed_doubles(2, sim.neurons().iter().map(|n| n.v_mem));
+            reply.packed_uints(3, synapses.iter().map(|s| s.pre_neuron as u64));
+            reply.packed_uints(4, synapses.iter().map(|s| s.post_neuron as u64));
+            reply.packed_doubles(5, synapses.iter().map(|s| s.weight));
+            Ok(reply.bytes)
+        }
+    }
+}
+
+/// Protobuf message being encoded. Fields with default values are
+/// omitted, as in proto3.
+#[derive(Default)]
+struct ProtoWriter {
+    bytes: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn key(&mut self, field: u32, wire_type: u8) {
+        write_varint(
+            &mut self.bytes,
+            u64::from(field) << 3 | u64::from(wire_type),
+        );
+    }
+
+    fn uint(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.key(field, 0);
+            write_varint(&mut self.bytes, value);
+        }
+    }
+
+    fn double(&mut self, field: u32, value: f64) {
+        if value.to_bits() != 0 {
+            self.key(field, 1);
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn length_delimited(&mut self, field: u32, data: &[u8]) {
+        if !data.is_empty() {
+            self.key(field, 2);
+            write_varint(&mut self.bytes, data.len() as u64);
+            self.bytes.extend_from_slice(data);
+        }
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.length_delimited(field, value.as_bytes());
+    }
+
+    fn packed_uints(&mut self, field: u32, values: impl Iterator<Item = u64>) {
+        let mut data = Vec::new();
+        values.for_each(|v| write_varint(&mut data, v));
+        self.length_delimited(field, &data);
+    }
+
+    fn packed_doubles(&mut self, field: u32, values: impl Iterator<Item = f64>) {
+        let data: Vec<u8> = values.flat_map(f64::to_le_bytes).collect();
+        self.length_delimited(field, &data);
+    }
+}
+
+/// Value of a decoded protobuf field.
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32,
+    Bytes(&'a [u8]),
+}
+
+/// Fields of a decoded protobuf message, in wire order.
+struct ProtoMessage<'a> {
+    fields: Vec<(u32, ProtoValue<'a>)>,
+}
+
+impl<'a> ProtoMessage<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, Failure> {
+        let malformed = || Failure::new(INVALID_ARGUMENT, "Malformed request message");
+        let mut pos = 0;
+        let mut fields = Vec::new();
+        while pos < bytes.len() {
+            let key = read_varint(bytes, &mut pos).ok_or_else(malformed)?;
+            let field = u32::try_from(key >> 3).map_err(|_| malformed())?;
+            let value = match key & 7 {
+                0 => ProtoValue::Varint(read_varint(bytes, &mut pos).ok_or_else(malformed)?),
+                1 => {
+                    let data = bytes.get(pos..pos + 8).ok_or_else(malformed)?;
+                    pos += 8;
+                    ProtoValue::Fixed64(u64::from_le_bytes(data.try_into().unwrap()))
+                }
+                2 => {
+                    let len = read_varint(bytes, &mut pos).ok_or_else(malformed)?;
+                    let end = usize::try_from(len)
+                        .ok()
+                        .and_then(|len| pos.checked_add(len))
+                        .filter(|&end| end <= bytes.len())
+                        .ok_or_else(malformed)?;
+                    let data = &bytes[pos..end];
+                    pos = end;
+                    ProtoValue::Bytes(data)
+                }
+                5 => {
+                    bytes.get(pos..pos + 4).ok_or_else(malformed)?;
+                    pos += 4;
+                    ProtoValue::Fixed32
+                }
+                _ => return Err(malformed()),
+            };
+            fields.push((field, value));
+        }
+        Ok(Self { fields })
+    }
+
+    /// Last value of `field`, which wins in protobuf.
+    fn last(&self, field: u32) -> Option<&ProtoValue<'a>> {
+        self.fields
+            .iter()
+            .rev()
+            .find(|(f, _)| *f == field)
+            .map(|(_, v)| v)
+    }
+
+    fn uint(&self, field: u32) -> u64 {
+        match self.last(field) {
+            Some(ProtoValue::Varint(v)) => *v,
+            _ => 0,
+        }
+    }
+
+    fn double(&self, field: u32) -> f64 {
+        match self.last(field) {
+            Some(ProtoValue::Fixed64(bits)) => f64::from_bits(*bits),
+            _ => 0.0,
+        }
+    }
+
+    fn string(&self, field: u32) -> Result<&'a str, Failure> {
+        match self.last(field) {
+            Some(ProtoValue::Bytes(data)) => std::str::from_utf8(data)
+                .map_err(|_| Failure::new(INVALID_ARGUMENT, format!("Field {field} is not UTF-8"))),
+            _ => Ok(""),
+        }
+    }
+
+    /// Repeated `uint64` field, packed or not.
+    fn uints(&self, field: u32) -> Result<Vec<u64>, Failure> {
+        let mut values = Vec::new();
+        for (_, value) in self.fields.iter().filter(|(f, _)| *f == field) {
+            match value {
+                ProtoValue::Varint(v) => values.push(*v),
+                ProtoValue::Bytes(data) => {
+                    let mut pos = 0;
+                    while pos < data.len() {
+                        values.push(read_varint(data, &mut pos).ok_or_else(|| {
+                            Failure::new(INVALID_ARGUMENT, "Malformed packed field")
+                        })?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// HTTP/2 frame.
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream: u32,
+    payload: Vec<u8>,
+}
+
+/// Request stream of a connection.
+#[derive(Default)]
+struct Stream {
+    path: String,
+    body: Vec<u8>,
+    /// Whether the body exceeded `MAX_MESSAGE_SIZE` and was discarded
+    oversized: bool,
+    /// Bytes the client is ready to receive on this stream
+    window: i64,
+}
+
+/// Server side of an HTTP/2 connection.
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    decoder: HpackDecoder,
+    streams: HashMap<u32, Stream>,
+    /// Streams whose request is complete, in arrival order
+    ready: VecDeque<u32>,
+    /// Header block being continued: stream, block so far, end of stream
+    continuing: Option<(u32, Vec<u8>, bool)>,
+    /// Bytes the client is ready to receive on the connection
+    window: i64,
+    /// Client's initial stream window
+    initial_window: i64,
+    /// Client's frame size limit
+    max_frame: usize,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+            decoder: HpackDecoder::new(),
+            streams: HashMap::new(),
+            ready: VecDeque::new(),
+            continuing: None,
+            window: DEFAULT_WINDOW,
+            initial_window: DEFAULT_WINDOW,
+            max_frame: DEFAULT_MAX_FRAME,
+        })
+    }
+
+    /// Serve the connection until the client closes it.
+    fn serve(mut self, session: &Mutex<Option<Session>>) -> io::Result<()> {
+        let mut preface = [0; 24];
+        self.reader.read_exact(&mut preface)?;
+        if &preface != PREFACE {
+            return Err(invalid("Not an HTTP/2 connection".to_string()));
+        }
+        let mut settings = SETTINGS_MAX_CONCURRENT_STREAMS.to_be_bytes().to_vec();
+        settings.extend_from_slice(&MAX_CONCURRENT_STREAMS.to_be_bytes());
+        self.write_frame(SETTINGS, 0, 0, &settings)?;
+
+        loop {
+            while let Some(id) = self.ready.pop_front() {
+                self.respond(id, session)?;
+            }
+            self.writer.flush()?;
+            let frame = match self.read_frame() {
+                Ok(frame) => frame,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            if frame.kind == GOAWAY {
+                return self.writer.flush();
+            }
+            self.handle(frame)?;
+        }
+    }
+
+    fn read_frame(&mut self) -> io::Result<Frame> {
+        let mut header = [0; 9];
+        self.reader.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        if len > DEFAULT_MAX_FRAME {
+            return Err(invalid(format!("Frame of {len} bytes exceeds the limit")));
+        }
+        let mut payload = vec![0; len];
+        self.reader.read_exact(&mut payload)?;
+        Ok(Frame {
+            kind: header[3],
+            flags: header[4],
+            stream: u32::from_be_bytes(header[5..9].try_into().unwrap()) & 0x7fff_ffff,
+            payload,
+        })
+    }
+
+    fn write_frame(&mut self, kind: u8, flags: u8, stream: u32, payload: &[u8]) -> io::Result<()> {
+        let len = (payload.len() as u32).to_be_bytes();
+        self.writer.write_all(&len[1..])?;
+        self.writer.write_all(&[kind, flags])?;
+        self.writer.write_all(&stream.to_be_bytes())?;
+        self.writer.write_all(payload)
+    }
+
+    /// Process a frame from the client. Completed requests are queued in
+    /// `ready` rather than answered, so that this can run while a response
+    /// waits for flow-control credit.
+    fn handle(&mut self, frame: Frame) -> io::Result<()> {
+        let Frame {
+            kind,
+            flags,
+            stream: id,
+            mut payload,
+        } = frame;
+
+        if let Some((continued, block, end_stream)) = self.continuing.as_mut() {
+            if kind != CONTINUATION || id != *continued {
+                return Err(invalid("Header block interrupted".to_string()));
+            }
+            block.extend_from_slice(&payload);
+            if block.len() > MAX_HEADER_BLOCK {
+                return Err(invalid("Header block too large".to_string()));
+            }
+            if flags & END_HEADERS != 0 {
+                let (id, block, end_stream) = (*continued, std::mem::take(block), *end_stream);
+                self.continuing = None;
+                self.headers(id, &block, end_stream)?;
+            }
+            return Ok(());
+        }
+
+        match kind {
+            DATA => {
+                let len = payload.len() as u32;
+                if flags & PADDED != 0 {
+                    strip_padding(&mut payload)?;
+                }
+                if let Some(stream) = self.streams.get_mut(&id) {
+                    if stream.body.len() + payload.len() > MAX_MESSAGE_SIZE + 5 {
+                        stream.oversized = true;
+                        stream.body = Vec::new();
+                    }
+                    if !stream.oversized {
+                        stream.body.extend_from_slice(&payload);
+                    }
+                    if flags & END_STREAM != 0 {
+                        self.ready.push_back(id);
+                    } else if len > 0 {
+                        self.write_frame(WINDOW_UPDATE, 0, id, &len.to_be_bytes())?;
+                    }
+                }
+                // Return the credit so that the client can keep sending
+                if len > 0 {
+                    self.write_frame(WINDOW_UPDATE, 0, 0, &len.to_be_bytes())?;
+                }
+            }
+            HEADERS => {
+                if flags & PADDED != 0 {
+                    strip_padding(&mut payload)?;
+                }
+                if flags & PRIORITY != 0 {
+                    payload.drain(..5.min(payload.len()));
+                }
+                let end_stream = flags & END_STREAM != 0;
+                if flags & END_HEADERS != 0 {
+                    self.headers(id, &payload, end_stream)?;
+                } else {
+                    self.continuing = Some((id, payload, end_stream));
+                }
+            }
+            RST_STREAM => {
+                self.streams.remove(&id);
+                self.ready.retain(|&ready| ready != id);
+            }
+            SETTINGS if flags & ACK == 0 => {
+                for setting in payload.chunks_exact(6) {
+                    let value = u32::from_be_bytes(setting[2..].try_into().unwrap());
+                    match u16::from_be_bytes([setting[0], setting[1]]) {
+                        SETTINGS_INITIAL_WINDOW_SIZE => {
+                            let delta = i64::from(value) - self.initial_window;
+                            self.streams.values_mut().for_each(|s| s.window += delta);
+                            self.initial_window = i64::from(value);
+                        }
+                        SETTINGS_MAX_FRAME_SIZE => {
+                            self.max_frame = (value as usize).max(DEFAULT_MAX_FRAME);
+                        }
+                        _ => {}
+                    }
+                }
+                self.write_frame(SETTINGS, ACK, 0, &[])?;
+            }
+            PING if flags & ACK == 0 => self.write_frame(PING, ACK, 0, &payload)?,
+            WINDOW_UPDATE if payload.len() == 4 => {
+                let increment =
+                    i64::from(u32::from_be_bytes(payload[..].try_into().unwrap()) & 0x7fff_ffff);
+                if id == 0 {
+                    self.window += increment;
+                } else if let Some(stream) = self.streams.get_mut(&id) {
+                    stream.window += increment;
+                }
+            }
+            CONTINUATION => return Err(invalid("Unexpected CONTINUATION frame".to_string())),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Process a complete header block of stream `id`.
+    fn headers(&mut self, id: u32, block: &[u8], end_stream: bool) -> io::Result<()> {
+        // Decode even for unknown streams to keep the HPACK table in sync
+        let headers = self.decoder.decode(block)?;
+        if !self.streams.contains_key(&id) {
+            if self.streams.len() >= MAX_CONCURRENT_STREAMS as usize {
+                return self.write_frame(RST_STREAM, 0, id, &REFUSED_STREAM.to_be_bytes());
+            }
+            let path = headers
+                .into_iter()
+                .find(|(name, _)| name == ":path")
+                .map(|(_, value)| value)
+                .unwrap_or_default();
+            let stream = Stream {
+                path,
+                window: self.initial_window,
+                ..Stream::default()
+            };
+            self.streams.insert(id, stream);
+        }
+        if end_stream {
+            self.ready.push_back(id);
+        }
+        Ok(())
+    }
+
+    /// Answer the complete request of stream `id`.
+    fn respond(&mut self, id: u32, session: &Mutex<Option<Session>>) -> io::Result<()> {
+        let Some(stream) = self.streams.get_mut(&id) else {
+            return Ok(());
+        };
+        let (path, body, oversized) = (
+            std::mem::take(&mut stream.path),
+            std::mem::take(&mut stream.body),
+            stream.oversized,
+        );
+        let reply = match path.strip_prefix('/').and_then(|p| p.split_once('/')) {
+            Some((SERVICE, method)) => {
+                unframe(&body, oversized).and_then(|m| call(session, method, m))
+            }
+            _ => Err(Failure::new(UNIMPLEMENTED, format!("Unknown path {path}"))),
+        };
+
+        let mut headers = vec![0x88]; // :status 200 from the HPACK static table
+        encode_header(&mut headers, "content-type", "application/grpc");
+        match reply {
+            Ok(message) => {
+                self.write_frame(HEADERS, END_HEADERS, id, &headers)?;
+                let mut data = Vec::with_capacity(message.len() + 5);
+                data.push(0);
+                data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+                data.extend_from_slice(&message);
+                if self.send_data(id, &data)? {
+                    let mut trailers = Vec::new();
+                    encode_header(&mut trailers, "grpc-status", &OK.to_string());
+                    self.write_frame(HEADERS, END_HEADERS | END_STREAM, id, &trailers)?;
+                }
+            }
+            Err(failure) => {
+                encode_header(&mut headers, "grpc-status", &failure.code.to_string());
+                encode_header(
+                    &mut headers,
+                    "grpc-message",
+                    &percent_encode(&failure.message),
+                );
+                self.write_frame(HEADERS, END_HEADERS | END_STREAM, id, &headers)?;
+            }
+        }
+        self.streams.remove(&id);
+        Ok(())
+    }
+
+    /// Send `data` on stream `id` within the client's flow-control windows,
+    /// reading frames while waiting for credit. Returns false if the
+    /// client reset the stream meanwhile.
+    fn send_data(&mut self, id: u32, data: &[u8]) -> io::Result<bool> {
+        let mut sent = 0;
+        while sent < data.len() {
+            let Some(stream) = self.streams.get_mut(&id) else {
+                return Ok(false);
+            };
+            let credit = self.window.min(stream.window).min(self.max_frame as i64);
+            if credit <= 0 {
+                self.writer.flush()?;
+                let frame = self.read_frame()?;
+                if frame.kind == GOAWAY {
+                    return Ok(false);
+                }
+                self.handle(frame)?;
+                continue;
+            }
+            let n = (credit as usize).min(data.len() - sent);
+            stream.window -= n as i64;
+            self.window -= n as i64;
+            self.write_frame(DATA, 0, id, &data[sent..sent + n])?;
+            sent += n;
+        }
+        Ok(true)
+    }
+}
+
+/// Message of a gRPC request body, which holds one length-prefixed message.
+fn unframe(body: &[u8], oversized: bool) -> Result<&[u8], Failure> {
+    if oversized {
+        return Err(Failure::new(
+            RESOURCE_EXHAUSTED,
+            format!("Request exceeds {MAX_MESSAGE_SIZE} bytes"),
+        ));
+    }
+    let malformed = || Failure::new(INTERNAL, "Malformed gRPC request body");
+    let header = body.get(..5).ok_or_else(malformed)?;
+    if header[0] != 0 {
+        return Err(Failure::new(
+            UNIMPLEMENTED,
+            "Compressed requests are not supported",
+        ));
+    }
+    let len = u32::from_be_bytes(header[1..5].try_into().unwrap()) as usize;
+    body.get(5..)
+        .filter(|m| m.len() == len)
+        .ok_or_else(malformed)
+}
+
+/// Remove the padding of a padded frame payload.
+fn strip_padding(payload: &mut Vec<u8>) -> io::Result<()> {
+    let padding = *payload.first().unwrap_or(&0) as usize;
+    if payload.is_empty() || padding >= payload.len() {
+        return Err(invalid("Invalid frame padding".to_string()));
+    }
+    payload.truncate(payload.len() - padding);
+    payload.remove(0);
+    Ok(())
+}
+
+/// `text` with the bytes not allowed in `grpc-message` percent-encoded.
+fn percent_encode(text: &str) -> String {
+    let mut out = String::new();
+    for &byte in text.as_bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'%' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Append a header to an HPACK block as a literal without indexing.
+fn encode_header(block: &mut Vec<u8>, name: &str, value: &str) {
+    block.push(0);
+    for text in [name, value] {
+        write_hpack_int(block, 0, 7, text.len());
+        block.extend_from_slice(text.as_bytes());
+    }
+}
+
+/// Append `value` as an HPACK integer with a `prefix`-bit prefix, sharing
+/// its first byte with the high bits `flags`.
+fn write_hpack_int(block: &mut Vec<u8>, flags: u8, prefix: u32, mut value: usize) {
+    let limit = (1 << prefix) - 1;
+    if value < limit {
+        block.push(flags | value as u8);
+        return;
+    }
+    block.push(flags | limit as u8);
+    value -= limit;
+    while value >= 0x80 {
+        block.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    block.push(value as u8);
+}
+
+/// HPACK header block decoder (RFC 7541).
+struct HpackDecoder {
+    /// Dynamic table, newest entry first
+    table: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl HpackDecoder {
+    fn new() -> Self {
+        Self {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: HEADER_TABLE_SIZE,
+        }
+    }
+
+    fn decode(&mut self, block: &[u8]) -> io::Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+        while pos < block.len() {
+            let byte = block[pos];
+            if byte & 0x80 != 0 {
+                // Indexed header field
+                let index = read_hpack_int(block, &mut pos, 7)?;
+                headers.push(self.entry(index)?);
+            } else if byte & 0xe0 == 0x20 {
+                // Dynamic table size update
+                let size = read_hpack_int(block, &mut pos, 5)?;
+                if size > HEADER_TABLE_SIZE {
+                    return Err(invalid(format!(
+                        "HPACK table size {size} exceeds the limit"
+                    )));
+                }
+                self.max_size = size;
+                self.evict(0);
+            } else {
+                // Literal, added to the table with incremental indexing
+                let indexing = byte & 0x40 != 0;
+                let index = read_hpack_int(block, &mut pos, if indexing { 6 } else { 4 })?;
+                let name = match index {
+                    0 => read_hpack_string(block, &mut pos)?,
+                    _ => self.entry(index)?.0,
+                };
+                let value = read_hpack_string(block, &mut pos)?;
+                if indexing {
+                    self.insert(name.clone(), value.clone());
+                }
+                headers.push((name, value));
+            }
+        }
+        Ok(headers)
+    }
+
+    fn entry(&self, index: usize) -> io::Result<(String, String)> {
+        let entry = match index {
+            0 => None,
+            1..=61 => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Some((name.to_string(), value.to_string()))
+            }
+            _ => self.table.get(index - 62).cloned(),
+        };
+        entry.ok_or_else(|| invalid(format!("Invalid HPACK index {index}")))
+    }
+
+    fn insert(&mut self, name: String, value: String) {
+        let size = name.len() + value.len() + 32;
+        self.evict(size);
+        if size <= self.max_size {
+            self.size += size;
+            self.table.push_front((name, value));
+        }
+    }
+
+    /// Evict the oldest entries until `extra` more bytes fit.
+    fn evict(&mut self, extra: usize) {
+        while self.size + extra > self.max_size {
+            let Some((name, value)) = self.table.pop_back() else {
+                break;
+            };
+            self.size -= name.len() + value.len() + 32;
+        }
+    }
+}
+
+fn read_hpack_int(block: &[u8], pos: &mut usize, prefix: u32) -> io::Result<usize> {
+    let truncated = || invalid("Truncated HPACK integer".to_string());
+    let limit = (1 << prefix) - 1;
+    let mut value = (*block.get(*pos).ok_or_else(truncated)? & limit as u8) as usize;
+    *pos += 1;
+    if value < limit {
+        return Ok(value);
+    }
+    for shift in (0..28).step_by(7) {
+        let byte = *block.get(*pos).ok_or_else(truncated)?;
+        *pos += 1;
+        value += ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("HPACK integer too large".to_string()))
+}
+
+fn read_hpack_string(block: &[u8], pos: &mut usize) -> io::Result<String> {
+    let huffman = block.get(*pos).is_some_and(|b| b & 0x80 != 0);
+    let len = read_hpack_int(block, pos, 7)?;
+    let data = block
+        .get(*pos..*pos + len)
+        .ok_or_else(|| invalid("Truncated HPACK string".to_string()))?;
+    *pos += len;
+    let bytes = if huffman {
+        huffman_decode(data)?
+    } else {
+        data.to_vec()
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Canonical decoding table of the HPACK Huffman code.
+struct Huffman {
+    /// Symbols ordered by code
+    symbols: Vec<u16>,
+    /// First code, number of codes and index of the first symbol of each
+    /// code length
+    first_code: [u32; 31],
+    count: [u32; 31],
+    offset: [usize; 31],
+}
+
+fn huffman() -> &'static Huffman {
+    static TABLE: OnceLock<Huffman> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut symbols: Vec<u16> = (0..257).collect();
+        symbols.sort_by_key(|&s| (HUFFMAN_LENGTHS[s as usize], s));
+        let mut table = Huffman {
+            symbols,
+            first_code: [0; 31],
+            count: [0; 31],
+            offset: [0; 31],
+        };
+        for &len in &HUFFMAN_LENGTHS {
+            table.count[len as usize] += 1;
+        }
+        for len in 1..31 {
+            table.first_code[len] = (table.first_code[len - 1] + table.count[len - 1]) << 1;
+            table.offset[len] = table.offset[len - 1] + table.count[len - 1] as usize;
+        }
+        table
+    })
+}
+
+fn huffman_decode(data: &[u8]) -> io::Result<Vec<u8>> {
+    let table = huffman();
+    let mut out = Vec::new();
+    let (mut code, mut len) = (0u32, 0);
+    for &byte in data {
+        for bit in (0..8).rev() {
+            code = code << 1 | u32::from(byte >> bit & 1);
+            len += 1;
+            let index = code.wrapping_sub(table.first_code[len]);
+            if index < table.count[len] {
+                match table.symbols[table.offset[len] + index as usize] {
+                    256 => return Err(invalid("HPACK string contains EOS".to_string())),
+                    symbol => out.push(symbol as u8),
+                }
+                (code, len) = (0, 0);
+            } else if len == 30 {
+                return Err(invalid("Invalid HPACK Huffman code".to_string()));
+            }
+        }
+    }
+    // Padding is at most 7 bits of the EOS code, which is all ones
+    if len > 7 || code != (1 << len) - 1 {
+        return Err(invalid("Invalid HPACK Huffman padding".to_string()));
+    }
+    Ok(out)
+}
+
+/// Code lengths of the HPACK Huffman code by symbol, 256 being EOS
+/// (RFC 7541, Appendix B). The code is canonical, so the codes follow.
+#[rustfmt::skip]
+const HUFFMAN_LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28,
+    28, 28, 28, 28, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6,
+    5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10,
+    13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6,
+    15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6, 6, 5,
+    6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28,
+    20, 22, 20, 20, 22, 22, 22, 23, 22, 23, 23, 23, 23, 23, 24, 23,
+    24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24,
+    22, 21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23,
+    21, 21, 22, 21, 23, 22, 23, 23, 20, 22, 22, 22, 23, 22, 22, 23,
+    26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25,
+    19, 21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27,
+    20, 24, 20, 21, 22, 21, 21, 23, 22, 22, 25, 25, 24, 24, 26, 23,
+    26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26,
+    30,
+];
+
+/// HPACK static table (RFC 7541, Appendix A).
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
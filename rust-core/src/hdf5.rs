@@ -0,0 +1,1438 @@
+//! hdf5.rs
+//!
+//! HDF5 output for large recordings, and a small reader.
+//!
+//! CSV stops being practical once a recording reaches millions of spikes or
+//! thousands of weight snapshots: files are several times larger than the
+//! data and must be parsed line by line. This module writes HDF5 files that
+//! h5py, MATLAB and HDFView open directly, without linking libhdf5. Dataset
+//! contents are streamed to disk as they are written and only the group
+//! structure is kept in memory until `finish`.
+//!
+//! The writer emits the original HDF5 file format (superblock version 0,
+//! symbol-table groups, contiguous little-endian datasets), which every
+//! HDF5 reader supports. Groups hold at most 1024 members.
+//!
+//! `write_spikes`, `write_voltages` and `write_snapshots` use this layout:
+//!
+//! ```text
+//! /spikes
+//!     neuron_id     uint64  [spikes]
+//!     time_ms       float64 [spikes]
+//! /voltages
+//!     neuron_id     uint64  [neurons]            probed neurons, in column order
+//!     time_ms       float64 [samples]
+//!     v_mem         float64 [samples, neurons]
+//! /weights
+//!     time_ms       float64 [snapshots]
+//!     num_neurons   uint64  [snapshots]
+//!     index         uint64  [snapshots + 1]      snapshot k is rows index[k]..index[k + 1]
+//!     pre_neuron    uint64  [rows]
+//!     post_neuron   uint64  [rows]
+//!     weight        float64 [rows]
+//! ```
+//!
+//! Time datasets carry a `units` attribute of `"ms"`. When the synapse list
+//! does not change between snapshots, `weight` reshapes to
+//! `[snapshots, synapses]`.
+//!
+//! Strings, in string datasets and attributes, are stored as
+//! variable-length UTF-8 like h5py stores `str`, and attributes can refer to
+//! datasets, which is what standards built on HDF5 such as NWB need.
+//!
+//! `Hdf5File` reads numeric and string datasets and attributes back, from
+//! these files and from files written by h5py with default settings, which
+//! is enough to import circuits from other tools (see `sonata`).
+
+use crate::probe::VoltageProbe;
+use crate::snapshot::WeightSnapshot;
+use crate::spike::Spike;
+use crate::util::{invalid, invalid_input};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Address written for "no address" fields.
+const UNDEFINED: u64 = u64::MAX;
+/// Size of the version 0 superblock, including the root symbol table entry
+const SUPERBLOCK_SIZE: u64 = 96;
+/// Half the capacity of a symbol table node
+const LEAF_K: usize = 16;
+/// Half the capacity of a group B-tree node
+const INTERNAL_K: usize = 16;
+/// Size of a symbol table entry
+const ENTRY_SIZE: usize = 40;
+/// Smallest global heap collection
+const MIN_COLLECTION_SIZE: usize = 4096;
+
+/// Object header message types.
+const MSG_DATASPACE: u16 = 0x01;
+const MSG_LINK_INFO: u16 = 0x02;
+const MSG_DATATYPE: u16 = 0x03;
+const MSG_LINK: u16 = 0x06;
+const MSG_LAYOUT: u16 = 0x08;
+const MSG_ATTRIBUTE: u16 = 0x0C;
+const MSG_CONTINUATION: u16 = 0x10;
+const MSG_SYMBOL_TABLE: u16 = 0x11;
+
+/// Value of an attribute attached to a group or dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attribute {
+    Float(f64),
+    Int(i64),
+    Text(String),
+    /// One-dimensional array of integers
+    Ints(Vec<i64>),
+    /// One-dimensional array of strings
+    Texts(Vec<String>),
+    /// Object reference to the dataset at this path, which must already be
+    /// written
+    Reference(String),
+}
+
+/// Element type that can be stored in a dataset.
+pub trait Element: Copy {
+    /// Encoded HDF5 datatype message
+    fn datatype() -> Vec<u8>;
+    /// Little-endian bytes of the value
+    fn to_le(self) -> [u8; 8];
+}
+
+impl Element for f64 {
+    fn datatype() -> Vec<u8> {
+        float64_datatype()
+    }
+
+    fn to_le(self) -> [u8; 8] {
+        self.to_le_bytes()
+    }
+}
+
+impl Element for u64 {
+    fn datatype() -> Vec<u8> {
+        integer64_datatype(false)
+    }
+
+    fn to_le(self) -> [u8; 8] {
+        self.to_le_bytes()
+    }
+}
+
+impl Element for i64 {
+    fn datatype() -> Vec<u8> {
+        integer64_datatype(true)
+    }
+
+    fn to_le(self) -> [u8; 8] {
+        self.to_le_bytes()
+    }
+}
+
+/// Group awaiting its metadata in `finish`.
+#[derive(Debug, Default)]
+struct Group {
+    /// Encoded attribute messages
+    attributes: Vec<Vec<u8>>,
+    members: BTreeMap<String, Member>,
+}
+
+#[derive(Debug)]
+enum Member {
+    Group(Group),
+    /// Object header address of a written dataset
+    Dataset(u64),
+}
+
+/// Streaming writer of an HDF5 file.
+///
+/// Datasets are written immediately; the file is only valid after
+/// `finish`, which writes the groups and the superblock.
+#[derive(Debug)]
+pub struct Hdf5Writer {
+    file: BufWriter<File>,
+    position: u64,
+    root: Group,
+    /// Global heap collection still taking strings
+    heap: Option<HeapCollection>,
+}
+
+/// Global heap collection of variable-length strings, written once full.
+#[derive(Debug)]
+struct HeapCollection {
+    address: u64,
+    capacity: usize,
+    /// Number of objects
+    count: u16,
+    /// Encoded objects
+    objects: Vec<u8>,
+}
+
+impl Hdf5Writer {
+    /// Create the file at `path`, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        // Placeholder until `finish` knows the root group's addresses
+        file.write_all(&[0; SUPERBLOCK_SIZE as usize])?;
+        Ok(Self {
+            file,
+            position: SUPERBLOCK_SIZE,
+            root: Group::default(),
+            heap: None,
+        })
+    }
+
+    /// Create the group at `path`, e.g. `"/runs/a"`, and any missing
+    /// parents, adding `attributes` to it.
+    pub fn create_group(&mut self, path: &str, attributes: &[(&str, Attribute)]) -> io::Result<()> {
+        let names = split(path)?;
+        self.group_mut(&names)?;
+        let mut encoded = Vec::with_capacity(attributes.len());
+        for (n, v) in attributes {
+            encoded.push(self.attribute(n, v)?);
+        }
+        self.group_mut(&names)?.attributes.extend(encoded);
+        Ok(())
+    }
+
+    /// Write a one-dimensional dataset at `path`, creating missing parent
+    /// groups.
+    pub fn write_dataset<T: Element>(
+        &mut self,
+        path: &str,
+        values: impl IntoIterator<Item = T>,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        self.write_array(path, None, values, attributes)
+    }
+
+    /// Write a row-major two-dimensional dataset with `columns` columns at
+    /// `path`, creating missing parent groups. The number of values must be
+    /// a multiple of `columns`.
+    pub fn write_matrix<T: Element>(
+        &mut self,
+        path: &str,
+        columns: usize,
+        values: impl IntoIterator<Item = T>,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        self.write_array(path, Some(columns), values, attributes)
+    }
+
+    /// Write a one-dimensional dataset of variable-length UTF-8 strings at
+    /// `path`, which h5py reads as `str`, creating missing parent groups.
+    pub fn write_strings<S: AsRef<str>>(
+        &mut self,
+        path: &str,
+        values: impl IntoIterator<Item = S>,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        let values: Vec<S> = values.into_iter().collect();
+        let strings: Vec<&str> = values.iter().map(AsRef::as_ref).collect();
+        self.write_text_dataset(path, &[strings.len() as u64], &strings, attributes)
+    }
+
+    /// Write a scalar variable-length UTF-8 string dataset at `path`,
+    /// creating missing parent groups.
+    pub fn write_text(
+        &mut self,
+        path: &str,
+        value: &str,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        self.write_text_dataset(path, &[], &[value], attributes)
+    }
+
+    /// Write `spikes` to `/spikes`.
+    pub fn write_spikes(&mut self, spikes: &[Spike]) -> io::Result<()> {
+        self.write_dataset(
+            "/spikes/neuron_id",
+            spikes.iter().map(|s| s.neuron_id as u64),
+            &[],
+        )?;
+        self.write_dataset("/spikes/time_ms", spikes.iter().map(|s| s.time.0), &[ms()])
+    }
+
+    /// Write the traces recorded by `probe` to `/voltages`.
+    pub fn write_voltages(&mut self, probe: &VoltageProbe) -> io::Result<()> {
+        let neurons = probe.neurons();
+        let samples = probe.samples();
+        self.write_dataset(
+            "/voltages/neuron_id",
+            neurons.iter().map(|&n| n as u64),
+            &[],
+        )?;
+        self.write_dataset(
+            "/voltages/time_ms",
+            samples.iter().map(|(t, _)| t.0),
+            &[ms()],
+        )?;
+        self.write_matrix(
+            "/voltages/v_mem",
+            neurons.len(),
+            samples.iter().flat_map(|(_, v)| v.iter().copied()),
+            &[],
+        )
+    }
+
+    /// Write `snapshots` to `/weights` as ragged arrays indexed by
+    /// `/weights/index`.
+    pub fn write_snapshots(&mut self, snapshots: &[WeightSnapshot]) -> io::Result<()> {
+        let index = std::iter::once(0).chain(snapshots.iter().scan(0, |rows, s| {
+            *rows += s.synapses.len() as u64;
+            Some(*rows)
+        }));
+        let synapses = || snapshots.iter().flat_map(|s| s.synapses.iter());
+        self.write_dataset(
+            "/weights/time_ms",
+            snapshots.iter().map(|s| s.time.0),
+            &[ms()],
+        )?;
+        self.write_dataset(
+            "/weights/num_neurons",
+            snapshots.iter().map(|s| s.num_neurons as u64),
+            &[],
+        )?;
+        self.write_dataset("/weights/index", index, &[])?;
+        self.write_dataset("/weights/pre_neuron", synapses().map(|s| s.0 as u64), &[])?;
+        self.write_dataset("/weights/post_neuron", synapses().map(|s| s.1 as u64), &[])?;
+        self.write_dataset("/weights/weight", synapses().map(|s| s.2), &[])
+    }
+
+    /// Write the group structure and superblock, completing the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        let root = std::mem::take(&mut self.root);
+        let (header, btree, heap) = self.write_group(&root)?;
+        self.flush_global_heap()?;
+        let end = self.position;
+
+        let mut superblock = Vec::with_capacity(SUPERBLOCK_SIZE as usize);
+        superblock.extend_from_slice(b"\x89HDF\r\n\x1a\n");
+        // Superblock, free-space, root entry and shared header versions,
+        // then offset and length sizes
+        superblock.extend_from_slice(&[0, 0, 0, 0, 0, 8, 8, 0]);
+        put_u16(&mut superblock, LEAF_K as u16);
+        put_u16(&mut superblock, INTERNAL_K as u16);
+        put_u32(&mut superblock, 0);
+        // Base address, free-space info, end of file, driver info
+        for address in [0, UNDEFINED, end, UNDEFINED] {
+            put_u64(&mut superblock, address);
+        }
+        put_entry(&mut superblock, 0, header, Some((btree, heap)));
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&superblock)?;
+        self.file.flush()
+    }
+
+    /// Stream `values` to disk and add a dataset header for them at `path`.
+    fn write_array<T: Element>(
+        &mut self,
+        path: &str,
+        columns: Option<usize>,
+        values: impl IntoIterator<Item = T>,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        self.check_new(path)?;
+        self.align()?;
+        let address = self.position;
+        let mut count = 0u64;
+        for value in values {
+            self.write(&value.to_le())?;
+            count += 1;
+        }
+        let dims = match columns {
+            None => vec![count],
+            Some(0) if count == 0 => vec![0, 0],
+            Some(c) if c > 0 && count.is_multiple_of(c as u64) => vec![count / c as u64, c as u64],
+            Some(c) => {
+                return Err(invalid_input(format!(
+                    "{count} values do not fill rows of {c} columns in {path}"
+                )))
+            }
+        };
+
+        self.add_dataset(path, &dims, T::datatype(), address, count * 8, attributes)
+    }
+
+    /// Write variable-length strings with dimensions `dims` as a dataset
+    /// at `path`.
+    fn write_text_dataset(
+        &mut self,
+        path: &str,
+        dims: &[u64],
+        strings: &[&str],
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        self.check_new(path)?;
+        let descriptors = self.write_global_heap(strings)?;
+        self.align()?;
+        let address = self.position;
+        self.write(&descriptors)?;
+        let size = descriptors.len() as u64;
+        self.add_dataset(path, dims, vlen_string_datatype(), address, size, attributes)
+    }
+
+    /// Fail if `path` already exists.
+    fn check_new(&mut self, path: &str) -> io::Result<()> {
+        let mut names = split(path)?;
+        let Some(name) = names.pop() else {
+            return Err(invalid_input("Dataset path is empty".to_string()));
+        };
+        if self.group_mut(&names)?.members.contains_key(&name) {
+            return Err(invalid_input(format!("{path} already exists")));
+        }
+        Ok(())
+    }
+
+    /// Add a dataset header for `size` bytes of data at `address` to the
+    /// group structure at `path`.
+    fn add_dataset(
+        &mut self,
+        path: &str,
+        dims: &[u64],
+        datatype: Vec<u8>,
+        address: u64,
+        size: u64,
+        attributes: &[(&str, Attribute)],
+    ) -> io::Result<()> {
+        let mut layout = vec![3, 1];
+        put_u64(&mut layout, if size == 0 { UNDEFINED } else { address });
+        put_u64(&mut layout, size);
+        let mut messages = vec![
+            (MSG_DATASPACE, dataspace(dims)),
+            (MSG_DATATYPE, datatype),
+            (MSG_LAYOUT, layout),
+        ];
+        for (n, v) in attributes {
+            messages.push((MSG_ATTRIBUTE, self.attribute(n, v)?));
+        }
+        let header = self.write_object_header(&messages)?;
+        let mut names = split(path)?;
+        let name = names.pop().expect("Checked by check_new");
+        self.group_mut(&names)?
+            .members
+            .insert(name, Member::Dataset(header));
+        Ok(())
+    }
+
+    /// Store `strings` in global heap collections, returning the
+    /// variable-length descriptors (length, collection address, object
+    /// index) that refer to them.
+    fn write_global_heap(&mut self, strings: &[&str]) -> io::Result<Vec<u8>> {
+        let mut descriptors = Vec::with_capacity(16 * strings.len());
+        for s in strings {
+            let size = 16 + s.len().next_multiple_of(8);
+            let full = self.heap.as_ref().is_none_or(|heap| {
+                heap.count == u16::MAX || 16 + heap.objects.len() + size > heap.capacity
+            });
+            if full {
+                self.flush_global_heap()?;
+                // Reserve the collection's space now and fill it in later
+                self.align()?;
+                let capacity = (16 + size).max(MIN_COLLECTION_SIZE);
+                self.heap = Some(HeapCollection {
+                    address: self.position,
+                    capacity,
+                    count: 0,
+                    objects: Vec::new(),
+                });
+                self.write(&vec![0; capacity])?;
+            }
+            let heap = self.heap.as_mut().expect("Collection allocated above");
+            heap.count += 1;
+            // Index, reference count, reserved, size, then the bytes
+            put_u16(&mut heap.objects, heap.count);
+            put_u16(&mut heap.objects, 0);
+            put_u32(&mut heap.objects, 0);
+            put_u64(&mut heap.objects, s.len() as u64);
+            heap.objects.extend_from_slice(s.as_bytes());
+            pad(&mut heap.objects);
+            put_u32(&mut descriptors, s.len() as u32);
+            put_u64(&mut descriptors, heap.address);
+            put_u32(&mut descriptors, u32::from(heap.count));
+        }
+        Ok(descriptors)
+    }
+
+    /// Write the open global heap collection into its reserved space.
+    fn flush_global_heap(&mut self) -> io::Result<()> {
+        let Some(heap) = self.heap.take() else {
+            return Ok(());
+        };
+        let mut block = b"GCOL\x01\0\0\0".to_vec();
+        put_u64(&mut block, heap.capacity as u64);
+        block.extend_from_slice(&heap.objects);
+        // Object 0 describes the free space, if there is room for it
+        let free = heap.capacity - block.len();
+        if free >= 16 {
+            put_u64(&mut block, 0);
+            put_u64(&mut block, free as u64);
+        }
+        self.file.seek(SeekFrom::Start(heap.address))?;
+        self.file.write_all(&block)?;
+        self.file.seek(SeekFrom::Start(self.position))?;
+        Ok(())
+    }
+
+    /// Object header address of the dataset at `path`.
+    fn dataset_header(&self, path: &str) -> io::Result<u64> {
+        let mut member = None;
+        let mut group = Some(&self.root);
+        for name in split(path)? {
+            member = group.and_then(|g| g.members.get(&name));
+            group = match member {
+                Some(Member::Group(g)) => Some(g),
+                _ => None,
+            };
+        }
+        match member {
+            Some(Member::Dataset(header)) => Ok(*header),
+            _ => Err(invalid_input(format!(
+                "Referenced dataset {path} has not been written"
+            ))),
+        }
+    }
+
+    /// Version 1 attribute message for `value`.
+    fn attribute(&mut self, name: &str, value: &Attribute) -> io::Result<Vec<u8>> {
+        if name.is_empty() || name.contains('\0') {
+            return Err(invalid_input(format!("Invalid attribute name {name:?}")));
+        }
+        let (datatype, bytes) = match value {
+            Attribute::Float(v) => (float64_datatype(), v.to_le_bytes().to_vec()),
+            Attribute::Int(v) => (integer64_datatype(true), v.to_le_bytes().to_vec()),
+            Attribute::Text(s) => (vlen_string_datatype(), self.write_global_heap(&[s])?),
+            Attribute::Ints(v) => (
+                integer64_datatype(true),
+                v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ),
+            Attribute::Texts(v) => {
+                let strings: Vec<&str> = v.iter().map(String::as_str).collect();
+                (vlen_string_datatype(), self.write_global_heap(&strings)?)
+            }
+            Attribute::Reference(path) => {
+                // Class 7, version 1: object reference, an object header address
+                let mut datatype = vec![0x17, 0, 0, 0];
+                put_u32(&mut datatype, 8);
+                (datatype, self.dataset_header(path)?.to_le_bytes().to_vec())
+            }
+        };
+        let space = match value {
+            Attribute::Ints(v) => dataspace(&[v.len() as u64]),
+            Attribute::Texts(v) => dataspace(&[v.len() as u64]),
+            _ => dataspace(&[]),
+        };
+
+        let mut data = vec![1, 0];
+        put_u16(&mut data, name.len() as u16 + 1);
+        put_u16(&mut data, datatype.len() as u16);
+        put_u16(&mut data, space.len() as u16);
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        pad(&mut data);
+        data.extend_from_slice(&datatype);
+        pad(&mut data);
+        data.extend_from_slice(&space);
+        pad(&mut data);
+        data.extend_from_slice(&bytes);
+        Ok(data)
+    }
+
+    /// Group at `names` below the root, created if missing.
+    fn group_mut(&mut self, names: &[String]) -> io::Result<&mut Group> {
+        let mut group = &mut self.root;
+        for name in names {
+            let member = group
+                .members
+                .entry(name.clone())
+                .or_insert_with(|| Member::Group(Group::default()));
+            group = match member {
+                Member::Group(g) => g,
+                Member::Dataset(_) => {
+                    return Err(invalid_input(format!("{name} is a dataset, not a group")))
+                }
+            };
+        }
+        Ok(group)
+    }
+
+    /// Write `group` and its subgroups, returning the addresses of its
+    /// object header, B-tree and local heap.
+    fn write_group(&mut self, group: &Group) -> io::Result<(u64, u64, u64)> {
+        if group.members.len() > 2 * LEAF_K * 2 * INTERNAL_K {
+            return Err(invalid_input(format!(
+                "Groups can hold at most {} members",
+                2 * LEAF_K * 2 * INTERNAL_K
+            )));
+        }
+        let mut members = Vec::with_capacity(group.members.len());
+        for (name, member) in &group.members {
+            let entry = match member {
+                Member::Group(g) => {
+                    let (header, btree, heap) = self.write_group(g)?;
+                    (header, Some((btree, heap)))
+                }
+                Member::Dataset(header) => (*header, None),
+            };
+            members.push((name, entry));
+        }
+
+        // Local heap of member names; offset 0 holds the empty string
+        let mut names = vec![0u8; 8];
+        let mut offsets = Vec::with_capacity(members.len());
+        for (name, _) in &members {
+            offsets.push(names.len() as u64);
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+            pad(&mut names);
+        }
+        self.align()?;
+        let heap = self.position;
+        let mut block = b"HEAP\0\0\0\0".to_vec();
+        put_u64(&mut block, names.len() as u64);
+        // Empty free list
+        put_u64(&mut block, 1);
+        put_u64(&mut block, heap + 32);
+        block.extend_from_slice(&names);
+        self.write(&block)?;
+
+        // Symbol table nodes of up to 2K sorted entries each
+        let mut nodes = Vec::new();
+        for (chunk, chunk_offsets) in members.chunks(2 * LEAF_K).zip(offsets.chunks(2 * LEAF_K)) {
+            let mut node = b"SNOD\x01\0".to_vec();
+            put_u16(&mut node, chunk.len() as u16);
+            for ((_, (header, scratch)), &offset) in chunk.iter().zip(chunk_offsets) {
+                put_entry(&mut node, offset, *header, *scratch);
+            }
+            node.resize(8 + 2 * LEAF_K * ENTRY_SIZE, 0);
+            nodes.push((self.position, *chunk_offsets.last().unwrap()));
+            self.write(&node)?;
+        }
+
+        // Single-level B-tree over the nodes; key i + 1 is the last name in
+        // child i
+        let btree = self.position;
+        let mut tree = b"TREE\0\0".to_vec();
+        put_u16(&mut tree, nodes.len() as u16);
+        put_u64(&mut tree, UNDEFINED);
+        put_u64(&mut tree, UNDEFINED);
+        put_u64(&mut tree, 0);
+        for (address, last_name) in &nodes {
+            put_u64(&mut tree, *address);
+            put_u64(&mut tree, *last_name);
+        }
+        tree.resize(24 + (4 * INTERNAL_K + 1) * 8, 0);
+        self.write(&tree)?;
+
+        let mut table = Vec::with_capacity(16);
+        put_u64(&mut table, btree);
+        put_u64(&mut table, heap);
+        let mut messages = vec![(MSG_SYMBOL_TABLE, table)];
+        messages.extend(group.attributes.iter().map(|a| (MSG_ATTRIBUTE, a.clone())));
+        let header = self.write_object_header(&messages)?;
+        Ok((header, btree, heap))
+    }
+
+    /// Write a version 1 object header holding `messages`.
+    fn write_object_header(&mut self, messages: &[(u16, Vec<u8>)]) -> io::Result<u64> {
+        let mut body = Vec::new();
+        for (kind, data) in messages {
+            let size = data.len().next_multiple_of(8);
+            let size = u16::try_from(size)
+                .map_err(|_| invalid_input("Header message is too large".to_string()))?;
+            put_u16(&mut body, *kind);
+            put_u16(&mut body, size);
+            body.extend_from_slice(&[0; 4]);
+            body.extend_from_slice(data);
+            pad(&mut body);
+        }
+        let mut header = vec![1, 0];
+        put_u16(&mut header, messages.len() as u16);
+        // Reference count
+        put_u32(&mut header, 1);
+        put_u32(&mut header, body.len() as u32);
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&body);
+
+        self.align()?;
+        let address = self.position;
+        self.write(&header)?;
+        Ok(address)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Pad the file to a multiple of 8 bytes.
+    fn align(&mut self) -> io::Result<()> {
+        let padding = self.position.next_multiple_of(8) - self.position;
+        self.write(&[0; 8][..padding as usize])
+    }
+}
+
+/// HDF5 file loaded into memory for reading.
+///
+/// Reads the files this module writes as well as typical h5py output:
+/// superblock versions 0 to 3, version 1 and 2 object headers, symbol-table
+/// and compact link groups, contiguous and compact datasets of integers,
+/// floats and strings, and attributes of these types. Chunked (and so
+/// compressed) datasets and groups or attributes in dense storage are
+/// rejected with `Unsupported` errors.
+#[derive(Debug)]
+pub struct Hdf5File {
+    data: Vec<u8>,
+    /// Offset of the superblock, to which addresses are relative
+    base: u64,
+    root: u64,
+}
+
+/// Parsed element type of a dataset or attribute.
+#[derive(Debug, Clone, Copy)]
+enum Datatype {
+    Int {
+        size: usize,
+        signed: bool,
+        big_endian: bool,
+    },
+    Float {
+        size: usize,
+        big_endian: bool,
+    },
+    /// Fixed-length string
+    Text {
+        size: usize,
+    },
+    /// Variable-length string stored in a global heap
+    VarText,
+}
+
+impl Datatype {
+    fn size(self) -> usize {
+        match self {
+            Datatype::Int { size, .. } | Datatype::Float { size, .. } | Datatype::Text { size } => {
+                size
+            }
+            // Length, collection address and object index
+            Datatype::VarText => 16,
+        }
+    }
+}
+
+/// Decoded elements of a dataset or attribute.
+enum Values {
+    Ints(Vec<i64>),
+    Floats(Vec<f64>),
+    Texts(Vec<String>),
+}
+
+impl Hdf5File {
+    /// Load the file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Parse a file already in memory.
+    pub fn from_bytes(data: Vec<u8>) -> io::Result<Self> {
+        // The superblock follows an optional user block of 512 * 2^k bytes
+        let base = std::iter::successors(Some(0usize), |&b| Some(if b == 0 { 512 } else { 2 * b }))
+            .take_while(|&b| b + 8 <= data.len())
+            .find(|&b| &data[b..b + 8] == b"\x89HDF\r\n\x1a\n")
+            .ok_or_else(|| invalid("Not an HDF5 file".to_string()))?;
+        let mut c = Cursor::new(&data, base + 8);
+        let version = c.u8()?;
+        let root = match version {
+            0 | 1 => {
+                c.skip(4)?;
+                check_sizes(c.u8()?, c.u8()?)?;
+                // Reserved, group K values, flags, indexed storage K (v1)
+                c.skip(1 + 4 + 4 + if version == 1 { 4 } else { 0 })?;
+                // Base, free-space, end-of-file and driver addresses, then
+                // the root entry's link name offset
+                c.skip(5 * 8)?;
+                c.u64()?
+            }
+            2 | 3 => {
+                check_sizes(c.u8()?, c.u8()?)?;
+                // Flags, base, extension and end-of-file addresses
+                c.skip(1 + 3 * 8)?;
+                c.u64()?
+            }
+            v => return Err(unsupported(format!("Superblock version {v}"))),
+        };
+        Ok(Self {
+            data,
+            base: base as u64,
+            root,
+        })
+    }
+
+    /// Whether an object exists at `path`.
+    pub fn contains(&self, path: &str) -> bool {
+        self.object(path).is_ok()
+    }
+
+    /// Names of the members of the group at `path`, sorted.
+    pub fn members(&self, path: &str) -> io::Result<Vec<String>> {
+        let mut names: Vec<String> = self
+            .links(self.object(path)?)?
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Dimensions of the dataset at `path`; empty for a scalar.
+    pub fn shape(&self, path: &str) -> io::Result<Vec<u64>> {
+        let messages = self.messages(self.object(path)?)?;
+        let space = find_message(&messages, MSG_DATASPACE, path)?;
+        Ok(parse_dataspace(space)?.unwrap_or_default())
+    }
+
+    /// Elements of the integer dataset at `path`, in row-major order.
+    pub fn read_ints(&self, path: &str) -> io::Result<Vec<i64>> {
+        match self.read(path)? {
+            Values::Ints(v) => Ok(v),
+            _ => Err(invalid(format!("{path} is not an integer dataset"))),
+        }
+    }
+
+    /// Elements of the numeric dataset at `path` as floats, in row-major
+    /// order.
+    pub fn read_floats(&self, path: &str) -> io::Result<Vec<f64>> {
+        match self.read(path)? {
+            Values::Floats(v) => Ok(v),
+            Values::Ints(v) => Ok(v.into_iter().map(|x| x as f64).collect()),
+            Values::Texts(_) => Err(invalid(format!("{path} is not a numeric dataset"))),
+        }
+    }
+
+    /// Elements of the string dataset at `path`.
+    pub fn read_strings(&self, path: &str) -> io::Result<Vec<String>> {
+        match self.read(path)? {
+            Values::Texts(v) => Ok(v),
+            _ => Err(invalid(format!("{path} is not a string dataset"))),
+        }
+    }
+
+    /// Attribute `name` of the object at `path`, or `None` if it has no
+    /// such attribute. Arrays of one element are returned as scalars.
+    pub fn attribute(&self, path: &str, name: &str) -> io::Result<Option<Attribute>> {
+        for (kind, data) in self.messages(self.object(path)?)? {
+            if kind != MSG_ATTRIBUTE {
+                continue;
+            }
+            let attr = parse_attribute(data)?;
+            if attr.name != name {
+                continue;
+            }
+            let count = element_count(&attr.space);
+            let values = self.decode(parse_datatype(attr.datatype)?, attr.data, count)?;
+            let value = match values {
+                Values::Ints(v) if v.len() == 1 => Attribute::Int(v[0]),
+                Values::Ints(v) => Attribute::Ints(v),
+                Values::Floats(v) if v.len() == 1 => Attribute::Float(v[0]),
+                Values::Texts(mut v) if v.len() == 1 => Attribute::Text(v.remove(0)),
+                Values::Texts(v) => Attribute::Texts(v),
+                _ => {
+                    return Err(unsupported(format!(
+                        "Attribute {name} of {path} is not a scalar or an integer or string array"
+                    )))
+                }
+            };
+            return Ok(Some(value));
+        }
+        Ok(None)
+    }
+
+    fn read(&self, path: &str) -> io::Result<Values> {
+        let messages = self.messages(self.object(path)?)?;
+        let space = parse_dataspace(find_message(&messages, MSG_DATASPACE, path)?)?;
+        let datatype = parse_datatype(find_message(&messages, MSG_DATATYPE, path)?)?;
+        let count = element_count(&space);
+        let size = count * datatype.size();
+        let layout = find_message(&messages, MSG_LAYOUT, path)?;
+        let raw = match self.layout(layout, size)? {
+            Some(raw) => raw,
+            // Never written: every element has the default fill value
+            None => return self.decode(datatype, &vec![0; size], count),
+        };
+        self.decode(datatype, raw, count)
+    }
+
+    /// Raw data described by a layout message, or `None` if unallocated.
+    fn layout<'a>(&'a self, message: &'a [u8], size: usize) -> io::Result<Option<&'a [u8]>> {
+        let mut c = Cursor::new(message, 0);
+        let version = c.u8()?;
+        let (class, address) = match version {
+            1 | 2 => {
+                let rank = c.u8()? as usize;
+                let class = c.u8()?;
+                c.skip(5)?;
+                let address = if class == 0 { UNDEFINED } else { c.u64()? };
+                c.skip(rank * 4)?;
+                if class == 0 {
+                    let len = c.u32()? as usize;
+                    return c.bytes(len.min(size)).map(Some);
+                }
+                (class, address)
+            }
+            3 | 4 => {
+                let class = c.u8()?;
+                if class == 0 {
+                    let len = c.u16()? as usize;
+                    return c.bytes(len.min(size)).map(Some);
+                }
+                (class, if class == 1 { c.u64()? } else { UNDEFINED })
+            }
+            v => return Err(unsupported(format!("Layout message version {v}"))),
+        };
+        match class {
+            1 if address == UNDEFINED => Ok(None),
+            1 => Cursor::new(&self.data, self.offset(address)?)
+                .bytes(size)
+                .map(Some),
+            _ => Err(unsupported(
+                "Chunked datasets are not supported".to_string(),
+            )),
+        }
+    }
+
+    /// Decode `count` elements of `datatype` from `raw`.
+    fn decode(&self, datatype: Datatype, raw: &[u8], count: usize) -> io::Result<Values> {
+        let size = datatype.size();
+        if raw.len() < count * size {
+            return Err(invalid("Dataset is shorter than its shape".to_string()));
+        }
+        let elements = raw.chunks_exact(size.max(1)).take(count);
+        Ok(match datatype {
+            Datatype::Int {
+                signed, big_endian, ..
+            } => Values::Ints(
+                elements
+                    .map(|e| {
+                        let value = uint(e, big_endian);
+                        let bits = 8 * size as u32;
+                        if signed && bits < 64 && value >> (bits - 1) & 1 == 1 {
+                            // Sign-extend
+                            Ok((value | (u64::MAX << bits)) as i64)
+                        } else if !signed && value > i64::MAX as u64 {
+                            Err(invalid(format!("Integer {value} is too large")))
+                        } else {
+                            Ok(value as i64)
+                        }
+                    })
+                    .collect::<io::Result<_>>()?,
+            ),
+            Datatype::Float { big_endian, .. } => Values::Floats(
+                elements
+                    .map(|e| {
+                        let bits = uint(e, big_endian);
+                        if size == 4 {
+                            f32::from_bits(bits as u32) as f64
+                        } else {
+                            f64::from_bits(bits)
+                        }
+                    })
+                    .collect(),
+            ),
+            Datatype::Text { .. } => Values::Texts(elements.map(text).collect()),
+            Datatype::VarText => Values::Texts(
+                elements
+                    .map(|e| {
+                        let mut c = Cursor::new(e, 0);
+                        let len = c.u32()? as usize;
+                        let collection = c.u64()?;
+                        let index = c.u32()?;
+                        let object = self.global_heap_object(collection, index)?;
+                        Ok(text(&object[..len.min(object.len())]))
+                    })
+                    .collect::<io::Result<_>>()?,
+            ),
+        })
+    }
+
+    /// Object `index` of the global heap collection at `address`.
+    fn global_heap_object(&self, address: u64, index: u32) -> io::Result<&[u8]> {
+        let start = self.offset(address)?;
+        let mut c = Cursor::new(&self.data, start);
+        if c.bytes(4)? != b"GCOL" {
+            return Err(invalid("Bad global heap signature".to_string()));
+        }
+        c.skip(4)?;
+        let end = start + c.u64()? as usize;
+        while c.pos + 16 <= end {
+            let id = c.u16()?;
+            c.skip(6)?;
+            let size = c.u64()? as usize;
+            if id == 0 {
+                break;
+            }
+            let data = c.bytes(size)?;
+            if u32::from(id) == index {
+                return Ok(data);
+            }
+            c.skip(size.next_multiple_of(8) - size)?;
+        }
+        Err(invalid(format!("Global heap object {index} not found")))
+    }
+
+    /// Object header address of the object at `path`.
+    fn object(&self, path: &str) -> io::Result<u64> {
+        let mut address = self.root;
+        for name in split(path)? {
+            address = self
+                .links(address)?
+                .into_iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, a)| a)
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("{path} does not exist"))
+                })?;
+        }
+        Ok(address)
+    }
+
+    /// Names and object header addresses of a group's hard links.
+    fn links(&self, group: u64) -> io::Result<Vec<(String, u64)>> {
+        let mut links = Vec::new();
+        for (kind, data) in self.messages(group)? {
+            let mut c = Cursor::new(data, 0);
+            match kind {
+                MSG_SYMBOL_TABLE => {
+                    let btree = c.u64()?;
+                    let heap = c.u64()?;
+                    let mut h = Cursor::new(&self.data, self.offset(heap)?);
+                    if h.bytes(4)? != b"HEAP" {
+                        return Err(invalid("Bad local heap signature".to_string()));
+                    }
+                    h.skip(20)?;
+                    let names = self.offset(h.u64()?)?;
+                    self.symbol_tree(btree, names, &mut links)?;
+                }
+                MSG_LINK => {
+                    c.skip(1)?;
+                    let flags = c.u8()?;
+                    let link_type = if flags & 0x08 != 0 { c.u8()? } else { 0 };
+                    if flags & 0x04 != 0 {
+                        c.skip(8)?;
+                    }
+                    if flags & 0x10 != 0 {
+                        c.skip(1)?;
+                    }
+                    let len = c.uint(1 << (flags & 3))? as usize;
+                    let name = text(c.bytes(len)?);
+                    // Soft and external links are not followed
+                    if link_type == 0 {
+                        links.push((name, c.u64()?));
+                    }
+                }
+                MSG_LINK_INFO => {
+                    c.skip(1)?;
+                    if c.u8()? & 1 != 0 {
+                        c.skip(8)?;
+                    }
+                    if c.u64()? != UNDEFINED {
+                        return Err(unsupported(
+                            "Groups in dense link storage are not supported".to_string(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(links)
+    }
+
+    /// Collect the entries of a group's B-tree at `address`.
+    fn symbol_tree(
+        &self,
+        address: u64,
+        names: usize,
+        links: &mut Vec<(String, u64)>,
+    ) -> io::Result<()> {
+        let mut c = Cursor::new(&self.data, self.offset(address)?);
+        if c.bytes(4)? != b"TREE" || c.u8()? != 0 {
+            return Err(invalid("Bad group B-tree node".to_string()));
+        }
+        let level = c.u8()?;
+        let entries = c.u16()?;
+        c.skip(16 + 8)?;
+        for _ in 0..entries {
+            let child = c.u64()?;
+            c.skip(8)?;
+            if level > 0 {
+                self.symbol_tree(child, names, links)?;
+                continue;
+            }
+            let mut node = Cursor::new(&self.data, self.offset(child)?);
+            if node.bytes(4)? != b"SNOD" {
+                return Err(invalid("Bad symbol table node".to_string()));
+            }
+            node.skip(2)?;
+            for _ in 0..node.u16()? {
+                let name_offset = node.u64()? as usize;
+                let header = node.u64()?;
+                node.skip(24)?;
+                let start = names + name_offset;
+                let len = self.data[start.min(self.data.len())..]
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(|| invalid("Unterminated link name".to_string()))?;
+                links.push((text(&self.data[start..start + len]), header));
+            }
+        }
+        Ok(())
+    }
+
+    /// Messages of the object header at `address`, following
+    /// continuations.
+    fn messages(&self, address: u64) -> io::Result<Vec<(u16, &[u8])>> {
+        let start = self.offset(address)?;
+        let mut c = Cursor::new(&self.data, start);
+        // Chunks as (start, end) with the header version
+        let mut chunks = Vec::new();
+        let version2 = c.bytes(4)? == b"OHDR";
+        let mut creation_order = false;
+        if version2 {
+            c.skip(1)?;
+            let flags = c.u8()?;
+            creation_order = flags & 0x04 != 0;
+            if flags & 0x20 != 0 {
+                c.skip(16)?;
+            }
+            if flags & 0x10 != 0 {
+                c.skip(4)?;
+            }
+            let size = c.uint(1 << (flags & 3))? as usize;
+            // The chunk ends with a checksum
+            chunks.push((c.pos, (c.pos + size).saturating_sub(4)));
+        } else {
+            let mut c = Cursor::new(&self.data, start);
+            if c.u8()? != 1 {
+                return Err(invalid("Bad object header".to_string()));
+            }
+            c.skip(7)?;
+            let size = c.u32()? as usize;
+            chunks.push((start + 16, start + 16 + size));
+        }
+
+        let mut messages = Vec::new();
+        let mut k = 0;
+        while let Some(&(begin, end)) = chunks.get(k) {
+            k += 1;
+            let mut c = Cursor::new(&self.data, begin);
+            let prefix = if version2 {
+                4 + 2 * creation_order as usize
+            } else {
+                8
+            };
+            while c.pos + prefix <= end {
+                let (kind, size) = if version2 {
+                    let kind = u16::from(c.u8()?);
+                    let size = c.u16()? as usize;
+                    c.skip(prefix - 3)?;
+                    (kind, size)
+                } else {
+                    let kind = c.u16()?;
+                    let size = c.u16()? as usize;
+                    c.skip(4)?;
+                    (kind, size)
+                };
+                let data = c.bytes(size)?;
+                if kind == MSG_CONTINUATION {
+                    let mut d = Cursor::new(data, 0);
+                    let begin = self.offset(d.u64()?)?;
+                    let len = d.u64()? as usize;
+                    chunks.push(if version2 {
+                        (begin + 4, begin + len - 4)
+                    } else {
+                        (begin, begin + len)
+                    });
+                } else {
+                    messages.push((kind, data));
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Position in the file of `address`.
+    fn offset(&self, address: u64) -> io::Result<usize> {
+        let offset = address.saturating_add(self.base);
+        if offset >= self.data.len() as u64 {
+            return Err(invalid(format!("Address {address} is outside the file")));
+        }
+        Ok(offset as usize)
+    }
+}
+
+/// Bounds-checked little-endian reader over a byte slice.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("Unexpected end of HDF5 data".to_string()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn uint(&mut self, size: usize) -> io::Result<u64> {
+        self.bytes(size).map(|b| uint(b, false))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.uint(1).map(|v| v as u8)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        self.uint(2).map(|v| v as u16)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.uint(4).map(|v| v as u32)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        self.uint(8)
+    }
+}
+
+/// Unsigned integer of up to 8 bytes.
+fn uint(bytes: &[u8], big_endian: bool) -> u64 {
+    let fold = |v: u64, &b: &u8| v << 8 | u64::from(b);
+    if big_endian {
+        bytes.iter().take(8).fold(0, fold)
+    } else {
+        bytes.iter().take(8).rev().fold(0, fold)
+    }
+}
+
+/// String up to the first NUL.
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn check_sizes(offsets: u8, lengths: u8) -> io::Result<()> {
+    if offsets == 8 && lengths == 8 {
+        Ok(())
+    } else {
+        Err(unsupported(format!(
+            "Offsets of {offsets} and lengths of {lengths} bytes"
+        )))
+    }
+}
+
+fn find_message<'a>(messages: &[(u16, &'a [u8])], kind: u16, path: &str) -> io::Result<&'a [u8]> {
+    messages
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, d)| *d)
+        .ok_or_else(|| invalid(format!("{path} is not a dataset")))
+}
+
+/// Dimensions of a dataspace; `None` for a null dataspace.
+fn parse_dataspace(message: &[u8]) -> io::Result<Option<Vec<u64>>> {
+    let mut c = Cursor::new(message, 0);
+    let version = c.u8()?;
+    let rank = c.u8()? as usize;
+    c.skip(1)?;
+    match version {
+        1 => c.skip(5)?,
+        2 => {
+            if c.u8()? == 2 {
+                return Ok(None);
+            }
+        }
+        v => return Err(unsupported(format!("Dataspace version {v}"))),
+    }
+    (0..rank)
+        .map(|_| c.u64())
+        .collect::<io::Result<_>>()
+        .map(Some)
+}
+
+fn element_count(space: &Option<Vec<u64>>) -> usize {
+    space
+        .as_ref()
+        .map_or(0, |dims| dims.iter().product::<u64>() as usize)
+}
+
+fn parse_datatype(message: &[u8]) -> io::Result<Datatype> {
+    let mut c = Cursor::new(message, 0);
+    let class = c.u8()? & 0x0F;
+    let bits = c.u8()?;
+    c.skip(2)?;
+    let size = c.u32()? as usize;
+    let big_endian = bits & 1 != 0;
+    match class {
+        0 if (1..=8).contains(&size) => Ok(Datatype::Int {
+            size,
+            signed: bits & 0x08 != 0,
+            big_endian,
+        }),
+        1 if size == 4 || size == 8 => Ok(Datatype::Float { size, big_endian }),
+        3 => Ok(Datatype::Text { size }),
+        9 if bits & 0x0F == 1 => Ok(Datatype::VarText),
+        _ => Err(unsupported(format!(
+            "Datatype class {class} of {size} bytes"
+        ))),
+    }
+}
+
+/// Fields of an attribute message.
+struct RawAttribute<'a> {
+    name: String,
+    datatype: &'a [u8],
+    space: Option<Vec<u64>>,
+    data: &'a [u8],
+}
+
+fn parse_attribute(message: &[u8]) -> io::Result<RawAttribute<'_>> {
+    let mut c = Cursor::new(message, 0);
+    let version = c.u8()?;
+    c.skip(1)?;
+    let name_size = c.u16()? as usize;
+    let datatype_size = c.u16()? as usize;
+    let space_size = c.u16()? as usize;
+    let padded = |n: usize| {
+        if version == 1 {
+            n.next_multiple_of(8)
+        } else {
+            n
+        }
+    };
+    match version {
+        1 | 2 => {}
+        3 => c.skip(1)?,
+        v => return Err(unsupported(format!("Attribute message version {v}"))),
+    }
+    let name = text(c.bytes(name_size)?);
+    c.skip(padded(name_size) - name_size)?;
+    let datatype = c.bytes(datatype_size)?;
+    c.skip(padded(datatype_size) - datatype_size)?;
+    let space = parse_dataspace(c.bytes(space_size)?)?;
+    c.skip(padded(space_size) - space_size)?;
+    Ok(RawAttribute {
+        name,
+        datatype,
+        space,
+        data: &message[c.pos..],
+    })
+}
+
+/// `units = "ms"` attribute of time datasets.
+fn ms() -> (&'static str, Attribute) {
+    ("units", Attribute::Text("ms".to_string()))
+}
+
+/// Split an absolute or relative path into its names.
+fn split(path: &str) -> io::Result<Vec<String>> {
+    let names: Vec<String> = path
+        .split('/')
+        .filter(|n| !n.is_empty() && *n != ".")
+        .map(str::to_string)
+        .collect();
+    if names.iter().any(|n| n.contains('\0')) {
+        return Err(invalid_input(format!("Invalid path {path:?}")));
+    }
+    Ok(names)
+}
+
+/// Version 1 simple dataspace with `dims`; no dimensions is a scalar.
+fn dataspace(dims: &[u64]) -> Vec<u8> {
+    let mut data = vec![1, dims.len() as u8, 0, 0, 0, 0, 0, 0];
+    for &d in dims {
+        put_u64(&mut data, d);
+    }
+    data
+}
+
+/// Little-endian IEEE double.
+fn float64_datatype() -> Vec<u8> {
+    // Class 1, version 1; implied leading mantissa bit, sign at bit 63
+    let mut data = vec![0x11, 0x20, 63, 0];
+    put_u32(&mut data, 8);
+    put_u16(&mut data, 0);
+    put_u16(&mut data, 64);
+    // Exponent at bit 52 of size 11, mantissa at bit 0 of size 52
+    data.extend_from_slice(&[52, 11, 0, 52]);
+    put_u32(&mut data, 1023);
+    data
+}
+
+/// Variable-length UTF-8 string, as h5py stores `str`.
+fn vlen_string_datatype() -> Vec<u8> {
+    // Class 9, version 1: sequence of type string, null-terminated padding,
+    // UTF-8; each element is a 16-byte global heap descriptor
+    let mut data = vec![0x19, 0x01, 0x01, 0];
+    put_u32(&mut data, 16);
+    // Base type: unsigned byte
+    data.extend_from_slice(&[0x10, 0, 0, 0]);
+    put_u32(&mut data, 1);
+    put_u16(&mut data, 0);
+    put_u16(&mut data, 8);
+    data
+}
+
+/// Little-endian 64-bit integer.
+fn integer64_datatype(signed: bool) -> Vec<u8> {
+    let mut data = vec![0x10, if signed { 0x08 } else { 0 }, 0, 0];
+    put_u32(&mut data, 8);
+    put_u16(&mut data, 0);
+    put_u16(&mut data, 64);
+    data
+}
+
+/// Symbol table entry; `scratch` caches a group's B-tree and heap.
+fn put_entry(out: &mut Vec<u8>, name_offset: u64, header: u64, scratch: Option<(u64, u64)>) {
+    put_u64(out, name_offset);
+    put_u64(out, header);
+    put_u32(out, scratch.is_some() as u32);
+    put_u32(out, 0);
+    let (btree, heap) = scratch.unwrap_or((0, 0));
+    put_u64(out, btree);
+    put_u64(out, heap);
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Zero-pad `out` to a multiple of 8 bytes.
+fn pad(out: &mut Vec<u8>) {
+    out.resize(out.len().next_multiple_of(8), 0);
+}
+
+fn unsupported(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
@@ -0,0 +1,246 @@
+//! inference.rs
+//!
+//! Effective connectivity inference from spike trains.
+//!
+//! Experimental data only offer recorded spikes, from which the circuit
+//! has to be guessed. In simulation the true synapse graph is known, so
+//! inference methods can be checked against it. This module scores every
+//! ordered neuron pair by transfer entropy or by the causal peak of its
+//! cross-correlogram, and compares score matrices with the ground-truth
+//! synapses by precision, recall and ROC AUC.
+//!
+//! Score matrices are indexed `[source][target]` relative to the start of
+//! the analysed neuron range; the diagonal is zero.
+
+use crate::analysis::{binned_counts, cross_correlogram, spike_trains};
+use crate::spike::Spike;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Transfer entropy (bits) from `source` to `target`, two binary series of
+/// equal length.
+///
+/// Uses `history` past bins of both series, with the source history ending
+/// `lag` bins before the predicted target bin (`lag = 1` is the usual
+/// choice; larger lags account for synaptic delays).
+///
+/// # Panics
+/// Panics if `history` is zero or above 16, or `lag` is zero.
+pub fn transfer_entropy(source: &[bool], target: &[bool], history: usize, lag: usize) -> f64 {
+    assert!((1..=16).contains(&history), "History must be 1 to 16 bins");
+    assert!(lag >= 1, "Lag must be at least one bin");
+    let n = source.len().min(target.len());
+    let first = history.max(lag + history - 1);
+    if n <= first {
+        return 0.0;
+    }
+    let pack = |series: &[bool], end: usize| {
+        series[end + 1 - history..=end]
+            .iter()
+            .fold(0u32, |acc, &b| (acc << 1) | b as u32)
+    };
+
+    // Joint counts of (next target, target history, source history)
+    let mut joint: HashMap<(bool, u32, u32), usize> = HashMap::new();
+    for t in first..n {
+        let y_hist = pack(target, t - 1);
+        let x_hist = pack(source, t - lag);
+        *joint.entry((target[t], y_hist, x_hist)).or_default() += 1;
+    }
+    let total = (n - first) as f64;
+    let mut yy: HashMap<(bool, u32), usize> = HashMap::new();
+    let mut yx: HashMap<(u32, u32), usize> = HashMap::new();
+    let mut y: HashMap<u32, usize> = HashMap::new();
+    for (&(next, yh, xh), &c) in &joint {
+        *yy.entry((next, yh)).or_default() += c;
+        *yx.entry((yh, xh)).or_default() += c;
+        *y.entry(yh).or_default() += c;
+    }
+    joint
+        .iter()
+        .map(|(&(next, yh, xh), &c)| {
+            let with_source = c as f64 / yx[&(yh, xh)] as f64;
+            let without = yy[&(next, yh)] as f64 / y[&yh] as f64;
+            c as f64 / total * (with_source / without).log2()
+        })
+        .sum::<f64>()
+        .max(0.0)
+}
+
+/// Pairwise transfer entropy between the neurons in `neurons`, from spikes
+/// binned with width `bin` over `[start, end)`. A bin is active if it
+/// holds at least one spike.
+pub fn transfer_entropy_matrix(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    start: Milliseconds,
+    end: Milliseconds,
+    bin: Milliseconds,
+    history: usize,
+    lag: usize,
+) -> Vec<Vec<f64>> {
+    let series: Vec<Vec<bool>> = binned_counts(spikes, neurons, start, end, bin)
+        .into_iter()
+        .map(|counts| counts.into_iter().map(|c| c > 0.0).collect())
+        .collect();
+    pairwise(series.len(), |i, j| {
+        transfer_entropy(&series[i], &series[j], history, lag)
+    })
+}
+
+/// Pairwise cross-correlogram scores between the neurons in `neurons`.
+///
+/// The score of `i → j` is the height of the largest bin at positive lags
+/// up to `max_lag` (`j` firing after `i`) above the mean of the
+/// negative-lag bins, in units of the Poisson standard deviation of that
+/// mean.
+pub fn correlation_matrix(
+    spikes: &[Spike],
+    neurons: Range<usize>,
+    max_lag: Milliseconds,
+    bin: Milliseconds,
+) -> Vec<Vec<f64>> {
+    let offset = neurons.start;
+    let trains = spike_trains(spikes, neurons.end);
+    pairwise(neurons.len(), |i, j| {
+        let ccg = cross_correlogram(&trains[offset + i], &trains[offset + j], max_lag, bin);
+        let lags = ccg.lags();
+        let (mut baseline, mut n_baseline, mut peak) = (0.0, 0, 0usize);
+        for (lag, &c) in lags.iter().zip(&ccg.counts) {
+            if lag.0 + bin.0 <= 1e-9 {
+                baseline += c as f64;
+                n_baseline += 1;
+            } else if lag.0 >= -1e-9 {
+                peak = peak.max(c);
+            }
+        }
+        let mean = if n_baseline > 0 {
+            baseline / n_baseline as f64
+        } else {
+            0.0
+        };
+        (peak as f64 - mean) / mean.max(1.0).sqrt()
+    })
+}
+
+/// Agreement of an inferred connectivity matrix with the true synapses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectivityComparison {
+    /// Connected pairs scored at or above the threshold
+    pub true_positives: usize,
+    /// Unconnected pairs scored at or above the threshold
+    pub false_positives: usize,
+    /// Connected pairs scored below the threshold
+    pub false_negatives: usize,
+    /// Unconnected pairs scored below the threshold
+    pub true_negatives: usize,
+}
+
+impl ConnectivityComparison {
+    /// Compare `scores` over `neurons` with the nonzero synapses among
+    /// them, predicting a connection where the score is at least
+    /// `threshold`. Self-pairs are ignored.
+    pub fn compute(
+        scores: &[Vec<f64>],
+        neurons: Range<usize>,
+        synapses: &[Synapse],
+        threshold: f64,
+    ) -> Self {
+        let truth = ground_truth(&neurons, synapses);
+        let mut result = Self {
+            true_positives: 0,
+            false_positives: 0,
+            false_negatives: 0,
+            true_negatives: 0,
+        };
+        for (score, connected) in labelled_scores(scores, &truth) {
+            match (score >= threshold, connected) {
+                (true, true) => result.true_positives += 1,
+                (true, false) => result.false_positives += 1,
+                (false, true) => result.false_negatives += 1,
+                (false, false) => result.true_negatives += 1,
+            }
+        }
+        result
+    }
+
+    /// Fraction of predicted connections that exist, if any were predicted.
+    pub fn precision(&self) -> Option<f64> {
+        let predicted = self.true_positives + self.false_positives;
+        (predicted > 0).then(|| self.true_positives as f64 / predicted as f64)
+    }
+
+    /// Fraction of existing connections that were predicted, if any exist.
+    pub fn recall(&self) -> Option<f64> {
+        let actual = self.true_positives + self.false_negatives;
+        (actual > 0).then(|| self.true_positives as f64 / actual as f64)
+    }
+}
+
+/// Area under the ROC curve of `scores` as a predictor of the synapses
+/// among `neurons`: the probability that a connected pair outscores an
+/// unconnected one, ties counting half. `None` if either class is empty.
+pub fn roc_auc(scores: &[Vec<f64>], neurons: Range<usize>, synapses: &[Synapse]) -> Option<f64> {
+    let truth = ground_truth(&neurons, synapses);
+    let mut labelled = labelled_scores(scores, &truth);
+    labelled.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let positives = labelled.iter().filter(|(_, c)| *c).count();
+    let negatives = labelled.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+    // Mann–Whitney U from average ranks of tied groups
+    let mut rank_sum = 0.0;
+    let mut k = 0;
+    while k < labelled.len() {
+        let mut end = k;
+        while end + 1 < labelled.len() && labelled[end + 1].0 == labelled[k].0 {
+            end += 1;
+        }
+        let average_rank = (k + end) as f64 / 2.0 + 1.0;
+        let tied_positives = labelled[k..=end].iter().filter(|(_, c)| *c).count();
+        rank_sum += average_rank * tied_positives as f64;
+        k = end + 1;
+    }
+    let p = positives as f64;
+    Some((rank_sum - p * (p + 1.0) / 2.0) / (p * negatives as f64))
+}
+
+/// `n × n` matrix of `score(i, j)` with a zero diagonal.
+fn pairwise<F: Fn(usize, usize) -> f64>(n: usize, score: F) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if i == j { 0.0 } else { score(i, j) })
+                .collect()
+        })
+        .collect()
+}
+
+/// Adjacency among `neurons` implied by the nonzero synapses.
+fn ground_truth(neurons: &Range<usize>, synapses: &[Synapse]) -> Vec<Vec<bool>> {
+    let n = neurons.len();
+    let mut truth = vec![vec![false; n]; n];
+    for s in synapses {
+        if s.weight != 0.0 && neurons.contains(&s.pre_neuron) && neurons.contains(&s.post_neuron) {
+            truth[s.pre_neuron - neurons.start][s.post_neuron - neurons.start] = true;
+        }
+    }
+    truth
+}
+
+/// Off-diagonal scores paired with whether the pair is connected.
+fn labelled_scores(scores: &[Vec<f64>], truth: &[Vec<bool>]) -> Vec<(f64, bool)> {
+    let mut labelled = Vec::new();
+    for (i, (row, connected)) in scores.iter().zip(truth).enumerate() {
+        for (j, (&score, &c)) in row.iter().zip(connected).enumerate() {
+            if i != j {
+                labelled.push((score, c));
+            }
+        }
+    }
+    labelled
+}
@@ -0,0 +1,252 @@
+//! lava.rs
+//!
+//! Export to Intel's Lava framework for Loihi.
+//!
+//! Lava builds networks from `LIF` neuron processes connected by `Sparse`
+//! and `DelaySparse` synapse processes, which run on a CPU or, with the
+//! bit-accurate models, on Loihi 2. This module describes a simulation's
+//! network as a JSON document holding the constructor arguments of these
+//! processes, in floating point for Lava's CPU models and as the integer
+//! mantissas and exponents Loihi needs, so a prototype can be rebuilt in
+//! Lava with a few lines of Python:
+//!
+//! ```python
+//! import json, numpy as np, scipy.sparse as sp
+//! from lava.proc.lif.process import LIF
+//! from lava.proc.sparse.process import DelaySparse
+//!
+//! net = json.load(open("net.lava.json"))
+//! lif = {p["name"]: LIF(shape=(p["size"],), **p["fixed"]) for p in net["populations"]}
+//! for c in net["projections"]:
+//!     matrix = lambda v: sp.csr_matrix((v, (c["post_index"], c["pre_index"])), shape=c["shape"])
+//!     syn = DelaySparse(weights=matrix(c["fixed"]["weights"]), delays=matrix(c["delays"]),
+//!                       weight_exp=c["fixed"]["weight_exp"], num_weight_bits=8)
+//!     lif[c["pre"]].s_out.connect(syn.s_in)
+//!     syn.a_out.connect(lif[c["post"]].a_in)
+//! ```
+//!
+//! Lava's current-based LIF resets to zero, so each population's voltages
+//! are shifted by its `v_reset`, which is recorded as `v_offset`. With
+//! `du = 1` the synaptic current lasts one step and reproduces this crate's
+//! instantaneous voltage jumps; weights are scaled by `1 - dv` of their
+//! target to match the leak applied in the arrival step, and `delays` count
+//! the steps beyond Lava's inherent one-step latency. In fixed point,
+//! voltages are multiplied by `voltage_scale` and rounded: thresholds to 17
+//! bits, biases to 13-bit mantissas, and weights of each projection to
+//! 8-bit mantissas with a shared exponent. A constant input current `I`
+//! becomes an extra bias of `I * dv`.
+//!
+//! Plasticity is not exported; the synapses are static at their current
+//! weights, which can be quantized first with a `Quantization` to study
+//! the effect of on-chip precision.
+
+use crate::compression;
+use crate::json::{write_number, write_string};
+use crate::neuroml::segments;
+use crate::simulation::Simulation;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+/// Loihi fixed-point limits.
+const DECAY_UNITY: f64 = 4096.0;
+const MAX_VTH: f64 = ((1 << 17) - 1) as f64;
+const MAX_BIAS_MANT: f64 = 4095.0;
+const MAX_BIAS_EXP: i32 = 7;
+const MAX_WEIGHT_MANT: f64 = 127.0;
+const WEIGHT_EXP_RANGE: (i32, i32) = (-8, 7);
+/// Left shift applied to synaptic input and thresholds by the hardware
+const ACTIVATION_SHIFT: i32 = 6;
+
+/// JSON description of the network of `sim` as Lava processes; see the
+/// module documentation for its use.
+pub fn to_lava(sim: &Simulation, name: &str) -> String {
+    let segments = segments(sim);
+    let neurons = sim.neurons();
+    let dt = sim.config().dt.0;
+    let dv = |i: usize| (dt / neurons[i].params.tau_m.0).min(1.0);
+    let mut segment_of = vec![0; neurons.len()];
+    for (k, segment) in segments.iter().enumerate() {
+        segment_of[segment.range.clone()].fill(k);
+    }
+
+    // One voltage scale for the whole network, as large as the highest
+    // threshold and weight allow
+    let max_vth = segments
+        .iter()
+        .map(|s| {
+            let p = &neurons[s.range.start].params;
+            p.v_thresh - p.v_reset
+        })
+        .fold(0.0, f64::max);
+    let max_weight = sim
+        .synapses()
+        .iter()
+        .map(|s| (s.weight * (1.0 - dv(s.post_neuron))).abs())
+        .fold(0.0, f64::max);
+    let weight_limit = MAX_WEIGHT_MANT * f64::from(WEIGHT_EXP_RANGE.1).exp2();
+    let shift = [(MAX_VTH, max_vth), (weight_limit, max_weight)]
+        .iter()
+        .filter(|(_, value)| *value > 0.0)
+        .map(|(limit, value)| (limit / value).log2().floor() as i32)
+        .min()
+        .unwrap_or(0);
+    let vth_scale = f64::from(shift).exp2();
+    let scale = vth_scale * f64::from(ACTIVATION_SHIFT).exp2();
+
+    let mut out = String::from("{\n  \"name\": ");
+    write_string(&mut out, name);
+    out.push_str(",\n  \"dt_ms\": ");
+    write_number(&mut out, dt);
+    out.push_str(",\n  \"num_steps\": ");
+    let t_max = sim.config().t_max.0;
+    if t_max.is_finite() {
+        let _ = write!(out, "{}", (t_max / dt).round() as u64);
+    } else {
+        out.push_str("null");
+    }
+    out.push_str(",\n  \"voltage_scale\": ");
+    write_number(&mut out, scale);
+    out.push_str(",\n  \"populations\": [");
+
+    for (k, segment) in segments.iter().enumerate() {
+        let p = &neurons[segment.range.start].params;
+        let dv = dv(segment.range.start);
+        let bias = (p.v_rest - p.v_reset) * dv;
+        let vth = p.v_thresh - p.v_reset;
+        let v: Vec<f64> = segment
+            .range
+            .clone()
+            .map(|i| neurons[i].v_mem - p.v_reset)
+            .collect();
+        let (bias_mant, bias_exp) = mantissa(bias * scale, MAX_BIAS_MANT, (0, MAX_BIAS_EXP));
+
+        out.push_str(if k == 0 { "\n    {" } else { ",\n    {" });
+        out.push_str("\"name\": ");
+        write_string(&mut out, &segment.id);
+        let _ = write!(
+            out,
+            ", \"size\": {}, \"first_neuron\": {}, \"v_offset\": ",
+            segment.range.len(),
+            segment.range.start
+        );
+        write_number(&mut out, p.v_reset);
+        out.push_str(",\n     \"float\": {\"du\": 1, \"dv\": ");
+        write_number(&mut out, dv);
+        out.push_str(", \"bias_mant\": ");
+        write_number(&mut out, bias);
+        out.push_str(", \"vth\": ");
+        write_number(&mut out, vth);
+        out.push_str(", \"v\": ");
+        write_list(&mut out, v.iter().copied());
+        let _ = write!(
+            out,
+            "}},\n     \"fixed\": {{\"du\": 4095, \"dv\": {}, \"bias_mant\": {bias_mant}, \
+             \"bias_exp\": {bias_exp}, \"vth\": {}, \"v\": ",
+            (dv * DECAY_UNITY).round().min(DECAY_UNITY - 1.0),
+            (vth * vth_scale).round().clamp(0.0, MAX_VTH)
+        );
+        write_list(&mut out, v.iter().map(|v| (v * scale).round()));
+        out.push_str("}}");
+    }
+    out.push_str("\n  ],\n  \"projections\": [");
+
+    // One projection per pair of populations, in population order
+    let mut projections: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (k, syn) in sim.synapses().iter().enumerate() {
+        projections
+            .entry((segment_of[syn.pre_neuron], segment_of[syn.post_neuron]))
+            .or_default()
+            .push(k);
+    }
+    for (n, ((pre, post), synapses)) in projections.into_iter().enumerate() {
+        let (pre, post) = (&segments[pre], &segments[post]);
+        let synapses: Vec<_> = synapses.iter().map(|&k| &sim.synapses()[k]).collect();
+        let weights: Vec<f64> = synapses
+            .iter()
+            .map(|s| s.weight * (1.0 - dv(s.post_neuron)))
+            .collect();
+        let largest = weights.iter().fold(0.0, |m: f64, w| m.max(w.abs()));
+        let (_, weight_exp) = mantissa(largest * vth_scale, MAX_WEIGHT_MANT, WEIGHT_EXP_RANGE);
+        let weight_unit = f64::from(weight_exp).exp2();
+
+        out.push_str(if n == 0 { "\n    {" } else { ",\n    {" });
+        out.push_str("\"pre\": ");
+        write_string(&mut out, &pre.id);
+        out.push_str(", \"post\": ");
+        write_string(&mut out, &post.id);
+        let _ = write!(
+            out,
+            ", \"shape\": [{}, {}],\n     \"pre_index\": ",
+            post.range.len(),
+            pre.range.len()
+        );
+        write_list(
+            &mut out,
+            synapses
+                .iter()
+                .map(|s| (s.pre_neuron - pre.range.start) as f64),
+        );
+        out.push_str(",\n     \"post_index\": ");
+        write_list(
+            &mut out,
+            synapses
+                .iter()
+                .map(|s| (s.post_neuron - post.range.start) as f64),
+        );
+        out.push_str(",\n     \"delays\": ");
+        write_list(
+            &mut out,
+            synapses
+                .iter()
+                .map(|s| (s.delay.0 / dt).round().max(1.0) - 1.0),
+        );
+        out.push_str(",\n     \"float\": {\"weights\": ");
+        write_list(&mut out, weights.iter().copied());
+        out.push_str("},\n     \"fixed\": {\"weights\": ");
+        write_list(
+            &mut out,
+            weights.iter().map(|w| {
+                (w * vth_scale / weight_unit)
+                    .round()
+                    .clamp(-MAX_WEIGHT_MANT - 1.0, MAX_WEIGHT_MANT)
+            }),
+        );
+        let _ = write!(out, ", \"weight_exp\": {weight_exp}}}}}");
+    }
+    out.push_str("\n  ]\n}\n");
+    out
+}
+
+/// Write the Lava description of `sim` to `path`.
+pub fn write_lava<P: AsRef<Path>>(sim: &Simulation, name: &str, path: P) -> io::Result<()> {
+    compression::write(path, to_lava(sim, name))
+}
+
+/// Mantissa and exponent with `mantissa * 2^exponent` closest to `value`,
+/// using the smallest exponent in `exponents` whose mantissa fits in
+/// `max_mantissa`.
+fn mantissa(value: f64, max_mantissa: f64, exponents: (i32, i32)) -> (f64, i32) {
+    let (low, high) = exponents;
+    let mut exponent = low;
+    while exponent < high && (value / f64::from(exponent).exp2()).round().abs() > max_mantissa {
+        exponent += 1;
+    }
+    let mantissa = (value / f64::from(exponent).exp2())
+        .round()
+        .clamp(-max_mantissa - 1.0, max_mantissa);
+    (mantissa, exponent)
+}
+
+/// Write `values` as a JSON array.
+fn write_list(out: &mut String, values: impl Iterator<Item = f64>) {
+    out.push('[');
+    for (k, value) in values.enumerate() {
+        if k > 0 {
+            out.push_str(", ");
+        }
+        write_number(out, value);
+    }
+    out.push(']');
+}
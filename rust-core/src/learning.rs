@@ -0,0 +1,238 @@
+//! learning.rs
+//!
+//! Trial-based supervised learning rules.
+//!
+//! STDP updates weights online from local spike timing and needs no
+//! teacher. Supervised rules instead compare what the network did during
+//! one presentation of an input, a trial, with what it should have done,
+//! and update weights once the trial is over. A `LearningRule` observes
+//! every step of a trial like a `Monitor` and is then asked for the error
+//! and a weight update; `run_trial`, `train_trial` and `train` drive the
+//! simulation through trials so rules only implement the learning.
+//!
+//! Each trial starts from `Simulation::reset`, injects the input spikes and
+//! runs without external current. Synapses trained by a rule should be
+//! `Plasticity::Static`, otherwise STDP changes them during the trial too.
+//!
+//! Supervised STDP needs no rule: `run_teacher_forced` clamps the output
+//! neurons to fire target spikes during a trial, so that STDP potentiates
+//! the inputs that precede each target spike and depresses those that
+//! follow it.
+
+use crate::monitor::StepView;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+
+/// A supervised rule that updates weights after each trial.
+pub trait LearningRule {
+    /// What a trial should produce, e.g. a class label or target trains
+    type Target;
+
+    /// Clear per-trial state before a trial of `sim` starts.
+    fn begin_trial(&mut self, sim: &Simulation);
+
+    /// Observe one step of the trial.
+    fn observe(&mut self, step: &StepView<'_>);
+
+    /// Error of the observed trial with respect to `target`; 0 means the
+    /// network did exactly what it should.
+    fn error(&self, target: &Self::Target) -> f64;
+
+    /// Update the weights of `sim` from the observed trial and `target`.
+    fn update(&mut self, sim: &mut Simulation, target: &Self::Target);
+}
+
+/// Present `input` to `sim` from rest for `duration` while `rule` observes,
+/// and return the spikes of the trial.
+pub fn run_trial<R: LearningRule>(
+    sim: &mut Simulation,
+    rule: &mut R,
+    input: &[Spike],
+    duration: Milliseconds,
+) -> Vec<Spike> {
+    sim.reset();
+    rule.begin_trial(sim);
+    sim.inject_spikes(input);
+    let dt = sim.config().dt;
+    let mut spikes = Vec::new();
+    while sim.time() < duration {
+        let time = sim.time();
+        let stepped = sim.step(|_, _| 0.0);
+        let fired: Vec<usize> = stepped.iter().map(|s| s.neuron_id).collect();
+        rule.observe(&StepView {
+            time,
+            dt,
+            fired: &fired,
+            neurons: sim.neurons(),
+            synapses: sim.synapses(),
+        });
+        spikes.extend(stepped);
+    }
+    spikes
+}
+
+/// Run one trial, update the weights and return the error before the
+/// update.
+pub fn train_trial<R: LearningRule>(
+    sim: &mut Simulation,
+    rule: &mut R,
+    input: &[Spike],
+    duration: Milliseconds,
+    target: &R::Target,
+) -> f64 {
+    run_trial(sim, rule, input, duration);
+    let error = rule.error(target);
+    rule.update(sim, target);
+    error
+}
+
+/// Mean error of `rule` over `samples` of `(input, target)`, without
+/// learning.
+pub fn evaluate<R: LearningRule>(
+    sim: &mut Simulation,
+    rule: &mut R,
+    samples: &[(Vec<Spike>, R::Target)],
+    duration: Milliseconds,
+) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = samples
+        .iter()
+        .map(|(input, target)| {
+            run_trial(sim, rule, input, duration);
+            rule.error(target)
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// Train on `samples` of `(input, target)` in order for `epochs` passes,
+/// each trial lasting `duration`, and return the mean error of every epoch.
+///
+/// Training stops early after an epoch without errors.
+pub fn train<R: LearningRule>(
+    sim: &mut Simulation,
+    rule: &mut R,
+    samples: &[(Vec<Spike>, R::Target)],
+    duration: Milliseconds,
+    epochs: usize,
+) -> Vec<f64> {
+    let mut errors = Vec::with_capacity(epochs);
+    for _ in 0..epochs {
+        let total: f64 = samples
+            .iter()
+            .map(|(input, target)| train_trial(sim, rule, input, duration, target))
+            .sum();
+        let mean = total / samples.len().max(1) as f64;
+        errors.push(mean);
+        if mean == 0.0 {
+            break;
+        }
+    }
+    errors
+}
+
+/// Present `input` to `sim` from rest for `duration` while the neurons of
+/// `teacher` are clamped to fire exactly its spikes, and return the spikes
+/// of the trial.
+///
+/// Neurons that were not clamped before the trial are released after it.
+pub fn run_teacher_forced(
+    sim: &mut Simulation,
+    input: &[Spike],
+    teacher: &[Spike],
+    duration: Milliseconds,
+) -> Vec<Spike> {
+    let mut released: Vec<usize> = teacher
+        .iter()
+        .map(|s| s.neuron_id)
+        .filter(|&i| !sim.is_clamped(i))
+        .collect();
+    released.sort_unstable();
+    released.dedup();
+    sim.reset();
+    sim.clamp_spikes(teacher);
+    sim.inject_spikes(input);
+    let mut spikes = Vec::new();
+    while sim.time() < duration {
+        spikes.extend(sim.step(|_, _| 0.0));
+    }
+    sim.unclamp(&released);
+    spikes
+}
+
+/// A synapse onto a trained neuron and the input it carried in a trial.
+#[derive(Debug, Clone)]
+pub(crate) struct TrainedSynapse {
+    /// Index in the simulation
+    pub(crate) index: usize,
+    /// Position of the post neuron among the trained neurons
+    pub(crate) output: usize,
+    /// Transmission delay in steps
+    delay: usize,
+    /// Steps of the trial at which input arrived
+    pub(crate) arrivals: Vec<usize>,
+}
+
+/// Input arriving at a set of trained neurons during one trial, in steps
+/// from the start of the trial.
+#[derive(Debug, Clone)]
+pub(crate) struct TrialInputs {
+    pub(crate) synapses: Vec<TrainedSynapse>,
+    pub(crate) start: Milliseconds,
+    pub(crate) dt: Milliseconds,
+}
+
+impl TrialInputs {
+    /// Start recording the synapses of `sim` that target `outputs`.
+    pub(crate) fn begin(sim: &Simulation, outputs: &[usize]) -> Self {
+        let dt = sim.config().dt;
+        let synapses = sim
+            .synapses()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, syn)| {
+                let output = outputs.iter().position(|&o| o == syn.post_neuron)?;
+                Some(TrainedSynapse {
+                    index,
+                    output,
+                    delay: ((syn.delay / dt).round() as usize).max(1),
+                    arrivals: Vec::new(),
+                })
+            })
+            .collect();
+        Self {
+            synapses,
+            start: sim.time(),
+            dt,
+        }
+    }
+
+    /// Step of the trial `step` belongs to.
+    pub(crate) fn step_index(&self, step: &StepView<'_>) -> usize {
+        ((step.time - self.start) / self.dt).round() as usize
+    }
+
+    /// Record the input scheduled by the spikes of `step`.
+    pub(crate) fn observe(&mut self, step: &StepView<'_>) {
+        let k = self.step_index(step);
+        for syn in &mut self.synapses {
+            let pre = step.synapses[syn.index].pre_neuron;
+            if step.fired.binary_search(&pre).is_ok() {
+                syn.arrivals.push(k + syn.delay);
+            }
+        }
+    }
+}
+
+impl Default for TrialInputs {
+    fn default() -> Self {
+        Self {
+            synapses: Vec::new(),
+            start: Milliseconds::ZERO,
+            dt: Milliseconds(1.0),
+        }
+    }
+}
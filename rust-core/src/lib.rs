@@ -8,39 +8,195 @@
 //! - Time-based neuron dynamics
 //! - Local state and learning (no backpropagation)
 
+/// Emit a `trace` event from this crate, e.g.
+/// `trace_event!(Info, "run finished", spikes = n)`; compiled out without
+/// the `tracing` feature.
+macro_rules! trace_event {
+    ($level:ident, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        if $crate::trace::enabled($crate::trace::Level::$level, module_path!()) {
+            $crate::trace::event(
+                $crate::trace::Level::$level,
+                module_path!(),
+                $message,
+                vec![$((stringify!($key), $crate::trace::Value::from($value))),*],
+            );
+        }
+        #[cfg(not(feature = "tracing"))]
+        if false {
+            $(let _ = &$value;)*
+        }
+    }};
+}
+
+/// Open a `trace` span from this crate, timed until the returned guard is
+/// dropped; compiled out without the `tracing` feature.
+macro_rules! trace_span {
+    ($level:ident, $name:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = if $crate::trace::enabled($crate::trace::Level::$level, module_path!()) {
+            $crate::trace::span(
+                $crate::trace::Level::$level,
+                module_path!(),
+                $name,
+                vec![$((stringify!($key), $crate::trace::Value::from($value))),*],
+            )
+        } else {
+            $crate::trace::Span::none()
+        };
+        #[cfg(not(feature = "tracing"))]
+        let span = {
+            if false {
+                $(let _ = &$value;)*
+            }
+            $crate::NoSpan
+        };
+        span
+    }};
+}
+
+/// Stand-in for a span guard without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+pub mod analysis;
+pub mod balanced;
+pub mod bursts;
+pub mod cancellation;
+pub mod compression;
+pub mod config;
+pub mod connectivity;
+pub mod conversion;
+pub mod convergence;
+pub mod cpg;
+#[cfg(feature = "mnist")]
+pub mod dataset;
+pub mod decoding;
+pub mod encoding;
+pub mod energy;
+pub mod events;
+pub mod experiment;
+pub mod feedforward;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "hdf5")]
+pub mod hdf5;
+pub mod inference;
+mod json;
+pub mod learning;
+pub mod lava;
+pub mod live;
+pub mod manifest;
+pub mod models;
+pub mod monitor;
+pub mod network;
+pub mod neuroml;
+pub mod neuromodulation;
 pub mod neuron;
+pub mod numpy;
+#[cfg(feature = "hdf5")]
+pub mod nwb;
+pub mod output;
+pub mod parquet;
+pub mod pipeline;
+#[cfg(feature = "plot")]
+pub mod plot;
+mod png;
+pub mod polychrony;
+pub mod population;
+pub mod probe;
+pub mod progress;
+pub mod quantization;
+pub mod readout;
+pub mod receptive_field;
+pub mod recorder;
+pub mod reinforcement;
+pub mod replay;
+pub mod results;
+pub mod resume;
+pub mod reservoir;
+pub mod rng;
+pub mod sequence;
+pub mod snapshot;
+pub mod som;
+#[cfg(feature = "hdf5")]
+pub mod sonata;
+pub mod spatial;
 pub mod spike;
+pub mod spike_codec;
+pub mod spike_io;
+pub mod spike_server;
+pub mod spike_trains;
 pub mod simulation;
 pub mod synapse;
+pub mod synfire;
+pub mod tempotron;
+pub mod topology;
+#[cfg(feature = "tracing")]
+pub mod trace;
+pub mod units;
+mod util;
 pub mod stdp;
+pub mod stopping;
+#[cfg(feature = "surrogate")]
+pub mod surrogate;
+pub mod sweep;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod weights;
+pub mod wta;
 
 use neuron::NeuronParams;
+use output::{OutputConfig, OutputFormat, RunOutput};
 use simulation::{Simulation, SimulationConfig};
 use stdp::STDPParams;
+use std::io;
+use std::path::Path;
+use units::Milliseconds;
 
 /// Run a minimal example simulation.
 ///
 /// This function is intended for quick validation and experimentation.
 /// It simulates a small population of neurons receiving constant input
-/// current and returns the emitted spike events.
+/// current and writes the emitted spike events and weight log as CSV to
+/// `../data/raw`, where the analysis scripts of the project expect them.
+/// Use `run_example_with` to write elsewhere.
+///
+/// Nothing is printed: with the `tracing` feature the summary, and any
+/// failure to write the results, are reported as `trace` events; install
+/// a subscriber to see them. Call `run_example_with` to handle errors.
 pub fn run_example() {
+    let mut output = OutputConfig::new(Path::new("..").join("data").join("raw"));
+    output.formats = vec![OutputFormat::Csv];
+    output.per_run = false;
+    if let Err(e) = run_example_with(&output) {
+        trace_event!(Error, "run_example failed", error = e.to_string());
+    }
+}
+
+/// Run the example simulation of `run_example`, writing its results as
+/// configured by `output`.
+pub fn run_example_with(output: &OutputConfig) -> io::Result<RunOutput> {
     let neuron_params = NeuronParams {
-        tau_m: 10.0,
+        tau_m: Milliseconds(10.0),
         v_rest: 0.0,
         v_thresh: 1.0,
         v_reset: 0.0,
     };
 
     let sim_config = SimulationConfig {
-        dt: 0.1,
-        t_max: 100.0,
+        dt: Milliseconds(0.1),
+        t_max: Milliseconds(100.0),
+        ..SimulationConfig::default()
     };
 
     let stdp_params = STDPParams {
         a_plus: 0.01,
         a_minus: 0.012,
-        tau_plus: 20.0,
-        tau_minus: 20.0,
+        tau_plus: Milliseconds(20.0),
+        tau_minus: Milliseconds(20.0),
         w_min: 0.0,
         w_max: 1.0,
     };
@@ -58,50 +214,20 @@ pub fn run_example() {
     });
 
 
-    use std::path::PathBuf;
-
-    // Resolve project root (neuromorphic-ai-paradigm/)
-    let project_root: PathBuf = std::env::current_dir()
-        .expect("Failed to get current directory")
-        .parent()
-        .expect("Failed to resolve project root")
-        .to_path_buf();
-
-    let csv_path = project_root
-        .join("data")
-        .join("raw")
-        .join("spikes.csv");
-
-    let weights_csv_path = project_root
-        .join("data")
-        .join("raw")
-        .join("weights.csv");
-
-    sim.write_spikes_to_csv(
-        &spikes,
-        csv_path
-            .to_str()
-            .expect("Failed to convert CSV path to string"),
-    );
+    let run = {
+        let _span = trace_span!(Info, "write_outputs");
+        sim.write_outputs(output, &spikes, &weights)?
+    };
 
-    sim.write_weights_to_csv(
-        &weights,
-        weights_csv_path
-            .to_str()
-            .expect("Failed to convert weights CSV path to string"),
+    trace_event!(
+        Info,
+        "simulation complete",
+        spikes = spikes.len(),
+        weight_updates = weights.len(),
     );
-
-    println!("Simulation complete. Emitted {} spikes.", spikes.len());
-
     for spike in spikes.iter().take(10) {
-        println!(
-            "Spike from neuron {} at time {:.2} ms",
-            spike.neuron_id, spike.time
-        );
+        trace_event!(Debug, "spike", neuron = spike.neuron_id, time_ms = spike.time);
     }
 
-    println!(
-        "Recorded {} synaptic weight updates.",
-        weights.len()
-    );
-}
\ No newline at end of file
+    Ok(run)
+}
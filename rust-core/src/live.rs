@@ -0,0 +1,96 @@
+//! live.rs
+//!
+//! Live metric snapshots during a run.
+//!
+//! Progress callbacks report how far a run has got, but a dashboard or
+//! logger watching training wants to see what the network is doing. A
+//! `MetricsStream` is a monitor that, at a fixed simulated-time interval,
+//! sends a `MetricSnapshot` with the recent population rate, the spike
+//! count and the mean weight over a standard channel. The receiving end
+//! can live on another thread and iterate over snapshots as they arrive.
+
+use crate::monitor::{reached, Monitor, StepView};
+use crate::units::Milliseconds;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Network activity summary sent by a `MetricsStream`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricSnapshot {
+    /// Simulation time of the snapshot
+    pub time: Milliseconds,
+    /// Spikes since the previous snapshot
+    pub window_spikes: usize,
+    /// Spikes since the stream was attached
+    pub total_spikes: usize,
+    /// Mean firing rate per neuron since the previous snapshot (Hz)
+    pub population_rate_hz: f64,
+    /// Mean synaptic weight
+    pub mean_weight: f64,
+}
+
+/// Monitor sending a `MetricSnapshot` every `interval` of simulated time.
+///
+/// Sending never blocks the simulation. Once the receiver is dropped,
+/// snapshots are discarded. The receiver's iterator ends when the stream is
+/// dropped, e.g. by `Simulation::clear_monitors` or dropping the
+/// simulation.
+#[derive(Debug)]
+pub struct MetricsStream {
+    sender: Sender<MetricSnapshot>,
+    interval: Milliseconds,
+    /// Time of the previous snapshot, or of the first recorded step
+    window_start: Option<Milliseconds>,
+    next_snapshot: Option<Milliseconds>,
+    window_spikes: usize,
+    total_spikes: usize,
+}
+
+impl MetricsStream {
+    /// Create a stream and the receiver for its snapshots.
+    pub fn new(interval: Milliseconds) -> (Self, Receiver<MetricSnapshot>) {
+        let (sender, receiver) = mpsc::channel();
+        let stream = Self {
+            sender,
+            interval,
+            window_start: None,
+            next_snapshot: None,
+            window_spikes: 0,
+            total_spikes: 0,
+        };
+        (stream, receiver)
+    }
+}
+
+impl Monitor for MetricsStream {
+    fn record(&mut self, step: &StepView<'_>) {
+        let start = *self.window_start.get_or_insert(step.time);
+        let next = *self.next_snapshot.get_or_insert(start + self.interval);
+        self.window_spikes += step.fired.len();
+        self.total_spikes += step.fired.len();
+        let time = step.end_time();
+        if !reached(time, next) {
+            return;
+        }
+        let elapsed = (time - start).as_secs() * step.neurons.len() as f64;
+        let mean_weight = if step.synapses.is_empty() {
+            0.0
+        } else {
+            step.synapses.iter().map(|s| s.weight).sum::<f64>() / step.synapses.len() as f64
+        };
+        let _ = self.sender.send(MetricSnapshot {
+            time,
+            window_spikes: self.window_spikes,
+            total_spikes: self.total_spikes,
+            population_rate_hz: if elapsed > 0.0 {
+                self.window_spikes as f64 / elapsed
+            } else {
+                0.0
+            },
+            mean_weight,
+        });
+        self.window_spikes = 0;
+        self.window_start = Some(time);
+        self.next_snapshot = Some(next + self.interval);
+    }
+}
@@ -1,3 +1,31 @@
-fn main() {
-    neuromorphic_core::run_example();
+use neuromorphic_core::output::{OutputConfig, OutputFormat};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    // NEUROMORPHIC_LOG selects the level of the records shown, INFO by default
+    #[cfg(feature = "tracing")]
+    {
+        use neuromorphic_core::trace::{set_global_subscriber, FmtSubscriber, Level};
+        let level = std::env::var("NEUROMORPHIC_LOG")
+            .ok()
+            .and_then(|name| Level::from_name(&name))
+            .unwrap_or(Level::Info);
+        set_global_subscriber(FmtSubscriber::new(level));
+    }
+
+    // Results go to ../data/raw, or to the directory given as argument
+    let dir = std::env::args().nth(1).unwrap_or_else(|| "../data/raw".to_string());
+    let mut output = OutputConfig::new(dir);
+    output.formats = vec![OutputFormat::Csv];
+    output.per_run = false;
+    match neuromorphic_core::run_example_with(&output) {
+        Ok(run) => {
+            println!("wrote results to {}", run.dir().display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
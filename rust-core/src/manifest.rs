@@ -0,0 +1,427 @@
+//! manifest.rs
+//!
+//! Reproducibility manifests.
+//!
+//! A figure in a paper is only as trustworthy as the ability to say which
+//! configuration produced it. A `RunManifest` records what is needed to
+//! rerun a simulation and to recognize its outputs: the crate version, the
+//! random seed, every simulation and STDP parameter, a hash of the network
+//! and, when available, the configuration file itself, together with the
+//! size and SHA-256 checksum of each output file. It is written as JSON:
+//!
+//! ```text
+//! {
+//!   "crate": "neuromorphic_core", "crate_version": "0.1.0",
+//!   "created": "2026-03-01T12:00:00.000000+00:00", "seed": 42,
+//!   "num_neurons": 110, "num_synapses": 1000,
+//!   "topology_sha256": "9f86d0...",
+//!   "parameters": {
+//!     "simulation": {"dt_ms": 0.1, "t_max_ms": 1000, "num_partitions": 1,
+//!                    "realtime_factor": null, "warmup_ms": 0,
+//!                    "plasticity_during_warmup": false,
+//!                    "synaptic_transmission": true, "quantization": null},
+//!     "stdp": {"a_plus": 0.01, "a_minus": 0.012, "tau_plus_ms": 20,
+//!              "tau_minus_ms": 20, "w_min": 0, "w_max": 1},
+//!     "plasticity_sets": [...]
+//!   },
+//!   "config": "seed = 42\n...",
+//!   "outputs": [{"path": "spikes.csv", "bytes": 10240, "sha256": "..."}]
+//! }
+//! ```
+//!
+//! The topology hash covers the neuron parameters, the plasticity sets and
+//! every synapse's endpoints, weight, delay and plasticity, so it must be
+//! taken before the run changes the weights. Output checksums match those
+//! of `sha256sum`, and output paths inside the manifest's directory are
+//! stored relative to it.
+
+use crate::compression;
+use crate::json::{write_number, write_string};
+use crate::simulation::Simulation;
+use crate::stdp::STDPParams;
+use crate::synapse::Plasticity;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size and checksum of an output file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputChecksum {
+    /// Path of the file
+    pub path: PathBuf,
+    /// File size in bytes
+    pub bytes: u64,
+    /// SHA-256 of the contents, as lower-case hex
+    pub sha256: String,
+}
+
+/// Everything needed to trace the results of a run back to its setup.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunManifest {
+    /// Version of this crate
+    pub crate_version: String,
+    /// Time the manifest was taken, as ISO 8601 in UTC
+    pub created: String,
+    /// Seed of the network builder and stimuli, if the run used one
+    pub seed: Option<u64>,
+    /// Number of neurons
+    pub num_neurons: usize,
+    /// Number of synapses
+    pub num_synapses: usize,
+    /// SHA-256 of the network before the run, see `topology_sha256`
+    pub topology_sha256: String,
+    /// Parameters as a JSON object
+    pub parameters: String,
+    /// Text of the configuration file the run was built from
+    pub config: Option<String>,
+    /// Files written by the run
+    pub outputs: Vec<OutputChecksum>,
+}
+
+impl RunManifest {
+    /// Manifest of a run of `sim`, taken before it starts.
+    pub fn new(sim: &Simulation, seed: Option<u64>) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            created: iso8601(SystemTime::now()),
+            seed,
+            num_neurons: sim.neurons().len(),
+            num_synapses: sim.synapses().len(),
+            topology_sha256: topology_sha256(sim),
+            parameters: parameters_json(sim),
+            config: None,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Record the text of the configuration file the run was built from.
+    pub fn with_config(mut self, text: &str) -> Self {
+        self.config = Some(text.to_string());
+        self
+    }
+
+    /// Checksum the finished output file at `path` and add it.
+    pub fn add_output<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let (bytes, sha256) = file_sha256(path)?;
+        self.outputs.push(OutputChecksum {
+            path: path.to_path_buf(),
+            bytes,
+            sha256,
+        });
+        Ok(())
+    }
+
+    /// Serialize to JSON, with output paths relative to `base` where
+    /// they lie inside it.
+    pub fn to_json(&self, base: &Path) -> String {
+        let mut out = String::from("{\"crate\":\"neuromorphic_core\",\"crate_version\":");
+        write_string(&mut out, &self.crate_version);
+        out.push_str(",\"created\":");
+        write_string(&mut out, &self.created);
+        out.push_str(",\"seed\":");
+        match self.seed {
+            Some(seed) => out.push_str(&seed.to_string()),
+            None => out.push_str("null"),
+        }
+        let _ = write!(
+            out,
+            ",\"num_neurons\":{},\"num_synapses\":{},\"topology_sha256\":",
+            self.num_neurons, self.num_synapses
+        );
+        write_string(&mut out, &self.topology_sha256);
+        out.push_str(",\"parameters\":");
+        out.push_str(&self.parameters);
+        out.push_str(",\"config\":");
+        match &self.config {
+            Some(text) => write_string(&mut out, text),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"outputs\":[");
+        for (i, output) in self.outputs.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let path = output.path.strip_prefix(base).unwrap_or(&output.path);
+            out.push_str("{\"path\":");
+            write_string(&mut out, &path.to_string_lossy());
+            let _ = write!(out, ",\"bytes\":{},\"sha256\":", output.bytes);
+            write_string(&mut out, &output.sha256);
+            out.push('}');
+        }
+        out.push_str("]}\n");
+        out
+    }
+
+    /// Write the manifest as JSON to `path`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+        compression::write(path, self.to_json(base))
+    }
+}
+
+/// SHA-256, as lower-case hex, of the neuron parameters, plasticity sets
+/// and synapses of `sim`.
+pub fn topology_sha256(sim: &Simulation) -> String {
+    let mut hash = Sha256::new();
+    hash.update(&(sim.neurons().len() as u64).to_le_bytes());
+    for neuron in sim.neurons() {
+        let p = &neuron.params;
+        for value in [p.tau_m.0, p.v_rest, p.v_thresh, p.v_reset] {
+            hash.update(&value.to_le_bytes());
+        }
+    }
+    hash.update(&(sim.plasticity_sets().len() as u64).to_le_bytes());
+    for set in sim.plasticity_sets() {
+        for value in stdp_values(set) {
+            hash.update(&value.to_le_bytes());
+        }
+    }
+    hash.update(&(sim.synapses().len() as u64).to_le_bytes());
+    for syn in sim.synapses() {
+        let plasticity = match syn.plasticity {
+            Plasticity::Global => 0,
+            Plasticity::Static => 1,
+            Plasticity::Set(k) => 2 + k as u64,
+        };
+        for value in [syn.pre_neuron as u64, syn.post_neuron as u64, plasticity] {
+            hash.update(&value.to_le_bytes());
+        }
+        hash.update(&syn.weight.to_le_bytes());
+        hash.update(&syn.delay.0.to_le_bytes());
+    }
+    hex(&hash.finish())
+}
+
+/// Size and SHA-256, as lower-case hex, of the file at `path`.
+pub fn file_sha256<P: AsRef<Path>>(path: P) -> io::Result<(u64, String)> {
+    let mut file = File::open(path)?;
+    let mut hash = Sha256::new();
+    let mut buffer = vec![0; 1 << 16];
+    let mut bytes = 0u64;
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hash.update(&buffer[..n]);
+        bytes += n as u64;
+    }
+    Ok((bytes, hex(&hash.finish())))
+}
+
+/// STDP parameters in declaration order.
+fn stdp_values(p: &STDPParams) -> [f64; 6] {
+    [
+        p.a_plus,
+        p.a_minus,
+        p.tau_plus.0,
+        p.tau_minus.0,
+        p.w_min,
+        p.w_max,
+    ]
+}
+
+/// Simulation and STDP parameters of `sim` as a JSON object.
+fn parameters_json(sim: &Simulation) -> String {
+    let c = sim.config();
+    let mut out = String::from("{\"simulation\":{\"dt_ms\":");
+    write_number(&mut out, c.dt.0);
+    out.push_str(",\"t_max_ms\":");
+    write_number(&mut out, c.t_max.0);
+    let _ = write!(out, ",\"num_partitions\":{}", c.num_partitions);
+    out.push_str(",\"realtime_factor\":");
+    match c.realtime_factor {
+        Some(factor) => write_number(&mut out, factor),
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"warmup_ms\":");
+    write_number(&mut out, c.warmup.0);
+    let _ = write!(
+        out,
+        ",\"plasticity_during_warmup\":{},\"synaptic_transmission\":{}",
+        c.plasticity_during_warmup, c.synaptic_transmission
+    );
+    out.push_str(",\"quantization\":");
+    match &c.quantization {
+        Some(q) => write_string(&mut out, &format!("{q:?}")),
+        None => out.push_str("null"),
+    }
+    out.push_str("},\"stdp\":");
+    stdp_json(&mut out, sim.stdp_params());
+    out.push_str(",\"plasticity_sets\":[");
+    for (i, set) in sim.plasticity_sets().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        stdp_json(&mut out, set);
+    }
+    out.push_str("]}");
+    out
+}
+
+/// Write STDP parameters as a JSON object.
+fn stdp_json(out: &mut String, p: &STDPParams) {
+    let names = ["a_plus", "a_minus", "tau_plus_ms", "tau_minus_ms", "w_min", "w_max"];
+    out.push('{');
+    for (i, (name, value)) in names.iter().zip(stdp_values(p)).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(out, name);
+        out.push(':');
+        write_number(out, value);
+    }
+    out.push('}');
+}
+
+/// Lower-case hex of `bytes`.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+/// `time` as an ISO 8601 date and time in UTC.
+pub(crate) fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    // Civil date from days since 1970-01-01, after Howard Hinnant
+    let z = (seconds / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}+00:00",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+/// Round constants of SHA-256.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Streaming SHA-256 (FIPS 180-4).
+struct Sha256 {
+    state: [u32; 8],
+    /// Bytes not yet forming a full block
+    pending: Vec<u8>,
+    /// Total message length in bytes
+    length: u64,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            pending: Vec::with_capacity(64),
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+        if !self.pending.is_empty() {
+            let take = bytes.len().min(64 - self.pending.len());
+            self.pending.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            if self.pending.len() < 64 {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.compress(&block);
+        }
+        let mut blocks = bytes.chunks_exact(64);
+        for block in &mut blocks {
+            self.compress(block);
+        }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        let bits = self.length.wrapping_mul(8);
+        let mut tail = std::mem::take(&mut self.pending);
+        tail.push(0x80);
+        while tail.len() % 64 != 56 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&bits.to_be_bytes());
+        for block in tail.chunks_exact(64) {
+            self.compress(block);
+        }
+        let mut digest = [0; 32];
+        for (out, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
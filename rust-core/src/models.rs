@@ -0,0 +1,358 @@
+//! models.rs
+//!
+//! Model zoo of ready-to-run preset networks.
+//!
+//! Building a network that does something interesting means choosing
+//! neuron parameters, wiring, weights and an input that drives it in the
+//! right regime, which is a lot to get right before the first experiment.
+//! Each preset here returns a configured `Simulation` together with a
+//! recommended `Stimulus`, so `Model::run` produces meaningful activity
+//! straight away:
+//!
+//! - `balanced_random`: a Brunel-style E/I network in the asynchronous
+//!   irregular regime, driven by heterogeneous constant currents;
+//! - `wta_digit_classifier`: 35 input pixels projecting through STDP
+//!   synapses onto a 10-neuron winner-take-all layer, shown Poisson-coded
+//!   5x7 digit bitmaps;
+//! - `synfire_chain`: pools of neurons with all-to-all feedforward wiring
+//!   between consecutive pools, started by a synchronous volley;
+//! - `reservoir`: a liquid state machine driven by Poisson inputs whose
+//!   rates change every 100 ms.
+//!
+//! Presets use dimensionless potentials (rest and reset 0, threshold 1)
+//! and a 0.1 ms time step. Their fields are public, so any part can be
+//! adjusted before running.
+
+use crate::balanced::{build_balanced, BalancedParams};
+use crate::encoding::sort_spikes;
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::reservoir::{build_reservoir, ReservoirParams};
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::spike_trains::poisson;
+use crate::stdp::STDPParams;
+use crate::synapse::Synapse;
+use crate::synfire::{build_synfire, SynfireParams};
+use crate::units::Milliseconds;
+use crate::wta::{add_wta, Competition, LateralInhibition, WtaParams};
+use std::ops::Range;
+
+/// Names of the presets accepted by `by_name`.
+pub const MODEL_NAMES: [&str; 4] = ["balanced", "wta-digits", "synfire", "reservoir"];
+
+/// Width of the digit bitmaps of `wta_digit_classifier`.
+pub const DIGIT_WIDTH: usize = 5;
+
+/// Height of the digit bitmaps of `wta_digit_classifier`.
+pub const DIGIT_HEIGHT: usize = 7;
+
+/// 5x7 bitmaps of the digits 0 to 9, one row per string.
+#[rustfmt::skip]
+const DIGITS: [[&str; DIGIT_HEIGHT]; 10] = [
+    [".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###."],
+    ["..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###."],
+    [".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####"],
+    ["#####", "...#.", "..#..", "...#.", "....#", "#...#", ".###."],
+    ["...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#."],
+    ["#####", "#....", "####.", "....#", "....#", "#...#", ".###."],
+    ["..##.", ".#...", "#....", "####.", "#...#", "#...#", ".###."],
+    ["#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#..."],
+    [".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###."],
+    [".###.", "#...#", "#...#", ".####", "....#", "...#.", ".##.."],
+];
+
+/// One labeled input presentation of a stimulus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Presentation {
+    /// Class shown
+    pub label: usize,
+    /// Start of the presentation
+    pub start: Milliseconds,
+    /// End of the presentation, exclusive
+    pub end: Milliseconds,
+}
+
+/// Recommended input of a preset model.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stimulus {
+    /// Spikes to inject, sorted by time
+    pub spikes: Vec<Spike>,
+    /// Constant input current of each neuron; missing entries are 0
+    pub currents: Vec<f64>,
+    /// Labeled presentations, for presets that classify their input
+    pub presentations: Vec<Presentation>,
+}
+
+impl Stimulus {
+    /// Constant input current of neuron `i`.
+    pub fn current(&self, i: usize) -> f64 {
+        self.currents.get(i).copied().unwrap_or(0.0)
+    }
+}
+
+/// A preset network with its recommended stimulus.
+pub struct Model {
+    /// Preset name, one of `MODEL_NAMES`
+    pub name: &'static str,
+    /// The configured simulation
+    pub simulation: Simulation,
+    /// Recommended input
+    pub stimulus: Stimulus,
+}
+
+impl Model {
+    /// Index range of the population `name`, see `Simulation::population`.
+    pub fn population(&self, name: &str) -> Option<Range<usize>> {
+        self.simulation.population(name)
+    }
+
+    /// Inject the stimulus spikes, run until `t_max` with the stimulus
+    /// currents and return the spikes.
+    ///
+    /// No weight log is kept, since the presets have up to 100 000
+    /// synapses; learned weights are in `simulation.synapses()` afterwards.
+    pub fn run(&mut self) -> Vec<Spike> {
+        self.simulation.inject_spikes(&self.stimulus.spikes);
+        let stimulus = &self.stimulus;
+        let t_max = self.simulation.config().t_max;
+        let mut spikes = Vec::new();
+        while self.simulation.time() < t_max {
+            spikes.extend(self.simulation.step(|i, _| stimulus.current(i)));
+        }
+        spikes
+    }
+}
+
+/// The preset `name` (see `MODEL_NAMES`) built with `seed`.
+pub fn by_name(name: &str, seed: u64) -> Option<Model> {
+    match name {
+        "balanced" => Some(balanced_random(seed)),
+        "wta-digits" => Some(wta_digit_classifier(seed)),
+        "synfire" => Some(synfire_chain(seed)),
+        "reservoir" => Some(reservoir(seed)),
+        _ => None,
+    }
+}
+
+/// Neuron parameters shared by the presets.
+fn preset_neuron() -> NeuronParams {
+    NeuronParams {
+        tau_m: Milliseconds(20.0),
+        v_rest: 0.0,
+        v_thresh: 1.0,
+        v_reset: 0.0,
+    }
+}
+
+/// Simulation configuration of the presets, running for `t_max`.
+fn preset_config(t_max: Milliseconds) -> SimulationConfig {
+    SimulationConfig {
+        dt: Milliseconds(0.1),
+        t_max,
+        synaptic_transmission: true,
+        ..SimulationConfig::default()
+    }
+}
+
+/// Pair-based STDP of the plastic presets, slow and depression-dominated
+/// so that no single neuron takes over every input.
+fn preset_stdp() -> STDPParams {
+    STDPParams {
+        a_plus: 0.002,
+        a_minus: 0.0025,
+        tau_plus: Milliseconds(20.0),
+        tau_minus: Milliseconds(20.0),
+        w_min: 0.0,
+        w_max: 0.3,
+    }
+}
+
+/// Brunel-style balanced network of 800 excitatory and 200 inhibitory
+/// neurons, run for 1 s.
+///
+/// Every neuron receives a constant current drawn uniformly from
+/// `[1.0, 1.3]`, just above threshold, so the network starts out
+/// desynchronized and recurrent inhibition keeps it asynchronous and
+/// irregular.
+pub fn balanced_random(seed: u64) -> Model {
+    let params = BalancedParams {
+        seed,
+        ..BalancedParams::default()
+    };
+    let network = build_balanced(
+        &params,
+        preset_neuron(),
+        preset_config(Milliseconds(1000.0)),
+        preset_stdp(),
+    );
+    let mut rng = Rng::new(seed ^ 0x5354_494d);
+    let n = params.num_excitatory + params.num_inhibitory;
+    let currents = (0..n).map(|_| rng.uniform(1.0, 1.3)).collect();
+    Model {
+        name: "balanced",
+        simulation: network.simulation,
+        stimulus: Stimulus {
+            currents,
+            ..Stimulus::default()
+        },
+    }
+}
+
+/// The 5x7 bitmap of `digit` as pixel intensities in `{0, 1}`, row by
+/// row.
+///
+/// # Panics
+/// Panics if `digit` is not below 10.
+pub fn digit_bitmap(digit: usize) -> Vec<f64> {
+    DIGITS[digit]
+        .iter()
+        .flat_map(|row| row.bytes().map(|b| if b == b'#' { 1.0 } else { 0.0 }))
+        .collect()
+}
+
+/// Two-layer digit classifier: 35 input neurons, one per pixel of a 5x7
+/// bitmap, fully connected through STDP synapses to a hard winner-take-all
+/// layer of 10 neurons.
+///
+/// Populations are `input` and `output`. Input weights start uniform in
+/// `[0, 0.2]` and are bounded by 0.3. The stimulus shows the ten digits in
+/// shuffled order for 5 epochs, each for 100 ms as 60 Hz Poisson trains on
+/// lit pixels followed by 50 ms of silence; `Stimulus::presentations`
+/// records the schedule for assigning labels to output neurons afterwards.
+/// Without homeostasis some outputs end up sharing digits.
+pub fn wta_digit_classifier(seed: u64) -> Model {
+    const EPOCHS: usize = 5;
+    let (shown, rest) = (Milliseconds(100.0), Milliseconds(50.0));
+    let mut rng = Rng::new(seed);
+
+    let mut builder = NetworkBuilder::new();
+    let input = builder.add_population("input", DIGIT_WIDTH * DIGIT_HEIGHT, preset_neuron());
+    let wta = WtaParams {
+        size: 10,
+        competition: Competition::Hard,
+        inhibition: LateralInhibition::Direct,
+    };
+    let output = add_wta(&mut builder, "output", &wta, preset_neuron()).excitatory;
+    let feedforward: Vec<Synapse> = input
+        .clone()
+        .flat_map(|i| output.clone().map(move |j| (i, j)))
+        .map(|(i, j)| Synapse::new(i, j, rng.uniform(0.0, 0.2)))
+        .collect();
+    builder.add_synapses(feedforward);
+
+    let mut stimulus = Stimulus::default();
+    let mut start = Milliseconds::ZERO;
+    for _ in 0..EPOCHS {
+        let mut order: Vec<usize> = (0..10).collect();
+        rng.shuffle(&mut order);
+        for digit in order {
+            for (pixel, value) in digit_bitmap(digit).into_iter().enumerate() {
+                if value > 0.0 {
+                    let neuron = input.start + pixel;
+                    stimulus
+                        .spikes
+                        .extend(poisson(neuron, 60.0, start, shown, &mut rng));
+                }
+            }
+            stimulus.presentations.push(Presentation {
+                label: digit,
+                start,
+                end: start + shown,
+            });
+            start = start + shown + rest;
+        }
+    }
+    sort_spikes(&mut stimulus.spikes);
+
+    Model {
+        name: "wta-digits",
+        simulation: Simulation::from_network(builder.build(), preset_config(start), preset_stdp()),
+        stimulus,
+    }
+}
+
+/// Synfire chain of 10 pools of 100 neurons, run for 200 ms.
+///
+/// Every neuron of a pool connects to every neuron of the next with a
+/// static synapse of weight 0.02 and a 5 ms delay, so a volley of more
+/// than half a pool fires the next pool about 5 ms later. Pools are
+/// registered as populations `pool0` to `pool9` by `build_synfire`. The
+/// stimulus fires the whole of `pool0` at 10 ms with 1 ms of Gaussian
+/// jitter.
+pub fn synfire_chain(seed: u64) -> Model {
+    let mut rng = Rng::new(seed);
+    let chain = build_synfire(
+        &SynfireParams::default(),
+        preset_neuron(),
+        preset_config(Milliseconds(200.0)),
+        preset_stdp(),
+    );
+    let pool_size = chain.pools[0].len();
+    let spikes = chain.packet(pool_size, Milliseconds(1.0), Milliseconds(10.0), &mut rng);
+
+    Model {
+        name: "synfire",
+        simulation: chain.simulation,
+        stimulus: Stimulus {
+            spikes,
+            ..Stimulus::default()
+        },
+    }
+}
+
+/// Liquid state machine of 135 neurons with 4 input neurons, run for 2 s.
+///
+/// Uses `ReservoirParams::default` apart from the inputs. The stimulus
+/// drives each input with a Poisson train whose rate is redrawn uniformly
+/// from `[0, 80]` Hz every 100 ms; each segment is recorded as a
+/// presentation labeled with the index of the most active input, a ready
+/// target for a readout trained on `Reservoir::states`.
+pub fn reservoir(seed: u64) -> Model {
+    const INPUTS: usize = 4;
+    let segment = Milliseconds(100.0);
+    let t_max = Milliseconds(2000.0);
+    let params = ReservoirParams {
+        num_inputs: INPUTS,
+        seed,
+        ..ReservoirParams::default()
+    };
+    let network = build_reservoir(
+        &params,
+        preset_neuron(),
+        preset_config(t_max),
+        preset_stdp(),
+    );
+
+    let mut rng = Rng::new(seed ^ 0x5354_494d);
+    let mut stimulus = Stimulus::default();
+    let mut start = Milliseconds::ZERO;
+    while start < t_max {
+        let rates: Vec<f64> = (0..INPUTS).map(|_| rng.uniform(0.0, 80.0)).collect();
+        for (k, &rate) in rates.iter().enumerate() {
+            let neuron = network.inputs.start + k;
+            stimulus
+                .spikes
+                .extend(poisson(neuron, rate, start, segment, &mut rng));
+        }
+        let label = (0..INPUTS)
+            .max_by(|&a, &b| rates[a].total_cmp(&rates[b]))
+            .unwrap_or(0);
+        stimulus.presentations.push(Presentation {
+            label,
+            start,
+            end: start + segment,
+        });
+        start += segment;
+    }
+    sort_spikes(&mut stimulus.spikes);
+
+    Model {
+        name: "reservoir",
+        simulation: network.simulation,
+        stimulus,
+    }
+}
@@ -0,0 +1,526 @@
+//! monitor.rs
+//!
+//! Pluggable recording of simulation state.
+//!
+//! `run` always returns every spike and a weight log with one entry per
+//! synapse per spike, which is either more or less than an experiment
+//! needs. Monitors let users choose what is recorded and at what
+//! granularity: each attached `Monitor` is shown the state of the network
+//! after every step and keeps whatever it wants. The monitors provided
+//! here are cloneable handles around shared state, like `VoltageProbe`:
+//! attach one clone with `Simulation::add_monitor` and read the results
+//! from another after the run.
+//!
+//! Monitors that select neurons or synapses by index are told when
+//! `Simulation::remove_neuron` or `remove_synapse` shifts the indices, so
+//! they keep following the same elements; a removed element reads as NaN
+//! or stops being recorded.
+
+use crate::neuron::Neuron;
+use crate::spike::Spike;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use crate::util::{lock, mean_std};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Network state after one simulation step, as seen by monitors.
+pub struct StepView<'a> {
+    /// Start time of the step; spikes of this step carry this time
+    pub time: Milliseconds,
+    /// Step length
+    pub dt: Milliseconds,
+    /// Neurons that fired during the step, in ascending order
+    pub fired: &'a [usize],
+    /// Neuron states at the end of the step
+    pub neurons: &'a [Neuron],
+    /// Synapses after this step's plasticity updates
+    pub synapses: &'a [Synapse],
+}
+
+impl StepView<'_> {
+    /// End time of the step.
+    pub fn end_time(&self) -> Milliseconds {
+        self.time + self.dt
+    }
+}
+
+/// Observer attached to a `Simulation`, called after every step outside
+/// the warmup phase.
+pub trait Monitor: Send {
+    /// Record whatever is of interest from `step`.
+    fn record(&mut self, step: &StepView<'_>);
+
+    /// Neuron `index` was removed and every higher neuron shifted down by
+    /// one. Monitors that select neurons by index renumber them here.
+    fn neuron_removed(&mut self, _index: usize) {}
+
+    /// Synapse `index` was removed and every higher synapse shifted down
+    /// by one. Monitors that select synapses by index renumber them here.
+    fn synapse_removed(&mut self, _index: usize) {}
+
+    /// Largest neuron index the monitor selects, checked when it is
+    /// attached.
+    fn max_neuron(&self) -> Option<usize> {
+        None
+    }
+
+    /// Largest synapse index the monitor selects, checked when it is
+    /// attached.
+    fn max_synapse(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Follow the removal of element `index` in `selected`: the removed element
+/// becomes `None` and higher indices shift down by one.
+pub(crate) fn shift_selected(selected: &mut [Option<usize>], index: usize) {
+    for slot in selected.iter_mut() {
+        *slot = match *slot {
+            Some(i) if i == index => None,
+            Some(i) if i > index => Some(i - 1),
+            other => other,
+        };
+    }
+}
+
+/// Whether `time` has reached `scheduled`, tolerating accumulated rounding
+/// in step times so periodic recording stays on schedule.
+pub(crate) fn reached(time: Milliseconds, scheduled: Milliseconds) -> bool {
+    time.0 >= scheduled.0 - 1e-9
+}
+
+/// Whether a sample is due at `time` under the schedule `next`, which is
+/// advanced by `interval` if so. `None` means the first sample is due.
+pub(crate) fn sample_due(
+    next: &mut Option<Milliseconds>,
+    time: Milliseconds,
+    interval: Milliseconds,
+) -> bool {
+    if next.is_some_and(|next| !reached(time, next)) {
+        return false;
+    }
+    *next = Some(next.unwrap_or(time) + interval);
+    true
+}
+
+/// Records spikes, optionally restricted to selected neurons.
+#[derive(Debug, Clone, Default)]
+pub struct SpikeMonitor {
+    filter: Option<Vec<usize>>,
+    spikes: Arc<Mutex<Vec<Spike>>>,
+}
+
+impl SpikeMonitor {
+    /// Record the spikes of every neuron.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record only the spikes of `neurons`.
+    ///
+    /// Spikes carry the index a neuron had when it fired, which changes if
+    /// a neuron below it is removed.
+    pub fn of_neurons(mut neurons: Vec<usize>) -> Self {
+        neurons.sort_unstable();
+        neurons.dedup();
+        Self {
+            filter: Some(neurons),
+            ..Self::default()
+        }
+    }
+
+    /// Recorded spikes in time order.
+    pub fn spikes(&self) -> Vec<Spike> {
+        lock(&self.spikes).clone()
+    }
+
+    /// Number of recorded spikes.
+    pub fn len(&self) -> usize {
+        lock(&self.spikes).len()
+    }
+
+    /// Whether no spike has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard recorded spikes.
+    pub fn clear(&self) {
+        lock(&self.spikes).clear();
+    }
+}
+
+impl Monitor for SpikeMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut spikes = lock(&self.spikes);
+        for &i in step.fired {
+            if self
+                .filter
+                .as_ref()
+                .is_none_or(|f| f.binary_search(&i).is_ok())
+            {
+                spikes.push(Spike::new(i, step.time));
+            }
+        }
+    }
+
+    fn neuron_removed(&mut self, index: usize) {
+        if let Some(filter) = self.filter.as_mut() {
+            filter.retain(|&i| i != index);
+            for i in filter.iter_mut() {
+                if *i > index {
+                    *i -= 1;
+                }
+            }
+        }
+    }
+
+    fn max_neuron(&self) -> Option<usize> {
+        self.filter.as_ref().and_then(|f| f.last().copied())
+    }
+}
+
+/// Samples synaptic weights at a fixed simulated-time interval.
+#[derive(Debug, Clone)]
+pub struct WeightMonitor {
+    state: Arc<Mutex<WeightState>>,
+}
+
+/// Schedule and samples shared by all clones of a `WeightMonitor`.
+#[derive(Debug)]
+struct WeightState {
+    /// Current index of each selected synapse; `None` once removed
+    synapses: Option<Vec<Option<usize>>>,
+    interval: Milliseconds,
+    next_sample: Option<Milliseconds>,
+    samples: Vec<(Milliseconds, Vec<f64>)>,
+}
+
+impl WeightMonitor {
+    /// Sample every synapse every `interval`.
+    pub fn new(interval: Milliseconds) -> Self {
+        Self::with_synapses(None, interval)
+    }
+
+    /// Sample the synapses with the given indices every `interval`.
+    pub fn of_synapses(synapses: Vec<usize>, interval: Milliseconds) -> Self {
+        Self::with_synapses(Some(synapses.into_iter().map(Some).collect()), interval)
+    }
+
+    fn with_synapses(synapses: Option<Vec<Option<usize>>>, interval: Milliseconds) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(WeightState {
+                synapses,
+                interval,
+                next_sample: None,
+                samples: Vec::new(),
+            })),
+        }
+    }
+
+    /// Recorded samples as `(time, weights)`. Weights are in synapse order,
+    /// or in the order given to `of_synapses`; synapses that no longer
+    /// exist read as NaN.
+    pub fn samples(&self) -> Vec<(Milliseconds, Vec<f64>)> {
+        lock(&self.state).samples.clone()
+    }
+
+    /// Weight time series of the `k`-th monitored synapse.
+    pub fn trace(&self, k: usize) -> Vec<f64> {
+        lock(&self.state)
+            .samples
+            .iter()
+            .map(|(_, w)| w.get(k).copied().unwrap_or(f64::NAN))
+            .collect()
+    }
+
+    /// Discard recorded samples and restart the sampling schedule.
+    pub fn clear(&self) {
+        let mut state = lock(&self.state);
+        state.samples.clear();
+        state.next_sample = None;
+    }
+}
+
+impl Monitor for WeightMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = lock(&self.state);
+        let time = step.end_time();
+        let interval = state.interval;
+        if !sample_due(&mut state.next_sample, time, interval) {
+            return;
+        }
+        let weights = match &state.synapses {
+            Some(indices) => indices
+                .iter()
+                .map(|k| {
+                    k.and_then(|k| step.synapses.get(k))
+                        .map_or(f64::NAN, |s| s.weight)
+                })
+                .collect(),
+            None => step.synapses.iter().map(|s| s.weight).collect(),
+        };
+        state.samples.push((time, weights));
+    }
+
+    fn synapse_removed(&mut self, index: usize) {
+        if let Some(synapses) = lock(&self.state).synapses.as_mut() {
+            shift_selected(synapses, index);
+        }
+    }
+
+    fn max_synapse(&self) -> Option<usize> {
+        let state = lock(&self.state);
+        state.synapses.as_ref()?.iter().flatten().copied().max()
+    }
+}
+
+/// Summary of the weight distribution at one point in time.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightStats {
+    /// Sample time
+    pub time: Milliseconds,
+    /// Mean weight
+    pub mean: f64,
+    /// Population standard deviation of the weights
+    pub std: f64,
+    /// Smallest weight
+    pub min: f64,
+    /// Largest weight
+    pub max: f64,
+    /// Counts in equal-width bins spanning `[w_min, w_max]`; the top bin
+    /// includes `w_max`
+    pub histogram: Vec<usize>,
+    /// Fraction of weights within 1e-9 of `w_min`
+    pub fraction_at_min: f64,
+    /// Fraction of weights within 1e-9 of `w_max`
+    pub fraction_at_max: f64,
+}
+
+impl WeightStats {
+    /// Summarize `weights` against the bounds `[w_min, w_max]` with
+    /// `bins` histogram bins. Weights outside the bounds are left out of
+    /// the histogram.
+    pub fn compute(
+        time: Milliseconds,
+        weights: &[f64],
+        w_min: f64,
+        w_max: f64,
+        bins: usize,
+    ) -> Self {
+        let (mean, std) = mean_std(weights);
+        let n = weights.len().max(1) as f64;
+        let at = |bound: f64| {
+            weights
+                .iter()
+                .filter(|&&w| (w - bound).abs() <= 1e-9)
+                .count() as f64
+                / n
+        };
+        let mut histogram = vec![0; bins];
+        let width = (w_max - w_min) / bins as f64;
+        for &w in weights {
+            if bins > 0 && width > 0.0 && (w_min..=w_max).contains(&w) {
+                let k = (((w - w_min) / width) as usize).min(bins - 1);
+                histogram[k] += 1;
+            }
+        }
+        Self {
+            time,
+            mean,
+            std,
+            min: weights.iter().copied().fold(f64::INFINITY, f64::min),
+            max: weights.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            histogram,
+            fraction_at_min: at(w_min),
+            fraction_at_max: at(w_max),
+        }
+    }
+}
+
+/// Tracks summary statistics of all weights at a fixed simulated-time
+/// interval, without keeping the weights themselves.
+#[derive(Debug, Clone)]
+pub struct WeightStatsMonitor {
+    state: Arc<Mutex<WeightStatsState>>,
+}
+
+/// Settings and samples shared by all clones of a `WeightStatsMonitor`.
+#[derive(Debug)]
+struct WeightStatsState {
+    w_min: f64,
+    w_max: f64,
+    bins: usize,
+    interval: Milliseconds,
+    next_sample: Option<Milliseconds>,
+    samples: Vec<WeightStats>,
+}
+
+impl WeightStatsMonitor {
+    /// Summarize the weights every `interval`, with a histogram of `bins`
+    /// bins over `[w_min, w_max]`, usually the STDP weight bounds.
+    pub fn new(w_min: f64, w_max: f64, bins: usize, interval: Milliseconds) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(WeightStatsState {
+                w_min,
+                w_max,
+                bins,
+                interval,
+                next_sample: None,
+                samples: Vec::new(),
+            })),
+        }
+    }
+
+    /// Recorded statistics in time order.
+    pub fn samples(&self) -> Vec<WeightStats> {
+        lock(&self.state).samples.clone()
+    }
+
+    /// Discard recorded statistics and restart the sampling schedule.
+    pub fn clear(&self) {
+        let mut state = lock(&self.state);
+        state.samples.clear();
+        state.next_sample = None;
+    }
+}
+
+impl Monitor for WeightStatsMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = lock(&self.state);
+        let time = step.end_time();
+        let interval = state.interval;
+        if !sample_due(&mut state.next_sample, time, interval) {
+            return;
+        }
+        let weights: Vec<f64> = step.synapses.iter().map(|s| s.weight).collect();
+        let stats = WeightStats::compute(time, &weights, state.w_min, state.w_max, state.bins);
+        state.samples.push(stats);
+    }
+}
+
+/// Mean firing rate of neuron populations in consecutive time bins.
+#[derive(Debug, Clone)]
+pub struct PopulationRateMonitor {
+    state: Arc<Mutex<RateState>>,
+}
+
+/// Bins and counts shared by all clones of a `PopulationRateMonitor`.
+#[derive(Debug)]
+struct RateState {
+    populations: Vec<Range<usize>>,
+    bin: Milliseconds,
+    bin_start: Option<Milliseconds>,
+    last_end: Milliseconds,
+    counts: Vec<usize>,
+    rates: Vec<(Milliseconds, Vec<f64>)>,
+}
+
+impl RateState {
+    /// Rates (Hz) of the current bin over `duration`.
+    fn current_rates(&self, duration: Milliseconds) -> Vec<f64> {
+        self.populations
+            .iter()
+            .zip(&self.counts)
+            .map(|(p, &c)| {
+                let secs = duration.as_secs() * p.len() as f64;
+                if secs > 0.0 {
+                    c as f64 / secs
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+impl PopulationRateMonitor {
+    /// Track the mean rate of each population in bins of length `bin`.
+    ///
+    /// A spike counts towards the bin containing the start of its step, so
+    /// bins shorter than the simulation step are mostly empty.
+    ///
+    /// # Panics
+    /// Panics if `bin` is not positive.
+    pub fn new(populations: Vec<Range<usize>>, bin: Milliseconds) -> Self {
+        assert!(bin.0 > 0.0, "Rate bins must be longer than zero, got {bin:?}");
+        let counts = vec![0; populations.len()];
+        Self {
+            state: Arc::new(Mutex::new(RateState {
+                populations,
+                bin,
+                bin_start: None,
+                last_end: Milliseconds::ZERO,
+                counts,
+                rates: Vec::new(),
+            })),
+        }
+    }
+
+    /// Mean rate (Hz) of each population per bin, as `(bin_start, rates)`.
+    ///
+    /// The last entry covers the bin still in progress, normalized by the
+    /// time simulated in it so far.
+    pub fn rates(&self) -> Vec<(Milliseconds, Vec<f64>)> {
+        let state = lock(&self.state);
+        let mut rates = state.rates.clone();
+        if let Some(start) = state.bin_start {
+            rates.push((start, state.current_rates(state.last_end - start)));
+        }
+        rates
+    }
+
+    /// Discard recorded bins.
+    pub fn clear(&self) {
+        let mut state = lock(&self.state);
+        state.rates.clear();
+        state.bin_start = None;
+        state.counts.iter_mut().for_each(|c| *c = 0);
+    }
+}
+
+impl Monitor for PopulationRateMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = lock(&self.state);
+        let mut start = *state.bin_start.get_or_insert(step.time);
+        // Close every bin that ended before this step, including empty
+        // ones after a gap or when bins are shorter than a step
+        while reached(step.time, start + state.bin) {
+            let rates = state.current_rates(state.bin);
+            state.rates.push((start, rates));
+            state.counts.iter_mut().for_each(|c| *c = 0);
+            start += state.bin;
+            state.bin_start = Some(start);
+        }
+        let RateState {
+            populations,
+            counts,
+            ..
+        } = &mut *state;
+        for &i in step.fired {
+            for (p, c) in populations.iter().zip(counts.iter_mut()) {
+                if p.contains(&i) {
+                    *c += 1;
+                }
+            }
+        }
+        state.last_end = step.end_time();
+    }
+
+    fn neuron_removed(&mut self, index: usize) {
+        for p in lock(&self.state).populations.iter_mut() {
+            if p.start > index {
+                *p = p.start - 1..p.end - 1;
+            } else if p.contains(&index) {
+                p.end -= 1;
+            }
+        }
+    }
+
+    fn max_neuron(&self) -> Option<usize> {
+        let state = lock(&self.state);
+        state.populations.iter().filter(|p| !p.is_empty()).map(|p| p.end - 1).max()
+    }
+}
@@ -0,0 +1,620 @@
+//! network.rs
+//!
+//! Network topology construction.
+//!
+//! A `NetworkBuilder` collects neurons, named populations, and explicit
+//! synaptic connections, and produces a `Network` that a `Simulation` is
+//! built from. This decouples the wiring of a model from the simulation
+//! engine, so arbitrary sparse or structured topologies can be expressed
+//! instead of a fixed all-to-all graph. Complete networks can in turn be
+//! composed as regions of a larger model and linked with projections.
+
+use crate::connectivity::SheetShape;
+use crate::neuron::{Neuron, NeuronParams};
+use crate::population::{Connector, Population, Projection};
+use crate::rng::Rng;
+use crate::spatial::{
+    difference_of_gaussians, distance_dependent, grid_neighbors, grid_positions, Boundary,
+    DelayedConnection, DistanceRule, DogKernel, Position,
+};
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use crate::util::invalid_input;
+use crate::weights::WeightInit;
+use std::io;
+use std::ops::Range;
+
+/// A named, contiguous range of neuron indices.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedPopulation {
+    /// Population name
+    pub name: String,
+    /// Neuron indices belonging to the population
+    pub range: Range<usize>,
+}
+
+/// A named, arbitrary set of neuron indices.
+///
+/// Unlike populations, groups need not be contiguous and a neuron may
+/// belong to several groups.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NeuronGroup {
+    /// Group label
+    pub name: String,
+    /// Member neuron indices, sorted and without duplicates
+    pub neurons: Vec<usize>,
+}
+
+/// A fully specified network: neurons, synapses, and population labels.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Network {
+    pub(crate) neurons: Vec<Neuron>,
+    pub(crate) synapses: Vec<Synapse>,
+    pub(crate) populations: Vec<NamedPopulation>,
+    pub(crate) plasticity_sets: Vec<STDPParams>,
+    pub(crate) positions: Vec<Option<Position>>,
+    pub(crate) groups: Vec<NeuronGroup>,
+}
+
+impl Network {
+    /// Build a network from population and projection descriptions.
+    ///
+    /// Fails with `InvalidInput` if a projection cannot be wired; see
+    /// `NetworkBuilder::project`.
+    pub fn from_populations(
+        populations: &[Population],
+        projections: &[Projection],
+    ) -> io::Result<Self> {
+        let mut builder = NetworkBuilder::new();
+        for population in populations {
+            builder.add(population);
+        }
+        for projection in projections {
+            builder.project(projection)?;
+        }
+        Ok(builder.build())
+    }
+
+    /// Neurons in the network, indexed by neuron id.
+    pub fn neurons(&self) -> &[Neuron] {
+        &self.neurons
+    }
+
+    /// Synapses in the network.
+    pub fn synapses(&self) -> &[Synapse] {
+        &self.synapses
+    }
+
+    /// Named populations, in creation order.
+    pub fn populations(&self) -> &[NamedPopulation] {
+        &self.populations
+    }
+
+    /// Neuron index range of the population called `name`.
+    pub fn population(&self, name: &str) -> Option<Range<usize>> {
+        find_population(&self.populations, name)
+    }
+
+    /// Plasticity parameter sets referenced by `Plasticity::Set`.
+    pub fn plasticity_sets(&self) -> &[STDPParams] {
+        &self.plasticity_sets
+    }
+
+    /// Spatial position of each neuron, if one was assigned.
+    pub fn positions(&self) -> &[Option<Position>] {
+        &self.positions
+    }
+
+    /// Tagged neuron groups, in creation order.
+    pub fn groups(&self) -> &[NeuronGroup] {
+        &self.groups
+    }
+}
+
+/// Incremental builder for a `Network`.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkBuilder {
+    neurons: Vec<NeuronParams>,
+    synapses: Vec<Synapse>,
+    populations: Vec<NamedPopulation>,
+    plasticity_sets: Vec<STDPParams>,
+    positions: Vec<Option<Position>>,
+    groups: Vec<NeuronGroup>,
+    rng: Rng,
+    allow_self_connections: bool,
+}
+
+impl NetworkBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow all-to-all and one-to-one wiring (`connect_all_to_all` and
+    /// `Connector::AllToAll` and `Connector::OneToOne` projections) to
+    /// create self-connections.
+    ///
+    /// Off by default. Explicit connections made with `connect` are always
+    /// honoured; the random and spatial generators never produce them.
+    pub fn allow_self_connections(&mut self, allow: bool) -> &mut Self {
+        self.allow_self_connections = allow;
+        self
+    }
+
+    /// Add an autapse (a synapse from a neuron onto itself) with the given
+    /// weight and delay to every neuron in `neurons`.
+    ///
+    /// Delayed autapses feed a neuron's own spikes back after `delay`,
+    /// which some oscillator models rely on.
+    pub fn add_autapses(
+        &mut self,
+        neurons: Range<usize>,
+        weight: f64,
+        delay: Milliseconds,
+    ) -> &mut Self {
+        self.synapses.extend(neurons.map(|i| Synapse::with_delay(i, i, weight, delay)));
+        self
+    }
+
+    /// Seed the generator used for random initial weights and projection
+    /// delays.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Number of neurons added so far.
+    pub fn num_neurons(&self) -> usize {
+        self.neurons.len()
+    }
+
+    /// Add `n` neurons sharing `params` and return their index range.
+    pub fn add_neurons(&mut self, n: usize, params: NeuronParams) -> Range<usize> {
+        let start = self.neurons.len();
+        self.neurons.extend(std::iter::repeat_n(params, n));
+        self.positions.resize(self.neurons.len(), None);
+        start..self.neurons.len()
+    }
+
+    /// Assign spatial positions to the neurons in `range`, in order.
+    ///
+    /// # Panics
+    /// Panics if `range` exceeds the neurons added so far.
+    pub fn place<I>(&mut self, range: Range<usize>, positions: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Position>,
+    {
+        assert!(range.end <= self.neurons.len(), "Cannot place neurons that were never added");
+        for (i, p) in range.zip(positions) {
+            self.positions[i] = Some(p);
+        }
+        self
+    }
+
+    /// Add `n` neurons as a population called `name` and return their
+    /// index range.
+    ///
+    /// # Panics
+    /// Panics if a population with the same name already exists.
+    pub fn add_population(&mut self, name: &str, n: usize, params: NeuronParams) -> Range<usize> {
+        assert!(
+            find_population(&self.populations, name).is_none(),
+            "Duplicate population name: {name}"
+        );
+        let range = self.add_neurons(n, params);
+        self.populations.push(NamedPopulation {
+            name: name.to_string(),
+            range: range.clone(),
+        });
+        range
+    }
+
+    /// Add a population laid out on a 2D grid with the given spacing and
+    /// return its index range.
+    ///
+    /// Neurons are numbered row by row and placed at `(x, y) * spacing`.
+    pub fn add_grid(
+        &mut self,
+        name: &str,
+        shape: SheetShape,
+        spacing: f64,
+        params: NeuronParams,
+    ) -> Range<usize> {
+        let range = self.add_population(name, shape.len(), params);
+        self.place(range.clone(), grid_positions(shape, spacing));
+        range
+    }
+
+    /// Connect each neuron of a grid population starting at `start` to its
+    /// neighbours within `radius` grid steps; see `spatial::grid_neighbors`.
+    pub fn connect_grid_neighbors<W: Into<WeightInit>>(
+        &mut self,
+        start: usize,
+        shape: SheetShape,
+        radius: f64,
+        boundary: Boundary,
+        weight: W,
+    ) -> &mut Self {
+        let weight = weight.into();
+        for (pre, post) in grid_neighbors(start, shape, radius, boundary) {
+            let w = weight.sample(&mut self.rng);
+            self.synapses.push(Synapse::new(pre, post, w));
+        }
+        self
+    }
+
+    /// Add the neurons described by `population` and return their index
+    /// range.
+    pub fn add(&mut self, population: &Population) -> Range<usize> {
+        self.add_population(&population.name, population.size, population.params.clone())
+    }
+
+    /// Neuron index range of the population called `name`.
+    pub fn population(&self, name: &str) -> Option<Range<usize>> {
+        find_population(&self.populations, name)
+    }
+
+    /// Add a complete sub-network as a region called `name` and return its
+    /// neuron index range.
+    ///
+    /// The region's neurons, synapses, positions, and plasticity sets are
+    /// copied in with indices offset accordingly. The region itself becomes
+    /// a population named `name`, and its populations and groups are
+    /// renamed to `{name}.{inner}`, so projections between regions can
+    /// refer to e.g. `thalamus.relay` and `cortex.l4`.
+    ///
+    /// # Panics
+    /// Panics if any resulting population name already exists.
+    pub fn add_region(&mut self, name: &str, region: Network) -> Range<usize> {
+        assert!(
+            find_population(&self.populations, name).is_none(),
+            "Duplicate population name: {name}"
+        );
+        let offset = self.neurons.len();
+        let set_offset = self.plasticity_sets.len();
+
+        self.neurons.extend(region.neurons.into_iter().map(|n| n.params));
+        self.positions.extend(region.positions);
+        self.plasticity_sets.extend(region.plasticity_sets);
+        let range = offset..self.neurons.len();
+        self.populations.push(NamedPopulation {
+            name: name.to_string(),
+            range: range.clone(),
+        });
+
+        for p in region.populations {
+            let inner = format!("{name}.{}", p.name);
+            assert!(
+                find_population(&self.populations, &inner).is_none(),
+                "Duplicate population name: {inner}"
+            );
+            self.populations.push(NamedPopulation {
+                name: inner,
+                range: p.range.start + offset..p.range.end + offset,
+            });
+        }
+        for g in region.groups {
+            tag_group(
+                &mut self.groups,
+                &format!("{name}.{}", g.name),
+                g.neurons.into_iter().map(|i| i + offset),
+            );
+        }
+        for mut synapse in region.synapses {
+            synapse.pre_neuron += offset;
+            synapse.post_neuron += offset;
+            if let Plasticity::Set(k) = synapse.plasticity {
+                synapse.plasticity = Plasticity::Set(k + set_offset);
+            }
+            self.synapses.push(synapse);
+        }
+        range
+    }
+
+    /// Tag `neurons` with the group label `name`, extending the group if it
+    /// already exists.
+    pub fn tag<I>(&mut self, name: &str, neurons: I) -> &mut Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        tag_group(&mut self.groups, name, neurons);
+        self
+    }
+
+    /// Register a plasticity parameter set and return the rule that refers
+    /// to it.
+    pub fn add_plasticity(&mut self, params: STDPParams) -> Plasticity {
+        self.plasticity_sets.push(params);
+        Plasticity::Set(self.plasticity_sets.len() - 1)
+    }
+
+    /// Wire the synapses described by `projection`.
+    ///
+    /// Random weights and delays are drawn from the builder's generator;
+    /// see `seed`.
+    ///
+    /// Fails with `InvalidInput`, wiring nothing, if either population has
+    /// not been added, a `OneToOne` projection joins populations of
+    /// different sizes, or a `List` connection is out of range.
+    pub fn project(&mut self, projection: &Projection) -> io::Result<&mut Self> {
+        let population = |name: &str| {
+            self.population(name)
+                .ok_or_else(|| invalid_input(format!("Unknown population: {name}")))
+        };
+        let pre = population(&projection.pre)?;
+        let post = population(&projection.post)?;
+        let name = || format!("{} -> {}", projection.pre, projection.post);
+        let plasticity = match &projection.plasticity {
+            Some(params) => self.add_plasticity(params.clone()),
+            None => Plasticity::Static,
+        };
+
+        let pairs: Vec<(usize, usize)> = match &projection.connector {
+            Connector::AllToAll => pre
+                .clone()
+                .flat_map(|i| post.clone().map(move |j| (i, j)))
+                .filter(|(i, j)| self.allow_self_connections || i != j)
+                .collect(),
+            Connector::OneToOne => {
+                if pre.len() != post.len() {
+                    return Err(invalid_input(format!(
+                        "One-to-one projection {} joins populations of sizes {} and {}",
+                        name(),
+                        pre.len(),
+                        post.len()
+                    )));
+                }
+                pre.clone()
+                    .zip(post.clone())
+                    .filter(|(i, j)| self.allow_self_connections || i != j)
+                    .collect()
+            }
+            Connector::List(pairs) => {
+                let out_of_range = |&&(i, j): &&(usize, usize)| i >= pre.len() || j >= post.len();
+                if let Some((i, j)) = pairs.iter().find(out_of_range) {
+                    return Err(invalid_input(format!(
+                        "Connection ({i}, {j}) out of range for projection {}",
+                        name()
+                    )));
+                }
+                pairs
+                    .iter()
+                    .map(|&(i, j)| (pre.start + i, post.start + j))
+                    .collect()
+            }
+        };
+
+        for (i, j) in pairs {
+            let weight = projection.weight.sample(&mut self.rng);
+            let delay = projection.delay.sample(&mut self.rng);
+            let mut synapse = Synapse::with_delay(i, j, weight, delay);
+            synapse.plasticity = plasticity;
+            self.synapses.push(synapse);
+        }
+        Ok(self)
+    }
+
+    /// Connect neuron `pre` to neuron `post` with the given weight.
+    pub fn connect(&mut self, pre: usize, post: usize, weight: f64) -> &mut Self {
+        self.synapses.push(Synapse::new(pre, post, weight));
+        self
+    }
+
+    /// Add every `(pre, post, weight)` connection produced by `connections`.
+    pub fn connect_all<I>(&mut self, connections: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (usize, usize, f64)>,
+    {
+        self.synapses.extend(
+            connections
+                .into_iter()
+                .map(|(pre, post, weight)| Synapse::new(pre, post, weight)),
+        );
+        self
+    }
+
+    /// Add fully specified synapses, e.g. with a non-default plasticity rule.
+    pub fn add_synapses<I>(&mut self, synapses: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Synapse>,
+    {
+        self.synapses.extend(synapses);
+        self
+    }
+
+    /// Add every `(pre, post, weight, delay)` connection produced by
+    /// `connections`.
+    pub fn connect_all_delayed<I>(&mut self, connections: I) -> &mut Self
+    where
+        I: IntoIterator<Item = DelayedConnection>,
+    {
+        self.synapses.extend(
+            connections
+                .into_iter()
+                .map(|(pre, post, weight, delay)| Synapse::with_delay(pre, post, weight, delay)),
+        );
+        self
+    }
+
+    /// Connect `pre` to `post` with distance-dependent probability and
+    /// delay, using the positions assigned with `place`.
+    ///
+    /// # Panics
+    /// Panics if any neuron in either range has no position.
+    pub fn connect_by_distance<W>(
+        &mut self,
+        pre: Range<usize>,
+        post: Range<usize>,
+        rule: &DistanceRule,
+        rng: &mut Rng,
+        weight: W,
+    ) -> &mut Self
+    where
+        W: FnMut(usize, usize, f64, &mut Rng) -> f64,
+    {
+        let positions = self.positions_of(&[pre.clone(), post.clone()]);
+        let connections = distance_dependent(pre, post, &positions, rule, rng, weight);
+        self.connect_all_delayed(connections)
+    }
+
+    /// Wire static difference-of-Gaussians lateral connections among
+    /// `neurons`, using the positions assigned with `place`.
+    ///
+    /// # Panics
+    /// Panics if any neuron in `neurons` has no position.
+    pub fn connect_lateral(&mut self, neurons: Range<usize>, kernel: &DogKernel) -> &mut Self {
+        let positions = self.positions_of(std::slice::from_ref(&neurons));
+        let synapses = difference_of_gaussians(neurons, &positions, kernel)
+            .into_iter()
+            .map(|(pre, post, w)| {
+                let mut synapse = Synapse::new(pre, post, w);
+                synapse.plasticity = Plasticity::Static;
+                synapse
+            });
+        self.add_synapses(synapses)
+    }
+
+    /// Positions indexed by neuron id, up to the end of the last range.
+    ///
+    /// # Panics
+    /// Panics if a neuron in any of `ranges` has no position.
+    fn positions_of(&self, ranges: &[Range<usize>]) -> Vec<Position> {
+        let end = ranges.iter().map(|r| r.end).max().unwrap_or(0);
+        self.positions[..end]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                if ranges.iter().any(|r| r.contains(&i)) {
+                    p.unwrap_or_else(|| panic!("Neuron {i} has no position"))
+                } else {
+                    p.unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+
+    /// Connect every neuron in `pre` to every neuron in `post`, skipping
+    /// self-connections unless `allow_self_connections` is set.
+    ///
+    /// Random initial weights are drawn from the builder's generator; see
+    /// `seed`.
+    pub fn connect_all_to_all<W: Into<WeightInit>>(
+        &mut self,
+        pre: Range<usize>,
+        post: Range<usize>,
+        weight: W,
+    ) -> &mut Self {
+        let weight = weight.into();
+        for i in pre {
+            for j in post.clone() {
+                if self.allow_self_connections || i != j {
+                    let w = weight.sample(&mut self.rng);
+                    self.synapses.push(Synapse::new(i, j, w));
+                }
+            }
+        }
+        self
+    }
+
+    /// Finish construction.
+    ///
+    /// # Panics
+    /// Panics if any connection or group refers to a neuron that was never
+    /// added, or a synapse to a plasticity set that was never registered.
+    pub fn build(self) -> Network {
+        let n = self.neurons.len();
+        if let Some(s) = self
+            .synapses
+            .iter()
+            .find(|s| s.pre_neuron >= n || s.post_neuron >= n)
+        {
+            panic!(
+                "Synapse {} -> {} references a neuron outside 0..{}",
+                s.pre_neuron, s.post_neuron, n
+            );
+        }
+        for g in &self.groups {
+            assert!(
+                g.neurons.iter().all(|&i| i < n),
+                "Group {} references a neuron outside 0..{}",
+                g.name,
+                n
+            );
+        }
+        for s in &self.synapses {
+            if let Plasticity::Set(k) = s.plasticity {
+                assert!(
+                    k < self.plasticity_sets.len(),
+                    "Synapse {} -> {} references unknown plasticity set {}",
+                    s.pre_neuron,
+                    s.post_neuron,
+                    k
+                );
+            }
+        }
+
+        Network {
+            neurons: self.neurons.into_iter().map(Neuron::new).collect(),
+            synapses: self.synapses,
+            populations: self.populations,
+            plasticity_sets: self.plasticity_sets,
+            positions: self.positions,
+            groups: self.groups,
+        }
+    }
+}
+
+/// Look up a population range by name.
+pub(crate) fn find_population(populations: &[NamedPopulation], name: &str) -> Option<Range<usize>> {
+    populations
+        .iter()
+        .find(|p| p.name == name)
+        .map(|p| p.range.clone())
+}
+
+/// Add `neurons` to the group called `name`, creating it if needed.
+pub(crate) fn tag_group<I>(groups: &mut Vec<NeuronGroup>, name: &str, neurons: I)
+where
+    I: IntoIterator<Item = usize>,
+{
+    let index = match groups.iter().position(|g| g.name == name) {
+        Some(index) => index,
+        None => {
+            groups.push(NeuronGroup {
+                name: name.to_string(),
+                neurons: Vec::new(),
+            });
+            groups.len() - 1
+        }
+    };
+    let members = &mut groups[index].neurons;
+    members.extend(neurons);
+    members.sort_unstable();
+    members.dedup();
+}
+
+/// Neurons labelled `name`, either as a population or as a tagged group.
+///
+/// When a population and a group share a name, their members are merged.
+pub(crate) fn group_members(
+    populations: &[NamedPopulation],
+    groups: &[NeuronGroup],
+    name: &str,
+) -> Option<Vec<usize>> {
+    let population = find_population(populations, name);
+    let group = groups.iter().find(|g| g.name == name);
+    if population.is_none() && group.is_none() {
+        return None;
+    }
+    let mut members: Vec<usize> = population.into_iter().flatten().collect();
+    if let Some(g) = group {
+        members.extend(&g.neurons);
+        members.sort_unstable();
+        members.dedup();
+    }
+    Some(members)
+}
@@ -0,0 +1,304 @@
+//! neuroml.rs
+//!
+//! NeuroML2 and LEMS export.
+//!
+//! NeuroML2 is the common model description language of computational
+//! neuroscience: jNeuroML simulates it directly and converts it for
+//! NEURON, NetPyNE and Brian, and the model repositories accept it. This
+//! module exports a simulation's network, with its current weights, as a
+//! NeuroML2 document and a LEMS simulation file that runs it for the
+//! configured duration and records every neuron's spikes:
+//!
+//! ```text
+//! jnml LEMS_<id>.xml            # simulate with jNeuroML
+//! jnml LEMS_<id>.xml -neuron    # generate and run NEURON code
+//! ```
+//!
+//! The model uses PyNN's dimensionless conventions (mV, ms, nA, nF):
+//!
+//! - Each population becomes an `IF_curr_exp` cell type and population.
+//!   With a 1 MΩ membrane resistance, `cm = tau_m` nF, and a current `I`
+//!   of this crate's LIF equation is `I` nA. Neurons outside populations
+//!   form a population called `neurons`, and populations whose neurons
+//!   differ in parameters are split. Positions, where every neuron of a
+//!   population has one, are exported as instance locations.
+//! - Synapses become `connectionWD`s through a single `expCurrSynapse`
+//!   whose time constant is the simulation step. The weight is scaled so
+//!   that a spike deposits the same charge as the instantaneous voltage
+//!   jump of this crate, which the short exponential current approximates.
+//!
+//! Plasticity is not exported: NeuroML2 has no standard STDP rule, so the
+//! synapses are static at their current weights. External input currents
+//! are part of the run, not the network, and must be added in the target
+//! simulator, e.g. through `i_offset` or NeuroML input lists.
+
+use crate::compression;
+use crate::simulation::Simulation;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// Id of the network element.
+const NETWORK_ID: &str = "network";
+/// Id of the synapse component.
+const SYNAPSE_ID: &str = "syn";
+
+/// A run of neurons exported as one NeuroML population.
+pub(crate) struct Segment {
+    pub(crate) id: String,
+    pub(crate) range: Range<usize>,
+    /// Whether instances with locations are listed
+    listed: bool,
+}
+
+impl Segment {
+    fn cell(&self) -> String {
+        format!("{}_cell", self.id)
+    }
+
+    /// Path of neuron `i` of the segment relative to the network.
+    fn path(&self, i: usize) -> String {
+        let local = i - self.range.start;
+        if self.listed {
+            format!("{}/{local}/{}", self.id, self.cell())
+        } else {
+            format!("{}[{local}]", self.id)
+        }
+    }
+}
+
+/// NeuroML2 document describing the network of `sim`.
+///
+/// `id` names the document; characters other than ASCII letters, digits
+/// and underscores are replaced by underscores.
+pub fn to_neuroml(sim: &Simulation, id: &str) -> String {
+    let segments = segments(sim);
+    let neurons = sim.neurons();
+    let tau_syn = sim.config().dt.0;
+    let mut segment_of = vec![0; neurons.len()];
+    for (k, segment) in segments.iter().enumerate() {
+        segment_of[segment.range.clone()].fill(k);
+    }
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<neuroml xmlns=\"http://www.neuroml.org/schema/neuroml2\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://www.neuroml.org/schema/neuroml2 \
+         https://raw.github.com/NeuroML/NeuroML2/development/Schemas/NeuroML2/NeuroML_v2.3.xsd\" \
+         id=\"{}\">",
+        nml_id(id)
+    );
+
+    for segment in &segments {
+        let p = &neurons[segment.range.start].params;
+        let _ = writeln!(
+            out,
+            "    <IF_curr_exp id=\"{}\" cm=\"{}\" i_offset=\"0\" tau_syn_E=\"{tau_syn}\" \
+             tau_syn_I=\"{tau_syn}\" v_init=\"{}\" tau_m=\"{}\" tau_refrac=\"0\" \
+             v_reset=\"{}\" v_rest=\"{}\" v_thresh=\"{}\"/>",
+            segment.cell(),
+            p.tau_m.0,
+            p.v_rest,
+            p.tau_m.0,
+            p.v_reset,
+            p.v_rest,
+            p.v_thresh
+        );
+    }
+    let _ = writeln!(
+        out,
+        "    <expCurrSynapse id=\"{SYNAPSE_ID}\" tau_syn=\"{tau_syn}\"/>"
+    );
+
+    let _ = writeln!(out, "    <network id=\"{NETWORK_ID}\">");
+    let positions = sim.positions();
+    for segment in &segments {
+        if !segment.listed {
+            let _ = writeln!(
+                out,
+                "        <population id=\"{}\" component=\"{}\" size=\"{}\"/>",
+                segment.id,
+                segment.cell(),
+                segment.range.len()
+            );
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            "        <population id=\"{}\" component=\"{}\" type=\"populationList\" size=\"{}\">",
+            segment.id,
+            segment.cell(),
+            segment.range.len()
+        );
+        for i in segment.range.clone() {
+            if let Some(p) = &positions[i] {
+                let _ = writeln!(
+                    out,
+                    "            <instance id=\"{}\"><location x=\"{}\" y=\"{}\" z=\"{}\"/></instance>",
+                    i - segment.range.start,
+                    p.x,
+                    p.y,
+                    p.z
+                );
+            }
+        }
+        out.push_str("        </population>\n");
+    }
+
+    // One projection per pair of populations, in population order
+    let mut projections: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (k, syn) in sim.synapses().iter().enumerate() {
+        projections
+            .entry((segment_of[syn.pre_neuron], segment_of[syn.post_neuron]))
+            .or_default()
+            .push(k);
+    }
+    for ((pre, post), synapses) in projections {
+        let (pre, post) = (&segments[pre], &segments[post]);
+        let _ = writeln!(
+            out,
+            "        <projection id=\"{}_to_{}\" presynapticPopulation=\"{}\" \
+             postsynapticPopulation=\"{}\" synapse=\"{SYNAPSE_ID}\">",
+            pre.id, post.id, pre.id, post.id
+        );
+        for (c, &k) in synapses.iter().enumerate() {
+            let syn = &sim.synapses()[k];
+            // Charge w * cm deposited by a current decaying with tau_syn
+            let weight = syn.weight * neurons[syn.post_neuron].params.tau_m.0 / tau_syn;
+            let _ = writeln!(
+                out,
+                "            <connectionWD id=\"{c}\" preCellId=\"../{}\" postCellId=\"../{}\" \
+                 weight=\"{weight}\" delay=\"{}ms\"/>",
+                pre.path(syn.pre_neuron),
+                post.path(syn.post_neuron),
+                syn.delay.0
+            );
+        }
+        out.push_str("        </projection>\n");
+    }
+    out.push_str("    </network>\n</neuroml>\n");
+    out
+}
+
+/// LEMS file simulating the NeuroML2 document `neuroml_file` written for
+/// `sim` for `t_max` with step `dt`, recording the spikes of every neuron
+/// to `<id>.spikes` as `neuron_id time_s` lines.
+///
+/// Fails if the simulation has no finite duration.
+pub fn to_lems(sim: &Simulation, id: &str, neuroml_file: &str) -> io::Result<String> {
+    let config = sim.config();
+    if !config.t_max.0.is_finite() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "LEMS export needs a finite t_max",
+        ));
+    }
+    let id = nml_id(id);
+    let mut out = String::new();
+    out.push_str("<Lems>\n");
+    let _ = writeln!(out, "    <Target component=\"sim_{id}\"/>");
+    for core in ["Cells.xml", "Networks.xml", "Simulation.xml", "PyNN.xml"] {
+        let _ = writeln!(out, "    <Include file=\"{core}\"/>");
+    }
+    let _ = writeln!(out, "    <Include file=\"{}\"/>", xml_escape(neuroml_file));
+    let _ = writeln!(
+        out,
+        "    <Simulation id=\"sim_{id}\" length=\"{}ms\" step=\"{}ms\" target=\"{NETWORK_ID}\">",
+        config.t_max.0, config.dt.0
+    );
+    let _ = writeln!(
+        out,
+        "        <EventOutputFile id=\"spikes\" fileName=\"{id}.spikes\" format=\"ID_TIME\">"
+    );
+    for segment in segments(sim) {
+        for i in segment.range.clone() {
+            let _ = writeln!(
+                out,
+                "            <EventSelection id=\"{i}\" select=\"{}\" eventPort=\"spike\"/>",
+                segment.path(i)
+            );
+        }
+    }
+    out.push_str("        </EventOutputFile>\n    </Simulation>\n</Lems>\n");
+    Ok(out)
+}
+
+/// Write `<id>.net.nml` and `LEMS_<id>.xml` for `sim` into `dir`.
+pub fn write_neuroml<P: AsRef<Path>>(sim: &Simulation, id: &str, dir: P) -> io::Result<()> {
+    let dir = dir.as_ref();
+    let id = nml_id(id);
+    let neuroml_file = format!("{id}.net.nml");
+    let lems = to_lems(sim, &id, &neuroml_file)?;
+    compression::write(dir.join(&neuroml_file), to_neuroml(sim, &id))?;
+    compression::write(dir.join(format!("LEMS_{id}.xml")), lems)
+}
+
+/// Split the neurons into populations of identical parameters.
+pub(crate) fn segments(sim: &Simulation) -> Vec<Segment> {
+    let neurons = sim.neurons();
+    let positions = sim.positions();
+    let population_of = |i: usize| {
+        sim.populations()
+            .iter()
+            .find(|p| p.range.contains(&i))
+            .map(|p| p.name.as_str())
+    };
+
+    let mut runs: Vec<(Option<&str>, Range<usize>)> = Vec::new();
+    for i in 0..neurons.len() {
+        let population = population_of(i);
+        let p = &neurons[i].params;
+        if let Some((name, range)) = runs.last_mut() {
+            let q = &neurons[range.start].params;
+            if *name == population
+                && p.tau_m == q.tau_m
+                && p.v_rest == q.v_rest
+                && p.v_thresh == q.v_thresh
+                && p.v_reset == q.v_reset
+            {
+                range.end = i + 1;
+                continue;
+            }
+        }
+        runs.push((population, i..i + 1));
+    }
+
+    let mut used = HashSet::new();
+    runs.into_iter()
+        .map(|(name, range)| {
+            let base = nml_id(name.unwrap_or("neurons"));
+            let mut id = base.clone();
+            let mut k = 1;
+            while !used.insert(id.clone()) {
+                id = format!("{base}_{k}");
+                k += 1;
+            }
+            let listed = positions[range.clone()].iter().all(Option::is_some);
+            Segment { id, range, listed }
+        })
+        .collect()
+}
+
+/// `name` as a valid NeuroML id.
+fn nml_id(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,152 @@
+//! neuromodulation.rs
+//!
+//! Neuromodulatory signals that scale plasticity and excitability.
+//!
+//! Neuromodulators such as dopamine and acetylcholine do not carry spikes
+//! from neuron to neuron; they are diffuse signals that change how strongly
+//! whole regions learn and respond. A `Modulator` is a named scalar level
+//! registered on a `Simulation`. It either reaches every neuron or is gated
+//! to a list of populations and tagged groups, and at level `m` it
+//! multiplies, for each neuron it reaches,
+//!
+//! ```text
+//! STDP rates of synapses onto the neuron   by 1 + plasticity_gain × m
+//! external current and synaptic input      by 1 + excitability_gain × m
+//! ```
+//!
+//! Each factor is floored at zero and factors of several modulators
+//! reaching the same neuron multiply, so at level 0 a modulator has no
+//! effect. Levels are set at any time with `Simulation::set_modulator`, and
+//! a modulator with a decay time constant relaxes back to 0 afterwards,
+//! turning a single call into a phasic burst. The level is thus the third
+//! factor of a three-factor rule: STDP proposes, the modulator gates.
+
+use crate::network::{group_members, NamedPopulation, NeuronGroup};
+use crate::units::Milliseconds;
+
+/// A named neuromodulatory signal.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modulator {
+    /// Name used by `Simulation::set_modulator`
+    pub name: String,
+    /// Current level; 0 leaves its targets unchanged
+    pub level: f64,
+    /// Populations or groups it reaches; empty reaches every neuron
+    pub targets: Vec<String>,
+    /// Relative change of STDP rates per unit of level
+    pub plasticity_gain: f64,
+    /// Relative change of neuron input per unit of level
+    pub excitability_gain: f64,
+    /// Time constant of the relaxation to 0, or `None` to hold the level
+    pub decay: Option<Milliseconds>,
+}
+
+impl Modulator {
+    /// A global modulator at level 0 that scales plasticity only and holds
+    /// its level.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            level: 0.0,
+            targets: Vec::new(),
+            plasticity_gain: 1.0,
+            excitability_gain: 0.0,
+            decay: None,
+        }
+    }
+
+    /// Dopamine analogue: a phasic signal gating plasticity, decaying with
+    /// a 200 ms time constant.
+    ///
+    /// With the default level of 0 and the gain of 1, setting the level to
+    /// -1 blocks plasticity and +1 doubles it.
+    pub fn dopamine() -> Self {
+        Self {
+            decay: Some(Milliseconds(200.0)),
+            ..Self::new("dopamine")
+        }
+    }
+
+    /// Acetylcholine analogue: a tonic signal raising both excitability and
+    /// plasticity by half per unit of level.
+    pub fn acetylcholine() -> Self {
+        Self {
+            plasticity_gain: 0.5,
+            excitability_gain: 0.5,
+            ..Self::new("acetylcholine")
+        }
+    }
+
+    /// Restrict the modulator to the populations or groups `targets`.
+    pub fn gated<I, S>(mut self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.targets = targets.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Let the level relax for `dt`.
+    pub(crate) fn decay(&mut self, dt: Milliseconds) {
+        if let Some(tau) = self.decay {
+            self.level *= (-(dt / tau)).exp();
+        }
+    }
+}
+
+/// Per-neuron factors of all modulators at one step.
+#[derive(Debug, Clone)]
+pub(crate) struct Modulation {
+    /// Factor of the STDP rates of synapses onto each neuron
+    pub(crate) plasticity: Vec<f64>,
+    /// Factor of the input to each neuron
+    pub(crate) excitability: Vec<f64>,
+}
+
+impl Modulation {
+    /// Combine `modulators` over `n` neurons, resolving targets against
+    /// `populations` and `groups`; `None` when nothing is modulated.
+    ///
+    /// Targets that name no population or group reach no neuron.
+    pub(crate) fn compute(
+        modulators: &[Modulator],
+        n: usize,
+        populations: &[NamedPopulation],
+        groups: &[NeuronGroup],
+    ) -> Option<Self> {
+        let active: Vec<&Modulator> = modulators.iter().filter(|m| m.level != 0.0).collect();
+        if active.is_empty() {
+            return None;
+        }
+        let mut plasticity = vec![1.0; n];
+        let mut excitability = vec![1.0; n];
+        for m in active {
+            let p = (1.0 + m.plasticity_gain * m.level).max(0.0);
+            let e = (1.0 + m.excitability_gain * m.level).max(0.0);
+            let mut apply = |i: usize| {
+                plasticity[i] *= p;
+                excitability[i] *= e;
+            };
+            if m.targets.is_empty() {
+                (0..n).for_each(&mut apply);
+            } else {
+                let mut members: Vec<usize> = m
+                    .targets
+                    .iter()
+                    .filter_map(|t| group_members(populations, groups, t))
+                    .flatten()
+                    .filter(|&i| i < n)
+                    .collect();
+                members.sort_unstable();
+                members.dedup();
+                members.into_iter().for_each(&mut apply);
+            }
+        }
+        Some(Self {
+            plasticity,
+            excitability,
+        })
+    }
+}
@@ -12,11 +12,15 @@
 //! The model is intentionally simple and software-focused, serving as a
 //! conceptual exploration of event-driven, time-based computation.
 
+use crate::quantization::FixedPoint;
+use crate::units::Milliseconds;
+
 /// Parameters governing neuron dynamics.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NeuronParams {
-    /// Membrane time constant (ms)
-    pub tau_m: f64,
+    /// Membrane time constant
+    pub tau_m: Milliseconds,
     /// Resting membrane potential
     pub v_rest: f64,
     /// Firing threshold
@@ -25,8 +29,21 @@ pub struct NeuronParams {
     pub v_reset: f64,
 }
 
+/// What happens to the membrane potential when a neuron fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResetMode {
+    /// Set the potential to `v_reset`
+    #[default]
+    ToReset,
+    /// Subtract the distance from `v_reset` to the threshold, keeping the
+    /// charge above threshold for the next spike
+    Subtract,
+}
+
 /// Leaky Integrate-and-Fire neuron state.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Neuron {
     /// Current membrane potential
     pub v_mem: f64,
@@ -47,22 +64,61 @@ impl Neuron {
     ///
     /// # Arguments
     /// * `input_current` - Synaptic input current at this timestep
-    /// * `dt` - Time step (ms)
+    /// * `dt` - Time step
     ///
     /// # Returns
     /// * `true` if the neuron emits a spike
     /// * `false` otherwise
-    pub fn step(&mut self, input_current: f64, dt: f64) -> bool {
+    pub fn step(&mut self, input_current: f64, dt: Milliseconds) -> bool {
+        self.step_with_reset(input_current, dt, ResetMode::ToReset)
+    }
+
+    /// Advance neuron state by one time step, resetting as `reset` says.
+    pub fn step_with_reset(
+        &mut self,
+        input_current: f64,
+        dt: Milliseconds,
+        reset: ResetMode,
+    ) -> bool {
+        self.integrate(input_current, dt);
+        self.fire(reset)
+    }
+
+    /// Advance neuron state by one time step in fixed point: the membrane
+    /// potential is rounded to `format` after integration and reset, and
+    /// the rounded value is compared with the threshold.
+    pub fn step_quantized(
+        &mut self,
+        input_current: f64,
+        dt: Milliseconds,
+        format: &FixedPoint,
+        reset: ResetMode,
+    ) -> bool {
+        self.integrate(input_current, dt);
+        self.v_mem = format.quantize(self.v_mem);
+        let fired = self.fire(reset);
+        if fired {
+            self.v_mem = format.quantize(self.v_mem);
+        }
+        fired
+    }
+
+    fn integrate(&mut self, input_current: f64, dt: Milliseconds) {
         // Leaky integration of membrane potential
-        let dv = (-(self.v_mem - self.params.v_rest) + input_current) / self.params.tau_m;
-        self.v_mem += dv * dt;
+        let dv = (-(self.v_mem - self.params.v_rest) + input_current) / self.params.tau_m.0;
+        self.v_mem += dv * dt.0;
+    }
 
-        // Check for spike
+    /// Check for a spike and reset if the threshold is reached.
+    fn fire(&mut self, reset: ResetMode) -> bool {
         if self.v_mem >= self.params.v_thresh {
-            self.v_mem = self.params.v_reset;
+            self.v_mem = match reset {
+                ResetMode::ToReset => self.params.v_reset,
+                ResetMode::Subtract => self.v_mem - (self.params.v_thresh - self.params.v_reset),
+            };
             true
         } else {
             false
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,483 @@
+//! numpy.rs
+//!
+//! NumPy `.npy` and `.npz` export and import.
+//!
+//! Loading a large CSV spike log into Python means parsing every number as
+//! text. NumPy's own formats store the raw little-endian values behind a
+//! short header, so `numpy.load` maps them straight into arrays. An
+//! `NpyArray` is built from spikes, a weight log, the current weight matrix
+//! or a voltage probe, and written on its own as `.npy` or together with
+//! others as an `.npz` archive (an uncompressed zip of `.npy` files):
+//!
+//! ```text
+//! spikes          structured [spikes]   neuron_id (uint64), time_ms (float64)
+//! weight log      structured [samples]  time_ms, pre_neuron, post_neuron, weight
+//! weight matrix   float64 [pre, post]
+//! voltages        float64 [samples, probed neurons]
+//! ```
+//!
+//! Structured arrays use the CSV column names as field names, e.g.
+//! `numpy.load("spikes.npy")["time_ms"]`.
+//!
+//! `read_npy` and `read_npz` load numeric arrays saved by NumPy, such as
+//! trained weights, in C order. Archives must be written by `numpy.savez`;
+//! the deflated entries of `numpy.savez_compressed` are not supported.
+
+use crate::probe::VoltageProbe;
+use crate::simulation::{Simulation, WeightSample};
+use crate::spike::Spike;
+use crate::util::{crc32, invalid};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// An array ready to be written in the NumPy format.
+#[derive(Debug, Clone)]
+pub struct NpyArray {
+    /// NumPy type description, as a Python literal
+    descr: String,
+    shape: Vec<usize>,
+    /// Little-endian values in C order
+    data: Vec<u8>,
+}
+
+impl NpyArray {
+    /// `float64` array of the given shape, from values in C (row-major)
+    /// order.
+    ///
+    /// # Panics
+    /// If the number of values does not match the shape.
+    pub fn float64(shape: &[usize], values: impl IntoIterator<Item = f64>) -> Self {
+        Self::new("'<f8'", shape, 1, values.into_iter().map(f64::to_le_bytes))
+    }
+
+    /// `uint64` array of the given shape, from values in C (row-major)
+    /// order.
+    ///
+    /// # Panics
+    /// If the number of values does not match the shape.
+    pub fn uint64(shape: &[usize], values: impl IntoIterator<Item = u64>) -> Self {
+        Self::new("'<u8'", shape, 1, values.into_iter().map(u64::to_le_bytes))
+    }
+
+    /// Structured array of spikes with `neuron_id` and `time_ms` fields.
+    pub fn spikes(spikes: &[Spike]) -> Self {
+        Self::new(
+            "[('neuron_id', '<u8'), ('time_ms', '<f8')]",
+            &[spikes.len()],
+            2,
+            spikes
+                .iter()
+                .flat_map(|s| [(s.neuron_id as u64).to_le_bytes(), s.time.0.to_le_bytes()]),
+        )
+    }
+
+    /// Structured array of a weight log, as returned by `Simulation::run`,
+    /// with `time_ms`, `pre_neuron`, `post_neuron` and `weight` fields.
+    pub fn weight_log(weights: &[WeightSample]) -> Self {
+        Self::new(
+            "[('time_ms', '<f8'), ('pre_neuron', '<u8'), ('post_neuron', '<u8'), \
+             ('weight', '<f8')]",
+            &[weights.len()],
+            4,
+            weights.iter().flat_map(|&(t, pre, post, w)| {
+                [
+                    t.0.to_le_bytes(),
+                    (pre as u64).to_le_bytes(),
+                    (post as u64).to_le_bytes(),
+                    w.to_le_bytes(),
+                ]
+            }),
+        )
+    }
+
+    /// Current weights of `sim` as a dense `float64` matrix indexed
+    /// `[pre, post]`; parallel synapses are summed.
+    pub fn weight_matrix(sim: &Simulation) -> Self {
+        let n = sim.neurons().len();
+        let mut matrix = vec![0.0; n * n];
+        for s in sim.synapses() {
+            matrix[s.pre_neuron * n + s.post_neuron] += s.weight;
+        }
+        Self::float64(&[n, n], matrix)
+    }
+
+    /// Samples of `probe` as a `float64` array indexed `[sample, neuron]`,
+    /// with neurons in the order of `VoltageProbe::neurons`.
+    pub fn voltages(probe: &VoltageProbe) -> Self {
+        let samples = probe.samples();
+        Self::float64(
+            &[samples.len(), probe.neurons().len()],
+            samples.into_iter().flat_map(|(_, v)| v),
+        )
+    }
+
+    /// Array dimensions.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Values of a numeric array as `f64` in C order, or `None` for
+    /// structured arrays and unsupported types.
+    pub fn to_f64(&self) -> Option<Vec<f64>> {
+        let descr = self.descr.trim_matches('\'');
+        let (order, kind) = descr.split_at(descr.len().min(1));
+        let little = matches!(order, "<" | "|" | "=");
+        if !little && order != ">" {
+            return None;
+        }
+        let (kind, size) = kind.split_at(kind.len().min(1));
+        let size: usize = size.parse().ok()?;
+        let value = |bytes: &[u8]| -> Option<f64> {
+            let mut le = [0u8; 8];
+            le[..size].copy_from_slice(bytes);
+            if !little {
+                le[..size].reverse();
+            }
+            let sign_extend = |bits: u32| {
+                let shift = 64 - bits;
+                ((u64::from_le_bytes(le) << shift) as i64 >> shift) as f64
+            };
+            Some(match (kind, size) {
+                ("f", 8) => f64::from_le_bytes(le),
+                ("f", 4) => f64::from(f32::from_le_bytes([le[0], le[1], le[2], le[3]])),
+                ("i", 1 | 2 | 4 | 8) => sign_extend(size as u32 * 8),
+                ("u" | "b", 1 | 2 | 4 | 8) => u64::from_le_bytes(le) as f64,
+                _ => return None,
+            })
+        };
+        self.data.chunks_exact(size).map(value).collect()
+    }
+
+    /// Parse an array in the `.npy` format.
+    ///
+    /// Returns an `InvalidData` error for malformed data and for arrays
+    /// stored in Fortran order.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let magic = b"\x93NUMPY";
+        if bytes.len() < 10 || &bytes[..6] != magic {
+            return Err(invalid("Not an npy array".to_string()));
+        }
+        let (header_start, header_len) = match bytes[6] {
+            1 => (10, usize::from(u16::from_le_bytes([bytes[8], bytes[9]]))),
+            2 | 3 if bytes.len() >= 12 => (
+                12,
+                u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            ),
+            version => return Err(invalid(format!("Unsupported npy version {version}"))),
+        };
+        let header = bytes
+            .get(header_start..header_start + header_len)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .ok_or_else(|| invalid("Truncated npy header".to_string()))?;
+        let field = |name: &str| {
+            let key = format!("'{name}':");
+            header
+                .find(&key)
+                .map(|i| header[i + key.len()..].trim_start())
+                .ok_or_else(|| invalid(format!("npy header lacks `{name}`")))
+        };
+
+        let descr = field("descr")?;
+        let descr = match descr.strip_prefix('\'') {
+            Some(rest) => {
+                let end = rest
+                    .find('\'')
+                    .ok_or_else(|| invalid("Malformed npy descr".to_string()))?;
+                descr[..end + 2].to_string()
+            }
+            None => return Err(invalid("Structured npy arrays are not supported".to_string())),
+        };
+        if field("fortran_order")?.starts_with("True") {
+            return Err(invalid("Fortran-order npy arrays are not supported".to_string()));
+        }
+        let shape = field("shape")?;
+        let shape = shape
+            .strip_prefix('(')
+            .and_then(|s| s.split(')').next())
+            .ok_or_else(|| invalid("Malformed npy shape".to_string()))?
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(|d| {
+                d.parse::<usize>()
+                    .map_err(|_| invalid(format!("Invalid npy dimension `{d}`")))
+            })
+            .collect::<io::Result<Vec<usize>>>()?;
+
+        let size: usize = descr
+            .trim_matches('\'')
+            .get(2..)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid(format!("Unsupported npy type {descr}")))?;
+        let data = &bytes[header_start + header_len..];
+        let expected = shape.iter().product::<usize>() * size;
+        if data.len() < expected {
+            return Err(invalid("Truncated npy data".to_string()));
+        }
+        Ok(Self {
+            descr,
+            shape,
+            data: data[..expected].to_vec(),
+        })
+    }
+
+    /// The array in the `.npy` format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let shape = match self.shape.as_slice() {
+            [n] => format!("({n},)"),
+            dims => {
+                let dims: Vec<String> = dims.iter().map(usize::to_string).collect();
+                format!("({})", dims.join(", "))
+            }
+        };
+        let mut header = format!(
+            "{{'descr': {}, 'fortran_order': False, 'shape': {shape}, }}",
+            self.descr
+        );
+        // Magic (6) + version (2) + header length (2) + header + newline must
+        // be a multiple of 64 bytes
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(10 + header.len() + self.data.len());
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Write the array as a `.npy` file.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&self.to_bytes())?;
+        writer.flush()
+    }
+
+    /// Array of elements made of `fields` 8-byte values each.
+    fn new(
+        descr: &str,
+        shape: &[usize],
+        fields: usize,
+        values: impl Iterator<Item = [u8; 8]>,
+    ) -> Self {
+        let data: Vec<u8> = values.flatten().collect();
+        assert_eq!(
+            data.len(),
+            shape.iter().product::<usize>() * fields * 8,
+            "Number of values does not match shape {shape:?}"
+        );
+        Self {
+            descr: descr.to_string(),
+            shape: shape.to_vec(),
+            data,
+        }
+    }
+}
+
+/// Read a `.npy` file, see `NpyArray::from_bytes`.
+pub fn read_npy<P: AsRef<Path>>(path: P) -> io::Result<NpyArray> {
+    NpyArray::from_bytes(&std::fs::read(path)?)
+}
+
+/// Read the arrays of an uncompressed `.npz` archive, by name without the
+/// `.npy` extension, in archive order.
+pub fn read_npz<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, NpyArray)>> {
+    let bytes = std::fs::read(path)?;
+    let truncated = || invalid("Truncated npz archive".to_string());
+    let u16_at = |at: usize| -> io::Result<u64> {
+        let b = bytes.get(at..at + 2).ok_or_else(truncated)?;
+        Ok(u64::from(u16::from_le_bytes([b[0], b[1]])))
+    };
+    let u32_at = |at: usize| -> io::Result<u64> {
+        let b = bytes.get(at..at + 4).ok_or_else(truncated)?;
+        Ok(u64::from(u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+    };
+    let u64_at = |at: usize| -> io::Result<u64> {
+        let b = bytes.get(at..at + 8).ok_or_else(truncated)?;
+        Ok(u64::from_le_bytes(b.try_into().expect("8 bytes")))
+    };
+    let offset = |value: u64| usize::try_from(value).map_err(|_| truncated());
+
+    // End of central directory record, followed by a comment of up to 64 KiB
+    let search_start = bytes.len().saturating_sub(22 + 0xFFFF);
+    let end = (search_start..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| bytes[i..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(|| invalid("Not a zip archive".to_string()))?;
+    let mut entries = u16_at(end + 10)?;
+    let mut central = u32_at(end + 16)?;
+    if entries == 0xFFFF || central == 0xFFFF_FFFF {
+        // Zip64 end of central directory, located by the record before
+        let locator = end.checked_sub(20).ok_or_else(truncated)?;
+        if !bytes[locator..].starts_with(b"PK\x06\x07") {
+            return Err(invalid("Missing zip64 locator".to_string()));
+        }
+        let end64 = offset(u64_at(locator + 8)?)?;
+        entries = u64_at(end64 + 32)?;
+        central = u64_at(end64 + 48)?;
+    }
+
+    let mut arrays = Vec::new();
+    let mut at = offset(central)?;
+    for _ in 0..entries {
+        if !bytes.get(at..).is_some_and(|b| b.starts_with(b"PK\x01\x02")) {
+            return Err(invalid("Malformed zip central directory".to_string()));
+        }
+        let method = u16_at(at + 10)?;
+        let mut size = u32_at(at + 24)?;
+        let name_len = offset(u16_at(at + 28)?)?;
+        let extra_len = offset(u16_at(at + 30)?)?;
+        let comment_len = offset(u16_at(at + 32)?)?;
+        let mut local = u32_at(at + 42)?;
+        let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // Zip64 extra field: the 32-bit fields that overflowed, in order
+        let mut extra = at + 46 + name_len;
+        let extra_end = extra + extra_len;
+        while extra + 4 <= extra_end {
+            let (id, len) = (u16_at(extra)?, offset(u16_at(extra + 2)?)?);
+            if id == 1 {
+                let mut field = extra + 4;
+                let mut next = || -> io::Result<u64> {
+                    let value = u64_at(field)?;
+                    field += 8;
+                    Ok(value)
+                };
+                if u32_at(at + 24)? == 0xFFFF_FFFF {
+                    size = next()?;
+                }
+                if u32_at(at + 20)? == 0xFFFF_FFFF {
+                    next()?;
+                }
+                if local == 0xFFFF_FFFF {
+                    local = next()?;
+                }
+            }
+            extra += 4 + len;
+        }
+        at = extra_end + comment_len;
+
+        if method != 0 {
+            return Err(invalid(format!(
+                "npz entry `{name}` is compressed; save with numpy.savez"
+            )));
+        }
+        let local = offset(local)?;
+        if !bytes.get(local..).is_some_and(|b| b.starts_with(b"PK\x03\x04")) {
+            return Err(invalid(format!("Malformed zip entry `{name}`")));
+        }
+        let data = local + 30 + offset(u16_at(local + 26)?)? + offset(u16_at(local + 28)?)?;
+        let data = bytes
+            .get(data..data + offset(size)?)
+            .ok_or_else(truncated)?;
+        let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+        arrays.push((name, NpyArray::from_bytes(data)?));
+    }
+    Ok(arrays)
+}
+
+/// Write `spikes` as a structured `.npy` file, see `NpyArray::spikes`.
+pub fn write_spikes_npy<P: AsRef<Path>>(spikes: &[Spike], path: P) -> io::Result<()> {
+    NpyArray::spikes(spikes).write(path)
+}
+
+/// Write the current weight matrix of `sim` as a `.npy` file, see
+/// `NpyArray::weight_matrix`.
+pub fn write_weight_matrix_npy<P: AsRef<Path>>(sim: &Simulation, path: P) -> io::Result<()> {
+    NpyArray::weight_matrix(sim).write(path)
+}
+
+/// Write the samples of `probe` as a `.npy` file, see
+/// `NpyArray::voltages`.
+pub fn write_voltages_npy<P: AsRef<Path>>(probe: &VoltageProbe, path: P) -> io::Result<()> {
+    NpyArray::voltages(probe).write(path)
+}
+
+/// Write the results of a run of `sim` as an `.npz` archive with arrays
+/// `spikes` and `weights` (the final weight matrix), plus `probe_neurons`,
+/// `probe_time_ms` and `v_mem` if a probe is given.
+pub fn write_results_npz<P: AsRef<Path>>(
+    sim: &Simulation,
+    spikes: &[Spike],
+    probe: Option<&VoltageProbe>,
+    path: P,
+) -> io::Result<()> {
+    let mut arrays = vec![
+        ("spikes", NpyArray::spikes(spikes)),
+        ("weights", NpyArray::weight_matrix(sim)),
+    ];
+    if let Some(probe) = probe {
+        let neurons = probe.neurons();
+        let times = probe.times();
+        arrays.push((
+            "probe_neurons",
+            NpyArray::uint64(&[neurons.len()], neurons.iter().map(|&n| n as u64)),
+        ));
+        arrays.push((
+            "probe_time_ms",
+            NpyArray::float64(&[times.len()], times.iter().map(|t| t.0)),
+        ));
+        arrays.push(("v_mem", NpyArray::voltages(probe)));
+    }
+    write_npz(&arrays, path)
+}
+
+/// Write `arrays` as an uncompressed `.npz` archive, each under its name
+/// (without the `.npy` extension), as read by `numpy.load`.
+pub fn write_npz<P: AsRef<Path>>(arrays: &[(&str, NpyArray)], path: P) -> io::Result<()> {
+    // Zip fields used by every entry: version needed (2.0), flags, method
+    // (stored), modification time and date (1980-01-01 00:00)
+    const ENTRY_INFO: [u8; 10] = [20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0];
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "npz archive larger than 4 GiB");
+
+    let entries = u16::try_from(arrays.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Too many npz arrays"))?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut offset = 0u32;
+    let mut central = Vec::new();
+    for (name, array) in arrays {
+        let name = format!("{name}.npy");
+        let data = array.to_bytes();
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let mut info = Vec::with_capacity(24);
+        info.extend_from_slice(&ENTRY_INFO);
+        info.extend_from_slice(&crc32(data.iter()).to_le_bytes());
+        info.extend_from_slice(&size.to_le_bytes());
+        info.extend_from_slice(&size.to_le_bytes());
+        info.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        info.extend_from_slice(&[0, 0]); // extra field length
+
+        let mut local = b"PK\x03\x04".to_vec();
+        local.extend_from_slice(&info);
+        local.extend_from_slice(name.as_bytes());
+        writer.write_all(&local)?;
+        writer.write_all(&data)?;
+
+        central.extend_from_slice(b"PK\x01\x02");
+        central.extend_from_slice(&[20, 0]); // version made by
+        central.extend_from_slice(&info);
+        // Comment length, disk number, internal and external attributes
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        offset = (local.len() + data.len())
+            .try_into()
+            .ok()
+            .and_then(|len| offset.checked_add(len))
+            .ok_or_else(too_large)?;
+    }
+
+    writer.write_all(&central)?;
+    writer.write_all(b"PK\x05\x06")?;
+    writer.write_all(&[0; 4])?; // disk numbers
+    writer.write_all(&entries.to_le_bytes())?;
+    writer.write_all(&entries.to_le_bytes())?;
+    writer.write_all(&(central.len() as u32).to_le_bytes())?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&[0, 0])?; // comment length
+    writer.flush()
+}
@@ -0,0 +1,235 @@
+//! nwb.rs
+//!
+//! Neurodata Without Borders export.
+//!
+//! NWB is the standard format for experimental neurophysiology data, read
+//! by pynwb and MatNWB and by analysis tools such as SpikeInterface and
+//! the DANDI archive. Writing simulated recordings in the same format lets
+//! them go through the pipelines used for recorded data, and models be
+//! compared with experiments directly. `write_nwb` stores the spikes of a
+//! run as the NWB `Units` table, with one unit per neuron, and membrane
+//! traces from a `VoltageProbe` as a `TimeSeries`:
+//!
+//! ```text
+//! /units                               Units, one row per neuron
+//!     id                   int64   [neurons]
+//!     spike_times          float64 [spikes]       in s, grouped by unit
+//!     spike_times_index    uint64  [neurons]      end of each unit's spikes
+//! /acquisition/membrane_potential      TimeSeries
+//!     data                 float64 [samples, probed neurons]
+//!     timestamps           float64 [samples]      in s
+//! ```
+//!
+//! Times are in seconds from the session start, which is simulation time
+//! zero. Potentials are taken to be in millivolts, like SONATA weights, so
+//! `data` has a `conversion` of 0.001 to volts. The file follows NWB 2.7
+//! and opens with `pynwb.NWBHDF5IO(path).read()`.
+
+use crate::hdf5::{Attribute, Hdf5Writer};
+use crate::manifest::iso8601;
+use crate::probe::VoltageProbe;
+use crate::rng::Rng;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// NWB version the files follow.
+pub const NWB_VERSION: &str = "2.7.0";
+
+/// Session metadata of an NWB file.
+#[derive(Debug, Clone)]
+pub struct NwbSession {
+    /// Unique identifier of the file
+    pub identifier: String,
+    /// Description of the session
+    pub description: String,
+    /// Wall-clock time of simulation time zero
+    pub start_time: SystemTime,
+}
+
+impl NwbSession {
+    /// Session starting now, with a random identifier.
+    pub fn new(description: &str) -> Self {
+        let start_time = SystemTime::now();
+        Self {
+            identifier: uuid(&mut id_rng(start_time)),
+            description: description.to_string(),
+            start_time,
+        }
+    }
+}
+
+/// Write the spikes of a run of `sim` and, if given, the traces of `probe`
+/// to the NWB file at `path`.
+pub fn write_nwb<P: AsRef<Path>>(
+    sim: &Simulation,
+    spikes: &[Spike],
+    probe: Option<&VoltageProbe>,
+    session: &NwbSession,
+    path: P,
+) -> io::Result<()> {
+    let now = SystemTime::now();
+    let mut rng = id_rng(now);
+    let mut ids = || Attribute::Text(uuid(&mut rng));
+    let text = |s: &str| Attribute::Text(s.to_string());
+    let mut file = Hdf5Writer::create(path)?;
+
+    file.create_group(
+        "/",
+        &[
+            ("namespace", text("core")),
+            ("neurodata_type", text("NWBFile")),
+            ("nwb_version", text(NWB_VERSION)),
+            ("object_id", ids()),
+        ],
+    )?;
+    for group in [
+        "/acquisition",
+        "/analysis",
+        "/general",
+        "/processing",
+        "/stimulus/presentation",
+        "/stimulus/templates",
+    ] {
+        file.create_group(group, &[])?;
+    }
+    let start = iso8601(session.start_time);
+    file.write_strings("/file_create_date", [iso8601(now)], &[])?;
+    file.write_text("/identifier", &session.identifier, &[])?;
+    file.write_text("/session_description", &session.description, &[])?;
+    file.write_text("/session_start_time", &start, &[])?;
+    file.write_text("/timestamps_reference_time", &start, &[])?;
+
+    // Units table: spike times grouped by neuron, in time order
+    let num_neurons = sim.neurons().len();
+    let mut by_neuron = vec![Vec::new(); num_neurons];
+    for spike in spikes {
+        if let Some(times) = by_neuron.get_mut(spike.neuron_id) {
+            times.push(spike.time.0 / 1000.0);
+        }
+    }
+    by_neuron
+        .iter_mut()
+        .for_each(|times| times.sort_by(f64::total_cmp));
+    let index = by_neuron.iter().scan(0u64, |end, times| {
+        *end += times.len() as u64;
+        Some(*end)
+    });
+    file.write_dataset(
+        "/units/id",
+        0..num_neurons as i64,
+        &[
+            ("namespace", text("hdmf-common")),
+            ("neurodata_type", text("ElementIdentifiers")),
+            ("object_id", ids()),
+        ],
+    )?;
+    file.write_dataset(
+        "/units/spike_times",
+        by_neuron.iter().flatten().copied(),
+        &[
+            (
+                "description",
+                text("the spike times for each unit in seconds"),
+            ),
+            ("namespace", text("hdmf-common")),
+            ("neurodata_type", text("VectorData")),
+            ("object_id", ids()),
+            ("resolution", Attribute::Float(sim.config().dt.0 / 1000.0)),
+        ],
+    )?;
+    file.write_dataset(
+        "/units/spike_times_index",
+        index,
+        &[
+            ("description", text("Index for VectorData 'spike_times'")),
+            ("namespace", text("hdmf-common")),
+            ("neurodata_type", text("VectorIndex")),
+            ("object_id", ids()),
+            (
+                "target",
+                Attribute::Reference("/units/spike_times".to_string()),
+            ),
+        ],
+    )?;
+    file.create_group(
+        "/units",
+        &[
+            (
+                "colnames",
+                Attribute::Texts(vec!["spike_times".to_string()]),
+            ),
+            (
+                "description",
+                text("Simulated neurons, one unit per neuron"),
+            ),
+            ("namespace", text("core")),
+            ("neurodata_type", text("Units")),
+            ("object_id", ids()),
+        ],
+    )?;
+
+    if let Some(probe) = probe {
+        let neurons = probe.neurons();
+        let samples = probe.samples();
+        let columns: Vec<String> = neurons.iter().map(usize::to_string).collect();
+        file.write_matrix(
+            "/acquisition/membrane_potential/data",
+            neurons.len(),
+            samples.iter().flat_map(|(_, v)| v.iter().copied()),
+            &[
+                ("conversion", Attribute::Float(0.001)),
+                ("offset", Attribute::Float(0.0)),
+                ("resolution", Attribute::Float(-1.0)),
+                ("unit", text("volts")),
+            ],
+        )?;
+        file.write_dataset(
+            "/acquisition/membrane_potential/timestamps",
+            samples.iter().map(|(t, _)| t.0 / 1000.0),
+            &[("interval", Attribute::Int(1)), ("unit", text("seconds"))],
+        )?;
+        file.create_group(
+            "/acquisition/membrane_potential",
+            &[
+                ("comments", text("no comments")),
+                (
+                    "description",
+                    Attribute::Text(format!(
+                        "Membrane potential of neurons {} (columns)",
+                        columns.join(", ")
+                    )),
+                ),
+                ("namespace", text("core")),
+                ("neurodata_type", text("TimeSeries")),
+                ("object_id", ids()),
+            ],
+        )?;
+    }
+    file.finish()
+}
+
+/// Generator of object identifiers, seeded from `time`.
+fn id_rng(time: SystemTime) -> Rng {
+    let nanos = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    Rng::new(nanos as u64 ^ u64::from(std::process::id()).rotate_left(32))
+}
+
+/// Random (version 4) UUID.
+fn uuid(rng: &mut Rng) -> String {
+    let high = rng.next_u64() & !0xF000 | 0x4000;
+    let low = rng.next_u64() & !(0xC << 60) | 0x8 << 60;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        high >> 16 & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF
+    )
+}
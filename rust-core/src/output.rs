@@ -0,0 +1,269 @@
+//! output.rs
+//!
+//! Output directories and the layout of run artifacts.
+//!
+//! Hardcoding where results go works for one binary run from one place,
+//! but not for a crate used as a dependency or for batches of runs that
+//! must not overwrite each other. An `OutputConfig` names a base directory,
+//! the formats to write and their compression; each call to `create_run`
+//! makes a fresh numbered subdirectory for one run:
+//!
+//! ```text
+//! <dir>/
+//!     <prefix>-0001/
+//!         spikes.csv  weights.csv      OutputFormat::Csv
+//!         results.json                 OutputFormat::Json
+//!         results.npz                  OutputFormat::Npz
+//!         spikes.parquet  weights.parquet
+//!                                      OutputFormat::Parquet
+//!         spikes.spk                   OutputFormat::Spk
+//!     <prefix>-0002/
+//!         ...
+//! ```
+//!
+//! With `per_run` disabled the files go straight into `dir`, replacing
+//! those of the previous run. Compression applies to the CSV, JSON and
+//! `.spk` files. Errors name the path that could not be created or
+//! written.
+
+use crate::compression::{self, Compression};
+use crate::numpy::write_results_npz;
+use crate::parquet::{write_spikes_parquet, write_weights_parquet};
+use crate::recorder::{SpikeRecorder, SpillFormat};
+use crate::results::RunResults;
+use crate::simulation::{Simulation, WeightSample};
+use crate::spike::Spike;
+use crate::spike_codec::write_spikes_binary;
+use crate::spike_io::write_spikes_csv;
+use crate::stopping::StopReason;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// File format of run artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputFormat {
+    /// `spikes.csv` and `weights.csv`
+    Csv,
+    /// `results.json`, see `RunResults`
+    Json,
+    /// `results.npz` with spikes and the final weight matrix
+    Npz,
+    /// `spikes.parquet` and `weights.parquet`
+    Parquet,
+    /// `spikes.spk`, the compact binary spike format
+    Spk,
+}
+
+impl OutputFormat {
+    /// Format from its name, as used on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "npz" => Some(Self::Npz),
+            "parquet" => Some(Self::Parquet),
+            "spk" => Some(Self::Spk),
+            _ => None,
+        }
+    }
+}
+
+/// Where and how the artifacts of runs are written.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputConfig {
+    /// Base directory, created if missing
+    pub dir: PathBuf,
+    /// Name of run subdirectories, before their number
+    pub prefix: String,
+    /// Formats to write
+    pub formats: Vec<OutputFormat>,
+    /// Compression of CSV, JSON and `.spk` files
+    pub compression: Compression,
+    /// Whether each run gets its own subdirectory
+    pub per_run: bool,
+}
+
+impl OutputConfig {
+    /// Runs in numbered `run-NNNN` subdirectories of `dir`, written as CSV
+    /// and JSON.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: "run".to_string(),
+            formats: vec![OutputFormat::Csv, OutputFormat::Json],
+            compression: Compression::None,
+            per_run: true,
+        }
+    }
+
+    /// Create the directory for the next run.
+    ///
+    /// Run subdirectories are numbered one past the highest existing number
+    /// with the same prefix, so concurrent processes sharing `dir` never
+    /// write into the same run.
+    pub fn create_run(&self) -> io::Result<RunOutput> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| path_error(e, "create output directory", &self.dir))?;
+        let dir = if self.per_run {
+            let mut number = self.last_run_number()? + 1;
+            loop {
+                let dir = self.dir.join(format!("{}-{number:04}", self.prefix));
+                match fs::create_dir(&dir) {
+                    Ok(()) => break dir,
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => number += 1,
+                    Err(e) => return Err(path_error(e, "create run directory", &dir)),
+                }
+            }
+        } else {
+            self.dir.clone()
+        };
+        Ok(RunOutput {
+            dir,
+            formats: self.formats.clone(),
+            compression: self.compression,
+            files: Vec::new(),
+        })
+    }
+
+    /// Highest number of an existing run subdirectory, or 0.
+    fn last_run_number(&self) -> io::Result<u32> {
+        let entries =
+            fs::read_dir(&self.dir).map_err(|e| path_error(e, "list output directory", &self.dir))?;
+        let start = format!("{}-", self.prefix);
+        Ok(entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                name.to_str()?.strip_prefix(&start)?.parse::<u32>().ok()
+            })
+            .max()
+            .unwrap_or(0))
+    }
+}
+
+/// Directory of one run and the files written to it.
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    dir: PathBuf,
+    formats: Vec<OutputFormat>,
+    compression: Compression,
+    files: Vec<PathBuf>,
+}
+
+impl RunOutput {
+    /// Directory of the run.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Files written so far, in order.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Path of the artifact `name` in the run directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Write the spikes and weight log of a run of `sim` in every
+    /// configured format.
+    pub fn write_results(
+        &mut self,
+        sim: &Simulation,
+        spikes: &[Spike],
+        weights: &[WeightSample],
+        stop_reason: Option<StopReason>,
+    ) -> io::Result<()> {
+        for format in self.formats.clone() {
+            match format {
+                OutputFormat::Csv => {
+                    self.write("spikes.csv", true, |path| write_spikes_csv(spikes, path))?;
+                    self.write("weights.csv", true, |path| {
+                        let mut writer = compression::create(path)?;
+                        sim.write_weights_to_csv(weights, &mut writer)?;
+                        writer.finish()?;
+                        Ok(())
+                    })?;
+                }
+                OutputFormat::Json => {
+                    let results =
+                        RunResults::new(sim, spikes.to_vec(), weights.to_vec(), stop_reason);
+                    self.write("results.json", true, |path| results.write_json(path))?;
+                }
+                OutputFormat::Npz => {
+                    self.write("results.npz", false, |path| {
+                        write_results_npz(sim, spikes, None, path)
+                    })?;
+                }
+                OutputFormat::Parquet => {
+                    self.write("spikes.parquet", false, |path| {
+                        write_spikes_parquet(spikes, path)
+                    })?;
+                    self.write("weights.parquet", false, |path| {
+                        write_weights_parquet(weights, path)
+                    })?;
+                }
+                OutputFormat::Spk => {
+                    let resolution = sim.config().dt;
+                    self.write("spikes.spk", true, |path| {
+                        write_spikes_binary(spikes, resolution, path)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A recorder spilling spikes to `spikes.csv` or, for the binary
+    /// format, `spikes.bin` in the run directory, compressed as configured.
+    pub fn spike_recorder(
+        &mut self,
+        capacity: usize,
+        format: SpillFormat,
+    ) -> io::Result<SpikeRecorder> {
+        let name = match format {
+            SpillFormat::Csv => "spikes.csv",
+            SpillFormat::Binary => "spikes.bin",
+        };
+        let path = self.compressed_path(name);
+        let recorder = SpikeRecorder::create(&path, capacity, format)
+            .map_err(|e| path_error(e, "create", &path))?;
+        self.files.push(path);
+        Ok(recorder)
+    }
+
+    /// Path of `name`, with the compression extension added.
+    fn compressed_path(&self, name: &str) -> PathBuf {
+        match self.compression.extension() {
+            Some(extension) => self.path(&format!("{name}.{extension}")),
+            None => self.path(name),
+        }
+    }
+
+    /// Write the artifact `name` with `write` and remember its path.
+    fn write<F>(&mut self, name: &str, compressed: bool, write: F) -> io::Result<()>
+    where
+        F: FnOnce(&Path) -> io::Result<()>,
+    {
+        let path = if compressed {
+            self.compressed_path(name)
+        } else {
+            self.path(name)
+        };
+        write(&path).map_err(|e| path_error(e, "write", &path))?;
+        self.files.push(path);
+        Ok(())
+    }
+}
+
+/// `error` with the failed action and its path in the message.
+fn path_error(error: io::Error, action: &str, path: &Path) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        format!("cannot {action} `{}`: {error}", path.display()),
+    )
+}
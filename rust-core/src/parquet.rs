@@ -0,0 +1,316 @@
+//! parquet.rs
+//!
+//! Apache Parquet export of spike and weight logs.
+//!
+//! CSV stores every number as text, so pandas, polars and DuckDB must parse
+//! and guess the type of each column. Parquet files carry a typed schema
+//! and columnar binary data that these tools load directly, typically an
+//! order of magnitude faster. The writers here produce uncompressed,
+//! PLAIN-encoded files with required (non-null) columns, split into row
+//! groups of at most `ROW_GROUP_SIZE` rows:
+//!
+//! - spikes: `neuron_id` (uint64), `time_ms` (double)
+//! - weights: `time_ms` (double), `pre_neuron` (uint64),
+//!   `post_neuron` (uint64), `weight` (double)
+//!
+//! The column names match the CSV writers, so existing analysis code only
+//! needs to change the loader, e.g. `pandas.read_parquet("spikes.parquet")`.
+
+use crate::simulation::WeightSample;
+use crate::spike::Spike;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Maximum number of rows in a row group.
+pub const ROW_GROUP_SIZE: usize = 1 << 20;
+
+/// Write `spikes` as a Parquet file with `neuron_id` and `time_ms` columns.
+pub fn write_spikes_parquet<P: AsRef<Path>>(spikes: &[Spike], path: P) -> io::Result<()> {
+    let columns = [
+        Column::uint64("neuron_id", |i| spikes[i].neuron_id as u64),
+        Column::double("time_ms", |i| spikes[i].time.0),
+    ];
+    write_table(&columns, spikes.len(), path)
+}
+
+/// Write a weight log, as returned by `Simulation::run`, as a Parquet file
+/// with `time_ms`, `pre_neuron`, `post_neuron` and `weight` columns.
+pub fn write_weights_parquet<P: AsRef<Path>>(weights: &[WeightSample], path: P) -> io::Result<()> {
+    let columns = [
+        Column::double("time_ms", |i| weights[i].0 .0),
+        Column::uint64("pre_neuron", |i| weights[i].1 as u64),
+        Column::uint64("post_neuron", |i| weights[i].2 as u64),
+        Column::double("weight", |i| weights[i].3),
+    ];
+    write_table(&columns, weights.len(), path)
+}
+
+/// Physical types, as numbered by the Parquet format.
+const TYPE_INT64: i32 = 2;
+const TYPE_DOUBLE: i32 = 5;
+/// `UINT_64` converted type
+const CONVERTED_UINT64: i32 = 14;
+
+/// A required column of 8-byte values.
+struct Column<'a> {
+    name: &'static str,
+    physical_type: i32,
+    unsigned: bool,
+    /// Little-endian bytes of the value in a row
+    value: Box<dyn Fn(usize) -> [u8; 8] + 'a>,
+}
+
+impl<'a> Column<'a> {
+    fn uint64(name: &'static str, value: impl Fn(usize) -> u64 + 'a) -> Self {
+        Self {
+            name,
+            physical_type: TYPE_INT64,
+            unsigned: true,
+            value: Box::new(move |i| value(i).to_le_bytes()),
+        }
+    }
+
+    fn double(name: &'static str, value: impl Fn(usize) -> f64 + 'a) -> Self {
+        Self {
+            name,
+            physical_type: TYPE_DOUBLE,
+            unsigned: false,
+            value: Box::new(move |i| value(i).to_le_bytes()),
+        }
+    }
+}
+
+/// Location of a written column chunk.
+struct Chunk {
+    offset: u64,
+    size: u64,
+    rows: usize,
+}
+
+/// Write `rows` rows of `columns`, one data page per column chunk.
+fn write_table<P: AsRef<Path>>(columns: &[Column], rows: usize, path: P) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(b"PAR1")?;
+    let mut position = 4u64;
+
+    let mut row_groups = Vec::new();
+    let mut start = 0;
+    while start < rows {
+        let end = rows.min(start + ROW_GROUP_SIZE);
+        let mut chunks = Vec::with_capacity(columns.len());
+        for column in columns {
+            let data_size = ((end - start) * 8) as i32;
+            let mut header = Compact::default();
+            header.i32(1, 0); // DATA_PAGE
+            header.i32(2, data_size);
+            header.i32(3, data_size);
+            header.begin_struct(5);
+            header.i32(1, (end - start) as i32);
+            header.i32(2, 0); // PLAIN
+            header.i32(3, 3); // RLE definition levels
+            header.i32(4, 3); // RLE repetition levels
+            header.end_struct();
+            header.stop();
+
+            writer.write_all(&header.out)?;
+            for i in start..end {
+                writer.write_all(&(column.value)(i))?;
+            }
+            let size = header.out.len() as u64 + data_size as u64;
+            chunks.push(Chunk {
+                offset: position,
+                size,
+                rows: end - start,
+            });
+            position += size;
+        }
+        row_groups.push(chunks);
+        start = end;
+    }
+
+    let metadata = file_metadata(columns, rows, &row_groups);
+    writer.write_all(&metadata)?;
+    writer.write_all(&(metadata.len() as u32).to_le_bytes())?;
+    writer.write_all(b"PAR1")?;
+    writer.flush()
+}
+
+/// Thrift-encoded `FileMetaData` footer.
+fn file_metadata(columns: &[Column], rows: usize, row_groups: &[Vec<Chunk>]) -> Vec<u8> {
+    let mut meta = Compact::default();
+    meta.i32(1, 1);
+
+    // Schema: a root group followed by one leaf per column
+    meta.begin_list(2, STRUCT, columns.len() + 1);
+    meta.binary(4, b"schema");
+    meta.i32(5, columns.len() as i32);
+    meta.stop();
+    for column in columns {
+        meta.i32(1, column.physical_type);
+        meta.i32(3, 0); // REQUIRED
+        meta.binary(4, column.name.as_bytes());
+        if column.unsigned {
+            meta.i32(6, CONVERTED_UINT64);
+            // LogicalType INTEGER(64, unsigned)
+            meta.begin_struct(10);
+            meta.begin_struct(10);
+            meta.byte(1, 64);
+            meta.bool(2, false);
+            meta.end_struct();
+            meta.end_struct();
+        }
+        meta.stop();
+    }
+    meta.end_list();
+
+    meta.i64(3, rows as i64);
+    meta.begin_list(4, STRUCT, row_groups.len());
+    for chunks in row_groups {
+        meta.begin_list(1, STRUCT, chunks.len());
+        for (column, chunk) in columns.iter().zip(chunks) {
+            meta.i64(2, chunk.offset as i64);
+            meta.begin_struct(3);
+            meta.i32(1, column.physical_type);
+            meta.begin_list(2, I32, 1);
+            meta.list_i32(0); // PLAIN
+            meta.end_list();
+            meta.begin_list(3, BINARY, 1);
+            meta.list_binary(column.name.as_bytes());
+            meta.end_list();
+            meta.i32(4, 0); // UNCOMPRESSED
+            meta.i64(5, chunk.rows as i64);
+            meta.i64(6, chunk.size as i64);
+            meta.i64(7, chunk.size as i64);
+            meta.i64(9, chunk.offset as i64);
+            meta.end_struct();
+            meta.stop();
+        }
+        meta.end_list();
+        meta.i64(2, chunks.iter().map(|c| c.size as i64).sum());
+        meta.i64(3, chunks.first().map_or(0, |c| c.rows as i64));
+        meta.stop();
+    }
+    meta.end_list();
+    meta.binary(
+        6,
+        concat!("neuromorphic_core version ", env!("CARGO_PKG_VERSION")).as_bytes(),
+    );
+    meta.stop();
+    meta.out
+}
+
+/// Thrift compact protocol encoder for the structures above.
+///
+/// Fields must be written in increasing id order within each struct.
+#[derive(Default)]
+struct Compact {
+    out: Vec<u8>,
+    /// Id of the last field written in the current struct
+    last_id: i16,
+    /// `last_id` of enclosing structs and lists
+    stack: Vec<i16>,
+}
+
+const BOOL_TRUE: u8 = 1;
+const BOOL_FALSE: u8 = 2;
+const BYTE: u8 = 3;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+impl Compact {
+    fn field(&mut self, id: i16, kind: u8) {
+        let delta = id - self.last_id;
+        if (1..=15).contains(&delta) {
+            self.out.push((delta as u8) << 4 | kind);
+        } else {
+            self.out.push(kind);
+            self.varint(zigzag(id as i64));
+        }
+        self.last_id = id;
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.out.push(value as u8);
+    }
+
+    fn bool(&mut self, id: i16, value: bool) {
+        self.field(id, if value { BOOL_TRUE } else { BOOL_FALSE });
+    }
+
+    fn byte(&mut self, id: i16, value: u8) {
+        self.field(id, BYTE);
+        self.out.push(value);
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, I32);
+        self.varint(zigzag(value as i64));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, I64);
+        self.varint(zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, BINARY);
+        self.list_binary(value);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, STRUCT);
+        self.stack.push(self.last_id);
+        self.last_id = 0;
+    }
+
+    fn end_struct(&mut self) {
+        self.out.push(0);
+        self.last_id = self.stack.pop().unwrap_or(0);
+    }
+
+    /// Start a list field of `len` elements of type `kind`. Struct elements
+    /// follow as field sequences each ended by `stop`; others are added
+    /// with `list_*`.
+    fn begin_list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, LIST);
+        self.stack.push(self.last_id);
+        if len < 15 {
+            self.out.push((len as u8) << 4 | kind);
+        } else {
+            self.out.push(0xF0 | kind);
+            self.varint(len as u64);
+        }
+        self.last_id = 0;
+    }
+
+    fn end_list(&mut self) {
+        self.last_id = self.stack.pop().unwrap_or(0);
+    }
+
+    fn list_i32(&mut self, value: i32) {
+        self.varint(zigzag(value as i64));
+    }
+
+    fn list_binary(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.out.extend_from_slice(value);
+    }
+
+    /// End a struct that is a list element or the top-level struct.
+    fn stop(&mut self) {
+        self.out.push(0);
+        self.last_id = 0;
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
@@ -0,0 +1,221 @@
+//! pipeline.rs
+//!
+//! End-to-end classification pipelines.
+//!
+//! Benchmarking a spiking network on a labeled dataset always follows the
+//! same steps: encode each sample into input spikes, run a fresh network on
+//! them, decode the output spikes into a feature vector, train a readout on
+//! the training split, and score it on the test split. `Pipeline` wires
+//! these stages together from three closures so only the parts that differ
+//! between experiments have to be written.
+
+use crate::monitor::SpikeMonitor;
+use crate::readout::LogisticReadout;
+use crate::rng::Rng;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+
+/// Analog samples with integer class labels.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabeledDataset {
+    /// Input values of each sample
+    pub samples: Vec<Vec<f64>>,
+    /// Class of each sample
+    pub labels: Vec<usize>,
+}
+
+impl LabeledDataset {
+    /// Create a dataset; `samples` and `labels` must have equal length.
+    pub fn new(samples: Vec<Vec<f64>>, labels: Vec<usize>) -> Self {
+        assert_eq!(
+            samples.len(),
+            labels.len(),
+            "Every sample needs exactly one label"
+        );
+        Self { samples, labels }
+    }
+
+    /// Number of samples.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether the dataset contains no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Number of classes, i.e. one more than the largest label.
+    pub fn num_classes(&self) -> usize {
+        self.labels.iter().max().map_or(0, |&m| m + 1)
+    }
+
+    /// Samples at `indices`, in that order.
+    pub fn subset(&self, indices: &[usize]) -> Self {
+        Self {
+            samples: indices.iter().map(|&i| self.samples[i].clone()).collect(),
+            labels: indices.iter().map(|&i| self.labels[i]).collect(),
+        }
+    }
+
+    /// Shuffle and split into `(train, test)`, with a fraction
+    /// `test_fraction` of the samples in the test split.
+    pub fn split(&self, test_fraction: f64, rng: &mut Rng) -> (Self, Self) {
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        rng.shuffle(&mut indices);
+        let num_test = (self.len() as f64 * test_fraction.clamp(0.0, 1.0)).round() as usize;
+        let (test, train) = indices.split_at(num_test);
+        (self.subset(train), self.subset(test))
+    }
+}
+
+/// Classification scores.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Evaluation {
+    /// Fraction of samples classified correctly
+    pub accuracy: f64,
+    /// `confusion[true][predicted]` sample counts
+    pub confusion: Vec<Vec<usize>>,
+}
+
+impl Evaluation {
+    /// Score `predictions` against `labels` over `num_classes` classes.
+    pub fn compute(predictions: &[usize], labels: &[usize], num_classes: usize) -> Self {
+        let mut confusion = vec![vec![0; num_classes]; num_classes];
+        let mut correct = 0;
+        for (&p, &l) in predictions.iter().zip(labels) {
+            if p == l {
+                correct += 1;
+            }
+            if p < num_classes && l < num_classes {
+                confusion[l][p] += 1;
+            }
+        }
+        let n = predictions.len().min(labels.len());
+        let accuracy = if n == 0 {
+            0.0
+        } else {
+            correct as f64 / n as f64
+        };
+        Self {
+            accuracy,
+            confusion,
+        }
+    }
+
+    /// Fraction of samples of each class that were classified correctly.
+    pub fn recall(&self) -> Vec<f64> {
+        self.confusion
+            .iter()
+            .enumerate()
+            .map(|(c, row)| {
+                let total: usize = row.iter().sum();
+                if total == 0 {
+                    0.0
+                } else {
+                    row[c] as f64 / total as f64
+                }
+            })
+            .collect()
+    }
+}
+
+/// Result of training and testing a pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineResult {
+    /// Readout trained on the training split
+    pub readout: LogisticReadout,
+    /// Scores on the training split
+    pub train: Evaluation,
+    /// Scores on the test split
+    pub test: Evaluation,
+}
+
+/// Stage turning one sample into input spikes.
+type SampleEncoder<'a> = Box<dyn Fn(&[f64], &mut Rng) -> Vec<Spike> + 'a>;
+
+/// Stage turning recorded spikes into a feature vector.
+type SpikeDecoder<'a> = Box<dyn Fn(&[Spike]) -> Vec<f64> + 'a>;
+
+/// Encoder → simulation → decoder → readout classification pipeline.
+pub struct Pipeline<'a> {
+    encode: SampleEncoder<'a>,
+    network: Box<dyn Fn() -> Simulation + 'a>,
+    decode: SpikeDecoder<'a>,
+    /// Seed for the encoders
+    pub seed: u64,
+    /// Readout training epochs
+    pub epochs: usize,
+    /// Readout learning rate
+    pub learning_rate: f64,
+    /// Readout L2 penalty
+    pub l2: f64,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Create a pipeline from its stages.
+    ///
+    /// `encode` turns one sample into input spikes, `network` builds the
+    /// fresh simulation each sample is run on, and `decode` turns the
+    /// recorded spikes into a feature vector for the readout.
+    pub fn new<E, N, D>(encode: E, network: N, decode: D) -> Self
+    where
+        E: Fn(&[f64], &mut Rng) -> Vec<Spike> + 'a,
+        N: Fn() -> Simulation + 'a,
+        D: Fn(&[Spike]) -> Vec<f64> + 'a,
+    {
+        Self {
+            encode: Box::new(encode),
+            network: Box::new(network),
+            decode: Box::new(decode),
+            seed: 0,
+            epochs: 200,
+            learning_rate: 0.1,
+            l2: 0.0,
+        }
+    }
+
+    /// Feature vector of every sample in `data`.
+    pub fn features(&self, data: &LabeledDataset) -> Vec<Vec<f64>> {
+        let mut rng = Rng::new(self.seed);
+        data.samples
+            .iter()
+            .map(|sample| {
+                let input = (self.encode)(sample, &mut rng);
+                let mut simulation = (self.network)();
+                simulation.inject_spikes(&input);
+                // Only the spikes are decoded, so skip the weight log
+                let monitor = SpikeMonitor::new();
+                simulation.add_monitor(monitor.clone());
+                simulation.run_monitored(|_, _| 0.0, &[]);
+                (self.decode)(&monitor.spikes())
+            })
+            .collect()
+    }
+
+    /// Train a readout on `train` and score it on both splits.
+    pub fn run(&self, train: &LabeledDataset, test: &LabeledDataset) -> PipelineResult {
+        let num_classes = train.num_classes().max(test.num_classes());
+        let train_features = self.features(train);
+        let test_features = self.features(test);
+        let readout = LogisticReadout::fit(
+            &train_features,
+            &train.labels,
+            num_classes,
+            self.epochs,
+            self.learning_rate,
+            self.l2,
+        );
+        let evaluate = |features: &[Vec<f64>], labels: &[usize]| {
+            let predictions: Vec<usize> = features.iter().map(|x| readout.predict(x)).collect();
+            Evaluation::compute(&predictions, labels, num_classes)
+        };
+        PipelineResult {
+            train: evaluate(&train_features, &train.labels),
+            test: evaluate(&test_features, &test.labels),
+            readout,
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! plot.rs
+//!
+//! Quick-look SVG and PNG plots (feature `plot`).
+//!
+//! Checking that a run behaved sensibly usually means exporting CSV and
+//! plotting it in Python. For a first look that round trip is unnecessary:
+//! this module renders spike rasters, voltage traces and weight evolution
+//! straight to self-contained SVG files that any browser can open, or to
+//! PNG images. The plots are deliberately plain — axes, ticks, a title and
+//! the data — and are no replacement for publication figures.
+//!
+//! A plot is drawn once as a list of shapes and then rendered in the
+//! format chosen by the file extension. PNG output is rasterized without
+//! anti-aliasing and its text uses a built-in 5×7 pixel font of digits,
+//! capital letters and common punctuation; lowercase letters are drawn as
+//! capitals.
+
+use crate::compression::{self, Compression};
+use crate::monitor::WeightMonitor;
+use crate::png;
+use crate::probe::VoltageProbe;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+/// RGB color.
+type Color = [u8; 3];
+
+const BLACK: Color = [0, 0, 0];
+const WHITE: Color = [255, 255, 255];
+
+/// Line colors cycled through by multi-series plots.
+const PALETTE: [Color; 8] = [
+    [0x1f, 0x77, 0xb4],
+    [0xd6, 0x27, 0x28],
+    [0x2c, 0xa0, 0x2c],
+    [0xff, 0x7f, 0x0e],
+    [0x94, 0x67, 0xbd],
+    [0x8c, 0x56, 0x4b],
+    [0xe3, 0x77, 0xc2],
+    [0x17, 0xbe, 0xcf],
+];
+
+/// Margins around the plotting area, in pixels.
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 45.0;
+
+/// Image format of a plot file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlotFormat {
+    /// Scalable vector graphics
+    #[default]
+    Svg,
+    /// 8-bit RGB PNG image
+    Png,
+}
+
+impl PlotFormat {
+    /// Format implied by the extension of `path`, ignoring a compression
+    /// extension: `.png` for PNG, SVG otherwise.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let name = if Compression::from_path(path) == Compression::None {
+            Some(path)
+        } else {
+            path.file_stem().map(Path::new)
+        };
+        let extension = name
+            .and_then(|n| n.extension())
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png") => Self::Png,
+            _ => Self::Svg,
+        }
+    }
+}
+
+/// Size and title of a plot.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlotOptions {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Title drawn above the plot
+    pub title: Option<String>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 400,
+            title: None,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// Default size with a title.
+    pub fn titled(title: &str) -> Self {
+        Self {
+            title: Some(title.to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Write a spike raster of neurons `0..num_neurons`, one tick per spike.
+///
+/// The format follows the extension of `path` (see `PlotFormat::from_path`)
+/// and the file is compressed like those of `compression::create`.
+pub fn raster_plot<P: AsRef<Path>>(
+    spikes: &[Spike],
+    num_neurons: usize,
+    path: P,
+    options: &PlotOptions,
+) -> io::Result<()> {
+    let t_max = spikes.iter().map(|s| s.time.0).fold(0.0, f64::max);
+    let mut canvas = Canvas::new(
+        options,
+        (0.0, t_max.max(1.0)),
+        (0.0, num_neurons.max(1) as f64),
+    );
+    canvas.axes("Time (ms)", "Neuron");
+    let tick = (canvas.plot_height() / num_neurons.max(1) as f64).clamp(1.0, 6.0);
+    for s in spikes {
+        let x = canvas.x(s.time.0);
+        let y = canvas.y(s.neuron_id as f64 + 0.5);
+        canvas.line((x, y - tick / 2.0), (x, y + tick / 2.0), BLACK);
+    }
+    canvas.save(path)
+}
+
+/// Write the membrane potential traces recorded by `probe`, like
+/// `raster_plot`.
+pub fn voltage_traces_plot<P: AsRef<Path>>(
+    probe: &VoltageProbe,
+    path: P,
+    options: &PlotOptions,
+) -> io::Result<()> {
+    let series: Vec<(String, Vec<f64>)> = probe
+        .neurons()
+        .into_iter()
+        .filter_map(|n| Some((format!("neuron {n}"), probe.trace(n)?)))
+        .collect();
+    lines_plot(&probe.times(), &series, "Membrane potential", path, options)
+}
+
+/// Write the weight time series recorded by `monitor`, one line per
+/// monitored synapse, like `raster_plot`.
+pub fn weight_evolution_plot<P: AsRef<Path>>(
+    monitor: &WeightMonitor,
+    path: P,
+    options: &PlotOptions,
+) -> io::Result<()> {
+    let samples = monitor.samples();
+    let times: Vec<Milliseconds> = samples.iter().map(|(t, _)| *t).collect();
+    let count = samples.iter().map(|(_, w)| w.len()).max().unwrap_or(0);
+    let series: Vec<(String, Vec<f64>)> = (0..count)
+        .map(|k| (format!("synapse {k}"), monitor.trace(k)))
+        .collect();
+    lines_plot(&times, &series, "Weight", path, options)
+}
+
+/// Write named series sampled at common `times` as a line plot, like
+/// `raster_plot`. NaN values break a line. A legend is drawn for up to
+/// eight series.
+pub fn lines_plot<P: AsRef<Path>>(
+    times: &[Milliseconds],
+    series: &[(String, Vec<f64>)],
+    y_label: &str,
+    path: P,
+    options: &PlotOptions,
+) -> io::Result<()> {
+    let (t_lo, t_hi) = bounds(times.iter().map(|t| t.0));
+    let (v_lo, v_hi) = bounds(series.iter().flat_map(|(_, v)| v.iter().copied()));
+    let pad = 0.05 * (v_hi - v_lo);
+    let mut canvas = Canvas::new(options, (t_lo, t_hi), (v_lo - pad, v_hi + pad));
+    canvas.axes("Time (ms)", y_label);
+
+    for (k, (_, values)) in series.iter().enumerate() {
+        let color = PALETTE[k % PALETTE.len()];
+        let mut points = Vec::new();
+        for (t, v) in times.iter().zip(values) {
+            if v.is_finite() {
+                points.push((canvas.x(t.0), canvas.y(*v)));
+            } else if !points.is_empty() {
+                canvas.shapes.push(Shape::Polyline(std::mem::take(&mut points), color));
+            }
+        }
+        if !points.is_empty() {
+            canvas.shapes.push(Shape::Polyline(points, color));
+        }
+    }
+    if series.len() <= PALETTE.len() {
+        for (k, (name, _)) in series.iter().enumerate() {
+            let y = MARGIN_TOP + 14.0 * (k as f64 + 1.0);
+            let x = canvas.width - MARGIN_RIGHT - 110.0;
+            canvas.text((x, y), name, Text::SMALL, PALETTE[k]);
+        }
+    }
+    canvas.save(path)
+}
+
+/// Smallest and largest finite value, widened if empty or degenerate.
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (lo, hi) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lo > hi {
+        (0.0, 1.0)
+    } else if lo == hi {
+        (lo - 0.5, hi + 0.5)
+    } else {
+        (lo, hi)
+    }
+}
+
+/// "Nice" tick positions covering `[lo, hi]` with about `target` ticks.
+fn ticks(lo: f64, hi: f64, target: usize) -> Vec<f64> {
+    let raw = (hi - lo) / target.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&s| s >= raw)
+        .unwrap_or(10.0 * magnitude);
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    (first..=last).map(|k| k as f64 * step).collect()
+}
+
+/// Tick value with floating-point noise rounded away.
+fn tick_label(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+/// Escape text for inclusion in SVG.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `color` as an SVG color.
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Horizontal alignment of text relative to its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Font size, alignment and direction of a text.
+#[derive(Debug, Clone, Copy)]
+struct Text {
+    size: f64,
+    anchor: Anchor,
+    /// Rotated by 90° to read upwards
+    vertical: bool,
+}
+
+impl Text {
+    const SMALL: Self = Self {
+        size: 11.0,
+        anchor: Anchor::Start,
+        vertical: false,
+    };
+
+    fn size(self, size: f64) -> Self {
+        Self { size, ..self }
+    }
+
+    fn anchor(self, anchor: Anchor) -> Self {
+        Self { anchor, ..self }
+    }
+}
+
+/// Element of a plot, in pixel coordinates.
+#[derive(Debug, Clone)]
+enum Shape {
+    Line((f64, f64), (f64, f64), Color),
+    Polyline(Vec<(f64, f64)>, Color),
+    /// Outline of the rectangle between two corners
+    Frame((f64, f64), (f64, f64)),
+    /// Text with its baseline starting, centered or ending at the point
+    Text((f64, f64), String, Text, Color),
+}
+
+/// Plot with a linear mapping from data to pixel coordinates.
+struct Canvas {
+    width: f64,
+    height: f64,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    shapes: Vec<Shape>,
+}
+
+impl Canvas {
+    fn new(options: &PlotOptions, x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        let mut canvas = Self {
+            width: options.width as f64,
+            height: options.height as f64,
+            x_range,
+            y_range,
+            shapes: Vec::new(),
+        };
+        if let Some(title) = &options.title {
+            let at = (canvas.width / 2.0, 20.0);
+            let style = Text::SMALL.size(14.0).anchor(Anchor::Middle);
+            canvas.text(at, title, style, BLACK);
+        }
+        canvas
+    }
+
+    fn plot_width(&self) -> f64 {
+        (self.width - MARGIN_LEFT - MARGIN_RIGHT).max(1.0)
+    }
+
+    fn plot_height(&self) -> f64 {
+        (self.height - MARGIN_TOP - MARGIN_BOTTOM).max(1.0)
+    }
+
+    fn x(&self, value: f64) -> f64 {
+        let (lo, hi) = self.x_range;
+        MARGIN_LEFT + (value - lo) / (hi - lo) * self.plot_width()
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let (lo, hi) = self.y_range;
+        MARGIN_TOP + (1.0 - (value - lo) / (hi - lo)) * self.plot_height()
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: Color) {
+        self.shapes.push(Shape::Line(from, to, color));
+    }
+
+    fn text(&mut self, at: (f64, f64), text: &str, style: Text, color: Color) {
+        self.shapes.push(Shape::Text(at, text.to_string(), style, color));
+    }
+
+    /// Draw the frame, tick marks with labels, and axis labels.
+    fn axes(&mut self, x_label: &str, y_label: &str) {
+        let (left, top) = (MARGIN_LEFT, MARGIN_TOP);
+        let (right, bottom) = (left + self.plot_width(), top + self.plot_height());
+        self.shapes.push(Shape::Frame((left, top), (right, bottom)));
+        let label = Text::SMALL.anchor(Anchor::Middle);
+        for t in ticks(self.x_range.0, self.x_range.1, 8) {
+            let x = self.x(t);
+            self.line((x, bottom), (x, bottom + 4.0), BLACK);
+            self.text((x, bottom + 16.0), &tick_label(t).to_string(), label, BLACK);
+        }
+        for t in ticks(self.y_range.0, self.y_range.1, 6) {
+            let y = self.y(t);
+            self.line((left - 4.0, y), (left, y), BLACK);
+            let at = (left - 6.0, y + 4.0);
+            let style = Text::SMALL.anchor(Anchor::End);
+            self.text(at, &tick_label(t).to_string(), style, BLACK);
+        }
+        let style = label.size(12.0);
+        self.text(((left + right) / 2.0, self.height - 8.0), x_label, style, BLACK);
+        let style = Text {
+            vertical: true,
+            ..style
+        };
+        self.text((14.0, (top + bottom) / 2.0), y_label, style, BLACK);
+    }
+
+    /// The plot as an SVG document.
+    fn svg(&self) -> String {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}"><rect width="100%" height="100%" fill="white"/>"#,
+            w = self.width,
+            h = self.height
+        );
+        for shape in &self.shapes {
+            let _ = match shape {
+                Shape::Line((x1, y1), (x2, y2), color) => write!(
+                    svg,
+                    r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" stroke="{}" stroke-width="1"/>"#,
+                    hex(*color)
+                ),
+                Shape::Polyline(points, color) => {
+                    let points: Vec<String> =
+                        points.iter().map(|(x, y)| format!("{x:.2},{y:.2}")).collect();
+                    write!(
+                        svg,
+                        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.2"/>"#,
+                        points.join(" "),
+                        hex(*color)
+                    )
+                }
+                Shape::Frame((x1, y1), (x2, y2)) => write!(
+                    svg,
+                    r#"<rect x="{x1}" y="{y1}" width="{:.1}" height="{:.1}" fill="none" stroke="black"/>"#,
+                    x2 - x1,
+                    y2 - y1
+                ),
+                Shape::Text((x, y), text, style, color) => {
+                    let anchor = match style.anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+                    let rotate = if style.vertical {
+                        format!(r#" transform="rotate(-90 {x:.1} {y:.1})""#)
+                    } else {
+                        String::new()
+                    };
+                    write!(
+                        svg,
+                        r#"<text x="{x:.1}" y="{y:.1}" font-size="{}" text-anchor="{anchor}" fill="{}"{rotate}>{}</text>"#,
+                        style.size,
+                        hex(*color),
+                        escape(text)
+                    )
+                }
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// The plot as RGB pixels in row-major order.
+    fn pixels(&self) -> Vec<u8> {
+        let mut raster = Raster {
+            width: self.width as usize,
+            height: self.height as usize,
+            pixels: WHITE.repeat(self.width as usize * self.height as usize),
+        };
+        for shape in &self.shapes {
+            match shape {
+                Shape::Line(from, to, color) => raster.line(*from, *to, *color),
+                Shape::Polyline(points, color) => {
+                    for pair in points.windows(2) {
+                        raster.line(pair[0], pair[1], *color);
+                    }
+                    if let [point] = points[..] {
+                        raster.line(point, point, *color);
+                    }
+                }
+                Shape::Frame((x1, y1), (x2, y2)) => {
+                    raster.line((*x1, *y1), (*x2, *y1), BLACK);
+                    raster.line((*x2, *y1), (*x2, *y2), BLACK);
+                    raster.line((*x2, *y2), (*x1, *y2), BLACK);
+                    raster.line((*x1, *y2), (*x1, *y1), BLACK);
+                }
+                Shape::Text(at, text, style, color) => raster.text(*at, text, *style, *color),
+            }
+        }
+        raster.pixels
+    }
+
+    /// Write the plot to `path` in the format its extension implies.
+    fn save<P: AsRef<Path>>(self, path: P) -> io::Result<()> {
+        let data = match PlotFormat::from_path(&path) {
+            PlotFormat::Svg => self.svg().into_bytes(),
+            PlotFormat::Png => {
+                png::encode_rgb(self.width as usize, self.height as usize, &self.pixels())?
+            }
+        };
+        compression::write(path, data)
+    }
+}
+
+/// RGB image being drawn on.
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// Color the pixel containing `(x, y)`, if it is inside the image.
+    fn set(&mut self, (x, y): (f64, f64), color: Color) {
+        let (x, y) = (x.floor(), y.floor());
+        if x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height {
+            let at = 3 * (y as usize * self.width + x as usize);
+            self.pixels[at..at + 3].copy_from_slice(&color);
+        }
+    }
+
+    /// Draw a one-pixel line.
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), color: Color) {
+        let length = (x2 - x1).abs().max((y2 - y1).abs());
+        if !length.is_finite() {
+            return;
+        }
+        // Clip far-off points instead of stepping through them
+        let steps = length.ceil().clamp(1.0, 1e5) as usize;
+        for i in 0..=steps {
+            let f = i as f64 / steps as f64;
+            self.set((x1 + f * (x2 - x1), y1 + f * (y2 - y1)), color);
+        }
+    }
+
+    /// Draw `text` in the built-in font, scaled up for large sizes.
+    fn text(&mut self, (x, y): (f64, f64), text: &str, style: Text, color: Color) {
+        let scale = if style.size > 12.0 { 2.0 } else { 1.0 };
+        let advance = 6.0 * scale;
+        let length = text.chars().count() as f64 * advance - scale;
+        let start = match style.anchor {
+            Anchor::Start => 0.0,
+            Anchor::Middle => -length / 2.0,
+            Anchor::End => -length,
+        };
+        for (k, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0x10 >> col) == 0 {
+                        continue;
+                    }
+                    for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                        if scale == 1.0 && (dx, dy) != (0.0, 0.0) {
+                            continue;
+                        }
+                        // Offsets along the text and down from the baseline
+                        let along = start + k as f64 * advance + col as f64 * scale + dx;
+                        let down = (row as f64 - 7.0) * scale + dy;
+                        let point = if style.vertical {
+                            (x + down, y - along)
+                        } else {
+                            (x + along, y + down)
+                        };
+                        self.set(point, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rows of the 5×7 glyph of `c`, top first, with the leftmost pixel in bit
+/// 4. Lowercase letters map to capitals and unknown characters to `?`.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; 7],
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        'A' => [0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
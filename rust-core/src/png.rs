@@ -0,0 +1,97 @@
+//! png.rs
+//!
+//! Minimal PNG encoding.
+//!
+//! Receptive fields and plots are saved as PNG images, but the crate has no
+//! image or compression dependency. PNG only requires its image data to be
+//! a zlib stream, which may consist of uncompressed deflate blocks, so this
+//! module encodes images that way. The files are larger than those of an
+//! image library but are read by every viewer.
+
+use crate::util::{crc32, invalid_input};
+use std::io;
+
+/// Encode an 8-bit grayscale image with `pixels` in row-major order.
+///
+/// Fails with `InvalidInput` if the image has no pixels or is too large
+/// for PNG.
+pub(crate) fn encode_gray(width: usize, height: usize, pixels: &[u8]) -> io::Result<Vec<u8>> {
+    encode(width, height, 0, 1, pixels)
+}
+
+/// Encode an 8-bit RGB image with `pixels` in row-major order, three bytes
+/// per pixel.
+///
+/// Fails with `InvalidInput` if the image has no pixels or is too large
+/// for PNG.
+#[cfg(feature = "plot")]
+pub(crate) fn encode_rgb(width: usize, height: usize, pixels: &[u8]) -> io::Result<Vec<u8>> {
+    encode(width, height, 2, 3, pixels)
+}
+
+/// Encode an image of PNG color type `color_type` with `channels` bytes
+/// per pixel.
+fn encode(
+    width: usize,
+    height: usize,
+    color_type: u8,
+    channels: usize,
+    pixels: &[u8],
+) -> io::Result<Vec<u8>> {
+    if width == 0 || height == 0 {
+        return Err(invalid_input(format!(
+            "A PNG image needs at least one pixel, got {width}x{height}"
+        )));
+    }
+    let dimension = |n: usize| {
+        u32::try_from(n).map_err(|_| invalid_input("Image too large for PNG".to_string()))
+    };
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&dimension(width)?.to_be_bytes());
+    ihdr.extend_from_slice(&dimension(height)?.to_be_bytes());
+    // Bit depth 8, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+    // Every scanline starts with filter type 0 (none)
+    let stride = width * channels;
+    let mut raw = Vec::with_capacity((stride + 1) * height);
+    for row in pixels.chunks(stride).take(height) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+    while let Some(block) = blocks.next() {
+        zlib.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_chunk(&mut png, b"IHDR", &ihdr);
+    push_chunk(&mut png, b"IDAT", &zlib);
+    push_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+/// Append one PNG chunk with its length and CRC.
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(kind.iter().chain(data));
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Adler-32 checksum as used by zlib.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
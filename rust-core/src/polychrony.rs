@@ -0,0 +1,271 @@
+//! polychrony.rs
+//!
+//! Polychronous group detection.
+//!
+//! With axonal delays, STDP selects synapses whose delays make spikes from
+//! several neurons arrive at a common target at the same moment. The
+//! resulting time-locked but not synchronous firing patterns are the
+//! polychronous groups of Izhikevich (2006). This module searches the
+//! learned weight and delay structure for them: for every neuron and every
+//! choice of anchor neurons with strong synapses onto it, the anchors are
+//! fired so that their spikes arrive together, and activity is propagated
+//! along strong synapses, a neuron firing whenever enough strong inputs
+//! arrive within a coincidence window. Anchor sets that start a large
+//! enough cascade are reported as groups.
+
+use crate::spike::Spike;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+/// Search settings.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolychronyParams {
+    /// Synapses with at least this weight take part in propagation
+    pub weight_threshold: f64,
+    /// Coincident strong inputs needed to make a neuron fire; also the
+    /// number of anchor neurons
+    pub inputs_to_fire: usize,
+    /// Arrivals closer together than this count as coincident
+    pub coincidence_window: Milliseconds,
+    /// Minimum time between two spikes of one neuron
+    pub refractory: Milliseconds,
+    /// Smallest reported group, in spikes including the anchors
+    pub min_size: usize,
+    /// Smallest reported depth, the longest chain of causal links
+    pub min_depth: usize,
+    /// Propagation stops after this many spikes
+    pub max_spikes: usize,
+}
+
+impl Default for PolychronyParams {
+    fn default() -> Self {
+        Self {
+            weight_threshold: 0.9,
+            inputs_to_fire: 3,
+            coincidence_window: Milliseconds(1.0),
+            refractory: Milliseconds(5.0),
+            min_size: 5,
+            min_depth: 2,
+            max_spikes: 200,
+        }
+    }
+}
+
+/// A polychronous group found in the network.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolychronousGroup {
+    /// Spikes of the group in time order, starting at time zero
+    pub spikes: Vec<Spike>,
+    /// Indices of the anchor spikes in `spikes`
+    pub anchors: Vec<usize>,
+    /// Causal links as `(cause, effect)` indices into `spikes`
+    pub links: Vec<(usize, usize)>,
+    /// Longest chain of causal links from an anchor
+    pub depth: usize,
+}
+
+impl PolychronousGroup {
+    /// Number of spikes in the group.
+    pub fn size(&self) -> usize {
+        self.spikes.len()
+    }
+
+    /// Time from the first to the last spike.
+    pub fn duration(&self) -> Milliseconds {
+        match (self.spikes.first(), self.spikes.last()) {
+            (Some(a), Some(b)) => b.time - a.time,
+            _ => Milliseconds::ZERO,
+        }
+    }
+
+    /// Distinct neurons taking part, sorted.
+    pub fn neurons(&self) -> Vec<usize> {
+        let mut neurons: Vec<usize> = self.spikes.iter().map(|s| s.neuron_id).collect();
+        neurons.sort_unstable();
+        neurons.dedup();
+        neurons
+    }
+}
+
+/// Search `synapses` among `num_neurons` neurons for polychronous groups.
+///
+/// Every combination of `inputs_to_fire` strong inputs of every neuron is
+/// tried, so the cost grows quickly with in-degree; raise
+/// `weight_threshold` to keep the search tractable. Groups reached from
+/// different anchor sets are reported once.
+pub fn find_polychronous_groups(
+    synapses: &[Synapse],
+    num_neurons: usize,
+    params: &PolychronyParams,
+) -> Vec<PolychronousGroup> {
+    let mut incoming: Vec<Vec<(usize, Milliseconds)>> = vec![Vec::new(); num_neurons];
+    let mut outgoing: Vec<Vec<(usize, Milliseconds)>> = vec![Vec::new(); num_neurons];
+    for s in synapses {
+        if s.weight >= params.weight_threshold
+            && s.pre_neuron < num_neurons
+            && s.post_neuron < num_neurons
+            && s.pre_neuron != s.post_neuron
+        {
+            incoming[s.post_neuron].push((s.pre_neuron, s.delay));
+            outgoing[s.pre_neuron].push((s.post_neuron, s.delay));
+        }
+    }
+
+    let k = params.inputs_to_fire.max(1);
+    let mut seen = HashSet::new();
+    let mut groups = Vec::new();
+    for inputs in &incoming {
+        for combo in combinations(inputs.len(), k) {
+            let anchors: Vec<(usize, Milliseconds)> = combo.iter().map(|&c| inputs[c]).collect();
+            // Anchors sharing a neuron cannot fire together
+            let mut ids: Vec<usize> = anchors.iter().map(|a| a.0).collect();
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.len() < k {
+                continue;
+            }
+            let Some(group) = propagate(&anchors, &outgoing, params) else {
+                continue;
+            };
+            if group.size() >= params.min_size
+                && group.depth >= params.min_depth
+                && seen.insert(signature(&group))
+            {
+                groups.push(group);
+            }
+        }
+    }
+    groups
+}
+
+/// Fire `anchors` so that their spikes arrive simultaneously over the
+/// given delays, then propagate activity along `outgoing` strong synapses.
+fn propagate(
+    anchors: &[(usize, Milliseconds)],
+    outgoing: &[Vec<(usize, Milliseconds)>],
+    params: &PolychronyParams,
+) -> Option<PolychronousGroup> {
+    let latest = anchors.iter().map(|a| a.1 .0).fold(0.0, f64::max);
+    let mut spikes: Vec<Spike> = anchors
+        .iter()
+        .map(|&(n, d)| Spike::new(n, Milliseconds(latest - d.0)))
+        .collect();
+    spikes.sort_by(|a, b| a.time.0.total_cmp(&b.time.0));
+    let mut layer = vec![0usize; spikes.len()];
+    let mut links = Vec::new();
+
+    // Pending arrivals keyed by time; times are non-negative, so their bit
+    // patterns order like the values
+    let mut queue = BinaryHeap::new();
+    let schedule = |queue: &mut BinaryHeap<_>, spikes: &[Spike], k: usize| {
+        for &(post, delay) in &outgoing[spikes[k].neuron_id] {
+            let arrival = spikes[k].time + delay;
+            queue.push(Reverse((arrival.0.to_bits(), post, k)));
+        }
+    };
+    for k in 0..spikes.len() {
+        schedule(&mut queue, &spikes, k);
+    }
+    // Recent arrivals as `(neuron, time, causing spike)`
+    let mut arrivals: Vec<(usize, Milliseconds, usize)> = Vec::new();
+
+    while let Some(Reverse((bits, post, cause))) = queue.pop() {
+        if spikes.len() >= params.max_spikes {
+            break;
+        }
+        let time = Milliseconds(f64::from_bits(bits));
+        arrivals.retain(|&(_, t, _)| time - t <= params.coincidence_window);
+        arrivals.push((post, time, cause));
+        let refractory = spikes
+            .iter()
+            .any(|s| s.neuron_id == post && time - s.time < params.refractory);
+        if refractory {
+            continue;
+        }
+        let causes: Vec<usize> = arrivals
+            .iter()
+            .filter(|&&(n, _, _)| n == post)
+            .map(|&(_, _, c)| c)
+            .collect();
+        if causes.len() < params.inputs_to_fire.max(1) {
+            continue;
+        }
+        let effect = spikes.len();
+        spikes.push(Spike::new(post, time));
+        layer.push(1 + causes.iter().map(|&c| layer[c]).max().unwrap_or(0));
+        links.extend(causes.iter().map(|&c| (c, effect)));
+        arrivals.retain(|&(n, _, _)| n != post);
+        schedule(&mut queue, &spikes, effect);
+    }
+
+    let depth = layer.iter().copied().max().unwrap_or(0);
+    if depth == 0 {
+        return None;
+    }
+    // Propagated spikes can precede late anchors; put everything in time
+    // order and renumber the links
+    let mut order: Vec<usize> = (0..spikes.len()).collect();
+    order.sort_by(|&a, &b| spikes[a].time.0.total_cmp(&spikes[b].time.0));
+    let mut position = vec![0; spikes.len()];
+    for (new, &old) in order.iter().enumerate() {
+        position[old] = new;
+    }
+    Some(PolychronousGroup {
+        spikes: order.iter().map(|&k| spikes[k]).collect(),
+        anchors: (0..anchors.len()).map(|k| position[k]).collect(),
+        links: links
+            .into_iter()
+            .map(|(c, e)| (position[c], position[e]))
+            .collect(),
+        depth,
+    })
+}
+
+/// Identity of a group: its spikes relative to the first, rounded to 1 µs.
+fn signature(group: &PolychronousGroup) -> Vec<(usize, i64)> {
+    let start = group.spikes.first().map_or(0.0, |s| s.time.0);
+    let mut key: Vec<(usize, i64)> = group
+        .spikes
+        .iter()
+        .map(|s| (s.neuron_id, ((s.time.0 - start) * 1000.0).round() as i64))
+        .collect();
+    key.sort_unstable();
+    key
+}
+
+/// All `k`-element index combinations of `0..n` in lexicographic order,
+/// generated lazily since a neuron with many inputs has too many to hold.
+fn combinations(n: usize, k: usize) -> Combinations {
+    Combinations {
+        n,
+        next: (k <= n).then(|| (0..k).collect()),
+    }
+}
+
+/// Iterator behind `combinations`, holding the combination to yield next.
+struct Combinations {
+    n: usize,
+    next: Option<Vec<usize>>,
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let combo = self.next.take()?;
+        let k = combo.len();
+        if let Some(i) = (0..k).rev().find(|&i| combo[i] < self.n - k + i) {
+            let mut next = combo.clone();
+            next[i] += 1;
+            for j in i + 1..k {
+                next[j] = next[j - 1] + 1;
+            }
+            self.next = Some(next);
+        }
+        Some(combo)
+    }
+}
@@ -0,0 +1,133 @@
+//! population.rs
+//!
+//! Declarative population and projection descriptions.
+//!
+//! Following the PyNN model, a network is described as a set of
+//! `Population`s — named groups of neurons sharing one neuron model — and
+//! `Projection`s — bundles of synapses from one population to another with
+//! their own connection pattern and plasticity settings. `NetworkBuilder`
+//! turns these descriptions into concrete neurons and synapses.
+
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::stdp::STDPParams;
+use crate::units::Milliseconds;
+use crate::weights::WeightInit;
+
+/// A named group of neurons sharing a neuron model.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Population {
+    /// Population name, unique within a network
+    pub name: String,
+    /// Number of neurons
+    pub size: usize,
+    /// Parameters shared by every neuron in the population
+    pub params: NeuronParams,
+}
+
+impl Population {
+    /// Create a population description.
+    pub fn new(name: &str, size: usize, params: NeuronParams) -> Self {
+        Self {
+            name: name.to_string(),
+            size,
+            params,
+        }
+    }
+}
+
+/// How neurons of the source population connect to the target population.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Connector {
+    /// Every source neuron connects to every target neuron. Self-connections
+    /// are skipped when a population projects onto itself, unless the
+    /// builder allows them.
+    AllToAll,
+    /// Source neuron `i` connects to target neuron `i`; both populations
+    /// must have the same size. A population projecting onto itself gets
+    /// no autapses unless the builder allows self-connections.
+    OneToOne,
+    /// Explicit `(source, target)` pairs of population-local indices.
+    List(Vec<(usize, usize)>),
+}
+
+/// Distribution of axonal delays across the synapses of a projection.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DelayDistribution {
+    /// Every synapse has the same delay.
+    Constant(Milliseconds),
+    /// Delays drawn uniformly from `[min, max)`.
+    Uniform {
+        /// Smallest delay
+        min: Milliseconds,
+        /// Largest delay
+        max: Milliseconds,
+    },
+    /// Delays drawn from a normal distribution, clipped at zero.
+    Normal {
+        /// Mean delay
+        mean: Milliseconds,
+        /// Standard deviation
+        std_dev: Milliseconds,
+    },
+}
+
+impl DelayDistribution {
+    /// Draw one delay.
+    pub fn sample(&self, rng: &mut Rng) -> Milliseconds {
+        match *self {
+            DelayDistribution::Constant(d) => d,
+            DelayDistribution::Uniform { min, max } => Milliseconds(rng.uniform(min.0, max.0)),
+            DelayDistribution::Normal { mean, std_dev } => {
+                Milliseconds(rng.gaussian(mean.0, std_dev.0)).max(Milliseconds::ZERO)
+            }
+        }
+    }
+}
+
+/// A set of synapses from one population to another.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Projection {
+    /// Name of the source population
+    pub pre: String,
+    /// Name of the target population
+    pub post: String,
+    /// Connection pattern
+    pub connector: Connector,
+    /// Distribution of initial synaptic weights
+    pub weight: WeightInit,
+    /// STDP parameters for this projection; `None` keeps the synapses static
+    pub plasticity: Option<STDPParams>,
+    /// Axonal delay of each synapse
+    pub delay: DelayDistribution,
+}
+
+impl Projection {
+    /// Create a static projection between two named populations.
+    pub fn new<W: Into<WeightInit>>(pre: &str, post: &str, connector: Connector, weight: W) -> Self {
+        Self {
+            pre: pre.to_string(),
+            post: post.to_string(),
+            connector,
+            weight: weight.into(),
+            plasticity: None,
+            delay: DelayDistribution::Constant(Milliseconds::ZERO),
+        }
+    }
+
+    /// Make the projection plastic with its own STDP parameters.
+    pub fn with_plasticity(mut self, params: STDPParams) -> Self {
+        self.plasticity = Some(params);
+        self
+    }
+
+    /// Draw synaptic delays from `delay`.
+    pub fn with_delay(mut self, delay: DelayDistribution) -> Self {
+        self.delay = delay;
+        self
+    }
+}
@@ -0,0 +1,160 @@
+//! probe.rs
+//!
+//! Membrane voltage recording.
+//!
+//! Spike output alone says little about why a neuron did or did not fire.
+//! A `VoltageProbe` samples the membrane potential of selected neurons at a
+//! fixed simulated-time interval while the simulation runs. Like a
+//! `CancellationToken`, it is a cheap, cloneable handle around shared
+//! state: attach one clone to a `Simulation` and read the recorded traces
+//! from another after the run. It is one of the `Monitor`s a simulation
+//! can carry.
+
+use crate::compression;
+use crate::monitor::{sample_due, shift_selected, Monitor, StepView};
+use crate::units::Milliseconds;
+use crate::util::lock;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Shared handle recording the membrane potential of selected neurons.
+#[derive(Debug, Clone)]
+pub struct VoltageProbe {
+    state: Arc<Mutex<ProbeState>>,
+}
+
+/// Recording schedule and samples shared by all clones of a probe.
+#[derive(Debug)]
+struct ProbeState {
+    neurons: Vec<usize>,
+    /// Current index of each probed neuron; `None` once removed
+    targets: Vec<Option<usize>>,
+    interval: Milliseconds,
+    next_sample: Option<Milliseconds>,
+    times: Vec<Milliseconds>,
+    /// One row of potentials per sample, in `neurons` order
+    values: Vec<Vec<f64>>,
+}
+
+impl VoltageProbe {
+    /// Create a probe sampling `neurons` every `interval` of simulated time.
+    ///
+    /// An interval of zero or less samples every step.
+    pub fn new(neurons: Vec<usize>, interval: Milliseconds) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ProbeState {
+                targets: neurons.iter().copied().map(Some).collect(),
+                neurons,
+                interval,
+                next_sample: None,
+                times: Vec::new(),
+                values: Vec::new(),
+            })),
+        }
+    }
+
+    /// Probed neuron indices as given to `new`, in recording order. They
+    /// keep labelling the traces after neurons are removed from the
+    /// simulation.
+    pub fn neurons(&self) -> Vec<usize> {
+        self.lock().neurons.clone()
+    }
+
+    /// Times of the recorded samples.
+    pub fn times(&self) -> Vec<Milliseconds> {
+        self.lock().times.clone()
+    }
+
+    /// Recorded samples as `(time, potentials)`, with potentials in
+    /// `neurons()` order.
+    pub fn samples(&self) -> Vec<(Milliseconds, Vec<f64>)> {
+        let state = self.lock();
+        state
+            .times
+            .iter()
+            .copied()
+            .zip(state.values.iter().cloned())
+            .collect()
+    }
+
+    /// Membrane potential time series of `neuron`, or `None` if it is not
+    /// probed.
+    pub fn trace(&self, neuron: usize) -> Option<Vec<f64>> {
+        let state = self.lock();
+        let column = state.neurons.iter().position(|&n| n == neuron)?;
+        Some(state.values.iter().map(|row| row[column]).collect())
+    }
+
+    /// Number of recorded samples.
+    pub fn len(&self) -> usize {
+        self.lock().times.len()
+    }
+
+    /// Whether nothing has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard recorded samples and restart the sampling schedule.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.times.clear();
+        state.values.clear();
+        state.next_sample = None;
+    }
+
+    /// Write samples to a CSV file with one `time_ms` column followed by one
+    /// `v_<neuron>` column per probed neuron.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let state = self.lock();
+        let mut writer = compression::create(path)?;
+        write!(writer, "time_ms")?;
+        for n in &state.neurons {
+            write!(writer, ",v_{n}")?;
+        }
+        writeln!(writer)?;
+        for (t, row) in state.times.iter().zip(&state.values) {
+            write!(writer, "{}", t.0)?;
+            for v in row {
+                write!(writer, ",{v}")?;
+            }
+            writeln!(writer)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Lock the shared state.
+    fn lock(&self) -> MutexGuard<'_, ProbeState> {
+        lock(&self.state)
+    }
+}
+
+impl Monitor for VoltageProbe {
+    /// Sample the potentials at the end of the step if a sample is due.
+    /// Neurons removed from the network read as NaN.
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = self.lock();
+        let time = step.end_time();
+        let interval = state.interval;
+        if !sample_due(&mut state.next_sample, time, interval) {
+            return;
+        }
+        let row = state
+            .targets
+            .iter()
+            .map(|i| i.and_then(|i| step.neurons.get(i)).map_or(f64::NAN, |n| n.v_mem))
+            .collect();
+        state.times.push(time);
+        state.values.push(row);
+    }
+
+    fn neuron_removed(&mut self, index: usize) {
+        shift_selected(&mut self.lock().targets, index);
+    }
+
+    fn max_neuron(&self) -> Option<usize> {
+        self.lock().targets.iter().flatten().copied().max()
+    }
+}
@@ -0,0 +1,70 @@
+//! progress.rs
+//!
+//! Progress reporting for long-running simulations.
+//!
+//! A progress callback is invoked at a fixed simulated-time interval during
+//! `Simulation::run`, allowing applications to render progress bars or log
+//! throughput. Returning `false` from the callback aborts the run
+//! gracefully; the results collected so far are still returned.
+
+use crate::units::Milliseconds;
+
+/// Progress callback receiving `(current_time, t_max, spikes_so_far)`.
+///
+/// Return `true` to continue the simulation or `false` to abort it.
+pub type ProgressCallback = Box<dyn FnMut(Milliseconds, Milliseconds, usize) -> bool + Send>;
+
+/// A progress callback together with its reporting schedule.
+pub(crate) struct ProgressHook {
+    /// Reporting interval in simulated time
+    interval: Milliseconds,
+    /// Simulated time the schedule counts intervals from
+    start: Milliseconds,
+    /// Simulated time of the next report
+    next_report: Milliseconds,
+    callback: ProgressCallback,
+}
+
+impl ProgressHook {
+    /// Create a hook that first fires once `interval` has been simulated.
+    ///
+    /// # Panics
+    /// Panics if `interval` is not positive.
+    pub(crate) fn new(interval: Milliseconds, callback: ProgressCallback) -> Self {
+        assert!(
+            interval > Milliseconds::ZERO,
+            "Progress interval must be positive, got {interval}"
+        );
+        Self {
+            interval,
+            start: Milliseconds::ZERO,
+            next_report: interval,
+            callback,
+        }
+    }
+
+    /// Reset the schedule so the next report happens `interval` after `time`.
+    pub(crate) fn reset(&mut self, time: Milliseconds) {
+        self.start = time;
+        self.next_report = time + self.interval;
+    }
+
+    /// Invoke the callback if the reporting interval has elapsed.
+    ///
+    /// Returns `false` if the callback requested that the run be aborted.
+    pub(crate) fn report(
+        &mut self,
+        time: Milliseconds,
+        t_max: Milliseconds,
+        spikes_so_far: usize,
+    ) -> bool {
+        if time < self.next_report {
+            return true;
+        }
+        // The first multiple of the interval after `time`, computed rather
+        // than stepped to, since steps of a tiny interval can round away
+        let elapsed = ((time - self.start) / self.interval).floor();
+        self.next_report = self.start + self.interval * (elapsed + 1.0);
+        (self.callback)(time, t_max, spikes_so_far)
+    }
+}
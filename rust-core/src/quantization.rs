@@ -0,0 +1,214 @@
+//! quantization.rs
+//!
+//! Fixed-point arithmetic for hardware-faithful simulation.
+//!
+//! Neuromorphic chips do not store membrane potentials and weights as
+//! floating point numbers: Loihi keeps 8-bit synaptic weights and 24-bit
+//! compartment state, SpiNNaker 2 works in 16- and 32-bit fixed point.
+//! Coarse weights change what STDP can learn, since updates smaller than
+//! half a weight step are rounded away, and coarse state changes the
+//! dynamics near threshold. A `Quantization` in `SimulationConfig` makes the
+//! simulation round membrane potentials and weights to such formats after
+//! every update, so a model can be checked against these effects before it
+//! is deployed.
+//!
+//! Values are rounded to the nearest representable number and saturate at
+//! the ends of the range. Weights can instead be rounded stochastically, up
+//! or down with probabilities that make the expected result exact, which is
+//! how on-chip learning engines keep small updates from vanishing.
+
+use crate::rng::Rng;
+
+/// Binary fixed-point number format.
+///
+/// A value is an integer of `bits` bits, two's complement if `signed`,
+/// scaled by `2^-frac_bits`. `frac_bits` may be negative or exceed `bits`
+/// for formats whose step is larger than one or whose range is below one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedPoint {
+    /// Total number of bits, including the sign bit
+    pub bits: u32,
+    /// Number of bits after the binary point
+    pub frac_bits: i32,
+    /// Whether negative values are representable
+    pub signed: bool,
+}
+
+impl FixedPoint {
+    /// Signed format with `bits` bits, `frac_bits` of them fractional.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not in `2..=53`.
+    pub fn signed(bits: u32, frac_bits: i32) -> Self {
+        assert!(
+            (2..=53).contains(&bits),
+            "Signed fixed-point formats need 2 to 53 bits, got {bits}"
+        );
+        Self {
+            bits,
+            frac_bits,
+            signed: true,
+        }
+    }
+
+    /// Unsigned format with `bits` bits, `frac_bits` of them fractional.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not in `1..=53`.
+    pub fn unsigned(bits: u32, frac_bits: i32) -> Self {
+        assert!(
+            (1..=53).contains(&bits),
+            "Unsigned fixed-point formats need 1 to 53 bits, got {bits}"
+        );
+        Self {
+            bits,
+            frac_bits,
+            signed: false,
+        }
+    }
+
+    /// Finest format of `bits` bits whose range includes `max_abs` (and
+    /// `-max_abs` if `signed`).
+    ///
+    /// For example, `covering(8, false, 1.0)` has a step of 1/128 and
+    /// represents weights from 0 to 255/128.
+    ///
+    /// # Panics
+    /// Panics if `max_abs` is not positive and finite or `bits` is out of
+    /// range.
+    pub fn covering(bits: u32, signed: bool, max_abs: f64) -> Self {
+        assert!(
+            max_abs.is_finite() && max_abs > 0.0,
+            "Fixed-point range must be positive, got {max_abs}"
+        );
+        let magnitude_bits = bits as i32 - signed as i32;
+        // Largest frac_bits with (2^magnitude_bits - 1) * 2^-frac_bits >= max_abs
+        let mut frac_bits = magnitude_bits - max_abs.log2().ceil() as i32;
+        let format = |frac_bits| {
+            if signed {
+                Self::signed(bits, frac_bits)
+            } else {
+                Self::unsigned(bits, frac_bits)
+            }
+        };
+        while format(frac_bits).max() < max_abs {
+            frac_bits -= 1;
+        }
+        format(frac_bits)
+    }
+
+    /// Difference between adjacent representable values.
+    pub fn resolution(&self) -> f64 {
+        (-self.frac_bits as f64).exp2()
+    }
+
+    /// Smallest representable value.
+    pub fn min(&self) -> f64 {
+        if self.signed {
+            -((self.bits - 1) as f64).exp2() * self.resolution()
+        } else {
+            0.0
+        }
+    }
+
+    /// Largest representable value.
+    pub fn max(&self) -> f64 {
+        let magnitude_bits = self.bits - self.signed as u32;
+        ((magnitude_bits as f64).exp2() - 1.0) * self.resolution()
+    }
+
+    /// `x` rounded to the nearest representable value, with ties away from
+    /// zero, saturating at the ends of the range. NaN becomes zero.
+    pub fn quantize(&self, x: f64) -> f64 {
+        self.saturate((x / self.resolution()).round())
+    }
+
+    /// `x` rounded down or up to a neighbouring representable value, up
+    /// with probability equal to the fractional distance from the lower
+    /// neighbour, saturating at the ends of the range.
+    pub fn quantize_stochastic(&self, x: f64, rng: &mut Rng) -> f64 {
+        let scaled = x / self.resolution();
+        let lower = scaled.floor();
+        let steps = if rng.next_f64() < scaled - lower {
+            lower + 1.0
+        } else {
+            lower
+        };
+        self.saturate(steps)
+    }
+
+    /// Value of `steps` resolution steps, clamped to the range.
+    fn saturate(&self, steps: f64) -> f64 {
+        let resolution = self.resolution();
+        let low = self.min() / resolution;
+        let high = self.max() / resolution;
+        if steps.is_nan() {
+            return 0.0;
+        }
+        steps.clamp(low, high) * resolution
+    }
+}
+
+/// Rounding of weights after plasticity updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rounding {
+    /// Round to the nearest representable weight
+    #[default]
+    Nearest,
+    /// Round up or down at random so that updates are exact on average
+    Stochastic,
+}
+
+/// Fixed-point formats of a quantized simulation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quantization {
+    /// Format of membrane potentials
+    pub state: FixedPoint,
+    /// Format of synaptic weights
+    pub weights: FixedPoint,
+    /// Rounding of weights after plasticity updates
+    pub weight_rounding: Rounding,
+    /// Seed of the generator used for stochastic rounding
+    pub seed: u64,
+}
+
+impl Quantization {
+    /// Quantization with the given formats and nearest rounding.
+    pub fn new(state: FixedPoint, weights: FixedPoint) -> Self {
+        Self {
+            state,
+            weights,
+            weight_rounding: Rounding::Nearest,
+            seed: 0,
+        }
+    }
+
+    /// Quantization with `weight_bits`-bit weights covering `[w_min, w_max]`
+    /// and `state_bits`-bit signed membrane potentials covering
+    /// `[-v_max, v_max]`, e.g. `hardware(8, 16, 0.0, 1.0, 2.0)`.
+    ///
+    /// Weights are unsigned unless `w_min` is negative.
+    pub fn hardware(weight_bits: u32, state_bits: u32, w_min: f64, w_max: f64, v_max: f64) -> Self {
+        let weights = FixedPoint::covering(weight_bits, w_min < 0.0, w_max.abs().max(w_min.abs()));
+        Self::new(FixedPoint::covering(state_bits, true, v_max), weights)
+    }
+
+    /// Round weight updates stochastically, drawing from a generator
+    /// seeded with `seed`.
+    pub fn with_stochastic_rounding(mut self, seed: u64) -> Self {
+        self.weight_rounding = Rounding::Stochastic;
+        self.seed = seed;
+        self
+    }
+
+    /// `w` in the weight format, rounded as configured.
+    pub(crate) fn weight(&self, w: f64, rng: &mut Rng) -> f64 {
+        match self.weight_rounding {
+            Rounding::Nearest => self.weights.quantize(w),
+            Rounding::Stochastic => self.weights.quantize_stochastic(w, rng),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! readout.rs
+//!
+//! Trained linear readouts of network activity.
+//!
+//! In reservoir computing only the readout is trained: the recurrent
+//! network projects its input into a high-dimensional state, and a linear
+//! map from sampled states to targets is fitted afterwards. States are the
+//! low-pass filtered spike trains of selected neurons, sampled at chosen
+//! times. `LinearReadout` fits that map by ridge regression and
+//! `LogisticReadout` fits a softmax classifier by gradient descent.
+
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+
+/// Exponentially filtered activity of `neurons` sampled at each of `times`.
+///
+/// Entry `[k][j]` is `Σ exp(-(times[k] - t_s) / tau)` over spikes of
+/// `neurons[j]` at or before `times[k]`.
+pub fn sample_states(
+    spikes: &[Spike],
+    neurons: &[usize],
+    times: &[Milliseconds],
+    tau: Milliseconds,
+) -> Vec<Vec<f64>> {
+    times
+        .iter()
+        .map(|&t| {
+            let mut state = vec![0.0; neurons.len()];
+            for spike in spikes.iter().filter(|s| s.time <= t) {
+                let trace = (-((t - spike.time) / tau)).exp();
+                for (j, &n) in neurons.iter().enumerate() {
+                    if n == spike.neuron_id {
+                        state[j] += trace;
+                    }
+                }
+            }
+            state
+        })
+        .collect()
+}
+
+/// Linear map from states to real-valued outputs, with a bias term.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinearReadout {
+    /// Weights per output; the last entry of each row is the bias
+    pub weights: Vec<Vec<f64>>,
+}
+
+impl LinearReadout {
+    /// Fit by ridge regression, minimizing
+    /// `Σ ‖y - W x - b‖² + lambda ‖W‖²` (the bias is not penalized).
+    ///
+    /// Returns `None` if there are no samples, `states` and `targets`
+    /// differ in length, the states or the targets differ in length among
+    /// themselves, or the system is singular (e.g. `lambda = 0` with
+    /// collinear features).
+    pub fn fit_ridge(states: &[Vec<f64>], targets: &[Vec<f64>], lambda: f64) -> Option<Self> {
+        if states.is_empty() || states.len() != targets.len() {
+            return None;
+        }
+        let dim = states[0].len() + 1;
+        let outputs = targets[0].len();
+        if states.iter().any(|x| x.len() + 1 != dim) || targets.iter().any(|y| y.len() != outputs) {
+            return None;
+        }
+
+        // Normal equations (XᵀX + λI) W = XᵀY on bias-augmented states.
+        let mut gram = vec![vec![0.0; dim]; dim];
+        let mut rhs = vec![vec![0.0; outputs]; dim];
+        for (x, y) in states.iter().zip(targets) {
+            let x = augmented(x);
+            for i in 0..dim {
+                for j in 0..dim {
+                    gram[i][j] += x[i] * x[j];
+                }
+                for (o, &y) in y.iter().enumerate() {
+                    rhs[i][o] += x[i] * y;
+                }
+            }
+        }
+        for (i, row) in gram.iter_mut().enumerate().take(dim - 1) {
+            row[i] += lambda;
+        }
+
+        let solution = solve(gram, rhs)?;
+        let weights = (0..outputs)
+            .map(|o| solution.iter().map(|row| row[o]).collect())
+            .collect();
+        Some(Self { weights })
+    }
+
+    /// Outputs for `state`.
+    pub fn predict(&self, state: &[f64]) -> Vec<f64> {
+        let x = augmented(state);
+        self.weights.iter().map(|w| dot(w, &x)).collect()
+    }
+
+    /// Mean squared error over a set of samples.
+    pub fn mse(&self, states: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        let mut total = 0.0;
+        let mut count = 0;
+        for (x, y) in states.iter().zip(targets) {
+            for (p, t) in self.predict(x).iter().zip(y) {
+                total += (p - t).powi(2);
+                count += 1;
+            }
+        }
+        if count == 0 {
+            0.0
+        } else {
+            total / count as f64
+        }
+    }
+}
+
+/// Multinomial logistic (softmax) classifier with a bias term.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogisticReadout {
+    /// Weights per class; the last entry of each row is the bias
+    pub weights: Vec<Vec<f64>>,
+}
+
+impl LogisticReadout {
+    /// Fit by full-batch gradient descent on the cross-entropy loss with
+    /// L2 penalty `l2` on the non-bias weights.
+    ///
+    /// `labels` must be smaller than `num_classes`.
+    pub fn fit(
+        states: &[Vec<f64>],
+        labels: &[usize],
+        num_classes: usize,
+        epochs: usize,
+        learning_rate: f64,
+        l2: f64,
+    ) -> Self {
+        let dim = states.first().map_or(0, Vec::len) + 1;
+        let mut readout = Self {
+            weights: vec![vec![0.0; dim]; num_classes],
+        };
+        let n = states.len().min(labels.len());
+        if n == 0 {
+            return readout;
+        }
+        for _ in 0..epochs {
+            let mut grad = vec![vec![0.0; dim]; num_classes];
+            for (x, &label) in states.iter().zip(labels) {
+                let x = augmented(x);
+                let p = softmax(&readout.logits(&x));
+                for (c, g) in grad.iter_mut().enumerate() {
+                    let err = p[c] - f64::from(u8::from(c == label));
+                    for (g, xi) in g.iter_mut().zip(&x) {
+                        *g += err * xi;
+                    }
+                }
+            }
+            for (w, g) in readout.weights.iter_mut().zip(&grad) {
+                for j in 0..dim {
+                    let penalty = if j + 1 < dim { l2 * w[j] } else { 0.0 };
+                    w[j] -= learning_rate * (g[j] / n as f64 + penalty);
+                }
+            }
+        }
+        readout
+    }
+
+    /// Class probabilities for `state`.
+    pub fn predict_proba(&self, state: &[f64]) -> Vec<f64> {
+        softmax(&self.logits(&augmented(state)))
+    }
+
+    /// Most probable class for `state`.
+    pub fn predict(&self, state: &[f64]) -> usize {
+        let logits = self.logits(&augmented(state));
+        (0..logits.len())
+            .max_by(|&a, &b| logits[a].total_cmp(&logits[b]).then(b.cmp(&a)))
+            .unwrap_or(0)
+    }
+
+    /// Fraction of samples classified correctly.
+    pub fn accuracy(&self, states: &[Vec<f64>], labels: &[usize]) -> f64 {
+        let n = states.len().min(labels.len());
+        if n == 0 {
+            return 0.0;
+        }
+        let correct = states
+            .iter()
+            .zip(labels)
+            .filter(|(x, &label)| self.predict(x) == label)
+            .count();
+        correct as f64 / n as f64
+    }
+
+    /// Unnormalized class scores for a bias-augmented state.
+    fn logits(&self, x: &[f64]) -> Vec<f64> {
+        self.weights.iter().map(|w| dot(w, x)).collect()
+    }
+}
+
+/// `x` with a trailing `1.0` for the bias.
+fn augmented(x: &[f64]) -> Vec<f64> {
+    let mut x = x.to_vec();
+    x.push(1.0);
+    x
+}
+
+/// Inner product.
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Numerically stable softmax.
+fn softmax(logits: &[f64]) -> Vec<f64> {
+    let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exp: Vec<f64> = logits.iter().map(|&z| (z - max).exp()).collect();
+    let sum: f64 = exp.iter().sum();
+    exp.into_iter().map(|e| e / sum).collect()
+}
+
+/// Solve `a · x = b` for square `a` and a multi-column `b` by Gaussian
+/// elimination with partial pivoting.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (pivot_a, pivot_b) = (a[col].clone(), b[col].clone());
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_a[col];
+            if factor == 0.0 {
+                continue;
+            }
+            for (x, p) in a[row].iter_mut().zip(&pivot_a).skip(col) {
+                *x -= factor * p;
+            }
+            for (x, p) in b[row].iter_mut().zip(&pivot_b) {
+                *x -= factor * p;
+            }
+        }
+    }
+    for col in (0..n).rev() {
+        for k in 0..b[col].len() {
+            let mut value = b[col][k];
+            for j in col + 1..n {
+                value -= a[col][j] * b[j][k];
+            }
+            b[col][k] = value / a[col][col];
+        }
+    }
+    Some(b)
+}
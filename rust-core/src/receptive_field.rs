@@ -0,0 +1,173 @@
+//! receptive_field.rs
+//!
+//! Learned receptive field extraction.
+//!
+//! When an encoder maps an image onto a sheet of input neurons, the
+//! incoming weights of a neuron downstream of that sheet form a picture of
+//! the feature it has learned to respond to. Inspecting these pictures is
+//! the standard way of judging what STDP has extracted from the data. This
+//! module reshapes incoming weight vectors to the input geometry and writes
+//! them as CSV matrices or grayscale PNG images, individually or tiled into
+//! one image.
+
+use crate::compression;
+use crate::connectivity::SheetShape;
+use crate::png;
+use crate::synapse::Synapse;
+use crate::util::invalid_input;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Incoming weights of one neuron, arranged like its input sheet.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceptiveField {
+    /// Neuron whose inputs these are
+    pub neuron: usize,
+    /// Geometry of the input sheet
+    pub shape: SheetShape,
+    /// Row-major weights; unconnected inputs are zero and parallel
+    /// synapses are summed
+    pub weights: Vec<f64>,
+}
+
+impl ReceptiveField {
+    /// Collect the weights from the input sheet of `shape` starting at
+    /// neuron `input_start` onto `neuron`.
+    pub fn extract(
+        synapses: &[Synapse],
+        input_start: usize,
+        shape: SheetShape,
+        neuron: usize,
+    ) -> Self {
+        let inputs = input_start..input_start + shape.len();
+        let mut weights = vec![0.0; shape.len()];
+        for s in synapses {
+            if s.post_neuron == neuron && inputs.contains(&s.pre_neuron) {
+                weights[s.pre_neuron - input_start] += s.weight;
+            }
+        }
+        Self {
+            neuron,
+            shape,
+            weights,
+        }
+    }
+
+    /// Weight from input pixel `(x, y)`.
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        self.weights[y * self.shape.width + x]
+    }
+
+    /// Weights as a matrix of `height` rows.
+    pub fn rows(&self) -> Vec<Vec<f64>> {
+        self.weights
+            .chunks(self.shape.width.max(1))
+            .map(<[f64]>::to_vec)
+            .collect()
+    }
+
+    /// Smallest and largest weight.
+    pub fn range(&self) -> (f64, f64) {
+        self.weights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &w| {
+                (lo.min(w), hi.max(w))
+            })
+    }
+
+    /// Write the weights as a CSV matrix, one line per row.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        for row in self.rows() {
+            let line: Vec<String> = row.iter().map(|w| w.to_string()).collect();
+            writeln!(writer, "{}", line.join(","))?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Write the field as an 8-bit grayscale PNG, scaled so its smallest
+    /// weight is black and its largest white.
+    ///
+    /// Fails with `InvalidInput` if the field has no pixels.
+    pub fn write_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let (lo, hi) = self.range();
+        let pixels = self.weights.iter().map(|&w| gray(w, lo, hi)).collect();
+        write_gray_png(path, self.shape.width, self.shape.height, pixels)
+    }
+}
+
+/// Receptive fields of every neuron in `outputs`.
+pub fn extract_receptive_fields(
+    synapses: &[Synapse],
+    input_start: usize,
+    shape: SheetShape,
+    outputs: Range<usize>,
+) -> Vec<ReceptiveField> {
+    outputs
+        .map(|n| ReceptiveField::extract(synapses, input_start, shape, n))
+        .collect()
+}
+
+/// Tile `fields` into one grayscale PNG with `columns` fields per row and
+/// a one-pixel black border between tiles. Each tile is scaled to its own
+/// weight range.
+///
+/// Fails with `InvalidInput` if `fields` is empty or the fields have no
+/// pixels, since a PNG needs at least one.
+///
+/// # Panics
+/// Panics if the fields do not all have the same shape.
+pub fn write_png_grid<P: AsRef<Path>>(
+    fields: &[ReceptiveField],
+    columns: usize,
+    path: P,
+) -> io::Result<()> {
+    let Some(first) = fields.first() else {
+        return Err(invalid_input("Cannot write an empty receptive field grid".to_string()));
+    };
+    let shape = first.shape;
+    assert!(
+        fields.iter().all(|f| f.shape == shape),
+        "Receptive fields must share one shape"
+    );
+    let columns = columns.clamp(1, fields.len());
+    let grid_rows = fields.len().div_ceil(columns);
+    let width = columns * (shape.width + 1) + 1;
+    let height = grid_rows * (shape.height + 1) + 1;
+
+    let mut pixels = vec![0u8; width * height];
+    for (k, field) in fields.iter().enumerate() {
+        let (lo, hi) = field.range();
+        let left = (k % columns) * (shape.width + 1) + 1;
+        let top = (k / columns) * (shape.height + 1) + 1;
+        for y in 0..shape.height {
+            for x in 0..shape.width {
+                pixels[(top + y) * width + left + x] = gray(field.get(x, y), lo, hi);
+            }
+        }
+    }
+    write_gray_png(path, width, height, pixels)
+}
+
+/// Map `w` from `[lo, hi]` to a gray level; a flat field is mid-gray.
+fn gray(w: f64, lo: f64, hi: f64) -> u8 {
+    if hi > lo {
+        ((w - lo) / (hi - lo) * 255.0).round().clamp(0.0, 255.0) as u8
+    } else {
+        128
+    }
+}
+
+/// Write an 8-bit grayscale PNG to `path`.
+fn write_gray_png<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+) -> io::Result<()> {
+    let png = png::encode_gray(width, height, &pixels)?;
+    compression::write(path, png)
+}
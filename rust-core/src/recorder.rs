@@ -0,0 +1,128 @@
+//! recorder.rs
+//!
+//! Bounded-memory spike recording.
+//!
+//! Long simulated experiments can emit far more spikes than fit in memory.
+//! A `SpikeRecorder` keeps only the most recent spikes in a fixed-size
+//! buffer and streams older ones to a file on disk as the run progresses,
+//! so memory use stays constant regardless of simulated duration.
+
+use crate::compression::{self, CompressedWriter};
+use crate::spike::Spike;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// On-disk format for spilled spikes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpillFormat {
+    /// `neuron_id,time_ms` rows with a header, as written by
+    /// `Simulation::write_spikes_to_csv`.
+    Csv,
+    /// Fixed 16-byte records: neuron id as little-endian `u64` followed by
+    /// the spike time in milliseconds as little-endian `f64`.
+    Binary,
+}
+
+/// Spike recorder that keeps the most recent `capacity` spikes in memory
+/// and spills older spikes to disk.
+pub struct SpikeRecorder {
+    capacity: usize,
+    recent: VecDeque<Spike>,
+    writer: CompressedWriter<BufWriter<File>>,
+    format: SpillFormat,
+    spilled: usize,
+}
+
+impl SpikeRecorder {
+    /// Create a recorder spilling to a new file at `path`.
+    ///
+    /// Any existing file at `path` is truncated. Spikes are compressed if
+    /// the path ends in `.gz` or `.zst`.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        capacity: usize,
+        format: SpillFormat,
+    ) -> io::Result<Self> {
+        let mut writer = compression::create(path)?;
+        if format == SpillFormat::Csv {
+            writeln!(writer, "neuron_id,time_ms")?;
+        }
+        Ok(Self {
+            capacity,
+            recent: VecDeque::with_capacity(capacity),
+            writer,
+            format,
+            spilled: 0,
+        })
+    }
+
+    /// Record a spike, spilling the oldest buffered spike if the buffer is
+    /// full.
+    pub fn record(&mut self, spike: Spike) -> io::Result<()> {
+        if self.capacity == 0 {
+            return self.spill(spike);
+        }
+        if self.recent.len() == self.capacity {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.spill(oldest)?;
+            }
+        }
+        self.recent.push_back(spike);
+        Ok(())
+    }
+
+    /// Spikes currently held in memory, oldest first.
+    pub fn recent(&self) -> impl Iterator<Item = &Spike> {
+        self.recent.iter()
+    }
+
+    /// In-memory spikes as one contiguous slice, oldest first.
+    pub(crate) fn recent_slice(&mut self) -> &[Spike] {
+        self.recent.make_contiguous()
+    }
+
+    /// Number of spikes written to disk so far.
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+
+    /// Total number of spikes recorded, in memory and on disk.
+    pub fn total(&self) -> usize {
+        self.spilled + self.recent.len()
+    }
+
+    /// Flush spilled spikes to the underlying file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Write the in-memory spikes to disk as well and flush, so the file
+    /// holds the complete spike train.
+    ///
+    /// Returns the total number of spikes written.
+    pub fn finish(mut self) -> io::Result<usize> {
+        while let Some(spike) = self.recent.pop_front() {
+            self.spill(spike)?;
+        }
+        self.writer.finish()?;
+        Ok(self.spilled)
+    }
+
+    /// Append one spike to the spill file.
+    fn spill(&mut self, spike: Spike) -> io::Result<()> {
+        match self.format {
+            SpillFormat::Csv => {
+                writeln!(self.writer, "{},{}", spike.neuron_id, spike.time.0)?;
+            }
+            SpillFormat::Binary => {
+                self.writer.write_all(&(spike.neuron_id as u64).to_le_bytes())?;
+                self.writer.write_all(&spike.time.0.to_le_bytes())?;
+            }
+        }
+        self.spilled += 1;
+        Ok(())
+    }
+}
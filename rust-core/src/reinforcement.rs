@@ -0,0 +1,560 @@
+//! reinforcement.rs
+//!
+//! Closed-loop reinforcement learning with reward-modulated STDP.
+//!
+//! An `Environment` is anything that can be observed as a vector of values
+//! in `[0, 1]` and answers an action with a reward. `ClosedLoop` connects
+//! one to a network: every environment step it rate-codes the observation
+//! onto input populations for a decision window, lets the network run,
+//! picks the action whose population fired most, and hands the reward to
+//! R-STDP. R-STDP (Izhikevich, 2007; Florian, 2007) accumulates STDP pairs
+//! into a decaying eligibility trace per synapse instead of changing the
+//! weight, and commits the trace only when a reward arrives:
+//!
+//! ```text
+//! Δw = learning_rate × (reward - baseline) × eligibility
+//! ```
+//!
+//! The baseline is a running mean of past rewards, so only better- or
+//! worse-than-usual outcomes change weights. `Bandit` and `CartPole` are
+//! provided as environments and `build_agent` wires a ready network with
+//! one input population per observation value and one output population
+//! per action. Synapses trained by R-STDP should be `Plasticity::Static`
+//! so the simulation's own STDP leaves them alone.
+
+use crate::encoding::{GaussianPopulationEncoder, PoissonEncoder};
+use crate::monitor::StepView;
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// A task the network acts in.
+pub trait Environment {
+    /// Current observation, with values in `[0, 1]`.
+    fn observe(&self) -> Vec<f64>;
+
+    /// Take `action` and return the reward it earned.
+    fn act(&mut self, action: usize) -> f64;
+
+    /// Whether the current episode has ended.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    /// Start a new episode.
+    fn reset(&mut self) {}
+}
+
+/// Multi-armed bandit: action `k` pays 1 with probability
+/// `probabilities[k]` and 0 otherwise. The observation is a constant 1.
+#[derive(Debug, Clone)]
+pub struct Bandit {
+    /// Payout probability of each arm
+    pub probabilities: Vec<f64>,
+    rng: Rng,
+}
+
+impl Bandit {
+    /// Bandit with the given payout probabilities.
+    pub fn new(probabilities: Vec<f64>, seed: u64) -> Self {
+        Self {
+            probabilities,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Index of the arm with the highest payout probability.
+    pub fn best_arm(&self) -> usize {
+        (0..self.probabilities.len())
+            .max_by(|&a, &b| self.probabilities[a].total_cmp(&self.probabilities[b]))
+            .unwrap_or(0)
+    }
+}
+
+impl Environment for Bandit {
+    fn observe(&self) -> Vec<f64> {
+        vec![1.0]
+    }
+
+    fn act(&mut self, action: usize) -> f64 {
+        let p = self.probabilities.get(action).copied().unwrap_or(0.0);
+        if self.rng.bernoulli(p) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Cart-pole balancing (Barto, Sutton and Anderson, 1983).
+///
+/// Action 0 pushes the cart left and action 1 right with a 10 N force for
+/// 20 ms. Each step the pole stays within 12° of upright and the cart within
+/// 2.4 m of the center earns a reward of 1; the step that breaks either
+/// limit earns -1 and ends the episode. Each of the four state variables is
+/// observed through `resolution` Gaussian tuning curves, so observations
+/// have `4 × resolution` values.
+#[derive(Debug, Clone)]
+pub struct CartPole {
+    /// Cart position (m), velocity (m/s), pole angle (rad) and angular
+    /// velocity (rad/s)
+    pub state: [f64; 4],
+    encoders: [GaussianPopulationEncoder; 4],
+    done: bool,
+    rng: Rng,
+}
+
+impl CartPole {
+    const GRAVITY: f64 = 9.8;
+    const CART_MASS: f64 = 1.0;
+    const POLE_MASS: f64 = 0.1;
+    const POLE_HALF_LENGTH: f64 = 0.5;
+    const FORCE: f64 = 10.0;
+    const TAU: f64 = 0.02;
+    const X_LIMIT: f64 = 2.4;
+    const ANGLE_LIMIT: f64 = 12.0 * std::f64::consts::PI / 180.0;
+
+    /// Cart-pole observed with `resolution` tuning curves per variable,
+    /// starting from a small random perturbation.
+    pub fn new(resolution: usize, seed: u64) -> Self {
+        let encoders = [
+            GaussianPopulationEncoder::new(resolution, -Self::X_LIMIT, Self::X_LIMIT),
+            GaussianPopulationEncoder::new(resolution, -2.0, 2.0),
+            GaussianPopulationEncoder::new(resolution, -Self::ANGLE_LIMIT, Self::ANGLE_LIMIT),
+            GaussianPopulationEncoder::new(resolution, -2.0, 2.0),
+        ];
+        let mut cart_pole = Self {
+            state: [0.0; 4],
+            encoders,
+            done: false,
+            rng: Rng::new(seed),
+        };
+        cart_pole.reset();
+        cart_pole
+    }
+}
+
+impl Environment for CartPole {
+    fn observe(&self) -> Vec<f64> {
+        self.encoders
+            .iter()
+            .zip(self.state)
+            .flat_map(|(encoder, x)| encoder.activations(x))
+            .collect()
+    }
+
+    fn act(&mut self, action: usize) -> f64 {
+        if self.done {
+            return 0.0;
+        }
+        let [x, x_dot, theta, theta_dot] = self.state;
+        let force = if action == 1 {
+            Self::FORCE
+        } else {
+            -Self::FORCE
+        };
+        let total_mass = Self::CART_MASS + Self::POLE_MASS;
+        let pole_moment = Self::POLE_MASS * Self::POLE_HALF_LENGTH;
+        let (sin, cos) = theta.sin_cos();
+        let temp = (force + pole_moment * theta_dot * theta_dot * sin) / total_mass;
+        let theta_acc = (Self::GRAVITY * sin - cos * temp)
+            / (Self::POLE_HALF_LENGTH * (4.0 / 3.0 - Self::POLE_MASS * cos * cos / total_mass));
+        let x_acc = temp - pole_moment * theta_acc * cos / total_mass;
+        self.state = [
+            x + Self::TAU * x_dot,
+            x_dot + Self::TAU * x_acc,
+            theta + Self::TAU * theta_dot,
+            theta_dot + Self::TAU * theta_acc,
+        ];
+        self.done = self.state[0].abs() > Self::X_LIMIT || self.state[2].abs() > Self::ANGLE_LIMIT;
+        if self.done {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn reset(&mut self) {
+        for x in &mut self.state {
+            *x = self.rng.uniform(-0.05, 0.05);
+        }
+        self.done = false;
+    }
+}
+
+/// Parameters of reward-modulated STDP.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RStdpParams {
+    /// Eligibility added per causal pre-post pair
+    pub a_plus: f64,
+    /// Eligibility removed per anti-causal pair
+    pub a_minus: f64,
+    /// Time constant of the presynaptic trace
+    pub tau_plus: Milliseconds,
+    /// Time constant of the postsynaptic trace
+    pub tau_minus: Milliseconds,
+    /// Decay time constant of the eligibility trace
+    pub tau_eligibility: Milliseconds,
+    /// Scale of weight changes per unit of reward
+    pub learning_rate: f64,
+    /// Rate at which the reward baseline tracks rewards; 0 disables it
+    pub baseline_rate: f64,
+    /// Lower weight bound
+    pub w_min: f64,
+    /// Upper weight bound
+    pub w_max: f64,
+}
+
+impl Default for RStdpParams {
+    fn default() -> Self {
+        Self {
+            a_plus: 1.0,
+            a_minus: 1.0,
+            tau_plus: Milliseconds(20.0),
+            tau_minus: Milliseconds(20.0),
+            tau_eligibility: Milliseconds(200.0),
+            learning_rate: 0.01,
+            baseline_rate: 0.1,
+            w_min: 0.0,
+            w_max: 1.0,
+        }
+    }
+}
+
+/// Reward-modulated STDP on a set of synapses.
+#[derive(Debug, Clone)]
+pub struct RStdp {
+    /// Rule parameters
+    pub params: RStdpParams,
+    /// Trained synapse indices
+    synapses: Vec<usize>,
+    /// Eligibility of each trained synapse
+    eligibility: Vec<f64>,
+    /// Presynaptic trace of every neuron
+    pre_trace: Vec<f64>,
+    /// Postsynaptic trace of every neuron
+    post_trace: Vec<f64>,
+    baseline: f64,
+}
+
+impl RStdp {
+    /// R-STDP on the synapses of `sim` that target `neurons`.
+    pub fn for_targets(sim: &Simulation, neurons: Range<usize>, params: RStdpParams) -> Self {
+        let synapses: Vec<usize> = sim
+            .synapses()
+            .iter()
+            .enumerate()
+            .filter(|(_, syn)| neurons.contains(&syn.post_neuron))
+            .map(|(index, _)| index)
+            .collect();
+        let n = sim.neurons().len();
+        Self {
+            params,
+            eligibility: vec![0.0; synapses.len()],
+            synapses,
+            pre_trace: vec![0.0; n],
+            post_trace: vec![0.0; n],
+            baseline: 0.0,
+        }
+    }
+
+    /// Current reward baseline.
+    pub fn baseline(&self) -> f64 {
+        self.baseline
+    }
+
+    /// Update the traces with one simulation step.
+    pub fn observe(&mut self, step: &StepView<'_>) {
+        let p = &self.params;
+        let pre_decay = (-(step.dt / p.tau_plus)).exp();
+        let post_decay = (-(step.dt / p.tau_minus)).exp();
+        let eligibility_decay = (-(step.dt / p.tau_eligibility)).exp();
+        for e in &mut self.eligibility {
+            *e *= eligibility_decay;
+        }
+        for x in &mut self.pre_trace {
+            *x *= pre_decay;
+        }
+        for y in &mut self.post_trace {
+            *y *= post_decay;
+        }
+        if step.fired.is_empty() {
+            return;
+        }
+        for (e, &index) in self.eligibility.iter_mut().zip(&self.synapses) {
+            let syn = &step.synapses[index];
+            if step.fired.binary_search(&syn.post_neuron).is_ok() {
+                *e += p.a_plus * self.pre_trace[syn.pre_neuron];
+            }
+            if step.fired.binary_search(&syn.pre_neuron).is_ok() {
+                *e -= p.a_minus * self.post_trace[syn.post_neuron];
+            }
+        }
+        for &i in step.fired {
+            self.pre_trace[i] += 1.0;
+            self.post_trace[i] += 1.0;
+        }
+    }
+
+    /// Commit the eligibility traces to the weights of `sim` in proportion
+    /// to how much `reward` exceeds the baseline, then update the baseline.
+    pub fn reward(&mut self, sim: &mut Simulation, reward: f64) {
+        let p = &self.params;
+        let error = reward - self.baseline;
+        for (&e, &index) in self.eligibility.iter().zip(&self.synapses) {
+            if e == 0.0 {
+                continue;
+            }
+            let weight = sim.synapses()[index].weight + p.learning_rate * error * e;
+            sim.set_weight(index, weight.clamp(p.w_min, p.w_max));
+        }
+        self.baseline += p.baseline_rate * (reward - self.baseline);
+    }
+}
+
+/// Outcome of one environment step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// Action taken
+    pub action: usize,
+    /// Reward received
+    pub reward: f64,
+    /// Spike count of each action population during the decision window
+    pub counts: Vec<usize>,
+}
+
+/// Drives a network in closed loop with an environment.
+#[derive(Debug, Clone)]
+pub struct ClosedLoop {
+    /// Input population of each observation value
+    pub inputs: Vec<Range<usize>>,
+    /// Output population of each action
+    pub actions: Vec<Range<usize>>,
+    /// How long each observation is presented before acting
+    pub window: Milliseconds,
+    /// Input rate for an observation value of 1
+    pub max_rate_hz: f64,
+    /// Learning rule fed with the rewards
+    pub rule: RStdp,
+    rng: Rng,
+}
+
+impl ClosedLoop {
+    /// Closed loop over `sim` with R-STDP on every synapse onto an action
+    /// population.
+    pub fn new(
+        sim: &Simulation,
+        inputs: Vec<Range<usize>>,
+        actions: Vec<Range<usize>>,
+        params: RStdpParams,
+        seed: u64,
+    ) -> Self {
+        let outputs = actions.iter().map(|r| r.start).min().unwrap_or(0)
+            ..actions.iter().map(|r| r.end).max().unwrap_or(0);
+        Self {
+            rule: RStdp::for_targets(sim, outputs, params),
+            inputs,
+            actions,
+            window: Milliseconds(20.0),
+            max_rate_hz: 100.0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Observe `env`, run `sim` for one decision window, act and learn
+    /// from the reward.
+    ///
+    /// The action is the population with the most spikes; ties, including
+    /// windows without output spikes, are broken at random, which also
+    /// provides exploration early in learning.
+    pub fn step<E: Environment>(&mut self, sim: &mut Simulation, env: &mut E) -> Transition {
+        let observation = env.observe();
+        let start = sim.time();
+        let mut input = Vec::new();
+        for (range, &value) in self.inputs.iter().zip(&observation) {
+            let encoder = PoissonEncoder {
+                start,
+                first_neuron: range.start,
+                ..PoissonEncoder::new(self.max_rate_hz, self.window)
+            };
+            input.extend(encoder.encode(&vec![value; range.len()], &mut self.rng));
+        }
+        input.sort_by(|a: &Spike, b| a.time.0.total_cmp(&b.time.0));
+        sim.inject_spikes(&input);
+
+        let dt = sim.config().dt;
+        let mut counts = vec![0; self.actions.len()];
+        while sim.time() < start + self.window {
+            let time = sim.time();
+            let fired: Vec<usize> = sim.step(|_, _| 0.0).iter().map(|s| s.neuron_id).collect();
+            for &i in &fired {
+                if let Some(k) = self.actions.iter().position(|r| r.contains(&i)) {
+                    counts[k] += 1;
+                }
+            }
+            self.rule.observe(&StepView {
+                time,
+                dt,
+                fired: &fired,
+                neurons: sim.neurons(),
+                synapses: sim.synapses(),
+            });
+        }
+
+        let best = counts.iter().copied().max().unwrap_or(0);
+        let tied: Vec<usize> = (0..counts.len()).filter(|&k| counts[k] == best).collect();
+        let action = tied[self.rng.index(tied.len())];
+        let reward = env.act(action);
+        self.rule.reward(sim, reward);
+        Transition {
+            action,
+            reward,
+            counts,
+        }
+    }
+
+    /// Reset `env` and step until the episode ends or after `max_steps`,
+    /// returning the total reward.
+    pub fn run_episode<E: Environment>(
+        &mut self,
+        sim: &mut Simulation,
+        env: &mut E,
+        max_steps: usize,
+    ) -> f64 {
+        env.reset();
+        let mut total = 0.0;
+        for _ in 0..max_steps {
+            if env.is_done() {
+                break;
+            }
+            total += self.step(sim, env).reward;
+        }
+        total
+    }
+}
+
+/// Layout of a network built by `build_agent`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgentParams {
+    /// Neurons coding each observation value
+    pub input_size: usize,
+    /// Neurons voting for each action
+    pub action_size: usize,
+    /// Range of the initial input-to-action weights
+    pub initial_weight: (f64, f64),
+    /// Seed of wiring, input spikes and tie breaking
+    pub seed: u64,
+}
+
+impl Default for AgentParams {
+    fn default() -> Self {
+        Self {
+            input_size: 10,
+            action_size: 10,
+            initial_weight: (0.1, 0.3),
+            seed: 0,
+        }
+    }
+}
+
+/// A network and the closed loop driving it.
+pub struct Agent {
+    /// The network
+    pub simulation: Simulation,
+    /// The driver, holding the learning rule
+    pub driver: ClosedLoop,
+}
+
+impl Agent {
+    /// One environment step, see `ClosedLoop::step`.
+    pub fn step<E: Environment>(&mut self, env: &mut E) -> Transition {
+        self.driver.step(&mut self.simulation, env)
+    }
+
+    /// One episode, see `ClosedLoop::run_episode`.
+    pub fn run_episode<E: Environment>(&mut self, env: &mut E, max_steps: usize) -> f64 {
+        self.driver
+            .run_episode(&mut self.simulation, env, max_steps)
+    }
+}
+
+/// Agent for observations of `observation_size` values and
+/// `num_actions` actions.
+///
+/// Populations `input{k}` and `action{k}` are registered on the network,
+/// and every input neuron connects to every action neuron through a static
+/// synapse with a uniformly drawn initial weight, trained by R-STDP.
+pub fn build_agent(
+    observation_size: usize,
+    num_actions: usize,
+    params: &AgentParams,
+    rstdp: RStdpParams,
+) -> Agent {
+    let neuron_params = NeuronParams {
+        tau_m: Milliseconds(20.0),
+        v_rest: 0.0,
+        v_thresh: 1.0,
+        v_reset: 0.0,
+    };
+    let mut rng = Rng::new(params.seed);
+    let mut builder = NetworkBuilder::new();
+    let inputs: Vec<Range<usize>> = (0..observation_size)
+        .map(|k| {
+            builder.add_population(
+                &format!("input{k}"),
+                params.input_size,
+                neuron_params.clone(),
+            )
+        })
+        .collect();
+    let actions: Vec<Range<usize>> = (0..num_actions)
+        .map(|k| {
+            builder.add_population(
+                &format!("action{k}"),
+                params.action_size,
+                neuron_params.clone(),
+            )
+        })
+        .collect();
+    let (low, high) = params.initial_weight;
+    let mut synapses = Vec::new();
+    for i in inputs.iter().flat_map(|r| r.clone()) {
+        for j in actions.iter().flat_map(|r| r.clone()) {
+            synapses.push(Synapse {
+                plasticity: Plasticity::Static,
+                ..Synapse::new(i, j, rng.uniform(low, high))
+            });
+        }
+    }
+    builder.add_synapses(synapses);
+
+    let config = SimulationConfig {
+        dt: Milliseconds(1.0),
+        synaptic_transmission: true,
+        ..SimulationConfig::default()
+    };
+    // Every synapse is static; learning happens through R-STDP.
+    let stdp = STDPParams {
+        a_plus: 0.0,
+        a_minus: 0.0,
+        tau_plus: rstdp.tau_plus,
+        tau_minus: rstdp.tau_minus,
+        w_min: rstdp.w_min,
+        w_max: rstdp.w_max,
+    };
+    let simulation = Simulation::from_network(builder.build(), config, stdp);
+    let driver = ClosedLoop::new(&simulation, inputs, actions, rstdp, params.seed ^ 0x524c);
+    Agent { simulation, driver }
+}
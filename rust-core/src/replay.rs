@@ -0,0 +1,98 @@
+//! replay.rs
+//!
+//! Deterministic recording and replay of simulation runs.
+//!
+//! Rare dynamical events such as network-wide synchronization bursts are
+//! hard to debug when they cannot be reproduced. Given its starting state
+//! and the external input delivered at every step, the engine is fully
+//! deterministic, so a run can be captured in a `ReplayLog` and replayed
+//! bit-for-bit later, e.g. with extra instrumentation attached.
+
+use crate::rng::Rng;
+use crate::spike::Spike;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Everything needed to reproduce a run exactly.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayLog {
+    /// Simulation time at the start of the run
+    pub start_time: Milliseconds,
+    /// Simulation time at the end of the run
+    pub end_time: Milliseconds,
+    /// Time step used for the run
+    pub dt: Milliseconds,
+    /// Membrane potential of each neuron at the start of the run
+    pub initial_potentials: Vec<f64>,
+    /// Synapse state (weights and spike history) at the start of the run
+    pub initial_synapses: Vec<Synapse>,
+    /// Synaptic input in transit at the start of the run, one entry per
+    /// upcoming step
+    pub initial_arrivals: Vec<Vec<f64>>,
+    /// Injected spikes still pending at the start of the run
+    pub initial_injected: Vec<Spike>,
+    /// Generator of stochastic weight rounding at the start of the run
+    pub initial_rounding_rng: Rng,
+    /// Level of each neuromodulator at the start of the run, in
+    /// registration order
+    pub initial_modulator_levels: Vec<f64>,
+    /// External input current per neuron, one entry per step
+    pub inputs: Vec<Vec<f64>>,
+}
+
+/// Thread-safe per-neuron input recorder.
+///
+/// Each neuron is only ever stepped by one partition thread, so the
+/// per-neuron locks are uncontended.
+pub(crate) struct InputRecorder {
+    inputs: Vec<Mutex<Vec<f64>>>,
+}
+
+impl InputRecorder {
+    pub(crate) fn new(num_neurons: usize) -> Self {
+        Self {
+            inputs: (0..num_neurons).map(|_| Mutex::new(Vec::new())).collect(),
+        }
+    }
+
+    /// Record the input delivered to neuron `i` at the current step.
+    pub(crate) fn record(&self, i: usize, value: f64) {
+        if let Some(slot) = self.inputs.get(i) {
+            slot.lock().expect("Input recorder lock poisoned").push(value);
+        }
+    }
+
+    pub(crate) fn into_inputs(self) -> Vec<Vec<f64>> {
+        self.inputs
+            .into_iter()
+            .map(|m| m.into_inner().expect("Input recorder lock poisoned"))
+            .collect()
+    }
+}
+
+/// Plays back recorded inputs in step order.
+pub(crate) struct InputPlayer<'a> {
+    inputs: &'a [Vec<f64>],
+    cursors: Vec<AtomicUsize>,
+}
+
+impl<'a> InputPlayer<'a> {
+    pub(crate) fn new(inputs: &'a [Vec<f64>]) -> Self {
+        Self {
+            inputs,
+            cursors: inputs.iter().map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    /// Next recorded input for neuron `i`; `0.0` once the log is exhausted.
+    pub(crate) fn next(&self, i: usize) -> f64 {
+        let Some(cursor) = self.cursors.get(i) else {
+            return 0.0;
+        };
+        let k = cursor.fetch_add(1, Ordering::Relaxed);
+        self.inputs[i].get(k).copied().unwrap_or(0.0)
+    }
+}
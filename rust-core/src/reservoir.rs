@@ -0,0 +1,168 @@
+//! reservoir.rs
+//!
+//! Recurrent reservoir (liquid state machine) construction.
+//!
+//! A liquid state machine projects its input into a large, fixed, sparsely
+//! and recurrently connected spiking network. The reservoir's transient
+//! activity acts as a high-dimensional nonlinear memory of recent input,
+//! and only a simple readout trained on that activity is learned. This
+//! module builds such reservoirs and extracts low-pass filtered activity
+//! states for training the readouts of `readout`.
+
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::readout::sample_states;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Parameters of a spiking reservoir.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReservoirParams {
+    /// Number of input neurons
+    pub num_inputs: usize,
+    /// Number of reservoir neurons
+    pub size: usize,
+    /// Fraction of reservoir neurons that are excitatory
+    pub excitatory_fraction: f64,
+    /// Recurrent connection probability
+    pub connection_prob: f64,
+    /// Target spectral radius of the recurrent weight matrix
+    pub spectral_radius: f64,
+    /// Strength of inhibitory relative to excitatory weights
+    pub inhibition_ratio: f64,
+    /// Probability that an input neuron connects to a reservoir neuron
+    pub input_prob: f64,
+    /// Weight of input synapses
+    pub input_weight: f64,
+    /// Seed for wiring and weight initialization
+    pub seed: u64,
+}
+
+impl Default for ReservoirParams {
+    fn default() -> Self {
+        Self {
+            num_inputs: 1,
+            size: 135,
+            excitatory_fraction: 0.8,
+            connection_prob: 0.1,
+            spectral_radius: 0.9,
+            inhibition_ratio: 4.0,
+            input_prob: 0.3,
+            input_weight: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// A reservoir simulation together with its layout.
+pub struct Reservoir {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Input neuron indices
+    pub inputs: Range<usize>,
+    /// Excitatory reservoir neuron indices
+    pub excitatory: Range<usize>,
+    /// Inhibitory reservoir neuron indices
+    pub inhibitory: Range<usize>,
+}
+
+impl Reservoir {
+    /// All reservoir neuron indices (excitatory followed by inhibitory).
+    pub fn reservoir(&self) -> Range<usize> {
+        self.excitatory.start..self.inhibitory.end
+    }
+
+    /// Filtered reservoir state at each of `times`; see
+    /// `readout::sample_states`.
+    pub fn states(
+        &self,
+        spikes: &[Spike],
+        times: &[Milliseconds],
+        tau: Milliseconds,
+    ) -> Vec<Vec<f64>> {
+        let neurons: Vec<usize> = self.reservoir().collect();
+        sample_states(spikes, &neurons, times, tau)
+    }
+}
+
+/// Build a sparsely connected recurrent reservoir.
+///
+/// Populations `input`, `excitatory`, and `inhibitory` are registered on
+/// the network. Recurrent weights are drawn uniformly, negated and scaled
+/// by `inhibition_ratio` for inhibitory neurons, and then rescaled so the
+/// circular-law estimate of the spectral radius, `sqrt(Σ w² / N)`, matches
+/// `spectral_radius`. All reservoir and input synapses are static.
+///
+/// Synaptic transmission is enabled on `config`, since a reservoir without
+/// recurrent dynamics would be inert.
+pub fn build_reservoir(
+    params: &ReservoirParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+    stdp_params: STDPParams,
+) -> Reservoir {
+    let mut rng = Rng::new(params.seed);
+    let num_exc = (params.size as f64 * params.excitatory_fraction).round() as usize;
+    let num_exc = num_exc.min(params.size);
+
+    let mut builder = NetworkBuilder::new();
+    let inputs = builder.add_population("input", params.num_inputs, neuron_params.clone());
+    let excitatory = builder.add_population("excitatory", num_exc, neuron_params.clone());
+    let inhibitory = builder.add_population("inhibitory", params.size - num_exc, neuron_params);
+    let reservoir = excitatory.start..inhibitory.end;
+
+    let mut recurrent = Vec::new();
+    for i in reservoir.clone() {
+        let sign = if excitatory.contains(&i) {
+            1.0
+        } else {
+            -params.inhibition_ratio
+        };
+        for j in reservoir.clone() {
+            if i != j && rng.bernoulli(params.connection_prob) {
+                recurrent.push((i, j, sign * rng.next_f64()));
+            }
+        }
+    }
+
+    let radius = if params.size == 0 {
+        0.0
+    } else {
+        (recurrent.iter().map(|&(_, _, w)| w * w).sum::<f64>() / params.size as f64).sqrt()
+    };
+    let scale = if radius > 0.0 {
+        params.spectral_radius / radius
+    } else {
+        0.0
+    };
+
+    let mut synapses: Vec<Synapse> = recurrent
+        .into_iter()
+        .map(|(i, j, w)| Synapse::new(i, j, w * scale))
+        .collect();
+    for i in inputs.clone() {
+        for j in reservoir.clone() {
+            if rng.bernoulli(params.input_prob) {
+                synapses.push(Synapse::new(i, j, params.input_weight));
+            }
+        }
+    }
+    for synapse in &mut synapses {
+        synapse.plasticity = Plasticity::Static;
+    }
+    builder.add_synapses(synapses);
+
+    config.synaptic_transmission = true;
+    Reservoir {
+        simulation: Simulation::from_network(builder.build(), config, stdp_params),
+        inputs,
+        excitatory,
+        inhibitory,
+    }
+}
@@ -0,0 +1,298 @@
+//! results.rs
+//!
+//! JSON export and import of run results.
+//!
+//! The CSV writers produce one file per quantity with ad-hoc layouts.
+//! Web visualizations and notebooks are easier to feed with a single JSON
+//! document holding everything a run produced: `RunResults` bundles the
+//! spikes, the weight log and metadata describing the run, and converts to
+//! and from JSON of the form
+//!
+//! ```text
+//! {
+//!   "metadata": {"num_neurons": 10, "num_synapses": 90, "dt_ms": 0.1,
+//!                "t_max_ms": 1000, "end_time_ms": 1000,
+//!                "stop_reason": "Completed"},
+//!   "spikes": {"neuron_id": [...], "time_ms": [...]},
+//!   "weights": {"time_ms": [...], "pre_neuron": [...],
+//!               "post_neuron": [...], "weight": [...]}
+//! }
+//! ```
+//!
+//! The `spikes` object uses the column layout accepted by
+//! `spike_io::read_spikes_json`. `stop_reason` may be `null`. JSON has no
+//! infinity or NaN, so non-finite numbers are written as `null`; a `null`
+//! `t_max_ms` reads back as infinity, the unbounded run length, and any
+//! other `null` time as NaN.
+
+use crate::compression;
+use crate::json::{write_number, write_string, Json, JsonParser};
+use crate::simulation::{Simulation, WeightSample};
+use crate::spike::Spike;
+use crate::stopping::StopReason;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::path::Path;
+
+/// Description of a finished run.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunMetadata {
+    /// Number of neurons
+    pub num_neurons: usize,
+    /// Number of synapses
+    pub num_synapses: usize,
+    /// Simulation time step
+    pub dt: Milliseconds,
+    /// Configured run duration
+    pub t_max: Milliseconds,
+    /// Simulation time when the run ended
+    pub end_time: Milliseconds,
+    /// Why the run ended, if known
+    pub stop_reason: Option<StopReason>,
+}
+
+/// Spikes, weight log and metadata of one run.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunResults {
+    /// Run description
+    pub metadata: RunMetadata,
+    /// Emitted spikes
+    pub spikes: Vec<Spike>,
+    /// Logged weight samples
+    pub weights: Vec<WeightSample>,
+}
+
+/// Stop reasons by their JSON name.
+const STOP_REASONS: [(StopReason, &str); 7] = [
+    (StopReason::Completed, "Completed"),
+    (StopReason::MaxSpikes, "MaxSpikes"),
+    (StopReason::WeightConverged, "WeightConverged"),
+    (StopReason::TargetRate, "TargetRate"),
+    (StopReason::Custom, "Custom"),
+    (StopReason::Aborted, "Aborted"),
+    (StopReason::Cancelled, "Cancelled"),
+];
+
+impl RunResults {
+    /// Bundle the output of a run of `sim` with metadata taken from it.
+    pub fn new(
+        sim: &Simulation,
+        spikes: Vec<Spike>,
+        weights: Vec<WeightSample>,
+        stop_reason: Option<StopReason>,
+    ) -> Self {
+        Self {
+            metadata: RunMetadata {
+                num_neurons: sim.neurons().len(),
+                num_synapses: sim.synapses().len(),
+                dt: sim.config().dt,
+                t_max: sim.config().t_max,
+                end_time: sim.time(),
+                stop_reason,
+            },
+            spikes,
+            weights,
+        }
+    }
+
+    /// Serialize to a JSON document.
+    pub fn to_json(&self) -> String {
+        let m = &self.metadata;
+        let mut out = String::from("{\"metadata\":{");
+        let _ = write!(
+            out,
+            "\"num_neurons\":{},\"num_synapses\":{},\"dt_ms\":",
+            m.num_neurons, m.num_synapses
+        );
+        write_number(&mut out, m.dt.0);
+        out.push_str(",\"t_max_ms\":");
+        write_number(&mut out, m.t_max.0);
+        out.push_str(",\"end_time_ms\":");
+        write_number(&mut out, m.end_time.0);
+        out.push_str(",\"stop_reason\":");
+        match m.stop_reason.and_then(stop_reason_name) {
+            Some(name) => write_string(&mut out, name),
+            None => out.push_str("null"),
+        }
+
+        out.push_str("},\"spikes\":{");
+        column(
+            &mut out,
+            "neuron_id",
+            self.spikes.iter().map(|s| s.neuron_id as f64),
+        );
+        out.push(',');
+        column(&mut out, "time_ms", self.spikes.iter().map(|s| s.time.0));
+
+        out.push_str("},\"weights\":{");
+        column(&mut out, "time_ms", self.weights.iter().map(|w| w.0 .0));
+        out.push(',');
+        column(
+            &mut out,
+            "pre_neuron",
+            self.weights.iter().map(|w| w.1 as f64),
+        );
+        out.push(',');
+        column(
+            &mut out,
+            "post_neuron",
+            self.weights.iter().map(|w| w.2 as f64),
+        );
+        out.push(',');
+        column(&mut out, "weight", self.weights.iter().map(|w| w.3));
+        out.push_str("}}\n");
+        out
+    }
+
+    /// Parse a document written by `to_json`.
+    ///
+    /// Returns an `InvalidData` error if the document is malformed or
+    /// fields are missing, mistyped, or of unequal length.
+    pub fn from_json(text: &str) -> io::Result<Self> {
+        let doc = JsonParser::new(text).parse_document().map_err(invalid)?;
+        let metadata = doc
+            .get("metadata")
+            .ok_or_else(|| invalid("Missing `metadata`".to_string()))?;
+        let stop_reason = match metadata.get("stop_reason") {
+            Some(Json::String(name)) => Some(
+                STOP_REASONS
+                    .iter()
+                    .find(|(_, n)| n == name)
+                    .map(|(r, _)| *r)
+                    .ok_or_else(|| invalid(format!("Unknown stop reason `{name}`")))?,
+            ),
+            _ => None,
+        };
+        let metadata = RunMetadata {
+            num_neurons: index(number(metadata, "num_neurons", f64::NAN)?, "num_neurons")?,
+            num_synapses: index(number(metadata, "num_synapses", f64::NAN)?, "num_synapses")?,
+            dt: Milliseconds(number(metadata, "dt_ms", f64::NAN)?),
+            t_max: Milliseconds(number(metadata, "t_max_ms", f64::INFINITY)?),
+            end_time: Milliseconds(number(metadata, "end_time_ms", f64::NAN)?),
+            stop_reason,
+        };
+
+        let spikes = doc
+            .get("spikes")
+            .ok_or_else(|| invalid("Missing `spikes`".to_string()))?;
+        let ids = numbers(spikes, "neuron_id")?;
+        let times = numbers(spikes, "time_ms")?;
+        same_length(&[&ids, &times], "spikes")?;
+        let spikes = ids
+            .iter()
+            .zip(&times)
+            .map(|(&id, &t)| Ok(Spike::new(index(id, "neuron_id")?, Milliseconds(t))))
+            .collect::<io::Result<Vec<Spike>>>()?;
+
+        let weights = doc
+            .get("weights")
+            .ok_or_else(|| invalid("Missing `weights`".to_string()))?;
+        let times = numbers(weights, "time_ms")?;
+        let pre = numbers(weights, "pre_neuron")?;
+        let post = numbers(weights, "post_neuron")?;
+        let values = numbers(weights, "weight")?;
+        same_length(&[&times, &pre, &post, &values], "weights")?;
+        let weights = (0..times.len())
+            .map(|k| {
+                Ok((
+                    Milliseconds(times[k]),
+                    index(pre[k], "pre_neuron")?,
+                    index(post[k], "post_neuron")?,
+                    values[k],
+                ))
+            })
+            .collect::<io::Result<Vec<WeightSample>>>()?;
+
+        Ok(Self {
+            metadata,
+            spikes,
+            weights,
+        })
+    }
+
+    /// Write `to_json` output to a file, compressed if the path ends in
+    /// `.gz` or `.zst`.
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        writer.write_all(self.to_json().as_bytes())?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Read a file written by `write_json`, which must not be compressed.
+    pub fn read_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_json(&io::read_to_string(compression::open(path)?)?)
+    }
+}
+
+/// JSON name of a stop reason.
+fn stop_reason_name(reason: StopReason) -> Option<&'static str> {
+    STOP_REASONS
+        .iter()
+        .find(|(r, _)| *r == reason)
+        .map(|(_, n)| *n)
+}
+
+/// Write `"name":[v, ...]`.
+fn column(out: &mut String, name: &str, values: impl Iterator<Item = f64>) {
+    write_string(out, name);
+    out.push_str(":[");
+    for (k, v) in values.enumerate() {
+        if k > 0 {
+            out.push(',');
+        }
+        write_number(out, v);
+    }
+    out.push(']');
+}
+
+/// Numeric field `name` of `object`; `null` reads as `null_value`.
+fn number(object: &Json, name: &str, null_value: f64) -> io::Result<f64> {
+    match object.get(name) {
+        Some(Json::Number(x)) => Ok(*x),
+        Some(Json::Scalar) => Ok(null_value),
+        Some(_) => Err(invalid(format!("`{name}` is not a number"))),
+        None => Err(invalid(format!("Missing `{name}`"))),
+    }
+}
+
+/// Numeric array field `name` of `object`; `null` entries read as NaN.
+fn numbers(object: &Json, name: &str) -> io::Result<Vec<f64>> {
+    match object.get(name) {
+        Some(Json::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(k, item)| match item {
+                Json::Number(x) => Ok(*x),
+                Json::Scalar => Ok(f64::NAN),
+                _ => Err(invalid(format!("`{name}` entry {k} is not a number"))),
+            })
+            .collect(),
+        Some(_) => Err(invalid(format!("`{name}` is not an array"))),
+        None => Err(invalid(format!("Missing `{name}`"))),
+    }
+}
+
+/// Check that a value is a non-negative integer and convert it.
+fn index(value: f64, name: &str) -> io::Result<usize> {
+    if value >= 0.0 && value.fract() == 0.0 && value <= usize::MAX as f64 {
+        Ok(value as usize)
+    } else {
+        Err(invalid(format!(
+            "`{name}` value {value} is not a non-negative integer"
+        )))
+    }
+}
+
+/// Check that all columns of `table` have the same length.
+fn same_length(columns: &[&Vec<f64>], table: &str) -> io::Result<()> {
+    if columns.windows(2).all(|w| w[0].len() == w[1].len()) {
+        Ok(())
+    } else {
+        Err(invalid(format!("Columns of `{table}` differ in length")))
+    }
+}
@@ -0,0 +1,180 @@
+//! resume.rs
+//!
+//! ReSuMe learning of target spike trains.
+//!
+//! Remote Supervised Method (Ponulak and Kasiński, 2010) trains output
+//! neurons to fire at prescribed times. Every target spike potentiates and
+//! every actual spike depresses the synapses onto the neuron, each by a
+//! constant non-Hebbian amount plus an STDP-like term for input that
+//! arrived shortly before:
+//!
+//! ```text
+//! Δw_i = λ Σ_{t ∈ target} s_i(t) - λ Σ_{t ∈ actual} s_i(t)
+//! s_i(t) = a + A Σ_{t_j ≤ t} exp(-(t - t_j) / tau)
+//! ```
+//!
+//! where `t_j` are the arrival times of input at synapse `i`. Spikes at the
+//! right times cancel, so weights settle once the neuron reproduces its
+//! target. Here the update is applied once per trial. Progress is measured
+//! with the van Rossum distance between actual and target trains.
+
+use crate::analysis::van_rossum;
+use crate::learning::{LearningRule, TrialInputs};
+use crate::monitor::StepView;
+use crate::simulation::Simulation;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+
+/// Parameters of the ReSuMe rule.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReSuMeParams {
+    /// Learning rate λ
+    pub learning_rate: f64,
+    /// Non-Hebbian term `a`, adjusting the firing rate
+    pub non_hebbian: f64,
+    /// Amplitude `A` of the learning window
+    pub amplitude: f64,
+    /// Time constant of the learning window
+    pub tau: Milliseconds,
+    /// Lower weight bound
+    pub w_min: f64,
+    /// Upper weight bound
+    pub w_max: f64,
+    /// Time constant of the van Rossum distance reported as error
+    pub error_tau: Milliseconds,
+}
+
+impl Default for ReSuMeParams {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.01,
+            non_hebbian: 0.05,
+            amplitude: 1.0,
+            tau: Milliseconds(5.0),
+            w_min: f64::NEG_INFINITY,
+            w_max: f64::INFINITY,
+            error_tau: Milliseconds(10.0),
+        }
+    }
+}
+
+/// The ReSuMe rule for a set of output neurons.
+///
+/// The target of a trial is the spike times of each output, relative to the
+/// start of the trial and in the order the outputs were given.
+#[derive(Debug, Clone)]
+pub struct ReSuMe {
+    outputs: Vec<usize>,
+    params: ReSuMeParams,
+    inputs: TrialInputs,
+    /// Spike steps of each output in the last trial
+    fired: Vec<Vec<usize>>,
+}
+
+impl ReSuMe {
+    /// ReSuMe training the synapses onto `outputs`.
+    pub fn new(outputs: Vec<usize>, params: ReSuMeParams) -> Self {
+        let fired = vec![Vec::new(); outputs.len()];
+        Self {
+            outputs,
+            params,
+            inputs: TrialInputs::default(),
+            fired,
+        }
+    }
+
+    /// The output neurons.
+    pub fn outputs(&self) -> &[usize] {
+        &self.outputs
+    }
+
+    /// Targets for `train` from spikes of the output neurons; spikes of
+    /// other neurons are ignored.
+    pub fn targets(&self, spikes: &[Spike]) -> Vec<Vec<Milliseconds>> {
+        let mut targets = vec![Vec::new(); self.outputs.len()];
+        for spike in spikes {
+            if let Some(k) = self.outputs.iter().position(|&o| o == spike.neuron_id) {
+                targets[k].push(spike.time);
+            }
+        }
+        for train in &mut targets {
+            train.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        targets
+    }
+
+    /// Spike times of each output in the last trial, relative to its start.
+    pub fn output_trains(&self) -> Vec<Vec<Milliseconds>> {
+        let dt = self.inputs.dt;
+        self.fired
+            .iter()
+            .map(|steps| steps.iter().map(|&k| dt * k as f64).collect())
+            .collect()
+    }
+
+    /// van Rossum distance of each output from its target in the last trial.
+    pub fn distances(&self, target: &[Vec<Milliseconds>]) -> Vec<f64> {
+        self.output_trains()
+            .iter()
+            .enumerate()
+            .map(|(k, train)| {
+                let wanted = target.get(k).map_or(&[][..], Vec::as_slice);
+                van_rossum(train, wanted, self.params.error_tau)
+            })
+            .collect()
+    }
+}
+
+impl LearningRule for ReSuMe {
+    type Target = Vec<Vec<Milliseconds>>;
+
+    fn begin_trial(&mut self, sim: &Simulation) {
+        self.inputs = TrialInputs::begin(sim, &self.outputs);
+        self.fired = vec![Vec::new(); self.outputs.len()];
+    }
+
+    fn observe(&mut self, step: &StepView<'_>) {
+        self.inputs.observe(step);
+        let k_step = self.inputs.step_index(step);
+        for (fired, neuron) in self.fired.iter_mut().zip(&self.outputs) {
+            if step.fired.binary_search(neuron).is_ok() {
+                fired.push(k_step);
+            }
+        }
+    }
+
+    /// Sum over outputs of the van Rossum distance to the target train.
+    fn error(&self, target: &Vec<Vec<Milliseconds>>) -> f64 {
+        self.distances(target).iter().sum()
+    }
+
+    fn update(&mut self, sim: &mut Simulation, target: &Vec<Vec<Milliseconds>>) {
+        let dt = self.inputs.dt;
+        let params = &self.params;
+        let window = |arrivals: &[Milliseconds], t: Milliseconds| {
+            let stdp: f64 = arrivals
+                .iter()
+                .filter(|&&a| a <= t)
+                .map(|&a| ((a - t) / params.tau).exp())
+                .sum();
+            params.non_hebbian + params.amplitude * stdp
+        };
+        let actual = self.output_trains();
+        for syn in &self.inputs.synapses {
+            let arrivals: Vec<Milliseconds> = syn.arrivals.iter().map(|&k| dt * k as f64).collect();
+            let wanted = target.get(syn.output).map_or(&[][..], Vec::as_slice);
+            let potentiation: f64 = wanted.iter().map(|&t| window(&arrivals, t)).sum();
+            let depression: f64 = actual[syn.output]
+                .iter()
+                .map(|&t| window(&arrivals, t))
+                .sum();
+            let change = params.learning_rate * (potentiation - depression);
+            if change == 0.0 {
+                continue;
+            }
+            let weight = sim.synapses()[syn.index].weight + change;
+            sim.set_weight(syn.index, weight.clamp(params.w_min, params.w_max));
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! sequence.rs
+//!
+//! Sequence learning in recurrent assemblies, and cued replay.
+//!
+//! Remembering the order of events, and recalling the rest of a sequence
+//! from its beginning, is a basic memory benchmark. Here every element of
+//! a sequence is an assembly of neurons, and assemblies are connected
+//! all-to-all with plastic, delayed synapses. Training forces the
+//! assemblies of a sequence to fire one after another, `interval` apart.
+//! With a delay shorter than the interval, spikes of element `k` arrive
+//! just before element `k + 1` fires, so STDP potentiates the forward
+//! synapses and depresses the backward ones: the asymmetry of the
+//! learning window turns temporal order into directed connectivity.
+//! Synapses skipping ahead to element `k + 2` and beyond are potentiated
+//! too, but less, since their spikes arrive a whole interval earlier;
+//! this works best with an STDP time constant well below the interval.
+//! After every training presentation the summed input weight of each
+//! neuron is scaled down to at most `max_input_weight`, so that the
+//! forward synapses grow at the expense of the skipping ones instead of
+//! all saturating.
+//!
+//! Recall presents only the first elements of a sequence with plasticity
+//! switched off. Once forward weights are strong enough for one assembly
+//! to ignite the next, the rest of the sequence replays by itself,
+//! compressed in time: each step takes about one synaptic delay instead
+//! of the training interval, as in hippocampal replay. `Recall::completion`
+//! scores how much of the remainder was replayed in order.
+//!
+//! Two neuromodulators switch the network between the phases.
+//! `PLASTICITY_MODULATOR` is held at level 0 while training and at -1,
+//! which blocks STDP, during recall. `RECURRENCE_MODULATOR` does the
+//! opposite for synaptic input: at -1 during training it silences the
+//! recurrent synapses, so that the network fires exactly the presented
+//! sequence rather than replaying it early and learning from its own
+//! replay. Elements must be distinct within a sequence, and sequences that
+//! share an element interfere at that element.
+
+use crate::network::NetworkBuilder;
+use crate::neuromodulation::Modulator;
+use crate::neuron::NeuronParams;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Name of the modulator gating plasticity in a `SequenceNetwork`.
+pub const PLASTICITY_MODULATOR: &str = "sequence_plasticity";
+
+/// Name of the modulator gating recurrent input in a `SequenceNetwork`.
+pub const RECURRENCE_MODULATOR: &str = "sequence_recurrence";
+
+/// Layout and timing of a sequence network.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SequenceParams {
+    /// Number of assemblies, one per possible element
+    pub num_elements: usize,
+    /// Neurons per assembly
+    pub assembly_size: usize,
+    /// Initial weight of every synapse between assemblies
+    pub initial_weight: f64,
+    /// Delay of every synapse; shorter than `interval`
+    pub delay: Milliseconds,
+    /// Time between elements during training and cueing
+    pub interval: Milliseconds,
+    /// Upper bound of the summed input weight of each neuron
+    pub max_input_weight: f64,
+    /// Fraction of an assembly that must fire for it to count as active
+    pub activation_fraction: f64,
+}
+
+impl Default for SequenceParams {
+    fn default() -> Self {
+        Self {
+            num_elements: 10,
+            assembly_size: 20,
+            initial_weight: 0.005,
+            delay: Milliseconds(5.0),
+            interval: Milliseconds(20.0),
+            max_input_weight: 2.0,
+            activation_fraction: 0.5,
+        }
+    }
+}
+
+/// A recurrent network of assemblies trained on sequences.
+pub struct SequenceNetwork {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Neurons of each element
+    pub assemblies: Vec<Range<usize>>,
+    /// Parameters the network was built with
+    pub params: SequenceParams,
+}
+
+/// Build a sequence network.
+///
+/// Assemblies are registered as populations `element0`, `element1`, ...
+/// and every neuron connects to every neuron of the other assemblies with
+/// a plastic synapse following `stdp_params`; the two phase modulators
+/// are registered. Synaptic transmission is enabled on `config`.
+pub fn build_sequence_network(
+    params: &SequenceParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+    stdp_params: STDPParams,
+) -> SequenceNetwork {
+    let mut builder = NetworkBuilder::new();
+    let assemblies: Vec<Range<usize>> = (0..params.num_elements)
+        .map(|k| {
+            builder.add_population(
+                &format!("element{k}"),
+                params.assembly_size,
+                neuron_params.clone(),
+            )
+        })
+        .collect();
+    let mut synapses = Vec::new();
+    for (a, pre) in assemblies.iter().enumerate() {
+        for (b, post) in assemblies.iter().enumerate() {
+            if a == b {
+                continue;
+            }
+            for i in pre.clone() {
+                synapses.extend(
+                    post.clone()
+                        .map(|j| Synapse::with_delay(i, j, params.initial_weight, params.delay)),
+                );
+            }
+        }
+    }
+    builder.add_synapses(synapses);
+
+    config.synaptic_transmission = true;
+    let mut simulation = Simulation::from_network(builder.build(), config, stdp_params);
+    simulation.add_modulator(Modulator::new(PLASTICITY_MODULATOR));
+    simulation.add_modulator(Modulator {
+        plasticity_gain: 0.0,
+        excitability_gain: 1.0,
+        ..Modulator::new(RECURRENCE_MODULATOR)
+    });
+    SequenceNetwork {
+        simulation,
+        assemblies,
+        params: params.clone(),
+    }
+}
+
+impl SequenceNetwork {
+    /// Spikes forcing every neuron of each element of `sequence` to fire,
+    /// element `k` at `start + k × interval`.
+    ///
+    /// # Panics
+    /// Panics if an element has no assembly.
+    pub fn sequence_spikes(&self, sequence: &[usize], start: Milliseconds) -> Vec<Spike> {
+        sequence
+            .iter()
+            .enumerate()
+            .flat_map(|(k, &element)| {
+                let time = start + self.params.interval * k as f64;
+                self.assemblies[element]
+                    .clone()
+                    .map(move |i| Spike::new(i, time))
+            })
+            .collect()
+    }
+
+    /// Force `elements` from rest and run for `duration`; returns the
+    /// spikes.
+    ///
+    /// With `learn`, plasticity is on and recurrent input off; without,
+    /// the reverse.
+    pub fn present(
+        &mut self,
+        elements: &[usize],
+        learn: bool,
+        duration: Milliseconds,
+    ) -> Vec<Spike> {
+        let input = self.sequence_spikes(elements, Milliseconds::ZERO);
+        self.simulation.reset();
+        let (plasticity, recurrence) = if learn { (0.0, -1.0) } else { (-1.0, 0.0) };
+        self.simulation
+            .set_modulator(PLASTICITY_MODULATOR, plasticity);
+        self.simulation
+            .set_modulator(RECURRENCE_MODULATOR, recurrence);
+        self.simulation.inject_spikes(&input);
+        let mut spikes = Vec::new();
+        while self.simulation.time() < duration {
+            spikes.extend(self.simulation.step(|_, _| 0.0));
+        }
+        spikes
+    }
+
+    /// Present each of `sequences` `repetitions` times, in turn, with
+    /// plasticity on.
+    pub fn train(&mut self, sequences: &[Vec<usize>], repetitions: usize) {
+        for _ in 0..repetitions {
+            for sequence in sequences {
+                let duration = self.params.interval * (sequence.len() + 1) as f64;
+                self.present(sequence, true, duration);
+                self.normalize_inputs();
+            }
+        }
+    }
+
+    /// Scale down the input weights of every neuron whose summed input
+    /// exceeds `max_input_weight`.
+    fn normalize_inputs(&mut self) {
+        let mut totals = vec![0.0; self.simulation.neurons().len()];
+        for syn in self.simulation.synapses() {
+            totals[syn.post_neuron] += syn.weight;
+        }
+        let limit = self.params.max_input_weight;
+        for index in 0..self.simulation.synapses().len() {
+            let syn = &self.simulation.synapses()[index];
+            let total = totals[syn.post_neuron];
+            if total > limit {
+                let weight = syn.weight * limit / total;
+                self.simulation.set_weight(index, weight);
+            }
+        }
+    }
+
+    /// Present `cue`, the first elements of a sequence, with plasticity
+    /// off and let the network run on for long enough to replay every
+    /// remaining element.
+    pub fn cue(&mut self, cue: &[usize]) -> Recall {
+        let p = &self.params;
+        let duration =
+            p.interval * cue.len() as f64 + (p.delay + p.interval) * p.num_elements as f64;
+        let spikes = self.present(cue, false, duration);
+        Recall {
+            activations: activations(
+                &spikes,
+                &self.assemblies,
+                self.params.activation_fraction,
+                self.params.delay,
+            ),
+            cue: cue.to_vec(),
+        }
+    }
+
+    /// Cue the first `cue_len` elements of `sequence` and return the
+    /// completion of the rest.
+    pub fn completion(&mut self, sequence: &[usize], cue_len: usize) -> f64 {
+        let cue_len = cue_len.min(sequence.len());
+        self.cue(&sequence[..cue_len]).completion(sequence)
+    }
+
+    /// Mean weight from each assembly onto each other, `[pre][post]`.
+    pub fn assembly_weights(&self) -> Vec<Vec<f64>> {
+        let n = self.assemblies.len();
+        let element_of = |i: usize| self.assemblies.iter().position(|r| r.contains(&i));
+        let mut sums = vec![vec![0.0; n]; n];
+        let mut counts = vec![vec![0usize; n]; n];
+        for syn in self.simulation.synapses() {
+            if let (Some(a), Some(b)) = (element_of(syn.pre_neuron), element_of(syn.post_neuron)) {
+                sums[a][b] += syn.weight;
+                counts[a][b] += 1;
+            }
+        }
+        sums.iter()
+            .zip(&counts)
+            .map(|(row, n)| {
+                row.iter()
+                    .zip(n)
+                    .map(|(&s, &c)| if c == 0 { 0.0 } else { s / c as f64 })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Activations of the assemblies during a recall.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recall {
+    /// Element and onset of every activation, by onset
+    pub activations: Vec<(usize, Milliseconds)>,
+    /// Elements that were presented as the cue
+    pub cue: Vec<usize>,
+}
+
+impl Recall {
+    /// Elements recalled after the cue, in order of their first activation.
+    pub fn recalled(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        for &(element, _) in &self.activations {
+            if !self.cue.contains(&element) && !order.contains(&element) {
+                order.push(element);
+            }
+        }
+        order
+    }
+
+    /// Fraction of the elements of `sequence` after the cue that were
+    /// replayed in the right order, counting up to the first mistake; 1
+    /// when nothing remains to recall.
+    pub fn completion(&self, sequence: &[usize]) -> f64 {
+        let remaining = &sequence[self.cue.len().min(sequence.len())..];
+        if remaining.is_empty() {
+            return 1.0;
+        }
+        let correct = self
+            .recalled()
+            .iter()
+            .zip(remaining)
+            .take_while(|(a, b)| a == b)
+            .count();
+        correct as f64 / remaining.len() as f64
+    }
+}
+
+/// Activations of `assemblies` in `spikes`, by onset.
+///
+/// The spikes of an assembly are split into groups wherever the assembly
+/// is silent for longer than `gap`; a group counts as an activation when
+/// at least `min_fraction` of the assembly's neurons fire in it, and its
+/// onset is its first spike.
+pub fn activations(
+    spikes: &[Spike],
+    assemblies: &[Range<usize>],
+    min_fraction: f64,
+    gap: Milliseconds,
+) -> Vec<(usize, Milliseconds)> {
+    let mut result = Vec::new();
+    for (element, assembly) in assemblies.iter().enumerate() {
+        let mut own: Vec<&Spike> = spikes
+            .iter()
+            .filter(|s| assembly.contains(&s.neuron_id))
+            .collect();
+        own.sort_by(|a, b| a.time.0.total_cmp(&b.time.0));
+        let needed = (min_fraction * assembly.len() as f64).ceil().max(1.0) as usize;
+        let mut start = 0;
+        while start < own.len() {
+            let mut end = start + 1;
+            while end < own.len() && own[end].time - own[end - 1].time <= gap {
+                end += 1;
+            }
+            let mut neurons: Vec<usize> = own[start..end].iter().map(|s| s.neuron_id).collect();
+            neurons.sort_unstable();
+            neurons.dedup();
+            if neurons.len() >= needed {
+                result.push((element, own[start].time));
+            }
+            start = end;
+        }
+    }
+    result.sort_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+    result
+}
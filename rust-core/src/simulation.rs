@@ -7,155 +7,1621 @@
 //! While simplified, this structure mirrors how event-driven neuromorphic
 //! systems operate at a conceptual level.
 
-use crate::neuron::{Neuron, NeuronParams};
+use crate::cancellation::CancellationToken;
+use crate::compression;
+use crate::convergence::ConvergenceMonitor;
+use crate::encoding::sort_spikes;
+use crate::network::{
+    find_population, group_members, tag_group, NamedPopulation, Network, NetworkBuilder,
+    NeuronGroup,
+};
+use crate::neuromodulation::{Modulation, Modulator};
+use crate::neuron::{Neuron, NeuronParams, ResetMode};
+use crate::monitor::{Monitor, StepView};
+use crate::output::{OutputConfig, RunOutput};
+use crate::probe::VoltageProbe;
+use crate::progress::{ProgressCallback, ProgressHook};
+use crate::quantization::{FixedPoint, Quantization};
+use crate::recorder::SpikeRecorder;
+use crate::replay::{InputPlayer, InputRecorder, ReplayLog};
+use crate::rng::Rng;
+use crate::spatial::Position;
 use crate::spike::Spike;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use crate::synapse::Synapse;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::Scope;
+use std::time::{Duration, Instant};
+use crate::synapse::{Plasticity, Synapse};
 use crate::stdp::STDPParams;
+use crate::stopping::{StopCondition, StopMonitor, StopReason};
+use crate::topology::{validate, NetworkStats, ValidationIssue};
+use crate::units::Milliseconds;
+use crate::weights::WeightInit;
+
+/// A logged synaptic weight sample: `(time, pre, post, weight)`.
+pub type WeightSample = (Milliseconds, usize, usize, f64);
+
+/// Result of a run with stop conditions: spikes, weight log and stop reason.
+pub type RunOutcome = (Vec<Spike>, Vec<WeightSample>, StopReason);
 
 /// Simulation configuration parameters.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimulationConfig {
-    /// Simulation time step (ms)
-    pub dt: f64,
-    /// Total simulation duration (ms)
-    pub t_max: f64,
+    /// Simulation time step
+    pub dt: Milliseconds,
+    /// Total simulation duration
+    pub t_max: Milliseconds,
+    /// Number of neuron partitions `Simulation::run_parallel` updates on
+    /// separate threads.
+    ///
+    /// A value of `0` or `1` runs the serial engine. Larger values split the
+    /// neuron population into contiguous partitions whose spikes are merged
+    /// in neuron order each step, so results are bit-identical to serial.
+    /// Other runs, and all runs on wasm32, which has no threads, update
+    /// neurons serially.
+    pub num_partitions: usize,
+    /// Optional wall-clock pacing: simulated milliseconds per real millisecond.
+    ///
+    /// `Some(1.0)` runs in real time, `Some(2.0)` twice as fast and
+    /// `Some(0.5)` at half speed. `None` runs as fast as possible.
+    pub realtime_factor: Option<f64>,
+    /// Warmup duration at the start of the simulation.
+    ///
+    /// While `time < warmup` the network receives input as usual, but spikes
+    /// and weights are not recorded, so startup transients don't pollute
+    /// analyses.
+    pub warmup: Milliseconds,
+    /// Whether STDP is active during the warmup phase.
+    pub plasticity_during_warmup: bool,
+    /// Whether spikes are transmitted through synapses.
+    ///
+    /// When enabled, a presynaptic spike raises the postsynaptic membrane
+    /// potential by the synaptic weight once the synapse's delay (at least
+    /// one step) has elapsed. When disabled, neurons are driven by external
+    /// input only and synapses take part in learning but not in dynamics.
+    pub synaptic_transmission: bool,
+    /// Fixed-point formats of membrane potentials and weights.
+    ///
+    /// `None` simulates in floating point. Otherwise membrane potentials
+    /// are rounded after every update, and weights when the simulation is
+    /// created, when synapses are added and after every plasticity update.
+    pub quantization: Option<Quantization>,
+    /// How membrane potentials are reset after a spike.
+    ///
+    /// `ResetMode::Subtract` keeps the charge above threshold, which makes
+    /// firing rates proportional to input, as rate-coded networks converted
+    /// from ANNs require.
+    pub reset: ResetMode,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            dt: Milliseconds(0.1),
+            t_max: Milliseconds(100.0),
+            num_partitions: 1,
+            realtime_factor: None,
+            warmup: Milliseconds::ZERO,
+            plasticity_during_warmup: false,
+            synaptic_transmission: false,
+            quantization: None,
+            reset: ResetMode::ToReset,
+        }
+    }
 }
 
 /// A minimal spiking neural network simulation.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Simulation {
     neurons: Vec<Neuron>,
     synapses: Vec<Synapse>,
+    populations: Vec<NamedPopulation>,
     stdp_params: STDPParams,
+    plasticity_sets: Vec<STDPParams>,
+    positions: Vec<Option<Position>>,
+    groups: Vec<NeuronGroup>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    modulators: Vec<Modulator>,
     config: SimulationConfig,
-    time: f64,
+    time: Milliseconds,
+    /// Pending synaptic input per neuron, one entry per upcoming step
+    arrivals: VecDeque<Vec<f64>>,
+    /// Externally scheduled spikes not yet emitted, sorted by time
+    injected: VecDeque<Spike>,
+    /// Neurons whose own dynamics are overridden, sorted
+    #[cfg_attr(feature = "serde", serde(default))]
+    clamped: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    progress: Option<ProgressHook>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cancellation: Option<CancellationToken>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    monitors: Vec<Box<dyn Monitor>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    convergence: Vec<ConvergenceMonitor>,
+    /// Generator for stochastic weight rounding
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rounding_rng: Rng,
 }
 
 /// Snapshot of synaptic weight at a given time.
 #[derive(Debug)]
 struct WeightRecord {
-    time: f64,
+    time: Milliseconds,
     pre: usize,
     post: usize,
     weight: f64,
 }
 
+/// Destination of the spikes emitted during a run.
+enum SpikeSink<'a> {
+    /// Collect spikes and the weight log in memory
+    Collect,
+    /// Stream spikes into a recorder; no weight log
+    Spill(&'a mut SpikeRecorder),
+    /// Only count spikes; attached monitors do the recording
+    Discard,
+}
+
 impl Simulation {
-    /// Create a new simulation with identical neuron parameters.
-    pub fn new(
+    /// Create a new, fully connected simulation with identical neuron
+    /// parameters.
+    ///
+    /// `initial_weight` is either a plain weight or a `WeightInit`
+    /// distribution; random weights are drawn from a generator seeded with
+    /// `0`. Use `NetworkBuilder::seed` for other seeds.
+    pub fn new<W: Into<WeightInit>>(
         num_neurons: usize,
         neuron_params: NeuronParams,
         config: SimulationConfig,
         stdp_params: STDPParams,
-        initial_weight: f64,
+        initial_weight: W,
+    ) -> Self {
+        let mut builder = NetworkBuilder::new();
+        let all = builder.add_neurons(num_neurons, neuron_params);
+        builder.connect_all_to_all(all.clone(), all, initial_weight);
+        Self::from_network(builder.build(), config, stdp_params)
+    }
+
+    /// Create a simulation from a dense weight matrix.
+    ///
+    /// `weights[pre][post]` is the weight of the synapse from `pre` to
+    /// `post`; zero entries create no synapse. Nonzero diagonal entries
+    /// create self-connections.
+    ///
+    /// # Panics
+    /// Panics if the matrix is not square.
+    pub fn from_weight_matrix(
+        weights: &[Vec<f64>],
+        neuron_params: NeuronParams,
+        config: SimulationConfig,
+        stdp_params: STDPParams,
     ) -> Self {
-        let neurons = (0..num_neurons)
-            .map(|_| Neuron::new(neuron_params.clone()))
+        let n = weights.len();
+        assert!(
+            weights.iter().all(|row| row.len() == n),
+            "Weight matrix must be square"
+        );
+        let triplets: Vec<(usize, usize, f64)> = weights
+            .iter()
+            .enumerate()
+            .flat_map(|(pre, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &w)| w != 0.0)
+                    .map(move |(post, &w)| (pre, post, w))
+            })
             .collect();
+        Self::from_triplets(n, &triplets, neuron_params, config, stdp_params)
+    }
 
-        // Fully connected feedforward synapses (excluding self-connections)
-        let mut synapses = Vec::new();
-        for pre in 0..num_neurons {
-            for post in 0..num_neurons {
-                if pre != post {
-                    synapses.push(Synapse::new(pre, post, initial_weight));
-                }
-            }
-        }
+    /// Create a simulation from a sparse `(pre, post, weight)` list.
+    ///
+    /// # Panics
+    /// Panics if a triplet refers to a neuron outside `0..num_neurons`.
+    pub fn from_triplets(
+        num_neurons: usize,
+        triplets: &[(usize, usize, f64)],
+        neuron_params: NeuronParams,
+        config: SimulationConfig,
+        stdp_params: STDPParams,
+    ) -> Self {
+        let mut builder = NetworkBuilder::new();
+        builder.add_neurons(num_neurons, neuron_params);
+        builder.connect_all(triplets.iter().copied());
+        Self::from_network(builder.build(), config, stdp_params)
+    }
 
-        Self {
-            neurons,
-            synapses,
+    /// Create a simulation from a network built with `NetworkBuilder`.
+    pub fn from_network(
+        network: Network,
+        config: SimulationConfig,
+        stdp_params: STDPParams,
+    ) -> Self {
+        let rounding_rng = Rng::new(config.quantization.as_ref().map_or(0, |q| q.seed));
+        let mut sim = Self {
+            neurons: network.neurons,
+            synapses: network.synapses,
+            populations: network.populations,
             stdp_params,
+            plasticity_sets: network.plasticity_sets,
+            positions: network.positions,
+            groups: network.groups,
+            modulators: Vec::new(),
             config,
-            time: 0.0,
+            time: Milliseconds::ZERO,
+            arrivals: VecDeque::new(),
+            injected: VecDeque::new(),
+            clamped: Vec::new(),
+            progress: None,
+            cancellation: None,
+            monitors: Vec::new(),
+            convergence: Vec::new(),
+            rounding_rng,
+        };
+        if let Some(q) = &sim.config.quantization {
+            for neuron in sim.neurons.iter_mut() {
+                neuron.v_mem = q.state.quantize(neuron.v_mem);
+            }
+            for syn in sim.synapses.iter_mut() {
+                syn.weight = q.weights.quantize(syn.weight);
+            }
+        }
+        sim
+    }
+
+    /// Neurons in the network, indexed by neuron id.
+    pub fn neurons(&self) -> &[Neuron] {
+        &self.neurons
+    }
+
+    /// Synapses in the network.
+    pub fn synapses(&self) -> &[Synapse] {
+        &self.synapses
+    }
+
+    /// Named populations, in creation order.
+    pub fn populations(&self) -> &[NamedPopulation] {
+        &self.populations
+    }
+
+    /// Neuron index range of the population called `name`.
+    pub fn population(&self, name: &str) -> Option<Range<usize>> {
+        find_population(&self.populations, name)
+    }
+
+    /// Indices of the synapses from population `pre` to population `post`,
+    /// or `None` if either population is unknown.
+    pub fn projection_synapses(&self, pre: &str, post: &str) -> Option<Vec<usize>> {
+        let pre = self.population(pre)?;
+        let post = self.population(post)?;
+        Some(
+            self.synapses
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| pre.contains(&s.pre_neuron) && post.contains(&s.post_neuron))
+                .map(|(k, _)| k)
+                .collect(),
+        )
+    }
+
+    /// Tagged neuron groups, in creation order.
+    pub fn groups(&self) -> &[NeuronGroup] {
+        &self.groups
+    }
+
+    /// Tag `neurons` with the group label `name`, extending the group if it
+    /// already exists.
+    ///
+    /// # Panics
+    /// Panics if any index is outside the network.
+    pub fn tag<I>(&mut self, name: &str, neurons: I)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let n = self.neurons.len();
+        let neurons: Vec<usize> = neurons.into_iter().collect();
+        assert!(
+            neurons.iter().all(|&i| i < n),
+            "Cannot tag a neuron outside 0..{n}"
+        );
+        tag_group(&mut self.groups, name, neurons);
+    }
+
+    /// Sorted indices of the neurons labelled `name`, either as a population
+    /// or as a tagged group.
+    pub fn neurons_in(&self, name: &str) -> Option<Vec<usize>> {
+        group_members(&self.populations, &self.groups, name)
+    }
+
+    /// Spikes emitted by neurons labelled `name`, in their original order.
+    ///
+    /// Returns an empty vector for unknown labels.
+    pub fn spikes_of_group(&self, name: &str, spikes: &[Spike]) -> Vec<Spike> {
+        let members = self.neurons_in(name).unwrap_or_default();
+        spikes
+            .iter()
+            .filter(|s| members.binary_search(&s.neuron_id).is_ok())
+            .copied()
+            .collect()
+    }
+
+    /// Labels of every population and group containing `neuron`, sorted.
+    pub fn groups_of(&self, neuron: usize) -> Vec<&str> {
+        let populations = self
+            .populations
+            .iter()
+            .filter(|p| p.range.contains(&neuron))
+            .map(|p| p.name.as_str());
+        let groups = self
+            .groups
+            .iter()
+            .filter(|g| g.neurons.binary_search(&neuron).is_ok())
+            .map(|g| g.name.as_str());
+        let mut labels: Vec<&str> = populations.chain(groups).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        labels
+    }
+
+    /// Spatial position of each neuron, if one was assigned.
+    pub fn positions(&self) -> &[Option<Position>] {
+        &self.positions
+    }
+
+    /// Simulation configuration.
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// STDP parameters of synapses with `Plasticity::Global`.
+    pub fn stdp_params(&self) -> &STDPParams {
+        &self.stdp_params
+    }
+
+    /// Plasticity parameter sets referenced by `Plasticity::Set`.
+    pub fn plasticity_sets(&self) -> &[STDPParams] {
+        &self.plasticity_sets
+    }
+
+    /// Current simulation time.
+    pub fn time(&self) -> Milliseconds {
+        self.time
+    }
+
+    /// Return to time zero with every neuron at rest and no synaptic input
+    /// or injected spikes pending, e.g. between the samples of a
+    /// classification task. Synapses keep their weights but forget the
+    /// spike times STDP pairs with, and clamped neurons stay clamped.
+    pub fn reset(&mut self) {
+        let format = self.config.quantization.as_ref().map(|q| q.state);
+        for neuron in self.neurons.iter_mut() {
+            neuron.v_mem = match &format {
+                Some(format) => format.quantize(neuron.params.v_rest),
+                None => neuron.params.v_rest,
+            };
+        }
+        for syn in self.synapses.iter_mut() {
+            syn.last_pre_spike = None;
+            syn.last_post_spike = None;
+        }
+        self.arrivals.clear();
+        self.injected.clear();
+        self.time = Milliseconds::ZERO;
+    }
+
+    /// Add a neuron at rest and return its index.
+    ///
+    /// The new neuron has no synapses and belongs to no population or group.
+    pub fn add_neuron(&mut self, params: NeuronParams) -> usize {
+        let mut neuron = Neuron::new(params);
+        if let Some(q) = &self.config.quantization {
+            neuron.v_mem = q.state.quantize(neuron.v_mem);
+        }
+        self.neurons.push(neuron);
+        self.positions.push(None);
+        for arrivals in self.arrivals.iter_mut() {
+            arrivals.push(0.0);
+        }
+        self.neurons.len() - 1
+    }
+
+    /// Remove neuron `index` together with all of its synapses.
+    ///
+    /// Neurons with a higher index shift down by one; synapses, population
+    /// ranges, groups, pending synaptic input and attached monitors are
+    /// renumbered to match.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn remove_neuron(&mut self, index: usize) -> Neuron {
+        assert!(index < self.neurons.len(), "Neuron {index} does not exist");
+        let neuron = self.neurons.remove(index);
+        self.positions.remove(index);
+        for arrivals in self.arrivals.iter_mut() {
+            arrivals.remove(index);
+        }
+        self.injected.retain(|s| s.neuron_id != index);
+        for spike in self.injected.iter_mut() {
+            if spike.neuron_id > index {
+                spike.neuron_id -= 1;
+            }
+        }
+
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        self.clamped.retain(|&i| i != index);
+        for i in self.clamped.iter_mut() {
+            *i = shift(*i);
+        }
+        let removed: Vec<usize> = (0..self.synapses.len())
+            .filter(|&k| {
+                let s = &self.synapses[k];
+                s.pre_neuron == index || s.post_neuron == index
+            })
+            .collect();
+        for &k in removed.iter().rev() {
+            for monitor in self.monitors.iter_mut() {
+                monitor.synapse_removed(k);
+            }
+        }
+        for monitor in self.monitors.iter_mut() {
+            monitor.neuron_removed(index);
+        }
+        self.synapses.retain(|s| s.pre_neuron != index && s.post_neuron != index);
+        for syn in self.synapses.iter_mut() {
+            syn.pre_neuron = shift(syn.pre_neuron);
+            syn.post_neuron = shift(syn.post_neuron);
+        }
+        for p in self.populations.iter_mut() {
+            if p.range.start > index {
+                p.range = p.range.start - 1..p.range.end - 1;
+            } else if p.range.contains(&index) {
+                p.range.end -= 1;
+            }
+        }
+        for g in self.groups.iter_mut() {
+            g.neurons.retain(|&i| i != index);
+            for i in g.neurons.iter_mut() {
+                *i = shift(*i);
+            }
+        }
+        neuron
+    }
+
+    /// Add a synapse and return its index. With quantization, its weight
+    /// is rounded to the weight format.
+    ///
+    /// # Panics
+    /// Panics if the synapse refers to a neuron or plasticity set that does
+    /// not exist.
+    pub fn add_synapse(&mut self, mut synapse: Synapse) -> usize {
+        let n = self.neurons.len();
+        assert!(
+            synapse.pre_neuron < n && synapse.post_neuron < n,
+            "Synapse {} -> {} references a neuron outside 0..{}",
+            synapse.pre_neuron,
+            synapse.post_neuron,
+            n
+        );
+        if let Plasticity::Set(k) = synapse.plasticity {
+            assert!(k < self.plasticity_sets.len(), "Unknown plasticity set {k}");
+        }
+        if let Some(q) = &self.config.quantization {
+            synapse.weight = q.weights.quantize(synapse.weight);
         }
+        self.synapses.push(synapse);
+        self.synapses.len() - 1
+    }
+
+    /// Remove and return synapse `index`.
+    ///
+    /// Synapses with a higher index shift down by one, and attached
+    /// monitors are renumbered to match. Input already in transit from
+    /// this synapse is still delivered.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn remove_synapse(&mut self, index: usize) -> Synapse {
+        assert!(index < self.synapses.len(), "Synapse {index} does not exist");
+        for monitor in self.monitors.iter_mut() {
+            monitor.synapse_removed(index);
+        }
+        self.synapses.remove(index)
+    }
+
+    /// Index of the first synapse from `pre` to `post`, if any.
+    pub fn find_synapse(&self, pre: usize, post: usize) -> Option<usize> {
+        self.synapses
+            .iter()
+            .position(|s| s.pre_neuron == pre && s.post_neuron == post)
+    }
+
+    /// Set the weight of synapse `index`. With quantization, the weight is
+    /// rounded to the weight format.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn set_weight(&mut self, index: usize, weight: f64) {
+        assert!(index < self.synapses.len(), "Synapse {index} does not exist");
+        self.synapses[index].weight = match &self.config.quantization {
+            Some(q) => q.weights.quantize(weight),
+            None => weight,
+        };
+    }
+
+    /// Register a neuromodulator.
+    ///
+    /// # Panics
+    /// Panics if a modulator with the same name is already registered.
+    pub fn add_modulator(&mut self, modulator: Modulator) {
+        assert!(
+            self.modulator(&modulator.name).is_none(),
+            "Modulator {} already exists",
+            modulator.name
+        );
+        self.modulators.push(modulator);
+    }
+
+    /// Set the level of modulator `name` from the next step on.
+    ///
+    /// # Panics
+    /// Panics if no modulator `name` is registered.
+    pub fn set_modulator(&mut self, name: &str, level: f64) {
+        let modulator = self
+            .modulators
+            .iter_mut()
+            .find(|m| m.name == name)
+            .unwrap_or_else(|| panic!("Unknown modulator {name}"));
+        modulator.level = level;
+    }
+
+    /// Current level of modulator `name`.
+    pub fn modulator(&self, name: &str) -> Option<f64> {
+        self.modulators.iter().find(|m| m.name == name).map(|m| m.level)
+    }
+
+    /// Registered neuromodulators, in registration order.
+    pub fn modulators(&self) -> &[Modulator] {
+        &self.modulators
+    }
+
+    /// Connectivity and weight statistics of the current network.
+    pub fn stats(&self) -> NetworkStats {
+        NetworkStats::compute(self.neurons.len(), &self.synapses)
+    }
+
+    /// Check the network for NaN or out-of-bounds weights and other
+    /// invalid synapses before running.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let issues = validate(
+            self.neurons.len(),
+            &self.synapses,
+            &self.stdp_params,
+            &self.plasticity_sets,
+        );
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Schedule externally generated spikes, e.g. from an encoder.
+    ///
+    /// Each spike forces its neuron to fire during the step containing the
+    /// spike time: the spike is recorded, drives plasticity and synaptic
+    /// transmission like a natural spike, and resets the membrane
+    /// potential. Spikes scheduled before the current time fire on the next
+    /// step.
+    ///
+    /// # Panics
+    /// Panics if a spike targets a neuron outside the network.
+    pub fn inject_spikes(&mut self, spikes: &[Spike]) {
+        let n = self.neurons.len();
+        assert!(
+            spikes.iter().all(|s| s.neuron_id < n),
+            "Cannot inject a spike into a neuron outside 0..{n}"
+        );
+        let mut merged: Vec<Spike> = self.injected.drain(..).collect();
+        merged.extend_from_slice(spikes);
+        sort_spikes(&mut merged);
+        self.injected = merged.into();
+    }
+
+    /// Number of injected spikes that have not been emitted yet.
+    pub fn pending_injected_spikes(&self) -> usize {
+        self.injected.len()
+    }
+
+    /// Clamp `neurons`, overriding their own dynamics.
+    ///
+    /// A clamped neuron fires only when an injected spike forces it to:
+    /// spikes it would fire on its own are suppressed and its membrane
+    /// potential is held at rest in between. Clamping lasts until
+    /// `unclamp` or `clear_clamps`, across `reset`.
+    ///
+    /// # Panics
+    /// Panics if a neuron is outside the network.
+    pub fn clamp(&mut self, neurons: &[usize]) {
+        let n = self.neurons.len();
+        assert!(
+            neurons.iter().all(|&i| i < n),
+            "Cannot clamp a neuron outside 0..{n}"
+        );
+        self.clamped.extend_from_slice(neurons);
+        self.clamped.sort_unstable();
+        self.clamped.dedup();
+    }
+
+    /// Clamp the neurons of `spikes` and schedule `spikes`, so that those
+    /// neurons fire at exactly the given times and at no other.
+    ///
+    /// # Panics
+    /// Panics if a spike targets a neuron outside the network.
+    pub fn clamp_spikes(&mut self, spikes: &[Spike]) {
+        let neurons: Vec<usize> = spikes.iter().map(|s| s.neuron_id).collect();
+        self.clamp(&neurons);
+        self.inject_spikes(spikes);
+    }
+
+    /// Release `neurons` back to their own dynamics.
+    pub fn unclamp(&mut self, neurons: &[usize]) {
+        self.clamped.retain(|i| !neurons.contains(i));
+    }
+
+    /// Release every clamped neuron.
+    pub fn clear_clamps(&mut self) {
+        self.clamped.clear();
+    }
+
+    /// Clamped neurons, sorted.
+    pub fn clamped(&self) -> &[usize] {
+        &self.clamped
+    }
+
+    /// Whether neuron `index` is clamped.
+    pub fn is_clamped(&self, index: usize) -> bool {
+        self.clamped.binary_search(&index).is_ok()
+    }
+
+    /// Register a progress callback invoked every `interval` of simulated
+    /// time during `run`.
+    ///
+    /// The callback receives `(current_time, t_max, spikes_so_far)` and may
+    /// return `false` to abort the run with `StopReason::Aborted`.
+    ///
+    /// # Panics
+    /// Panics if `interval` is not positive.
+    pub fn set_progress_callback(&mut self, interval: Milliseconds, callback: ProgressCallback) {
+        self.progress = Some(ProgressHook::new(interval, callback));
+    }
+
+    /// Remove a previously registered progress callback.
+    pub fn clear_progress_callback(&mut self) {
+        self.progress = None;
+    }
+
+    /// Attach a cancellation token checked once per simulation step.
+    ///
+    /// Keep a clone of the token and call `cancel` from any thread to stop
+    /// the run with `StopReason::Cancelled`.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    /// Attach a monitor that is shown the network state after every step
+    /// of `run` and `step`, except during the warmup phase.
+    ///
+    /// The provided monitors are shared handles; keep a clone to read the
+    /// recorded data afterwards.
+    ///
+    /// # Panics
+    /// Panics if the monitor selects a neuron or synapse outside the
+    /// network.
+    pub fn add_monitor<M: Monitor + 'static>(&mut self, monitor: M) {
+        let (n, m) = (self.neurons.len(), self.synapses.len());
+        assert!(
+            monitor.max_neuron().is_none_or(|i| i < n),
+            "Cannot monitor a neuron outside 0..{n}"
+        );
+        assert!(
+            monitor.max_synapse().is_none_or(|k| k < m),
+            "Cannot monitor a synapse outside 0..{m}"
+        );
+        self.monitors.push(Box::new(monitor));
+    }
+
+    /// Attach a voltage probe that samples membrane potentials at the end
+    /// of each step, after spike resets.
+    ///
+    /// # Panics
+    /// Panics if the probe refers to a neuron outside the network.
+    pub fn add_voltage_probe(&mut self, probe: VoltageProbe) {
+        self.add_monitor(probe);
+    }
+
+    /// Attach a convergence monitor. Unlike `add_monitor`, this lets a
+    /// monitor with `ConvergenceAction::Freeze` make converged projections
+    /// static.
+    pub fn add_convergence_monitor(&mut self, monitor: ConvergenceMonitor) {
+        self.convergence.push(monitor.clone());
+        self.add_monitor(monitor);
+    }
+
+    /// Detach all monitors, including voltage probes and convergence
+    /// monitors.
+    pub fn clear_monitors(&mut self) {
+        self.monitors.clear();
+        self.convergence.clear();
     }
 
     /// Run the simulation and return all emitted spike events and weight log.
     ///
     /// `input_current_fn` provides external input current as a function
     /// of neuron index and simulation time.
-    pub fn run<F>(&mut self, input_current_fn: F) -> (Vec<Spike>, Vec<(f64, usize, usize, f64)>)
+    pub fn run<F>(&mut self, input_current_fn: F) -> (Vec<Spike>, Vec<WeightSample>)
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let (spikes, weights, _) = self.run_until(input_current_fn, &[]);
+        (spikes, weights)
+    }
+
+    /// Run the simulation until `t_max` or until any stop condition is met.
+    ///
+    /// Conditions are checked after every step. Returns the spikes and weight
+    /// log collected so far together with the reason the run ended.
+    pub fn run_until<F>(
+        &mut self,
+        input_current_fn: F,
+        conditions: &[StopCondition],
+    ) -> RunOutcome
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        self.run_inner(&input_current_fn, None, conditions, SpikeSink::Collect)
+            .expect("Run without a spike recorder performs no I/O")
+    }
+
+    /// Like `run_until`, but with neuron updates distributed over
+    /// `config.num_partitions` threads, which requires `input_current_fn`
+    /// to be `Sync`.
+    ///
+    /// The threads are started once and kept for the whole run. Results are
+    /// bit-identical to `run_until`.
+    pub fn run_parallel<F>(
+        &mut self,
+        input_current_fn: F,
+        conditions: &[StopCondition],
+    ) -> RunOutcome
+    where
+        F: Fn(usize, Milliseconds) -> f64 + Sync,
+    {
+        let partitions = self.config.num_partitions.max(1).min(self.neurons.len().max(1));
+        // wasm32 has no threads, so partitions are updated serially there
+        if partitions == 1 || cfg!(target_arch = "wasm32") {
+            return self.run_until(input_current_fn, conditions);
+        }
+        let input_current_fn = &input_current_fn;
+        std::thread::scope(|scope| {
+            let pool = PartitionPool::new(scope, partitions, input_current_fn);
+            self.run_inner(input_current_fn, Some(&pool), conditions, SpikeSink::Collect)
+        })
+        .expect("Run without a spike recorder performs no I/O")
+    }
+
+    /// Run the simulation while streaming spikes into a bounded-memory
+    /// `SpikeRecorder`.
+    ///
+    /// Only the recorder's most recent spikes are kept in memory; older ones
+    /// are spilled to its file as the run progresses. The weight log is not
+    /// collected in this mode; final weights are available via `synapses`.
+    ///
+    /// `MaxSpikes` counts every recorded spike, but `TargetRate` and
+    /// `Custom` conditions only see the spikes still held in memory, so the
+    /// recorder capacity should cover their window.
+    pub fn run_spilled<F>(
+        &mut self,
+        input_current_fn: F,
+        conditions: &[StopCondition],
+        recorder: &mut SpikeRecorder,
+    ) -> io::Result<StopReason>
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let (_, _, reason) =
+            self.run_inner(&input_current_fn, None, conditions, SpikeSink::Spill(recorder))?;
+        recorder.flush()?;
+        Ok(reason)
+    }
+
+    /// Run the simulation, leaving all recording to attached monitors.
+    ///
+    /// Neither spikes nor a weight log are collected by the simulation
+    /// itself, so memory use is bounded by what the monitors keep. Stop
+    /// conditions still apply, but `TargetRate` and `Custom` conditions see
+    /// no spikes; `MaxSpikes` counts every spike emitted after warmup.
+    pub fn run_monitored<F>(
+        &mut self,
+        input_current_fn: F,
+        conditions: &[StopCondition],
+    ) -> StopReason
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let (_, _, reason) = self
+            .run_inner(&input_current_fn, None, conditions, SpikeSink::Discard)
+            .expect("Run without a spike recorder performs no I/O");
+        reason
+    }
+
+    /// Shared simulation loop. Spikes and the weight log are handled as
+    /// selected by `sink`; neurons are updated by `pool` if given.
+    fn run_inner<F>(
+        &mut self,
+        input_current_fn: &F,
+        pool: Option<&PartitionPool>,
+        conditions: &[StopCondition],
+        mut sink: SpikeSink<'_>,
+    ) -> io::Result<RunOutcome>
     where
-        F: Fn(usize, f64) -> f64,
+        F: Fn(usize, Milliseconds) -> f64,
     {
         let mut spikes: Vec<Spike> = Vec::new();
         let mut weight_log: Vec<WeightRecord> = Vec::new();
+        // Only read the clock when pacing; it is unavailable on wasm32
+        let wall_start = self.config.realtime_factor.map(|_| Instant::now());
+        let sim_start = self.time;
+        let mut monitor = StopMonitor::new(conditions, self.time, &self.synapses);
+        let mut reason = StopReason::Completed;
+        let log_weights = matches!(sink, SpikeSink::Collect);
+        let mut discarded = 0;
+        let mut steps = 0usize;
+        if let Some(hook) = self.progress.as_mut() {
+            hook.reset(self.time);
+        }
+        let _span = trace_span!(
+            Info,
+            "run",
+            t_start = self.time,
+            t_max = self.config.t_max,
+            neurons = self.neurons.len(),
+            synapses = self.synapses.len(),
+        );
 
         while self.time < self.config.t_max {
-            for (i, neuron) in self.neurons.iter_mut().enumerate() {
-                let input_current = input_current_fn(i, self.time);
-                let fired = neuron.step(input_current, self.config.dt);
-
-                if fired {
-                    spikes.push(Spike::new(i, self.time));
+            if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+                reason = StopReason::Cancelled;
+                break;
+            }
 
-                    // Notify synapses of spike events
-                    for syn in self.synapses.iter_mut() {
-                        if syn.pre_neuron == i {
-                            syn.on_pre_spike(self.time, &self.stdp_params);
-                        }
-                        if syn.post_neuron == i {
-                            syn.on_post_spike(self.time, &self.stdp_params);
-                        }
-                    }
+            let time = self.time;
+            let (fired, warming_up) = self.advance(input_current_fn, pool, |t, synapses| {
+                if log_weights {
                     // Log synaptic weights after learning event
-                    for syn in self.synapses.iter() {
-                        weight_log.push(WeightRecord {
-                            time: self.time,
-                            pre: syn.pre_neuron,
-                            post: syn.post_neuron,
-                            weight: syn.weight,
-                        });
+                    weight_log.extend(synapses.iter().map(|syn| WeightRecord {
+                        time: t,
+                        pre: syn.pre_neuron,
+                        post: syn.post_neuron,
+                        weight: syn.weight,
+                    }));
+                }
+            });
+            steps += 1;
+
+            if !warming_up {
+                for i in fired {
+                    let spike = Spike::new(i, time);
+                    match &mut sink {
+                        SpikeSink::Collect => spikes.push(spike),
+                        SpikeSink::Spill(r) => r.record(spike)?,
+                        SpikeSink::Discard => discarded += 1,
                     }
                 }
             }
 
-            self.time += self.config.dt;
+            if let (Some(factor), Some(wall_start)) = (self.config.realtime_factor, wall_start) {
+                pace_to_wall_clock(wall_start, self.time - sim_start, factor);
+            }
+
+            let total_spikes = match &sink {
+                SpikeSink::Collect => spikes.len(),
+                SpikeSink::Spill(r) => r.total(),
+                SpikeSink::Discard => discarded,
+            };
+
+            if let Some(hook) = self.progress.as_mut() {
+                if !hook.report(self.time, self.config.t_max, total_spikes) {
+                    reason = StopReason::Aborted;
+                    break;
+                }
+            }
+
+            let visible: &[Spike] = match &mut sink {
+                SpikeSink::Collect => &spikes,
+                SpikeSink::Spill(r) => r.recent_slice(),
+                SpikeSink::Discard => &[],
+            };
+            if let Some(r) = monitor.check(
+                self.time,
+                visible,
+                total_spikes,
+                &self.synapses,
+                self.neurons.len(),
+            ) {
+                reason = r;
+                break;
+            }
         }
 
-        (
+        trace_event!(
+            Info,
+            "run finished",
+            reason = format!("{reason:?}"),
+            steps = steps,
+            spikes = match &sink {
+                SpikeSink::Collect => spikes.len(),
+                SpikeSink::Spill(r) => r.total(),
+                SpikeSink::Discard => discarded,
+            },
+        );
+
+        Ok((
             spikes,
             weight_log
                 .into_iter()
                 .map(|r| (r.time, r.pre, r.post, r.weight))
                 .collect(),
-        )
+            reason,
+        ))
     }
 
-    /// Write spike events to a CSV file for downstream analysis.
-    pub fn write_spikes_to_csv(&self, spikes: &[Spike], path: &str) {
-        let file = File::create(path)
-            .expect("Failed to create spike CSV file");
-        let mut writer = BufWriter::new(file);
+    /// Advance the simulation by a single time step and return the spikes
+    /// emitted during it.
+    ///
+    /// This is the incremental counterpart of `run` for driving a network
+    /// from a live source: inject spikes or update the input between calls.
+    /// `t_max`, stop conditions, pacing and progress callbacks are not
+    /// consulted, and no weight log is collected. Spikes emitted during the
+    /// warmup phase are not returned.
+    pub fn step<F>(&mut self, input_current_fn: F) -> Vec<Spike>
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let time = self.time;
+        let (fired, warming_up) = self.advance(&input_current_fn, None, |_, _| {});
+        if warming_up {
+            return Vec::new();
+        }
+        fired.into_iter().map(|i| Spike::new(i, time)).collect()
+    }
 
-        // CSV header
-        writeln!(writer, "neuron_id,time_ms")
-            .expect("Failed to write CSV header");
+    /// Perform one step of the simulation loop: deliver synaptic input,
+    /// update neurons, hold clamped neurons, fire injected spikes,
+    /// transmit, apply STDP and advance the clock.
+    ///
+    /// `on_learning` is called with the step time and the synapses after the
+    /// plasticity update for each fired neuron outside warmup, and attached
+    /// monitors are shown the resulting state. Returns the neurons that
+    /// fired and whether the step was part of the warmup.
+    fn advance<F, L>(
+        &mut self,
+        input_current_fn: &F,
+        pool: Option<&PartitionPool>,
+        mut on_learning: L,
+    ) -> (Vec<usize>, bool)
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+        L: FnMut(Milliseconds, &[Synapse]),
+    {
+        let modulation = Modulation::compute(
+            &self.modulators,
+            self.neurons.len(),
+            &self.populations,
+            &self.groups,
+        );
+        {
+            let _span = trace_span!(Trace, "deliver");
+            self.deliver_arrivals(modulation.as_ref());
+        }
+        let fired = {
+            let _span = trace_span!(Trace, "neurons");
+            let fired = self.step_neurons(input_current_fn, pool, modulation.as_ref());
+            let fired = self.hold_clamped(fired);
+            self.fire_injected(fired)
+        };
+        if self.config.synaptic_transmission {
+            let _span = trace_span!(Trace, "transmit", fired = fired.len());
+            self.transmit(&fired);
+        }
 
-        for spike in spikes {
-            writeln!(writer, "{},{}", spike.neuron_id, spike.time)
-                .expect("Failed to write spike row");
+        let warming_up = self.time < self.config.warmup;
+        let plastic = !warming_up || self.config.plasticity_during_warmup;
+
+        if plastic && !fired.is_empty() {
+            let _span = trace_span!(Trace, "plasticity");
+            let quantization = self.config.quantization.as_ref();
+            for &i in &fired {
+                // Notify synapses of spike events
+                for syn in self.synapses.iter_mut() {
+                    if syn.pre_neuron != i && syn.post_neuron != i {
+                        continue;
+                    }
+                    let params = match syn.plasticity {
+                        Plasticity::Global => &self.stdp_params,
+                        Plasticity::Static => continue,
+                        Plasticity::Set(k) => &self.plasticity_sets[k],
+                    };
+                    let modulated;
+                    let params = match &modulation {
+                        Some(m) if m.plasticity[syn.post_neuron] != 1.0 => {
+                            let factor = m.plasticity[syn.post_neuron];
+                            modulated = STDPParams {
+                                a_plus: params.a_plus * factor,
+                                a_minus: params.a_minus * factor,
+                                ..params.clone()
+                            };
+                            &modulated
+                        }
+                        _ => params,
+                    };
+                    if syn.pre_neuron == i {
+                        syn.on_pre_spike(self.time, params);
+                    }
+                    if syn.post_neuron == i {
+                        syn.on_post_spike(self.time, params);
+                    }
+                    if let Some(q) = quantization {
+                        if syn.pre_neuron == i || syn.post_neuron == i {
+                            syn.weight = q.weight(syn.weight, &mut self.rounding_rng);
+                        }
+                    }
+                }
+                if !warming_up {
+                    on_learning(self.time, &self.synapses);
+                }
+            }
+        }
+
+        if !warming_up && !self.monitors.is_empty() {
+            let _span = trace_span!(Trace, "monitors");
+            let view = StepView {
+                time: self.time,
+                dt: self.config.dt,
+                fired: &fired,
+                neurons: &self.neurons,
+                synapses: &self.synapses,
+            };
+            for monitor in self.monitors.iter_mut() {
+                monitor.record(&view);
+            }
+            for monitor in &self.convergence {
+                for k in monitor.take_frozen() {
+                    if let Some(syn) = self.synapses.get_mut(k) {
+                        syn.plasticity = Plasticity::Static;
+                    }
+                }
+            }
+        }
+
+        for modulator in self.modulators.iter_mut() {
+            modulator.decay(self.config.dt);
+        }
+        self.time += self.config.dt;
+        (fired, warming_up)
+    }
+
+    /// Run the simulation while recording a `ReplayLog`.
+    ///
+    /// The log captures the starting state and every external input value,
+    /// so `replay` can later reproduce this run exactly.
+    pub fn run_recorded<F>(
+        &mut self,
+        input_current_fn: F,
+        conditions: &[StopCondition],
+    ) -> (RunOutcome, ReplayLog)
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let start_time = self.time;
+        let initial_potentials = self.neurons.iter().map(|n| n.v_mem).collect();
+        let initial_synapses = self.synapses.clone();
+        let initial_arrivals = self.arrivals.iter().cloned().collect();
+        let initial_injected = self.injected.iter().copied().collect();
+        let initial_rounding_rng = self.rounding_rng.clone();
+        let initial_modulator_levels = self.modulators.iter().map(|m| m.level).collect();
+        let recorder = InputRecorder::new(self.neurons.len());
+
+        let outcome = self.run_until(
+            |i, t| {
+                let value = input_current_fn(i, t);
+                recorder.record(i, value);
+                value
+            },
+            conditions,
+        );
+
+        let log = ReplayLog {
+            start_time,
+            end_time: self.time,
+            dt: self.config.dt,
+            initial_potentials,
+            initial_synapses,
+            initial_arrivals,
+            initial_injected,
+            initial_rounding_rng,
+            initial_modulator_levels,
+            inputs: recorder.into_inputs(),
+        };
+        (outcome, log)
+    }
+
+    /// Restore the state captured in `log` and replay the recorded run.
+    ///
+    /// The replay stops at the recorded end time, so runs that were ended
+    /// early by a stop condition or cancellation are reproduced as well.
+    ///
+    /// # Panics
+    /// Panics if the log was recorded with a different network size, number
+    /// of modulators or `dt`.
+    pub fn replay(&mut self, log: &ReplayLog) -> (Vec<Spike>, Vec<WeightSample>) {
+        assert_eq!(
+            log.initial_potentials.len(),
+            self.neurons.len(),
+            "Replay log neuron count does not match simulation"
+        );
+        assert_eq!(
+            log.initial_modulator_levels.len(),
+            self.modulators.len(),
+            "Replay log modulator count does not match simulation"
+        );
+        assert!(
+            log.dt.0.to_bits() == self.config.dt.0.to_bits(),
+            "Replay log dt does not match simulation dt"
+        );
+
+        self.time = log.start_time;
+        for (neuron, &v) in self.neurons.iter_mut().zip(&log.initial_potentials) {
+            neuron.v_mem = v;
+        }
+        self.synapses = log.initial_synapses.clone();
+        self.arrivals = log.initial_arrivals.iter().cloned().collect();
+        self.injected = log.initial_injected.iter().copied().collect();
+        self.rounding_rng = log.initial_rounding_rng.clone();
+        for (modulator, &level) in self.modulators.iter_mut().zip(&log.initial_modulator_levels) {
+            modulator.level = level;
+        }
+
+        let player = InputPlayer::new(&log.inputs);
+        let end_time = log.end_time;
+        let (spikes, weights, _) = self.run_until(
+            |i, _t| player.next(i),
+            &[StopCondition::Custom(Box::new(move |t, _| t >= end_time))],
+        );
+        (spikes, weights)
+    }
+
+    /// Apply the synaptic input scheduled for the current step, scaled by
+    /// the excitability of each neuron under `modulation`.
+    fn deliver_arrivals(&mut self, modulation: Option<&Modulation>) {
+        if let Some(arrivals) = self.arrivals.pop_front() {
+            let format = self.config.quantization.as_ref().map(|q| q.state);
+            for (i, (neuron, dv)) in self.neurons.iter_mut().zip(arrivals).enumerate() {
+                neuron.v_mem += match modulation {
+                    Some(m) => dv * m.excitability[i],
+                    None => dv,
+                };
+                if let Some(format) = format {
+                    neuron.v_mem = format.quantize(neuron.v_mem);
+                }
+            }
+        }
+    }
+
+    /// Drop clamped neurons from `fired` and hold their membrane potential
+    /// at rest.
+    fn hold_clamped(&mut self, mut fired: Vec<usize>) -> Vec<usize> {
+        if self.clamped.is_empty() {
+            return fired;
+        }
+        fired.retain(|i| self.clamped.binary_search(i).is_err());
+        for &i in &self.clamped {
+            let neuron = &mut self.neurons[i];
+            neuron.v_mem = match &self.config.quantization {
+                Some(q) => q.state.quantize(neuron.params.v_rest),
+                None => neuron.params.v_rest,
+            };
+        }
+        fired
+    }
+
+    /// Force the neurons with injected spikes due in the current step to
+    /// fire, and merge them into `fired`, keeping it sorted.
+    fn fire_injected(&mut self, mut fired: Vec<usize>) -> Vec<usize> {
+        let step_end = self.time + self.config.dt;
+        let before = fired.len();
+        while let Some(&spike) = self.injected.front() {
+            if spike.time >= step_end {
+                break;
+            }
+            self.injected.pop_front();
+            let neuron = &mut self.neurons[spike.neuron_id];
+            neuron.v_mem = match &self.config.quantization {
+                Some(q) => q.state.quantize(neuron.params.v_reset),
+                None => neuron.params.v_reset,
+            };
+            fired.push(spike.neuron_id);
+        }
+        if fired.len() > before {
+            fired.sort_unstable();
+            fired.dedup();
+        }
+        fired
+    }
+
+    /// Schedule the synaptic input caused by the neurons in `fired`.
+    fn transmit(&mut self, fired: &[usize]) {
+        if fired.is_empty() {
+            return;
+        }
+        let n = self.neurons.len();
+        for syn in &self.synapses {
+            if fired.binary_search(&syn.pre_neuron).is_err() {
+                continue;
+            }
+            let steps = ((syn.delay / self.config.dt).round() as usize).max(1);
+            while self.arrivals.len() < steps {
+                self.arrivals.push_back(vec![0.0; n]);
+            }
+            self.arrivals[steps - 1][syn.post_neuron] += syn.weight;
+        }
+    }
+
+    /// Advance every neuron by one time step and return the indices of the
+    /// neurons that fired, in ascending order.
+    ///
+    /// With a `pool`, neurons are split into contiguous partitions, each
+    /// updated on its own thread with a private spike queue. Queues are
+    /// concatenated in partition order, so the result matches the serial
+    /// engine exactly.
+    fn step_neurons<F>(
+        &mut self,
+        input_current_fn: &F,
+        pool: Option<&PartitionPool>,
+        modulation: Option<&Modulation>,
+    ) -> Vec<usize>
+    where
+        F: Fn(usize, Milliseconds) -> f64,
+    {
+        let update = NeuronUpdate {
+            time: self.time,
+            dt: self.config.dt,
+            format: self.config.quantization.as_ref().map(|q| q.state),
+            reset: self.config.reset,
+        };
+        let excitability = modulation.map(|m| m.excitability.as_slice());
+        match pool {
+            Some(pool) => pool.step(&mut self.neurons, update, excitability),
+            None => step_partition(&mut self.neurons, 0, update, excitability, input_current_fn),
         }
     }
-    /// Write synaptic weight evolution to CSV.
-    pub fn write_weights_to_csv(
+
+    /// Write spike events as CSV with a `neuron_id,time_ms` header, for
+    /// downstream analysis.
+    ///
+    /// `writer` may be a file, stdout, a socket or an in-memory buffer; it
+    /// is not buffered here, so wrap unbuffered writers in a `BufWriter`.
+    pub fn write_spikes_to_csv<W: Write>(
         &self,
-        weights: &[(f64, usize, usize, f64)],
-        path: &str,
-    ) {
-        let file = File::create(path)
-            .expect("Failed to create weights CSV file");
-        let mut writer = BufWriter::new(file);
+        spikes: &[Spike],
+        mut writer: W,
+    ) -> io::Result<()> {
+        writeln!(writer, "neuron_id,time_ms")?;
+        for spike in spikes {
+            writeln!(writer, "{},{}", spike.neuron_id, spike.time.0)?;
+        }
+        writer.flush()
+    }
+
+    /// Spike events in the CSV layout of `write_spikes_to_csv`.
+    pub fn spikes_to_csv_string(&self, spikes: &[Spike]) -> String {
+        let mut csv = Vec::new();
+        self.write_spikes_to_csv(spikes, &mut csv)
+            .expect("Writing to a Vec cannot fail");
+        String::from_utf8(csv).expect("CSV is ASCII")
+    }
+
+    /// Write the current connectivity as a GraphViz DOT digraph.
+    ///
+    /// Every synapse becomes an edge carrying its weight (and delay, if
+    /// nonzero) as `synaptic_weight` and `delay_ms` attributes; GraphViz's
+    /// own `weight` attribute is left alone since layout engines reject
+    /// negative values. With `color_groups`, neurons are filled with
+    /// a color per population, or per tagged group for neurons outside any
+    /// population, and labelled accordingly.
+    ///
+    /// Like `write_spikes_to_csv`, `writer` is not buffered here.
+    pub fn export_dot<W: Write>(&self, mut writer: W, color_groups: bool) -> io::Result<()> {
+        writeln!(writer, "digraph network {{")?;
+        writeln!(writer, "  node [shape=circle, style=filled, fillcolor=white];")?;
+
+        for i in 0..self.neurons.len() {
+            let label = if color_groups {
+                self.populations
+                    .iter()
+                    .position(|p| p.range.contains(&i))
+                    .map(|k| (k, self.populations[k].name.as_str()))
+                    .or_else(|| {
+                        self.groups
+                            .iter()
+                            .position(|g| g.neurons.binary_search(&i).is_ok())
+                            .map(|k| (self.populations.len() + k, self.groups[k].name.as_str()))
+                    })
+            } else {
+                None
+            };
+            match label {
+                Some((k, name)) => writeln!(
+                    writer,
+                    "  n{i} [label=\"{i}\", group=\"{name}\", fillcolor=\"{}\"];",
+                    DOT_PALETTE[k % DOT_PALETTE.len()]
+                ),
+                None => writeln!(writer, "  n{i} [label=\"{i}\"];"),
+            }?;
+        }
 
-        writeln!(writer, "time_ms,pre_neuron,post_neuron,weight")
-            .expect("Failed to write weights CSV header");
+        for syn in &self.synapses {
+            let delay = if syn.delay > Milliseconds::ZERO {
+                format!(", delay_ms={}", syn.delay.0)
+            } else {
+                String::new()
+            };
+            writeln!(
+                writer,
+                "  n{} -> n{} [synaptic_weight={}, label=\"{:.3}\"{}];",
+                syn.pre_neuron, syn.post_neuron, syn.weight, syn.weight, delay
+            )?;
+        }
+
+        writeln!(writer, "}}")?;
+        writer.flush()
+    }
+
+    /// Write the current connectivity as a GraphViz DOT file at `path`, as
+    /// `export_dot` does, compressed as its extension implies.
+    pub fn write_dot<P: AsRef<Path>>(&self, path: P, color_groups: bool) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        self.export_dot(&mut writer, color_groups)?;
+        writer.finish()?;
+        Ok(())
+    }
 
+    /// Write synaptic weight evolution as CSV with a
+    /// `time_ms,pre_neuron,post_neuron,weight` header.
+    ///
+    /// Like `write_spikes_to_csv`, `writer` is not buffered here.
+    pub fn write_weights_to_csv<W: Write>(
+        &self,
+        weights: &[WeightSample],
+        mut writer: W,
+    ) -> io::Result<()> {
+        writeln!(writer, "time_ms,pre_neuron,post_neuron,weight")?;
         for (t, pre, post, w) in weights {
-            writeln!(writer, "{},{},{},{}", t, pre, post, w)
-                .expect("Failed to write weight row");
+            writeln!(writer, "{},{},{},{}", t.0, pre, post, w)?;
+        }
+        writer.flush()
+    }
+
+    /// Weight log in the CSV layout of `write_weights_to_csv`.
+    pub fn weights_to_csv_string(&self, weights: &[WeightSample]) -> String {
+        let mut csv = Vec::new();
+        self.write_weights_to_csv(weights, &mut csv)
+            .expect("Writing to a Vec cannot fail");
+        String::from_utf8(csv).expect("CSV is ASCII")
+    }
+
+    /// Write the spikes and weight log of a run in a new run directory of
+    /// `output`, in each of its formats.
+    pub fn write_outputs(
+        &self,
+        output: &OutputConfig,
+        spikes: &[Spike],
+        weights: &[WeightSample],
+    ) -> io::Result<RunOutput> {
+        let mut run = output.create_run()?;
+        run.write_results(self, spikes, weights, None)?;
+        Ok(run)
+    }
+}
+
+/// Fill colors cycled through by `export_dot` group coloring.
+const DOT_PALETTE: [&str; 8] = [
+    "lightblue",
+    "lightcoral",
+    "palegreen",
+    "khaki",
+    "plum",
+    "lightsalmon",
+    "lightcyan",
+    "wheat",
+];
+
+/// Sleep until `sim_elapsed` of simulated time, scaled by `factor`,
+/// have passed on the wall clock since `wall_start`.
+///
+/// If the simulation has fallen behind real time no sleep occurs; the
+/// engine catches up rather than skipping steps.
+pub(crate) fn pace_to_wall_clock(wall_start: Instant, sim_elapsed: Milliseconds, factor: f64) {
+    if factor <= 0.0 || !factor.is_finite() {
+        return;
+    }
+    let target = Duration::from_secs_f64((sim_elapsed.as_secs() / factor).max(0.0));
+    let elapsed = wall_start.elapsed();
+    if target > elapsed {
+        std::thread::sleep(target - elapsed);
+    }
+}
+
+/// Settings shared by every neuron update of one step.
+#[derive(Debug, Clone, Copy)]
+struct NeuronUpdate {
+    time: Milliseconds,
+    dt: Milliseconds,
+    /// Fixed-point format of membrane potentials, if quantized
+    format: Option<FixedPoint>,
+    reset: ResetMode,
+}
+
+/// Step one contiguous partition of neurons starting at global index
+/// `offset`, and return the global indices of those that fired.
+///
+/// `excitability`, indexed globally, scales the input current of each neuron.
+fn step_partition<F>(
+    neurons: &mut [Neuron],
+    offset: usize,
+    update: NeuronUpdate,
+    excitability: Option<&[f64]>,
+    input_current_fn: &F,
+) -> Vec<usize>
+where
+    F: Fn(usize, Milliseconds) -> f64,
+{
+    let mut fired = Vec::new();
+    for (local, neuron) in neurons.iter_mut().enumerate() {
+        let i = offset + local;
+        let input = match excitability {
+            Some(gain) => input_current_fn(i, update.time) * gain[i],
+            None => input_current_fn(i, update.time),
+        };
+        let spiked = match &update.format {
+            Some(format) => neuron.step_quantized(input, update.dt, format, update.reset),
+            None => neuron.step_with_reset(input, update.dt, update.reset),
+        };
+        if spiked {
+            fired.push(i);
         }
     }
-}
\ No newline at end of file
+    fired
+}
+
+/// One partition's share of a step, sent to a `PartitionPool` worker.
+struct PartitionJob {
+    neurons: *mut Neuron,
+    len: usize,
+    offset: usize,
+    update: NeuronUpdate,
+    excitability: Option<(*const f64, usize)>,
+}
+
+// SAFETY: the pointers are only dereferenced while `PartitionPool::step`
+// holds the borrows they were made from, see there.
+unsafe impl Send for PartitionJob {}
+
+/// Fired neurons of a partition, or the payload of a panic while stepping it.
+type PartitionResult = (usize, std::thread::Result<Vec<usize>>);
+
+/// Worker threads stepping neuron partitions, started once per
+/// `Simulation::run_parallel` rather than once per step.
+struct PartitionPool {
+    jobs: Vec<Sender<PartitionJob>>,
+    results: Receiver<PartitionResult>,
+}
+
+impl PartitionPool {
+    /// Start `partitions` workers in `scope`, calling `input_current_fn`.
+    /// They exit when the pool is dropped.
+    fn new<'scope, 'env, F>(
+        scope: &'scope Scope<'scope, 'env>,
+        partitions: usize,
+        input_current_fn: &'env F,
+    ) -> Self
+    where
+        F: Fn(usize, Milliseconds) -> f64 + Sync,
+    {
+        let (result_sender, results) = mpsc::channel();
+        let jobs = (0..partitions)
+            .map(|p| {
+                let (sender, receiver) = mpsc::channel::<PartitionJob>();
+                let results = result_sender.clone();
+                scope.spawn(move || {
+                    for job in receiver {
+                        // SAFETY: see `PartitionJob`
+                        let neurons = unsafe { slice::from_raw_parts_mut(job.neurons, job.len) };
+                        let excitability =
+                            job.excitability.map(|(gain, n)| unsafe { slice::from_raw_parts(gain, n) });
+                        let fired = panic::catch_unwind(AssertUnwindSafe(|| {
+                            step_partition(
+                                neurons,
+                                job.offset,
+                                job.update,
+                                excitability,
+                                input_current_fn,
+                            )
+                        }));
+                        if results.send((p, fired)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                sender
+            })
+            .collect();
+        Self { jobs, results }
+    }
+
+    /// Step `neurons` on the workers and return the fired neurons in
+    /// ascending order.
+    ///
+    /// Blocks until every worker given a partition has finished with it, so
+    /// no worker touches `neurons` or `excitability` after this returns.
+    fn step(
+        &self,
+        neurons: &mut [Neuron],
+        update: NeuronUpdate,
+        excitability: Option<&[f64]>,
+    ) -> Vec<usize> {
+        let chunk_size = neurons.len().div_ceil(self.jobs.len()).max(1);
+        let excitability = excitability.map(|gain| (gain.as_ptr(), gain.len()));
+        let mut sent = 0;
+        for ((p, chunk), worker) in neurons.chunks_mut(chunk_size).enumerate().zip(&self.jobs) {
+            let job = PartitionJob {
+                neurons: chunk.as_mut_ptr(),
+                len: chunk.len(),
+                offset: p * chunk_size,
+                update,
+                excitability,
+            };
+            if worker.send(job).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+
+        let mut fired = vec![Vec::new(); sent];
+        let mut panicked = None;
+        for _ in 0..sent {
+            let (p, result) = self
+                .results
+                .recv()
+                .expect("Neuron partition threads outlive the pool");
+            match result {
+                Ok(partition) => fired[p] = partition,
+                Err(payload) => panicked = Some(payload),
+            }
+        }
+        if let Some(payload) = panicked {
+            panic::resume_unwind(payload);
+        }
+        assert!(
+            sent * chunk_size >= neurons.len(),
+            "Neuron partition thread exited"
+        );
+        fired.concat()
+    }
+}
@@ -0,0 +1,195 @@
+//! snapshot.rs
+//!
+//! Scheduled weight-matrix snapshots.
+//!
+//! The weight log returned by `run` grows with every spike and records one
+//! synapse at a time, which makes it awkward for looking at the connectivity
+//! as a whole. A `SnapshotMonitor` instead captures the full weight matrix
+//! at chosen simulated times, either on a fixed interval or at an explicit
+//! list of times. Snapshots can be read as sparse triplets or a dense
+//! matrix and written to CSV or NumPy `.npy` files.
+
+use crate::compression;
+use crate::monitor::{reached, sample_due, Monitor, StepView};
+use crate::numpy::NpyArray;
+use crate::units::Milliseconds;
+use crate::util::lock;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Weights of every synapse at one point in simulated time.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightSnapshot {
+    /// Time the snapshot was taken
+    pub time: Milliseconds,
+    /// Number of neurons in the network at that time
+    pub num_neurons: usize,
+    /// `(pre, post, weight)` for every synapse, in synapse order
+    pub synapses: Vec<(usize, usize, f64)>,
+}
+
+impl WeightSnapshot {
+    /// Dense `num_neurons × num_neurons` matrix indexed `[pre][post]`.
+    ///
+    /// Absent connections are zero; parallel synapses between the same
+    /// pair are summed.
+    pub fn dense(&self) -> Vec<Vec<f64>> {
+        let mut matrix = vec![vec![0.0; self.num_neurons]; self.num_neurons];
+        for &(pre, post, w) in &self.synapses {
+            matrix[pre][post] += w;
+        }
+        matrix
+    }
+
+    /// Write the dense matrix as CSV, one row per pre-synaptic neuron.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        for row in self.dense() {
+            let line: Vec<String> = row.iter().map(|w| w.to_string()).collect();
+            writeln!(writer, "{}", line.join(","))?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Write the synapses as CSV with a `pre_neuron,post_neuron,weight`
+    /// header.
+    pub fn write_triplets_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = compression::create(path)?;
+        writeln!(writer, "pre_neuron,post_neuron,weight")?;
+        for (pre, post, w) in &self.synapses {
+            writeln!(writer, "{pre},{post},{w}")?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Write the dense matrix as a NumPy `.npy` file of little-endian
+    /// `float64` with shape `(num_neurons, num_neurons)`.
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let n = self.num_neurons;
+        NpyArray::float64(&[n, n], self.dense().into_iter().flatten()).write(path)
+    }
+}
+
+/// When snapshots are due.
+#[derive(Debug)]
+enum Schedule {
+    Every(Milliseconds),
+    /// Remaining times, in descending order so the next is at the end
+    At(Vec<Milliseconds>),
+}
+
+/// Schedule and snapshots shared by all clones of a `SnapshotMonitor`.
+#[derive(Debug)]
+struct SnapshotState {
+    schedule: Schedule,
+    next_snapshot: Option<Milliseconds>,
+    snapshots: Vec<WeightSnapshot>,
+}
+
+/// Captures full weight-matrix snapshots at scheduled simulated times.
+#[derive(Debug, Clone)]
+pub struct SnapshotMonitor {
+    state: Arc<Mutex<SnapshotState>>,
+}
+
+impl SnapshotMonitor {
+    /// Take a snapshot at the end of the first monitored step and then
+    /// every `interval`.
+    pub fn every(interval: Milliseconds) -> Self {
+        Self::with_schedule(Schedule::Every(interval))
+    }
+
+    /// Take one snapshot at the end of the first step reaching each of
+    /// `times`.
+    pub fn at(mut times: Vec<Milliseconds>) -> Self {
+        times.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Self::with_schedule(Schedule::At(times))
+    }
+
+    fn with_schedule(schedule: Schedule) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SnapshotState {
+                schedule,
+                next_snapshot: None,
+                snapshots: Vec::new(),
+            })),
+        }
+    }
+
+    /// Snapshots taken so far, in time order.
+    pub fn snapshots(&self) -> Vec<WeightSnapshot> {
+        self.lock().snapshots.clone()
+    }
+
+    /// Most recent snapshot, if any.
+    pub fn latest(&self) -> Option<WeightSnapshot> {
+        self.lock().snapshots.last().cloned()
+    }
+
+    /// Number of snapshots taken.
+    pub fn len(&self) -> usize {
+        self.lock().snapshots.len()
+    }
+
+    /// Whether no snapshot has been taken yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write each snapshot as a dense CSV matrix named
+    /// `<prefix>_<time_ms>.csv` in `dir`.
+    pub fn write_csv_dir<P: AsRef<Path>>(&self, dir: P, prefix: &str) -> io::Result<()> {
+        for snapshot in self.lock().snapshots.iter() {
+            let name = format!("{prefix}_{}.csv", snapshot.time.0);
+            snapshot.write_csv(dir.as_ref().join(name))?;
+        }
+        Ok(())
+    }
+
+    /// Lock the shared state.
+    fn lock(&self) -> MutexGuard<'_, SnapshotState> {
+        lock(&self.state)
+    }
+}
+
+impl Monitor for SnapshotMonitor {
+    fn record(&mut self, step: &StepView<'_>) {
+        let mut state = self.lock();
+        let SnapshotState {
+            schedule,
+            next_snapshot,
+            snapshots,
+        } = &mut *state;
+        let time = step.end_time();
+        let reached = |&t: &Milliseconds| reached(time, t);
+        match schedule {
+            Schedule::Every(interval) => {
+                if !sample_due(next_snapshot, time, *interval) {
+                    return;
+                }
+            }
+            Schedule::At(times) => {
+                if !times.last().is_some_and(reached) {
+                    return;
+                }
+                // Several requested times may fall within one step
+                while times.last().is_some_and(reached) {
+                    times.pop();
+                }
+            }
+        }
+        snapshots.push(WeightSnapshot {
+            time,
+            num_neurons: step.neurons.len(),
+            synapses: step
+                .synapses
+                .iter()
+                .map(|s| (s.pre_neuron, s.post_neuron, s.weight))
+                .collect(),
+        });
+    }
+}
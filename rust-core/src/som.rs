@@ -0,0 +1,368 @@
+//! som.rs
+//!
+//! Spiking self-organizing maps.
+//!
+//! A self-organizing map (Kohonen, 1982) is a 2D sheet of units that learns
+//! a topographic map of its input: neighbouring units come to prefer
+//! similar inputs. The spiking version here keeps the two ingredients of
+//! the classic algorithm. Competition is a hard winner-take-all circuit on
+//! the sheet, so the unit whose weights best match the input fires first
+//! and silences the rest. Cooperation is a Gaussian neighbourhood function
+//! around the unit that fires, which spreads its STDP update to nearby
+//! units on the sheet:
+//!
+//! ```text
+//! Δw_ij = η h(d_jk) (w_max x_i - w_ij)        at each spike of unit k
+//! h(d) = exp(-d² / 2σ²)
+//! ```
+//!
+//! where `x_i` is the nearest-spike trace of input `i`, equal to 1 at an
+//! input spike and decaying with `tau_pre`, and `d_jk` is the grid
+//! distance between units. Inputs that spiked just before the winner are
+//! potentiated and silent ones depressed, as with STDP. Competition by
+//! firing favours units with large weights overall, so after every update
+//! the weights of each unit are rescaled to a fixed sum, as in the
+//! dot-product variant of the classic map; the unit that fires first is
+//! then the one whose weights point most closely in the direction of the
+//! input. Both `η` and `σ` shrink exponentially over training, from a
+//! coarse global ordering to local refinement.
+//!
+//! Inputs are values in `[0, 1]`, Poisson-encoded at rates proportional to
+//! the value. After training, `weights`, `receptive_fields`, `label_map`
+//! and `neighbor_distance_ratio` extract and judge the learned map.
+
+use crate::connectivity::SheetShape;
+use crate::encoding::PoissonEncoder;
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::receptive_field::{extract_receptive_fields, ReceptiveField};
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spatial::{grid_positions, Position};
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use crate::wta::{add_wta, Competition, LateralInhibition, WtaParams};
+use std::ops::Range;
+
+/// Parameters of a spiking self-organizing map.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SomParams {
+    /// Number of input neurons
+    pub input_size: usize,
+    /// Shape of the map sheet
+    pub map: SheetShape,
+    /// Upper weight bound
+    pub w_max: f64,
+    /// Sum every unit's input weights are normalized to
+    pub weight_sum: f64,
+    /// Range of the initial input weights
+    pub initial_weight: (f64, f64),
+    /// Decay time constant of the input traces
+    pub tau_pre: Milliseconds,
+    /// Input rate for a value of 1
+    pub max_rate_hz: f64,
+    /// Presentation time of each input
+    pub duration: Milliseconds,
+    /// Neighbourhood width in grid units at the start and end of training
+    pub sigma: (f64, f64),
+    /// Learning rate at the start and end of training
+    pub learning_rate: (f64, f64),
+    /// Seed for the initial weights
+    pub seed: u64,
+}
+
+impl Default for SomParams {
+    fn default() -> Self {
+        Self {
+            input_size: 20,
+            map: SheetShape::new(8, 8),
+            w_max: 1.0,
+            weight_sum: 4.0,
+            initial_weight: (0.1, 0.3),
+            tau_pre: Milliseconds(20.0),
+            max_rate_hz: 100.0,
+            duration: Milliseconds(50.0),
+            sigma: (3.0, 0.5),
+            learning_rate: (0.1, 0.01),
+            seed: 0,
+        }
+    }
+}
+
+/// A spiking self-organizing map.
+pub struct SpikingSom {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Input neurons
+    pub input: Range<usize>,
+    /// Map units, row-major on the sheet
+    pub map: Range<usize>,
+    /// Parameters the map was built with
+    pub params: SomParams,
+    /// Synapse index from each input onto each unit, `[unit][input]`
+    feedforward: Vec<Vec<usize>>,
+    /// Current neighbourhood width
+    sigma: f64,
+    /// Current learning rate
+    learning_rate: f64,
+}
+
+/// Build a spiking self-organizing map.
+///
+/// The input is registered as population `input` and the sheet as `map`,
+/// placed on a grid of unit spacing and wired as a hard WTA circuit. Every
+/// input connects to every unit. All synapses are static, since the map
+/// applies its own neighbourhood STDP, and synaptic transmission is
+/// enabled on `config`.
+pub fn build_som(
+    params: &SomParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+) -> SpikingSom {
+    let mut rng = Rng::new(params.seed);
+    let mut builder = NetworkBuilder::new();
+    let input = builder.add_population("input", params.input_size, neuron_params.clone());
+    let wta = WtaParams {
+        size: params.map.len(),
+        competition: Competition::Hard,
+        inhibition: LateralInhibition::Direct,
+    };
+    let map = add_wta(&mut builder, "map", &wta, neuron_params).excitatory;
+    builder.place(map.clone(), grid_positions(params.map, 1.0));
+
+    let (low, high) = params.initial_weight;
+    let mut synapses = Vec::with_capacity(input.len() * map.len());
+    for j in map.clone() {
+        for i in input.clone() {
+            synapses.push(Synapse {
+                plasticity: Plasticity::Static,
+                ..Synapse::new(i, j, rng.uniform(low, high))
+            });
+        }
+    }
+    builder.add_synapses(synapses);
+
+    config.synaptic_transmission = true;
+    let stdp = STDPParams {
+        a_plus: 0.0,
+        a_minus: 0.0,
+        tau_plus: params.tau_pre,
+        tau_minus: params.tau_pre,
+        w_min: 0.0,
+        w_max: params.w_max,
+    };
+    let simulation = Simulation::from_network(builder.build(), config, stdp);
+
+    let mut feedforward = vec![vec![0; input.len()]; map.len()];
+    for (index, syn) in simulation.synapses().iter().enumerate() {
+        if input.contains(&syn.pre_neuron) && map.contains(&syn.post_neuron) {
+            feedforward[syn.post_neuron - map.start][syn.pre_neuron - input.start] = index;
+        }
+    }
+    SpikingSom {
+        simulation,
+        input,
+        map,
+        sigma: params.sigma.0,
+        learning_rate: params.learning_rate.0,
+        params: params.clone(),
+        feedforward,
+    }
+}
+
+impl SpikingSom {
+    /// Grid position `(x, y)` of unit `k`.
+    pub fn coordinates(&self, k: usize) -> (usize, usize) {
+        (k % self.params.map.width, k / self.params.map.width)
+    }
+
+    /// Grid position of every unit.
+    fn positions(&self) -> Vec<Position> {
+        self.simulation.positions()[self.map.clone()]
+            .iter()
+            .map(|p| p.unwrap_or_default())
+            .collect()
+    }
+
+    /// Current neighbourhood width and learning rate.
+    pub fn schedule(&self) -> (f64, f64) {
+        (self.sigma, self.learning_rate)
+    }
+
+    /// Present `values` from rest for the presentation time and return the
+    /// spike count of every unit; with `learn`, weights are updated at
+    /// every unit spike.
+    pub fn present(&mut self, values: &[f64], learn: bool, rng: &mut Rng) -> Vec<usize> {
+        let encoder = PoissonEncoder {
+            first_neuron: self.input.start,
+            ..PoissonEncoder::new(self.params.max_rate_hz, self.params.duration)
+        };
+        let input = encoder.encode(&values[..values.len().min(self.input.len())], rng);
+        self.simulation.reset();
+        self.simulation.inject_spikes(&input);
+
+        let positions = if learn { self.positions() } else { Vec::new() };
+        let dt = self.simulation.config().dt;
+        let decay = (-(dt / self.params.tau_pre)).exp();
+        let mut traces = vec![0.0; self.input.len()];
+        let mut counts = vec![0; self.map.len()];
+        while self.simulation.time() < self.params.duration {
+            let spikes = self.simulation.step(|_, _| 0.0);
+            for x in &mut traces {
+                *x *= decay;
+            }
+            let mut winners = Vec::new();
+            for spike in &spikes {
+                if self.input.contains(&spike.neuron_id) {
+                    traces[spike.neuron_id - self.input.start] = 1.0;
+                } else if self.map.contains(&spike.neuron_id) {
+                    let k = spike.neuron_id - self.map.start;
+                    counts[k] += 1;
+                    winners.push(k);
+                }
+            }
+            if learn {
+                for k in winners {
+                    self.update(k, &traces, &positions);
+                }
+            }
+        }
+        counts
+    }
+
+    /// Apply the neighbourhood STDP update for a spike of unit `winner`.
+    fn update(&mut self, winner: usize, traces: &[f64], positions: &[Position]) {
+        let w_max = self.params.w_max;
+        for (j, synapses) in self.feedforward.iter().enumerate() {
+            let d = positions[j].distance(&positions[winner]);
+            let h = (-(d * d) / (2.0 * self.sigma * self.sigma)).exp();
+            let rate = self.learning_rate * h;
+            if rate < 1e-6 {
+                continue;
+            }
+            let updated: Vec<f64> = synapses
+                .iter()
+                .zip(traces)
+                .map(|(&index, &x)| {
+                    let w = self.simulation.synapses()[index].weight;
+                    (w + rate * (w_max * x - w)).clamp(0.0, w_max)
+                })
+                .collect();
+            let total: f64 = updated.iter().sum();
+            let scale = if total > 0.0 {
+                self.params.weight_sum / total
+            } else {
+                1.0
+            };
+            for (&index, w) in synapses.iter().zip(updated) {
+                self.simulation.set_weight(index, (w * scale).min(w_max));
+            }
+        }
+    }
+
+    /// Unit that fires most for `values`, the first on ties, or `None` if
+    /// the map stays silent. Weights are not changed.
+    pub fn winner(&mut self, values: &[f64], rng: &mut Rng) -> Option<usize> {
+        let counts = self.present(values, false, rng);
+        let best = counts.iter().copied().max().filter(|&c| c > 0)?;
+        counts.iter().position(|&c| c == best)
+    }
+
+    /// Train on `samples` for `epochs` shuffled passes, decaying the
+    /// neighbourhood width and learning rate exponentially from their start
+    /// to their end values over all presentations.
+    pub fn train(&mut self, samples: &[Vec<f64>], epochs: usize, rng: &mut Rng) {
+        let total = (samples.len() * epochs).max(2) - 1;
+        let interpolate = |(start, end): (f64, f64), f: f64| start * (end / start).powf(f);
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        let mut step = 0;
+        for _ in 0..epochs {
+            rng.shuffle(&mut order);
+            for &s in &order {
+                let f = step as f64 / total as f64;
+                self.sigma = interpolate(self.params.sigma, f);
+                self.learning_rate = interpolate(self.params.learning_rate, f);
+                self.present(&samples[s], true, rng);
+                step += 1;
+            }
+        }
+    }
+
+    /// Input weights of every unit, `[unit][input]`.
+    pub fn weights(&self) -> Vec<Vec<f64>> {
+        let synapses = self.simulation.synapses();
+        self.feedforward
+            .iter()
+            .map(|row| row.iter().map(|&index| synapses[index].weight).collect())
+            .collect()
+    }
+
+    /// Input weights of every unit arranged like an input image of `shape`.
+    pub fn receptive_fields(&self, shape: SheetShape) -> Vec<ReceptiveField> {
+        extract_receptive_fields(
+            self.simulation.synapses(),
+            self.input.start,
+            shape,
+            self.map.clone(),
+        )
+    }
+
+    /// Most frequent label of the samples each unit wins, or `None` for
+    /// units that win none.
+    pub fn label_map(
+        &mut self,
+        samples: &[Vec<f64>],
+        labels: &[usize],
+        rng: &mut Rng,
+    ) -> Vec<Option<usize>> {
+        let classes = labels.iter().copied().max().map_or(0, |m| m + 1);
+        let mut votes = vec![vec![0usize; classes]; self.map.len()];
+        for (values, &label) in samples.iter().zip(labels) {
+            if let Some(k) = self.winner(values, rng) {
+                votes[k][label] += 1;
+            }
+        }
+        votes
+            .iter()
+            .map(|v| {
+                let best = v.iter().copied().max().filter(|&c| c > 0)?;
+                v.iter().position(|&c| c == best)
+            })
+            .collect()
+    }
+
+    /// Mean weight distance between grid neighbours divided by the mean
+    /// between all pairs of units.
+    ///
+    /// A topographically ordered map, where neighbours prefer similar
+    /// inputs, scores well below 1; an unordered map scores about 1.
+    pub fn neighbor_distance_ratio(&self) -> f64 {
+        let weights = self.weights();
+        let positions = self.positions();
+        let distance = |a: &[f64], b: &[f64]| {
+            a.iter()
+                .zip(b)
+                .map(|(x, y)| (x - y).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        };
+        let (mut near, mut near_n, mut all, mut all_n) = (0.0, 0usize, 0.0, 0usize);
+        for a in 0..weights.len() {
+            for b in a + 1..weights.len() {
+                let d = distance(&weights[a], &weights[b]);
+                all += d;
+                all_n += 1;
+                if positions[a].distance(&positions[b]) <= 1.0 {
+                    near += d;
+                    near_n += 1;
+                }
+            }
+        }
+        if near_n == 0 || all == 0.0 {
+            return 1.0;
+        }
+        (near / near_n as f64) / (all / all_n as f64)
+    }
+}
@@ -0,0 +1,726 @@
+//! sonata.rs
+//!
+//! SONATA circuit import and export.
+//!
+//! SONATA is the circuit format of BMTK, NEST's SONATA loader, NetPyNE and
+//! the Blue Brain tools: node and edge tables in HDF5, type tables in
+//! space-separated CSV, and a JSON configuration tying them together. This
+//! module writes a `Network` built with the builder API as a SONATA
+//! circuit, and reads externally built point-neuron circuits back into a
+//! `Network` that a `Simulation` can run.
+//!
+//! `write_sonata(network, name, dir)` writes:
+//!
+//! ```text
+//! dir/circuit_config.json
+//! dir/network/<name>_nodes.h5            node population <name>
+//! dir/network/<name>_node_types.csv
+//! dir/network/<name>_<name>_edges.h5     edge population <name>_to_<name>
+//! dir/network/<name>_<name>_edge_types.csv
+//! dir/components/point_neuron_models/<name>_<type>.json
+//! ```
+//!
+//! Neurons are `nest:iaf_psc_delta` point neurons, whose synapses make the
+//! same instantaneous voltage jump as this crate's, so `syn_weight` is the
+//! weight in mV and `delay` the delay in ms. There is one node type per
+//! population and parameter set, with the population name as `pop_name`.
+//! Plasticity is not exported; the synapses are `static_synapse`s at their
+//! current weights.
+//!
+//! `read_sonata` accepts a circuit or simulation configuration. Every node
+//! population becomes a population of the network, in configuration order,
+//! and every `pop_name` a population as well when its nodes are
+//! contiguous, or a group otherwise. Neuron parameters are read from the
+//! `tau_m`, `E_L`, `V_th` and `V_reset` node attributes, node type columns
+//! or dynamics parameter files, falling back to the given defaults, and
+//! synapses from `syn_weight` (times `nsyns`, if present) and `delay`.
+//! Multi-compartment morphologies, weight functions and virtual input
+//! populations without edges into the circuit are outside the model of this
+//! crate and ignored; nodes of virtual populations become neurons all the
+//! same.
+
+use crate::compression;
+use crate::hdf5::{Attribute, Hdf5File, Hdf5Writer};
+use crate::json::{write_number, write_string, Json, JsonParser};
+use crate::network::{tag_group, NamedPopulation, Network, NeuronGroup};
+use crate::neuron::{Neuron, NeuronParams};
+use crate::spatial::Position;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// First node and edge type id, as in BMTK.
+const FIRST_TYPE_ID: usize = 100;
+/// Neuron model of exported nodes.
+const MODEL_TEMPLATE: &str = "nest:iaf_psc_delta";
+
+/// Write `network` as a SONATA circuit called `name` into `dir`, creating
+/// the directory tree.
+pub fn write_sonata<P: AsRef<Path>>(network: &Network, name: &str, dir: P) -> io::Result<()> {
+    let dir = dir.as_ref();
+    let network_dir = dir.join("network");
+    let models_dir = dir.join("components").join("point_neuron_models");
+    std::fs::create_dir_all(&network_dir)?;
+    std::fs::create_dir_all(&models_dir)?;
+
+    // Node types: one per population and parameter set, in neuron order
+    let neurons = network.neurons();
+    let mut types: Vec<(&str, &NeuronParams)> = Vec::new();
+    let mut type_of = Vec::with_capacity(neurons.len());
+    for (i, neuron) in neurons.iter().enumerate() {
+        let pop_name = population_of(network, i).unwrap_or(name);
+        let k = match types
+            .iter()
+            .position(|(p, q)| *p == pop_name && same_params(q, &neuron.params))
+        {
+            Some(k) => k,
+            None => {
+                types.push((pop_name, &neuron.params));
+                types.len() - 1
+            }
+        };
+        type_of.push(FIRST_TYPE_ID + k);
+    }
+
+    let mut node_types =
+        String::from("node_type_id pop_name model_type model_template dynamics_params\n");
+    for (k, (pop_name, params)) in types.iter().enumerate() {
+        let id = FIRST_TYPE_ID + k;
+        let file = format!("{name}_{id}.json");
+        let _ = writeln!(
+            node_types,
+            "{id} {} point_neuron {MODEL_TEMPLATE} {}",
+            csv_field(pop_name),
+            csv_field(&file)
+        );
+        compression::write(models_dir.join(&file), dynamics_params(params))?;
+    }
+    compression::write(
+        network_dir.join(format!("{name}_node_types.csv")),
+        node_types,
+    )?;
+
+    let mut nodes = Hdf5Writer::create(network_dir.join(format!("{name}_nodes.h5")))?;
+    write_header(&mut nodes)?;
+    let base = format!("/nodes/{name}");
+    nodes.write_dataset(&format!("{base}/node_id"), 0..neurons.len() as u64, &[])?;
+    nodes.write_dataset(
+        &format!("{base}/node_type_id"),
+        type_of.iter().map(|&t| t as u64),
+        &[],
+    )?;
+    nodes.write_dataset(
+        &format!("{base}/node_group_id"),
+        neurons.iter().map(|_| 0u64),
+        &[],
+    )?;
+    nodes.write_dataset(
+        &format!("{base}/node_group_index"),
+        0..neurons.len() as u64,
+        &[],
+    )?;
+    let positions = network.positions();
+    if positions.iter().any(Option::is_some) {
+        let coordinate = |c: fn(&Position) -> f64| {
+            positions
+                .iter()
+                .map(move |p| p.as_ref().map_or(f64::NAN, c))
+        };
+        nodes.write_dataset(&format!("{base}/0/x"), coordinate(|p| p.x), &[])?;
+        nodes.write_dataset(&format!("{base}/0/y"), coordinate(|p| p.y), &[])?;
+        nodes.write_dataset(&format!("{base}/0/z"), coordinate(|p| p.z), &[])?;
+    } else {
+        nodes.create_group(&format!("{base}/0"), &[])?;
+    }
+    nodes.finish()?;
+
+    let synapses = network.synapses();
+    let edge_name = format!("{name}_to_{name}");
+    let mut edges = Hdf5Writer::create(network_dir.join(format!("{name}_{name}_edges.h5")))?;
+    write_header(&mut edges)?;
+    let base = format!("/edges/{edge_name}");
+    let population = [("node_population", Attribute::Text(name.to_string()))];
+    edges.write_dataset(
+        &format!("{base}/source_node_id"),
+        synapses.iter().map(|s| s.pre_neuron as u64),
+        &population,
+    )?;
+    edges.write_dataset(
+        &format!("{base}/target_node_id"),
+        synapses.iter().map(|s| s.post_neuron as u64),
+        &population,
+    )?;
+    edges.write_dataset(
+        &format!("{base}/edge_type_id"),
+        synapses.iter().map(|_| FIRST_TYPE_ID as u64),
+        &[],
+    )?;
+    edges.write_dataset(
+        &format!("{base}/edge_group_id"),
+        synapses.iter().map(|_| 0u64),
+        &[],
+    )?;
+    edges.write_dataset(
+        &format!("{base}/edge_group_index"),
+        0..synapses.len() as u64,
+        &[],
+    )?;
+    edges.write_dataset(
+        &format!("{base}/0/syn_weight"),
+        synapses.iter().map(|s| s.weight),
+        &[],
+    )?;
+    edges.write_dataset(
+        &format!("{base}/0/delay"),
+        synapses.iter().map(|s| s.delay.0),
+        &[],
+    )?;
+    edges.finish()?;
+    compression::write(
+        network_dir.join(format!("{name}_{name}_edge_types.csv")),
+        format!("edge_type_id model_template\n{FIRST_TYPE_ID} static_synapse\n"),
+    )?;
+
+    compression::write(dir.join("circuit_config.json"), circuit_config(name))
+}
+
+/// Read the circuit of the SONATA circuit or simulation configuration at
+/// `config`, using `defaults` for neuron parameters the circuit does not
+/// specify.
+pub fn read_sonata<P: AsRef<Path>>(config: P, defaults: &NeuronParams) -> io::Result<Network> {
+    let mut config = Config::load(config.as_ref())?;
+    // A simulation configuration refers to its circuit configuration
+    if config.json.get("networks").is_none() {
+        if let Some(circuit) = config.path("network")? {
+            config = Config::load(&circuit)?;
+        }
+    }
+    let models_dir = config.path_at(&["components", "point_neuron_models_dir"])?;
+    let networks = config
+        .json
+        .get("networks")
+        .ok_or_else(|| invalid("Configuration has no networks".to_string()))?;
+
+    let mut neurons = Vec::new();
+    let mut positions = Vec::new();
+    let mut populations = Vec::new();
+    let mut groups: Vec<NeuronGroup> = Vec::new();
+    // Neuron index of each node, by population and node id
+    let mut node_index: HashMap<String, HashMap<i64, usize>> = HashMap::new();
+
+    for entry in list(networks.get("nodes")) {
+        let file = config.required_path(entry, "nodes_file")?;
+        let types = match config.entry_path(entry, "node_types_file")? {
+            Some(path) => TypeTable::load(&path)?,
+            None => TypeTable::default(),
+        };
+        let h5 = Hdf5File::open(&file)?;
+        for population in h5.members("/nodes")? {
+            let base = format!("/nodes/{population}");
+            let table = ElementTable::read(&h5, &base, "node")?;
+            let start = neurons.len();
+            let ids = match h5.contains(&format!("{base}/node_id")) {
+                true => h5.read_ints(&format!("{base}/node_id"))?,
+                false => (0..table.len() as i64).collect(),
+            };
+            let index = node_index.entry(population.clone()).or_default();
+            for (k, &id) in ids.iter().enumerate() {
+                index.insert(id, start + k);
+            }
+
+            let mut type_params: HashMap<i64, Option<Json>> = HashMap::new();
+            let mut pop_names: Vec<(String, Vec<usize>)> = Vec::new();
+            for k in 0..table.len() {
+                let type_id = table.type_ids[k];
+                let row = types.row(type_id);
+                let dynamics = match type_params.entry(type_id) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => {
+                        e.insert(match row.and_then(|r| r.get("dynamics_params")) {
+                            Some(file) if !is_null(file) => {
+                                let dir = models_dir.as_deref().unwrap_or(&config.dir);
+                                Some(load_json(&dir.join(file))?)
+                            }
+                            _ => None,
+                        })
+                    }
+                };
+                let dynamics = dynamics.as_ref();
+                let param = |key: &str| -> io::Result<Option<f64>> {
+                    if let Some(value) = table.value(k, key) {
+                        return Ok(Some(value));
+                    }
+                    if let Some(value) = types.number(type_id, key)? {
+                        return Ok(Some(value));
+                    }
+                    Ok(match dynamics.and_then(|d| d.get(key)) {
+                        Some(Json::Number(x)) => Some(*x),
+                        _ => None,
+                    })
+                };
+                neurons.push(Neuron::new(NeuronParams {
+                    tau_m: param("tau_m")?.map_or(defaults.tau_m, Milliseconds),
+                    v_rest: param("E_L")?.unwrap_or(defaults.v_rest),
+                    v_thresh: param("V_th")?.unwrap_or(defaults.v_thresh),
+                    v_reset: param("V_reset")?.unwrap_or(defaults.v_reset),
+                }));
+                let position = match (
+                    table.value(k, "x"),
+                    table.value(k, "y"),
+                    table.value(k, "z"),
+                ) {
+                    (Some(x), Some(y), z) if !x.is_nan() && !y.is_nan() => Some(Position::new(
+                        x,
+                        y,
+                        z.filter(|z| !z.is_nan()).unwrap_or(0.0),
+                    )),
+                    _ => None,
+                };
+                positions.push(position);
+
+                if let Some(pop_name) = row.and_then(|r| r.get("pop_name")) {
+                    if *pop_name != population && !is_null(pop_name) {
+                        match pop_names.iter_mut().find(|(n, _)| n == pop_name) {
+                            Some((_, members)) => members.push(start + k),
+                            None => pop_names.push((pop_name.clone(), vec![start + k])),
+                        }
+                    }
+                }
+            }
+
+            populations.push(NamedPopulation {
+                name: population.clone(),
+                range: start..neurons.len(),
+            });
+            for (name, members) in pop_names {
+                match contiguous(&members) {
+                    Some(range)
+                        if !populations.iter().any(|p: &NamedPopulation| p.name == name) =>
+                    {
+                        populations.push(NamedPopulation { name, range })
+                    }
+                    _ => tag_group(&mut groups, &name, members),
+                }
+            }
+        }
+    }
+
+    let mut synapses = Vec::new();
+    for entry in list(networks.get("edges")) {
+        let file = config.required_path(entry, "edges_file")?;
+        let types = match config.entry_path(entry, "edge_types_file")? {
+            Some(path) => TypeTable::load(&path)?,
+            None => TypeTable::default(),
+        };
+        let h5 = Hdf5File::open(&file)?;
+        for population in h5.members("/edges")? {
+            let base = format!("/edges/{population}");
+            let table = ElementTable::read(&h5, &base, "edge")?;
+            let endpoint = |side: &str| -> io::Result<Vec<usize>> {
+                let path = format!("{base}/{side}_node_id");
+                let nodes = match h5.attribute(&path, "node_population")? {
+                    Some(Attribute::Text(name)) => name,
+                    _ if node_index.len() == 1 => {
+                        node_index.keys().next().cloned().unwrap_or_default()
+                    }
+                    _ => return Err(invalid(format!("{path} has no node_population attribute"))),
+                };
+                let index = node_index
+                    .get(&nodes)
+                    .ok_or_else(|| invalid(format!("Unknown node population {nodes} in {path}")))?;
+                h5.read_ints(&path)?
+                    .into_iter()
+                    .map(|id| {
+                        index.get(&id).copied().ok_or_else(|| {
+                            invalid(format!("Unknown node {id} of {nodes} in {path}"))
+                        })
+                    })
+                    .collect()
+            };
+            let sources = endpoint("source")?;
+            let targets = endpoint("target")?;
+            if sources.len() != table.len() || targets.len() != table.len() {
+                return Err(invalid(format!("Edge datasets of {base} differ in length")));
+            }
+            for k in 0..table.len() {
+                let type_id = table.type_ids[k];
+                let value = |key: &str| -> io::Result<Option<f64>> {
+                    match table.value(k, key) {
+                        Some(value) => Ok(Some(value)),
+                        None => types.number(type_id, key),
+                    }
+                };
+                let weight = value("syn_weight")?
+                    .ok_or_else(|| invalid(format!("Edge {k} of {base} has no syn_weight")))?;
+                let nsyns = value("nsyns")?.unwrap_or(1.0);
+                let delay = value("delay")?.unwrap_or(0.0);
+                synapses.push(Synapse::with_delay(
+                    sources[k],
+                    targets[k],
+                    weight * nsyns,
+                    Milliseconds(delay),
+                ));
+            }
+        }
+    }
+
+    Ok(Network {
+        neurons,
+        synapses,
+        populations,
+        plasticity_sets: Vec::new(),
+        positions,
+        groups,
+    })
+}
+
+/// Innermost population containing neuron `i`.
+fn population_of(network: &Network, i: usize) -> Option<&str> {
+    network
+        .populations()
+        .iter()
+        .filter(|p| p.range.contains(&i))
+        .min_by_key(|p| p.range.len())
+        .map(|p| p.name.as_str())
+}
+
+fn same_params(a: &NeuronParams, b: &NeuronParams) -> bool {
+    a.tau_m == b.tau_m && a.v_rest == b.v_rest && a.v_thresh == b.v_thresh && a.v_reset == b.v_reset
+}
+
+/// NEST `iaf_psc_delta` parameters equivalent to `params`, with a 1 GΩ
+/// membrane resistance so that a current `I` of the LIF equation is `I` pA.
+fn dynamics_params(params: &NeuronParams) -> String {
+    let mut out = String::from("{");
+    let fields = [
+        ("C_m", params.tau_m.0),
+        ("tau_m", params.tau_m.0),
+        ("E_L", params.v_rest),
+        ("V_m", params.v_rest),
+        ("V_th", params.v_thresh),
+        ("V_reset", params.v_reset),
+        ("t_ref", 0.0),
+    ];
+    for (k, (name, value)) in fields.iter().enumerate() {
+        if k > 0 {
+            out.push_str(", ");
+        }
+        write_string(&mut out, name);
+        out.push_str(": ");
+        write_number(&mut out, *value);
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn circuit_config(name: &str) -> String {
+    let mut out = String::from(
+        "{\n  \"manifest\": {\n    \"$BASE_DIR\": \"${configdir}\",\n    \
+         \"$NETWORK_DIR\": \"$BASE_DIR/network\",\n    \
+         \"$COMPONENTS_DIR\": \"$BASE_DIR/components\"\n  },\n  \
+         \"components\": {\n    \
+         \"point_neuron_models_dir\": \"$COMPONENTS_DIR/point_neuron_models\"\n  },\n  \
+         \"networks\": {\n",
+    );
+    let files = [
+        (
+            "nodes",
+            format!("{name}_nodes.h5"),
+            "node_types",
+            format!("{name}_node_types.csv"),
+        ),
+        (
+            "edges",
+            format!("{name}_{name}_edges.h5"),
+            "edge_types",
+            format!("{name}_{name}_edge_types.csv"),
+        ),
+    ];
+    for (k, (kind, file, types_kind, types_file)) in files.iter().enumerate() {
+        let _ = write!(
+            out,
+            "    \"{kind}\": [\n      {{\n        \"{kind}_file\": "
+        );
+        write_string(&mut out, &format!("$NETWORK_DIR/{file}"));
+        let _ = write!(out, ",\n        \"{types_kind}_file\": ");
+        write_string(&mut out, &format!("$NETWORK_DIR/{types_file}"));
+        out.push_str("\n      }\n    ]");
+        out.push_str(if k == 0 { ",\n" } else { "\n" });
+    }
+    out.push_str("  }\n}\n");
+    out
+}
+
+/// Write the SONATA format `magic` and `version` root attributes.
+fn write_header(writer: &mut Hdf5Writer) -> io::Result<()> {
+    writer.create_group(
+        "/",
+        &[
+            ("magic", Attribute::Int(0x0A7A)),
+            ("version", Attribute::Ints(vec![0, 1])),
+        ],
+    )
+}
+
+/// `text` as a field of a space-separated CSV file.
+fn csv_field(text: &str) -> String {
+    if text.is_empty() || text.contains([' ', '"', '\t']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Type ids, group ids and group datasets of a node or edge population.
+struct ElementTable {
+    type_ids: Vec<i64>,
+    group_ids: Vec<i64>,
+    group_indices: Vec<i64>,
+    /// Numeric datasets of each group, by group id and name
+    columns: HashMap<(i64, String), Vec<f64>>,
+}
+
+impl ElementTable {
+    /// Read the population at `base`, whose datasets are prefixed with
+    /// `kind` (`node` or `edge`).
+    fn read(h5: &Hdf5File, base: &str, kind: &str) -> io::Result<Self> {
+        let type_ids = h5.read_ints(&format!("{base}/{kind}_type_id"))?;
+        let groups: Vec<i64> = h5
+            .members(base)?
+            .iter()
+            .filter_map(|m| m.parse().ok())
+            .collect();
+        let (group_ids, group_indices) = if h5.contains(&format!("{base}/{kind}_group_id")) {
+            (
+                h5.read_ints(&format!("{base}/{kind}_group_id"))?,
+                h5.read_ints(&format!("{base}/{kind}_group_index"))?,
+            )
+        } else {
+            // A single group indexed like the population
+            let group = groups.first().copied().unwrap_or(0);
+            (
+                vec![group; type_ids.len()],
+                (0..type_ids.len() as i64).collect(),
+            )
+        };
+        if group_ids.len() != type_ids.len() || group_indices.len() != type_ids.len() {
+            return Err(invalid(format!("Datasets of {base} differ in length")));
+        }
+
+        let mut columns = HashMap::new();
+        for group in groups {
+            let path = format!("{base}/{group}");
+            for name in h5.members(&path)? {
+                // Skip subgroups and string datasets such as model names
+                if let Ok(values) = h5.read_floats(&format!("{path}/{name}")) {
+                    columns.insert((group, name), values);
+                }
+            }
+        }
+        Ok(Self {
+            type_ids,
+            group_ids,
+            group_indices,
+            columns,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.type_ids.len()
+    }
+
+    /// Value of dataset `name` in the group of element `k`.
+    fn value(&self, k: usize, name: &str) -> Option<f64> {
+        let column = self.columns.get(&(self.group_ids[k], name.to_string()))?;
+        usize::try_from(self.group_indices[k])
+            .ok()
+            .and_then(|i| column.get(i))
+            .copied()
+    }
+}
+
+/// Rows of a node or edge types CSV file, by type id.
+#[derive(Default)]
+struct TypeTable {
+    rows: HashMap<i64, HashMap<String, String>>,
+}
+
+impl TypeTable {
+    fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header = split_fields(lines.next().unwrap_or_default());
+        let id_column = header
+            .iter()
+            .position(|h| h.ends_with("_type_id"))
+            .ok_or_else(|| invalid(format!("{} has no type id column", path.display())))?;
+        let mut rows = HashMap::new();
+        for line in lines {
+            let fields = split_fields(line);
+            let id = fields
+                .get(id_column)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| invalid(format!("Bad type id in {}: {line}", path.display())))?;
+            rows.insert(id, header.iter().cloned().zip(fields).collect());
+        }
+        Ok(Self { rows })
+    }
+
+    fn row(&self, id: i64) -> Option<&HashMap<String, String>> {
+        self.rows.get(&id)
+    }
+
+    /// Numeric column `name` of type `id`, if present and not null.
+    fn number(&self, id: i64, name: &str) -> io::Result<Option<f64>> {
+        match self.row(id).and_then(|r| r.get(name)) {
+            Some(text) if !is_null(text) => text
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid(format!("{name} of type {id} is not a number: {text}"))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Fields of a space-separated CSV line, with double-quoted fields.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return fields;
+        };
+        let mut field = String::new();
+        if first == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                if c == '"' && chars.next_if_eq(&'"').is_none() {
+                    break;
+                }
+                field.push(c);
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                field.push(c);
+            }
+        }
+        fields.push(field);
+    }
+}
+
+/// Whether a CSV field is one of the null markers of pandas and BMTK.
+fn is_null(field: &str) -> bool {
+    matches!(field, "" | "NULL" | "NONE" | "None" | "NaN" | "nan")
+}
+
+/// `members` as a range if they are consecutive.
+fn contiguous(members: &[usize]) -> Option<Range<usize>> {
+    let start = *members.first()?;
+    let consecutive = members.iter().enumerate().all(|(k, &i)| i == start + k);
+    consecutive.then(|| start..start + members.len())
+}
+
+fn list(value: Option<&Json>) -> &[Json] {
+    match value {
+        Some(Json::Array(items)) => items,
+        _ => &[],
+    }
+}
+
+fn load_json(path: &Path) -> io::Result<Json> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+    JsonParser::new(&text)
+        .parse_document()
+        .map_err(|e| invalid(format!("{}: {e}", path.display())))
+}
+
+/// A parsed configuration file with its manifest.
+struct Config {
+    json: Json,
+    /// Manifest variables with their resolved values, longest name first
+    manifest: Vec<(String, String)>,
+    dir: PathBuf,
+}
+
+impl Config {
+    fn load(path: &Path) -> io::Result<Self> {
+        let json = load_json(path)?;
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut manifest: Vec<(String, String)> = match json.get("manifest") {
+            Some(Json::Object(fields)) => fields
+                .iter()
+                .filter_map(|(k, v)| match v {
+                    Json::String(s) => Some((k.clone(), s.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        manifest.sort_by_key(|(k, _)| std::cmp::Reverse(k.len()));
+        let mut config = Self {
+            json,
+            manifest: Vec::new(),
+            dir,
+        };
+        // Variables may refer to each other; resolve in dependency order
+        for _ in 0..manifest.len() {
+            let resolved: Vec<_> = manifest
+                .iter()
+                .map(|(k, v)| (k.clone(), config.expand_with(v, &manifest)))
+                .collect();
+            manifest = resolved;
+        }
+        config.manifest = manifest;
+        Ok(config)
+    }
+
+    fn expand_with(&self, text: &str, manifest: &[(String, String)]) -> String {
+        let mut text = text.replace("${configdir}", &self.dir.to_string_lossy());
+        for (name, value) in manifest {
+            text = text.replace(name.as_str(), value);
+        }
+        text
+    }
+
+    /// `text` with manifest variables substituted, relative to the
+    /// configuration's directory.
+    fn resolve(&self, text: &str) -> PathBuf {
+        self.dir.join(self.expand_with(text, &self.manifest))
+    }
+
+    /// Path in string field `name` of the top-level object.
+    fn path(&self, name: &str) -> io::Result<Option<PathBuf>> {
+        self.entry_path(&self.json, name)
+    }
+
+    /// Path at the nested string field `names`.
+    fn path_at(&self, names: &[&str]) -> io::Result<Option<PathBuf>> {
+        let (last, parents) = names.split_last().expect("field path is not empty");
+        match parents.iter().try_fold(&self.json, |j, n| j.get(n)) {
+            Some(object) => self.entry_path(object, last),
+            None => Ok(None),
+        }
+    }
+
+    /// Path in string field `name` of `entry`.
+    fn entry_path(&self, entry: &Json, name: &str) -> io::Result<Option<PathBuf>> {
+        match entry.get(name) {
+            Some(Json::String(text)) => Ok(Some(self.resolve(text))),
+            None => Ok(None),
+            Some(_) => Err(invalid(format!("{name} is not a string"))),
+        }
+    }
+
+    fn required_path(&self, entry: &Json, name: &str) -> io::Result<PathBuf> {
+        self.entry_path(entry, name)?
+            .ok_or_else(|| invalid(format!("Network entry has no {name}")))
+    }
+}
@@ -0,0 +1,262 @@
+//! spatial.rs
+//!
+//! Spatial neuron positions and distance-dependent connectivity.
+//!
+//! Topographic map formation and wave-propagation models depend on where
+//! neurons sit in space: nearby neurons are more likely to be connected,
+//! and signals between distant neurons take longer to arrive. Neurons can
+//! be given optional 2D or 3D positions, and a `DistanceRule` turns
+//! Euclidean distance into a connection probability and an axonal delay.
+
+use crate::connectivity::SheetShape;
+use crate::rng::Rng;
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// A point in space. 2D layouts use `z = 0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// x coordinate
+    pub x: f64,
+    /// y coordinate
+    pub y: f64,
+    /// z coordinate
+    pub z: f64,
+}
+
+impl Position {
+    /// Create a 3D position.
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Create a 2D position in the `z = 0` plane.
+    pub fn planar(x: f64, y: f64) -> Self {
+        Self { x, y, z: 0.0 }
+    }
+
+    /// Euclidean distance to `other`.
+    pub fn distance(&self, other: &Position) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2))
+            .sqrt()
+    }
+}
+
+/// Connection probability as a function of distance.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistanceKernel {
+    /// `p(d) = p_max * exp(-d² / (2 σ²))`
+    Gaussian {
+        /// Probability at zero distance
+        p_max: f64,
+        /// Kernel width
+        sigma: f64,
+    },
+    /// `p(d) = p_max * exp(-d / λ)`
+    Exponential {
+        /// Probability at zero distance
+        p_max: f64,
+        /// Length constant
+        lambda: f64,
+    },
+}
+
+impl DistanceKernel {
+    /// Connection probability at distance `d`.
+    pub fn probability(&self, d: f64) -> f64 {
+        match *self {
+            DistanceKernel::Gaussian { p_max, sigma } => {
+                p_max * (-(d * d) / (2.0 * sigma * sigma)).exp()
+            }
+            DistanceKernel::Exponential { p_max, lambda } => p_max * (-d / lambda).exp(),
+        }
+    }
+}
+
+/// Distance-dependent connection rule.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceRule {
+    /// Connection probability as a function of distance
+    pub kernel: DistanceKernel,
+    /// Delay at zero distance
+    pub delay_offset: Milliseconds,
+    /// Additional delay per unit of distance (inverse conduction velocity)
+    pub delay_per_unit: Milliseconds,
+}
+
+impl DistanceRule {
+    /// Axonal delay for a connection spanning distance `d`.
+    pub fn delay(&self, d: f64) -> Milliseconds {
+        self.delay_offset + self.delay_per_unit * d
+    }
+}
+
+/// A generated connection with delay: `(pre, post, weight, delay)`.
+pub type DelayedConnection = (usize, usize, f64, Milliseconds);
+
+/// Connect neurons in `pre` to neurons in `post` with a probability and
+/// delay that depend on their Euclidean distance.
+///
+/// `positions` is indexed by neuron id. The weight hook receives
+/// `(pre, post, distance, rng)`. Self-connections are never generated.
+///
+/// # Panics
+/// Panics if a neuron in either range has no entry in `positions`.
+pub fn distance_dependent<W>(
+    pre: Range<usize>,
+    post: Range<usize>,
+    positions: &[Position],
+    rule: &DistanceRule,
+    rng: &mut Rng,
+    mut weight: W,
+) -> Vec<DelayedConnection>
+where
+    W: FnMut(usize, usize, f64, &mut Rng) -> f64,
+{
+    assert!(
+        pre.end <= positions.len() && post.end <= positions.len(),
+        "Every connected neuron needs a position"
+    );
+    let mut connections = Vec::new();
+    for i in pre {
+        for j in post.clone() {
+            if i == j {
+                continue;
+            }
+            let d = positions[i].distance(&positions[j]);
+            if rng.bernoulli(rule.kernel.probability(d)) {
+                let w = weight(i, j, d, rng);
+                connections.push((i, j, w, rule.delay(d)));
+            }
+        }
+    }
+    connections
+}
+
+/// Difference-of-Gaussians lateral interaction profile.
+///
+/// `w(d) = exc_amplitude * exp(-d² / 2σ_e²) - inh_amplitude * exp(-d² / 2σ_i²)`,
+/// giving excitation between close neighbours and inhibition in a wider
+/// surround when `inh_sigma > exc_sigma`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DogKernel {
+    /// Peak of the excitatory center
+    pub exc_amplitude: f64,
+    /// Width of the excitatory center
+    pub exc_sigma: f64,
+    /// Peak of the inhibitory surround
+    pub inh_amplitude: f64,
+    /// Width of the inhibitory surround
+    pub inh_sigma: f64,
+    /// Connections with `|w|` below this value are omitted
+    pub cutoff: f64,
+}
+
+impl DogKernel {
+    /// Interaction weight at distance `d`.
+    pub fn weight(&self, d: f64) -> f64 {
+        let g = |a: f64, s: f64| a * (-(d * d) / (2.0 * s * s)).exp();
+        g(self.exc_amplitude, self.exc_sigma) - g(self.inh_amplitude, self.inh_sigma)
+    }
+}
+
+/// Difference-of-Gaussians lateral connectivity among `neurons`.
+///
+/// Works for 1D and 2D sheets alike, since only Euclidean distance between
+/// `positions` (indexed by neuron id) matters. Self-connections are never
+/// generated, and weights smaller in magnitude than `kernel.cutoff` are
+/// dropped to keep the network sparse.
+///
+/// # Panics
+/// Panics if a neuron in `neurons` has no entry in `positions`.
+pub fn difference_of_gaussians(
+    neurons: Range<usize>,
+    positions: &[Position],
+    kernel: &DogKernel,
+) -> Vec<(usize, usize, f64)> {
+    assert!(
+        neurons.end <= positions.len(),
+        "Every connected neuron needs a position"
+    );
+    let mut connections = Vec::new();
+    for i in neurons.clone() {
+        for j in neurons.clone() {
+            if i == j {
+                continue;
+            }
+            let w = kernel.weight(positions[i].distance(&positions[j]));
+            if w.abs() >= kernel.cutoff {
+                connections.push((i, j, w));
+            }
+        }
+    }
+    connections
+}
+
+/// Boundary condition of a 2D grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    /// Edges are hard; border neurons have fewer neighbours.
+    Open,
+    /// Opposite edges are joined, turning the grid into a torus.
+    Periodic,
+}
+
+/// Positions of a row-major grid with the given spacing, in the `z = 0`
+/// plane.
+pub fn grid_positions(shape: SheetShape, spacing: f64) -> Vec<Position> {
+    (0..shape.len())
+        .map(|k| {
+            let (x, y) = (k % shape.width, k / shape.width);
+            Position::planar(x as f64 * spacing, y as f64 * spacing)
+        })
+        .collect()
+}
+
+/// Nearest-neighbour pairs on a row-major grid of neurons starting at
+/// index `start`.
+///
+/// Neurons are linked in both directions when their grid distance, in
+/// units of the grid spacing, is at most `radius`. With
+/// `Boundary::Periodic` distances wrap around the edges. `radius = 1`
+/// gives the von Neumann neighbourhood and `radius = √2` the Moore
+/// neighbourhood.
+pub fn grid_neighbors(
+    start: usize,
+    shape: SheetShape,
+    radius: f64,
+    boundary: Boundary,
+) -> Vec<(usize, usize)> {
+    let (w, h) = (shape.width as i64, shape.height as i64);
+    let reach = radius.max(0.0).floor() as i64;
+    let mut pairs = Vec::new();
+    for k in 0..shape.len() {
+        let (x, y) = ((k % shape.width) as i64, (k / shape.width) as i64);
+        let mut targets = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if (dx == 0 && dy == 0) || ((dx * dx + dy * dy) as f64).sqrt() > radius {
+                    continue;
+                }
+                let (nx, ny) = match boundary {
+                    Boundary::Open => (x + dx, y + dy),
+                    Boundary::Periodic => ((x + dx).rem_euclid(w), (y + dy).rem_euclid(h)),
+                };
+                if (0..w).contains(&nx) && (0..h).contains(&ny) {
+                    let target = start + (ny * w + nx) as usize;
+                    // Small tori can reach the same neighbour twice
+                    if target != start + k && !targets.contains(&target) {
+                        targets.push(target);
+                    }
+                }
+            }
+        }
+        pairs.extend(targets.into_iter().map(|t| (start + k, t)));
+    }
+    pairs
+}
@@ -7,18 +7,21 @@
 //! characterized primarily by *when* it occurs, making time a first-class
 //! computational variable.
 
+use crate::units::Milliseconds;
+
 /// A spike emitted by a neuron at a specific time.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spike {
     /// ID of the neuron that emitted the spike
     pub neuron_id: usize,
-    /// Time of spike emission (ms)
-    pub time: f64,
+    /// Time of spike emission
+    pub time: Milliseconds,
 }
 
 impl Spike {
     /// Create a new spike event.
-    pub fn new(neuron_id: usize, time: f64) -> Self {
+    pub fn new(neuron_id: usize, time: Milliseconds) -> Self {
         Self { neuron_id, time }
     }
 }
@@ -0,0 +1,210 @@
+//! spike_io.rs
+//!
+//! Import and export of spike trains.
+//!
+//! Stimuli are often produced outside the crate, by Python scripts or from
+//! recorded datasets. This module reads them from CSV or JSON, checks them
+//! against a fixed schema, and returns spikes sorted by time, ready for
+//! `Simulation::inject_spikes`. Problems are reported as `InvalidData`
+//! errors naming the offending row or element. The writers produce the
+//! same formats, so spike trains can be converted between them.
+//!
+//! CSV files need a header containing the columns `neuron_id` and
+//! `time_ms`, in any order; other columns are ignored. This is the layout
+//! written by `Simulation::write_spikes_to_csv`.
+//!
+//! JSON files contain either an array of spike objects,
+//! `[{"neuron_id": 0, "time_ms": 1.5}, ...]`, or an object of two equally
+//! long columns, `{"neuron_id": [0, ...], "time_ms": [1.5, ...]}`.
+//!
+//! In both formats neuron ids must be non-negative integers and times
+//! finite and non-negative.
+//!
+//! The writers compress their output when the path ends in `.gz` or
+//! `.zst`, see `compression`; the readers reject such paths.
+
+use crate::compression;
+use crate::encoding::sort_spikes;
+use crate::json::{write_number, Json, JsonParser};
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use crate::util::invalid;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Read spikes from a CSV file with `neuron_id` and `time_ms` columns.
+pub fn read_spikes_csv<P: AsRef<Path>>(path: P) -> io::Result<Vec<Spike>> {
+    let mut lines = BufReader::new(compression::open(path)?).lines();
+    let header = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| invalid("Spike CSV file is empty".to_string()))?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| {
+        columns
+            .iter()
+            .position(|&c| c == name)
+            .ok_or_else(|| invalid(format!("Spike CSV header lacks a `{name}` column")))
+    };
+    let (id_col, time_col) = (column("neuron_id")?, column("time_ms")?);
+
+    let mut spikes = Vec::new();
+    for (n, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = n + 2;
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != columns.len() {
+            return Err(invalid(format!(
+                "Row {row} has {} fields, expected {}",
+                fields.len(),
+                columns.len()
+            )));
+        }
+        let neuron_id = fields[id_col]
+            .parse::<f64>()
+            .map_err(|_| invalid(format!("Row {row}: invalid neuron_id `{}`", fields[id_col])))?;
+        let time = fields[time_col]
+            .parse::<f64>()
+            .map_err(|_| invalid(format!("Row {row}: invalid time_ms `{}`", fields[time_col])))?;
+        spikes.push(checked_spike(neuron_id, time, &format!("Row {row}"))?);
+    }
+    sort_spikes(&mut spikes);
+    Ok(spikes)
+}
+
+/// Read spikes from a JSON file in either the row or the column layout.
+pub fn read_spikes_json<P: AsRef<Path>>(path: P) -> io::Result<Vec<Spike>> {
+    let text = io::read_to_string(compression::open(path)?)?;
+    let mut parser = JsonParser::new(&text);
+    let value = parser.parse_document().map_err(invalid)?;
+
+    let mut spikes = match value {
+        Json::Array(rows) => rows
+            .iter()
+            .enumerate()
+            .map(|(k, row)| {
+                let context = format!("Element {k}");
+                let Json::Object(fields) = row else {
+                    return Err(invalid(format!("{context} is not an object")));
+                };
+                let id = number_field(fields, "neuron_id", &context)?;
+                let time = number_field(fields, "time_ms", &context)?;
+                checked_spike(id, time, &context)
+            })
+            .collect::<io::Result<Vec<Spike>>>()?,
+        Json::Object(fields) => {
+            let ids = array_field(&fields, "neuron_id")?;
+            let times = array_field(&fields, "time_ms")?;
+            if ids.len() != times.len() {
+                return Err(invalid(format!(
+                    "`neuron_id` has {} entries but `time_ms` has {}",
+                    ids.len(),
+                    times.len()
+                )));
+            }
+            ids.iter()
+                .zip(times)
+                .enumerate()
+                .map(|(k, (id, time))| {
+                    let context = format!("Entry {k}");
+                    match (id, time) {
+                        (Json::Number(id), Json::Number(time)) => {
+                            checked_spike(*id, *time, &context)
+                        }
+                        _ => Err(invalid(format!("{context} is not numeric"))),
+                    }
+                })
+                .collect::<io::Result<Vec<Spike>>>()?
+        }
+        _ => {
+            return Err(invalid(
+                "Spike JSON must be an array of spikes or an object of columns".to_string(),
+            ))
+        }
+    };
+    sort_spikes(&mut spikes);
+    Ok(spikes)
+}
+
+/// Write spikes as CSV with a `neuron_id,time_ms` header.
+pub fn write_spikes_csv<P: AsRef<Path>>(spikes: &[Spike], path: P) -> io::Result<()> {
+    let mut writer = compression::create(path)?;
+    writeln!(writer, "neuron_id,time_ms")?;
+    for spike in spikes {
+        writeln!(writer, "{},{}", spike.neuron_id, spike.time.0)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Write spikes as JSON in the column layout.
+pub fn write_spikes_json<P: AsRef<Path>>(spikes: &[Spike], path: P) -> io::Result<()> {
+    let mut out = String::from("{\"neuron_id\":[");
+    for (k, spike) in spikes.iter().enumerate() {
+        if k > 0 {
+            out.push(',');
+        }
+        out.push_str(&spike.neuron_id.to_string());
+    }
+    out.push_str("],\"time_ms\":[");
+    for (k, spike) in spikes.iter().enumerate() {
+        if k > 0 {
+            out.push(',');
+        }
+        write_number(&mut out, spike.time.0);
+    }
+    out.push_str("]}\n");
+    let mut writer = compression::create(path)?;
+    writer.write_all(out.as_bytes())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Check that every spike targets a neuron in `0..num_neurons`.
+///
+/// Returns an `InvalidData` error naming the first offending spike.
+pub fn validate_spikes(spikes: &[Spike], num_neurons: usize) -> io::Result<()> {
+    match spikes.iter().position(|s| s.neuron_id >= num_neurons) {
+        Some(k) => Err(invalid(format!(
+            "Spike {k} targets neuron {} outside 0..{num_neurons}",
+            spikes[k].neuron_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Build a spike after checking the schema constraints on its fields.
+fn checked_spike(neuron_id: f64, time: f64, context: &str) -> io::Result<Spike> {
+    if !(neuron_id >= 0.0 && neuron_id.fract() == 0.0 && neuron_id <= usize::MAX as f64) {
+        return Err(invalid(format!(
+            "{context}: neuron_id {neuron_id} is not a non-negative integer"
+        )));
+    }
+    if !time.is_finite() || time < 0.0 {
+        return Err(invalid(format!(
+            "{context}: time_ms {time} is not a finite non-negative number"
+        )));
+    }
+    Ok(Spike::new(neuron_id as usize, Milliseconds(time)))
+}
+
+/// Numeric field `name` of a spike object.
+fn number_field(fields: &[(String, Json)], name: &str, context: &str) -> io::Result<f64> {
+    match fields.iter().find(|(k, _)| k == name) {
+        Some((_, Json::Number(x))) => Ok(*x),
+        Some(_) => Err(invalid(format!("{context}: `{name}` is not a number"))),
+        None => Err(invalid(format!("{context}: missing `{name}`"))),
+    }
+}
+
+/// Array field `name` of a column-layout document.
+fn array_field<'a>(fields: &'a [(String, Json)], name: &str) -> io::Result<&'a [Json]> {
+    match fields.iter().find(|(k, _)| k == name) {
+        Some((_, Json::Array(items))) => Ok(items),
+        Some(_) => Err(invalid(format!("`{name}` is not an array"))),
+        None => Err(invalid(format!("Missing `{name}` column"))),
+    }
+}
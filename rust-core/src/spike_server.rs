@@ -0,0 +1,375 @@
+//! spike_server.rs
+//!
+//! Spike exchange over TCP.
+//!
+//! A visualization, a robot controller or a second simulator often runs as
+//! a separate process, possibly on another machine. A `SpikeServer` listens
+//! on a TCP port, streams the spikes of a simulation to every connected
+//! client as they are produced, and injects the spikes clients send into
+//! the network, so processes can be coupled without sharing memory.
+//!
+//! The protocol is line-based UTF-8 text, easy to speak from any language
+//! or even `nc`:
+//!
+//! ```text
+//! server -> client
+//!     spike <neuron> <time_ms>    a neuron fired
+//!     step <time_ms>              all spikes before this time have been sent
+//!                                 (only with step markers enabled)
+//! client -> server
+//!     spike <neuron> <time_ms>    make a neuron fire at that time
+//!     spike <neuron>              make a neuron fire during the next step
+//! ```
+//!
+//! Malformed lines and spikes for neurons outside the network are ignored.
+//! Clients that stop reading are disconnected once a write has blocked for
+//! `WRITE_TIMEOUT`, so a stalled viewer cannot stall the simulation, and
+//! clients sending a line longer than `MAX_LINE` bytes are disconnected, so
+//! a misbehaving peer cannot exhaust the server's memory.
+
+use crate::monitor::{Monitor, StepView};
+use crate::simulation::{pace_to_wall_clock, Simulation};
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use crate::util::{invalid, lock};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest time a write to one client may block before it is dropped.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Longest line, in bytes without the newline, either side accepts.
+pub const MAX_LINE: usize = 1024;
+/// Interval at which the accept thread checks for shutdown.
+const ACCEPT_POLL: Duration = Duration::from_millis(10);
+
+type Clients = Arc<Mutex<Vec<BufWriter<TcpStream>>>>;
+
+/// TCP server streaming spikes to clients and receiving injected spikes.
+///
+/// Clients may connect at any time; each receives the spikes produced
+/// after it connected. Dropping the server stops accepting connections and
+/// closes the existing ones.
+#[derive(Debug)]
+pub struct SpikeServer {
+    address: SocketAddr,
+    clients: Clients,
+    input: Mutex<Receiver<Spike>>,
+    step_markers: bool,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl SpikeServer {
+    /// Listen on `address`, e.g. `"127.0.0.1:7878"`; port 0 picks a free
+    /// port, see `local_addr`.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let clients: Clients = Arc::default();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let accept_clients = Arc::clone(&clients);
+        let accept_shutdown = Arc::clone(&shutdown);
+        std::thread::spawn(move || {
+            while !accept_shutdown.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Ok(writer) = connect(stream, sender.clone()) {
+                            lock(&accept_clients).push(writer);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(ACCEPT_POLL)
+                    }
+                    Err(_) => std::thread::sleep(ACCEPT_POLL),
+                }
+            }
+        });
+
+        Ok(Self {
+            address,
+            clients,
+            input: Mutex::new(receiver),
+            step_markers: false,
+            shutdown,
+        })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Send a `step` line after every step, so clients know when a step's
+    /// spikes are complete. Off by default.
+    pub fn set_step_markers(&mut self, enabled: bool) -> &mut Self {
+        self.step_markers = enabled;
+        self
+    }
+
+    /// Number of connected clients.
+    pub fn num_clients(&self) -> usize {
+        lock(&self.clients).len()
+    }
+
+    /// Wait until at least `n` clients are connected, for at most
+    /// `timeout`. Returns whether they are.
+    pub fn wait_for_clients(&self, n: usize, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.num_clients() < n {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            std::thread::sleep(ACCEPT_POLL);
+        }
+        true
+    }
+
+    /// Send `spikes` to every client, followed by a step marker for `end`
+    /// if markers are enabled.
+    pub fn broadcast(&self, spikes: &[Spike], end: Milliseconds) {
+        send(&self.clients, spikes, self.step_markers.then_some(end));
+    }
+
+    /// Monitor that streams the spikes of a simulation to this server's
+    /// clients, for use with `Simulation::run` and its variants when no
+    /// input is expected from clients.
+    pub fn broadcaster(&self) -> SpikeBroadcaster {
+        SpikeBroadcaster {
+            clients: Arc::clone(&self.clients),
+            step_markers: self.step_markers,
+        }
+    }
+
+    /// Spikes received from clients since the last call, in arrival order.
+    pub fn take_input(&self) -> Vec<Spike> {
+        lock(&self.input).try_iter().collect()
+    }
+
+    /// Run `sim` until its `t_max`, injecting the spikes received from
+    /// clients before every step and streaming the emitted spikes.
+    ///
+    /// The run is paced by the simulation's `realtime_factor`, which
+    /// remote peers usually need. Returns the emitted spikes.
+    pub fn run<F>(&self, sim: &mut Simulation, input_current_fn: F) -> Vec<Spike>
+    where
+        F: Fn(usize, Milliseconds) -> f64 + Sync,
+    {
+        let wall_start = Instant::now();
+        let sim_start = sim.time();
+        let mut spikes = Vec::new();
+        while sim.time() < sim.config().t_max {
+            let now = sim.time();
+            let n = sim.neurons().len();
+            let input: Vec<Spike> = self
+                .take_input()
+                .into_iter()
+                .filter(|s| s.neuron_id < n)
+                .map(|s| {
+                    if s.time.0.is_nan() {
+                        Spike::new(s.neuron_id, now)
+                    } else {
+                        s
+                    }
+                })
+                .collect();
+            if !input.is_empty() {
+                sim.inject_spikes(&input);
+            }
+
+            let fired = sim.step(&input_current_fn);
+            self.broadcast(&fired, sim.time());
+            spikes.extend(fired);
+            if let Some(factor) = sim.config().realtime_factor {
+                pace_to_wall_clock(wall_start, sim.time() - sim_start, factor);
+            }
+        }
+        spikes
+    }
+}
+
+impl Drop for SpikeServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        for client in lock(&self.clients).drain(..) {
+            let _ = client.get_ref().shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+/// Monitor streaming spikes to the clients of a `SpikeServer`.
+#[derive(Debug, Clone)]
+pub struct SpikeBroadcaster {
+    clients: Clients,
+    step_markers: bool,
+}
+
+impl Monitor for SpikeBroadcaster {
+    fn record(&mut self, step: &StepView<'_>) {
+        if step.fired.is_empty() && !self.step_markers {
+            return;
+        }
+        let spikes: Vec<Spike> = step
+            .fired
+            .iter()
+            .map(|&i| Spike::new(i, step.time))
+            .collect();
+        send(
+            &self.clients,
+            &spikes,
+            self.step_markers.then(|| step.end_time()),
+        );
+    }
+}
+
+/// Message received by a `SpikeClient`.
+#[derive(Debug, Clone, Copy)]
+pub enum Message {
+    /// A neuron fired
+    Spike(Spike),
+    /// All spikes before this time have been sent
+    Step(Milliseconds),
+}
+
+/// Client of a `SpikeServer`.
+#[derive(Debug)]
+pub struct SpikeClient {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    line: String,
+}
+
+impl SpikeClient {
+    /// Connect to the server at `address`.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+            line: String::new(),
+        })
+    }
+
+    /// Make `spike.neuron_id` fire at `spike.time`.
+    pub fn inject(&mut self, spike: &Spike) -> io::Result<()> {
+        writeln!(self.writer, "spike {} {}", spike.neuron_id, spike.time.0)?;
+        self.writer.flush()
+    }
+
+    /// Make `neuron` fire during the server's next step.
+    pub fn inject_now(&mut self, neuron: usize) -> io::Result<()> {
+        writeln!(self.writer, "spike {neuron}")?;
+        self.writer.flush()
+    }
+
+    /// Next message from the server, blocking until one arrives, or `None`
+    /// once the server has closed the connection.
+    pub fn recv(&mut self) -> io::Result<Option<Message>> {
+        self.line.clear();
+        if read_line(&mut self.reader, &mut self.line)? == 0 {
+            return Ok(None);
+        }
+        let mut fields = self.line.split_whitespace();
+        let message = match (fields.next(), fields.next(), fields.next()) {
+            (Some("spike"), Some(neuron), Some(time)) => neuron
+                .parse()
+                .ok()
+                .zip(time.parse().ok())
+                .map(|(n, t)| Message::Spike(Spike::new(n, Milliseconds(t)))),
+            (Some("step"), Some(time), None) => {
+                time.parse().ok().map(|t| Message::Step(Milliseconds(t)))
+            }
+            _ => None,
+        };
+        message
+            .map(Some)
+            .ok_or_else(|| invalid(format!("Unexpected message {:?}", self.line.trim_end())))
+    }
+}
+
+/// Set up a newly accepted client: a thread forwards the spikes it sends
+/// to `input`, and the returned writer streams spikes to it.
+fn connect(stream: TcpStream, input: Sender<Spike>) -> io::Result<BufWriter<TcpStream>> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match read_line(&mut reader, &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(_) => {
+                    // Closing the socket also makes the next broadcast
+                    // drop the client's writer
+                    let _ = reader.get_ref().shutdown(std::net::Shutdown::Both);
+                    break;
+                }
+            }
+            if let Some(spike) = parse_input(&line) {
+                if input.send(spike).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Ok(BufWriter::new(stream))
+}
+
+/// Read one line into `line` like `BufRead::read_line`, but fail with
+/// `InvalidData` instead of buffering a line longer than `MAX_LINE`.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    let limit = MAX_LINE as u64 + 1;
+    let n = reader.by_ref().take(limit).read_line(line)?;
+    if n as u64 == limit && !line.ends_with('\n') {
+        return Err(invalid(format!("Line longer than {MAX_LINE} bytes")));
+    }
+    Ok(n)
+}
+
+/// Spike requested by a client line; a NaN time means the next step.
+fn parse_input(line: &str) -> Option<Spike> {
+    let mut fields = line.split_whitespace();
+    if fields.next()? != "spike" {
+        return None;
+    }
+    let neuron = fields.next()?.parse().ok()?;
+    let time = match fields.next() {
+        Some(time) => time.parse().ok().filter(|t: &f64| t.is_finite())?,
+        None => f64::NAN,
+    };
+    fields
+        .next()
+        .is_none()
+        .then(|| Spike::new(neuron, Milliseconds(time)))
+}
+
+/// Write `spikes` and an optional step marker to every client, dropping
+/// clients whose connection fails.
+fn send(clients: &Clients, spikes: &[Spike], marker: Option<Milliseconds>) {
+    let mut clients = lock(clients);
+    if clients.is_empty() {
+        return;
+    }
+    let mut text = String::new();
+    for spike in spikes {
+        text.push_str(&format!("spike {} {}\n", spike.neuron_id, spike.time.0));
+    }
+    if let Some(end) = marker {
+        text.push_str(&format!("step {}\n", end.0));
+    }
+    clients.retain_mut(|client| {
+        client
+            .write_all(text.as_bytes())
+            .and_then(|_| client.flush())
+            .is_ok()
+    });
+}
@@ -0,0 +1,124 @@
+//! spike_trains.rs
+//!
+//! Synthetic input spike trains.
+//!
+//! Experiments and benchmarks repeatedly need the same handful of
+//! artificial inputs: perfectly regular trains, Poisson trains, noisy
+//! copies of a fixed template, and populations whose trains share a
+//! controlled fraction of their spikes. Every generator here takes an
+//! explicit time window and, where stochastic, an explicitly seeded `Rng`,
+//! and returns spikes sorted by time, then neuron id.
+
+use crate::encoding::sort_spikes;
+use crate::rng::Rng;
+use crate::spike::Spike;
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Spikes of `neuron` at a fixed rate, the first one at `start`, up to
+/// but excluding `start + duration`.
+pub fn regular(
+    neuron: usize,
+    rate_hz: f64,
+    start: Milliseconds,
+    duration: Milliseconds,
+) -> Vec<Spike> {
+    if rate_hz <= 0.0 || !rate_hz.is_finite() {
+        return Vec::new();
+    }
+    let period = 1000.0 / rate_hz;
+    let count = (duration.0 / period).ceil().max(0.0) as usize;
+    (0..count)
+        .map(|k| Milliseconds(k as f64 * period))
+        .filter(|&t| t < duration)
+        .map(|t| Spike::new(neuron, start + t))
+        .collect()
+}
+
+/// Homogeneous Poisson spike train of `neuron` in
+/// `[start, start + duration)`.
+pub fn poisson(
+    neuron: usize,
+    rate_hz: f64,
+    start: Milliseconds,
+    duration: Milliseconds,
+    rng: &mut Rng,
+) -> Vec<Spike> {
+    let mut spikes = Vec::new();
+    let rate_per_ms = rate_hz / 1000.0;
+    if rate_per_ms <= 0.0 || !rate_per_ms.is_finite() {
+        return spikes;
+    }
+    let mut t = rng.exponential(rate_per_ms);
+    while t < duration.0 {
+        spikes.push(Spike::new(neuron, start + Milliseconds(t)));
+        t += rng.exponential(rate_per_ms);
+    }
+    spikes
+}
+
+/// Independent Poisson trains for every neuron in `neurons`.
+pub fn poisson_population(
+    neurons: Range<usize>,
+    rate_hz: f64,
+    start: Milliseconds,
+    duration: Milliseconds,
+    rng: &mut Rng,
+) -> Vec<Spike> {
+    let mut spikes: Vec<Spike> = neurons
+        .flat_map(|i| poisson(i, rate_hz, start, duration, rng))
+        .collect();
+    sort_spikes(&mut spikes);
+    spikes
+}
+
+/// Copy of `template` with every spike shifted by Gaussian noise of
+/// standard deviation `jitter`.
+///
+/// Spikes that would move before time zero are clamped to zero.
+pub fn jittered(template: &[Spike], jitter: Milliseconds, rng: &mut Rng) -> Vec<Spike> {
+    let mut spikes: Vec<Spike> = template
+        .iter()
+        .map(|s| {
+            let t = Milliseconds(rng.gaussian(s.time.0, jitter.0)).max(Milliseconds::ZERO);
+            Spike::new(s.neuron_id, t)
+        })
+        .collect();
+    sort_spikes(&mut spikes);
+    spikes
+}
+
+/// Poisson trains for `neurons` with pairwise spike-count correlation
+/// `correlation` in `[0, 1]`.
+///
+/// Uses the single-interaction process: a shared mother train with rate
+/// `rate_hz / correlation` is generated and each neuron keeps every mother
+/// spike independently with probability `correlation`. Each train is then
+/// Poisson with rate `rate_hz`, and any two trains share a fraction
+/// `correlation` of their spikes. A correlation of zero gives independent
+/// trains.
+pub fn correlated(
+    neurons: Range<usize>,
+    rate_hz: f64,
+    correlation: f64,
+    start: Milliseconds,
+    duration: Milliseconds,
+    rng: &mut Rng,
+) -> Vec<Spike> {
+    let c = correlation.clamp(0.0, 1.0);
+    if c == 0.0 {
+        return poisson_population(neurons, rate_hz, start, duration, rng);
+    }
+    let mother = poisson(0, rate_hz / c, start, duration, rng);
+    let mut spikes = Vec::new();
+    for i in neurons {
+        spikes.extend(
+            mother
+                .iter()
+                .filter(|_| rng.bernoulli(c))
+                .map(|s| Spike::new(i, s.time)),
+        );
+    }
+    sort_spikes(&mut spikes);
+    spikes
+}
@@ -7,17 +7,20 @@
 //! spikes. This contrasts with backpropagation-based learning and is a core
 //! idea in neuromorphic computing.
 
+use crate::units::Milliseconds;
+
 /// Parameters controlling the STDP learning rule.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct STDPParams {
     /// Learning rate for potentiation (LTP)
     pub a_plus: f64,
     /// Learning rate for depression (LTD)
     pub a_minus: f64,
-    /// Time constant for potentiation (ms)
-    pub tau_plus: f64,
-    /// Time constant for depression (ms)
-    pub tau_minus: f64,
+    /// Time constant for potentiation
+    pub tau_plus: Milliseconds,
+    /// Time constant for depression
+    pub tau_minus: Milliseconds,
     /// Minimum synaptic weight
     pub w_min: f64,
     /// Maximum synaptic weight
@@ -39,13 +42,13 @@ pub fn clamp_weight(w: f64, w_min: f64, w_max: f64) -> f64 {
 ///
 /// # Arguments
 /// * `delta_t` - Time difference between post- and pre-synaptic spikes
-///               (t_post - t_pre)
+///   (t_post - t_pre)
 /// * `params` - STDP parameters
 ///
 /// # Returns
 /// * Weight change Δw
-pub fn stdp_update(delta_t: f64, params: &STDPParams) -> f64 {
-    if delta_t > 0.0 {
+pub fn stdp_update(delta_t: Milliseconds, params: &STDPParams) -> f64 {
+    if delta_t > Milliseconds::ZERO {
         // Pre-synaptic spike occurred before post-synaptic spike
         params.a_plus * (-delta_t / params.tau_plus).exp()
     } else {
@@ -63,7 +66,7 @@ pub fn stdp_update(delta_t: f64, params: &STDPParams) -> f64 {
 ///
 /// # Returns
 /// * Updated synaptic weight
-pub fn apply_stdp(w: f64, delta_t: f64, params: &STDPParams) -> f64 {
+pub fn apply_stdp(w: f64, delta_t: Milliseconds, params: &STDPParams) -> f64 {
     let dw = stdp_update(delta_t, params);
     clamp_weight(w + dw, params.w_min, params.w_max)
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! stopping.rs
+//!
+//! Early-stopping and convergence criteria for simulation runs.
+//!
+//! Long experiments often reach the regime of interest well before
+//! `t_max`: activity has saturated, learning has converged, or a target
+//! firing rate has been reached. Stop conditions are evaluated after every
+//! simulation step and end the run as soon as any of them is satisfied.
+
+use crate::spike::Spike;
+use crate::synapse::Synapse;
+use crate::units::Milliseconds;
+
+/// User predicate over `(time, spikes_so_far)`.
+pub type StopPredicate = Box<dyn Fn(Milliseconds, &[Spike]) -> bool + Send + Sync>;
+
+/// A criterion that ends a simulation run early.
+pub enum StopCondition {
+    /// Stop once the total number of recorded spikes exceeds this value.
+    MaxSpikes(usize),
+    /// Stop when the mean absolute weight change over a window falls below
+    /// `epsilon`.
+    WeightConverged {
+        /// Window length
+        window: Milliseconds,
+        /// Mean |Δw| threshold
+        epsilon: f64,
+    },
+    /// Stop when the population mean firing rate over a trailing window
+    /// reaches `rate_hz`.
+    TargetRate {
+        /// Target rate (Hz)
+        rate_hz: f64,
+        /// Window length
+        window: Milliseconds,
+    },
+    /// Stop when a user predicate over `(time, spikes_so_far)` returns `true`.
+    Custom(StopPredicate),
+}
+
+/// Why a simulation run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopReason {
+    /// The run reached `t_max`.
+    Completed,
+    /// A `MaxSpikes` condition was met.
+    MaxSpikes,
+    /// A `WeightConverged` condition was met.
+    WeightConverged,
+    /// A `TargetRate` condition was met.
+    TargetRate,
+    /// A `Custom` predicate returned `true`.
+    Custom,
+    /// The progress callback requested an abort.
+    Aborted,
+    /// The attached `CancellationToken` was triggered.
+    Cancelled,
+}
+
+/// Per-condition bookkeeping for criteria that look at a time window.
+enum WindowState {
+    None,
+    Weights {
+        start: Milliseconds,
+        snapshot: Vec<f64>,
+    },
+}
+
+/// Evaluates a set of stop conditions over the course of a run.
+pub(crate) struct StopMonitor<'a> {
+    conditions: &'a [StopCondition],
+    state: Vec<WindowState>,
+}
+
+impl<'a> StopMonitor<'a> {
+    /// Create a monitor for `conditions`, starting at simulation time `start`.
+    pub(crate) fn new(
+        conditions: &'a [StopCondition],
+        start: Milliseconds,
+        synapses: &[Synapse],
+    ) -> Self {
+        let state = conditions
+            .iter()
+            .map(|c| match c {
+                StopCondition::WeightConverged { .. } => WindowState::Weights {
+                    start,
+                    snapshot: synapses.iter().map(|s| s.weight).collect(),
+                },
+                _ => WindowState::None,
+            })
+            .collect();
+        Self { conditions, state }
+    }
+
+    /// Check all conditions at simulation time `time`.
+    ///
+    /// `spikes` holds the recorded spikes still in memory, while
+    /// `total_spikes` counts every spike recorded so far.
+    ///
+    /// Returns the reason of the first satisfied condition, if any.
+    pub(crate) fn check(
+        &mut self,
+        time: Milliseconds,
+        spikes: &[Spike],
+        total_spikes: usize,
+        synapses: &[Synapse],
+        num_neurons: usize,
+    ) -> Option<StopReason> {
+        for (cond, state) in self.conditions.iter().zip(self.state.iter_mut()) {
+            let reason = match (cond, state) {
+                (StopCondition::MaxSpikes(max), _) => {
+                    (total_spikes > *max).then_some(StopReason::MaxSpikes)
+                }
+                (
+                    StopCondition::WeightConverged { window, epsilon },
+                    WindowState::Weights { start, snapshot },
+                ) if time - *start >= *window => {
+                    let n = snapshot.len().min(synapses.len());
+                    let mean_change = if n == 0 {
+                        0.0
+                    } else {
+                        synapses
+                            .iter()
+                            .zip(snapshot.iter())
+                            .map(|(s, w)| (s.weight - w).abs())
+                            .sum::<f64>()
+                            / n as f64
+                    };
+                    *start = time;
+                    *snapshot = synapses.iter().map(|s| s.weight).collect();
+                    (mean_change < *epsilon).then_some(StopReason::WeightConverged)
+                }
+                (StopCondition::TargetRate { rate_hz, window }, _)
+                    if num_neurons > 0 && *window > Milliseconds::ZERO && time >= *window =>
+                {
+                    let recent = spikes
+                        .iter()
+                        .rev()
+                        .take_while(|s| s.time > time - *window)
+                        .count();
+                    let rate = recent as f64 / num_neurons as f64 / window.as_secs();
+                    (rate >= *rate_hz).then_some(StopReason::TargetRate)
+                }
+                (StopCondition::Custom(predicate), _) => {
+                    predicate(time, spikes).then_some(StopReason::Custom)
+                }
+                _ => None,
+            };
+            if reason.is_some() {
+                return reason;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,483 @@
+//! surrogate.rs
+//!
+//! Surrogate-gradient training of small feedforward spiking networks.
+//!
+//! STDP results only mean something next to a gradient-trained baseline,
+//! and the usual way to get one is backpropagation through time with a
+//! surrogate for the derivative of the spike. `SurrogateNetwork` unrolls
+//! the dynamics of this crate's neurons over the steps of a trial and
+//! trains its dense layers with plain SGD or Adam, without an external ML
+//! framework. The unrolled model matches `Simulation` step for step, so
+//! `to_simulation` turns a trained network into an ordinary simulation:
+//!
+//! ```text
+//! u[t] = α (v[t-1] + W s_in[t-1])      α = 1 - dt / tau_m
+//! s[t] = H(u[t] - threshold)
+//! v[t] = u[t] (1 - s[t])               reset to 0
+//! ```
+//!
+//! Input spikes fire the first layer during the step that contains them,
+//! and every layer sees the previous layer's spikes one step later, as
+//! static synapses with a one-step delay do. The derivative of `H` is
+//! replaced by the fast sigmoid `1 / (1 + slope |u - threshold|)²` and the
+//! reset is treated as a constant. The loss is the softmax cross-entropy
+//! of the output spike counts against a class label, so the predicted
+//! class is the most active output neuron.
+//!
+//! Everything is kept for the backward pass, which costs
+//! `O(steps × neurons)` memory per sample; this is meant for networks of a
+//! few hundred neurons.
+
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Dynamics and surrogate of a `SurrogateNetwork`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SurrogateParams {
+    /// Membrane time constant
+    pub tau_m: Milliseconds,
+    /// Simulation time step
+    pub dt: Milliseconds,
+    /// Length of a trial; input spikes after it are ignored
+    pub duration: Milliseconds,
+    /// Firing threshold
+    pub threshold: f64,
+    /// Steepness of the fast-sigmoid surrogate
+    pub slope: f64,
+}
+
+impl Default for SurrogateParams {
+    fn default() -> Self {
+        Self {
+            tau_m: Milliseconds(10.0),
+            dt: Milliseconds(1.0),
+            duration: Milliseconds(50.0),
+            threshold: 1.0,
+            slope: 5.0,
+        }
+    }
+}
+
+/// Update rule applied to the gradients of a batch.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OptimizerKind {
+    /// Plain gradient descent
+    Sgd,
+    /// Adam with the given decay rates of the moment estimates
+    Adam {
+        /// Decay of the first moment
+        beta1: f64,
+        /// Decay of the second moment
+        beta2: f64,
+        /// Term added to the denominator for stability
+        epsilon: f64,
+    },
+}
+
+/// A gradient-descent optimizer and its state.
+#[derive(Debug, Clone)]
+pub struct Optimizer {
+    /// Update rule
+    pub kind: OptimizerKind,
+    /// Step size
+    pub learning_rate: f64,
+    steps: i32,
+    first: Vec<Vec<Vec<f64>>>,
+    second: Vec<Vec<Vec<f64>>>,
+}
+
+impl Optimizer {
+    /// Plain gradient descent with `learning_rate`.
+    pub fn sgd(learning_rate: f64) -> Self {
+        Self::new(OptimizerKind::Sgd, learning_rate)
+    }
+
+    /// Adam with `learning_rate` and the usual decay rates 0.9 and 0.999.
+    pub fn adam(learning_rate: f64) -> Self {
+        let kind = OptimizerKind::Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        };
+        Self::new(kind, learning_rate)
+    }
+
+    /// Optimizer with the update rule `kind`.
+    pub fn new(kind: OptimizerKind, learning_rate: f64) -> Self {
+        Self {
+            kind,
+            learning_rate,
+            steps: 0,
+            first: Vec::new(),
+            second: Vec::new(),
+        }
+    }
+
+    /// Apply `gradients` to `weights`, both indexed `[layer][post][pre]`.
+    fn apply(&mut self, weights: &mut [Vec<Vec<f64>>], gradients: &[Vec<Vec<f64>>]) {
+        let lr = self.learning_rate;
+        let OptimizerKind::Adam {
+            beta1,
+            beta2,
+            epsilon,
+        } = self.kind
+        else {
+            for (w, g) in weights.iter_mut().flatten().zip(gradients.iter().flatten()) {
+                for (w, g) in w.iter_mut().zip(g) {
+                    *w -= lr * g;
+                }
+            }
+            return;
+        };
+        if self.first.is_empty() {
+            self.first = zeros_like(gradients);
+            self.second = zeros_like(gradients);
+        }
+        self.steps += 1;
+        let correction1 = 1.0 - beta1.powi(self.steps);
+        let correction2 = 1.0 - beta2.powi(self.steps);
+        let rows = weights
+            .iter_mut()
+            .flatten()
+            .zip(gradients.iter().flatten())
+            .zip(self.first.iter_mut().flatten())
+            .zip(self.second.iter_mut().flatten());
+        for (((w, g), m), v) in rows {
+            for (((w, g), m), v) in w.iter_mut().zip(g).zip(m.iter_mut()).zip(v.iter_mut()) {
+                *m = beta1 * *m + (1.0 - beta1) * g;
+                *v = beta2 * *v + (1.0 - beta2) * g * g;
+                let m_hat = *m / correction1;
+                let v_hat = *v / correction2;
+                *w -= lr * m_hat / (v_hat.sqrt() + epsilon);
+            }
+        }
+    }
+}
+
+/// States of one layer over a trial, indexed `[step][neuron]`.
+struct LayerTrace {
+    /// Membrane potential before the spike check
+    u: Vec<Vec<f64>>,
+    /// Spikes, 0 or 1
+    s: Vec<Vec<f64>>,
+}
+
+/// A feedforward spiking network trained by surrogate gradients.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SurrogateNetwork {
+    /// Dynamics and surrogate
+    pub params: SurrogateParams,
+    /// Number of neurons per layer, input layer first
+    sizes: Vec<usize>,
+    /// Weights indexed `[layer][post][pre]`, one matrix per projection
+    weights: Vec<Vec<Vec<f64>>>,
+}
+
+impl SurrogateNetwork {
+    /// Network with `sizes` neurons per layer, input layer first, and
+    /// Gaussian weights of standard deviation `gain / sqrt(inputs)`.
+    ///
+    /// # Panics
+    /// Panics if there are fewer than two layers.
+    pub fn new(sizes: &[usize], params: SurrogateParams, gain: f64, seed: u64) -> Self {
+        assert!(sizes.len() >= 2, "A network needs input and output layers");
+        let mut rng = Rng::new(seed);
+        let weights = sizes
+            .windows(2)
+            .map(|pair| {
+                let std_dev = gain / (pair[0].max(1) as f64).sqrt();
+                (0..pair[1])
+                    .map(|_| (0..pair[0]).map(|_| rng.gaussian(0.0, std_dev)).collect())
+                    .collect()
+            })
+            .collect();
+        Self {
+            params,
+            sizes: sizes.to_vec(),
+            weights,
+        }
+    }
+
+    /// Number of neurons per layer, input layer first.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// Weights of projection `layer` (into layer `layer + 1`), indexed
+    /// `[post][pre]`.
+    pub fn weights(&self, layer: usize) -> &[Vec<f64>] {
+        &self.weights[layer]
+    }
+
+    /// Number of steps of a trial.
+    pub fn steps(&self) -> usize {
+        (self.params.duration / self.params.dt).ceil() as usize
+    }
+
+    /// Spike count of every output neuron for `input`, whose neuron ids
+    /// index the input layer.
+    pub fn output_counts(&self, input: &[Spike]) -> Vec<usize> {
+        let traces = self.forward(input);
+        let output = &traces[traces.len() - 1].s;
+        (0..self.sizes[self.sizes.len() - 1])
+            .map(|j| output.iter().filter(|s| s[j] > 0.0).count())
+            .collect()
+    }
+
+    /// Most active output neuron for `input`; ties go to the lowest index.
+    pub fn predict(&self, input: &[Spike]) -> usize {
+        let counts = self.output_counts(input);
+        let best = counts.iter().copied().max().unwrap_or(0);
+        counts.iter().position(|&c| c == best).unwrap_or(0)
+    }
+
+    /// Fraction of `samples` of `(input, label)` predicted correctly.
+    pub fn accuracy(&self, samples: &[(Vec<Spike>, usize)]) -> f64 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let correct = samples
+            .iter()
+            .filter(|(input, label)| self.predict(input) == *label)
+            .count();
+        correct as f64 / samples.len() as f64
+    }
+
+    /// One optimizer step on the mean gradient of `batch` of
+    /// `(input, label)`; returns the mean loss before the step.
+    ///
+    /// # Panics
+    /// Panics if a label is not the index of an output neuron.
+    pub fn train_batch(&mut self, batch: &[(Vec<Spike>, usize)], optimizer: &mut Optimizer) -> f64 {
+        if batch.is_empty() {
+            return 0.0;
+        }
+        let mut gradients = zeros_like(&self.weights);
+        let mut loss = 0.0;
+        for (input, label) in batch {
+            loss += self.backward(input, *label, &mut gradients);
+        }
+        let scale = 1.0 / batch.len() as f64;
+        for g in gradients.iter_mut().flatten().flatten() {
+            *g *= scale;
+        }
+        optimizer.apply(&mut self.weights, &gradients);
+        loss * scale
+    }
+
+    /// Train for `epochs` passes over `samples` in batches of `batch_size`,
+    /// shuffled with `rng`, and return the mean loss of every epoch.
+    pub fn train(
+        &mut self,
+        samples: &[(Vec<Spike>, usize)],
+        batch_size: usize,
+        epochs: usize,
+        optimizer: &mut Optimizer,
+        rng: &mut Rng,
+    ) -> Vec<f64> {
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        (0..epochs)
+            .map(|_| {
+                rng.shuffle(&mut order);
+                let mut total = 0.0;
+                for chunk in order.chunks(batch_size.max(1)) {
+                    let batch: Vec<(Vec<Spike>, usize)> =
+                        chunk.iter().map(|&i| samples[i].clone()).collect();
+                    total += self.train_batch(&batch, optimizer) * chunk.len() as f64;
+                }
+                total / samples.len().max(1) as f64
+            })
+            .collect()
+    }
+
+    /// The trained network as a `Simulation` with populations `layer0`
+    /// (input) to `layerN`, static synapses with a one-step delay and
+    /// synaptic transmission enabled. Inject the input spikes and run for
+    /// `duration` to reproduce `output_counts`.
+    pub fn to_simulation(&self) -> (Simulation, Vec<Range<usize>>) {
+        let neuron_params = NeuronParams {
+            tau_m: self.params.tau_m,
+            v_rest: 0.0,
+            v_thresh: self.params.threshold,
+            v_reset: 0.0,
+        };
+        let mut builder = NetworkBuilder::new();
+        let layers: Vec<Range<usize>> = self
+            .sizes
+            .iter()
+            .enumerate()
+            .map(|(l, &n)| builder.add_population(&format!("layer{l}"), n, neuron_params.clone()))
+            .collect();
+        for (l, matrix) in self.weights.iter().enumerate() {
+            let (pre, post) = (layers[l].clone(), layers[l + 1].clone());
+            let dt = self.params.dt;
+            builder.add_synapses(post.zip(matrix).flat_map(|(j, row)| {
+                pre.clone().zip(row).map(move |(i, &w)| Synapse {
+                    plasticity: Plasticity::Static,
+                    ..Synapse::with_delay(i, j, w, dt)
+                })
+            }));
+        }
+        let config = SimulationConfig {
+            dt: self.params.dt,
+            t_max: self.params.duration,
+            synaptic_transmission: true,
+            ..SimulationConfig::default()
+        };
+        // Every synapse is static, so the learning rule never applies.
+        let stdp = STDPParams {
+            a_plus: 0.0,
+            a_minus: 0.0,
+            tau_plus: Milliseconds(20.0),
+            tau_minus: Milliseconds(20.0),
+            w_min: f64::NEG_INFINITY,
+            w_max: f64::INFINITY,
+        };
+        let simulation = Simulation::from_network(builder.build(), config, stdp);
+        (simulation, layers)
+    }
+
+    /// Per-step leak factor α.
+    fn alpha(&self) -> f64 {
+        1.0 - self.params.dt / self.params.tau_m
+    }
+
+    /// Input spikes as a `[step][neuron]` raster.
+    fn raster(&self, input: &[Spike]) -> Vec<Vec<f64>> {
+        let steps = self.steps();
+        let mut raster = vec![vec![0.0; self.sizes[0]]; steps];
+        for spike in input {
+            let step = (spike.time / self.params.dt).floor();
+            if step >= 0.0 && (step as usize) < steps && spike.neuron_id < self.sizes[0] {
+                raster[step as usize][spike.neuron_id] = 1.0;
+            }
+        }
+        raster
+    }
+
+    /// Run a trial and keep the state of every layer; the first trace is
+    /// the input raster.
+    fn forward(&self, input: &[Spike]) -> Vec<LayerTrace> {
+        let steps = self.steps();
+        let alpha = self.alpha();
+        let threshold = self.params.threshold;
+        let raster = self.raster(input);
+        let mut traces = vec![LayerTrace {
+            u: Vec::new(),
+            s: raster,
+        }];
+        for (l, matrix) in self.weights.iter().enumerate() {
+            let n = self.sizes[l + 1];
+            let mut v = vec![0.0; n];
+            let mut trace = LayerTrace {
+                u: Vec::with_capacity(steps),
+                s: Vec::with_capacity(steps),
+            };
+            for t in 0..steps {
+                let pre = (t > 0).then(|| &traces[l].s[t - 1]);
+                let mut u = vec![0.0; n];
+                let mut s = vec![0.0; n];
+                for j in 0..n {
+                    let input: f64 = pre.map_or(0.0, |pre| {
+                        matrix[j].iter().zip(pre).map(|(w, x)| w * x).sum()
+                    });
+                    u[j] = alpha * (v[j] + input);
+                    if u[j] >= threshold {
+                        s[j] = 1.0;
+                        v[j] = 0.0;
+                    } else {
+                        v[j] = u[j];
+                    }
+                }
+                trace.u.push(u);
+                trace.s.push(s);
+            }
+            traces.push(trace);
+        }
+        traces
+    }
+
+    /// Add the loss gradient of one sample to `gradients` and return its
+    /// loss.
+    fn backward(&self, input: &[Spike], label: usize, gradients: &mut [Vec<Vec<f64>>]) -> f64 {
+        let traces = self.forward(input);
+        let steps = self.steps();
+        let alpha = self.alpha();
+        let layers = self.weights.len();
+
+        // Softmax cross-entropy of the output spike counts
+        let output = &traces[layers].s;
+        let counts: Vec<f64> = (0..self.sizes[layers])
+            .map(|j| output.iter().map(|s| s[j]).sum())
+            .collect();
+        let max = counts.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let exp: Vec<f64> = counts.iter().map(|c| (c - max).exp()).collect();
+        let sum: f64 = exp.iter().sum();
+        let loss = -(exp[label] / sum).ln();
+        let count_gradient: Vec<f64> = exp
+            .iter()
+            .enumerate()
+            .map(|(j, e)| e / sum - if j == label { 1.0 } else { 0.0 })
+            .collect();
+
+        // Gradient with respect to the spikes of each layer, [step][neuron]
+        let mut spike_gradients: Vec<Vec<Vec<f64>>> = (0..=layers)
+            .map(|l| vec![vec![0.0; self.sizes[l]]; steps])
+            .collect();
+        for s in spike_gradients[layers].iter_mut() {
+            s.copy_from_slice(&count_gradient);
+        }
+        let mut next_u: Vec<Vec<f64>> = (1..=layers).map(|l| vec![0.0; self.sizes[l]]).collect();
+
+        for t in (0..steps).rev() {
+            for l in (1..=layers).rev() {
+                let trace = &traces[l];
+                let matrix = &self.weights[l - 1];
+                let mut grad_u = vec![0.0; self.sizes[l]];
+                for (j, g) in grad_u.iter_mut().enumerate() {
+                    let u = trace.u[t][j];
+                    let surrogate =
+                        1.0 / (1.0 + self.params.slope * (u - self.params.threshold).abs()).powi(2);
+                    let through_v = alpha * next_u[l - 1][j] * (1.0 - trace.s[t][j]);
+                    *g = spike_gradients[l][t][j] * surrogate + through_v;
+                }
+                if t > 0 {
+                    let pre = &traces[l - 1].s[t - 1];
+                    for (j, &g) in grad_u.iter().enumerate() {
+                        if g == 0.0 {
+                            continue;
+                        }
+                        for (i, &x) in pre.iter().enumerate() {
+                            gradients[l - 1][j][i] += alpha * g * x;
+                        }
+                        if l > 1 {
+                            for (i, w) in matrix[j].iter().enumerate() {
+                                spike_gradients[l - 1][t - 1][i] += alpha * g * w;
+                            }
+                        }
+                    }
+                }
+                next_u[l - 1] = grad_u;
+            }
+        }
+        loss
+    }
+}
+
+/// Zero matrices shaped like `weights`.
+fn zeros_like(weights: &[Vec<Vec<f64>>]) -> Vec<Vec<Vec<f64>>> {
+    weights
+        .iter()
+        .map(|m| m.iter().map(|row| vec![0.0; row.len()]).collect())
+        .collect()
+}
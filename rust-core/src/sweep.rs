@@ -0,0 +1,176 @@
+//! sweep.rs
+//!
+//! Batch runner for parameter sweeps.
+//!
+//! Exploring how network behavior depends on neuron, plasticity, and
+//! simulation parameters is a routine part of neuromorphic research. This
+//! module builds parameter sets as a Cartesian grid or as random samples,
+//! runs one simulation per set (optionally in parallel), and reduces each
+//! run to a handful of summary metrics.
+
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::stdp::STDPParams;
+use crate::units::Milliseconds;
+use crate::util::mean_std;
+use crate::weights::WeightInit;
+
+/// One point in parameter space.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepPoint {
+    /// Neuron parameters
+    pub neuron: NeuronParams,
+    /// STDP parameters
+    pub stdp: STDPParams,
+    /// Simulation configuration
+    pub config: SimulationConfig,
+}
+
+/// Summary metrics for a single sweep run.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepSummary {
+    /// Total number of emitted spikes
+    pub total_spikes: usize,
+    /// Mean firing rate per neuron (Hz)
+    pub mean_rate_hz: f64,
+    /// Mean synaptic weight at the end of the run
+    pub mean_final_weight: f64,
+    /// Standard deviation of synaptic weights at the end of the run
+    pub std_final_weight: f64,
+    /// Number of logged weight samples
+    pub weight_updates: usize,
+}
+
+/// A parameter point together with its summary metrics.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepResult {
+    /// The parameters that were simulated
+    pub point: SweepPoint,
+    /// Metrics describing the run
+    pub summary: SweepSummary,
+}
+
+/// Build the Cartesian product of neuron, STDP, and simulation settings.
+pub fn grid(
+    neuron: &[NeuronParams],
+    stdp: &[STDPParams],
+    config: &[SimulationConfig],
+) -> Vec<SweepPoint> {
+    let mut points = Vec::with_capacity(neuron.len() * stdp.len() * config.len());
+    for n in neuron {
+        for s in stdp {
+            for c in config {
+                points.push(SweepPoint {
+                    neuron: n.clone(),
+                    stdp: s.clone(),
+                    config: c.clone(),
+                });
+            }
+        }
+    }
+    points
+}
+
+/// Draw `n` random parameter points using a seeded generator.
+///
+/// `sampler` maps the generator to one parameter point, which keeps the
+/// choice of distributions with the caller.
+pub fn random_samples<S>(n: usize, seed: u64, mut sampler: S) -> Vec<SweepPoint>
+where
+    S: FnMut(&mut Rng) -> SweepPoint,
+{
+    let mut rng = Rng::new(seed);
+    (0..n).map(|_| sampler(&mut rng)).collect()
+}
+
+/// Run one simulation per point and summarize the results.
+///
+/// # Arguments
+/// * `points` - Parameter points to simulate
+/// * `num_neurons` - Network size used for every run
+/// * `initial_weight` - Initial synaptic weight or weight distribution
+/// * `input_current_fn` - External input as a function of neuron and time
+/// * `parallel` - Distribute runs across the available CPU cores (ignored
+///   on wasm32)
+///
+/// # Returns
+/// * One `SweepResult` per point, in the same order as `points`
+pub fn run_sweep<F>(
+    points: &[SweepPoint],
+    num_neurons: usize,
+    initial_weight: impl Into<WeightInit>,
+    input_current_fn: F,
+    parallel: bool,
+) -> Vec<SweepResult>
+where
+    F: Fn(usize, Milliseconds) -> f64 + Sync,
+{
+    let initial_weight = initial_weight.into();
+    let run_one = |point: &SweepPoint| SweepResult {
+        point: point.clone(),
+        summary: simulate(point, num_neurons, initial_weight, &input_current_fn),
+    };
+
+    if !parallel || points.len() < 2 || cfg!(target_arch = "wasm32") {
+        return points.iter().map(run_one).collect();
+    }
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(points.len());
+    let chunk_size = points.len().div_ceil(workers);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = points
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(run_one).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("Sweep worker thread panicked"))
+            .collect()
+    })
+}
+
+/// Run a single point and compute its summary.
+fn simulate<F>(
+    point: &SweepPoint,
+    num_neurons: usize,
+    initial_weight: WeightInit,
+    input_current_fn: &F,
+) -> SweepSummary
+where
+    F: Fn(usize, Milliseconds) -> f64 + Sync,
+{
+    let mut sim = Simulation::new(
+        num_neurons,
+        point.neuron.clone(),
+        point.config.clone(),
+        point.stdp.clone(),
+        initial_weight,
+    );
+    let (spikes, weights) = sim.run(input_current_fn);
+
+    let duration_s = sim.time().as_secs();
+    let mean_rate_hz = if num_neurons > 0 && duration_s > 0.0 {
+        spikes.len() as f64 / num_neurons as f64 / duration_s
+    } else {
+        0.0
+    };
+
+    let final_weights: Vec<f64> = sim.synapses().iter().map(|s| s.weight).collect();
+    let (mean, std) = mean_std(&final_weights);
+
+    SweepSummary {
+        total_spikes: spikes.len(),
+        mean_rate_hz,
+        mean_final_weight: mean,
+        std_final_weight: std,
+        weight_updates: weights.len(),
+    }
+}
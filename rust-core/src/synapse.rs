@@ -8,9 +8,24 @@
 //! rules such as STDP.
 
 use crate::stdp::{apply_stdp, STDPParams};
+use crate::units::Milliseconds;
+
+/// Which plasticity parameters govern a synapse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Plasticity {
+    /// Learn with the simulation-wide STDP parameters
+    #[default]
+    Global,
+    /// Fixed weight, no learning
+    Static,
+    /// Learn with the network's plasticity parameter set at this index
+    Set(usize),
+}
 
 /// A synapse connecting two neurons.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Synapse {
     /// Index of pre-synaptic neuron
     pub pre_neuron: usize,
@@ -18,10 +33,14 @@ pub struct Synapse {
     pub post_neuron: usize,
     /// Synaptic weight
     pub weight: f64,
-    /// Last pre-synaptic spike time (ms)
-    pub last_pre_spike: Option<f64>,
-    /// Last post-synaptic spike time (ms)
-    pub last_post_spike: Option<f64>,
+    /// Axonal delay between a pre-synaptic spike and its arrival
+    pub delay: Milliseconds,
+    /// Arrival time of the last pre-synaptic spike
+    pub last_pre_spike: Option<Milliseconds>,
+    /// Last post-synaptic spike time
+    pub last_post_spike: Option<Milliseconds>,
+    /// Plasticity rule applied to this synapse
+    pub plasticity: Plasticity,
 }
 
 impl Synapse {
@@ -31,13 +50,27 @@ impl Synapse {
             pre_neuron,
             post_neuron,
             weight,
+            delay: Milliseconds::ZERO,
             last_pre_spike: None,
             last_post_spike: None,
+            plasticity: Plasticity::Global,
+        }
+    }
+
+    /// Create a new synapse with an initial weight and axonal delay.
+    pub fn with_delay(pre_neuron: usize, post_neuron: usize, weight: f64, delay: Milliseconds) -> Self {
+        Self {
+            delay,
+            ..Self::new(pre_neuron, post_neuron, weight)
         }
     }
 
     /// Register a pre-synaptic spike and apply STDP if possible.
-    pub fn on_pre_spike(&mut self, t_pre: f64, params: &STDPParams) {
+    ///
+    /// The spike takes effect at the synapse after the axonal delay, so
+    /// timing is measured from `t_pre + delay`.
+    pub fn on_pre_spike(&mut self, t_pre: Milliseconds, params: &STDPParams) {
+        let t_pre = t_pre + self.delay;
         if let Some(t_post) = self.last_post_spike {
             self.weight = apply_stdp(self.weight, t_post - t_pre, params);
         }
@@ -45,7 +78,7 @@ impl Synapse {
     }
 
     /// Register a post-synaptic spike and apply STDP if possible.
-    pub fn on_post_spike(&mut self, t_post: f64, params: &STDPParams) {
+    pub fn on_post_spike(&mut self, t_post: Milliseconds, params: &STDPParams) {
         if let Some(t_pre) = self.last_pre_spike {
             self.weight = apply_stdp(self.weight, t_post - t_pre, params);
         }
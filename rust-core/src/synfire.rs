@@ -0,0 +1,320 @@
+//! synfire.rs
+//!
+//! Synfire chain construction and packet propagation analysis.
+//!
+//! A synfire chain (Abeles, 1991) is a sequence of pools of neurons, each
+//! projecting feedforward onto the next with convergent and divergent
+//! wiring: every neuron receives input from many neurons of the previous
+//! pool and sends output to many of the next. A pulse packet, a volley of
+//! `a` spikes with temporal spread `σ`, either sharpens as it travels down
+//! the chain, settling on a stable packet that propagates indefinitely, or
+//! dies out. Whether it survives depends on `(a, σ)` and on the wiring,
+//! and the map of that dependence (Diesmann, Gewaltig and Aertsen, 1999)
+//! is the classic benchmark for the reliability of spike timing.
+//!
+//! `build_synfire` wires a chain, `SynfireChain::run_packet` sends one
+//! packet down it and measures `(a, σ)` in every pool, and
+//! `stability_map` repeats that over a grid of initial packets. Neurons
+//! are deterministic and receive no background input, so jitter comes only
+//! from the initial packet and from the wiring.
+
+use crate::connectivity::fixed_in_degree;
+use crate::encoding::sort_spikes;
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::rng::Rng;
+use crate::simulation::{Simulation, SimulationConfig};
+use crate::spike::Spike;
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::units::Milliseconds;
+use std::ops::Range;
+
+/// Layout and wiring of a synfire chain.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SynfireParams {
+    /// Number of pools
+    pub num_pools: usize,
+    /// Neurons per pool
+    pub pool_size: usize,
+    /// Inputs each neuron receives from the previous pool; `None` connects
+    /// pools all-to-all
+    pub in_degree: Option<usize>,
+    /// Weight of every synapse
+    pub weight: f64,
+    /// Delay of every synapse
+    pub delay: Milliseconds,
+    /// Seed for wiring
+    pub seed: u64,
+}
+
+impl Default for SynfireParams {
+    fn default() -> Self {
+        Self {
+            num_pools: 10,
+            pool_size: 100,
+            in_degree: None,
+            weight: 0.02,
+            delay: Milliseconds(5.0),
+            seed: 0,
+        }
+    }
+}
+
+/// A synfire chain simulation together with its layout.
+pub struct SynfireChain {
+    /// The constructed simulation
+    pub simulation: Simulation,
+    /// Neuron index range of each pool, in chain order
+    pub pools: Vec<Range<usize>>,
+    /// Delay between consecutive pools
+    pub delay: Milliseconds,
+}
+
+/// Build a synfire chain.
+///
+/// Pools are registered as populations `pool0`, `pool1`, ... and connected
+/// by static synapses. Synaptic transmission is enabled on `config`.
+pub fn build_synfire(
+    params: &SynfireParams,
+    neuron_params: NeuronParams,
+    mut config: SimulationConfig,
+    stdp_params: STDPParams,
+) -> SynfireChain {
+    let mut rng = Rng::new(params.seed);
+    let mut builder = NetworkBuilder::new();
+    let pools: Vec<Range<usize>> = (0..params.num_pools)
+        .map(|k| {
+            builder.add_population(&format!("pool{k}"), params.pool_size, neuron_params.clone())
+        })
+        .collect();
+
+    for pair in pools.windows(2) {
+        let (pre, post) = (pair[0].clone(), pair[1].clone());
+        let connections: Vec<(usize, usize)> = match params.in_degree {
+            Some(k) => fixed_in_degree(pre, post, k, &mut rng, |_, _, _| 0.0)
+                .into_iter()
+                .map(|(i, j, _)| (i, j))
+                .collect(),
+            None => pre
+                .flat_map(|i| post.clone().map(move |j| (i, j)))
+                .collect(),
+        };
+        builder.add_synapses(connections.into_iter().map(|(i, j)| Synapse {
+            plasticity: Plasticity::Static,
+            ..Synapse::with_delay(i, j, params.weight, params.delay)
+        }));
+    }
+
+    config.synaptic_transmission = true;
+    SynfireChain {
+        simulation: Simulation::from_network(builder.build(), config, stdp_params),
+        pools,
+        delay: params.delay,
+    }
+}
+
+/// A pulse packet: how many spikes a pool fired and how spread out in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Packet {
+    /// Number of spikes `a`
+    pub activity: usize,
+    /// Mean spike time, or zero without spikes
+    pub time: Milliseconds,
+    /// Standard deviation `σ` of the spike times, or zero without spikes
+    pub jitter: Milliseconds,
+}
+
+impl Packet {
+    /// Packet of `times`.
+    pub fn from_times(times: &[Milliseconds]) -> Self {
+        if times.is_empty() {
+            return Self {
+                activity: 0,
+                time: Milliseconds::ZERO,
+                jitter: Milliseconds::ZERO,
+            };
+        }
+        let n = times.len() as f64;
+        let mean = times.iter().map(|t| t.0).sum::<f64>() / n;
+        let variance = times.iter().map(|t| (t.0 - mean).powi(2)).sum::<f64>() / n;
+        Self {
+            activity: times.len(),
+            time: Milliseconds(mean),
+            jitter: Milliseconds(variance.sqrt()),
+        }
+    }
+}
+
+/// Packets of every pool in one run.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Propagation {
+    /// Packet of each pool, in chain order
+    pub packets: Vec<Packet>,
+}
+
+impl Propagation {
+    /// Measure the packet of each of `pools` in `spikes`.
+    pub fn measure(spikes: &[Spike], pools: &[Range<usize>]) -> Self {
+        let mut times = vec![Vec::new(); pools.len()];
+        for spike in spikes {
+            if let Some(k) = pools.iter().position(|p| p.contains(&spike.neuron_id)) {
+                times[k].push(spike.time);
+            }
+        }
+        Self {
+            packets: times.iter().map(|t| Packet::from_times(t)).collect(),
+        }
+    }
+
+    /// Number of pools, counted from the first, whose packets have at least
+    /// `min_activity` spikes.
+    pub fn depth(&self, min_activity: usize) -> usize {
+        self.packets
+            .iter()
+            .take_while(|p| p.activity >= min_activity)
+            .count()
+    }
+
+    /// Whether a packet of at least `min_activity` spikes reached the last
+    /// pool.
+    pub fn succeeded(&self, min_activity: usize) -> bool {
+        self.depth(min_activity) == self.packets.len()
+    }
+
+    /// Mean time between the packets of consecutive pools that all reached
+    /// `min_activity`, or `None` if fewer than two did.
+    pub fn latency(&self, min_activity: usize) -> Option<Milliseconds> {
+        let depth = self.depth(min_activity);
+        if depth < 2 {
+            return None;
+        }
+        let span = self.packets[depth - 1].time - self.packets[0].time;
+        Some(span / (depth - 1) as f64)
+    }
+}
+
+impl SynfireChain {
+    /// Spikes of a pulse packet in the first pool: `activity` randomly
+    /// chosen neurons, or all of them, fire once at Gaussian times around
+    /// `time` with standard deviation `jitter`.
+    pub fn packet(
+        &self,
+        activity: usize,
+        jitter: Milliseconds,
+        time: Milliseconds,
+        rng: &mut Rng,
+    ) -> Vec<Spike> {
+        let Some(first) = self.pools.first() else {
+            return Vec::new();
+        };
+        let mut neurons: Vec<usize> = first.clone().collect();
+        if activity < neurons.len() {
+            rng.shuffle(&mut neurons);
+            neurons.truncate(activity);
+            neurons.sort_unstable();
+        }
+        let mut spikes: Vec<Spike> = neurons
+            .into_iter()
+            .map(|i| {
+                let t = rng.gaussian(time.0, jitter.0).max(0.0);
+                Spike::new(i, Milliseconds(t))
+            })
+            .collect();
+        sort_spikes(&mut spikes);
+        spikes
+    }
+
+    /// Long enough for a packet sent at `start` to cross the whole chain,
+    /// allowing 10 ms per pool on top of the delay.
+    pub fn crossing_time(&self, start: Milliseconds) -> Milliseconds {
+        start + (self.delay + Milliseconds(10.0)) * self.pools.len() as f64
+    }
+
+    /// Send a packet of `activity` spikes with spread `jitter` into the
+    /// first pool from rest and measure it in every pool.
+    pub fn run_packet(
+        &mut self,
+        activity: usize,
+        jitter: Milliseconds,
+        rng: &mut Rng,
+    ) -> Propagation {
+        let start = Milliseconds(10.0).max(jitter * 4.0);
+        let input = self.packet(activity, jitter, start, rng);
+        self.simulation.reset();
+        self.simulation.inject_spikes(&input);
+        let end = self.crossing_time(start);
+        let mut spikes = Vec::new();
+        while self.simulation.time() < end {
+            spikes.extend(self.simulation.step(|_, _| 0.0));
+        }
+        Propagation::measure(&spikes, &self.pools)
+    }
+}
+
+/// Outcome of the packets started from one point of a stability map.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StabilityPoint {
+    /// Initial activity `a`
+    pub activity: usize,
+    /// Initial spread `σ`
+    pub jitter: Milliseconds,
+    /// Fraction of trials whose packet reached the last pool
+    pub success_rate: f64,
+    /// Mean activity in the last pool
+    pub final_activity: f64,
+    /// Mean spread in the last pool over successful trials, or zero
+    pub final_jitter: Milliseconds,
+}
+
+/// Propagate `trials` packets from every combination of `activities` and
+/// `jitters` through `chain`.
+///
+/// A trial succeeds when the last pool fires at least half as many spikes
+/// as it has neurons. Successful points whose final packet is the same
+/// across initial conditions reveal the stable attractor of the chain.
+pub fn stability_map(
+    chain: &mut SynfireChain,
+    activities: &[usize],
+    jitters: &[Milliseconds],
+    trials: usize,
+    seed: u64,
+) -> Vec<StabilityPoint> {
+    let mut rng = Rng::new(seed);
+    let min_activity = chain.pools.last().map_or(0, |p| p.len().div_ceil(2));
+    let trials = trials.max(1);
+    let mut points = Vec::with_capacity(activities.len() * jitters.len());
+    for &activity in activities {
+        for &jitter in jitters {
+            let mut successes = 0;
+            let mut final_activity = 0.0;
+            let mut final_jitter = 0.0;
+            for _ in 0..trials {
+                let propagation = chain.run_packet(activity, jitter, &mut rng);
+                let last = propagation.packets.last().copied();
+                let last = last.unwrap_or(Packet::from_times(&[]));
+                final_activity += last.activity as f64;
+                if propagation.succeeded(min_activity) {
+                    successes += 1;
+                    final_jitter += last.jitter.0;
+                }
+            }
+            points.push(StabilityPoint {
+                activity,
+                jitter,
+                success_rate: successes as f64 / trials as f64,
+                final_activity: final_activity / trials as f64,
+                final_jitter: Milliseconds(if successes > 0 {
+                    final_jitter / successes as f64
+                } else {
+                    0.0
+                }),
+            });
+        }
+    }
+    points
+}
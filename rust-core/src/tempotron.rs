@@ -0,0 +1,172 @@
+//! tempotron.rs
+//!
+//! Tempotron learning of binary spike-pattern classification.
+//!
+//! The tempotron (Gütig and Sompolinsky, 2006) trains a neuron to fire at
+//! least once for patterns of one class and stay silent for the other,
+//! using only the timing of input spikes. After a misclassified trial,
+//! every input weight moves along the gradient of the membrane potential
+//! at the time `t_max` of its peak:
+//!
+//! ```text
+//! Δw_i = ±λ Σ_{t_i ≤ t_max} K(t_max - t_i)
+//! ```
+//!
+//! with `+` for a missed target and `-` for a false alarm. `K` is the
+//! postsynaptic potential caused by one input spike, which for the neurons
+//! of this crate is an exponential decay with the membrane time constant,
+//! taken here per step as `(1 - dt / tau_m)^k` so the update is the exact
+//! gradient of the simulated potential. For a false alarm the peak is the
+//! first spike.
+//!
+//! Each output neuron is an independent binary classifier trained on the
+//! static synapses that target it; inputs are injected spike patterns.
+
+use crate::learning::{LearningRule, TrialInputs};
+use crate::monitor::StepView;
+use crate::simulation::Simulation;
+
+/// Parameters of the tempotron rule.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempotronParams {
+    /// Learning rate λ
+    pub learning_rate: f64,
+    /// Lower weight bound
+    pub w_min: f64,
+    /// Upper weight bound
+    pub w_max: f64,
+}
+
+impl Default for TempotronParams {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.01,
+            w_min: f64::NEG_INFINITY,
+            w_max: f64::INFINITY,
+        }
+    }
+}
+
+/// Trial state of one output neuron.
+#[derive(Debug, Clone, Default)]
+struct OutputTrace {
+    /// Step of the first spike
+    first_spike: Option<usize>,
+    /// Step and value of the highest membrane potential without a spike
+    peak: Option<(usize, f64)>,
+}
+
+/// The tempotron rule for a set of output neurons.
+///
+/// The target of a trial is whether each output should fire, in the order
+/// the outputs were given.
+#[derive(Debug, Clone)]
+pub struct Tempotron {
+    outputs: Vec<usize>,
+    params: TempotronParams,
+    inputs: TrialInputs,
+    traces: Vec<OutputTrace>,
+}
+
+impl Tempotron {
+    /// Tempotron training the synapses onto `outputs`.
+    pub fn new(outputs: Vec<usize>, params: TempotronParams) -> Self {
+        let traces = vec![OutputTrace::default(); outputs.len()];
+        Self {
+            outputs,
+            params,
+            inputs: TrialInputs::default(),
+            traces,
+        }
+    }
+
+    /// The output neurons.
+    pub fn outputs(&self) -> &[usize] {
+        &self.outputs
+    }
+
+    /// Whether each output fired during the last trial.
+    pub fn decisions(&self) -> Vec<bool> {
+        self.traces
+            .iter()
+            .map(|t| t.first_spike.is_some())
+            .collect()
+    }
+
+    /// Step of the last trial at which output `k` peaked: its first spike,
+    /// or else its highest membrane potential.
+    fn t_max(&self, k: usize) -> Option<usize> {
+        let trace = &self.traces[k];
+        trace.first_spike.or(trace.peak.map(|(step, _)| step))
+    }
+}
+
+impl LearningRule for Tempotron {
+    type Target = Vec<bool>;
+
+    fn begin_trial(&mut self, sim: &Simulation) {
+        self.inputs = TrialInputs::begin(sim, &self.outputs);
+        self.traces = vec![OutputTrace::default(); self.outputs.len()];
+    }
+
+    fn observe(&mut self, step: &StepView<'_>) {
+        self.inputs.observe(step);
+        let k_step = self.inputs.step_index(step);
+        for (trace, &neuron) in self.traces.iter_mut().zip(&self.outputs) {
+            if trace.first_spike.is_some() {
+                continue;
+            }
+            if step.fired.binary_search(&neuron).is_ok() {
+                trace.first_spike = Some(k_step);
+            } else {
+                let v = step.neurons[neuron].v_mem;
+                if trace.peak.is_none_or(|(_, peak)| v > peak) {
+                    trace.peak = Some((k_step, v));
+                }
+            }
+        }
+    }
+
+    /// Number of outputs that fired when they should not or stayed silent
+    /// when they should have fired.
+    fn error(&self, target: &Vec<bool>) -> f64 {
+        self.decisions()
+            .iter()
+            .zip(target)
+            .filter(|(fired, wanted)| fired != wanted)
+            .count() as f64
+    }
+
+    fn update(&mut self, sim: &mut Simulation, target: &Vec<bool>) {
+        let decisions = self.decisions();
+        for syn in &self.inputs.synapses {
+            let k = syn.output;
+            let wanted = target.get(k).copied().unwrap_or(false);
+            if decisions[k] == wanted {
+                continue;
+            }
+            let Some(t_max) = self.t_max(k) else {
+                continue;
+            };
+            let tau_m = sim.neurons()[self.outputs[k]].params.tau_m;
+            let decay = 1.0 - self.inputs.dt / tau_m;
+            let gradient: f64 = syn
+                .arrivals
+                .iter()
+                .filter(|&&a| a <= t_max)
+                .map(|&a| decay.powi((t_max - a + 1) as i32))
+                .sum();
+            if gradient == 0.0 {
+                continue;
+            }
+            let sign = if wanted { 1.0 } else { -1.0 };
+            let weight =
+                sim.synapses()[syn.index].weight + sign * self.params.learning_rate * gradient;
+            sim.set_weight(
+                syn.index,
+                weight.clamp(self.params.w_min, self.params.w_max),
+            );
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! topology.rs
+//!
+//! Topology statistics and network validation.
+//!
+//! Generated networks are easy to get subtly wrong: an off-by-one range
+//! leaves neurons disconnected, a weight hook returns NaN, or inhibition
+//! overwhelms excitation. `NetworkStats` summarizes the connectivity and
+//! weights of a network, and `validate` flags synapses that would corrupt
+//! a run so problems are caught before simulating.
+
+use crate::stdp::STDPParams;
+use crate::synapse::{Plasticity, Synapse};
+use crate::util::mean_std;
+
+/// Summary statistics of a network's connectivity.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkStats {
+    /// Number of neurons
+    pub num_neurons: usize,
+    /// Number of synapses
+    pub num_synapses: usize,
+    /// Incoming synapse count per neuron
+    pub in_degree: Vec<usize>,
+    /// Outgoing synapse count per neuron
+    pub out_degree: Vec<usize>,
+    /// Number of neurons with each in-degree (index = degree)
+    pub in_degree_histogram: Vec<usize>,
+    /// Number of neurons with each out-degree (index = degree)
+    pub out_degree_histogram: Vec<usize>,
+    /// Mean synaptic weight
+    pub mean_weight: f64,
+    /// Standard deviation of synaptic weights
+    pub std_weight: f64,
+    /// Smallest synaptic weight (`0` without synapses)
+    pub min_weight: f64,
+    /// Largest synaptic weight (`0` without synapses)
+    pub max_weight: f64,
+    /// Number of synapses whose pre- and post-synaptic neuron coincide
+    pub self_connections: usize,
+    /// Neurons with neither incoming nor outgoing synapses
+    pub disconnected: Vec<usize>,
+    /// Number of synapses with positive weight
+    pub excitatory_synapses: usize,
+    /// Number of synapses with negative weight
+    pub inhibitory_synapses: usize,
+    /// Sum of positive weights
+    pub excitatory_weight: f64,
+    /// Sum of the magnitudes of negative weights
+    pub inhibitory_weight: f64,
+}
+
+impl NetworkStats {
+    /// Compute statistics for `synapses` over `num_neurons` neurons.
+    ///
+    /// Synapses referring to neurons outside the network are counted in
+    /// `num_synapses` and the weight statistics but not in the degrees.
+    pub fn compute(num_neurons: usize, synapses: &[Synapse]) -> Self {
+        let mut in_degree = vec![0; num_neurons];
+        let mut out_degree = vec![0; num_neurons];
+        let mut self_connections = 0;
+        let mut excitatory_synapses = 0;
+        let mut inhibitory_synapses = 0;
+        let mut excitatory_weight = 0.0;
+        let mut inhibitory_weight = 0.0;
+
+        for s in synapses {
+            if let Some(d) = out_degree.get_mut(s.pre_neuron) {
+                *d += 1;
+            }
+            if let Some(d) = in_degree.get_mut(s.post_neuron) {
+                *d += 1;
+            }
+            if s.pre_neuron == s.post_neuron {
+                self_connections += 1;
+            }
+            if s.weight > 0.0 {
+                excitatory_synapses += 1;
+                excitatory_weight += s.weight;
+            } else if s.weight < 0.0 {
+                inhibitory_synapses += 1;
+                inhibitory_weight -= s.weight;
+            }
+        }
+
+        let weights: Vec<f64> = synapses.iter().map(|s| s.weight).collect();
+        let (mean_weight, std_weight) = mean_std(&weights);
+        let (min_weight, max_weight) = if weights.is_empty() {
+            (0.0, 0.0)
+        } else {
+            weights
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &w| {
+                    (lo.min(w), hi.max(w))
+                })
+        };
+
+        let disconnected = (0..num_neurons)
+            .filter(|&i| in_degree[i] == 0 && out_degree[i] == 0)
+            .collect();
+
+        Self {
+            num_neurons,
+            num_synapses: synapses.len(),
+            in_degree_histogram: histogram(&in_degree),
+            out_degree_histogram: histogram(&out_degree),
+            in_degree,
+            out_degree,
+            mean_weight,
+            std_weight,
+            min_weight,
+            max_weight,
+            self_connections,
+            disconnected,
+            excitatory_synapses,
+            inhibitory_synapses,
+            excitatory_weight,
+            inhibitory_weight,
+        }
+    }
+
+    /// Ratio of total inhibitory to total excitatory weight.
+    ///
+    /// Returns `None` if there is no excitatory weight.
+    pub fn inhibition_ratio(&self) -> Option<f64> {
+        (self.excitatory_weight > 0.0).then(|| self.inhibitory_weight / self.excitatory_weight)
+    }
+}
+
+/// A problem found by `validate`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValidationIssue {
+    /// Synapse refers to a neuron outside the network.
+    DanglingSynapse {
+        /// Synapse index
+        synapse: usize,
+    },
+    /// Synapse weight is NaN or infinite.
+    NonFiniteWeight {
+        /// Synapse index
+        synapse: usize,
+        /// Offending weight
+        weight: f64,
+    },
+    /// Plastic synapse weight lies outside its STDP bounds.
+    WeightOutOfBounds {
+        /// Synapse index
+        synapse: usize,
+        /// Offending weight
+        weight: f64,
+        /// Lower bound
+        w_min: f64,
+        /// Upper bound
+        w_max: f64,
+    },
+    /// Synapse delay is negative, NaN, or infinite.
+    InvalidDelay {
+        /// Synapse index
+        synapse: usize,
+    },
+    /// Synapse refers to a plasticity set that does not exist.
+    UnknownPlasticitySet {
+        /// Synapse index
+        synapse: usize,
+        /// Referenced set
+        set: usize,
+    },
+}
+
+/// Check synapses for values that would corrupt a simulation.
+///
+/// Returns every issue found, in synapse order; an empty vector means the
+/// network is valid.
+pub fn validate(
+    num_neurons: usize,
+    synapses: &[Synapse],
+    stdp_params: &STDPParams,
+    plasticity_sets: &[STDPParams],
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for (k, s) in synapses.iter().enumerate() {
+        if s.pre_neuron >= num_neurons || s.post_neuron >= num_neurons {
+            issues.push(ValidationIssue::DanglingSynapse { synapse: k });
+        }
+        if !s.delay.0.is_finite() || s.delay.0 < 0.0 {
+            issues.push(ValidationIssue::InvalidDelay { synapse: k });
+        }
+        if !s.weight.is_finite() {
+            issues.push(ValidationIssue::NonFiniteWeight {
+                synapse: k,
+                weight: s.weight,
+            });
+            continue;
+        }
+        let params = match s.plasticity {
+            Plasticity::Global => stdp_params,
+            Plasticity::Static => continue,
+            Plasticity::Set(set) => match plasticity_sets.get(set) {
+                Some(params) => params,
+                None => {
+                    issues.push(ValidationIssue::UnknownPlasticitySet { synapse: k, set });
+                    continue;
+                }
+            },
+        };
+        if s.weight < params.w_min || s.weight > params.w_max {
+            issues.push(ValidationIssue::WeightOutOfBounds {
+                synapse: k,
+                weight: s.weight,
+                w_min: params.w_min,
+                w_max: params.w_max,
+            });
+        }
+    }
+    issues
+}
+
+/// Count how many entries take each value.
+fn histogram(values: &[usize]) -> Vec<usize> {
+    let mut counts = vec![0; values.iter().max().map_or(0, |&m| m + 1)];
+    for &v in values {
+        counts[v] += 1;
+    }
+    counts
+}
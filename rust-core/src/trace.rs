@@ -0,0 +1,545 @@
+//! trace.rs
+//!
+//! Structured logging and run telemetry.
+//!
+//! Printing from library code leaves users no say over what is shown or
+//! where it goes, and no way to collect timings programmatically, so the
+//! library does not print. With the `tracing` feature enabled, the
+//! simulation loop and `run_example` report through this module instead:
+//! events carry named fields, and spans time a phase of work and report its
+//! duration when they close. Without the feature the instrumentation is
+//! compiled out. Nothing is recorded until a `Subscriber` is installed,
+//! either for the whole process with `set_global_subscriber` or for one
+//! thread with `with_subscriber`.
+//!
+//! The model follows the `tracing` crate (levels, targets, fields, nested
+//! spans) but is built on the standard library alone, like the HDF5 and
+//! Parquet writers. Records only reach the subscribers of this module, not
+//! those of `tracing-subscriber`; forward them with a `Subscriber` that
+//! calls into another logger where one is in use. Instrumentation in this
+//! crate:
+//!
+//! ```text
+//! INFO   run               span around a call to Simulation::run and friends
+//! INFO   run finished      event with the stop reason, steps and spikes
+//! INFO   write_outputs     span around the file output of run_example
+//! INFO   simulation complete  event of run_example with the spike and
+//!                          weight update counts
+//! DEBUG  spike             event of run_example for each of its first spikes
+//! ERROR  run_example failed  event if run_example cannot write its results
+//! TRACE  deliver           span per step: delivery of delayed synaptic input
+//! TRACE  neurons           span per step: neuron updates and injected spikes
+//! TRACE  transmit          span per step: propagation of this step's spikes
+//! TRACE  plasticity        span per step: STDP updates
+//! TRACE  monitors          span per step: attached monitors
+//! ```
+//!
+//! Three subscribers are provided: `FmtSubscriber` writes human-readable
+//! lines, `JsonSubscriber` writes one JSON object per record and
+//! `TraceCollector` keeps records in memory, e.g. to total the time spent
+//! in each phase. Span timings use `Instant`, which is unavailable on
+//! `wasm32-unknown-unknown`.
+
+use crate::json::{write_number, write_string};
+use crate::units::Milliseconds;
+use crate::util::lock;
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Verbosity of a record, from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Upper-case name of the level.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// Level from its name, in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .into_iter()
+        .find(|level| level.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Value of a record field.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Uint(u) => write!(f, "{u}"),
+            Value::Float(x) => write!(f, "{x}"),
+            Value::Text(s) => write!(f, "{s:?}"),
+        }
+    }
+}
+
+macro_rules! value_from {
+    ($($ty:ty => $variant:ident as $as:ty),* $(,)?) => {
+        $(impl From<$ty> for Value {
+            fn from(value: $ty) -> Self {
+                Value::$variant(value as $as)
+            }
+        })*
+    };
+}
+
+value_from! {
+    i32 => Int as i64,
+    i64 => Int as i64,
+    u32 => Uint as u64,
+    u64 => Uint as u64,
+    usize => Uint as u64,
+    f64 => Float as f64,
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<Milliseconds> for Value {
+    fn from(value: Milliseconds) -> Self {
+        Value::Float(value.0)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+/// An event, or a span that has closed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub level: Level,
+    /// Module that emitted the record
+    pub target: &'static str,
+    /// Event message or span name
+    pub name: &'static str,
+    pub fields: Vec<(&'static str, Value)>,
+    /// Names of the enclosing spans on the emitting thread, outermost first;
+    /// for a span this ends with the span itself
+    pub scope: Vec<&'static str>,
+    /// Time between opening and closing, for spans
+    pub elapsed: Option<Duration>,
+}
+
+impl Record {
+    /// Whether this record is a closed span rather than an event.
+    pub fn is_span(&self) -> bool {
+        self.elapsed.is_some()
+    }
+
+    /// Value of the field `name`.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+    }
+}
+
+/// Receiver of the records emitted by instrumented code.
+pub trait Subscriber: Send + Sync {
+    /// Most verbose level this subscriber wants.
+    fn max_level(&self) -> Level;
+
+    /// Whether records at `level` from `target` are wanted. Disabled spans
+    /// are not timed at all.
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        let _ = target;
+        level <= self.max_level()
+    }
+
+    /// Handle an event, or a span that has just closed.
+    fn record(&self, record: &Record);
+}
+
+static GLOBAL: OnceLock<Box<dyn Subscriber>> = OnceLock::new();
+/// Most verbose level any installed subscriber wants, 0 if none; checked
+/// before anything else so disabled instrumentation costs one load
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+thread_local! {
+    static SCOPED: RefCell<Option<Arc<dyn Subscriber>>> = const { RefCell::new(None) };
+    static SCOPE: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Install `subscriber` for all threads for the rest of the process.
+///
+/// Returns `false`, dropping `subscriber`, if one was already installed.
+pub fn set_global_subscriber<S: Subscriber + 'static>(subscriber: S) -> bool {
+    let level = subscriber.max_level();
+    let installed = GLOBAL.set(Box::new(subscriber)).is_ok();
+    if installed {
+        MAX_LEVEL.fetch_max(level as u8, Ordering::Relaxed);
+    }
+    installed
+}
+
+/// Call `f` with `subscriber` receiving the records of the current thread,
+/// in place of the global subscriber.
+pub fn with_subscriber<S, R, F>(subscriber: S, f: F) -> R
+where
+    S: Subscriber + 'static,
+    F: FnOnce() -> R,
+{
+    /// Restores the previous scoped subscriber, also when `f` panics.
+    struct Restore(Option<Arc<dyn Subscriber>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+
+    MAX_LEVEL.fetch_max(subscriber.max_level() as u8, Ordering::Relaxed);
+    let subscriber: Arc<dyn Subscriber> = Arc::new(subscriber);
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(Some(subscriber))));
+    f()
+}
+
+/// Call `f` with the subscriber of the current thread, if any.
+fn dispatch<R>(f: impl FnOnce(&dyn Subscriber) -> R) -> Option<R> {
+    if let Some(scoped) = SCOPED.with(|scoped| scoped.borrow().clone()) {
+        return Some(f(scoped.as_ref()));
+    }
+    GLOBAL.get().map(|global| f(global.as_ref()))
+}
+
+/// Whether a record at `level` from `target` would be recorded.
+pub fn enabled(level: Level, target: &str) -> bool {
+    (level as u8) <= MAX_LEVEL.load(Ordering::Relaxed)
+        && dispatch(|s| s.enabled(level, target)).unwrap_or(false)
+}
+
+/// Emit an event.
+pub fn event(
+    level: Level,
+    target: &'static str,
+    message: &'static str,
+    fields: Vec<(&'static str, Value)>,
+) {
+    if !enabled(level, target) {
+        return;
+    }
+    let record = Record {
+        level,
+        target,
+        name: message,
+        fields,
+        scope: SCOPE.with(|scope| scope.borrow().clone()),
+        elapsed: None,
+    };
+    dispatch(|s| s.record(&record));
+}
+
+/// Open a span, which is timed until the returned guard is dropped.
+///
+/// Spans opened while it is open on the same thread are nested in it and
+/// must be closed first.
+pub fn span(
+    level: Level,
+    target: &'static str,
+    name: &'static str,
+    fields: Vec<(&'static str, Value)>,
+) -> Span {
+    if !enabled(level, target) {
+        return Span::none();
+    }
+    let scope = SCOPE.with(|scope| {
+        let mut scope = scope.borrow_mut();
+        scope.push(name);
+        scope.clone()
+    });
+    Span {
+        open: Some((
+            Record {
+                level,
+                target,
+                name,
+                fields,
+                scope,
+                elapsed: None,
+            },
+            Instant::now(),
+        )),
+    }
+}
+
+/// Guard of an open span; the span closes when it is dropped.
+#[derive(Debug)]
+#[must_use = "the span closes as soon as the guard is dropped"]
+pub struct Span {
+    open: Option<(Record, Instant)>,
+}
+
+impl Span {
+    /// A span that records nothing.
+    pub fn none() -> Self {
+        Self { open: None }
+    }
+
+    /// Whether the span will be recorded when it closes.
+    pub fn is_enabled(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Add a field, e.g. a result only known at the end of the span.
+    pub fn record<V: Into<Value>>(&mut self, name: &'static str, value: V) {
+        if let Some((record, _)) = self.open.as_mut() {
+            record.fields.push((name, value.into()));
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some((mut record, start)) = self.open.take() else {
+            return;
+        };
+        record.elapsed = Some(start.elapsed());
+        SCOPE.with(|scope| scope.borrow_mut().pop());
+        dispatch(|s| s.record(&record));
+    }
+}
+
+/// Writes one human-readable line per record, by default to standard
+/// error:
+///
+/// ```text
+///  INFO run: neuromorphic_core::simulation: run finished reason="Completed" steps=1000 spikes=212
+///  INFO run: neuromorphic_core::simulation: close elapsed_ms=3.412 neurons=3 synapses=6
+/// ```
+pub struct FmtSubscriber {
+    max_level: Level,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl FmtSubscriber {
+    /// Subscriber writing records up to `max_level` to standard error.
+    pub fn new(max_level: Level) -> Self {
+        Self::with_writer(max_level, io::stderr())
+    }
+
+    /// Subscriber writing records up to `max_level` to `writer`.
+    pub fn with_writer<W: Write + Send + 'static>(max_level: Level, writer: W) -> Self {
+        Self {
+            max_level,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl Subscriber for FmtSubscriber {
+    fn max_level(&self) -> Level {
+        self.max_level
+    }
+
+    fn record(&self, record: &Record) {
+        let mut line = format!("{:>5} ", record.level.as_str());
+        if !record.scope.is_empty() {
+            line.push_str(&record.scope.join(":"));
+            line.push_str(": ");
+        }
+        line.push_str(record.target);
+        line.push_str(": ");
+        match record.elapsed {
+            Some(elapsed) => {
+                line.push_str(&format!("close elapsed_ms={:.3}", elapsed.as_secs_f64() * 1e3))
+            }
+            None => line.push_str(record.name),
+        }
+        for (name, value) in &record.fields {
+            line.push_str(&format!(" {name}={value}"));
+        }
+        line.push('\n');
+        // Logging must not fail the run; a broken writer loses the line
+        let _ = lock(&self.writer).write_all(line.as_bytes());
+    }
+}
+
+/// Writes one JSON object per line for each record, for collection by log
+/// pipelines:
+///
+/// ```text
+/// {"level":"INFO","target":"neuromorphic_core::simulation","name":"run","scope":["run"],
+///  "elapsed_ms":3.412,"fields":{"neurons":3,"synapses":6}}
+/// ```
+///
+/// `elapsed_ms` is only present for spans.
+pub struct JsonSubscriber {
+    max_level: Level,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonSubscriber {
+    /// Subscriber writing records up to `max_level` to `writer`.
+    pub fn new<W: Write + Send + 'static>(max_level: Level, writer: W) -> Self {
+        Self {
+            max_level,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl Subscriber for JsonSubscriber {
+    fn max_level(&self) -> Level {
+        self.max_level
+    }
+
+    fn record(&self, record: &Record) {
+        let mut line = String::from("{\"level\":");
+        write_string(&mut line, record.level.as_str());
+        line.push_str(",\"target\":");
+        write_string(&mut line, record.target);
+        line.push_str(",\"name\":");
+        write_string(&mut line, record.name);
+        line.push_str(",\"scope\":[");
+        for (i, name) in record.scope.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            write_string(&mut line, name);
+        }
+        line.push(']');
+        if let Some(elapsed) = record.elapsed {
+            line.push_str(",\"elapsed_ms\":");
+            write_number(&mut line, elapsed.as_secs_f64() * 1e3);
+        }
+        line.push_str(",\"fields\":{");
+        for (i, (name, value)) in record.fields.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            write_string(&mut line, name);
+            line.push(':');
+            match value {
+                Value::Bool(b) => line.push_str(if *b { "true" } else { "false" }),
+                Value::Int(i) => line.push_str(&i.to_string()),
+                Value::Uint(u) => line.push_str(&u.to_string()),
+                Value::Float(x) => write_number(&mut line, *x),
+                Value::Text(s) => write_string(&mut line, s),
+            }
+        }
+        line.push_str("}}\n");
+        let _ = lock(&self.writer).write_all(line.as_bytes());
+    }
+}
+
+/// Total time spent in spans of one name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanTotal {
+    pub name: &'static str,
+    /// Number of times the span closed
+    pub count: usize,
+    pub total: Duration,
+}
+
+/// Keeps records in memory. Clones share the records, so one clone can be
+/// installed and another read after the run.
+#[derive(Debug, Clone)]
+pub struct TraceCollector {
+    max_level: Level,
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+impl TraceCollector {
+    /// Collector of records up to `max_level`.
+    pub fn new(max_level: Level) -> Self {
+        Self {
+            max_level,
+            records: Arc::default(),
+        }
+    }
+
+    /// Records collected so far, in the order they were emitted.
+    pub fn records(&self) -> Vec<Record> {
+        lock(&self.records).clone()
+    }
+
+    /// Time spent in each span name, in order of first closing.
+    pub fn span_totals(&self) -> Vec<SpanTotal> {
+        let mut totals: Vec<SpanTotal> = Vec::new();
+        for record in lock(&self.records).iter() {
+            let Some(elapsed) = record.elapsed else {
+                continue;
+            };
+            match totals.iter_mut().find(|t| t.name == record.name) {
+                Some(total) => {
+                    total.count += 1;
+                    total.total += elapsed;
+                }
+                None => totals.push(SpanTotal {
+                    name: record.name,
+                    count: 1,
+                    total: elapsed,
+                }),
+            }
+        }
+        totals
+    }
+
+    /// Discard the records collected so far.
+    pub fn clear(&self) {
+        lock(&self.records).clear();
+    }
+}
+
+impl Subscriber for TraceCollector {
+    fn max_level(&self) -> Level {
+        self.max_level
+    }
+
+    fn record(&self, record: &Record) {
+        lock(&self.records).push(record.clone());
+    }
+}
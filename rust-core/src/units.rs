@@ -0,0 +1,142 @@
+//! units.rs
+//!
+//! Explicit time units.
+//!
+//! Spike times, time constants, and simulation steps are all durations in
+//! milliseconds, while rates and many external signals are naturally
+//! expressed in seconds. Mixing the two silently is an easy mistake, so
+//! time values in this crate are wrapped in unit-carrying newtypes and
+//! converted explicitly.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A time or duration in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Milliseconds(pub f64);
+
+/// A time or duration in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Seconds(pub f64);
+
+impl Milliseconds {
+    /// Zero duration.
+    pub const ZERO: Milliseconds = Milliseconds(0.0);
+
+    /// Convert from seconds.
+    pub fn from_secs(secs: f64) -> Self {
+        Milliseconds(secs * 1000.0)
+    }
+
+    /// Value in seconds.
+    pub fn as_secs(self) -> f64 {
+        self.0 / 1000.0
+    }
+
+    /// Absolute value.
+    pub fn abs(self) -> Self {
+        Milliseconds(self.0.abs())
+    }
+
+    /// Larger of two times.
+    pub fn max(self, other: Self) -> Self {
+        Milliseconds(self.0.max(other.0))
+    }
+
+    /// Smaller of two times.
+    pub fn min(self, other: Self) -> Self {
+        Milliseconds(self.0.min(other.0))
+    }
+}
+
+impl Seconds {
+    /// Value in milliseconds.
+    pub fn as_millis(self) -> f64 {
+        self.0 * 1000.0
+    }
+}
+
+impl From<Seconds> for Milliseconds {
+    fn from(s: Seconds) -> Self {
+        Milliseconds(s.as_millis())
+    }
+}
+
+impl From<Milliseconds> for Seconds {
+    fn from(ms: Milliseconds) -> Self {
+        Seconds(ms.as_secs())
+    }
+}
+
+impl fmt::Display for Milliseconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        f.write_str(" ms")
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        f.write_str(" s")
+    }
+}
+
+impl Add for Milliseconds {
+    type Output = Milliseconds;
+    fn add(self, rhs: Self) -> Self {
+        Milliseconds(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Milliseconds {
+    type Output = Milliseconds;
+    fn sub(self, rhs: Self) -> Self {
+        Milliseconds(self.0 - rhs.0)
+    }
+}
+
+impl AddAssign for Milliseconds {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Milliseconds {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Milliseconds {
+    type Output = Milliseconds;
+    fn neg(self) -> Self {
+        Milliseconds(-self.0)
+    }
+}
+
+impl Mul<f64> for Milliseconds {
+    type Output = Milliseconds;
+    fn mul(self, rhs: f64) -> Self {
+        Milliseconds(self.0 * rhs)
+    }
+}
+
+impl Div<f64> for Milliseconds {
+    type Output = Milliseconds;
+    fn div(self, rhs: f64) -> Self {
+        Milliseconds(self.0 / rhs)
+    }
+}
+
+/// Ratio of two durations (dimensionless).
+impl Div for Milliseconds {
+    type Output = f64;
+    fn div(self, rhs: Self) -> f64 {
+        self.0 / rhs.0
+    }
+}
@@ -0,0 +1,59 @@
+//! util.rs
+//!
+//! Small helpers shared across modules.
+//!
+//! Readers of the many file formats report malformed input the same way,
+//! monitors share state with their handles behind a `Mutex`, several
+//! analyses summarize samples by mean and standard deviation, and the PNG,
+//! zip and gzip writers checksum their data with the same CRC-32. Keeping
+//! these helpers in one place keeps their behaviour identical everywhere.
+
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+
+/// Build an `InvalidData` error.
+pub(crate) fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Build an `InvalidInput` error.
+pub(crate) fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Lock `state`, which is only poisoned if a holder panicked.
+pub(crate) fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().expect("Shared state lock poisoned")
+}
+
+/// Population mean and standard deviation; `(0, 0)` for empty input.
+pub(crate) fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+/// CRC-32 (IEEE) as used by PNG, zip and gzip.
+pub(crate) fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    crc32_update(0, bytes)
+}
+
+/// CRC-32 of earlier bytes, `crc`, continued over `bytes`.
+pub(crate) fn crc32_update<'a>(crc: u32, bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !crc;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,54 @@
+//! wasm.rs
+//!
+//! Step-wise simulation API for WebAssembly hosts.
+//!
+//! Browser demos need to advance a network a few steps per animation frame,
+//! nudge it with input, and draw the resulting spikes and weights. That is
+//! what the C interface in `ffi` already offers, so the `wasm` feature
+//! enables it and a JavaScript host calls the same `nc_*` functions on the
+//! instantiated module: every call goes through the same handle and panic
+//! guard, and `nc_last_error` explains failures. The only addition here is
+//! memory management, since a C host owns the buffers it passes in but
+//! JavaScript can only write into the module's linear memory:
+//! `snn_alloc` and `snn_free` reserve buffers there for the configuration,
+//! input currents, spikes and weights. `web/snn.js` provides a small class
+//! around these calls.
+//!
+//! The exports are hand-written rather than generated with `wasm-bindgen`,
+//! which keeps the crate free of dependencies beyond the optional `serde`;
+//! the price is that strings and arrays cross the boundary as pointers and
+//! lengths. `usize` is 32 bits wide on wasm32. Build the module with
+//! `cargo build --release --lib --target wasm32-unknown-unknown --features wasm`,
+//! and check that the crate still compiles for wasm32 with
+//! `scripts/check_wasm.sh`. Code for wasm32-unknown-unknown aborts on
+//! panic, so there a panic traps instead of setting the last error.
+
+use std::alloc::{self, Layout};
+use std::ptr::NonNull;
+
+/// Alignment of buffers from `snn_alloc`, enough for doubles.
+const ALIGN: usize = 8;
+
+/// Allocate `len` bytes for the host to write into, e.g. a configuration,
+/// aligned for doubles. Returns null if the memory cannot be allocated.
+#[no_mangle]
+pub extern "C" fn snn_alloc(len: usize) -> *mut u8 {
+    match Layout::from_size_align(len, ALIGN) {
+        Ok(_) if len == 0 => NonNull::<u64>::dangling().as_ptr().cast(),
+        // SAFETY: the layout has a nonzero size
+        Ok(layout) => unsafe { alloc::alloc(layout) },
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Release a buffer from `snn_alloc`.
+///
+/// # Safety
+/// `ptr` and `len` must come from one `snn_alloc` call, and the buffer must
+/// not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn snn_free(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() && len > 0 {
+        alloc::dealloc(ptr, Layout::from_size_align_unchecked(len, ALIGN));
+    }
+}
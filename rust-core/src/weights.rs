@@ -0,0 +1,79 @@
+//! weights.rs
+//!
+//! Initial synaptic weight distributions.
+//!
+//! Starting every synapse at the same weight makes all neurons initially
+//! equivalent, which slows down or prevents symmetry breaking under STDP.
+//! A `WeightInit` describes how initial weights are drawn, and is accepted
+//! wherever connectivity is built. Random draws always come from a seeded
+//! `Rng`, so networks remain reproducible.
+
+use crate::rng::Rng;
+
+/// Distribution of initial synaptic weights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WeightInit {
+    /// Every synapse gets the same weight.
+    Constant(f64),
+    /// Weights drawn uniformly from `[low, high)`.
+    Uniform {
+        /// Lower bound
+        low: f64,
+        /// Upper bound
+        high: f64,
+    },
+    /// Normally distributed weights clipped to `[min, max]`; `min` must
+    /// not exceed `max`.
+    Gaussian {
+        /// Mean weight
+        mean: f64,
+        /// Standard deviation
+        std_dev: f64,
+        /// Lower clipping bound
+        min: f64,
+        /// Upper clipping bound
+        max: f64,
+    },
+    /// Log-normal weights: `exp(N(mu, sigma²))`.
+    LogNormal {
+        /// Mean of the underlying normal distribution
+        mu: f64,
+        /// Standard deviation of the underlying normal distribution
+        sigma: f64,
+    },
+}
+
+impl WeightInit {
+    /// Draw one weight.
+    ///
+    /// # Panics
+    /// Panics if a `Gaussian` has `min > max` or a NaN bound.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        match *self {
+            WeightInit::Constant(w) => w,
+            WeightInit::Uniform { low, high } => rng.uniform(low, high),
+            WeightInit::Gaussian {
+                mean,
+                std_dev,
+                min,
+                max,
+            } => rng.gaussian(mean, std_dev).clamp(min, max),
+            WeightInit::LogNormal { mu, sigma } => rng.gaussian(mu, sigma).exp(),
+        }
+    }
+
+    /// Weight hook for the generators in `connectivity`.
+    ///
+    /// # Panics
+    /// The hook panics like `sample`.
+    pub fn hook(self) -> impl FnMut(usize, usize, &mut Rng) -> f64 {
+        move |_, _, rng| self.sample(rng)
+    }
+}
+
+impl From<f64> for WeightInit {
+    fn from(w: f64) -> Self {
+        WeightInit::Constant(w)
+    }
+}
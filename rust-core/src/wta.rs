@@ -0,0 +1,118 @@
+//! wta.rs
+//!
+//! Winner-take-all microcircuits.
+//!
+//! A winner-take-all (WTA) circuit is a population of excitatory neurons
+//! that compete through lateral inhibition: the first neuron to fire
+//! suppresses the others. Combined with STDP on the incoming synapses, this
+//! competition makes each neuron specialize on a different input pattern,
+//! which is the basis of unsupervised spiking classifiers.
+//!
+//! Inhibition only acts on the dynamics when
+//! `SimulationConfig::synaptic_transmission` is enabled.
+
+use crate::network::NetworkBuilder;
+use crate::neuron::NeuronParams;
+use crate::synapse::{Plasticity, Synapse};
+use std::ops::Range;
+
+/// Strength of the competition between excitatory neurons.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Competition {
+    /// A single winner spike pushes every competitor down by the full
+    /// threshold-to-reset distance, silencing it.
+    Hard,
+    /// Competitors are pushed down by the given amount.
+    Soft(f64),
+}
+
+/// How lateral inhibition is wired.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LateralInhibition {
+    /// Excitatory neurons inhibit each other directly.
+    Direct,
+    /// Each excitatory neuron drives its own inhibitory partner with weight
+    /// `drive`, which in turn inhibits every other excitatory neuron.
+    Pool {
+        /// Weight of the excitatory-to-inhibitory synapses
+        drive: f64,
+    },
+}
+
+/// Parameters of a WTA circuit.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WtaParams {
+    /// Number of competing excitatory neurons
+    pub size: usize,
+    /// Strength of the competition
+    pub competition: Competition,
+    /// Wiring of lateral inhibition
+    pub inhibition: LateralInhibition,
+}
+
+/// Neuron layout of a WTA circuit.
+#[derive(Debug, Clone)]
+pub struct WtaCircuit {
+    /// Competing excitatory neurons
+    pub excitatory: Range<usize>,
+    /// Inhibitory pool, if one was created
+    pub inhibitory: Option<Range<usize>>,
+}
+
+/// Add a WTA circuit to `builder`.
+///
+/// The excitatory neurons are registered as population `name` and the
+/// inhibitory pool, if any, as `{name}_inh`. All circuit synapses are
+/// static; connect plastic inputs to `excitatory` separately.
+pub fn add_wta(
+    builder: &mut NetworkBuilder,
+    name: &str,
+    params: &WtaParams,
+    neuron_params: NeuronParams,
+) -> WtaCircuit {
+    let strength = match params.competition {
+        Competition::Hard => neuron_params.v_thresh - neuron_params.v_reset,
+        Competition::Soft(s) => s,
+    };
+    let excitatory = builder.add_population(name, params.size, neuron_params.clone());
+
+    let mut synapses = Vec::new();
+    let inhibitory = match params.inhibition {
+        LateralInhibition::Direct => {
+            for i in excitatory.clone() {
+                for j in excitatory.clone() {
+                    if i != j {
+                        synapses.push(Synapse::new(i, j, -strength));
+                    }
+                }
+            }
+            None
+        }
+        LateralInhibition::Pool { drive } => {
+            let pool =
+                builder.add_population(&format!("{name}_inh"), params.size, neuron_params);
+            for (e, h) in excitatory.clone().zip(pool.clone()) {
+                synapses.push(Synapse::new(e, h, drive));
+                for j in excitatory.clone() {
+                    if j != e {
+                        synapses.push(Synapse::new(h, j, -strength));
+                    }
+                }
+            }
+            Some(pool)
+        }
+    };
+
+    for synapse in &mut synapses {
+        synapse.plasticity = Plasticity::Static;
+    }
+    builder.add_synapses(synapses);
+
+    WtaCircuit {
+        excitatory,
+        inhibitory,
+    }
+}
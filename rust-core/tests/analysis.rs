@@ -0,0 +1,59 @@
+use neuromorphic_core::analysis::{
+    binned_counts, mean_pairwise_correlation, spike_synchrony, spike_triggered_input,
+    synchrony_index,
+};
+use neuromorphic_core::inference::transfer_entropy_matrix;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+
+#[test]
+fn synchrony_index_is_one_for_identical_signals() {
+    let signal = vec![0.0, 1.0, 0.0, 2.0];
+    let chi = synchrony_index(&[signal.clone(), signal.clone(), signal]).unwrap();
+    assert!((chi - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn synchrony_index_is_zero_for_cancelling_signals() {
+    let chi = synchrony_index(&[vec![1.0, -1.0], vec![-1.0, 1.0]]).unwrap();
+    assert!(chi.abs() < 1e-12);
+}
+
+#[test]
+fn synchrony_index_rejects_ragged_or_flat_signals() {
+    assert_eq!(synchrony_index(&[vec![0.0, 1.0], vec![1.0]]), None);
+    assert_eq!(synchrony_index(&[vec![1.0, 1.0], vec![2.0, 2.0]]), None);
+    assert_eq!(synchrony_index(&[]), None);
+}
+
+#[test]
+fn binning_without_bins_yields_empty_rows() {
+    let spikes = [
+        Spike::new(0, Milliseconds(1.0)),
+        Spike::new(1, Milliseconds(2.0)),
+    ];
+    let (start, end) = (Milliseconds::ZERO, Milliseconds(10.0));
+    for bin in [
+        Milliseconds::ZERO,
+        Milliseconds(-1.0),
+        Milliseconds(f64::NAN),
+        Milliseconds(f64::INFINITY),
+    ] {
+        assert_eq!(binned_counts(&spikes, 0..2, start, end, bin), [[]; 2]);
+        assert_eq!(spike_synchrony(&spikes, 0..2, start, end, bin), None);
+        assert_eq!(
+            mean_pairwise_correlation(&spikes, 0..2, start, end, bin),
+            None
+        );
+        assert_eq!(
+            spike_triggered_input(&spikes, 0..2, &[Milliseconds(5.0)], end, bin),
+            None
+        );
+        assert_eq!(
+            transfer_entropy_matrix(&spikes, 0..2, start, end, bin, 1, 1),
+            [[0.0; 2]; 2]
+        );
+    }
+    let bin = Milliseconds(1.0);
+    assert_eq!(binned_counts(&spikes, 0..2, end, start, bin), [[]; 2]);
+}
@@ -0,0 +1,83 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use neuromorphic_core::connectivity::erdos_renyi;
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::neuron::NeuronParams;
+use neuromorphic_core::rng::Rng;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::stdp::STDPParams;
+use neuromorphic_core::units::Milliseconds;
+use std::path::PathBuf;
+
+pub fn neuron_params() -> NeuronParams {
+    NeuronParams {
+        tau_m: Milliseconds(20.0),
+        v_rest: 0.0,
+        v_thresh: 1.0,
+        v_reset: 0.0,
+    }
+}
+
+pub fn stdp_params() -> STDPParams {
+    STDPParams {
+        a_plus: 0.01,
+        a_minus: 0.012,
+        tau_plus: Milliseconds(20.0),
+        tau_minus: Milliseconds(20.0),
+        w_min: 0.0,
+        w_max: 1.0,
+    }
+}
+
+/// A random recurrent network of `n` neurons with plastic synapses and
+/// synaptic transmission.
+pub fn random_network(n: usize, config: SimulationConfig, seed: u64) -> Simulation {
+    let mut rng = Rng::new(seed);
+    let mut builder = NetworkBuilder::new();
+    let all = builder.add_neurons(n, neuron_params());
+    builder.connect_all(erdos_renyi(all.clone(), all, 0.2, &mut rng, |_, _, rng| {
+        rng.uniform(0.05, 0.3)
+    }));
+    let config = SimulationConfig {
+        synaptic_transmission: true,
+        ..config
+    };
+    Simulation::from_network(builder.build(), config, stdp_params())
+}
+
+/// Deterministic, irregular external current.
+pub fn drive(i: usize, t: Milliseconds) -> f64 {
+    let phase = (i as f64 * 0.7 + t.0 * 0.31).sin();
+    1.0 + 0.3 * phase
+}
+
+/// Run `sim` for `duration` with `drive`, returning every spike.
+pub fn run_for(sim: &mut Simulation, duration: Milliseconds) -> Vec<Spike> {
+    let end = sim.time() + duration;
+    let mut spikes = Vec::new();
+    while sim.time() < end {
+        spikes.extend(sim.step(drive));
+    }
+    spikes
+}
+
+/// Bit patterns of the synaptic weights, for exact comparison.
+pub fn weight_bits(sim: &Simulation) -> Vec<u64> {
+    sim.synapses().iter().map(|s| s.weight.to_bits()).collect()
+}
+
+/// A path in the temporary directory unique to this test process.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("nc_test_{}_{name}", std::process::id()))
+}
+
+/// Neuron ids and time bit patterns of `spikes`, for exact comparison.
+pub fn spike_bits(spikes: &[Spike]) -> Vec<(usize, u64)> {
+    spikes
+        .iter()
+        .map(|s| (s.neuron_id, s.time.0.to_bits()))
+        .collect()
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::experiment::{ExperimentReport, Metrics};
+use neuromorphic_core::lava::write_lava;
+use neuromorphic_core::manifest::RunManifest;
+use neuromorphic_core::results::{RunMetadata, RunResults};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::spike_codec::{read_spikes_binary, write_spikes_binary};
+use neuromorphic_core::spike_io::{
+    read_spikes_csv, read_spikes_json, write_spikes_csv, write_spikes_json,
+};
+use neuromorphic_core::units::Milliseconds;
+use std::io;
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+fn assert_rejected<T: std::fmt::Debug>(path: &Path, result: io::Result<T>) {
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{path:?}");
+    assert!(error.to_string().contains("decompress it first"), "{error}");
+}
+
+#[test]
+fn readers_reject_compressed_outputs() {
+    let spikes = vec![
+        Spike::new(0, Milliseconds(1.0)),
+        Spike::new(3, Milliseconds(2.5)),
+    ];
+
+    let csv = temp_path("spikes.csv.gz");
+    write_spikes_csv(&spikes, &csv).unwrap();
+    assert_eq!(std::fs::read(&csv).unwrap()[..2], GZIP_MAGIC);
+    assert_rejected(&csv, read_spikes_csv(&csv));
+
+    let json = temp_path("spikes.json.zst");
+    write_spikes_json(&spikes, &json).unwrap();
+    assert_eq!(std::fs::read(&json).unwrap()[..4], ZSTD_MAGIC);
+    assert_rejected(&json, read_spikes_json(&json));
+
+    let spk = temp_path("spikes.spk.GZ");
+    write_spikes_binary(&spikes, Milliseconds(0.1), &spk).unwrap();
+    assert_eq!(std::fs::read(&spk).unwrap()[..2], GZIP_MAGIC);
+    assert_rejected(&spk, read_spikes_binary(&spk));
+
+    let results = RunResults {
+        metadata: RunMetadata {
+            num_neurons: 4,
+            num_synapses: 0,
+            dt: Milliseconds(0.1),
+            t_max: Milliseconds(5.0),
+            end_time: Milliseconds(5.0),
+            stop_reason: None,
+        },
+        spikes,
+        weights: Vec::new(),
+    };
+    let path = temp_path("results.json.zst");
+    results.write_json(&path).unwrap();
+    assert_rejected(&path, RunResults::read_json(&path));
+}
+
+#[test]
+fn exports_are_compressed_by_extension() {
+    let sim = common::random_network(4, SimulationConfig::default(), 3);
+
+    let dot = temp_path("network.dot");
+    sim.write_dot(&dot, false).unwrap();
+    let plain = std::fs::read_to_string(&dot).unwrap();
+    assert!(plain.starts_with("digraph network {"), "{plain}");
+    let dot = temp_path("network.dot.gz");
+    sim.write_dot(&dot, false).unwrap();
+    assert_eq!(std::fs::read(&dot).unwrap()[..2], GZIP_MAGIC);
+
+    let lava = temp_path("network.json.zst");
+    write_lava(&sim, "net", &lava).unwrap();
+    assert_eq!(std::fs::read(&lava).unwrap()[..4], ZSTD_MAGIC);
+
+    let manifest = temp_path("manifest.json.gz");
+    RunManifest::new(&sim, Some(3)).write(&manifest).unwrap();
+    assert_eq!(std::fs::read(&manifest).unwrap()[..2], GZIP_MAGIC);
+
+    let metrics = Metrics::from([("rate".to_string(), 1.0)]);
+    let report = ExperimentReport::new("trial", vec![1], vec![metrics]);
+    let csv = temp_path("experiment.csv.zst");
+    report.write_csv(&csv).unwrap();
+    assert_eq!(std::fs::read(&csv).unwrap()[..4], ZSTD_MAGIC);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::{spike_bits, temp_path, weight_bits};
+use neuromorphic_core::config::{ExperimentConfig, Stimulus};
+use neuromorphic_core::population::Connector;
+use neuromorphic_core::units::Milliseconds;
+use std::io::ErrorKind;
+
+const CONFIG: &str = r#"
+seed = 42
+
+[simulation]
+dt_ms = 0.1
+t_max_ms = 100.0
+synaptic_transmission = true
+
+[neuron]
+tau_m_ms = 20.0
+v_rest = 0.0
+v_thresh = 1.0
+v_reset = 0.0
+
+[stdp]
+a_plus = 0.01
+a_minus = 0.012
+tau_plus_ms = 20.0
+tau_minus_ms = 20.0
+w_min = 0.0
+w_max = 1.0
+
+[[population]]
+name = "input"
+size = 100
+
+[[population]]
+name = "output"
+size = 10
+tau_m_ms = 10.0          # overrides [neuron]
+
+[[projection]]
+pre = "input"
+post = "output"
+connector = "all_to_all"
+weight_min = 0.1
+weight_max = 0.5
+delay_ms = 1.0
+plastic = true
+
+[[stimulus]]
+kind = "poisson"
+population = "input"
+rate_hz = 20.0
+start_ms = 0.0
+duration_ms = 50.0
+
+[[stimulus]]
+kind = "current"
+population = "output"
+amplitude = 0.5
+start_ms = 10.0
+"#;
+
+#[test]
+fn parses_every_section() {
+    let config = ExperimentConfig::from_toml(CONFIG).unwrap();
+    assert_eq!(config.seed, 42);
+    assert_eq!(config.simulation.t_max, Milliseconds(100.0));
+    assert!(config.simulation.synaptic_transmission);
+    assert_eq!(config.stdp.a_minus, 0.012);
+
+    let sizes: Vec<(&str, usize)> = config
+        .populations
+        .iter()
+        .map(|p| (p.name.as_str(), p.size))
+        .collect();
+    assert_eq!(sizes, vec![("input", 100), ("output", 10)]);
+    assert_eq!(config.populations[0].params.tau_m, Milliseconds(20.0));
+    assert_eq!(config.populations[1].params.tau_m, Milliseconds(10.0));
+
+    let projection = &config.projections[0];
+    assert!(matches!(projection.connector, Connector::AllToAll));
+    assert!(projection.plasticity.is_some());
+
+    match &config.stimuli[1] {
+        Stimulus::Current {
+            amplitude,
+            start,
+            duration,
+            ..
+        } => {
+            assert_eq!(*amplitude, 0.5);
+            assert_eq!(*start, Milliseconds(10.0));
+            // Defaults to the rest of the run
+            assert_eq!(*duration, Milliseconds(90.0));
+        }
+        other => panic!("Expected a current stimulus, got {other:?}"),
+    }
+    let current = config.input_current();
+    assert_eq!(current(105, Milliseconds(5.0)), 0.0);
+    assert_eq!(current(105, Milliseconds(20.0)), 0.5);
+    assert_eq!(current(5, Milliseconds(20.0)), 0.0);
+}
+
+#[test]
+fn loaded_file_builds_the_same_run_every_time() {
+    let path = temp_path("experiment.toml");
+    std::fs::write(&path, CONFIG).unwrap();
+    let config = ExperimentConfig::load(&path).unwrap();
+    assert_eq!(
+        format!("{config:?}"),
+        format!("{:?}", ExperimentConfig::from_toml(CONFIG).unwrap())
+    );
+
+    let run = || {
+        let mut sim = config.build();
+        assert_eq!(sim.neurons().len(), 110);
+        assert_eq!(sim.synapses().len(), 1000);
+        let (spikes, _) = sim.run(config.input_current());
+        (spike_bits(&spikes), weight_bits(&sim))
+    };
+    let first = run();
+    assert!(!first.0.is_empty());
+    assert_eq!(first, run());
+}
+
+#[test]
+fn rejects_mistakes_instead_of_using_defaults() {
+    let cases = [
+        CONFIG.replace("t_max_ms = 100.0", "tmax_ms = 100.0"),
+        CONFIG.replace("[stdp]", "[stpd]"),
+        CONFIG.replace("post = \"output\"", "post = \"outptu\""),
+        CONFIG.replace("dt_ms = 0.1", "dt_ms = 0.0"),
+        CONFIG.replace("weight_max = 0.5", ""),
+        CONFIG.replace("size = 10\n", "size = \"ten\"\n"),
+        CONFIG.replace("connector = \"all_to_all\"", "connector = \"one_to_one\""),
+    ];
+    for text in &cases {
+        let err = ExperimentConfig::from_toml(text).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{text}");
+    }
+}
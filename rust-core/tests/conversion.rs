@@ -0,0 +1,152 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::conversion::{convert, ConversionParams, DenseLayer, ReluNetwork};
+use neuromorphic_core::numpy::{write_npz, NpyArray};
+use std::io::ErrorKind;
+
+/// A 2-3-2 network with a unit silenced by ReLU for some inputs.
+fn ann() -> ReluNetwork {
+    ReluNetwork::new(vec![
+        DenseLayer {
+            weights: vec![vec![1.0, 0.0], vec![0.5, 0.5], vec![-1.0, 1.0]],
+            biases: vec![0.0, 0.1, 0.0],
+        },
+        DenseLayer {
+            weights: vec![vec![1.0, -0.5, 0.5], vec![0.2, 0.3, 0.0]],
+            biases: vec![0.0, 0.05],
+        },
+    ])
+    .unwrap()
+}
+
+fn inputs() -> Vec<Vec<f64>> {
+    vec![
+        vec![0.8, 0.3],
+        vec![0.2, 0.9],
+        vec![0.5, 0.5],
+        vec![1.0, 0.0],
+    ]
+}
+
+#[test]
+fn spike_rates_approximate_activations() {
+    let ann = ann();
+    assert_eq!(ann.activations(&[0.8, 0.3])[0], [0.8, 0.65, 0.0]);
+
+    // Model-based bounds: inputs of at most 1 reach 1, 1.1 and 1 in layer 1
+    let mut snn = convert(&ann, &[], &ConversionParams::default());
+    assert_eq!(snn.scales, [1.1, 1.5]);
+    assert_eq!(snn.layers, [0..2, 2..5, 5..7]);
+    let populations: Vec<&str> = snn
+        .simulation
+        .populations()
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(populations, ["layer0", "layer1", "layer2"]);
+    // Zero weights are not connected
+    assert_eq!(snn.simulation.synapses().len(), 5 + 5);
+
+    for input in inputs() {
+        let expected = ann.forward(&input);
+        let rates = snn.output_rates(&input, 2000);
+        for (rate, activation) in rates.iter().zip(&expected) {
+            assert!(
+                (rate - activation).abs() < 0.01,
+                "{input:?}: rates {rates:?}, activations {expected:?}"
+            );
+        }
+        assert_eq!(snn.predict(&input, 200), ann.predict(&input));
+    }
+    let labels: Vec<usize> = inputs().iter().map(|x| ann.predict(x)).collect();
+    assert_eq!(snn.accuracy(&inputs(), &labels, 200), 1.0);
+    assert_eq!(snn.accuracy(&[], &[], 200), 0.0);
+}
+
+#[test]
+fn calibration_inputs_set_the_layer_scales() {
+    let ann = ann();
+    let params = ConversionParams {
+        percentile: 100.0,
+        ..ConversionParams::default()
+    };
+    let snn = convert(&ann, &inputs(), &params);
+    let maxima: Vec<f64> = (0..2)
+        .map(|k| {
+            inputs()
+                .iter()
+                .flat_map(|x| ann.activations(x)[k].clone())
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    assert_eq!(snn.scales, maxima);
+
+    // Biases become currents scaled like the layer, inputs a current per unit
+    let currents = snn.currents(&[0.5, 0.0]);
+    let unit = currents[0] / 0.5;
+    assert_eq!(currents[1], 0.0);
+    assert_eq!(currents[3], 0.1 / maxima[0] * unit);
+    assert_eq!(currents[6], 0.05 / maxima[1] * unit);
+}
+
+#[test]
+fn weights_are_read_from_json_and_npz() {
+    let json = ReluNetwork::from_json(
+        r#"{"layers": [
+            {"weights": [[1, 0], [0.5, 0.5], [-1, 1]], "biases": [0, 0.1, 0]},
+            {"weights": [[1, -0.5, 0.5], [0.2, 0.3, 0]], "biases": [0, 0.05]}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(json, ann());
+    let unbiased = ReluNetwork::from_json(r#"{"layers": [{"weights": [[1, 2]]}]}"#).unwrap();
+    assert_eq!(unbiased.layers[0].biases, [0.0]);
+
+    let path = temp_path("ann.npz");
+    let ann = ann();
+    let mut arrays = Vec::new();
+    for layer in &ann.layers {
+        let shape = [layer.outputs(), layer.inputs()];
+        arrays.push(NpyArray::float64(&shape, layer.weights.concat()));
+        arrays.push(NpyArray::float64(&[layer.outputs()], layer.biases.clone()));
+    }
+    let names = ["W0", "b0", "W1", "b1"];
+    let named: Vec<(&str, NpyArray)> = names.into_iter().zip(arrays.clone()).collect();
+    write_npz(&named, &path).unwrap();
+    assert_eq!(ReluNetwork::read_npz(&path).unwrap(), ann);
+
+    // A bias that does not match its weights is rejected
+    let mismatched = [("W0", arrays[0].clone()), ("b0", arrays[3].clone())];
+    write_npz(&mismatched, &path).unwrap();
+    let error = ReluNetwork::read_npz(&path).unwrap_err();
+    assert_eq!(error.to_string(), "`b0` does not match `W0`");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn inconsistent_networks_are_rejected() {
+    let error = |text: &str| {
+        let error = ReluNetwork::from_json(text).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        error.to_string()
+    };
+    assert_eq!(error(r#"{"layers": []}"#), "Network has no layers");
+    assert_eq!(error(r#"{"weights": []}"#), "Missing `layers` array");
+    assert_eq!(
+        error(r#"{"layers": [{"weights": [[1, 2], [3]]}]}"#),
+        "Weight rows of layer 0 differ in length"
+    );
+    assert_eq!(
+        error(r#"{"layers": [{"weights": [[1]], "biases": [1, 2]}]}"#),
+        "Layer 0 has 2 biases for 1 outputs"
+    );
+    assert_eq!(
+        error(r#"{"layers": [{"weights": [[1, 2]]}, {"weights": [[1, 2]]}]}"#),
+        "Layer 1 takes 2 inputs but layer 0 has 1 outputs"
+    );
+    assert_eq!(
+        error(r#"{"layers": [{"weights": [["1"]]}]}"#),
+        "Non-numeric entry in weights of layer 0"
+    );
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use neuromorphic_core::bursts::MaxIntervalParams;
+use neuromorphic_core::cpg::{
+    build_cpg, burst_onsets, mean_phase, phase_at, relative_phases, Cpg, CpgParams, PhaseTracker,
+};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::units::Milliseconds;
+
+fn cpg(params: &CpgParams) -> Cpg {
+    build_cpg(
+        params,
+        common::neuron_params(),
+        SimulationConfig::default(),
+        common::stdp_params(),
+    )
+}
+
+/// Flexor burst onsets of every oscillator after the first second of a
+/// three second run.
+fn settled_onsets(params: &CpgParams) -> Vec<Vec<Milliseconds>> {
+    let mut cpg = cpg(params);
+    let spikes = cpg.run(Milliseconds(3000.0));
+    (0..cpg.num_oscillators())
+        .map(|k| {
+            burst_onsets(
+                &spikes,
+                cpg.flexors[k].clone(),
+                &MaxIntervalParams::default(),
+            )
+            .into_iter()
+            .filter(|t| t.0 > 1000.0)
+            .collect()
+        })
+        .collect()
+}
+
+fn mean_period(onsets: &[Milliseconds]) -> f64 {
+    (onsets[onsets.len() - 1] - onsets[0]).0 / (onsets.len() - 1) as f64
+}
+
+/// Distance between two phases on the unit circle.
+fn phase_distance(a: f64, b: f64) -> f64 {
+    let d = (a - b).rem_euclid(1.0);
+    d.min(1.0 - d)
+}
+
+#[test]
+fn half_centers_alternate() {
+    let mut cpg = cpg(&CpgParams::default());
+    let spikes = cpg.run(Milliseconds(2000.0));
+    let params = MaxIntervalParams::default();
+    let flexor = burst_onsets(&spikes, cpg.flexors[0].clone(), &params);
+    let extensor = burst_onsets(&spikes, cpg.extensors[0].clone(), &params);
+    assert!(flexor.len() >= 5, "{flexor:?}");
+    // The rhythm starts with a flexor burst
+    assert!(flexor[0] < extensor[0]);
+
+    let (phase, strength) = mean_phase(&relative_phases(&flexor, &extensor)).unwrap();
+    assert!(phase_distance(phase, 0.5) < 0.1, "extensor phase {phase}");
+    assert!(strength > 0.9, "vector strength {strength}");
+
+    // The online tracker agrees with the recorded bursts
+    let tracker = cpg.tracker(0);
+    assert_eq!(tracker.last_onset(), flexor.last().copied());
+    let last = flexor[flexor.len() - 1] - flexor[flexor.len() - 2];
+    assert_eq!(tracker.period(), Some(last));
+    assert!(cpg.phases()[0].is_some());
+}
+
+#[test]
+fn tripod_gait_locks_tripods_in_antiphase() {
+    let single = mean_period(&settled_onsets(&CpgParams::default())[0]);
+    for seed in 0..3 {
+        let params = CpgParams {
+            seed,
+            ..CpgParams::tripod_gait()
+        };
+        let onsets = settled_onsets(&params);
+        for (leg, leg_onsets) in onsets.iter().enumerate() {
+            let phases = relative_phases(&onsets[0], leg_onsets);
+            let (phase, strength) = mean_phase(&phases).unwrap();
+            // Legs 0, 2 and 4 form one tripod, legs 1, 3 and 5 the other
+            let expected = if leg % 2 == 0 { 0.0 } else { 0.5 };
+            assert!(
+                phase_distance(phase, expected) < 0.12,
+                "seed {seed}: leg {leg} at phase {phase}"
+            );
+            assert!(strength > 0.9, "seed {seed}: leg {leg} strength {strength}");
+
+            let period = mean_period(leg_onsets);
+            assert!(
+                period > single && period < 1.5 * single,
+                "seed {seed}: leg {leg} period {period} ms, {single} ms uncoupled"
+            );
+        }
+    }
+}
+
+#[test]
+fn weak_drive_silences_an_oscillator() {
+    let mut cpg = cpg(&CpgParams {
+        num_oscillators: 2,
+        ..CpgParams::default()
+    });
+    cpg.run(Milliseconds(200.0));
+    cpg.set_drive(1, 0.5);
+    assert_eq!(cpg.drive(1), 0.5);
+    let spikes = cpg.run(Milliseconds(500.0));
+    let silenced = cpg.flexors[1].start..cpg.extensors[1].end;
+    let late = spikes
+        .iter()
+        .filter(|s| s.time.0 > 400.0 && silenced.contains(&s.neuron_id))
+        .count();
+    assert_eq!(late, 0);
+    assert!(spikes.iter().any(|s| cpg.flexors[0].contains(&s.neuron_id)));
+
+    cpg.set_drive_all(0.0);
+    let spikes = cpg.run(Milliseconds(200.0));
+    assert!(spikes.iter().all(|s| s.time.0 < 850.0));
+}
+
+#[test]
+fn phase_tracker_follows_burst_onsets() {
+    let mut tracker = PhaseTracker::new(10..20, Milliseconds(30.0));
+    let fire = |tracker: &mut PhaseTracker, start: f64| {
+        for k in 0..5 {
+            tracker.observe(Milliseconds(start + 2.0 * k as f64), &[12]);
+        }
+    };
+    // Spikes of other neurons are ignored
+    tracker.observe(Milliseconds(0.0), &[3]);
+    fire(&mut tracker, 10.0);
+    assert_eq!(tracker.last_onset(), Some(Milliseconds(10.0)));
+    assert_eq!(tracker.phase(Milliseconds(20.0)), None);
+
+    fire(&mut tracker, 110.0);
+    assert_eq!(tracker.period(), Some(Milliseconds(100.0)));
+    assert_eq!(tracker.phase(Milliseconds(135.0)), Some(0.25));
+    // Beyond the expected next onset the phase wraps around
+    assert_eq!(tracker.phase(Milliseconds(235.0)), Some(0.25));
+}
+
+#[test]
+fn phases_of_onsets() {
+    let reference = [10.0, 110.0, 210.0].map(Milliseconds);
+    assert_eq!(phase_at(&reference, Milliseconds(5.0)), None);
+    assert_eq!(phase_at(&reference, Milliseconds(60.0)), Some(0.5));
+    assert_eq!(phase_at(&reference, Milliseconds(185.0)), Some(0.75));
+    assert_eq!(phase_at(&reference, Milliseconds(210.0)), None);
+
+    let other = [0.0, 70.0, 170.0, 300.0].map(Milliseconds);
+    assert_eq!(relative_phases(&reference, &other), [0.6, 0.6]);
+
+    // Phases on both sides of 0 average to 0, not 0.5
+    let (phase, strength) = mean_phase(&[0.95, 0.05]).unwrap();
+    assert!(phase_distance(phase, 0.0) < 1e-9, "{phase}");
+    assert!((strength - (0.1 * std::f64::consts::PI).cos()).abs() < 1e-9);
+    let (_, strength) = mean_phase(&[0.0, 0.5]).unwrap();
+    assert!(strength < 1e-9);
+    assert_eq!(mean_phase(&[]), None);
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "mnist")]
+
+use neuromorphic_core::dataset::{read_idx_images, read_idx_labels, Mnist};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+fn write_temp(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("nc_dataset_{}_{name}", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn idx_images(count: u32, rows: u32, cols: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for word in [0x0803, count, rows, cols] {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+fn idx_labels(count: u32, labels: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for word in [0x0801, count] {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+    bytes.extend_from_slice(labels);
+    bytes
+}
+
+#[test]
+fn reads_images_and_labels() {
+    let images = write_temp(
+        "images",
+        &idx_images(2, 2, 3, &[0, 51, 255, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+    );
+    let labels = write_temp("labels", &idx_labels(2, &[7, 3]));
+
+    let (raw, rows, cols) = read_idx_images(&images).unwrap();
+    assert_eq!((rows, cols), (2, 3));
+    assert_eq!(raw, vec![vec![0, 51, 255, 1, 2, 3], vec![4, 5, 6, 7, 8, 9]]);
+
+    let mnist = Mnist::load(&images, &labels).unwrap();
+    assert_eq!(mnist.labels, vec![7, 3]);
+    assert_eq!(mnist.images[0][..3], [0.0, 0.2, 1.0]);
+}
+
+#[test]
+fn rejects_wrong_magic() {
+    let path = write_temp("magic", &idx_labels(1, &[1])[..]);
+    let err = read_idx_images(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_truncated_files() {
+    let images = write_temp("short_images", &idx_images(3, 2, 2, &[0; 8]));
+    assert_eq!(
+        read_idx_images(&images).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    let labels = write_temp("short_labels", &idx_labels(5, &[1, 2]));
+    assert_eq!(
+        read_idx_labels(&labels).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn rejects_huge_headers_without_allocating() {
+    let images = write_temp(
+        "huge_images",
+        &idx_images(u32::MAX, u32::MAX, u32::MAX, &[]),
+    );
+    assert_eq!(
+        read_idx_images(&images).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    let labels = write_temp("huge_labels", &idx_labels(u32::MAX, &[]));
+    assert_eq!(
+        read_idx_labels(&labels).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
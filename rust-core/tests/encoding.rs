@@ -0,0 +1,44 @@
+use neuromorphic_core::encoding::{DeltaEncoder, StreamingEncoder, MAX_DELTA_SPIKES};
+use neuromorphic_core::units::Milliseconds;
+
+fn ms(t: f64) -> Milliseconds {
+    Milliseconds(t)
+}
+
+#[test]
+fn delta_encoder_emits_one_spike_per_threshold_crossed() {
+    let encoder = DeltaEncoder::new(0.5);
+    let spikes = encoder.encode(&[(ms(0.0), 0.0), (ms(1.0), 1.2), (ms(2.0), 0.1)]);
+    let ids: Vec<usize> = spikes.iter().map(|s| s.neuron_id).collect();
+    // Up by 1.2: two ON spikes, reference 1.0; down by 0.9: one OFF spike
+    assert_eq!(ids, vec![0, 0, 1]);
+    assert_eq!(spikes[2].time, ms(2.0));
+}
+
+#[test]
+fn delta_encoder_skips_non_finite_samples() {
+    let encoder = DeltaEncoder::new(1.0);
+    let spikes = encoder.encode(&[
+        (ms(0.0), f64::NAN),
+        (ms(1.0), 0.0),
+        (ms(2.0), f64::INFINITY),
+        (ms(3.0), 2.0),
+    ]);
+    assert_eq!(spikes.len(), 2);
+    assert!(spikes.iter().all(|s| s.time == ms(3.0)));
+}
+
+#[test]
+fn delta_encoder_caps_spikes_per_sample() {
+    let mut stream = DeltaEncoder::new(1e-300).stream();
+    assert!(stream.push_sample(ms(0.0), 1.0e6).is_empty());
+    assert_eq!(stream.push_sample(ms(1.0), 2.0e6).len(), MAX_DELTA_SPIKES);
+    // The reference moved to the sample, so an unchanged signal is silent
+    assert!(stream.push_sample(ms(2.0), 2.0e6).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn delta_encoder_rejects_non_positive_threshold() {
+    DeltaEncoder::new(0.0).encode(&[(ms(0.0), 0.0)]);
+}
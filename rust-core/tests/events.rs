@@ -0,0 +1,127 @@
+use neuromorphic_core::events::{read_aedat31, read_events_csv, read_nmnist_bin, DvsEvent};
+use neuromorphic_core::units::Milliseconds;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+fn write_temp(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("nc_events_{}_{name}", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn event(t_us: f64, x: u16, y: u16, polarity: bool) -> DvsEvent {
+    DvsEvent {
+        time: Milliseconds(t_us / 1000.0),
+        x,
+        y,
+        polarity,
+    }
+}
+
+/// An AEDAT 3.1 packet header.
+fn packet_header(event_type: i16, event_size: i32, overflow: i32, capacity: i32) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&event_type.to_le_bytes());
+    header.extend_from_slice(&0i16.to_le_bytes());
+    for field in [event_size, 4, overflow, capacity, capacity, capacity] {
+        header.extend_from_slice(&field.to_le_bytes());
+    }
+    header
+}
+
+/// An AEDAT 3.1 polarity event.
+fn polarity_event(x: u32, y: u32, polarity: bool, valid: bool, ts: i32) -> Vec<u8> {
+    let data = (x << 17) | (y << 2) | (u32::from(polarity) << 1) | u32::from(valid);
+    let mut bytes = data.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&ts.to_le_bytes());
+    bytes
+}
+
+const AEDAT_HEADER: &[u8] = b"#!AER-DAT3.1\r\n#Format: RAW\r\n#!END-HEADER\r\n";
+
+#[test]
+fn reads_csv_events() {
+    let path = write_temp("events.csv", b"t_us,x,y,polarity\n1000,3,4,1\n2500,0,1,0\n");
+    let events = read_events_csv(&path).unwrap();
+    assert_eq!(
+        events,
+        vec![event(1000.0, 3, 4, true), event(2500.0, 0, 1, false)]
+    );
+
+    let path = write_temp("bad.csv", b"1000,3,4,2\n");
+    assert_eq!(
+        read_events_csv(&path).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn reads_nmnist_events() {
+    // x=5, y=6, ON, t=0x000102 us; x=1, y=2, OFF, t=0x7FFFFF us
+    let path = write_temp(
+        "nmnist.bin",
+        &[5, 6, 0x80, 0x01, 0x02, 1, 2, 0x7F, 0xFF, 0xFF],
+    );
+    let events = read_nmnist_bin(&path).unwrap();
+    assert_eq!(
+        events,
+        vec![event(258.0, 5, 6, true), event(8_388_607.0, 1, 2, false)]
+    );
+
+    let path = write_temp("nmnist_short.bin", &[5, 6, 0x80, 0x01]);
+    assert_eq!(
+        read_nmnist_bin(&path).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn reads_aedat_polarity_packets() {
+    let mut bytes = AEDAT_HEADER.to_vec();
+    // A packet of another type is skipped
+    bytes.extend(packet_header(2, 4, 0, 1));
+    bytes.extend([0u8; 4]);
+    bytes.extend(packet_header(1, 8, 1, 3));
+    bytes.extend(polarity_event(10, 20, true, true, 500));
+    bytes.extend(polarity_event(11, 21, false, false, 600));
+    bytes.extend(polarity_event(12, 22, false, true, 700));
+    let path = write_temp("events.aedat", &bytes);
+
+    let overflow = (1i64 << 31) as f64;
+    assert_eq!(
+        read_aedat31(&path).unwrap(),
+        vec![
+            event(overflow + 500.0, 10, 20, true),
+            event(overflow + 700.0, 12, 22, false),
+        ]
+    );
+}
+
+#[test]
+fn rejects_aedat_packets_larger_than_the_file() {
+    let mut bytes = AEDAT_HEADER.to_vec();
+    bytes.extend(packet_header(1, i32::MAX, 0, i32::MAX));
+    let path = write_temp("huge.aedat", &bytes);
+    assert_eq!(
+        read_aedat31(&path).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+
+    let mut bytes = AEDAT_HEADER.to_vec();
+    bytes.extend(packet_header(1, 8, 0, 2));
+    bytes.extend(polarity_event(1, 1, true, true, 1));
+    let path = write_temp("truncated.aedat", &bytes);
+    assert_eq!(
+        read_aedat31(&path).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn rejects_files_without_aedat_header() {
+    let path = write_temp("plain.aedat", b"not an aedat file\n");
+    assert_eq!(
+        read_aedat31(&path).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
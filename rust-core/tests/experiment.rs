@@ -0,0 +1,111 @@
+mod common;
+
+use common::{random_network, temp_path};
+use neuromorphic_core::experiment::{run_metrics, Experiment, ExperimentReport, Metrics};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::units::Milliseconds;
+
+fn metrics(pairs: &[(&str, f64)]) -> Metrics {
+    pairs.iter().map(|&(k, v)| (k.to_string(), v)).collect()
+}
+
+/// Metrics of a random network built from `seed`.
+fn trial(seed: u64) -> Metrics {
+    let config = SimulationConfig {
+        t_max: Milliseconds(50.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(10, config, seed);
+    let (spikes, _) = sim.run(|i, t| common::drive(i, t) + 0.5);
+    let mut metrics = run_metrics(&sim, &spikes);
+    metrics.insert("seed".to_string(), seed as f64);
+    metrics
+}
+
+#[test]
+fn trials_run_once_per_seed_in_order() {
+    let mut experiment = Experiment::new("random", 5);
+    experiment.base_seed = 10;
+    assert_eq!(experiment.seeds(), [10, 11, 12, 13, 14]);
+    let serial = experiment.run(trial);
+    let seeds: Vec<f64> = serial.trials.iter().map(|m| m["seed"]).collect();
+    assert_eq!(seeds, [10.0, 11.0, 12.0, 13.0, 14.0]);
+    assert_eq!(serial.seeds, experiment.seeds());
+
+    // Parallel trials are the same trials in the same order
+    experiment.parallel = true;
+    let parallel = experiment.run(trial);
+    assert_eq!(parallel.trials, serial.trials);
+
+    let rate = serial.metric("mean_rate_hz").unwrap();
+    assert_eq!(rate.count, 5);
+    assert!(rate.mean > 0.0 && rate.min <= rate.mean && rate.mean <= rate.max);
+    // Trials of different seeds differ in their random weights
+    assert!(serial.metric("mean_weight").unwrap().std > 0.0);
+}
+
+#[test]
+fn run_metrics_describe_spikes_and_weights() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(100.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(8, config, 3);
+    let (spikes, _) = sim.run(|i, t| common::drive(i, t) + 0.5);
+    let metrics = run_metrics(&sim, &spikes);
+    assert!(!spikes.is_empty());
+    assert_eq!(metrics["total_spikes"], spikes.len() as f64);
+    let rate = spikes.len() as f64 / 8.0 / sim.time().as_secs();
+    assert!((metrics["mean_rate_hz"] - rate).abs() < 1e-9);
+    let weights: Vec<f64> = sim.synapses().iter().map(|s| s.weight).collect();
+    let mean = weights.iter().sum::<f64>() / weights.len() as f64;
+    assert!((metrics["mean_weight"] - mean).abs() < 1e-12);
+
+    // Before any step there is no rate
+    let fresh = random_network(8, SimulationConfig::default(), 3);
+    assert_eq!(run_metrics(&fresh, &[])["mean_rate_hz"], 0.0);
+}
+
+#[test]
+fn summaries_use_the_sample_deviation() {
+    let report = ExperimentReport::new(
+        "summary",
+        vec![1, 2, 3],
+        vec![
+            metrics(&[("a", 1.0), ("b", 5.0)]),
+            metrics(&[("a", 2.0)]),
+            metrics(&[("a", 3.0), ("b", 7.0)]),
+        ],
+    );
+    let names: Vec<&str> = report.summary.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["a", "b"]);
+    let a = report.metric("a").unwrap();
+    assert_eq!((a.count, a.mean, a.min, a.max), (3, 2.0, 1.0, 3.0));
+    assert!((a.std - 1.0).abs() < 1e-12);
+    let b = report.metric("b").unwrap();
+    assert_eq!((b.count, b.mean, b.min, b.max), (2, 6.0, 5.0, 7.0));
+    assert!((b.std - 2f64.sqrt()).abs() < 1e-12);
+    assert!(report.metric("c").is_none());
+
+    let single = ExperimentReport::new("one", vec![0], vec![metrics(&[("a", 4.0)])]);
+    assert_eq!(single.metric("a").unwrap().std, 0.0);
+
+    let table = report.to_string();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines[0], "summary (3 trials)");
+    assert_eq!(
+        lines[1],
+        "metric          mean           std           min           max"
+    );
+    assert_eq!(
+        lines[2],
+        "a             2.0000        1.0000        1.0000        3.0000"
+    );
+
+    // Metrics a trial did not report are left empty
+    let path = temp_path("experiment.csv");
+    report.write_csv(&path).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(csv, "seed,a,b\n1,1,5\n2,2,\n3,3,7\n");
+    std::fs::remove_file(&path).unwrap();
+}
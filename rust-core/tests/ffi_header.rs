@@ -0,0 +1,144 @@
+//! Checks `include/neuromorphic_core.h` against the declarations in
+//! `src/ffi.rs`: every exported function must be declared with matching
+//! parameter and return types, and `NcConfig` must have the same fields.
+
+use std::fs;
+use std::path::Path;
+
+/// `(name, return type, parameter types)` in the normalized C spelling.
+type Signature = (String, String, Vec<String>);
+
+fn read(path: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+/// C spelling of a Rust FFI type, without spaces around `*`.
+fn c_type(rust: &str) -> String {
+    let rust = rust.trim();
+    if let Some(inner) = rust.strip_prefix("*const ") {
+        return format!("const {}*", c_type(inner));
+    }
+    if let Some(inner) = rust.strip_prefix("*mut ") {
+        return format!("{}*", c_type(inner));
+    }
+    match rust {
+        "" => "void",
+        "usize" => "size_t",
+        "f64" => "double",
+        "i32" => "int32_t",
+        "u8" => "uint8_t",
+        "u32" => "uint32_t",
+        "c_char" => "char",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Collapse whitespace and drop spaces around `*`.
+fn normalize(c: &str) -> String {
+    c.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" *", "*")
+        .replace("* ", "*")
+}
+
+/// Split a C declarator such as `const double *currents` into its type.
+fn c_param_type(param: &str) -> String {
+    let param = normalize(param);
+    let end = param
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |k| k + 1);
+    param[..end].trim().to_string()
+}
+
+fn rust_functions(source: &str) -> Vec<Signature> {
+    let mut functions = Vec::new();
+    for (start, _) in source.match_indices("extern \"C\" fn ") {
+        let rest = &source[start + "extern \"C\" fn ".len()..];
+        let open = rest.find('(').unwrap();
+        let close = rest.find(')').unwrap();
+        let body = rest.find('{').unwrap();
+        let params = rest[open + 1..close]
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| c_type(p.split_once(':').unwrap().1))
+            .collect();
+        let ret = rest[close + 1..body].trim().trim_start_matches("->");
+        functions.push((rest[..open].to_string(), c_type(ret), params));
+    }
+    functions.sort();
+    functions
+}
+
+fn header_functions(header: &str) -> Vec<Signature> {
+    let without_comments: String = header
+        .split("/*")
+        .map(|part| part.split_once("*/").map_or(part, |(_, after)| after))
+        .collect();
+    let mut functions = Vec::new();
+    for statement in without_comments.split(';') {
+        let statement = statement.trim_start_matches(|c: char| c == '}' || c.is_whitespace());
+        let Some(open) = statement.find('(') else {
+            continue;
+        };
+        if statement.starts_with('#') || statement.contains('{') {
+            continue;
+        }
+        let close = statement.rfind(')').unwrap();
+        let head = normalize(&statement[..open]);
+        let name_at = head.rfind(['*', ' ']).map_or(0, |k| k + 1);
+        let params = match statement[open + 1..close].trim() {
+            "void" => Vec::new(),
+            list => list.split(',').map(c_param_type).collect(),
+        };
+        functions.push((
+            head[name_at..].to_string(),
+            head[..name_at].trim().to_string(),
+            params,
+        ));
+    }
+    functions.sort();
+    functions
+}
+
+/// `(field, type)` pairs of a Rust (`separator` `:`) or C struct body.
+fn fields(body: &str, separator: char) -> Vec<(String, String)> {
+    body.lines()
+        .map(|line| line.split("//").next().unwrap().split("/*").next().unwrap())
+        .filter_map(|line| {
+            let line = line.trim().trim_end_matches([',', ';']);
+            if separator == ':' {
+                let (name, ty) = line.strip_prefix("pub ")?.split_once(':')?;
+                Some((name.trim().to_string(), c_type(ty)))
+            } else {
+                let (ty, name) = line.rsplit_once(' ')?;
+                Some((name.to_string(), normalize(ty)))
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn header_declares_every_exported_function() {
+    let rust = rust_functions(&read("src/ffi.rs"));
+    let header = header_functions(&read("include/neuromorphic_core.h"));
+    assert!(rust.len() >= 14, "found only {} exports", rust.len());
+    assert_eq!(rust, header);
+}
+
+#[test]
+fn header_config_matches_the_rust_struct() {
+    let rust = read("src/ffi.rs");
+    let start = rust.find("pub struct NcConfig {").unwrap();
+    let body = &rust[start..start + rust[start..].find('}').unwrap()];
+    let rust_fields = fields(body, ':');
+
+    let header = read("include/neuromorphic_core.h");
+    let start = header.find("typedef struct NcConfig {").unwrap();
+    let body = &header[start..start + header[start..].find('}').unwrap()];
+    let header_fields = fields(&body[body.find('{').unwrap() + 1..], ' ');
+
+    assert_eq!(rust_fields.len(), 13);
+    assert_eq!(rust_fields, header_fields);
+}
@@ -0,0 +1,556 @@
+#![cfg(feature = "grpc")]
+
+//! Drives `ControlServer` with a minimal HTTP/2 client whose header blocks
+//! are the request examples of RFC 7541 Appendix C.4 and whose messages are
+//! hand-encoded protobuf.
+
+mod common;
+
+use common::spike_bits;
+use neuromorphic_core::config::ExperimentConfig;
+use neuromorphic_core::grpc::{ControlServer, MAX_CONCURRENT_STREAMS};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+const CONFIG: &str = r#"
+[neuron]
+tau_m_ms = 20.0
+v_rest = 0.0
+v_thresh = 1.0
+v_reset = 0.0
+
+[stdp]
+a_plus = 0.01
+a_minus = 0.012
+tau_plus_ms = 20.0
+tau_minus_ms = 20.0
+w_min = 0.0
+w_max = 1.0
+
+[[population]]
+name = "a"
+size = 5
+
+[[projection]]
+pre = "a"
+post = "a"
+weight = 0.2
+plastic = true
+
+[[stimulus]]
+kind = "current"
+population = "a"
+amplitude = 3.0
+"#;
+
+/// HPACK Huffman codes (RFC 7541 Appendix B) of the characters used here,
+/// as `(character, code, bits)`.
+const HUFFMAN: &[(u8, u32, u32)] = &[
+    (b'0', 0x0, 5),
+    (b'1', 0x1, 5),
+    (b'2', 0x2, 5),
+    (b'a', 0x3, 5),
+    (b'c', 0x4, 5),
+    (b'e', 0x5, 5),
+    (b'i', 0x6, 5),
+    (b'o', 0x7, 5),
+    (b's', 0x8, 5),
+    (b't', 0x9, 5),
+    (b' ', 0x14, 6),
+    (b'-', 0x16, 6),
+    (b'.', 0x17, 6),
+    (b'/', 0x18, 6),
+    (b'3', 0x19, 6),
+    (b'f', 0x25, 6),
+    (b'g', 0x26, 6),
+    (b'h', 0x27, 6),
+    (b'l', 0x28, 6),
+    (b'm', 0x29, 6),
+    (b'n', 0x2a, 6),
+    (b'p', 0x2b, 6),
+    (b'r', 0x2c, 6),
+    (b'u', 0x2d, 6),
+    (b':', 0x5c, 7),
+    (b'C', 0x5e, 7),
+    (b'G', 0x62, 7),
+    (b'M', 0x68, 7),
+    (b'O', 0x6a, 7),
+    (b'S', 0x6e, 7),
+    (b'T', 0x6f, 7),
+    (b'k', 0x75, 7),
+    (b'v', 0x77, 7),
+    (b'w', 0x78, 7),
+    (b'x', 0x79, 7),
+    (b'y', 0x7a, 7),
+    (b',', 0xfa, 8),
+];
+
+fn huffman_encode(text: &str) -> Vec<u8> {
+    let (mut out, mut acc, mut bits) = (Vec::new(), 0u64, 0u32);
+    for byte in text.bytes() {
+        let &(_, code, len) = HUFFMAN.iter().find(|(c, _, _)| *c == byte).unwrap();
+        acc = acc << len | u64::from(code);
+        bits += len;
+        while bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        // Pad with the most significant bits of EOS, which are all ones
+        out.push((acc << (8 - bits)) as u8 | (0xff >> bits));
+    }
+    out
+}
+
+fn hex(text: &str) -> Vec<u8> {
+    let digits: Vec<u8> = text.bytes().filter(u8::is_ascii_hexdigit).collect();
+    digits
+        .chunks(2)
+        .map(|d| u8::from_str_radix(std::str::from_utf8(d).unwrap(), 16).unwrap())
+        .collect()
+}
+
+/// Response of one call: headers and trailers, and the message if any.
+#[derive(Debug, Default)]
+struct Response {
+    headers: Vec<(String, String)>,
+    message: Option<Vec<u8>>,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    next_stream: u32,
+}
+
+impl Client {
+    fn connect(address: SocketAddr) -> Self {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            .unwrap();
+        let mut client = Self {
+            stream,
+            next_stream: 1,
+        };
+        client.frame(0x4, 0, 0, &[]);
+        // The server's preface is a SETTINGS frame that only limits the
+        // number of concurrent streams
+        let (kind, stream, payload) = client.read_frame().unwrap();
+        assert_eq!((kind, stream), (0x4, 0));
+        let mut expected = vec![0, 0x3];
+        expected.extend_from_slice(&MAX_CONCURRENT_STREAMS.to_be_bytes());
+        assert_eq!(payload, expected);
+        client
+    }
+
+    /// Read one frame as `(type, stream, payload)`, or `None` once the
+    /// server has closed the connection.
+    fn read_frame(&mut self) -> Option<(u8, u32, Vec<u8>)> {
+        let mut header = [0; 9];
+        match self.stream.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset
+                ) =>
+            {
+                return None
+            }
+            Err(e) => panic!("{e}"),
+        }
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let stream = u32::from_be_bytes(header[5..].try_into().unwrap());
+        let mut payload = vec![0; len];
+        self.stream.read_exact(&mut payload).unwrap();
+        Some((header[3], stream, payload))
+    }
+
+    /// Assert that the server closes the connection, skipping the frames it
+    /// sends before that.
+    fn assert_closed(&mut self) {
+        while let Some((kind, _, _)) = self.read_frame() {
+            assert_ne!(kind, 0x1, "the server answered a request");
+        }
+    }
+
+    fn frame(&mut self, kind: u8, flags: u8, stream: u32, payload: &[u8]) {
+        let len = (payload.len() as u32).to_be_bytes();
+        let mut frame = len[1..].to_vec();
+        frame.extend_from_slice(&[kind, flags]);
+        frame.extend_from_slice(&stream.to_be_bytes());
+        frame.extend_from_slice(payload);
+        self.stream.write_all(&frame).unwrap();
+    }
+
+    /// Send a request with header block `headers` and, unless `None`, one
+    /// length-prefixed message, then read frames until the stream ends.
+    fn call(&mut self, headers: &[u8], message: Option<&[u8]>) -> Response {
+        let id = self.next_stream;
+        self.next_stream += 2;
+        // END_HEADERS, plus END_STREAM when there is no body
+        let flags = if message.is_some() { 0x4 } else { 0x5 };
+        self.frame(0x1, flags, id, headers);
+        if let Some(message) = message {
+            let mut body = vec![0];
+            body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            body.extend_from_slice(message);
+            self.frame(0x0, 0x1, id, &body);
+        }
+
+        self.response(id)
+    }
+
+    /// Read frames until stream `id` ends, collecting its response.
+    fn response(&mut self, id: u32) -> Response {
+        let mut response = Response::default();
+        let mut data = Vec::new();
+        loop {
+            let mut header = [0; 9];
+            self.stream.read_exact(&mut header).unwrap();
+            let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+            let (kind, flags) = (header[3], header[4]);
+            let stream = u32::from_be_bytes(header[5..].try_into().unwrap());
+            let mut payload = vec![0; len];
+            self.stream.read_exact(&mut payload).unwrap();
+            if stream != id {
+                continue;
+            }
+            match kind {
+                0x0 => data.extend_from_slice(&payload),
+                0x1 => response.headers.extend(decode_response_headers(&payload)),
+                _ => {}
+            }
+            if kind == 0x1 && flags & 0x1 != 0 {
+                break;
+            }
+        }
+        if !data.is_empty() {
+            assert_eq!(data[0], 0, "uncompressed");
+            let len = u32::from_be_bytes(data[1..5].try_into().unwrap()) as usize;
+            assert_eq!(data.len(), 5 + len);
+            response.message = Some(data[5..].to_vec());
+        }
+        response
+    }
+}
+
+/// Decode the two representations the server emits: `:status 200` from the
+/// static table and literals without indexing with short raw strings.
+fn decode_response_headers(block: &[u8]) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    let mut pos = 0;
+    while pos < block.len() {
+        if block[pos] == 0x88 {
+            headers.push((":status".to_string(), "200".to_string()));
+            pos += 1;
+            continue;
+        }
+        assert_eq!(block[pos], 0);
+        pos += 1;
+        let mut strings = Vec::new();
+        for _ in 0..2 {
+            let len = block[pos] as usize;
+            assert!(len < 0x7f, "long or Huffman-coded string");
+            strings.push(String::from_utf8(block[pos + 1..pos + 1 + len].to_vec()).unwrap());
+            pos += 1 + len;
+        }
+        let value = strings.pop().unwrap();
+        headers.push((strings.pop().unwrap(), value));
+    }
+    headers
+}
+
+/// Request headers for `method`, with the path as a Huffman-coded literal
+/// added to the dynamic table.
+fn request_headers(method: &str) -> Vec<u8> {
+    // :method POST, :scheme http
+    let mut block = vec![0x83, 0x86];
+    let path = huffman_encode(&format!("/neuromorphic.Control/{method}"));
+    // Literal with incremental indexing, name :path (static index 4)
+    block.push(0x44);
+    assert!(path.len() < 0x7f);
+    block.push(0x80 | path.len() as u8);
+    block.extend_from_slice(&path);
+    block
+}
+
+/// Decode a protobuf message into `(field, wire type, value bytes)`.
+fn proto_fields(message: &[u8]) -> Vec<(u64, u64, Vec<u8>)> {
+    let varint = |pos: &mut usize| {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let b = message[*pos];
+            *pos += 1;
+            value |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    };
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < message.len() {
+        let key = varint(&mut pos);
+        let value = match key & 7 {
+            0 => varint(&mut pos).to_le_bytes().to_vec(),
+            1 => {
+                pos += 8;
+                message[pos - 8..pos].to_vec()
+            }
+            2 => {
+                let len = varint(&mut pos) as usize;
+                pos += len;
+                message[pos - len..pos].to_vec()
+            }
+            other => panic!("unexpected wire type {other}"),
+        };
+        fields.push((key >> 3, key & 7, value));
+    }
+    fields
+}
+
+fn unpack_varints(mut bytes: &[u8]) -> Vec<u64> {
+    let mut values = Vec::new();
+    while !bytes.is_empty() {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let b = bytes[0];
+            bytes = &bytes[1..];
+            value |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                break;
+            }
+            shift += 7;
+        }
+        values.push(value);
+    }
+    values
+}
+
+/// Start a server on a free port, returning its address.
+fn start_server() -> SocketAddr {
+    let server = ControlServer::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.serve());
+    address
+}
+
+#[test]
+fn test_encoder_reproduces_rfc_7541_huffman_examples() {
+    // Appendix C.4 and C.6
+    let cases = [
+        ("www.example.com", "f1e3 c2e5 f23a 6ba0 ab90 f4ff"),
+        ("no-cache", "a8eb 1064 9cbf"),
+        ("custom-key", "25a8 49e9 5ba9 7d7f"),
+        ("custom-value", "25a8 49e9 5bb8 e8b4 bf"),
+        ("302", "6402"),
+        ("private", "aec3 771a 4b"),
+        (
+            "Mon, 21 Oct 2013 20:13:21 GMT",
+            "d07a be94 1054 d444 a820 0595 040b 8166 e082 a62d 1bff",
+        ),
+        (
+            "https://www.example.com",
+            "9d29 ad17 1863 c78f 0b97 c8e9 ae82 ae43 d3",
+        ),
+    ];
+    for (text, encoded) in cases {
+        assert_eq!(huffman_encode(text), hex(encoded), "{text}");
+    }
+}
+
+#[test]
+fn server_decodes_rfc_7541_request_examples() {
+    let mut client = Client::connect(start_server());
+
+    // C.4.1 to C.4.3: Huffman-coded literals, and indices into the dynamic
+    // table those literals fill
+    let blocks = [
+        ("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff", "/"),
+        ("8286 84be 5886 a8eb 1064 9cbf", "/"),
+        (
+            "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+            "/index.html",
+        ),
+    ];
+    for (block, path) in blocks {
+        let response = client.call(&hex(block), None);
+        assert_eq!(response.header(":status"), Some("200"));
+        assert_eq!(response.header("grpc-status"), Some("12"));
+        let expected = format!("Unknown path {path}");
+        assert_eq!(response.header("grpc-message"), Some(expected.as_str()));
+    }
+}
+
+#[test]
+fn step_matches_a_local_simulation() {
+    let mut client = Client::connect(start_server());
+
+    // ConfigureRequest { config_toml = 1 }
+    let mut configure = vec![0x0a];
+    let mut len = CONFIG.len();
+    while len >= 0x80 {
+        configure.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    configure.push(len as u8);
+    configure.extend_from_slice(CONFIG.as_bytes());
+    let response = client.call(&request_headers("Configure"), Some(&configure));
+    assert_eq!(response.header("grpc-status"), Some("0"));
+    let status = proto_fields(&response.message.unwrap());
+    // Status { num_neurons = 5, num_synapses = 20, t_max_ms = 100.0 }, with
+    // the zero time_ms omitted
+    assert_eq!(status[0], (1, 0, 5u64.to_le_bytes().to_vec()));
+    assert_eq!(status[1], (2, 0, 20u64.to_le_bytes().to_vec()));
+    assert_eq!(status[2], (4, 1, 100.0f64.to_le_bytes().to_vec()));
+
+    let config = ExperimentConfig::from_toml(CONFIG).unwrap();
+    let (mut sim, input) = (config.build(), config.input_current());
+    let step = request_headers("Step");
+    // The second Step names its path by dynamic table index 62, the entry
+    // the first one added
+    for headers in [step, vec![0x83, 0x86, 0xbe]] {
+        // StepRequest { steps = 150 }, the varint example of the protobuf
+        // encoding guide
+        let response = client.call(&headers, Some(&[0x08, 0x96, 0x01]));
+        assert_eq!(response.header("grpc-status"), Some("0"));
+        let mut expected = Vec::new();
+        for _ in 0..150 {
+            expected.extend(sim.step(&input));
+        }
+        assert!(!expected.is_empty());
+
+        let mut reply = (0.0, Vec::new(), Vec::new());
+        for (field, wire, value) in proto_fields(&response.message.unwrap()) {
+            match (field, wire) {
+                (1, 1) => reply.0 = f64::from_le_bytes(value.try_into().unwrap()),
+                (2, 2) => reply.1 = unpack_varints(&value),
+                (3, 2) => {
+                    reply.2 = value
+                        .chunks(8)
+                        .map(|v| f64::from_le_bytes(v.try_into().unwrap()))
+                        .collect()
+                }
+                other => panic!("unexpected field {other:?}"),
+            }
+        }
+        assert_eq!(reply.0.to_bits(), sim.time().0.to_bits());
+        let received: Vec<Spike> = reply
+            .1
+            .iter()
+            .zip(&reply.2)
+            .map(|(&n, &t)| Spike::new(n as usize, Milliseconds(t)))
+            .collect();
+        assert_eq!(spike_bits(&received), spike_bits(&expected));
+    }
+}
+
+#[test]
+fn streams_beyond_the_advertised_limit_are_refused() {
+    let mut client = Client::connect(start_server());
+    let headers = request_headers("Step");
+
+    // Open as many requests as allowed, each waiting for its body
+    let open: Vec<u32> = (0..MAX_CONCURRENT_STREAMS).map(|k| 2 * k + 1).collect();
+    for &id in &open {
+        client.frame(0x1, 0x4, id, &headers);
+    }
+    // The next one is reset with REFUSED_STREAM
+    let refused = 2 * MAX_CONCURRENT_STREAMS + 1;
+    client.frame(0x1, 0x4, refused, &[0x83, 0x86, 0xbe]);
+    loop {
+        let (kind, stream, payload) = client.read_frame().unwrap();
+        assert_ne!(stream, open[0], "a waiting request was answered");
+        if kind == 0x3 {
+            assert_eq!(stream, refused);
+            assert_eq!(payload, 7u32.to_be_bytes());
+            break;
+        }
+    }
+
+    // The open requests are still served, and completing one frees a slot.
+    // Without a simulation, Step fails its precondition.
+    client.frame(0x0, 0x1, open[0], &[0, 0, 0, 0, 0]);
+    let response = client.response(open[0]);
+    assert_eq!(response.header("grpc-status"), Some("9"));
+    client.next_stream = refused + 2;
+    let response = client.call(&[0x83, 0x86, 0xbe], Some(&[]));
+    assert_eq!(response.header("grpc-status"), Some("9"));
+}
+
+#[test]
+fn protocol_errors_close_the_connection() {
+    let address = start_server();
+
+    // Not an HTTP/2 preface
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut rest = Vec::new();
+    let closed = stream.read_to_end(&mut rest);
+    assert!(closed.is_err() || rest.is_empty(), "{rest:?}");
+
+    let malformed: [(u8, u8, Vec<u8>); 4] = [
+        // CONTINUATION without a header block to continue
+        (0x9, 0x4, vec![0x83]),
+        // PADDED HEADERS whose padding is longer than the frame
+        (0x1, 0xc, vec![9, 0x83, 0x86]),
+        // PADDED DATA without a padding length
+        (0x0, 0x8, Vec::new()),
+        // HEADERS without END_HEADERS, followed by another HEADERS
+        (0x1, 0x0, vec![0x83]),
+    ];
+    for (kind, flags, payload) in malformed {
+        let mut client = Client::connect(address);
+        client.frame(kind, flags, 1, &payload);
+        client.frame(0x1, 0x5, 3, &request_headers("Step"));
+        client.assert_closed();
+    }
+
+    // A frame larger than SETTINGS_MAX_FRAME_SIZE, announced but not sent
+    let mut client = Client::connect(address);
+    client
+        .stream
+        .write_all(&[0x00, 0x40, 0x01, 0x0, 0x1, 0, 0, 0, 1])
+        .unwrap();
+    client.assert_closed();
+
+    // The server keeps accepting connections
+    let mut client = Client::connect(address);
+    let response = client.call(&request_headers("Step"), Some(&[]));
+    assert_eq!(response.header("grpc-status"), Some("9"));
+}
+
+#[test]
+fn malformed_messages_are_invalid_arguments() {
+    let mut client = Client::connect(start_server());
+    let cases: [(&[u8], &str); 3] = [
+        // Field 1 announced as a varint, then truncated
+        (&[0x08, 0x96], "3"),
+        // Unknown wire type 7
+        (&[0x0f], "3"),
+        // ConfigureRequest whose configuration is not UTF-8
+        (&[0x0a, 0x02, 0xff, 0xfe], "3"),
+    ];
+    let methods = ["Step", "Step", "Configure"];
+    for ((message, status), method) in cases.into_iter().zip(methods) {
+        let response = client.call(&request_headers(method), Some(message));
+        assert_eq!(response.header("grpc-status"), Some(status), "{message:?}");
+        assert!(response.message.is_none());
+    }
+}
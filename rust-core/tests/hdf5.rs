@@ -0,0 +1,205 @@
+#![cfg(feature = "hdf5")]
+
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::hdf5::{Attribute, Hdf5File, Hdf5Writer};
+use neuromorphic_core::probe::VoltageProbe;
+use neuromorphic_core::snapshot::WeightSnapshot;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+use std::io::ErrorKind;
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn spikes() -> Vec<Spike> {
+    vec![
+        Spike::new(2, Milliseconds(0.5)),
+        Spike::new(0, Milliseconds(1.25)),
+        Spike::new(7, Milliseconds(3.0)),
+    ]
+}
+
+#[test]
+fn superblock_and_root_group_follow_the_format_spec() {
+    let path = temp_path("layout.h5");
+    let mut writer = Hdf5Writer::create(&path).unwrap();
+    writer.write_spikes(&spikes()).unwrap();
+    writer.finish().unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+
+    // Signature, then superblock, free-space, root entry, reserved and
+    // shared header versions, 8-byte offsets and lengths
+    assert_eq!(&bytes[..8], b"\x89HDF\r\n\x1a\n");
+    assert_eq!(bytes[8..16], [0, 0, 0, 0, 0, 8, 8, 0]);
+    // Base address zero, no free-space info, end of file, no driver info
+    assert_eq!(u64_at(&bytes, 24), 0);
+    assert_eq!(u64_at(&bytes, 32), u64::MAX);
+    assert_eq!(u64_at(&bytes, 40), bytes.len() as u64);
+    assert_eq!(u64_at(&bytes, 48), u64::MAX);
+
+    // Root symbol table entry caching its B-tree and local heap addresses
+    let header = u64_at(&bytes, 64) as usize;
+    // Cache type 1: the scratch pad holds the addresses
+    assert_eq!(bytes[72..76], [1, 0, 0, 0]);
+    let (btree, heap) = (u64_at(&bytes, 80) as usize, u64_at(&bytes, 88) as usize);
+    assert_eq!(&bytes[btree..btree + 4], b"TREE");
+    assert_eq!(&bytes[heap..heap + 4], b"HEAP");
+
+    // Version 1 object header whose symbol table message agrees
+    assert_eq!(bytes[header], 1);
+    let messages = u16_at(&bytes, header + 2) as usize;
+    let mut at = header + 16;
+    let mut found = false;
+    for _ in 0..messages {
+        let (kind, size) = (u16_at(&bytes, at), u16_at(&bytes, at + 2) as usize);
+        if kind == 0x11 {
+            assert_eq!(u64_at(&bytes, at + 8) as usize, btree);
+            assert_eq!(u64_at(&bytes, at + 16) as usize, heap);
+            found = true;
+        }
+        at += 8 + size;
+    }
+    assert!(found, "root group has no symbol table message");
+}
+
+#[test]
+fn recordings_round_trip() {
+    let probe = VoltageProbe::new(vec![4, 9], Milliseconds(1.0));
+    let snapshots = vec![
+        WeightSnapshot {
+            time: Milliseconds(0.0),
+            num_neurons: 3,
+            synapses: vec![(0, 1, 0.25), (1, 2, 0.5)],
+        },
+        WeightSnapshot {
+            time: Milliseconds(10.0),
+            num_neurons: 3,
+            synapses: vec![(0, 1, 0.75)],
+        },
+    ];
+    let path = temp_path("recording.h5");
+    let mut writer = Hdf5Writer::create(&path).unwrap();
+    writer.write_spikes(&spikes()).unwrap();
+    writer.write_voltages(&probe).unwrap();
+    writer.write_snapshots(&snapshots).unwrap();
+    writer.finish().unwrap();
+
+    let file = Hdf5File::open(&path).unwrap();
+    assert_eq!(
+        file.members("/").unwrap(),
+        ["spikes", "voltages", "weights"]
+    );
+    assert_eq!(file.read_ints("/spikes/neuron_id").unwrap(), [2, 0, 7]);
+    assert_eq!(
+        file.read_floats("/spikes/time_ms").unwrap(),
+        [0.5, 1.25, 3.0]
+    );
+    assert_eq!(
+        file.attribute("/spikes/time_ms", "units").unwrap(),
+        Some(Attribute::Text("ms".to_string()))
+    );
+    assert_eq!(file.read_ints("/voltages/neuron_id").unwrap(), [4, 9]);
+    assert_eq!(file.shape("/voltages/v_mem").unwrap(), [0, 2]);
+    assert_eq!(file.read_ints("/weights/index").unwrap(), [0, 2, 3]);
+    assert_eq!(file.read_ints("/weights/pre_neuron").unwrap(), [0, 1, 0]);
+    assert_eq!(
+        file.read_floats("/weights/weight").unwrap(),
+        [0.25, 0.5, 0.75]
+    );
+}
+
+#[test]
+fn groups_strings_matrices_and_attributes_round_trip() {
+    let path = temp_path("general.h5");
+    let mut writer = Hdf5Writer::create(&path).unwrap();
+    writer
+        .create_group(
+            "/meta/run",
+            &[
+                ("seed", Attribute::Int(42)),
+                ("dt", Attribute::Float(0.1)),
+                ("tags", Attribute::Texts(vec!["a".into(), "bé".into()])),
+                ("sizes", Attribute::Ints(vec![3, 4])),
+            ],
+        )
+        .unwrap();
+    writer
+        .write_matrix("/meta/m", 3, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[])
+        .unwrap();
+    writer
+        .write_strings("/meta/names", ["input", "output"], &[])
+        .unwrap();
+    writer.write_text("/meta/note", "hello", &[]).unwrap();
+    // Enough members to split the group's symbol table and B-tree nodes
+    for k in 0..200 {
+        writer
+            .write_dataset(&format!("/many/d{k:03}"), [k as u64], &[])
+            .unwrap();
+    }
+    writer
+        .create_group(
+            "/links",
+            &[("target", Attribute::Reference("/meta/m".into()))],
+        )
+        .unwrap();
+    let err = writer.write_text("/meta/note", "again", &[]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    writer.finish().unwrap();
+
+    let file = Hdf5File::open(&path).unwrap();
+    let attr = |name| file.attribute("/meta/run", name).unwrap().unwrap();
+    assert_eq!(attr("seed"), Attribute::Int(42));
+    assert_eq!(attr("dt"), Attribute::Float(0.1));
+    assert_eq!(
+        attr("tags"),
+        Attribute::Texts(vec!["a".into(), "bé".into()])
+    );
+    assert_eq!(attr("sizes"), Attribute::Ints(vec![3, 4]));
+    assert_eq!(file.attribute("/meta/run", "missing").unwrap(), None);
+    assert_eq!(file.shape("/meta/m").unwrap(), [2, 3]);
+    assert_eq!(
+        file.read_floats("/meta/m").unwrap(),
+        [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+    );
+    assert_eq!(
+        file.read_strings("/meta/names").unwrap(),
+        ["input", "output"]
+    );
+    assert_eq!(file.read_strings("/meta/note").unwrap(), ["hello"]);
+    assert!(file.shape("/meta/note").unwrap().is_empty());
+    let many = file.members("/many").unwrap();
+    assert_eq!(many.len(), 200);
+    assert_eq!(file.read_ints("/many/d137").unwrap(), [137]);
+    assert!(file.contains("/links"));
+    assert!(!file.contains("/meta/absent"));
+}
+
+#[test]
+fn reads_past_a_user_block_and_rejects_other_files() {
+    let path = temp_path("user_block.h5");
+    let mut writer = Hdf5Writer::create(&path).unwrap();
+    writer.write_spikes(&spikes()).unwrap();
+    writer.finish().unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+
+    // Addresses are relative to the superblock, so a user block in front
+    // of it moves nothing else
+    let mut shifted = vec![0u8; 512];
+    shifted.extend_from_slice(&bytes);
+    let file = Hdf5File::from_bytes(shifted).unwrap();
+    assert_eq!(file.read_ints("/spikes/neuron_id").unwrap(), [2, 0, 7]);
+
+    let err = Hdf5File::from_bytes(b"neuron_id,time_ms\n".to_vec()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = Hdf5File::from_bytes(bytes[..100].to_vec())
+        .and_then(|f| f.read_ints("/spikes/neuron_id"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::lava::{to_lava, write_lava};
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::neuron::NeuronParams;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::units::Milliseconds;
+
+/// Two excitatory neurons projecting onto an inhibitory neuron that
+/// resets below rest, and one weak feedback synapse.
+fn simulation(config: SimulationConfig) -> Simulation {
+    let mut builder = NetworkBuilder::new();
+    builder.add_population("exc", 2, common::neuron_params());
+    builder.add_population(
+        "inh",
+        1,
+        NeuronParams {
+            v_reset: -0.5,
+            ..common::neuron_params()
+        },
+    );
+    builder.connect_all_delayed([
+        (0, 2, 0.5, Milliseconds(1.0)),
+        (1, 2, -0.25, Milliseconds(0.1)),
+        (2, 0, 0.01, Milliseconds(0.0)),
+    ]);
+    Simulation::from_network(builder.build(), config, common::stdp_params())
+}
+
+#[test]
+fn populations_are_shifted_to_reset_at_zero() {
+    let lava = to_lava(&simulation(SimulationConfig::default()), "net");
+    assert!(lava.starts_with("{\n  \"name\": \"net\",\n  \"dt_ms\": 0.1,\n  \"num_steps\": 1000,"));
+
+    // The largest weight fixes the scale: 0.4975 * 2^14 fits 127 * 2^7
+    assert!(lava.contains("\"voltage_scale\": 1048576,"), "{lava}");
+    assert!(lava.contains(
+        "{\"name\": \"exc\", \"size\": 2, \"first_neuron\": 0, \"v_offset\": 0,\n     \
+         \"float\": {\"du\": 1, \"dv\": 0.005, \"bias_mant\": 0, \"vth\": 1, \"v\": [0, 0]},\n     \
+         \"fixed\": {\"du\": 4095, \"dv\": 20, \"bias_mant\": 0, \"bias_exp\": 0, \"vth\": 16384, \"v\": [0, 0]}}"
+    ));
+    // Rest above reset becomes a bias of (v_rest - v_reset) * dv
+    assert!(lava.contains(
+        "{\"name\": \"inh\", \"size\": 1, \"first_neuron\": 2, \"v_offset\": -0.5,\n     \
+         \"float\": {\"du\": 1, \"dv\": 0.005, \"bias_mant\": 0.0025, \"vth\": 1.5, \"v\": [0.5]},\n     \
+         \"fixed\": {\"du\": 4095, \"dv\": 20, \"bias_mant\": 2621, \"bias_exp\": 0, \"vth\": 24576, \"v\": [524288]}}"
+    ));
+}
+
+#[test]
+fn projections_hold_sparse_weights_and_delays() {
+    let lava = to_lava(&simulation(SimulationConfig::default()), "net");
+    // Weights are scaled by 1 - dv and delays exclude the inherent step
+    assert!(lava.contains(
+        "{\"pre\": \"exc\", \"post\": \"inh\", \"shape\": [1, 2],\n     \
+         \"pre_index\": [0, 1],\n     \"post_index\": [0, 0],\n     \"delays\": [9, 0],\n     \
+         \"float\": {\"weights\": [0.4975, -0.24875]},\n     \
+         \"fixed\": {\"weights\": [127, -64], \"weight_exp\": 6}}"
+    ));
+    // Each projection has its own exponent, so small weights keep precision
+    assert!(lava.contains(
+        "{\"pre\": \"inh\", \"post\": \"exc\", \"shape\": [2, 1],\n     \
+         \"pre_index\": [0],\n     \"post_index\": [0],\n     \"delays\": [0],\n     \
+         \"float\": {\"weights\": [0.00995]},\n     \
+         \"fixed\": {\"weights\": [82], \"weight_exp\": 1}}"
+    ));
+    assert_eq!(lava.matches("\"pre\": ").count(), 2);
+}
+
+#[test]
+fn endless_runs_have_no_step_count() {
+    let sim = simulation(SimulationConfig {
+        t_max: Milliseconds(f64::INFINITY),
+        ..SimulationConfig::default()
+    });
+    let lava = to_lava(&sim, "a \"quoted\" net");
+    assert!(lava.contains("\"name\": \"a \\\"quoted\\\" net\","));
+    assert!(lava.contains("\"num_steps\": null,"));
+
+    let path = temp_path("net.lava.json");
+    write_lava(&sim, "net", &path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        to_lava(&sim, "net")
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use neuromorphic_core::live::{MetricSnapshot, MetricsStream};
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+
+/// Snapshots of `n` unconnected neurons that each fire once per 1 ms step.
+fn snapshots(n: usize, interval: Milliseconds) -> Vec<MetricSnapshot> {
+    let mut builder = NetworkBuilder::new();
+    builder.add_neurons(n, common::neuron_params());
+    let config = SimulationConfig {
+        dt: Milliseconds(1.0),
+        t_max: Milliseconds(20.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = Simulation::from_network(builder.build(), config, common::stdp_params());
+    let spikes: Vec<Spike> = (0..20)
+        .flat_map(|t| (0..n).map(move |i| Spike::new(i, Milliseconds(t as f64))))
+        .collect();
+    sim.inject_spikes(&spikes);
+    let (stream, receiver) = MetricsStream::new(interval);
+    sim.add_monitor(stream);
+    sim.run(|_, _| 0.0);
+    drop(sim);
+    receiver.iter().collect()
+}
+
+#[test]
+fn constant_activity_reports_a_constant_rate() {
+    for interval in [0.25, 1.0, 2.5, 5.0] {
+        let snapshots = snapshots(4, Milliseconds(interval));
+        assert!(!snapshots.is_empty());
+        for s in &snapshots {
+            // Every neuron fires every millisecond: 1000 Hz
+            assert!(
+                (s.population_rate_hz - 1000.0).abs() < 1e-6,
+                "interval {interval}: {s:?}"
+            );
+        }
+        assert_eq!(snapshots.last().unwrap().total_spikes, 80);
+    }
+}
+
+#[test]
+fn snapshots_follow_the_interval() {
+    let times: Vec<f64> = snapshots(2, Milliseconds(2.5))
+        .iter()
+        .map(|s| s.time.0)
+        .collect();
+    assert_eq!(times, [3.0, 5.0, 8.0, 10.0, 13.0, 15.0, 18.0, 20.0]);
+    // Steps shorter than the interval yield one snapshot per step
+    assert_eq!(snapshots(2, Milliseconds(0.25)).len(), 20);
+}
@@ -0,0 +1,199 @@
+use neuromorphic_core::models::{
+    balanced_random, by_name, digit_bitmap, reservoir, synfire_chain, wta_digit_classifier,
+    DIGIT_HEIGHT, DIGIT_WIDTH, MODEL_NAMES,
+};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::synfire::Propagation;
+use neuromorphic_core::units::Milliseconds;
+
+fn is_sorted(spikes: &[Spike]) -> bool {
+    spikes.windows(2).all(|w| w[0].time <= w[1].time)
+}
+
+#[test]
+fn presets_are_found_by_name() {
+    for name in MODEL_NAMES {
+        let model = by_name(name, 0).unwrap();
+        assert_eq!(model.name, name);
+        assert_eq!(model.simulation.config().dt, Milliseconds(0.1));
+        assert!(model.simulation.config().synaptic_transmission);
+    }
+    assert!(by_name("hopfield", 0).is_none());
+}
+
+#[test]
+fn digits_are_five_by_seven_bitmaps() {
+    let one = digit_bitmap(1);
+    assert_eq!(one.len(), DIGIT_WIDTH * DIGIT_HEIGHT);
+    assert_eq!(one.iter().sum::<f64>(), 10.0);
+    assert_eq!(one[..DIGIT_WIDTH], [0.0, 0.0, 1.0, 0.0, 0.0]);
+    let bitmaps: Vec<Vec<f64>> = (0..10).map(digit_bitmap).collect();
+    for a in 0..10 {
+        for b in a + 1..10 {
+            assert_ne!(bitmaps[a], bitmaps[b], "digits {a} and {b}");
+        }
+    }
+}
+
+#[test]
+fn synfire_volleys_travel_down_the_chain() {
+    let mut model = synfire_chain(1);
+    let pools: Vec<_> = (0..10)
+        .map(|k| model.population(&format!("pool{k}")).unwrap())
+        .collect();
+    assert_eq!(pools[9], 900..1000);
+    assert_eq!(model.stimulus.spikes.len(), 100);
+    assert!(model
+        .stimulus
+        .spikes
+        .iter()
+        .all(|s| pools[0].contains(&s.neuron_id)));
+
+    let spikes = model.run();
+    let propagation = Propagation::measure(&spikes, &pools);
+    assert!(propagation.succeeded(90), "{propagation:?}");
+    let latency = propagation.latency(90).unwrap();
+    assert!((latency.0 - 5.0).abs() < 1.0, "{latency:?}");
+    assert!((propagation.packets[0].time.0 - 10.0).abs() < 1.0);
+}
+
+#[test]
+fn digits_are_shown_in_shuffled_epochs() {
+    let mut model = wta_digit_classifier(2);
+    let input = model.population("input").unwrap();
+    let output = model.population("output").unwrap();
+    assert_eq!((input.len(), output.len()), (35, 10));
+
+    let stimulus = &model.stimulus;
+    assert_eq!(stimulus.presentations.len(), 50);
+    for (epoch, shown) in stimulus.presentations.chunks(10).enumerate() {
+        let mut labels: Vec<usize> = shown.iter().map(|p| p.label).collect();
+        labels.sort_unstable();
+        assert_eq!(labels, (0..10).collect::<Vec<_>>(), "epoch {epoch}");
+    }
+    let last = stimulus.presentations[49];
+    assert_eq!(last.end - last.start, Milliseconds(100.0));
+    assert_eq!(
+        model.simulation.config().t_max,
+        last.end + Milliseconds(50.0)
+    );
+    assert!(is_sorted(&stimulus.spikes));
+    // Only lit pixels of the digit shown fire
+    for spike in &stimulus.spikes {
+        let shown = stimulus
+            .presentations
+            .iter()
+            .find(|p| p.start <= spike.time && spike.time < p.end)
+            .unwrap();
+        assert_eq!(
+            digit_bitmap(shown.label)[spike.neuron_id - input.start],
+            1.0
+        );
+    }
+
+    let weights: Vec<f64> = model
+        .simulation
+        .synapses()
+        .iter()
+        .map(|s| s.weight)
+        .collect();
+    let spikes = model.run();
+    assert!(spikes.iter().any(|s| output.contains(&s.neuron_id)));
+    let learned: Vec<f64> = model
+        .simulation
+        .synapses()
+        .iter()
+        .map(|s| s.weight)
+        .collect();
+    assert_ne!(learned, weights);
+    let feedforward = model
+        .simulation
+        .synapses()
+        .iter()
+        .filter(|s| input.contains(&s.pre_neuron));
+    assert!(feedforward
+        .into_iter()
+        .all(|s| (0.0..=0.3).contains(&s.weight)));
+}
+
+#[test]
+fn reservoir_segments_are_labeled_by_the_strongest_input() {
+    let model = reservoir(3);
+    let stimulus = &model.stimulus;
+    assert_eq!(stimulus.presentations.len(), 20);
+    assert!(is_sorted(&stimulus.spikes));
+    let inputs = model.population("input").unwrap();
+    assert_eq!(inputs.len(), 4);
+    // The label is the input with the highest rate, which mostly fires
+    // most; chance would agree in 5 of 20 segments
+    let mut agree = 0;
+    for p in &stimulus.presentations {
+        let mut counts = [0; 4];
+        for s in stimulus
+            .spikes
+            .iter()
+            .filter(|s| p.start <= s.time && s.time < p.end)
+        {
+            counts[s.neuron_id - inputs.start] += 1;
+        }
+        let most = (0..4).max_by_key(|&k| counts[k]).unwrap();
+        agree += usize::from(most == p.label);
+    }
+    assert!(agree >= 10, "{agree} of 20 segments");
+}
+
+#[test]
+fn balanced_networks_fire_asynchronously() {
+    let mut model = balanced_random(4);
+    assert_eq!(model.stimulus.currents.len(), 1000);
+    assert!(model
+        .stimulus
+        .currents
+        .iter()
+        .all(|c| (1.0..1.3).contains(c)));
+    assert_eq!(model.stimulus.current(1000), 0.0);
+
+    // The first 300 ms, without the transient of the first 100 ms
+    let stimulus = model.stimulus.clone();
+    let sim = &mut model.simulation;
+    let mut spikes = Vec::new();
+    while sim.time() < Milliseconds(300.0) {
+        spikes.extend(sim.step(|i, _| stimulus.current(i)));
+    }
+    spikes.retain(|s| s.time >= Milliseconds(100.0));
+    let rate = spikes.len() as f64 / 1000.0 / 0.2;
+    assert!(rate > 5.0 && rate < 100.0, "mean rate {rate} Hz");
+
+    // Firing is irregular, with interspike intervals varying about as
+    // much as a Poisson process's
+    let mut trains = vec![Vec::new(); 1000];
+    for spike in &spikes {
+        trains[spike.neuron_id].push(spike.time.0);
+    }
+    let cvs: Vec<f64> = trains
+        .iter()
+        .filter(|t| t.len() > 3)
+        .map(|t| {
+            let isi: Vec<f64> = t.windows(2).map(|w| w[1] - w[0]).collect();
+            let mean = isi.iter().sum::<f64>() / isi.len() as f64;
+            let var = isi.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / isi.len() as f64;
+            var.sqrt() / mean
+        })
+        .collect();
+    let cv = cvs.iter().sum::<f64>() / cvs.len() as f64;
+    assert!(
+        cvs.len() > 500 && cv > 0.8,
+        "mean CV {cv} of {} neurons",
+        cvs.len()
+    );
+
+    // Population bursts occur, but never recruit most of the network
+    let mut per_step = std::collections::HashMap::new();
+    for spike in &spikes {
+        *per_step
+            .entry((spike.time.0 * 10.0).round() as i64)
+            .or_insert(0) += 1;
+    }
+    let peak = per_step.values().copied().max().unwrap();
+    assert!(peak < 300, "{peak} spikes in one step");
+}
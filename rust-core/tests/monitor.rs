@@ -0,0 +1,133 @@
+mod common;
+
+use common::neuron_params;
+use neuromorphic_core::convergence::{ConvergenceAction, ConvergenceMonitor};
+use neuromorphic_core::monitor::{PopulationRateMonitor, SpikeMonitor, WeightMonitor};
+use neuromorphic_core::probe::VoltageProbe;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::synapse::Plasticity;
+use neuromorphic_core::units::Milliseconds;
+
+fn chain() -> Simulation {
+    // 0 -> 1 -> 2 -> 3 with weights 0.1, 0.2, 0.3
+    Simulation::from_triplets(
+        4,
+        &[(0, 1, 0.1), (1, 2, 0.2), (2, 3, 0.3)],
+        neuron_params(),
+        SimulationConfig::default(),
+        common::stdp_params(),
+    )
+}
+
+#[test]
+fn voltage_probe_follows_neurons_across_removal() {
+    let mut sim = chain();
+    let probe = VoltageProbe::new(vec![1, 3], Milliseconds::ZERO);
+    sim.add_voltage_probe(probe.clone());
+    sim.remove_neuron(0);
+    sim.step(|i, _| if i == 2 { 5.0 } else { 0.0 });
+    sim.remove_neuron(0);
+    sim.step(|_, _| 0.0);
+
+    assert_eq!(probe.neurons(), vec![1, 3]);
+    // Neuron 3 is index 2 after the first removal and driven in step one
+    let v3 = probe.trace(3).unwrap();
+    assert!(v3[0] > 0.0);
+    let v1 = probe.trace(1).unwrap();
+    assert_eq!(v1[0], 0.0);
+    assert!(v1[1].is_nan(), "removed neuron reads as NaN");
+}
+
+#[test]
+fn weight_monitor_follows_synapses_across_removal() {
+    let mut sim = chain();
+    let monitor = WeightMonitor::of_synapses(vec![2, 1], Milliseconds::ZERO);
+    sim.add_monitor(monitor.clone());
+    sim.remove_synapse(0);
+    sim.step(|_, _| 0.0);
+    // Removing neuron 1 also removes its outgoing synapse 1 -> 2
+    sim.remove_neuron(1);
+    sim.step(|_, _| 0.0);
+
+    let samples = monitor.samples();
+    assert_eq!(samples[0].1, vec![0.3, 0.2]);
+    assert_eq!(samples[1].1[0], 0.3);
+    assert!(samples[1].1[1].is_nan());
+}
+
+#[test]
+fn spike_monitor_filter_follows_neurons_across_removal() {
+    let mut sim = chain();
+    let monitor = SpikeMonitor::of_neurons(vec![3]);
+    sim.add_monitor(monitor.clone());
+    sim.remove_neuron(0);
+    sim.step(|i, _| if i == 2 { 1e6 } else { 0.0 });
+    let spikes = monitor.spikes();
+    assert_eq!(spikes.len(), 1);
+    assert_eq!(spikes[0].neuron_id, 2);
+}
+
+#[test]
+#[should_panic(expected = "outside 0..4")]
+fn monitors_with_out_of_range_neurons_are_rejected() {
+    chain().add_monitor(SpikeMonitor::of_neurons(vec![4]));
+}
+
+#[test]
+#[should_panic(expected = "outside 0..3")]
+fn monitors_with_out_of_range_synapses_are_rejected() {
+    chain().add_monitor(WeightMonitor::of_synapses(vec![0, 3], Milliseconds(1.0)));
+}
+
+#[test]
+fn population_rates_close_every_elapsed_bin() {
+    let config = SimulationConfig {
+        dt: Milliseconds(1.0),
+        ..Default::default()
+    };
+    let mut sim = Simulation::from_triplets(2, &[], neuron_params(), config, common::stdp_params());
+    let population = 0..2;
+    let monitor = PopulationRateMonitor::new(vec![population], Milliseconds(0.5));
+    sim.add_monitor(monitor.clone());
+    sim.inject_spikes(&[
+        Spike::new(0, Milliseconds(0.0)),
+        Spike::new(1, Milliseconds(2.0)),
+    ]);
+    for _ in 0..3 {
+        sim.step(|_, _| 0.0);
+    }
+
+    let rates = monitor.rates();
+    let starts: Vec<f64> = rates.iter().map(|(t, _)| t.0).collect();
+    assert_eq!(starts, vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+    // One spike of two neurons in half a millisecond is 1000 Hz
+    let values: Vec<f64> = rates.iter().map(|(_, r)| r[0]).collect();
+    assert_eq!(values[..4], [1000.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn convergence_monitor_freezes_tracked_synapses_after_removal() {
+    let mut sim = chain();
+    let monitor = ConvergenceMonitor::new(Milliseconds(1.0), 1.0, ConvergenceAction::Freeze)
+        .track("last", vec![2]);
+    sim.add_convergence_monitor(monitor.clone());
+    sim.step(|_, _| 0.0);
+    // Synapse 2 -> 3 becomes index 1 and index 2 no longer exists
+    sim.remove_synapse(0);
+    common::run_for(&mut sim, Milliseconds(2.0));
+
+    assert!(monitor.all_converged());
+    assert_eq!(sim.synapses()[0].plasticity, Plasticity::Global);
+    assert_eq!(sim.synapses()[1].plasticity, Plasticity::Static);
+    assert_eq!(sim.synapses()[1].pre_neuron, 2);
+}
+
+#[test]
+#[should_panic(expected = "synapse outside")]
+fn convergence_monitor_rejects_missing_synapses() {
+    let mut sim = chain();
+    let monitor = ConvergenceMonitor::new(Milliseconds(1.0), 1.0, ConvergenceAction::Report)
+        .track("bad", vec![3]);
+    sim.add_convergence_monitor(monitor);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use neuromorphic_core::network::{Network, NetworkBuilder};
+use neuromorphic_core::population::{Connector, Population, Projection};
+use std::io::ErrorKind;
+
+fn pairs(network: &Network) -> Vec<(usize, usize)> {
+    network
+        .synapses()
+        .iter()
+        .map(|s| (s.pre_neuron, s.post_neuron))
+        .collect()
+}
+
+fn builder() -> NetworkBuilder {
+    let mut builder = NetworkBuilder::new();
+    builder.add_population("a", 3, common::neuron_params());
+    builder.add_population("b", 3, common::neuron_params());
+    builder.add_population("c", 2, common::neuron_params());
+    builder
+}
+
+#[test]
+fn one_to_one_pairs_neurons_by_index() {
+    let mut builder = builder();
+    builder
+        .project(&Projection::new("a", "b", Connector::OneToOne, 0.5))
+        .unwrap();
+    assert_eq!(pairs(&builder.build()), [(0, 3), (1, 4), (2, 5)]);
+}
+
+#[test]
+fn one_to_one_onto_itself_makes_autapses_only_when_allowed() {
+    let projection = Projection::new("a", "a", Connector::OneToOne, 0.5);
+    let mut builder = builder();
+    builder.project(&projection).unwrap();
+    assert!(pairs(&builder.build()).is_empty());
+
+    let mut builder = self::builder();
+    builder.allow_self_connections(true);
+    builder.project(&projection).unwrap();
+    assert_eq!(pairs(&builder.build()), [(0, 0), (1, 1), (2, 2)]);
+}
+
+#[test]
+fn projections_that_cannot_be_wired_are_errors() {
+    let cases = [
+        Projection::new("a", "c", Connector::OneToOne, 0.5),
+        Projection::new("a", "missing", Connector::AllToAll, 0.5),
+        Projection::new("c", "a", Connector::List(vec![(0, 0), (2, 1)]), 0.5),
+    ];
+    for projection in &cases {
+        let mut builder = builder();
+        let err = builder.project(projection).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{projection:?}");
+        assert!(builder.build().synapses().is_empty());
+    }
+}
+
+#[test]
+fn from_populations_reports_size_mismatches() {
+    let populations = [
+        Population::new("a", 3, common::neuron_params()),
+        Population::new("c", 2, common::neuron_params()),
+    ];
+    let projections = [Projection::new("a", "c", Connector::OneToOne, 0.5)];
+    let err = Network::from_populations(&populations, &projections).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "One-to-one projection a -> c joins populations of sizes 3 and 2"
+    );
+}
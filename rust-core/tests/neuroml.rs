@@ -0,0 +1,106 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::neuroml::{to_lems, to_neuroml, write_neuroml};
+use neuromorphic_core::neuron::NeuronParams;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spatial::Position;
+use neuromorphic_core::units::Milliseconds;
+
+/// Two placed excitatory neurons, then two unnamed neurons that differ in
+/// their time constant.
+fn simulation(config: SimulationConfig) -> Simulation {
+    let mut builder = NetworkBuilder::new();
+    let exc = builder.add_population("exc 1", 2, common::neuron_params());
+    builder.place(
+        exc,
+        [Position::new(1.0, 2.0, 3.0), Position::new(4.0, 5.0, 6.0)],
+    );
+    builder.add_neurons(1, common::neuron_params());
+    builder.add_neurons(
+        1,
+        NeuronParams {
+            tau_m: Milliseconds(10.0),
+            ..common::neuron_params()
+        },
+    );
+    builder.connect_all_delayed([
+        (0, 2, 0.5, Milliseconds(1.0)),
+        (1, 3, -0.25, Milliseconds(2.0)),
+        (0, 1, 0.1, Milliseconds(0.0)),
+    ]);
+    Simulation::from_network(builder.build(), config, common::stdp_params())
+}
+
+#[test]
+fn populations_and_projections_are_exported() {
+    let sim = simulation(SimulationConfig::default());
+    let nml = to_neuroml(&sim, "my net");
+    assert!(nml.contains(" id=\"my_net\">"), "{nml}");
+
+    // Populations are split where parameters differ and get unique ids
+    assert!(nml.contains(
+        "<IF_curr_exp id=\"exc_1_cell\" cm=\"20\" i_offset=\"0\" tau_syn_E=\"0.1\" \
+         tau_syn_I=\"0.1\" v_init=\"0\" tau_m=\"20\" tau_refrac=\"0\" v_reset=\"0\" \
+         v_rest=\"0\" v_thresh=\"1\"/>"
+    ));
+    assert!(nml.contains("<IF_curr_exp id=\"neurons_1_cell\" cm=\"10\""));
+    assert!(nml.contains("<expCurrSynapse id=\"syn\" tau_syn=\"0.1\"/>"));
+    assert!(nml.contains(
+        "<population id=\"exc_1\" component=\"exc_1_cell\" type=\"populationList\" size=\"2\">"
+    ));
+    assert!(nml.contains("<instance id=\"1\"><location x=\"4\" y=\"5\" z=\"6\"/></instance>"));
+    assert!(nml.contains("<population id=\"neurons\" component=\"neurons_cell\" size=\"1\"/>"));
+    assert!(nml.contains("<population id=\"neurons_1\" component=\"neurons_1_cell\" size=\"1\"/>"));
+
+    // Weights deposit the same charge through the exponential synapse
+    assert!(nml.contains(
+        "<projection id=\"exc_1_to_exc_1\" presynapticPopulation=\"exc_1\" \
+         postsynapticPopulation=\"exc_1\" synapse=\"syn\">"
+    ));
+    assert!(nml.contains(
+        "<connectionWD id=\"0\" preCellId=\"../exc_1/0/exc_1_cell\" \
+         postCellId=\"../neurons[0]\" weight=\"100\" delay=\"1ms\"/>"
+    ));
+    assert!(nml.contains(
+        "<connectionWD id=\"0\" preCellId=\"../exc_1/1/exc_1_cell\" \
+         postCellId=\"../neurons_1[0]\" weight=\"-25\" delay=\"2ms\"/>"
+    ));
+    assert_eq!(nml.matches("<projection ").count(), 3);
+    assert!(nml.ends_with("    </network>\n</neuroml>\n"));
+}
+
+#[test]
+fn lems_records_every_neuron() {
+    let sim = simulation(SimulationConfig::default());
+    let lems = to_lems(&sim, "net", "net.net.nml").unwrap();
+    assert!(lems.contains("<Include file=\"net.net.nml\"/>"));
+    assert!(lems.contains(
+        "<Simulation id=\"sim_net\" length=\"100ms\" step=\"0.1ms\" target=\"network\">"
+    ));
+    assert!(lems
+        .contains("<EventSelection id=\"1\" select=\"exc_1/1/exc_1_cell\" eventPort=\"spike\"/>"));
+    assert!(lems.contains("<EventSelection id=\"3\" select=\"neurons_1[0]\" eventPort=\"spike\"/>"));
+    assert_eq!(lems.matches("<EventSelection ").count(), 4);
+
+    let endless = simulation(SimulationConfig {
+        t_max: Milliseconds(f64::INFINITY),
+        ..SimulationConfig::default()
+    });
+    let error = to_lems(&endless, "net", "net.net.nml").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn documents_are_written_side_by_side() {
+    let sim = simulation(SimulationConfig::default());
+    let dir = temp_path("neuroml");
+    std::fs::create_dir_all(&dir).unwrap();
+    write_neuroml(&sim, "net-a", &dir).unwrap();
+    let nml = std::fs::read_to_string(dir.join("net_a.net.nml")).unwrap();
+    assert_eq!(nml, to_neuroml(&sim, "net_a"));
+    let lems = std::fs::read_to_string(dir.join("LEMS_net_a.xml")).unwrap();
+    assert_eq!(lems, to_lems(&sim, "net_a", "net_a.net.nml").unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "hdf5")]
+
+mod common;
+
+use common::{random_network, temp_path};
+use neuromorphic_core::hdf5::{Attribute, Hdf5File};
+use neuromorphic_core::nwb::{write_nwb, NwbSession, NWB_VERSION};
+use neuromorphic_core::probe::VoltageProbe;
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn text(file: &Hdf5File, path: &str, name: &str) -> String {
+    match file.attribute(path, name).unwrap() {
+        Some(Attribute::Text(text)) => text,
+        other => panic!("{path} {name}: {other:?}"),
+    }
+}
+
+#[test]
+fn units_and_traces_follow_the_nwb_layout() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(30.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(6, config, 5);
+    let probe = VoltageProbe::new(vec![1, 4], Milliseconds(1.0));
+    sim.add_monitor(probe.clone());
+    let (spikes, _) = sim.run(|i, _| 1.2 + 0.1 * i as f64);
+    assert!(!spikes.is_empty());
+
+    let mut session = NwbSession::new("layout test");
+    session.identifier = "run-7".to_string();
+    session.start_time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let path = temp_path("session.nwb");
+    write_nwb(&sim, &spikes, Some(&probe), &session, &path).unwrap();
+
+    let file = Hdf5File::open(&path).unwrap();
+    assert_eq!(text(&file, "/", "neurodata_type"), "NWBFile");
+    assert_eq!(text(&file, "/", "nwb_version"), NWB_VERSION);
+    assert_eq!(file.read_strings("/identifier").unwrap(), ["run-7"]);
+    assert_eq!(
+        file.read_strings("/session_description").unwrap(),
+        ["layout test"]
+    );
+    assert_eq!(
+        file.read_strings("/session_start_time").unwrap(),
+        ["2023-11-14T22:13:20.000000+00:00"]
+    );
+    for group in ["/acquisition", "/analysis", "/general", "/processing"] {
+        assert!(file.contains(group), "{group}");
+    }
+
+    // Spike times in seconds, grouped by unit and indexed by end offsets
+    assert_eq!(text(&file, "/units", "neurodata_type"), "Units");
+    assert_eq!(file.read_ints("/units/id").unwrap(), [0, 1, 2, 3, 4, 5]);
+    let times = file.read_floats("/units/spike_times").unwrap();
+    let index = file.read_ints("/units/spike_times_index").unwrap();
+    assert_eq!(index.len(), 6);
+    assert_eq!(*index.last().unwrap() as usize, spikes.len());
+    let mut start = 0;
+    for (neuron, &end) in index.iter().enumerate() {
+        let mut expected: Vec<f64> = spikes
+            .iter()
+            .filter(|s| s.neuron_id == neuron)
+            .map(|s| s.time.0 / 1000.0)
+            .collect();
+        expected.sort_by(f64::total_cmp);
+        assert_eq!(times[start..end as usize], expected[..], "unit {neuron}");
+        start = end as usize;
+    }
+    assert_eq!(
+        file.attribute("/units", "colnames").unwrap(),
+        Some(Attribute::Text("spike_times".to_string()))
+    );
+
+    // One column per probed neuron, in millivolts with a conversion to volts
+    let samples = probe.samples();
+    assert_eq!(
+        file.shape("/acquisition/membrane_potential/data").unwrap(),
+        [samples.len() as u64, 2]
+    );
+    let data = file
+        .read_floats("/acquisition/membrane_potential/data")
+        .unwrap();
+    let expected: Vec<f64> = samples.iter().flat_map(|(_, v)| v.clone()).collect();
+    assert_eq!(data, expected);
+    let timestamps = file
+        .read_floats("/acquisition/membrane_potential/timestamps")
+        .unwrap();
+    assert_eq!(timestamps[1], samples[1].0 .0 / 1000.0);
+    assert_eq!(
+        file.attribute("/acquisition/membrane_potential/data", "conversion")
+            .unwrap(),
+        Some(Attribute::Float(0.001))
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn files_without_a_probe_hold_only_units() {
+    let sim = random_network(3, SimulationConfig::default(), 1);
+    // Spikes of neurons outside the network are dropped
+    let spikes = [
+        Spike::new(2, Milliseconds(4.0)),
+        Spike::new(2, Milliseconds(1.0)),
+        Spike::new(9, Milliseconds(2.0)),
+    ];
+    let path = temp_path("units.nwb");
+    let session = NwbSession::new("units only");
+    write_nwb(&sim, &spikes, None, &session, &path).unwrap();
+
+    let file = Hdf5File::open(&path).unwrap();
+    assert!(!file.contains("/acquisition/membrane_potential"));
+    assert_eq!(
+        file.read_floats("/units/spike_times").unwrap(),
+        [0.001, 0.004]
+    );
+    assert_eq!(
+        file.read_ints("/units/spike_times_index").unwrap(),
+        [0, 0, 2]
+    );
+
+    // Identifiers are random version 4 UUIDs
+    let id = &session.identifier;
+    assert_eq!(id.len(), 36);
+    assert_eq!(&id[14..15], "4");
+    assert_ne!(NwbSession::new("other").identifier, *id);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,249 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::parquet::{write_spikes_parquet, write_weights_parquet, ROW_GROUP_SIZE};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+use std::collections::BTreeMap;
+
+/// Value decoded with the Thrift compact protocol.
+#[derive(Debug, Clone, PartialEq)]
+enum Thrift {
+    Bool(bool),
+    Int(i64),
+    Binary(Vec<u8>),
+    List(Vec<Thrift>),
+    Struct(BTreeMap<i16, Thrift>),
+}
+
+impl Thrift {
+    fn field(&self, id: i16) -> &Thrift {
+        match self {
+            Thrift::Struct(fields) => fields.get(&id).unwrap_or_else(|| panic!("no field {id}")),
+            other => panic!("{other:?} is not a struct"),
+        }
+    }
+
+    fn int(&self, id: i16) -> i64 {
+        match self.field(id) {
+            Thrift::Int(v) => *v,
+            other => panic!("field {id} is {other:?}"),
+        }
+    }
+
+    fn text(&self, id: i16) -> String {
+        match self.field(id) {
+            Thrift::Binary(b) => String::from_utf8(b.clone()).unwrap(),
+            other => panic!("field {id} is {other:?}"),
+        }
+    }
+
+    fn list(&self, id: i16) -> &[Thrift] {
+        match self.field(id) {
+            Thrift::List(items) => items,
+            other => panic!("field {id} is {other:?}"),
+        }
+    }
+}
+
+/// Independent reader of the Thrift compact protocol, per the Thrift spec.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> u8 {
+        self.pos += 1;
+        self.bytes[self.pos - 1]
+    }
+
+    fn varint(&mut self) -> u64 {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let b = self.byte();
+            value |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn zigzag(&mut self) -> i64 {
+        let v = self.varint();
+        (v >> 1) as i64 ^ -((v & 1) as i64)
+    }
+
+    fn value(&mut self, kind: u8) -> Thrift {
+        match kind {
+            1 => Thrift::Bool(true),
+            2 => Thrift::Bool(false),
+            3 => Thrift::Int(self.byte() as i8 as i64),
+            4..=6 => Thrift::Int(self.zigzag()),
+            8 => {
+                let len = self.varint() as usize;
+                self.pos += len;
+                Thrift::Binary(self.bytes[self.pos - len..self.pos].to_vec())
+            }
+            9 => {
+                let header = self.byte();
+                let len = match header >> 4 {
+                    15 => self.varint() as usize,
+                    n => n as usize,
+                };
+                Thrift::List((0..len).map(|_| self.value(header & 0x0f)).collect())
+            }
+            12 => self.structure(),
+            other => panic!("unexpected compact type {other}"),
+        }
+    }
+
+    fn structure(&mut self) -> Thrift {
+        let mut fields = BTreeMap::new();
+        let mut last = 0i16;
+        loop {
+            let header = self.byte();
+            if header == 0 {
+                return Thrift::Struct(fields);
+            }
+            let id = match header >> 4 {
+                0 => self.zigzag() as i16,
+                delta => last + delta as i16,
+            };
+            fields.insert(id, self.value(header & 0x0f));
+            last = id;
+        }
+    }
+}
+
+/// Column values of a file, decoded by following the footer and the page
+/// headers, after checking the schema.
+fn read(path: &std::path::Path, schema: &[(&str, i64, bool)]) -> Vec<Vec<[u8; 8]>> {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..4], b"PAR1");
+    assert_eq!(&bytes[bytes.len() - 4..], b"PAR1");
+    let footer_len =
+        u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+    let footer_start = bytes.len() - 8 - footer_len;
+    let mut reader = Reader {
+        bytes: &bytes[footer_start..bytes.len() - 8],
+        pos: 0,
+    };
+    let meta = reader.structure();
+    assert_eq!(reader.pos, footer_len);
+    assert_eq!(meta.int(1), 1);
+    assert!(meta.text(6).starts_with("neuromorphic_core"));
+
+    let elements = meta.list(2);
+    assert_eq!(elements[0].int(5), schema.len() as i64);
+    for (element, &(name, physical_type, unsigned)) in elements[1..].iter().zip(schema) {
+        assert_eq!(element.text(4), name);
+        assert_eq!(element.int(1), physical_type);
+        assert_eq!(element.int(3), 0, "columns are required");
+        if unsigned {
+            assert_eq!(element.int(6), 14);
+            let integer = element.field(10).field(10);
+            assert_eq!(integer.int(1), 64);
+            assert_eq!(integer.field(2), &Thrift::Bool(false));
+        }
+    }
+
+    let mut columns = vec![Vec::new(); schema.len()];
+    let mut rows = 0;
+    for group in meta.list(4) {
+        let group_rows = group.int(3) as usize;
+        rows += group_rows;
+        for (k, chunk) in group.list(1).iter().enumerate() {
+            let column = chunk.field(3);
+            assert_eq!(
+                column.list(3),
+                [Thrift::Binary(schema[k].0.as_bytes().to_vec())]
+            );
+            assert_eq!(column.int(4), 0, "uncompressed");
+            assert_eq!(column.int(5) as usize, group_rows);
+            let offset = column.int(9) as usize;
+            assert_eq!(offset, chunk.int(2) as usize);
+
+            let mut page = Reader {
+                bytes: &bytes[offset..],
+                pos: 0,
+            };
+            let header = page.structure();
+            assert_eq!(header.int(1), 0, "data page");
+            let size = header.int(3) as usize;
+            assert_eq!(page.pos + size, column.int(7) as usize);
+            let data = header.field(5);
+            assert_eq!(data.int(1) as usize, group_rows);
+            assert_eq!(data.int(2), 0, "PLAIN");
+            let values = &bytes[offset + page.pos..offset + page.pos + size];
+            columns[k].extend(values.chunks(8).map(|v| <[u8; 8]>::try_from(v).unwrap()));
+        }
+    }
+    assert_eq!(rows as i64, meta.int(3));
+    columns
+}
+
+#[test]
+fn spikes_decode_with_an_independent_reader() {
+    let spikes: Vec<Spike> = (0..1000)
+        .map(|k| Spike::new(k * 7 % 13, Milliseconds(k as f64 * 0.1)))
+        .collect();
+    let path = temp_path("spikes.parquet");
+    write_spikes_parquet(&spikes, &path).unwrap();
+
+    let columns = read(&path, &[("neuron_id", 2, true), ("time_ms", 5, false)]);
+    let ids: Vec<usize> = columns[0]
+        .iter()
+        .map(|v| u64::from_le_bytes(*v) as usize)
+        .collect();
+    let times: Vec<u64> = columns[1].iter().map(|v| u64::from_le_bytes(*v)).collect();
+    assert_eq!(ids, spikes.iter().map(|s| s.neuron_id).collect::<Vec<_>>());
+    assert_eq!(
+        times,
+        spikes
+            .iter()
+            .map(|s| s.time.0.to_bits())
+            .collect::<Vec<_>>()
+    );
+
+    // FileMetaData starts with field 1 (version), an i32 of value 1
+    let bytes = std::fs::read(&path).unwrap();
+    let footer_len =
+        u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap());
+    let footer = bytes.len() - 8 - footer_len as usize;
+    assert_eq!(bytes[footer..footer + 2], [0x15, 0x02]);
+}
+
+#[test]
+fn weights_decode_and_split_into_row_groups() {
+    let weights: Vec<_> = (0..ROW_GROUP_SIZE + 5)
+        .map(|k| (Milliseconds(k as f64), k % 3, k % 5, k as f64 / 8.0))
+        .collect();
+    let path = temp_path("weights.parquet");
+    write_weights_parquet(&weights, &path).unwrap();
+
+    let columns = read(
+        &path,
+        &[
+            ("time_ms", 5, false),
+            ("pre_neuron", 2, true),
+            ("post_neuron", 2, true),
+            ("weight", 5, false),
+        ],
+    );
+    assert!(columns.iter().all(|c| c.len() == weights.len()));
+    let last = weights.len() - 1;
+    assert_eq!(f64::from_le_bytes(columns[0][last]), last as f64);
+    assert_eq!(u64::from_le_bytes(columns[1][last]), (last % 3) as u64);
+    assert_eq!(u64::from_le_bytes(columns[2][last]), (last % 5) as u64);
+    assert_eq!(f64::from_le_bytes(columns[3][last]), last as f64 / 8.0);
+}
+
+#[test]
+fn empty_logs_write_a_valid_file() {
+    let path = temp_path("empty.parquet");
+    write_spikes_parquet(&[], &path).unwrap();
+    let columns = read(&path, &[("neuron_id", 2, true), ("time_ms", 5, false)]);
+    assert!(columns.iter().all(Vec::is_empty));
+}
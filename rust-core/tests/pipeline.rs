@@ -0,0 +1,49 @@
+mod common;
+
+use neuromorphic_core::pipeline::{LabeledDataset, Pipeline};
+use neuromorphic_core::rng::Rng;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+
+const N: usize = 12;
+
+fn encode(sample: &[f64], _: &mut Rng) -> Vec<Spike> {
+    sample
+        .iter()
+        .enumerate()
+        .map(|(i, &t)| Spike::new(i, Milliseconds(t)))
+        .collect()
+}
+
+fn network() -> Simulation {
+    let config = SimulationConfig {
+        t_max: Milliseconds(50.0),
+        ..SimulationConfig::default()
+    };
+    common::random_network(N, config, 7)
+}
+
+fn counts(spikes: &[Spike]) -> Vec<f64> {
+    let mut counts = vec![0.0; N];
+    for spike in spikes {
+        counts[spike.neuron_id] += 1.0;
+    }
+    counts
+}
+
+#[test]
+fn features_decode_the_spikes_of_a_plain_run() {
+    let data = LabeledDataset::new(vec![vec![1.0, 5.0, 9.0], vec![2.0, 2.0]], vec![0, 1]);
+    let pipeline = Pipeline::new(encode, network, counts);
+    let features = pipeline.features(&data);
+
+    let mut rng = Rng::new(pipeline.seed);
+    for (sample, features) in data.samples.iter().zip(&features) {
+        let mut simulation = network();
+        simulation.inject_spikes(&encode(sample, &mut rng));
+        let (spikes, _) = simulation.run(|_, _| 0.0);
+        assert_eq!(features, &counts(&spikes));
+    }
+    assert!(features.iter().flatten().sum::<f64>() >= 5.0);
+}
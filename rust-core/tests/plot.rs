@@ -0,0 +1,131 @@
+#![cfg(feature = "plot")]
+
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::plot::{lines_plot, raster_plot, PlotFormat, PlotOptions};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+
+/// Width, height and RGB pixels of a PNG made of stored deflate blocks.
+fn decode_png(png: &[u8]) -> (usize, usize, Vec<u8>) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let (mut at, mut ihdr, mut zlib) = (8, Vec::new(), Vec::new());
+    while at < png.len() {
+        let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+        let data = &png[at + 8..at + 8 + len];
+        match &png[at + 4..at + 8] {
+            b"IHDR" => ihdr = data.to_vec(),
+            b"IDAT" => zlib.extend_from_slice(data),
+            _ => {}
+        }
+        at += 12 + len;
+    }
+    let width = u32::from_be_bytes(ihdr[..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(ihdr[4..8].try_into().unwrap()) as usize;
+    // 8-bit RGB
+    assert_eq!(ihdr[8..10], [8, 2]);
+
+    let (mut raw, mut at) = (Vec::new(), 2);
+    loop {
+        let last = zlib[at] & 1 == 1;
+        let len = u16::from_le_bytes([zlib[at + 1], zlib[at + 2]]) as usize;
+        raw.extend_from_slice(&zlib[at + 5..at + 5 + len]);
+        at += 5 + len;
+        if last {
+            break;
+        }
+    }
+    let pixels = raw
+        .chunks(3 * width + 1)
+        .flat_map(|row| {
+            assert_eq!(row[0], 0);
+            row[1..].to_vec()
+        })
+        .collect();
+    (width, height, pixels)
+}
+
+fn spikes() -> Vec<Spike> {
+    (0..20)
+        .map(|k| Spike::new(k % 4, Milliseconds(k as f64 * 5.0)))
+        .collect()
+}
+
+#[test]
+fn format_follows_the_extension() {
+    assert_eq!(PlotFormat::from_path("raster.png"), PlotFormat::Png);
+    assert_eq!(PlotFormat::from_path("raster.PNG.gz"), PlotFormat::Png);
+    assert_eq!(PlotFormat::from_path("raster.svg"), PlotFormat::Svg);
+    assert_eq!(PlotFormat::from_path("raster.svg.zst"), PlotFormat::Svg);
+    assert_eq!(PlotFormat::from_path("raster"), PlotFormat::Svg);
+}
+
+#[test]
+fn svg_raster_has_one_tick_per_spike() {
+    let path = temp_path("raster.svg");
+    raster_plot(&spikes(), 4, &path, &PlotOptions::titled("A & B")).unwrap();
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains(">A &amp; B</text>"));
+    assert!(svg.contains(">Time (ms)</text>"));
+    let ticks = svg
+        .matches(r##"stroke="#000000" stroke-width="1"/>"##)
+        .count();
+    // One line per spike besides the axis ticks
+    assert!(ticks >= 20, "{ticks} lines");
+}
+
+#[test]
+fn png_raster_draws_on_a_white_background() {
+    let path = temp_path("raster.png");
+    let options = PlotOptions {
+        width: 200,
+        height: 120,
+        title: Some("Raster".to_string()),
+    };
+    raster_plot(&spikes(), 4, &path, &options).unwrap();
+    let (width, height, pixels) = decode_png(&std::fs::read(&path).unwrap());
+    assert_eq!((width, height), (200, 120));
+    assert_eq!(pixels.len(), 3 * 200 * 120);
+
+    let pixel = |x: usize, y: usize| &pixels[3 * (y * width + x)..3 * (y * width + x) + 3];
+    assert_eq!(pixel(0, 0), [255, 255, 255]);
+    assert_eq!(pixel(199, 119), [255, 255, 255]);
+    // The frame runs along the left margin of the plotting area
+    for y in 30..75 {
+        assert_eq!(pixel(60, y), [0, 0, 0], "y = {y}");
+    }
+    let black = pixels.chunks(3).filter(|p| *p == [0, 0, 0]).count();
+    assert!(black > 500, "{black} black pixels");
+}
+
+#[test]
+fn png_lines_use_the_series_colors() {
+    let path = temp_path("lines.png");
+    let times: Vec<Milliseconds> = (0..50).map(|t| Milliseconds(t as f64)).collect();
+    let series = vec![
+        ("rising".to_string(), (0..50).map(|t| t as f64).collect()),
+        ("falling".to_string(), (0..50).map(|t| -t as f64).collect()),
+    ];
+    lines_plot(&times, &series, "Value", &path, &PlotOptions::default()).unwrap();
+    let (_, _, pixels) = decode_png(&std::fs::read(&path).unwrap());
+    let count = |color: [u8; 3]| pixels.chunks(3).filter(|p| *p == color).count();
+    assert!(count([0x1f, 0x77, 0xb4]) > 100);
+    assert!(count([0xd6, 0x27, 0x28]) > 100);
+}
+
+#[test]
+fn compressed_plots_and_empty_images() {
+    let path = temp_path("raster.svg.gz");
+    raster_plot(&spikes(), 4, &path, &PlotOptions::default()).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap()[..2], [0x1f, 0x8b]);
+
+    let options = PlotOptions {
+        width: 0,
+        ..PlotOptions::default()
+    };
+    let err = raster_plot(&spikes(), 4, temp_path("empty.png"), &options).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
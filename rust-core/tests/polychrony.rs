@@ -0,0 +1,35 @@
+use neuromorphic_core::polychrony::{find_polychronous_groups, PolychronyParams};
+use neuromorphic_core::synapse::Synapse;
+use neuromorphic_core::units::Milliseconds;
+
+fn params() -> PolychronyParams {
+    PolychronyParams {
+        inputs_to_fire: 2,
+        min_size: 3,
+        min_depth: 1,
+        ..PolychronyParams::default()
+    }
+}
+
+#[test]
+fn every_pair_of_inputs_anchors_a_group() {
+    // Neurons 0, 1 and 2 reach neuron 3 after 1, 2 and 3 ms
+    let synapses: Vec<Synapse> = (0..3)
+        .map(|i| Synapse::with_delay(i, 3, 1.0, Milliseconds(i as f64 + 1.0)))
+        .collect();
+    let groups = find_polychronous_groups(&synapses, 4, &params());
+
+    let mut anchors: Vec<Vec<usize>> = groups
+        .iter()
+        .map(|g| g.neurons().into_iter().filter(|&n| n != 3).collect())
+        .collect();
+    anchors.sort();
+    assert_eq!(anchors, vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+    assert!(groups.iter().all(|g| g.size() == 3 && g.depth == 1));
+}
+
+#[test]
+fn too_few_inputs_anchor_nothing() {
+    let synapses = [Synapse::with_delay(0, 1, 1.0, Milliseconds(1.0))];
+    assert!(find_polychronous_groups(&synapses, 2, &params()).is_empty());
+}
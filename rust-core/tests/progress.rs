@@ -0,0 +1,78 @@
+mod common;
+
+use common::random_network;
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::stopping::StopReason;
+use neuromorphic_core::units::Milliseconds;
+use std::sync::{Arc, Mutex};
+
+fn config(t_max: f64) -> SimulationConfig {
+    SimulationConfig {
+        dt: Milliseconds(0.5),
+        t_max: Milliseconds(t_max),
+        ..SimulationConfig::default()
+    }
+}
+
+/// Times at which a callback with `interval` is called during a run of
+/// `sim` until `t_max`.
+fn report_times(t_max: f64, interval: Milliseconds, lead_in: f64) -> Vec<f64> {
+    let mut sim = random_network(4, config(t_max), 1);
+    common::run_for(&mut sim, Milliseconds(lead_in));
+    let times = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&times);
+    sim.set_progress_callback(
+        interval,
+        Box::new(move |t, _, _| {
+            seen.lock().unwrap().push(t.0);
+            true
+        }),
+    );
+    sim.run(common::drive);
+    let times = times.lock().unwrap().clone();
+    times
+}
+
+#[test]
+fn reports_every_interval_from_the_start_of_the_run() {
+    assert_eq!(
+        report_times(10.0, Milliseconds(2.0), 0.0),
+        [2.0, 4.0, 6.0, 8.0, 10.0]
+    );
+    // An interval that is not a multiple of dt reports at the first step
+    // past each multiple
+    assert_eq!(report_times(5.0, Milliseconds(1.2), 1.0), [2.5, 3.5, 5.0]);
+}
+
+#[test]
+fn tiny_intervals_report_every_step_without_stalling() {
+    // Late in a long run, adding such an interval to the time rounds away
+    let times = report_times(1.0e6 + 2.0, Milliseconds(f64::MIN_POSITIVE), 1.0e6);
+    assert_eq!(times.len(), 4);
+}
+
+#[test]
+fn returning_false_aborts_the_run() {
+    let mut sim = random_network(4, config(100.0), 1);
+    sim.set_progress_callback(
+        Milliseconds(10.0),
+        Box::new(|t, _, _| t < Milliseconds(30.0)),
+    );
+    let (_, _, reason) = sim.run_until(common::drive, &[]);
+    assert_eq!(reason, StopReason::Aborted);
+    assert_eq!(sim.time(), Milliseconds(30.0));
+}
+
+#[test]
+#[should_panic(expected = "Progress interval must be positive")]
+fn zero_interval_is_rejected() {
+    let mut sim = random_network(4, config(10.0), 1);
+    sim.set_progress_callback(Milliseconds::ZERO, Box::new(|_, _, _| true));
+}
+
+#[test]
+#[should_panic(expected = "Progress interval must be positive")]
+fn nan_interval_is_rejected() {
+    let mut sim = random_network(4, config(10.0), 1);
+    sim.set_progress_callback(Milliseconds(f64::NAN), Box::new(|_, _, _| true));
+}
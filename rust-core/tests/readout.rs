@@ -0,0 +1,24 @@
+use neuromorphic_core::readout::LinearReadout;
+
+#[test]
+fn ridge_fit_recovers_a_linear_map() {
+    let states: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64, (i * i) as f64]).collect();
+    let targets: Vec<Vec<f64>> = states
+        .iter()
+        .map(|x| vec![2.0 * x[0] - x[1] + 3.0])
+        .collect();
+    let readout = LinearReadout::fit_ridge(&states, &targets, 0.0).unwrap();
+    assert!(readout.mse(&states, &targets) < 1e-12);
+    assert!((readout.predict(&[20.0, 1.0])[0] - 42.0).abs() < 1e-6);
+}
+
+#[test]
+fn ridge_fit_rejects_ragged_samples() {
+    let targets = vec![vec![1.0], vec![2.0]];
+    let ragged_states = vec![vec![1.0, 2.0], vec![3.0]];
+    assert!(LinearReadout::fit_ridge(&ragged_states, &targets, 0.1).is_none());
+
+    let states = vec![vec![1.0], vec![2.0]];
+    let ragged_targets = vec![vec![1.0], vec![2.0, 3.0]];
+    assert!(LinearReadout::fit_ridge(&states, &ragged_targets, 0.1).is_none());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::connectivity::SheetShape;
+use neuromorphic_core::receptive_field::{write_png_grid, ReceptiveField};
+use std::io::ErrorKind;
+
+/// Reference CRC-32 (IEEE), bit by bit.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &b in bytes {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Split a PNG into `(kind, data)` chunks, checking every CRC.
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut chunks = Vec::new();
+    let mut at = 8;
+    while at < png.len() {
+        let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+        let body = &png[at + 4..at + 8 + len];
+        let crc = u32::from_be_bytes(png[at + 8 + len..at + 12 + len].try_into().unwrap());
+        assert_eq!(crc, crc32(body));
+        chunks.push((body[..4].try_into().unwrap(), body[4..].to_vec()));
+        at += 12 + len;
+    }
+    chunks
+}
+
+fn field(weights: Vec<f64>, width: usize, height: usize) -> ReceptiveField {
+    ReceptiveField {
+        neuron: 0,
+        shape: SheetShape { width, height },
+        weights,
+    }
+}
+
+#[test]
+fn png_has_valid_chunks_and_pixels() {
+    let path = temp_path("field.png");
+    field(vec![0.0, 0.5, 1.0], 3, 1).write_png(&path).unwrap();
+    let png = std::fs::read(&path).unwrap();
+    let chunks = chunks(&png);
+
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(k, _)| k).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+    assert_eq!(chunks[0].1, [0, 0, 0, 3, 0, 0, 0, 1, 8, 0, 0, 0, 0]);
+    // The IEND chunk of every PNG ends in the same CRC
+    assert_eq!(&png[png.len() - 4..], [0xae, 0x42, 0x60, 0x82]);
+
+    // zlib header, one final stored block, then the Adler-32 checksum
+    let idat = &chunks[1].1;
+    assert_eq!(idat[..3], [0x78, 0x01, 1]);
+    assert_eq!(idat[3..7], [4, 0, 0xfb, 0xff]);
+    assert_eq!(idat[7..11], [0, 0, 128, 255]);
+}
+
+#[test]
+fn empty_grids_are_rejected() {
+    let err = write_png_grid(&[], 4, temp_path("empty.png")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = field(Vec::new(), 0, 3)
+        .write_png(temp_path("flat.png"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn grid_tiles_fields_with_borders() {
+    let path = temp_path("grid.png");
+    let fields = [field(vec![0.0, 1.0], 2, 1), field(vec![1.0, 0.0], 2, 1)];
+    write_png_grid(&fields, 2, &path).unwrap();
+    let ihdr = &chunks(&std::fs::read(&path).unwrap())[0].1;
+    // Two 2x1 tiles side by side with one-pixel borders: 7x3
+    assert_eq!(ihdr[..8], [0, 0, 0, 7, 0, 0, 0, 3]);
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use neuromorphic_core::monitor::StepView;
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::reinforcement::{
+    build_agent, AgentParams, Bandit, CartPole, Environment, RStdp, RStdpParams,
+};
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::synapse::Plasticity;
+use neuromorphic_core::units::Milliseconds;
+
+#[test]
+fn agents_learn_the_better_bandit_arm() {
+    let mut bandit = Bandit::new(vec![0.2, 0.8], 1);
+    assert_eq!(bandit.best_arm(), 1);
+    let params = AgentParams {
+        seed: 2,
+        ..AgentParams::default()
+    };
+    let mut agent = build_agent(1, 2, &params, RStdpParams::default());
+    let best: Vec<bool> = (0..400)
+        .map(|_| agent.step(&mut bandit).action == 1)
+        .collect();
+    let late = best[300..].iter().filter(|&&b| b).count();
+    assert!(late > 80, "best arm in {late} of the last 100 steps");
+
+    // Synapses onto the better arm end up stronger
+    let sim = &agent.simulation;
+    let mean = |action: &str| {
+        let range = sim.population(action).unwrap();
+        let weights: Vec<f64> = sim
+            .synapses()
+            .iter()
+            .filter(|s| range.contains(&s.post_neuron))
+            .map(|s| s.weight)
+            .collect();
+        weights.iter().sum::<f64>() / weights.len() as f64
+    };
+    assert!(mean("action1") > mean("action0"));
+    assert!(agent.driver.rule.baseline() > 0.5);
+}
+
+#[test]
+fn agents_are_wired_input_to_action() {
+    let params = AgentParams {
+        input_size: 3,
+        action_size: 2,
+        ..AgentParams::default()
+    };
+    let agent = build_agent(2, 3, &params, RStdpParams::default());
+    let sim = &agent.simulation;
+    assert_eq!(sim.neurons().len(), 2 * 3 + 3 * 2);
+    assert_eq!(sim.population("input1"), Some(3..6));
+    assert_eq!(sim.population("action2"), Some(10..12));
+    assert_eq!(agent.driver.inputs, [0..3, 3..6]);
+    assert_eq!(agent.driver.actions, [6..8, 8..10, 10..12]);
+    assert_eq!(sim.synapses().len(), 6 * 6);
+    for syn in sim.synapses() {
+        assert!(syn.pre_neuron < 6 && syn.post_neuron >= 6);
+        assert!((0.1..0.3).contains(&syn.weight));
+        assert!(matches!(syn.plasticity, Plasticity::Static));
+    }
+}
+
+/// Feed `fired` to `rule` as one step of `sim` at `time`.
+fn observe(rule: &mut RStdp, sim: &Simulation, time: f64, fired: &[usize]) {
+    rule.observe(&StepView {
+        time: Milliseconds(time),
+        dt: Milliseconds(1.0),
+        fired,
+        neurons: sim.neurons(),
+        synapses: sim.synapses(),
+    });
+}
+
+#[test]
+fn rewards_commit_eligibility() {
+    let mut builder = NetworkBuilder::new();
+    builder.add_neurons(3, common::neuron_params());
+    builder.connect_all([(0, 1, 0.5), (1, 2, 0.5), (2, 0, 0.5)]);
+    let config = SimulationConfig {
+        dt: Milliseconds(1.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = Simulation::from_network(builder.build(), config, common::stdp_params());
+    let params = RStdpParams {
+        tau_plus: Milliseconds(10.0),
+        tau_minus: Milliseconds(10.0),
+        learning_rate: 0.1,
+        baseline_rate: 0.5,
+        ..RStdpParams::default()
+    };
+    // Only synapses onto neurons 1 and 2 are trained
+    let mut rule = RStdp::for_targets(&sim, 1..3, params);
+
+    // 0 before 1 is causal, 2 after 1 anti-causal
+    observe(&mut rule, &sim, 0.0, &[0, 2]);
+    observe(&mut rule, &sim, 1.0, &[1]);
+    let decay = (-0.1f64).exp();
+    rule.reward(&mut sim, 1.0);
+    let weights: Vec<f64> = sim.synapses().iter().map(|s| s.weight).collect();
+    let expected = [0.5 + 0.1 * decay, 0.5 - 0.1 * decay, 0.5];
+    for (w, e) in weights.iter().zip(expected) {
+        assert!((w - e).abs() < 1e-12, "{weights:?}");
+    }
+    assert_eq!(rule.baseline(), 0.5);
+
+    // Rewards at the baseline change nothing; eligibility decays meanwhile
+    rule.reward(&mut sim, 0.5);
+    assert_eq!(sim.synapses()[0].weight, weights[0]);
+    // Worse-than-usual outcomes depress, within the weight bounds
+    for t in 2..100 {
+        observe(&mut rule, &sim, t as f64, &[0]);
+        observe(&mut rule, &sim, t as f64 + 0.5, &[1]);
+    }
+    rule.reward(&mut sim, -100.0);
+    assert_eq!(sim.synapses()[0].weight, 0.0);
+}
+
+#[test]
+fn cart_poles_fall_without_control() {
+    let mut env = CartPole::new(5, 3);
+    assert_eq!(env.observe().len(), 20);
+    assert!(env.state.iter().all(|x| x.abs() <= 0.05));
+    assert!(env.observe().iter().all(|x| (0.0..=1.0).contains(x)));
+
+    // Pushing one way tips the pole over within a few seconds
+    let mut steps = 0;
+    while !env.is_done() {
+        let reward = env.act(1);
+        steps += 1;
+        assert_eq!(reward, if env.is_done() { -1.0 } else { 1.0 });
+        assert!(steps < 200);
+    }
+    assert!(env.state[2] < 0.0, "the pole falls against the push");
+    assert_eq!(env.act(1), 0.0);
+
+    env.reset();
+    assert!(!env.is_done());
+    // Alternating pushes balance longer than pushing one way
+    let balanced = (0..steps).take_while(|&k| env.act(k % 2) > 0.0).count();
+    assert_eq!(balanced, steps);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{random_network, spike_bits, weight_bits};
+use neuromorphic_core::neuromodulation::Modulator;
+use neuromorphic_core::quantization::Quantization;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig, WeightSample};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::stopping::StopCondition;
+use neuromorphic_core::units::Milliseconds;
+
+fn samples(weights: &[WeightSample]) -> Vec<(u64, usize, usize, u64)> {
+    weights
+        .iter()
+        .map(|w| (w.0 .0.to_bits(), w.1, w.2, w.3.to_bits()))
+        .collect()
+}
+
+fn modulator_bits(sim: &Simulation) -> Vec<u64> {
+    sim.modulators().iter().map(|m| m.level.to_bits()).collect()
+}
+
+/// Run `sim` recorded for 30 ms past a 10 ms lead-in, then replay the log
+/// on the same simulation and check the two runs agree bit for bit.
+fn assert_replays(mut sim: Simulation) -> (Vec<Spike>, Vec<u64>) {
+    common::run_for(&mut sim, Milliseconds(10.0));
+    let end = sim.time() + Milliseconds(30.0);
+    let ((spikes, weights, _), log) = sim.run_recorded(
+        |i, t| 3.0 * common::drive(i, t),
+        &[StopCondition::Custom(Box::new(move |t, _| t >= end))],
+    );
+    assert!(!spikes.is_empty());
+    let after = weight_bits(&sim);
+    let levels = modulator_bits(&sim);
+
+    // Replay on top of a further-advanced state, which it must overwrite
+    common::run_for(&mut sim, Milliseconds(5.0));
+    let (replayed, replayed_weights) = sim.replay(&log);
+    assert_eq!(spike_bits(&replayed), spike_bits(&spikes));
+    assert_eq!(samples(&replayed_weights), samples(&weights));
+    assert_eq!(weight_bits(&sim), after);
+    assert_eq!(modulator_bits(&sim), levels);
+    (spikes, after)
+}
+
+#[test]
+fn replay_reproduces_a_recorded_run() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(f64::INFINITY),
+        ..SimulationConfig::default()
+    };
+    assert_replays(random_network(40, config, 3));
+}
+
+#[test]
+fn replay_reproduces_stochastic_weight_rounding() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(f64::INFINITY),
+        quantization: Some(
+            Quantization::hardware(4, 16, 0.0, 1.0, 4.0).with_stochastic_rounding(11),
+        ),
+        ..SimulationConfig::default()
+    };
+    let (_, weights) = assert_replays(random_network(40, config, 3));
+    // Four-bit weights leave plasticity updates to stochastic rounding
+    assert!(weights.iter().any(|&w| f64::from_bits(w) != 0.0));
+}
+
+#[test]
+fn replay_restores_decaying_modulator_levels() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(f64::INFINITY),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(40, config, 3);
+    sim.add_modulator(Modulator::dopamine());
+    sim.add_modulator(Modulator {
+        excitability_gain: 0.5,
+        decay: Some(Milliseconds(20.0)),
+        ..Modulator::new("arousal")
+    });
+    sim.set_modulator("dopamine", 1.0);
+    sim.set_modulator("arousal", 1.0);
+    assert_replays(sim);
+}
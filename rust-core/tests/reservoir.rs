@@ -0,0 +1,73 @@
+mod common;
+
+use neuromorphic_core::reservoir::{build_reservoir, ReservoirParams};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::synapse::Plasticity;
+use neuromorphic_core::units::Milliseconds;
+
+fn reservoir(params: &ReservoirParams) -> neuromorphic_core::reservoir::Reservoir {
+    build_reservoir(
+        params,
+        common::neuron_params(),
+        SimulationConfig::default(),
+        common::stdp_params(),
+    )
+}
+
+#[test]
+fn layout_signs_and_scaling_follow_the_parameters() {
+    let params = ReservoirParams {
+        num_inputs: 3,
+        size: 50,
+        seed: 4,
+        ..ReservoirParams::default()
+    };
+    let reservoir = reservoir(&params);
+    assert_eq!(reservoir.inputs, 0..3);
+    assert_eq!(reservoir.excitatory, 3..43);
+    assert_eq!(reservoir.inhibitory, 43..53);
+    let sim = &reservoir.simulation;
+    assert!(sim.config().synaptic_transmission);
+    assert_eq!(sim.population("inhibitory"), Some(43..53));
+
+    let mut sum_sq = 0.0;
+    for s in sim.synapses() {
+        assert!(matches!(s.plasticity, Plasticity::Static));
+        assert_ne!(s.pre_neuron, s.post_neuron);
+        assert!(reservoir.reservoir().contains(&s.post_neuron));
+        if reservoir.inputs.contains(&s.pre_neuron) {
+            assert_eq!(s.weight, params.input_weight);
+            continue;
+        }
+        if reservoir.excitatory.contains(&s.pre_neuron) {
+            assert!(s.weight >= 0.0);
+        } else {
+            assert!(s.weight <= 0.0);
+        }
+        sum_sq += s.weight * s.weight;
+    }
+    let radius = (sum_sq / params.size as f64).sqrt();
+    assert!((radius - params.spectral_radius).abs() < 1e-9, "{radius}");
+}
+
+#[test]
+fn states_filter_the_spikes_of_reservoir_neurons_only() {
+    let reservoir = reservoir(&ReservoirParams {
+        num_inputs: 1,
+        size: 4,
+        ..ReservoirParams::default()
+    });
+    let spikes = [
+        Spike::new(0, Milliseconds(1.0)),
+        Spike::new(1, Milliseconds(1.0)),
+        Spike::new(1, Milliseconds(3.0)),
+        Spike::new(4, Milliseconds(5.0)),
+    ];
+    let times = [Milliseconds(2.0), Milliseconds(5.0)];
+    let states = reservoir.states(&spikes, &times, Milliseconds(10.0));
+    let decay = |dt: f64| (-dt / 10.0).exp();
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0], vec![decay(1.0), 0.0, 0.0, 0.0]);
+    assert_eq!(states[1], vec![decay(4.0) + decay(2.0), 0.0, 0.0, 1.0]);
+}
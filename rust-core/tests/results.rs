@@ -0,0 +1,74 @@
+mod common;
+
+use common::{random_network, spike_bits, temp_path};
+use neuromorphic_core::results::{RunMetadata, RunResults};
+use neuromorphic_core::simulation::{SimulationConfig, WeightSample};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::stopping::StopReason;
+use neuromorphic_core::units::Milliseconds;
+
+fn weight_bits(weights: &[WeightSample]) -> Vec<(u64, usize, usize, u64)> {
+    weights
+        .iter()
+        .map(|w| (w.0 .0.to_bits(), w.1, w.2, w.3.to_bits()))
+        .collect()
+}
+
+fn assert_same(a: &RunResults, b: &RunResults) {
+    assert_eq!(a.metadata, b.metadata);
+    assert_eq!(spike_bits(&a.spikes), spike_bits(&b.spikes));
+    assert_eq!(weight_bits(&a.weights), weight_bits(&b.weights));
+}
+
+#[test]
+fn simulated_run_round_trips() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(50.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(30, config, 5);
+    let (spikes, weights) = sim.run(|i, t| 3.0 * common::drive(i, t));
+    assert!(!spikes.is_empty() && !weights.is_empty());
+    let results = RunResults::new(&sim, spikes, weights, Some(StopReason::Completed));
+
+    let parsed = RunResults::from_json(&results.to_json()).unwrap();
+    assert_same(&results, &parsed);
+
+    let path = temp_path("results.json");
+    results.write_json(&path).unwrap();
+    assert_same(&results, &RunResults::read_json(&path).unwrap());
+}
+
+#[test]
+fn unbounded_run_length_round_trips() {
+    let results = RunResults {
+        metadata: RunMetadata {
+            num_neurons: 2,
+            num_synapses: 0,
+            dt: Milliseconds(0.1),
+            t_max: Milliseconds(f64::INFINITY),
+            end_time: Milliseconds(12.5),
+            stop_reason: Some(StopReason::MaxSpikes),
+        },
+        spikes: vec![Spike::new(1, Milliseconds(0.3))],
+        weights: Vec::new(),
+    };
+    let json = results.to_json();
+    assert!(json.contains("\"t_max_ms\":null"));
+    assert_same(&results, &RunResults::from_json(&json).unwrap());
+}
+
+#[test]
+fn null_times_read_as_nan_and_null_counts_fail() {
+    let json = r#"{"metadata":{"num_neurons":1,"num_synapses":0,"dt_ms":null,
+        "t_max_ms":10,"end_time_ms":null,"stop_reason":null},
+        "spikes":{"neuron_id":[],"time_ms":[]},
+        "weights":{"time_ms":[],"pre_neuron":[],"post_neuron":[],"weight":[]}}"#;
+    let metadata = RunResults::from_json(json).unwrap().metadata;
+    assert!(metadata.dt.0.is_nan() && metadata.end_time.0.is_nan());
+    assert_eq!(metadata.t_max, Milliseconds(10.0));
+    assert_eq!(metadata.stop_reason, None);
+
+    let json = json.replace("\"num_neurons\":1", "\"num_neurons\":null");
+    assert!(RunResults::from_json(&json).is_err());
+}
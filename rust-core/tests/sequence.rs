@@ -0,0 +1,123 @@
+mod common;
+
+use neuromorphic_core::sequence::{
+    activations, build_sequence_network, Recall, SequenceNetwork, SequenceParams,
+};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::stdp::STDPParams;
+use neuromorphic_core::units::Milliseconds;
+
+fn network() -> SequenceNetwork {
+    let params = SequenceParams {
+        num_elements: 8,
+        assembly_size: 10,
+        ..SequenceParams::default()
+    };
+    let stdp = STDPParams {
+        a_plus: 0.05,
+        a_minus: 0.06,
+        tau_plus: Milliseconds(8.0),
+        tau_minus: Milliseconds(8.0),
+        ..common::stdp_params()
+    };
+    build_sequence_network(
+        &params,
+        common::neuron_params(),
+        SimulationConfig::default(),
+        stdp,
+    )
+}
+
+#[test]
+fn training_turns_order_into_forward_weights() {
+    let mut net = network();
+    assert_eq!(net.simulation.population("element3"), Some(30..40));
+    assert_eq!(net.simulation.synapses().len(), 8 * 7 * 10 * 10);
+    let sequence = vec![2, 5, 0, 7, 3];
+    // Untrained, the cue does not spread
+    assert_eq!(net.completion(&sequence, 1), 0.0);
+
+    net.train(std::slice::from_ref(&sequence), 20);
+    let weights = net.assembly_weights();
+    for pair in sequence.windows(2) {
+        let (forward, backward) = (weights[pair[0]][pair[1]], weights[pair[1]][pair[0]]);
+        assert!(forward > 10.0 * backward.max(1e-6), "{pair:?}: {weights:?}");
+    }
+    // Skipping ahead is learned, but weaker than the next step
+    assert!(weights[2][0] < weights[2][5]);
+    // Elements outside the sequence keep their initial weights
+    assert!((weights[1][4] - 0.005).abs() < 1e-12);
+
+    // Summed input weights are bounded
+    let mut totals = vec![0.0; net.simulation.neurons().len()];
+    for syn in net.simulation.synapses() {
+        totals[syn.post_neuron] += syn.weight;
+    }
+    assert!(totals.iter().all(|&t| t <= 2.0 + 1e-9));
+}
+
+#[test]
+fn cues_replay_the_rest_compressed() {
+    let mut net = network();
+    let sequence = vec![2, 5, 0, 7, 3];
+    net.train(std::slice::from_ref(&sequence), 20);
+    let weights = net.assembly_weights();
+
+    let recall = net.cue(&sequence[..1]);
+    assert_eq!(recall.recalled(), [5, 0, 7, 3], "{recall:?}");
+    assert_eq!(recall.completion(&sequence), 1.0);
+    // Each step of the replay takes about a delay, not the interval
+    let onsets: Vec<f64> = recall.activations.iter().map(|a| a.1 .0).collect();
+    for step in onsets.windows(2) {
+        assert!(step[1] - step[0] < 10.0, "{onsets:?}");
+    }
+
+    // Recall does not learn
+    assert_eq!(net.assembly_weights(), weights);
+    assert_eq!(net.completion(&sequence, 5), 1.0);
+}
+
+#[test]
+fn completion_counts_up_to_the_first_mistake() {
+    let at = |pairs: &[(usize, f64)]| -> Vec<(usize, Milliseconds)> {
+        pairs.iter().map(|&(e, t)| (e, Milliseconds(t))).collect()
+    };
+    let recall = Recall {
+        activations: at(&[(1, 0.0), (2, 5.0), (1, 8.0), (4, 10.0), (3, 15.0)]),
+        cue: vec![1],
+    };
+    assert_eq!(recall.recalled(), [2, 4, 3]);
+    assert_eq!(recall.completion(&[1, 2, 3, 4]), 1.0 / 3.0);
+    assert_eq!(recall.completion(&[1, 2, 4, 3]), 1.0);
+    assert_eq!(recall.completion(&[1]), 1.0);
+}
+
+#[test]
+fn activations_need_enough_of_an_assembly() {
+    let spikes: Vec<Spike> = [
+        (0, 1.0),
+        (1, 2.0),
+        (2, 9.0),
+        (3, 20.0),
+        (4, 21.0),
+        (5, 22.0),
+    ]
+    .iter()
+    .map(|&(i, t)| Spike::new(i, Milliseconds(t)))
+    .collect();
+    let assemblies = [0..3, 3..6];
+    // A gap longer than 5 ms splits the first assembly's spikes
+    assert_eq!(
+        activations(&spikes, &assemblies, 0.5, Milliseconds(5.0)),
+        [(0, Milliseconds(1.0)), (1, Milliseconds(20.0))]
+    );
+    assert_eq!(
+        activations(&spikes, &assemblies, 1.0, Milliseconds(5.0)),
+        [(1, Milliseconds(20.0))]
+    );
+    assert_eq!(
+        activations(&spikes, &assemblies, 1.0, Milliseconds(10.0)),
+        [(0, Milliseconds(1.0)), (1, Milliseconds(20.0))]
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{random_network, run_for, spike_bits, weight_bits};
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::simulation::{Simulation, SimulationConfig};
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::units::Milliseconds;
+
+fn partitioned(partitions: usize) -> SimulationConfig {
+    SimulationConfig {
+        dt: Milliseconds(0.5),
+        num_partitions: partitions,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn partitioned_runs_are_bit_identical_to_serial() {
+    let mut serial = random_network(64, partitioned(1), 7);
+    let reference = run_for(&mut serial, Milliseconds(200.0));
+    assert!(reference.len() > 100, "network should be active");
+
+    for partitions in [2, 3, 8, 64, 100] {
+        let config = SimulationConfig {
+            t_max: Milliseconds(200.0),
+            ..partitioned(partitions)
+        };
+        let mut parallel = random_network(64, config, 7);
+        let (spikes, _, _) = parallel.run_parallel(common::drive, &[]);
+        assert_eq!(
+            spike_bits(&spikes),
+            spike_bits(&reference),
+            "{partitions} partitions"
+        );
+        assert_eq!(weight_bits(&parallel), weight_bits(&serial));
+    }
+}
+
+#[test]
+fn injected_spikes_fire_in_their_step() {
+    let mut sim = random_network(8, partitioned(1), 1);
+    sim.inject_spikes(&[Spike::new(3, Milliseconds(1.2))]);
+    let mut fired = Vec::new();
+    while sim.time() < Milliseconds(2.0) {
+        let time = sim.time();
+        for spike in sim.step(|_, _| 0.0) {
+            fired.push((time, spike.neuron_id));
+        }
+    }
+    assert_eq!(fired, vec![(Milliseconds(1.0), 3)]);
+}
+
+#[test]
+fn serial_runs_accept_closures_that_are_not_sync() {
+    let calls = std::cell::Cell::new(0usize);
+    let mut sim = random_network(16, partitioned(4), 2);
+    let (spikes, _) = sim.run(|i, t| {
+        calls.set(calls.get() + 1);
+        common::drive(i, t)
+    });
+    assert!(!spikes.is_empty());
+    assert_eq!(calls.get(), 16 * 200);
+}
+
+#[test]
+fn a_panicking_partition_reaches_the_caller() {
+    let mut sim = random_network(32, partitioned(4), 2);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        sim.run_parallel(
+            |i, t| {
+                assert!(i != 20 || t < Milliseconds(5.0), "input failed");
+                common::drive(i, t)
+            },
+            &[],
+        )
+    }));
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"input failed"));
+}
+
+#[test]
+fn dot_export_lists_nodes_groups_and_weighted_edges() {
+    let mut builder = NetworkBuilder::new();
+    let input = builder.add_population("input", 2, common::neuron_params());
+    builder.add_neurons(2, common::neuron_params());
+    builder.tag("readout", [3]);
+    builder.connect(input.start, 3, 0.25);
+    builder.connect_all_delayed([(1, 2, -0.5, Milliseconds(2.0))]);
+    let sim = Simulation::from_network(
+        builder.build(),
+        SimulationConfig::default(),
+        common::stdp_params(),
+    );
+
+    let mut plain = Vec::new();
+    sim.export_dot(&mut plain, false).unwrap();
+    let plain = String::from_utf8(plain).unwrap();
+    assert!(plain.starts_with("digraph network {\n"));
+    assert!(plain.ends_with("}\n"));
+    assert!(plain.contains("  n2 [label=\"2\"];\n"));
+    assert!(plain.contains("  n0 -> n3 [synaptic_weight=0.25, label=\"0.250\"];\n"));
+    assert!(plain.contains("  n1 -> n2 [synaptic_weight=-0.5, label=\"-0.500\", delay_ms=2];\n"));
+
+    let mut colored = Vec::new();
+    sim.export_dot(&mut colored, true).unwrap();
+    let colored = String::from_utf8(colored).unwrap();
+    assert!(colored.contains("  n1 [label=\"1\", group=\"input\", fillcolor="));
+    assert!(colored.contains("  n2 [label=\"2\"];\n"));
+    assert!(colored.contains("  n3 [label=\"3\", group=\"readout\", fillcolor="));
+}
+
+#[test]
+fn dot_export_reports_write_errors() {
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let sim = random_network(3, SimulationConfig::default(), 1);
+    let error = sim.export_dot(Broken, true).unwrap_err();
+    assert_eq!(error.to_string(), "disk full");
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use neuromorphic_core::connectivity::SheetShape;
+use neuromorphic_core::rng::Rng;
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::som::{build_som, SomParams, SpikingSom};
+use neuromorphic_core::synapse::Plasticity;
+use neuromorphic_core::units::Milliseconds;
+
+/// Map of a 6 × 6 sheet onto 20 inputs.
+fn som() -> SpikingSom {
+    let params = SomParams {
+        map: SheetShape::new(6, 6),
+        seed: 1,
+        ..SomParams::default()
+    };
+    let config = SimulationConfig {
+        dt: Milliseconds(1.0),
+        ..SimulationConfig::default()
+    };
+    build_som(&params, common::neuron_params(), config)
+}
+
+/// A Gaussian bump of activity centred on input `center`.
+fn bump(center: f64) -> Vec<f64> {
+    (0..20)
+        .map(|i| (-(i as f64 - center).powi(2) / 8.0).exp())
+        .collect()
+}
+
+#[test]
+fn maps_are_built_as_a_wta_sheet() {
+    let som = som();
+    let sim = &som.simulation;
+    assert_eq!(som.input, 0..20);
+    assert_eq!(sim.population("input"), Some(0..20));
+    assert_eq!(sim.population("map"), Some(som.map.clone()));
+    assert_eq!(som.map.len(), 36);
+    assert_eq!(som.coordinates(14), (2, 2));
+    let position = sim.positions()[som.map.start + 14].unwrap();
+    assert_eq!((position.x, position.y), (2.0, 2.0));
+    assert!(sim.config().synaptic_transmission);
+    assert!(sim
+        .synapses()
+        .iter()
+        .all(|s| matches!(s.plasticity, Plasticity::Static)));
+    let weights = som.weights();
+    assert_eq!((weights.len(), weights[0].len()), (36, 20));
+    assert!(weights.iter().flatten().all(|w| (0.1..0.3).contains(w)));
+    assert_eq!(som.schedule(), (3.0, 0.1));
+}
+
+#[test]
+fn training_orders_the_map() {
+    let mut som = som();
+    let mut rng = Rng::new(2);
+    let untrained = som.neighbor_distance_ratio();
+    assert!(untrained > 0.9, "untrained ratio {untrained}");
+
+    let samples: Vec<Vec<f64>> = (0..40).map(|k| bump(k as f64 * 0.5)).collect();
+    som.train(&samples, 3, &mut rng);
+    let (sigma, learning_rate) = som.schedule();
+    assert!((sigma - 0.5).abs() < 1e-9 && (learning_rate - 0.01).abs() < 1e-9);
+
+    let trained = som.neighbor_distance_ratio();
+    assert!(trained < 0.6, "trained ratio {trained}");
+    // Every update rescales a unit's weights to the fixed sum
+    for row in som.weights() {
+        let sum: f64 = row.iter().sum();
+        assert!((sum - 4.0).abs() < 1e-9, "{sum}");
+    }
+
+    // Units prefer the input their weights peak on, so the winners of
+    // the two ends of the input lie far apart on the sheet
+    let low = som.winner(&bump(2.0), &mut rng).unwrap();
+    let high = som.winner(&bump(17.0), &mut rng).unwrap();
+    let peak = |k: usize| {
+        let row = &som.weights()[k];
+        (0..20).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap()
+    };
+    assert!(
+        peak(low) < 7 && peak(high) > 12,
+        "{} {}",
+        peak(low),
+        peak(high)
+    );
+    let ((x0, y0), (x1, y1)) = (som.coordinates(low), som.coordinates(high));
+    assert!(x0.abs_diff(x1) + y0.abs_diff(y1) >= 4);
+
+    // Presenting without learning leaves the weights alone
+    let weights = som.weights();
+    som.present(&bump(10.0), false, &mut rng);
+    assert_eq!(som.weights(), weights);
+    assert_eq!(som.winner(&[0.0; 20], &mut rng), None);
+
+    let labels: Vec<usize> = (0..40).map(|k| usize::from(k >= 20)).collect();
+    let label_map = som.label_map(&samples, &labels, &mut rng);
+    assert_eq!(label_map.len(), 36);
+    assert_eq!(label_map[low], Some(0));
+    assert_eq!(label_map[high], Some(1));
+}
@@ -0,0 +1,224 @@
+#![cfg(feature = "hdf5")]
+
+mod common;
+
+use common::temp_path;
+use neuromorphic_core::hdf5::{Attribute, Hdf5File, Hdf5Writer};
+use neuromorphic_core::network::NetworkBuilder;
+use neuromorphic_core::neuron::NeuronParams;
+use neuromorphic_core::sonata::{read_sonata, write_sonata};
+use neuromorphic_core::spatial::Position;
+use neuromorphic_core::units::Milliseconds;
+use std::path::Path;
+
+fn inhibitory_params() -> NeuronParams {
+    NeuronParams {
+        tau_m: Milliseconds(10.0),
+        v_rest: -65.0,
+        v_thresh: -50.0,
+        v_reset: -70.0,
+    }
+}
+
+fn params_of(neuron: &neuromorphic_core::neuron::Neuron) -> (f64, f64, f64, f64) {
+    let p = &neuron.params;
+    (p.tau_m.0, p.v_rest, p.v_thresh, p.v_reset)
+}
+
+#[test]
+fn circuits_round_trip() {
+    let mut builder = NetworkBuilder::new();
+    let exc = builder.add_population("exc", 3, common::neuron_params());
+    let inh = builder.add_population("inh", 2, inhibitory_params());
+    builder.place(
+        exc.clone(),
+        (0..3).map(|i| Position::new(i as f64, 2.0 * i as f64, 0.5)),
+    );
+    builder.connect(exc.start, inh.start, 0.25);
+    builder.connect_all_delayed([
+        (inh.start, exc.start + 2, -0.5, Milliseconds(1.5)),
+        (exc.start + 1, exc.start, 0.125, Milliseconds(0.0)),
+    ]);
+    let network = builder.build();
+
+    let dir = temp_path("sonata_round_trip");
+    write_sonata(&network, "net", &dir).unwrap();
+    for file in [
+        "circuit_config.json",
+        "network/net_nodes.h5",
+        "network/net_node_types.csv",
+        "network/net_net_edges.h5",
+        "network/net_net_edge_types.csv",
+        "components/point_neuron_models/net_100.json",
+        "components/point_neuron_models/net_101.json",
+    ] {
+        assert!(dir.join(file).is_file(), "{file}");
+    }
+    let node_types = std::fs::read_to_string(dir.join("network/net_node_types.csv")).unwrap();
+    let mut lines = node_types.lines();
+    assert_eq!(
+        lines.next(),
+        Some("node_type_id pop_name model_type model_template dynamics_params")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("100 exc point_neuron nest:iaf_psc_delta net_100.json")
+    );
+    let nodes = Hdf5File::open(dir.join("network/net_nodes.h5")).unwrap();
+    assert_eq!(
+        nodes.read_ints("/nodes/net/node_type_id").unwrap(),
+        [100, 100, 100, 101, 101]
+    );
+    let edges = Hdf5File::open(dir.join("network/net_net_edges.h5")).unwrap();
+    assert_eq!(
+        edges
+            .attribute("/edges/net_to_net/source_node_id", "node_population")
+            .unwrap(),
+        Some(Attribute::Text("net".to_string()))
+    );
+
+    let defaults = NeuronParams {
+        tau_m: Milliseconds(99.0),
+        ..common::neuron_params()
+    };
+    let read = read_sonata(dir.join("circuit_config.json"), &defaults).unwrap();
+    let all: Vec<_> = network.neurons().iter().map(params_of).collect();
+    let read_all: Vec<_> = read.neurons().iter().map(params_of).collect();
+    assert_eq!(read_all, all);
+    assert_eq!(read.positions(), network.positions());
+    let synapses = |n: &neuromorphic_core::network::Network| -> Vec<(usize, usize, f64, f64)> {
+        n.synapses()
+            .iter()
+            .map(|s| (s.pre_neuron, s.post_neuron, s.weight, s.delay.0))
+            .collect()
+    };
+    assert_eq!(synapses(&read), synapses(&network));
+    // The node population, then one population per contiguous pop_name
+    assert_eq!(read.population("net"), Some(0..5));
+    assert_eq!(read.population("exc"), Some(exc));
+    assert_eq!(read.population("inh"), Some(inh));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Write an external circuit whose `pop_name`s interleave, with neuron
+/// parameters in node attributes and type columns and multiple synapses
+/// per edge, and a simulation configuration referring to it.
+fn write_external_circuit(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    let mut nodes = Hdf5Writer::create(dir.join("nodes.h5")).unwrap();
+    nodes
+        .write_dataset("/nodes/cortex/node_id", [10u64, 11, 12, 13], &[])
+        .unwrap();
+    nodes
+        .write_dataset("/nodes/cortex/node_type_id", [1u64, 2, 1, 2], &[])
+        .unwrap();
+    nodes
+        .write_dataset("/nodes/cortex/0/tau_m", [5.0, 6.0, 7.0, 8.0], &[])
+        .unwrap();
+    nodes.finish().unwrap();
+    std::fs::write(
+        dir.join("node_types.csv"),
+        "node_type_id pop_name V_th\n1 \"pyramidal\" 2.5\n2 basket NULL\n",
+    )
+    .unwrap();
+
+    let mut edges = Hdf5Writer::create(dir.join("edges.h5")).unwrap();
+    let population = [("node_population", Attribute::Text("cortex".to_string()))];
+    edges
+        .write_dataset(
+            "/edges/cortex_to_cortex/source_node_id",
+            [10u64, 13],
+            &population,
+        )
+        .unwrap();
+    edges
+        .write_dataset(
+            "/edges/cortex_to_cortex/target_node_id",
+            [11u64, 12],
+            &population,
+        )
+        .unwrap();
+    edges
+        .write_dataset("/edges/cortex_to_cortex/edge_type_id", [7u64, 7], &[])
+        .unwrap();
+    edges
+        .write_dataset("/edges/cortex_to_cortex/0/syn_weight", [0.5, -0.25], &[])
+        .unwrap();
+    edges
+        .write_dataset("/edges/cortex_to_cortex/0/nsyns", [2.0, 3.0], &[])
+        .unwrap();
+    edges.finish().unwrap();
+    std::fs::write(dir.join("edge_types.csv"), "edge_type_id delay\n7 2.0\n").unwrap();
+
+    std::fs::write(
+        dir.join("circuit.json"),
+        r#"{
+  "manifest": {"$BASE": "${configdir}"},
+  "networks": {
+    "nodes": [{"nodes_file": "$BASE/nodes.h5", "node_types_file": "$BASE/node_types.csv"}],
+    "edges": [{"edges_file": "$BASE/edges.h5", "edge_types_file": "$BASE/edge_types.csv"}]
+  }
+}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("simulation.json"),
+        r#"{"network": "${configdir}/circuit.json", "run": {"tstop": 100.0}}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn external_circuits_are_read() {
+    let dir = temp_path("sonata_external");
+    write_external_circuit(&dir);
+    let defaults = common::neuron_params();
+    let network = read_sonata(dir.join("simulation.json"), &defaults).unwrap();
+
+    let params: Vec<_> = network.neurons().iter().map(params_of).collect();
+    assert_eq!(
+        params,
+        [
+            (5.0, 0.0, 2.5, 0.0),
+            (6.0, 0.0, 1.0, 0.0),
+            (7.0, 0.0, 2.5, 0.0),
+            (8.0, 0.0, 1.0, 0.0),
+        ]
+    );
+    assert!(network.positions().iter().all(Option::is_none));
+
+    // Node ids are mapped to neurons, nsyns scales the weight and the
+    // delay comes from the edge type
+    let synapses: Vec<_> = network
+        .synapses()
+        .iter()
+        .map(|s| (s.pre_neuron, s.post_neuron, s.weight, s.delay.0))
+        .collect();
+    assert_eq!(synapses, [(0, 1, 1.0, 2.0), (3, 2, -0.75, 2.0)]);
+
+    // Interleaved pop_names become groups rather than populations
+    assert_eq!(network.population("cortex"), Some(0..4));
+    assert_eq!(network.population("pyramidal"), None);
+    let groups: Vec<_> = network
+        .groups()
+        .iter()
+        .map(|g| (g.name.as_str(), g.neurons.clone()))
+        .collect();
+    assert_eq!(groups, [("pyramidal", vec![0, 2]), ("basket", vec![1, 3])]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn malformed_circuits_are_rejected() {
+    let dir = temp_path("sonata_malformed");
+    write_external_circuit(&dir);
+    std::fs::write(dir.join("edge_types.csv"), "edge_type_id delay\n7 soon\n").unwrap();
+    let error = read_sonata(dir.join("circuit.json"), &common::neuron_params()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("delay of type 7"), "{error}");
+
+    std::fs::write(dir.join("circuit.json"), r#"{"components": {}}"#).unwrap();
+    let error = read_sonata(dir.join("circuit.json"), &common::neuron_params()).unwrap_err();
+    assert_eq!(error.to_string(), "Configuration has no networks");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{random_network, temp_path};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::spike_codec::{
+    read_spikes_binary, write_spikes_binary, SpikeDecoder, SpikeEncoder,
+};
+use neuromorphic_core::units::Milliseconds;
+use std::io::ErrorKind;
+
+fn spike(neuron: usize, time: f64) -> Spike {
+    Spike::new(neuron, Milliseconds(time))
+}
+
+#[test]
+fn encodes_the_documented_layout() {
+    let mut encoder = SpikeEncoder::new(Vec::new(), Milliseconds(0.5)).unwrap();
+    for s in [spike(3, 0.0), spike(1, 0.0), spike(300, 1.0)] {
+        encoder.write(&s).unwrap();
+    }
+    let bytes = encoder.finish().unwrap();
+
+    let mut expected = b"SPK1".to_vec();
+    expected.extend_from_slice(&0.5f64.to_le_bytes());
+    // Same tick: zigzag(3 - 0) = 6, zigzag(1 - 3) = 3
+    expected.extend_from_slice(&[0, 6, 0, 3]);
+    // Two ticks later, neuron 300 as a two-byte varint
+    expected.extend_from_slice(&[2, 0xac, 0x02]);
+    assert_eq!(bytes, expected);
+
+    let decoded: Vec<Spike> = SpikeDecoder::new(bytes.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let pairs: Vec<(usize, f64)> = decoded.iter().map(|s| (s.neuron_id, s.time.0)).collect();
+    assert_eq!(pairs, vec![(3, 0.0), (1, 0.0), (300, 1.0)]);
+}
+
+#[test]
+fn recorded_run_round_trips_within_one_tick() {
+    let config = SimulationConfig {
+        t_max: Milliseconds(100.0),
+        ..SimulationConfig::default()
+    };
+    let mut sim = random_network(40, config, 9);
+    let (spikes, _) = sim.run(|i, t| 3.0 * common::drive(i, t));
+    assert!(spikes.len() > 100);
+
+    let path = temp_path("run.spk");
+    write_spikes_binary(&spikes, Milliseconds(0.1), &path).unwrap();
+    let read = read_spikes_binary(&path).unwrap();
+    assert_eq!(read.len(), spikes.len());
+    for (a, b) in spikes.iter().zip(&read) {
+        assert_eq!(a.neuron_id, b.neuron_id);
+        assert!((a.time.0 - b.time.0).abs() < 1e-9);
+    }
+    // About a tenth of the CSV size: at most three bytes per spike here
+    let size = std::fs::metadata(&path).unwrap().len() as usize;
+    assert!(size <= 12 + 3 * spikes.len());
+}
+
+#[test]
+fn unsorted_spikes_are_written_in_time_order() {
+    let path = temp_path("unsorted.spk");
+    write_spikes_binary(&[spike(2, 5.0), spike(7, 1.0)], Milliseconds(1.0), &path).unwrap();
+    let ids: Vec<usize> = read_spikes_binary(&path)
+        .unwrap()
+        .iter()
+        .map(|s| s.neuron_id)
+        .collect();
+    assert_eq!(ids, vec![7, 2]);
+}
+
+#[test]
+fn rejects_invalid_input_and_files() {
+    let mut encoder = SpikeEncoder::new(Vec::new(), Milliseconds(1.0)).unwrap();
+    encoder.write(&spike(0, 5.0)).unwrap();
+    let err = encoder.write(&spike(0, 4.0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = encoder.write(&spike(0, -1.0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = SpikeEncoder::new(Vec::new(), Milliseconds(0.0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = SpikeDecoder::new(&b"CSV1\0\0\0\0\0\0\xf0\x3f"[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = SpikeDecoder::new(&b"SPK1"[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // A gap without its neuron id
+    let mut bytes = b"SPK1".to_vec();
+    bytes.extend_from_slice(&1.0f64.to_le_bytes());
+    bytes.push(1);
+    let result: Result<Vec<Spike>, _> = SpikeDecoder::new(bytes.as_slice()).unwrap().collect();
+    assert!(result.is_err());
+}
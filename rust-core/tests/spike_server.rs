@@ -0,0 +1,84 @@
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::spike_server::{Message, SpikeClient, SpikeServer, MAX_LINE};
+use neuromorphic_core::units::Milliseconds;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Poll the server until it has received `n` spikes.
+fn wait_for_input(server: &SpikeServer, n: usize) -> Vec<Spike> {
+    let start = Instant::now();
+    let mut input = Vec::new();
+    while input.len() < n && start.elapsed() < TIMEOUT {
+        input.extend(server.take_input());
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    input
+}
+
+#[test]
+fn spikes_flow_both_ways() {
+    let mut server = SpikeServer::bind("127.0.0.1:0").unwrap();
+    server.set_step_markers(true);
+    let mut client = SpikeClient::connect(server.local_addr()).unwrap();
+    assert!(server.wait_for_clients(1, TIMEOUT));
+
+    client.inject(&Spike::new(3, Milliseconds(2.5))).unwrap();
+    client.inject_now(4).unwrap();
+    let input = wait_for_input(&server, 2);
+    assert_eq!(input.len(), 2);
+    assert_eq!((input[0].neuron_id, input[0].time.0), (3, 2.5));
+    assert_eq!(input[1].neuron_id, 4);
+    assert!(input[1].time.0.is_nan());
+
+    server.broadcast(&[Spike::new(7, Milliseconds(1.0))], Milliseconds(1.5));
+    assert!(matches!(
+        client.recv().unwrap(),
+        Some(Message::Spike(s)) if s.neuron_id == 7 && s.time.0 == 1.0
+    ));
+    assert!(matches!(client.recv().unwrap(), Some(Message::Step(t)) if t.0 == 1.5));
+}
+
+#[test]
+fn overlong_lines_disconnect_the_client() {
+    let server = SpikeServer::bind("127.0.0.1:0").unwrap();
+    let mut flooder = TcpStream::connect(server.local_addr()).unwrap();
+    flooder.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let mut client = SpikeClient::connect(server.local_addr()).unwrap();
+    assert!(server.wait_for_clients(2, TIMEOUT));
+
+    // A line of exactly MAX_LINE bytes is still accepted
+    let padded = format!("spike 1{}\n", " ".repeat(MAX_LINE - 7));
+    flooder.write_all(padded.as_bytes()).unwrap();
+    assert_eq!(wait_for_input(&server, 1).len(), 1);
+
+    // The server stops reading partway through a newline-free flood and
+    // closes the connection, so writing eventually fails or reading sees
+    // the end of the stream
+    let flood = vec![b'x'; 64 * 1024];
+    for _ in 0..64 {
+        if flooder.write_all(&flood).is_err() {
+            break;
+        }
+    }
+    let mut rest = Vec::new();
+    let closed = match flooder.read_to_end(&mut rest) {
+        Ok(_) => true,
+        Err(e) => e.kind() == std::io::ErrorKind::ConnectionReset,
+    };
+    assert!(closed);
+
+    // The flooder's writer is dropped on the next broadcast, while the
+    // well-behaved client keeps receiving
+    server.broadcast(&[Spike::new(2, Milliseconds(1.0))], Milliseconds(1.0));
+    assert!(matches!(
+        client.recv().unwrap(),
+        Some(Message::Spike(s)) if s.neuron_id == 2
+    ));
+    server.broadcast(&[], Milliseconds(2.0));
+    assert_eq!(server.num_clients(), 1);
+    client.inject_now(5).unwrap();
+    assert_eq!(wait_for_input(&server, 1)[0].neuron_id, 5);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "surrogate")]
+
+use neuromorphic_core::rng::Rng;
+use neuromorphic_core::spike::Spike;
+use neuromorphic_core::surrogate::{Optimizer, SurrogateNetwork, SurrogateParams};
+use neuromorphic_core::units::Milliseconds;
+
+/// Class 0 fires the first half of the inputs early, class 1 the second
+/// half late, with jittered times.
+fn samples(inputs: usize, count: usize, seed: u64) -> Vec<(Vec<Spike>, usize)> {
+    let mut rng = Rng::new(seed);
+    (0..count)
+        .map(|k| {
+            let label = k % 2;
+            let (neurons, start) = if label == 0 {
+                (0..inputs / 2, 2.0)
+            } else {
+                (inputs / 2..inputs, 15.0)
+            };
+            let mut spikes = Vec::new();
+            for i in neurons {
+                for burst in 0..3 {
+                    let t = start + 4.0 * burst as f64 + rng.uniform(0.0, 3.0);
+                    spikes.push(Spike::new(i, Milliseconds(t)));
+                }
+            }
+            spikes.sort_by(|a, b| a.time.0.total_cmp(&b.time.0));
+            (spikes, label)
+        })
+        .collect()
+}
+
+#[test]
+fn training_separates_two_classes() {
+    let params = SurrogateParams {
+        duration: Milliseconds(30.0),
+        ..SurrogateParams::default()
+    };
+    let mut net = SurrogateNetwork::new(&[10, 8, 2], params, 2.0, 3);
+    assert_eq!(net.sizes(), [10, 8, 2]);
+    assert_eq!(net.steps(), 30);
+    assert_eq!(net.weights(0).len(), 8);
+    assert_eq!(net.weights(1)[0].len(), 8);
+
+    let train = samples(10, 40, 1);
+    let test = samples(10, 20, 2);
+    let mut optimizer = Optimizer::adam(0.05);
+    let losses = net.train(&train, 8, 30, &mut optimizer, &mut Rng::new(4));
+    assert_eq!(losses.len(), 30);
+    assert!(
+        losses[29] < 0.5 * losses[0],
+        "loss {} -> {}",
+        losses[0],
+        losses[29]
+    );
+    assert_eq!(net.accuracy(&test), 1.0);
+    assert_eq!(net.accuracy(&[]), 0.0);
+}
+
+#[test]
+fn simulations_reproduce_the_unrolled_network() {
+    let params = SurrogateParams {
+        duration: Milliseconds(30.0),
+        ..SurrogateParams::default()
+    };
+    let net = SurrogateNetwork::new(&[10, 6, 3], params, 3.0, 5);
+    let (mut sim, layers) = net.to_simulation();
+    assert_eq!(layers, [0..10, 10..16, 16..19]);
+    let mut active = 0;
+    for (input, _) in samples(10, 6, 6) {
+        let expected = net.output_counts(&input);
+        active += expected.iter().sum::<usize>();
+        sim.reset();
+        sim.inject_spikes(&input);
+        let (spikes, _) = sim.run(|_, _| 0.0);
+        let counts: Vec<usize> = layers[2]
+            .clone()
+            .map(|j| spikes.iter().filter(|s| s.neuron_id == j).count())
+            .collect();
+        assert_eq!(counts, expected);
+    }
+    assert!(active > 0);
+}
+
+#[test]
+fn optimizers_follow_the_gradient() {
+    let params = SurrogateParams::default();
+    let batch = samples(4, 2, 7);
+    let initial = SurrogateNetwork::new(&[4, 3], params, 3.0, 8);
+
+    // Without a step size nothing changes
+    let mut net = initial.clone();
+    let loss = net.train_batch(&batch, &mut Optimizer::sgd(0.0));
+    assert!(loss > 0.0);
+    assert_eq!(net.weights(0), initial.weights(0));
+    assert_eq!(net.train_batch(&[], &mut Optimizer::sgd(1.0)), 0.0);
+
+    // Adam moves every weight with a gradient by about the step size
+    let mut optimizer = Optimizer::adam(0.01);
+    net.train_batch(&batch, &mut optimizer);
+    let moved: Vec<f64> = net
+        .weights(0)
+        .iter()
+        .flatten()
+        .zip(initial.weights(0).iter().flatten())
+        .map(|(a, b)| (a - b).abs())
+        .filter(|d| *d > 0.0)
+        .collect();
+    assert!(!moved.is_empty());
+    assert!(moved.iter().all(|d| (d - 0.01).abs() < 1e-4), "{moved:?}");
+}
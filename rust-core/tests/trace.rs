@@ -0,0 +1,194 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use common::{random_network, temp_path};
+use neuromorphic_core::output::{OutputConfig, OutputFormat};
+use neuromorphic_core::simulation::SimulationConfig;
+use neuromorphic_core::trace::{
+    with_subscriber, FmtSubscriber, JsonSubscriber, Level, TraceCollector, Value,
+};
+use neuromorphic_core::units::Milliseconds;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Writer whose output can be read back after the subscriber is dropped.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn config() -> SimulationConfig {
+    SimulationConfig {
+        t_max: Milliseconds(50.0),
+        ..SimulationConfig::default()
+    }
+}
+
+#[test]
+fn run_reports_its_phases_and_outcome() {
+    let mut sim = random_network(10, config(), 4);
+    let collector = TraceCollector::new(Level::Trace);
+    let (spikes, _) = with_subscriber(collector.clone(), || sim.run(|_, _| 2.0));
+    assert!(!spikes.is_empty());
+    let steps = (50.0 / sim.config().dt.0).round() as u64;
+
+    let records = collector.records();
+    let finished = records.iter().find(|r| r.name == "run finished").unwrap();
+    assert_eq!(finished.level, Level::Info);
+    assert_eq!(finished.scope, ["run"]);
+    assert_eq!(finished.field("reason"), Some(&Value::from("Completed")));
+    assert_eq!(
+        finished.field("spikes"),
+        Some(&Value::Uint(spikes.len() as u64))
+    );
+    let recorded_steps = match finished.field("steps") {
+        Some(&Value::Uint(n)) => n,
+        other => panic!("steps {other:?}"),
+    };
+    assert!(
+        recorded_steps.abs_diff(steps) <= 1,
+        "{recorded_steps} steps"
+    );
+
+    // The run span closes last and encloses one span per phase and step
+    let run = records.last().unwrap();
+    assert!(run.is_span());
+    assert_eq!((run.name, run.level), ("run", Level::Info));
+    assert_eq!(run.field("neurons"), Some(&Value::Uint(10)));
+    let totals = collector.span_totals();
+    let count = |name| {
+        totals
+            .iter()
+            .find(|t| t.name == name)
+            .map_or(0, |t| t.count)
+    };
+    assert_eq!(count("deliver") as u64, recorded_steps);
+    assert_eq!(count("neurons") as u64, recorded_steps);
+    assert_eq!(count("transmit") as u64, recorded_steps);
+    assert!(count("plasticity") > 0);
+    assert_eq!(count("run"), 1);
+    let run_total = totals.iter().find(|t| t.name == "run").unwrap().total;
+    let phases: std::time::Duration = totals
+        .iter()
+        .filter(|t| t.name != "run")
+        .map(|t| t.total)
+        .sum();
+    assert!(phases <= run_total);
+    for record in records.iter().filter(|r| r.name == "neurons") {
+        assert_eq!(record.scope, ["run", "neurons"]);
+    }
+}
+
+#[test]
+fn levels_above_the_maximum_are_not_recorded() {
+    let mut sim = random_network(10, config(), 4);
+    let collector = TraceCollector::new(Level::Info);
+    with_subscriber(collector.clone(), || sim.run(|_, _| 2.0));
+    let names: Vec<&str> = collector.records().iter().map(|r| r.name).collect();
+    assert_eq!(names, ["run finished", "run"]);
+
+    // Without a subscriber nothing is recorded, also by an earlier one
+    collector.clear();
+    sim.reset();
+    sim.run(|_, _| 2.0);
+    assert!(collector.records().is_empty());
+}
+
+#[test]
+fn run_example_reports_instead_of_printing() {
+    let mut output = OutputConfig::new(temp_path("trace_example"));
+    output.formats = vec![OutputFormat::Csv];
+    output.per_run = false;
+    let collector = TraceCollector::new(Level::Debug);
+    let run = with_subscriber(collector.clone(), || {
+        neuromorphic_core::run_example_with(&output)
+    })
+    .unwrap();
+
+    let records = collector.records();
+    let complete = records
+        .iter()
+        .find(|r| r.name == "simulation complete")
+        .unwrap();
+    let spikes = match complete.field("spikes") {
+        Some(&Value::Uint(n)) => n as usize,
+        other => panic!("spikes {other:?}"),
+    };
+    assert!(spikes > 0);
+    let listed = records.iter().filter(|r| r.name == "spike").count();
+    assert_eq!(listed, spikes.min(10));
+    assert!(records
+        .iter()
+        .any(|r| r.is_span() && r.name == "write_outputs"));
+    std::fs::remove_dir_all(run.dir()).unwrap();
+}
+
+#[test]
+fn subscribers_format_records() {
+    let (text, json) = (SharedBuffer::default(), SharedBuffer::default());
+    let mut sim = random_network(5, config(), 2);
+    with_subscriber(
+        FmtSubscriber::with_writer(Level::Info, text.clone()),
+        || sim.run(|_, _| 2.0),
+    );
+    sim.reset();
+    with_subscriber(JsonSubscriber::new(Level::Info, json.clone()), || {
+        sim.run(|_, _| 2.0)
+    });
+
+    let lines: Vec<String> = text.text().lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    assert!(
+        lines[0].starts_with(
+            " INFO run: neuromorphic_core::simulation: run finished reason=\"Completed\""
+        ),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[1].starts_with(" INFO run: neuromorphic_core::simulation: close elapsed_ms="),
+        "{}",
+        lines[1]
+    );
+    assert!(
+        lines[1].ends_with(" t_start=0 t_max=50 neurons=5 synapses=5"),
+        "{}",
+        lines[1]
+    );
+
+    let lines: Vec<String> = json.text().lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    assert!(lines[0].starts_with(
+        "{\"level\":\"INFO\",\"target\":\"neuromorphic_core::simulation\",\"name\":\"run finished\",\"scope\":[\"run\"],\"fields\":{\"reason\":\"Completed\","
+    ));
+    assert!(lines[1].contains(",\"elapsed_ms\":"));
+    assert!(
+        lines[1].ends_with(",\"neurons\":5,\"synapses\":5}}"),
+        "{}",
+        lines[1]
+    );
+}
+
+#[test]
+fn levels_parse_case_insensitively() {
+    assert_eq!(Level::from_name("trace"), Some(Level::Trace));
+    assert_eq!(Level::from_name("WaRn"), Some(Level::Warn));
+    assert_eq!(Level::from_name("verbose"), None);
+    assert!(Level::Error < Level::Info && Level::Info < Level::Trace);
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "wasm")]
+
+//! Drives the module the way `web/snn.js` does: buffers come from
+//! `snn_alloc` and every simulation call goes through the `nc_*` API.
+
+use neuromorphic_core::ffi::*;
+use neuromorphic_core::wasm::{snn_alloc, snn_free};
+use std::ffi::CStr;
+
+const CONFIG: &str = r#"
+seed = 1
+
+[simulation]
+dt_ms = 1.0
+t_max_ms = 100.0
+synaptic_transmission = true
+
+[neuron]
+tau_m_ms = 20.0
+v_rest = 0.0
+v_thresh = 1.0
+v_reset = 0.0
+
+[stdp]
+a_plus = 0.01
+a_minus = 0.012
+tau_plus_ms = 20.0
+tau_minus_ms = 20.0
+w_min = 0.0
+w_max = 1.0
+
+[[population]]
+name = "a"
+size = 3
+
+[[population]]
+name = "b"
+size = 2
+
+[[projection]]
+pre = "a"
+post = "b"
+connector = "all_to_all"
+weight_min = 0.5
+weight_max = 0.5
+delay_ms = 1.0
+plastic = false
+"#;
+
+/// Copy `text` into a NUL-terminated buffer from `snn_alloc`.
+fn alloc_str(text: &str) -> (*mut u8, usize) {
+    let len = text.len() + 1;
+    let ptr = snn_alloc(len);
+    unsafe {
+        std::ptr::copy_nonoverlapping(text.as_ptr(), ptr, text.len());
+        *ptr.add(text.len()) = 0;
+    }
+    (ptr, len)
+}
+
+#[test]
+fn buffers_are_aligned_for_doubles() {
+    for len in [0, 1, 3, 8, 100] {
+        let ptr = snn_alloc(len);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 8, 0);
+        unsafe { snn_free(ptr, len) };
+    }
+}
+
+#[test]
+fn host_steps_a_configured_network_through_the_c_api() {
+    unsafe {
+        let (config, len) = alloc_str(CONFIG);
+        let sim = nc_create_from_toml(config.cast());
+        snn_free(config, len);
+        assert!(!sim.is_null());
+        assert_eq!(nc_num_neurons(sim), 5);
+        assert_eq!(nc_num_synapses(sim), 6);
+
+        // Drive neuron 0 hard through the input buffer
+        let inputs = snn_alloc(8 * 5).cast::<f64>();
+        std::slice::from_raw_parts_mut(inputs, 5).copy_from_slice(&[2.0, 0.0, 0.0, 0.0, 0.0]);
+        let emitted: usize = (0..20).map(|_| nc_step(sim, inputs, 5)).sum();
+        assert!(emitted > 0);
+        assert_eq!(nc_time(sim), 20.0);
+
+        let n = nc_pending_spikes(sim);
+        assert_eq!(n, emitted);
+        let neurons = snn_alloc(std::mem::size_of::<usize>() * n).cast::<usize>();
+        let times = snn_alloc(8 * n).cast::<f64>();
+        assert_eq!(nc_get_spikes(sim, neurons, times, n), n);
+        let neurons_out = std::slice::from_raw_parts(neurons, n);
+        assert!(neurons_out.contains(&0));
+        assert!(std::slice::from_raw_parts(times, n)
+            .iter()
+            .all(|t| (0.0..20.0).contains(t)));
+        assert_eq!(nc_pending_spikes(sim), 0);
+
+        let weights = snn_alloc(8 * 6).cast::<f64>();
+        let copied = nc_get_synapses(sim, std::ptr::null_mut(), std::ptr::null_mut(), weights, 6);
+        assert_eq!(copied, 6);
+        assert_eq!(std::slice::from_raw_parts(weights, 6), [0.5; 6]);
+
+        snn_free(neurons.cast(), std::mem::size_of::<usize>() * n);
+        snn_free(times.cast(), 8 * n);
+        snn_free(weights.cast(), 8 * 6);
+        snn_free(inputs.cast(), 8 * 5);
+        nc_destroy(sim);
+    }
+}
+
+#[test]
+fn invalid_configurations_explain_the_failure() {
+    unsafe {
+        let (config, len) = alloc_str("[simulation]\ndt_ms = \"fast\"\n");
+        let sim = nc_create_from_toml(config.cast());
+        snn_free(config, len);
+        assert!(sim.is_null());
+        let error = CStr::from_ptr(nc_last_error()).to_str().unwrap();
+        assert!(!error.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// snn.js
+//
+// Browser wrapper around the C API of src/ffi.rs, exported by the module
+// built with the `wasm` feature. Buffers live in the module's memory and
+// are reserved with snn_alloc; views on them are recreated after every
+// call, since memory may have grown.
+//
+//   const snn = await Snn.load("neuromorphic_core.wasm", tomlText);
+//   snn.step(10);
+//   const { neurons, times } = snn.takeSpikes();
+//   const weights = snn.weights();
+
+export class Snn {
+  static async load(url, config) {
+    const { instance } = await WebAssembly.instantiateStreaming(fetch(url), {});
+    return new Snn(instance.exports, config);
+  }
+
+  constructor(exports, config) {
+    this.x = exports;
+    const bytes = new TextEncoder().encode(config + "\0");
+    const ptr = this.x.snn_alloc(bytes.length);
+    new Uint8Array(this.x.memory.buffer, ptr, bytes.length).set(bytes);
+    this.handle = this.x.nc_create_from_toml(ptr);
+    this.x.snn_free(ptr, bytes.length);
+    if (this.handle === 0) {
+      throw new Error(this.lastError() ?? "Invalid experiment configuration");
+    }
+    // Extra input current per neuron, passed to every step
+    this.numInputs = this.numNeurons;
+    this.inputs = this.x.snn_alloc(8 * this.numInputs);
+    this.#view(Float64Array, this.inputs, this.numInputs).fill(0);
+  }
+
+  #view(type, ptr, length) {
+    return new type(this.x.memory.buffer, ptr, length);
+  }
+
+  // Description of the last failure, or null.
+  lastError() {
+    const ptr = this.x.nc_last_error();
+    if (ptr === 0) {
+      return null;
+    }
+    const bytes = new Uint8Array(this.x.memory.buffer, ptr);
+    return new TextDecoder().decode(bytes.subarray(0, bytes.indexOf(0)));
+  }
+
+  get time() {
+    return this.x.nc_time(this.handle);
+  }
+
+  get numNeurons() {
+    return this.x.nc_num_neurons(this.handle);
+  }
+
+  // Advance `steps` time steps and return the number of spikes emitted.
+  step(steps = 1) {
+    let emitted = 0;
+    for (let k = 0; k < steps; k++) {
+      emitted += this.x.nc_step(this.handle, this.inputs, this.numInputs);
+    }
+    return emitted;
+  }
+
+  // Add `current` to the input of `neuron` until changed.
+  setInput(neuron, current) {
+    if (neuron >= 0 && neuron < this.numInputs) {
+      this.#view(Float64Array, this.inputs, this.numInputs)[neuron] = current;
+    }
+  }
+
+  injectSpike(neuron) {
+    this.x.nc_inject_spike(this.handle, neuron);
+  }
+
+  // Copy out the spikes emitted since the last call.
+  takeSpikes() {
+    const n = this.x.nc_pending_spikes(this.handle);
+    const neuronsPtr = this.x.snn_alloc(4 * n);
+    const timesPtr = this.x.snn_alloc(8 * n);
+    this.x.nc_get_spikes(this.handle, neuronsPtr, timesPtr, n);
+    const neurons = this.#view(Uint32Array, neuronsPtr, n).slice();
+    const times = this.#view(Float64Array, timesPtr, n).slice();
+    this.x.snn_free(neuronsPtr, 4 * n);
+    this.x.snn_free(timesPtr, 8 * n);
+    return { neurons, times };
+  }
+
+  // Current synaptic weights in synapse order.
+  weights() {
+    const n = this.x.nc_num_synapses(this.handle);
+    const ptr = this.x.snn_alloc(8 * n);
+    this.x.nc_get_synapses(this.handle, 0, 0, ptr, n);
+    const weights = this.#view(Float64Array, ptr, n).slice();
+    this.x.snn_free(ptr, 8 * n);
+    return weights;
+  }
+
+  free() {
+    this.x.nc_destroy(this.handle);
+    this.x.snn_free(this.inputs, 8 * this.numInputs);
+    this.handle = 0;
+  }
+}